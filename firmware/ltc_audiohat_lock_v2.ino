@@ -38,6 +38,11 @@
 #include <Audio.h>
 #include "analyze_ltc.h"
 
+// —— Firmware identity, sent in answer to the host's "?" probe ——
+const char* FIRMWARE_NAME    = "ltc_audiohat_lock_v2";
+const char* FIRMWARE_VERSION = "2.1.0";
+const char* FIRMWARE_CAPS    = "fps-detect,free-run,drop-frame";
+
 // —— Configuration ——
 const float FORCE_FPS             = 0.0f;     // 0 → auto‑detect
 const int   FRAME_OFFSET          = 4;       // compensation in frames
@@ -78,6 +83,14 @@ void loop() {
   // compute framePeriod from currentFps
   unsigned long framePeriod = (unsigned long)(1000.0f / currentFps + 0.5f);
 
+  // 0) Answer the host's handshake probe
+  while (Serial.available()) {
+    if (Serial.read() == '?') {
+      Serial.printf("[VERSION] %s %s caps=%s\r\n",
+                    FIRMWARE_NAME, FIRMWARE_VERSION, FIRMWARE_CAPS);
+    }
+  }
+
   // 1) If in ACTIVE and we've gone > LOSS_TIMEOUT w/o decode, enter LOST
   if (ltcState == LTC_ACTIVE && (now - lastDecode) >= LOSS_TIMEOUT) {
     ltcState = LTC_LOST;
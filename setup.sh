@@ -5,7 +5,7 @@ echo "--- TimeTurner Setup ---"
 
 # Check if TimeTurner is already installed.
 INSTALL_DIR="/opt/timeturner"
-if [ -f "${INSTALL_DIR}/timeturner" ]; then
+if [ -f "${INSTALL_DIR}/timeturnerd" ] || [ -f "${INSTALL_DIR}/timeturner" ]; then
     echo "✅ TimeTurner is already installed."
     # Ask the user what to do
     read -p "Do you want to (U)pdate, (R)einstall, or (A)bort? [U/r/a] " choice
@@ -233,13 +233,19 @@ echo "🔧 Creating directories..."
 sudo mkdir -p $INSTALL_DIR
 echo "✅ Directory $INSTALL_DIR created."
 
-# 3. Install binary and static web files
-echo "🚀 Installing timeturner binary and web assets..."
-sudo cp target/release/ntp_timeturner $INSTALL_DIR/timeturner
+# 3. Install binaries and static web files
+echo "🚀 Installing timeturner binaries and web assets..."
+sudo cp target/release/timeturnerd $INSTALL_DIR/timeturnerd
+sudo cp target/release/timeturner-ctl $INSTALL_DIR/timeturner-ctl
+# Remove the pre-split single binary if present
+sudo rm -f $INSTALL_DIR/timeturner
 # The static directory contains the web UI files
 sudo cp -r static $INSTALL_DIR/
-sudo ln -sf $INSTALL_DIR/timeturner $BIN_DIR/timeturner
-echo "✅ Binary and assets installed to $INSTALL_DIR, and binary linked to $BIN_DIR."
+sudo ln -sf $INSTALL_DIR/timeturnerd $BIN_DIR/timeturnerd
+sudo ln -sf $INSTALL_DIR/timeturner-ctl $BIN_DIR/timeturner-ctl
+# `timeturner` keeps opening the TUI, as it did before the split
+sudo ln -sf $INSTALL_DIR/timeturner-ctl $BIN_DIR/timeturner
+echo "✅ Binaries and assets installed to $INSTALL_DIR, and linked to $BIN_DIR."
 
 # 4. Install systemd service file
 # Only needed for Linux systems (e.g., Raspberry Pi OS)
@@ -273,8 +279,8 @@ if [[ "$(uname)" == "Linux" ]]; then
     echo "  journalctl -u timeturner.service -f"
     echo ""
 fi
-echo "To run the interactive TUI instead, simply run from the project directory:"
-echo "  cargo run"
-echo "Or from anywhere after installation:"
-echo "  timeturner"
+echo "To open the interactive TUI (connects to the running daemon):"
+echo "  timeturner-ctl"
+echo "To run the daemon in the foreground instead of the service:"
+echo "  cd $INSTALL_DIR && sudo ./timeturnerd"
 echo ""
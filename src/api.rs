@@ -1,61 +1,514 @@
-
-use actix_files as fs;
-use actix_web::{get, post, web, App, HttpResponse, HttpServer, Responder};
-use chrono::{Local, Timelike};
-use get_if_addrs::get_if_addrs;
+use actix_web::{
+    dev::Server, get, http::StatusCode, middleware::from_fn, patch, post, put, web, App,
+    HttpRequest, HttpResponse, HttpServer, Responder,
+};
+use chrono::{DateTime, Local, Timelike};
 use serde::{Deserialize, Serialize};
-use serde_json;
 use std::collections::VecDeque;
+use socket2::{Domain, Protocol, Socket, Type};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use utoipa::{OpenApi, ToSchema};
 
-use crate::config::{self, Config};
-use crate::sync_logic::{self, LtcState};
-use crate::system;
+use crate::assets;
+use crate::audit::{self, AuditEntry, AuditLog, AuditSource};
+use crate::auth;
+use crate::auth_backend::AuthCache;
+use crate::chrony::{self, ChronyReport, ChronySource, ChronyTracking};
+use crate::clock_gate::{self, ClockGate};
+use crate::config::{self, Config, SyncSource, TimeturnerOffset};
+use crate::confirm::{self, Confirmations};
+use crate::export::{self, ExportStatus, Exporter};
+use crate::health::{Health, HealthReport, ReadyCheck, ReadyReport, WorkerHealth};
+use crate::supervisor::{ComponentHealth, Restart, Supervisor};
+use crate::history::{DeltaHistory, HistoryConfig, HistoryPoint, HistoryResponse};
+use crate::report::{AlertRecord, Session, SessionReport};
+use crate::series::{Series, SeriesKind, SeriesPoint, SeriesResponse, SERIES_MAX_MINUTES};
+use crate::host::{self, NetInterface, NicInfo, RtcInfo, SystemInfo, Throttling, Usage};
+use crate::logger::{self, LogFilter};
+use crate::metrics;
+use crate::ntp_client::{NtpCrossCheck, NtpSample};
+use crate::presets::{self, OffsetPreset};
+use crate::processor::PipelineReport;
+use crate::ramp;
+use crate::rtc::RtcWrite;
+use crate::ptp_capture::{ForeignMaster, PtpAnnounce, PtpCapture, PtpMasterStatus, PtpMessage};
+use crate::rtp_midi::{RtpMidiPeer, RtpMidiSessions, RtpMidiStatus};
+use crate::serial_input::{self, ReaderFirmware, ReaderLatency, ReaderPort, ReaderSelection, SerialPortReport};
+use crate::sinks::{SinkBoard, SinkConfig, SinkHealth};
+use crate::sources::{SourceHealth, SourceRegistry, SourceReport, TimeEstimate};
+use crate::status::StatusSnapshot;
+use crate::sync_logic::{
+    self, ClockDirection, ClockPair, ContinuityReport, DeltaFrames, Discontinuity, DiscontinuityKind, JitterStats, LtcState, LtcView,
+    Staleness,
+};
+use crate::system::{self, ClockControl, ServiceAction, SystemClock, TargetTimeError};
+use crate::time_daemons::{self, TimeConflict};
+use crate::timecode;
 use num_rational::Ratio;
-use num_traits::ToPrimitive;
 
 // Data structure for the main status response
-#[derive(Serialize, Deserialize)]
-struct ApiStatus {
-    ltc_status: String,
-    ltc_timecode: String,
-    frame_rate: String,
-    system_clock: String,
-    system_date: String,
-    timecode_delta_ms: i64,
-    timecode_delta_frames: i64,
-    sync_status: String,
-    jitter_status: String,
-    lock_ratio: f64,
-    ntp_active: bool,
-    interfaces: Vec<String>,
-    hardware_offset_ms: i64,
-}
-
-// AppState to hold shared data
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct ApiStatus {
+    pub ltc_status: String,
+    /// `LIVE`, `STALE` or `NO SIGNAL`: whether LTC frames are still
+    /// arriving, whatever their LOCK or FREE status.
+    #[serde(default)]
+    pub ltc_signal: Staleness,
+    /// Time since the latest LTC frame arrived, in ms; `null` before the
+    /// first.
+    #[serde(default)]
+    pub ltc_frame_age_ms: Option<u64>,
+    pub ltc_timecode: String,
+    /// How the timecode is being read: `UTC`, a fixed offset such as
+    /// `UTC+01:00`, or `local` with the host's current offset.
+    #[serde(default)]
+    pub ltc_time_zone: String,
+    pub frame_rate: String,
+    /// Set while the frames have disagreed with `expectedFrameRate` for more
+    /// than 25 frames, e.g. `LTC 24.00fps, expected 25.00fps`.
+    #[serde(default)]
+    pub frame_rate_warning: Option<String>,
+    pub system_clock: String,
+    pub system_date: String,
+    /// Smoothed clock delta: system clock minus LTC, positive when ahead.
+    pub timecode_delta_ms: i64,
+    pub timecode_delta_frames: i64,
+    /// The delta in whole frames at the incoming rate and then, if set, at
+    /// `secondaryFrameRate`; empty before the first frame.
+    #[serde(default)]
+    pub timecode_delta_by_rate: Vec<DeltaFrames>,
+    /// The same delta unrounded, in ms; `null` before the first LOCK.
+    #[serde(default)]
+    pub timecode_delta_raw_ms: Option<f64>,
+    /// The latest LOCK frame's own delta in ms, before the phase fit and
+    /// smoothing; `null` until there is one.
+    #[serde(default)]
+    pub timecode_delta_unfiltered_ms: Option<f64>,
+    /// Which side of the IN SYNC window the delta is on, whether or not a
+    /// timeturner offset is active.
+    #[serde(default)]
+    pub clock_direction: ClockDirection,
+    pub sync_status: String,
+    pub jitter_status: String,
+    pub lock_ratio: f64,
+    pub ntp_active: bool,
+    /// The non-loopback addresses of `network_interfaces`, for older clients.
+    pub interfaces: Vec<String>,
+    /// Each network interface with its addresses and link, and whether PTP
+    /// is served from it.
+    #[serde(default)]
+    pub network_interfaces: Vec<NetInterface>,
+    pub hardware_offset_ms: i64,
+    pub serial_port: String,
+    /// What the reader's firmware reported when its port opened; `null`
+    /// until it answers, and for firmware without the handshake.
+    #[serde(default)]
+    pub reader_firmware: Option<ReaderFirmware>,
+    /// Timecode counted on the monotonic clock since the first LOCK; it does
+    /// not jump when the system clock is stepped.
+    pub session_timecode: String,
+    /// What the sync engine is doing and why, in plain language.
+    pub why: String,
+    /// Estimated drift of the system clock against LTC in ppm (positive:
+    /// gaining), once a few minutes of history are available.
+    #[serde(default)]
+    pub drift_ppm: Option<f64>,
+    /// Spread of the recent jitter samples; `null` until there are any.
+    #[serde(default)]
+    pub jitter: Option<JitterStats>,
+    /// Backpressure on the queue between the serial reader and the frame
+    /// processor.
+    #[serde(default)]
+    pub frame_queue: PipelineReport,
+    /// Skips, repeats and reversals in the incoming timecode.
+    #[serde(default)]
+    pub continuity: ContinuityReport,
+    /// Other services disciplining the clock while auto-sync is on.
+    #[serde(default)]
+    pub time_conflicts: Vec<TimeConflict>,
+}
+
+impl ApiStatus {
+    /// `ltc_status` with the age of the latest frame, e.g.
+    /// `LOCK (last frame 12 ms ago)`, noting STALE once frames are late.
+    pub fn ltc_status_text(&self) -> String {
+        match (self.ltc_frame_age_ms, self.ltc_signal) {
+            (None, _) => self.ltc_status.clone(),
+            (Some(age), Staleness::Stale) => format!("{}, STALE (last frame {} ms ago)", self.ltc_status, age),
+            (Some(age), _) => format!("{} (last frame {} ms ago)", self.ltc_status, age),
+        }
+    }
+
+    /// `time_conflicts` for display: `none`, or each conflict's detail.
+    pub fn time_conflicts_text(&self) -> String {
+        if self.time_conflicts.is_empty() {
+            return "none".into();
+        }
+        let details: Vec<&str> = self.time_conflicts.iter().map(|c| c.detail.as_str()).collect();
+        format!("⚠️ {}", details.join(" "))
+    }
+
+    /// The delta in frames for display, e.g. `+1 frames, +2 @ 29.97fps`
+    /// with a secondary rate set.
+    pub fn delta_frames_text(&self) -> String {
+        let mut text = format!("{:+} frames", self.timecode_delta_frames);
+        for secondary in self.timecode_delta_by_rate.iter().skip(1) {
+            text.push_str(&format!(", {:+} @ {}", secondary.frames, secondary.frame_rate));
+        }
+        text
+    }
+}
+
+// Generic `{ status, message }` reply used by the command endpoints.
+// Only referenced by the OpenAPI document; handlers build it with `json!`.
+#[allow(dead_code)]
+#[derive(Serialize, ToSchema)]
+struct ApiMessage {
+    /// `"success"` or `"error"`.
+    status: String,
+    message: String,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "NTP Timeturner API",
+        description = "HTTP API for the NTP Timeturner LTC-to-system-clock bridge."
+    ),
+    paths(
+        get_status,
+        stream_status,
+        manual_sync,
+        preview_sync,
+        get_config,
+        get_config_offset,
+        update_config,
+        patch_config,
+        get_logs,
+        get_log_level,
+        set_log_level,
+        nudge_clock,
+        set_date,
+        preview_set_date,
+        get_audit,
+        get_metrics,
+        get_history,
+        get_delta_series,
+        get_jitter_series,
+        get_report,
+        reset_report,
+        get_ntp_check,
+        get_rtp_midi,
+        get_ptp_messages,
+        get_ptp_masters,
+        get_chrony,
+        control_chrony,
+        get_system,
+        get_source,
+        set_source,
+        get_serial_ports,
+        set_serial_port,
+        get_presets,
+        activate_preset,
+        get_health,
+        get_components,
+        get_sources,
+        get_sinks,
+        get_export,
+        start_export,
+        stop_export,
+        get_ready,
+        get_openapi
+    ),
+    components(schemas(
+        ApiStatus,
+        ClockDirection,
+        Staleness,
+        ContinuityReport,
+        Discontinuity,
+        DiscontinuityKind,
+        DeltaFrames,
+        ApiMessage,
+        SyncPreview,
+        DatePreview,
+        OffsetContribution,
+        SourceReport,
+        SourceHealth,
+        TimeEstimate,
+        SinkConfig,
+        SinkHealth,
+        ExportStatus,
+        TimeConflict,
+        ExportRequest,
+        Config,
+        TimeturnerOffset,
+        OffsetPreview,
+        NudgeRequest,
+        NudgeResponse,
+        SetDateRequest,
+        AuditEntry,
+        AuditSource,
+        HistoryConfig,
+        HistoryPoint,
+        HistoryResponse,
+        SeriesPoint,
+        SeriesResponse,
+        SessionReport,
+        AlertRecord,
+        NtpSample,
+        RtpMidiStatus,
+        RtpMidiPeer,
+        PtpMessage,
+        PtpAnnounce,
+        PtpMasterStatus,
+        ForeignMaster,
+        JitterStats,
+        PipelineReport,
+        LogLevel,
+        ChronyReport,
+        ChronyTracking,
+        ChronySource,
+        ServiceAction,
+        SystemInfo,
+        Usage,
+        Throttling,
+        NicInfo,
+        NetInterface,
+        RtcInfo,
+        RtcWrite,
+        HealthReport,
+        WorkerHealth,
+        ComponentHealth,
+        Restart,
+        ReadyReport,
+        ReadyCheck,
+        SyncSource,
+        SourceStatus,
+        SourceRequest,
+        SerialPortReport,
+        SerialPortRequest,
+        ReaderPort,
+        ReaderFirmware,
+        ReaderLatency,
+        OffsetPreset,
+        PresetsStatus,
+        ScheduledPreset,
+        PresetRequest
+    ))
+)]
+struct ApiDoc;
+
+/// Port the API and web UI are served on, over IPv4 and IPv6.
+const API_PORT: u16 = 8080;
+
+/// How long a serialized status snapshot is served before being rebuilt.
+/// One frame at 25 fps, so pollers never miss a timecode step.
+const STATUS_MAX_AGE: Duration = Duration::from_millis(40);
+
+/// Shortest gap between `/api/status/stream` events; frames arriving in
+/// between are coalesced into the next event.
+const STREAM_MIN_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Longest gap between `/api/status/stream` events, so clients see the clock
+/// advance without LTC and can tell a quiet daemon from a dead one.
+const STREAM_HEARTBEAT: Duration = Duration::from_secs(1);
+
+/// How long the NTP service state and interface list are reused for.
+const HOST_INFO_MAX_AGE: Duration = Duration::from_secs(1);
+
+/// The latest `/api/status` body, serialized once and shared by every
+/// poller until it expires or a command or config change invalidates it.
+#[derive(Default)]
+pub struct StatusCache {
+    snapshot: Mutex<Option<(Instant, web::Bytes)>>,
+    host_info: Mutex<Option<(Instant, bool, Vec<NetInterface>)>>,
+}
+
+impl StatusCache {
+    /// Force the next status request to rebuild the snapshot.
+    pub fn invalidate(&self) {
+        *self.snapshot.lock().unwrap() = None;
+    }
+
+    /// The current snapshot, if it is still fresh.
+    pub fn get(&self) -> Option<web::Bytes> {
+        self.snapshot
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|(at, _)| at.elapsed() < STATUS_MAX_AGE)
+            .map(|(_, bytes)| bytes.clone())
+    }
+
+    fn store(&self, bytes: web::Bytes) {
+        *self.snapshot.lock().unwrap() = Some((Instant::now(), bytes));
+    }
+
+    /// Whether NTP is active and the network interfaces, re-read at most
+    /// once per `HOST_INFO_MAX_AGE` since both shell out or walk interfaces.
+    async fn host_info(&self, clock: Arc<dyn ClockControl>, phc_device: Option<String>) -> (bool, Vec<NetInterface>) {
+        if let Some((at, ntp_active, interfaces)) = self.host_info.lock().unwrap().as_ref() {
+            if at.elapsed() < HOST_INFO_MAX_AGE {
+                return (*ntp_active, interfaces.clone());
+            }
+        }
+        let (ntp_active, interfaces) = web::block(move || {
+            (clock.ntp_active(), host::net_interfaces(phc_device.as_deref()))
+        })
+        .await
+        .unwrap_or_default();
+        *self.host_info.lock().unwrap() = Some((Instant::now(), ntp_active, interfaces.clone()));
+        (ntp_active, interfaces)
+    }
+}
+
+// AppState to hold shared data.
+//
+// Locking rules, shared with the frame processor and auto-sync executor:
+// - read LTC state through `ltc` snapshots; lock `ltc_state` only to change
+//   it (e.g. noting a step), so readers never block frame processing;
+// - take `ltc_state` before `config` when both are needed, never the reverse;
+// - never hold a guard across an `.await` or while running an external
+//   command (`date`, `adjtimex`, `systemctl`). Clone what is needed, drop the
+//   guards, then do the slow work (via `web::block` inside handlers).
 pub struct AppState {
     pub ltc_state: Arc<Mutex<LtcState>>,
+    /// Published snapshots of `ltc_state`, for everything that only reads.
+    pub ltc: LtcView,
     pub config: Arc<Mutex<Config>>,
     pub log_buffer: Arc<Mutex<VecDeque<String>>>,
+    /// Where API config changes are persisted.
+    pub config_path: String,
+    /// The LTC reader's port, reported in the status and switched with
+    /// `PUT /api/serial/port`.
+    pub serial: Arc<ReaderSelection>,
+    /// Trail of clock mutations, served at `/api/audit`.
+    pub audit: Arc<AuditLog>,
+    /// Runs sync, nudge and set-date commands.
+    pub clock: Arc<dyn ClockControl>,
+    /// Serialises clock changes with auto-sync and rate-limits API commands.
+    pub clock_gate: Arc<ClockGate>,
+    /// Authentication backend for the current `api` config.
+    pub auth: AuthCache,
+    /// Downsampled clock delta history, served at `/api/history`.
+    pub history: Arc<Mutex<DeltaHistory>>,
+    /// Per-second delta and jitter, served at `/api/series/*`.
+    pub series: Arc<Mutex<Series>>,
+    /// Totals for the end-of-show report, served at `/api/report`.
+    pub session: Arc<Mutex<Session>>,
+    /// Pre-serialized `/api/status` body.
+    pub status_cache: StatusCache,
+    /// Latest NTP cross-check results, served at `/api/ntp_check`.
+    pub ntp_check: Arc<Mutex<NtpCrossCheck>>,
+    /// Open RTP-MIDI sessions, served at `/api/rtp_midi`.
+    pub rtp_midi: Arc<Mutex<RtpMidiSessions>>,
+    /// Recent PTP messages, served at `/api/ptp/messages`.
+    pub ptp_capture: Arc<Mutex<PtpCapture>>,
+    /// Serve the web UI from this directory instead of the embedded copy.
+    pub web_root: Option<PathBuf>,
+    /// Worker heartbeats behind `/api/health` and `/api/ready`.
+    pub health: Arc<Health>,
+    /// Restart state of the supervised components, at `/api/components`.
+    pub supervisor: Arc<Supervisor>,
+    /// Tokens from `/api/sync/preview` for steps over `confirmStepMs`.
+    pub confirmations: Arc<Confirmations>,
+    /// Registered time sources, served at `/api/sources`.
+    pub sources: Arc<SourceRegistry>,
+    /// Health of the configured output sinks, served at `/api/sinks`.
+    pub sinks: Arc<SinkBoard>,
+    /// The raw measurement export driven by `/api/export/start` and `/stop`.
+    pub export: Arc<Mutex<Exporter>>,
+}
+
+/// Who sent `req`, from its `X-Timeturner-Source` header.
+fn request_source(req: &HttpRequest) -> AuditSource {
+    AuditSource::from_header(
+        req.headers()
+            .get(audit::SOURCE_HEADER)
+            .and_then(|v| v.to_str().ok()),
+    )
+}
+
+/// Snapshot the latest frame and smoothed delta for an audited command.
+fn frame_and_delta(data: &AppState) -> (Option<sync_logic::LtcFrame>, Option<i64>) {
+    let state = data.ltc.get();
+    (state.latest.clone(), audit::current_delta(&state))
 }
 
+#[utoipa::path(
+    responses((status = 200, description = "Current LTC and clock status", body = ApiStatus))
+)]
 #[get("/api/status")]
 async fn get_status(data: web::Data<AppState>) -> impl Responder {
-    let state = data.ltc_state.lock().unwrap();
-    let config = data.config.lock().unwrap();
-    let hw_offset_ms = config.hardware_offset_ms;
-
-    let ltc_status = state.latest.as_ref().map_or("(waiting)".to_string(), |f| f.status.clone());
-    let ltc_timecode = state.latest.as_ref().map_or("…".to_string(), |f| {
-        let sep = if f.is_drop_frame { ';' } else { ':' };
-        format!(
-            "{:02}:{:02}:{:02}{}{:02}",
-            f.hours, f.minutes, f.seconds, sep, f.frames
-        )
-    });
-    let frame_rate = state.latest.as_ref().map_or("…".to_string(), |f| {
-        format!("{:.2}fps", f.frame_rate.to_f64().unwrap_or(0.0))
+    match status_bytes(&data).await {
+        Ok(bytes) => HttpResponse::Ok()
+            .content_type(actix_web::http::header::ContentType::json())
+            .body(bytes),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// The serialized status, from the cache when it is fresh.
+async fn status_bytes(data: &AppState) -> Result<web::Bytes, serde_json::Error> {
+    if let Some(bytes) = data.status_cache.get() {
+        return Ok(bytes);
+    }
+    // Shelling out to systemctl and walking interfaces happens before any lock is taken.
+    let phc_device = data.config.lock().unwrap().phc.device.clone();
+    let (ntp_active, interfaces) = data.status_cache.host_info(data.clock.clone(), phc_device).await;
+    let status = build_status(data, ntp_active, interfaces);
+    let bytes = web::Bytes::from(serde_json::to_vec(&status)?);
+    data.status_cache.store(bytes.clone());
+    Ok(bytes)
+}
+
+#[utoipa::path(
+    responses((
+        status = 200,
+        description = "Server-sent events, one `data:` line with an ApiStatus per event",
+        content_type = "text/event-stream",
+        body = ApiStatus
+    ))
+)]
+#[get("/api/status/stream")]
+async fn stream_status(data: web::Data<AppState>) -> impl Responder {
+    let updates = data.ltc.subscribe();
+    let events = futures_util::stream::unfold((data, updates, true), |(data, mut updates, first)| async move {
+        if !first {
+            // Coalesce changes to one event per interval, and send one at
+            // least every heartbeat even when nothing is arriving.
+            tokio::time::sleep(STREAM_MIN_INTERVAL).await;
+            if !updates.has_changed().unwrap_or(false) {
+                let _ = tokio::time::timeout(STREAM_HEARTBEAT - STREAM_MIN_INTERVAL, updates.changed()).await;
+            }
+        }
+        updates.mark_unchanged();
+        let event = status_bytes(&data).await.map(|json| {
+            let mut event = b"data: ".to_vec();
+            event.extend_from_slice(&json);
+            event.extend_from_slice(b"\n\n");
+            web::Bytes::from(event)
+        });
+        Some((event.map_err(actix_web::error::ErrorInternalServerError), (data, updates, false)))
     });
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((actix_web::http::header::CACHE_CONTROL, "no-cache"))
+        .streaming(events)
+}
+
+fn status_snapshot(data: &AppState) -> StatusSnapshot {
+    StatusSnapshot::new(&data.ltc.get(), &data.config.lock().unwrap())
+}
+
+fn build_status(data: &AppState, ntp_active: bool, network_interfaces: Vec<NetInterface>) -> ApiStatus {
+    let secs_since_step = data.audit.secs_since_step();
+    let state = data.ltc.get();
+    let config = data.config.lock().unwrap();
+    let status = StatusSnapshot::new(&state, &config);
 
     let now_local = Local::now();
     let system_clock = format!(
@@ -67,49 +520,242 @@ async fn get_status(data: web::Data<AppState>) -> impl Responder {
     );
     let system_date = now_local.format("%Y-%m-%d").to_string();
 
-    let avg_delta = state.get_ewma_clock_delta();
-    let mut delta_frames = 0;
-    if let Some(frame) = &state.latest {
-        let delta_ms_ratio = Ratio::new(avg_delta, 1);
-        let frames_ratio = delta_ms_ratio * frame.frame_rate / Ratio::new(1000, 1);
-        delta_frames = frames_ratio.round().to_integer();
-    }
-
-    let sync_status = sync_logic::get_sync_status(avg_delta, &config);
-    let jitter_status = sync_logic::get_jitter_status(state.average_jitter());
-    let lock_ratio = state.lock_ratio();
-
-    let ntp_active = system::ntp_service_active();
-    let interfaces = get_if_addrs()
-        .unwrap_or_default()
-        .into_iter()
-        .filter(|ifa| !ifa.is_loopback())
-        .map(|ifa| ifa.ip().to_string())
-        .collect();
-
-    HttpResponse::Ok().json(ApiStatus {
-        ltc_status,
-        ltc_timecode,
-        frame_rate,
+    ApiStatus {
+        ltc_status: status.ltc_status.unwrap_or_else(|| "(waiting)".to_string()),
+        ltc_signal: status.staleness,
+        ltc_frame_age_ms: status.frame_age_ms,
+        ltc_timecode: status.timecode.unwrap_or_else(|| "…".to_string()),
+        ltc_time_zone: config.ltc_zone().describe(),
+        frame_rate: status.frame_rate.map_or("…".to_string(), sync_logic::frame_rate_label),
+        frame_rate_warning: status.frame_rate_warning,
         system_clock,
         system_date,
-        timecode_delta_ms: avg_delta,
-        timecode_delta_frames: delta_frames,
-        sync_status: sync_status.to_string(),
-        jitter_status: jitter_status.to_string(),
-        lock_ratio,
+        timecode_delta_ms: status.delta_ms,
+        timecode_delta_frames: status.delta_frames,
+        timecode_delta_by_rate: status.delta_by_rate,
+        timecode_delta_raw_ms: status.delta_raw_ms,
+        timecode_delta_unfiltered_ms: status.frame_delta_ms,
+        clock_direction: status.clock_direction,
+        sync_status: status.sync_status.to_string(),
+        jitter_status: status.jitter_status.to_string(),
+        lock_ratio: status.lock_ratio,
         ntp_active,
-        interfaces,
-        hardware_offset_ms: hw_offset_ms,
+        interfaces: network_interfaces
+            .iter()
+            .flat_map(|nic| nic.ipv4.iter().chain(&nic.ipv6).cloned())
+            .collect(),
+        network_interfaces,
+        hardware_offset_ms: config.hardware_offset_ms,
+        serial_port: data.serial.current().path,
+        reader_firmware: data.serial.firmware(),
+        session_timecode: state.session_timecode().unwrap_or_else(|| "…".to_string()),
+        why: sync_logic::explain(&state, &config, secs_since_step),
+        drift_ppm: status.drift_ppm,
+        jitter: state.jitter.clone(),
+        frame_queue: state.pipeline.clone(),
+        continuity: status.continuity,
+        time_conflicts: time_daemons::current(),
+    }
+}
+
+/// One offset in a sync preview and what it adds to the target time.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OffsetContribution {
+    /// `timeturner` or `hardware`.
+    pub source: String,
+    /// The offset as configured.
+    pub configured: String,
+    /// What it adds to the target time, in milliseconds. `hardwareOffsetMs`
+    /// is listed for reference and adds nothing.
+    pub applied_ms: f64,
+}
+
+/// What `POST /api/sync` would do now, from `GET /api/sync/preview`.
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncPreview {
+    /// The latest frame, which the sync would follow.
+    pub timecode: String,
+    /// The time that timecode names today, before any offset.
+    #[schema(value_type = String, format = DateTime)]
+    pub timecode_time: DateTime<Local>,
+    pub offsets: Vec<OffsetContribution>,
+    /// The time the clock would be set to: `timecodeTime` plus the offsets.
+    #[schema(value_type = String, format = DateTime)]
+    pub target_time: DateTime<Local>,
+    /// How far that moves the clock, in milliseconds; positive is forward.
+    pub step_ms: i64,
+    /// The step is larger than `confirmStepMs`, so `POST /api/sync` needs
+    /// `confirm=true` and `token`.
+    pub confirm_required: bool,
+    /// Confirms this step once, for `expiresInSecs`.
+    pub token: Option<String>,
+    pub expires_in_secs: Option<u64>,
+    /// The step is larger than `maxAutoStepMs`, so `POST /api/sync` needs
+    /// `override=true`.
+    pub over_step_limit: bool,
+}
+
+/// What a sync to `frame` would set the clock to, and how it got there.
+/// Leaves the confirmation fields unset.
+fn plan_sync(frame: &sync_logic::LtcFrame, config: &Config) -> Result<SyncPreview, TargetTimeError> {
+    let target_time = system::calculate_target_time(frame, config)?;
+    let offset = &config.timeturner_offset;
+    let timeturner = system::offset_duration(offset, frame.frame_rate)?;
+    let arrival = frame.arrival(&ClockPair::now()).with_timezone(&Local);
+    Ok(SyncPreview {
+        timecode: frame.timecode(),
+        timecode_time: target_time - timeturner,
+        offsets: vec![
+            OffsetContribution {
+                source: "timeturner".into(),
+                configured: format!(
+                    "{}h {}m {}s {}f {}ms",
+                    offset.hours, offset.minutes, offset.seconds, offset.frames, offset.milliseconds
+                ),
+                applied_ms: timeturner.num_microseconds().unwrap_or(0) as f64 / 1000.0,
+            },
+            OffsetContribution {
+                source: "hardware".into(),
+                configured: format!("{} ms", config.hardware_offset_ms),
+                applied_ms: 0.0,
+            },
+        ],
+        target_time,
+        step_ms: target_time.signed_duration_since(arrival).num_milliseconds(),
+        confirm_required: false,
+        token: None,
+        expires_in_secs: None,
+        over_step_limit: false,
     })
 }
 
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Target time, offsets and step of a sync now, with a token if it needs confirming. The clock is not touched", body = SyncPreview),
+        (status = 400, description = "No LTC timecode available", body = ApiMessage),
+        (status = 422, description = "The timecode names no valid time today", body = ApiMessage)
+    )
+)]
+#[get("/api/sync/preview")]
+async fn preview_sync(data: web::Data<AppState>) -> impl Responder {
+    let Some(frame) = data.ltc.get().latest.clone() else {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({ "status": "error", "message": "No LTC timecode available to sync to." }));
+    };
+    let config = data.config.lock().unwrap().clone();
+    match plan_sync(&frame, &config) {
+        Ok(mut preview) => {
+            preview.over_step_limit = config.step_over_limit(preview.step_ms);
+            preview.confirm_required = confirm::required(preview.step_ms, config.confirm_step_ms);
+            if preview.confirm_required {
+                preview.token = Some(data.confirmations.issue("sync", preview.step_ms, Instant::now()));
+                preview.expires_in_secs = Some(confirm::TOKEN_TTL.as_secs());
+            }
+            HttpResponse::Ok().json(preview)
+        }
+        Err(e) => HttpResponse::UnprocessableEntity()
+            .json(serde_json::json!({ "status": "error", "message": e.to_string() })),
+    }
+}
+
+/// Confirms a clock change larger than `confirmStepMs`.
+#[derive(Deserialize, utoipa::IntoParams)]
+struct ConfirmQuery {
+    /// Go ahead with a step larger than `confirmStepMs`; needs `token`.
+    #[serde(default)]
+    confirm: bool,
+    /// The token from the change's preview.
+    token: Option<String>,
+}
+
+/// Whether a clock change for `action` that previewed as `previewed` may run:
+/// its step of `step_ms` is within `confirmStepMs`, or `confirm` is set with
+/// a token issued for it. `previewed` is usually the step itself.
+fn step_confirmed(
+    data: &AppState,
+    config: &Config,
+    action: &str,
+    step_ms: i64,
+    previewed: i64,
+    confirm: bool,
+    token: Option<&str>,
+) -> bool {
+    !confirm::required(step_ms, config.confirm_step_ms)
+        || (confirm && token.is_some_and(|token| data.confirmations.redeem(action, token, previewed, Instant::now())))
+}
+
+/// The `428` for `what` stepping the clock by `step_ms` unconfirmed, naming
+/// the `preview` endpoint that issues its tokens.
+fn unconfirmed(what: &str, step_ms: i64, preview: &str) -> HttpResponse {
+    HttpResponse::PreconditionRequired().json(serde_json::json!({
+        "status": "error",
+        "message": format!(
+            "This {} steps the clock by {:+} ms; preview it at {} and confirm with its token.",
+            what, step_ms, preview
+        )
+    }))
+}
+
+/// Confirms a sync, or a config change that re-syncs, larger than
+/// `confirmStepMs` or `maxAutoStepMs`.
+#[derive(Deserialize, utoipa::IntoParams)]
+struct StepQuery {
+    /// Go ahead with a step larger than `confirmStepMs`; needs `token`.
+    #[serde(default)]
+    confirm: bool,
+    /// The token from `GET /api/sync/preview`, or from `GET /api/config/offset`
+    /// for a config change.
+    token: Option<String>,
+    /// Go ahead with a step larger than `maxAutoStepMs`.
+    #[serde(default, rename = "override")]
+    override_limit: bool,
+}
+
+#[utoipa::path(
+    params(StepQuery),
+    responses(
+        (status = 200, description = "Sync command issued", body = ApiMessage),
+        (status = 400, description = "No LTC timecode available", body = ApiMessage),
+        (status = 500, description = "Sync command failed", body = ApiMessage),
+        (status = 409, description = "Another clock change is in progress", body = ApiMessage),
+        (status = 428, description = "The step is larger than `confirmStepMs` and was not confirmed with a preview token, or larger than `maxAutoStepMs` without `override=true`", body = ApiMessage),
+        (status = 429, description = "Too soon after the last clock command", body = ApiMessage)
+    )
+)]
 #[post("/api/sync")]
-async fn manual_sync(data: web::Data<AppState>) -> impl Responder {
-    let state = data.ltc_state.lock().unwrap();
-    let config = data.config.lock().unwrap();
-    if let Some(frame) = &state.latest {
-        if system::trigger_sync(frame, &config).is_ok() {
+async fn manual_sync(req: HttpRequest, data: web::Data<AppState>, query: web::Query<StepQuery>) -> impl Responder {
+    let (frame, pre_delta) = frame_and_delta(&data);
+    if let Some(frame) = frame {
+        let config = data.config.lock().unwrap().clone();
+        let step_ms = plan_sync(&frame, &config).map(|plan| plan.step_ms);
+        if let Some(step_ms) = step_ms.ok().filter(|&step| config.step_over_limit(step) && !query.override_limit) {
+            return HttpResponse::PreconditionRequired().json(serde_json::json!({
+                "status": "error",
+                "message": format!(
+                    "This sync steps the clock by {:+} ms, over maxAutoStepMs ({} ms); sync with override=true to apply it.",
+                    step_ms, config.max_auto_step_ms
+                )
+            }));
+        }
+        if let Ok(step_ms) = step_ms {
+            if !step_confirmed(&data, &config, "sync", step_ms, step_ms, query.confirm, query.token.as_deref()) {
+                return unconfirmed("sync", step_ms, "/api/sync/preview");
+            }
+        }
+        let not_run = system::sync_command(&frame, &config);
+        let (clock, state) = (data.clock.clone(), data.ltc_state.clone());
+        let result = web::block(move || system::step_to_ltc(&*clock, &frame, &config, &state)).await;
+        data.status_cache.invalidate();
+        let (command, success) = result.unwrap_or((not_run, false));
+        if success {
+            data.ltc_state.lock().unwrap().note_step();
+        }
+        let mut entry = AuditEntry::new(request_source(&req), "sync", command, pre_delta);
+        entry.success = success;
+        audit::record_after_settle(data.audit.clone(), data.ltc.clone(), entry);
+        if success {
             HttpResponse::Ok().json(serde_json::json!({ "status": "success", "message": "Sync command issued." }))
         } else {
             HttpResponse::InternalServerError().json(serde_json::json!({ "status": "error", "message": "Sync command failed." }))
@@ -119,40 +765,521 @@ async fn manual_sync(data: web::Data<AppState>) -> impl Responder {
     }
 }
 
+#[utoipa::path(
+    responses((status = 200, description = "Current configuration", body = Config))
+)]
 #[get("/api/config")]
 async fn get_config(data: web::Data<AppState>) -> impl Responder {
     let config = data.config.lock().unwrap();
     HttpResponse::Ok().json(&*config)
 }
 
+/// An offset to preview instead of the configured `timeturnerOffset`. Any
+/// field given selects it, and the fields left out are 0.
+#[derive(Deserialize, utoipa::IntoParams)]
+struct OffsetQuery {
+    hours: Option<i64>,
+    minutes: Option<i64>,
+    seconds: Option<i64>,
+    frames: Option<i64>,
+    milliseconds: Option<i64>,
+}
+
+impl OffsetQuery {
+    fn offset(&self) -> Option<TimeturnerOffset> {
+        let fields = [self.hours, self.minutes, self.seconds, self.frames, self.milliseconds];
+        fields.iter().any(Option::is_some).then(|| TimeturnerOffset {
+            hours: self.hours.unwrap_or(0),
+            minutes: self.minutes.unwrap_or(0),
+            seconds: self.seconds.unwrap_or(0),
+            frames: self.frames.unwrap_or(0),
+            milliseconds: self.milliseconds.unwrap_or(0),
+        })
+    }
+}
+
+/// A timeturner offset in canonical form, from `GET /api/config/offset`.
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OffsetPreview {
+    /// The configured offset, or the one in the query.
+    pub offset: TimeturnerOffset,
+    /// The same offset carried and borrowed across fields at `frameRate`:
+    /// every field with the sign of the whole, and each below the next
+    /// field's unit. Saving it changes nothing.
+    pub normalized: TimeturnerOffset,
+    /// What the offset adds to the target time, in milliseconds.
+    pub net_ms: i64,
+    /// The rate frames are counted at: the incoming LTC's, or
+    /// `expectedFrameRate` without LTC.
+    pub frame_rate: String,
+    /// How far saving the offset would step the clock now, in milliseconds;
+    /// absent without LTC.
+    pub step_ms: Option<i64>,
+    /// The step is larger than `confirmStepMs`, so saving the offset needs
+    /// `confirm=true` and `token`.
+    pub confirm_required: bool,
+    /// Confirms saving this offset once, for `expiresInSecs`.
+    pub token: Option<String>,
+    pub expires_in_secs: Option<u64>,
+    /// The step is larger than `maxAutoStepMs`, so saving the offset needs
+    /// `override=true`.
+    pub over_step_limit: bool,
+}
+
+#[utoipa::path(
+    params(OffsetQuery),
+    responses(
+        (status = 200, description = "The offset in canonical form, its net effect in milliseconds and the step saving it would make, with a token if that needs confirming", body = OffsetPreview),
+        (status = 400, description = "No LTC or expectedFrameRate to count frames at, or the offset is out of range", body = ApiMessage)
+    )
+)]
+#[get("/api/config/offset")]
+async fn get_config_offset(data: web::Data<AppState>, query: web::Query<OffsetQuery>) -> impl Responder {
+    let config = data.config.lock().unwrap().clone();
+    let offset = query.offset().unwrap_or_else(|| config.timeturner_offset.clone());
+    let frame = data.ltc.get().latest.clone();
+    let Some(rate) = frame.as_ref().map(|f| f.frame_rate).or(config.expected_rate()) else {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "message": "No LTC frame rate to count frames at; set expectedFrameRate to preview without LTC.",
+        }));
+    };
+    let saved = Config { timeturner_offset: offset.clone(), ..config.clone() };
+    let step_ms = frame.and_then(|frame| plan_sync(&frame, &saved).ok()).map(|plan| plan.step_ms);
+    let confirm_required = step_ms.is_some_and(|step| confirm::required(step, config.confirm_step_ms));
+    match timecode::offset_ms(&offset, rate).zip(timecode::normalize_offset(&offset, rate)) {
+        Some((net_ms, normalized)) => HttpResponse::Ok().json(OffsetPreview {
+            offset,
+            normalized,
+            net_ms,
+            frame_rate: sync_logic::frame_rate_label(rate),
+            step_ms,
+            confirm_required,
+            token: step_ms
+                .filter(|_| confirm_required)
+                .map(|step| data.confirmations.issue("config", step, Instant::now())),
+            expires_in_secs: confirm_required.then(|| confirm::TOKEN_TTL.as_secs()),
+            over_step_limit: step_ms.is_some_and(|step| config.step_over_limit(step)),
+        }),
+        None => HttpResponse::BadRequest()
+            .json(serde_json::json!({ "status": "error", "message": "The offset is out of range." })),
+    }
+}
+
+#[utoipa::path(
+    responses((status = 200, description = "Most recent log lines, oldest first", body = [String]))
+)]
 #[get("/api/logs")]
 async fn get_logs(data: web::Data<AppState>) -> impl Responder {
     let logs = data.log_buffer.lock().unwrap();
     HttpResponse::Ok().json(&*logs)
 }
 
-#[derive(Deserialize)]
+/// The daemon's log filter, in `logLevel` syntax.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct LogLevel {
+    /// A level, optionally followed by per-module levels, e.g.
+    /// `info,ntp_client=warn,processor=debug`.
+    pub level: String,
+}
+
+#[utoipa::path(
+    responses((status = 200, description = "Log filter currently applied", body = LogLevel))
+)]
+#[get("/api/logs/level")]
+async fn get_log_level() -> impl Responder {
+    HttpResponse::Ok().json(LogLevel { level: logger::filter().to_string() })
+}
+
+#[utoipa::path(
+    request_body = LogLevel,
+    responses(
+        (status = 200, description = "Log filter applied until restart or the next `logLevel` change in config", body = LogLevel),
+        (status = 400, description = "Unknown level or malformed filter", body = ApiMessage)
+    )
+)]
+#[put("/api/logs/level")]
+async fn set_log_level(req: web::Json<LogLevel>) -> impl Responder {
+    match req.level.parse::<LogFilter>() {
+        Ok(filter) => {
+            let level = filter.to_string();
+            logger::set_filter(filter);
+            log::info!("Log level set via API: {}", level);
+            HttpResponse::Ok().json(LogLevel { level })
+        }
+        Err(e) => HttpResponse::BadRequest()
+            .json(serde_json::json!({ "status": "error", "message": e })),
+    }
+}
+
+/// The discipline source: what is selected and what auto-sync follows now.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SourceStatus {
+    /// `syncSource` from config; `auto` unless pinned.
+    pub selected: SyncSource,
+    /// What auto-sync is following now (`ltc`, `holdover` or `manual`);
+    /// `null` while auto-sync is off or has nothing to follow.
+    pub active: Option<SyncSource>,
+    pub auto_sync_enabled: bool,
+}
+
+/// The source to pin auto-sync to, or `auto` to release it.
+#[derive(Deserialize, ToSchema)]
+struct SourceRequest {
+    source: SyncSource,
+}
+
+fn source_status(data: &AppState) -> SourceStatus {
+    let state = data.ltc.get();
+    let config = data.config.lock().unwrap();
+    SourceStatus {
+        selected: config.sync_source,
+        active: sync_logic::active_source(&state, &config),
+        auto_sync_enabled: config.auto_sync_enabled,
+    }
+}
+
+#[utoipa::path(
+    responses((status = 200, description = "Selected and active discipline source", body = SourceStatus))
+)]
+#[get("/api/source")]
+async fn get_source(data: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(source_status(&data))
+}
+
+#[utoipa::path(
+    request_body = SourceRequest,
+    responses(
+        (status = 200, description = "Source pinned and saved to config.yml", body = SourceStatus),
+        (status = 400, description = "Unknown source", body = ApiMessage),
+        (status = 500, description = "Failed to write config.yml", body = ApiMessage)
+    )
+)]
+#[put("/api/source")]
+async fn set_source(data: web::Data<AppState>, req: web::Json<SourceRequest>) -> impl Responder {
+    let saved = {
+        let mut config = data.config.lock().unwrap();
+        config.sync_source = req.source;
+        config::save_config(&data.config_path, &config)
+    };
+    data.status_cache.invalidate();
+    if saved.is_err() {
+        log::error!("Failed to write config.yml");
+        return HttpResponse::InternalServerError().json(
+            serde_json::json!({ "status": "error", "message": "Failed to write config.yml" }),
+        );
+    }
+    log::info!("🎯 Sync source set to {:?} via API", req.source);
+    HttpResponse::Ok().json(source_status(&data))
+}
+
+#[utoipa::path(
+    responses((status = 200, description = "Serial ports on this host, with the LTC reader's marked active", body = [SerialPortReport]))
+)]
+#[get("/api/serial/ports")]
+async fn get_serial_ports(data: web::Data<AppState>) -> impl Responder {
+    let active = data.serial.current().path;
+    let ports = web::block(serial_input::available_ports).await.unwrap_or_default();
+    let reports: Vec<SerialPortReport> = ports.iter().map(|port| port.report(&active)).collect();
+    HttpResponse::Ok().json(reports)
+}
+
+/// The port to read LTC from, optionally at another baud rate than its
+/// `serialDevices` entry (or 115200).
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct SerialPortRequest {
+    path: String,
+    baud_rate: Option<u32>,
+}
+
+#[utoipa::path(
+    request_body = SerialPortRequest,
+    responses(
+        (status = 200, description = "The reader is moving to this port", body = ReaderPort),
+        (status = 400, description = "No such serial port, or a zero baud rate", body = ApiMessage),
+        (status = 409, description = "Timecode is read from VITC, not a serial port", body = ApiMessage)
+    )
+)]
+#[put("/api/serial/port")]
+async fn set_serial_port(data: web::Data<AppState>, req: web::Json<SerialPortRequest>) -> impl Responder {
+    if !data.serial.is_switchable() {
+        return HttpResponse::Conflict().json(serde_json::json!({
+            "status": "error",
+            "message": "Timecode is read from VITC, not a serial port."
+        }));
+    }
+    if req.baud_rate == Some(0) {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({ "status": "error", "message": "baudRate must be greater than zero." }));
+    }
+    let ports = web::block(serial_input::available_ports).await.unwrap_or_default();
+    let Some(port) = ports.iter().find(|port| port.path == req.path) else {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "message": format!("No serial port {}.", req.path)
+        }));
+    };
+    let mut reader = {
+        let config = data.config.lock().unwrap();
+        serial_input::reader_on(port, &config.serial_devices, config.reader_latency)
+    };
+    if let Some(baud_rate) = req.baud_rate {
+        reader.baud_rate = baud_rate;
+    }
+    data.serial.switch(reader.clone());
+    data.status_cache.invalidate();
+    log::info!("📡 LTC reader switched to {} @ {} baud via API", reader.path, reader.baud_rate);
+    HttpResponse::Ok().json(reader)
+}
+
+/// How far to slew the clock: exactly one of `microseconds` or `frames`.
+#[derive(Deserialize, ToSchema)]
 struct NudgeRequest {
+    /// Negative retards the clock.
+    #[serde(default)]
+    microseconds: Option<i64>,
+    /// Frames at the current LTC frame rate; negative retards the clock.
+    #[serde(default)]
+    frames: Option<i64>,
+}
+
+/// What a nudge ran and how it went.
+#[derive(Serialize, ToSchema)]
+struct NudgeResponse {
+    /// `"success"` or `"error"`.
+    status: String,
+    message: String,
+    /// The slew requested of `adjtimex`.
     microseconds: i64,
+    command: String,
+}
+
+/// The slew `req` asks for, in microseconds, or the status and reason for
+/// refusing it. `rate` is the current LTC frame rate, if any.
+fn nudge_microseconds(
+    req: &NudgeRequest,
+    rate: Option<Ratio<i64>>,
+    config: &Config,
+) -> Result<i64, (StatusCode, String)> {
+    let invalid = |message: String| (StatusCode::UNPROCESSABLE_ENTITY, message);
+    let microseconds = match (req.microseconds, req.frames) {
+        (Some(us), None) => us,
+        (None, Some(frames)) => {
+            let Some(rate) = rate else {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "No LTC frame rate to convert frames with.".into(),
+                ));
+            };
+            frames
+                .checked_mul(1_000_000)
+                .map(|us| (Ratio::from_integer(us) / rate).round().to_integer())
+                .ok_or_else(|| invalid(format!("{} frames is out of range.", frames)))?
+        }
+        _ => return Err(invalid("Give exactly one of microseconds or frames.".into())),
+    };
+    if microseconds < config.nudge_min_us || microseconds > config.nudge_max_us {
+        return Err(invalid(format!(
+            "A nudge of {} us is outside the allowed {} to {} us.",
+            microseconds, config.nudge_min_us, config.nudge_max_us
+        )));
+    }
+    Ok(microseconds)
 }
 
+#[utoipa::path(
+    request_body = NudgeRequest,
+    responses(
+        (status = 200, description = "Clock nudge command issued", body = NudgeResponse),
+        (status = 400, description = "`frames` given but no LTC frame rate is known", body = ApiMessage),
+        (status = 422, description = "Neither or both units given, or the slew is outside `nudgeMinUs..=nudgeMaxUs`", body = ApiMessage),
+        (status = 500, description = "Clock nudge command failed", body = NudgeResponse),
+        (status = 409, description = "Another clock change is in progress", body = ApiMessage),
+        (status = 429, description = "Too soon after the last clock command", body = ApiMessage)
+    )
+)]
 #[post("/api/nudge_clock")]
-async fn nudge_clock(req: web::Json<NudgeRequest>) -> impl Responder {
-    if system::nudge_clock(req.microseconds).is_ok() {
-        HttpResponse::Ok().json(serde_json::json!({ "status": "success", "message": "Clock nudge command issued." }))
+async fn nudge_clock(
+    http_req: HttpRequest,
+    data: web::Data<AppState>,
+    req: web::Json<NudgeRequest>,
+) -> impl Responder {
+    let (frame, pre_delta) = frame_and_delta(&data);
+    let config = data.config.lock().unwrap().clone();
+    let microseconds = match nudge_microseconds(&req, frame.map(|f| f.frame_rate), &config) {
+        Ok(microseconds) => microseconds,
+        Err((code, message)) => {
+            return HttpResponse::build(code)
+                .json(serde_json::json!({ "status": "error", "message": message }));
+        }
+    };
+    let command = system::nudge_command(microseconds);
+    let mut entry = AuditEntry::new(request_source(&http_req), "nudge", command.clone(), pre_delta);
+    let clock = data.clock.clone();
+    entry.success = matches!(web::block(move || clock.nudge(microseconds)).await, Ok(Ok(())));
+    data.status_cache.invalidate();
+    if entry.success {
+        data.ltc_state.lock().unwrap().note_slew(microseconds);
+    }
+    let success = entry.success;
+    audit::record_after_settle(data.audit.clone(), data.ltc.clone(), entry);
+    if success {
+        HttpResponse::Ok().json(NudgeResponse {
+            status: "success".into(),
+            message: "Clock nudge command issued.".into(),
+            microseconds,
+            command,
+        })
     } else {
-        HttpResponse::InternalServerError().json(serde_json::json!({ "status": "error", "message": "Clock nudge command failed." }))
+        HttpResponse::InternalServerError().json(NudgeResponse {
+            status: "error".into(),
+            message: "Clock nudge command failed.".into(),
+            microseconds,
+            command,
+        })
+    }
+}
+
+#[utoipa::path(
+    params(("action" = ServiceAction, Path, description = "`start`, `stop` or `restart`")),
+    responses(
+        (status = 200, description = "systemctl succeeded", body = ApiMessage),
+        (status = 404, description = "Unknown action"),
+        (status = 500, description = "systemctl failed; the message is its error output", body = ApiMessage)
+    )
+)]
+#[post("/api/services/chrony/{action}")]
+async fn control_chrony(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    action: web::Path<ServiceAction>,
+) -> impl Responder {
+    let action = action.into_inner();
+    log::warn!("chrony {} requested by {}", action.as_str(), request_source(&req).as_str());
+    let result = web::block(move || system::ntp_service_control(action))
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    data.status_cache.invalidate();
+    match result {
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "message": format!("chrony {} done.", action.as_str()),
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": format!("chrony {} failed: {}", action.as_str(), e),
+        })),
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct SetDateRequest {
+    /// Date in `YYYY-MM-DD` format.
+    #[schema(example = "2025-07-30")]
+    date: String,
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+struct DateQuery {
+    /// Date in `YYYY-MM-DD` format.
     date: String,
 }
 
+/// What `POST /api/set_date` would do now, from `GET /api/set_date/preview`.
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DatePreview {
+    pub date: String,
+    /// The time the clock would be set to: 10:00 local time on `date`.
+    #[schema(value_type = String, format = DateTime)]
+    pub target_time: DateTime<Local>,
+    /// How far that moves the clock, in milliseconds; positive is forward.
+    pub step_ms: i64,
+    /// The step is larger than `confirmStepMs`, so `POST /api/set_date`
+    /// needs `confirm=true` and `token`.
+    pub confirm_required: bool,
+    /// Confirms setting this date once, for `expiresInSecs`.
+    pub token: Option<String>,
+    pub expires_in_secs: Option<u64>,
+}
+
+#[utoipa::path(
+    params(DateQuery),
+    responses(
+        (status = 200, description = "Target time and step of setting the date, with a token if it needs confirming. The clock is not touched", body = DatePreview),
+        (status = 400, description = "The date is not YYYY-MM-DD", body = ApiMessage)
+    )
+)]
+#[get("/api/set_date/preview")]
+async fn preview_set_date(data: web::Data<AppState>, query: web::Query<DateQuery>) -> impl Responder {
+    let Some(target_time) = system::set_date_target(&query.date) else {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({ "status": "error", "message": "The date must be YYYY-MM-DD." }));
+    };
+    let step_ms = target_time.signed_duration_since(Local::now()).num_milliseconds();
+    let confirm_required = confirm::required(step_ms, data.config.lock().unwrap().confirm_step_ms);
+    HttpResponse::Ok().json(DatePreview {
+        date: query.date.clone(),
+        target_time,
+        step_ms,
+        confirm_required,
+        // The target rather than the step, which shrinks as time passes.
+        token: confirm_required
+            .then(|| data.confirmations.issue("setDate", target_time.timestamp_millis(), Instant::now())),
+        expires_in_secs: confirm_required.then(|| confirm::TOKEN_TTL.as_secs()),
+    })
+}
+
+#[utoipa::path(
+    params(ConfirmQuery),
+    request_body = SetDateRequest,
+    responses(
+        (status = 200, description = "Date update command issued", body = ApiMessage),
+        (status = 400, description = "The date is not YYYY-MM-DD", body = ApiMessage),
+        (status = 500, description = "Date update command failed", body = ApiMessage),
+        (status = 409, description = "Another clock change is in progress", body = ApiMessage),
+        (status = 428, description = "The step is larger than `confirmStepMs` and was not confirmed with a preview token", body = ApiMessage),
+        (status = 429, description = "Too soon after the last clock command", body = ApiMessage)
+    )
+)]
 #[post("/api/set_date")]
-async fn set_date(req: web::Json<SetDateRequest>) -> impl Responder {
-    if system::set_date(&req.date).is_ok() {
+async fn set_date(
+    http_req: HttpRequest,
+    data: web::Data<AppState>,
+    req: web::Json<SetDateRequest>,
+    query: web::Query<ConfirmQuery>,
+) -> impl Responder {
+    let date = req.into_inner().date;
+    // `date` accepts relative dates like "tomorrow" too, which would get
+    // around the confirmation, so only a plain date is passed on.
+    let Some(target) = system::set_date_target(&date) else {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({ "status": "error", "message": "The date must be YYYY-MM-DD." }));
+    };
+    let config = data.config.lock().unwrap().clone();
+    let step_ms = target.signed_duration_since(Local::now()).num_milliseconds();
+    let (confirm, token) = (query.confirm, query.token.as_deref());
+    if !step_confirmed(&data, &config, "setDate", step_ms, target.timestamp_millis(), confirm, token) {
+        return unconfirmed("date change", step_ms, "/api/set_date/preview");
+    }
+    let (_, pre_delta) = frame_and_delta(&data);
+    let mut entry = AuditEntry::new(
+        request_source(&http_req),
+        "setDate",
+        system::set_date_command(&date),
+        pre_delta,
+    );
+    let clock = data.clock.clone();
+    entry.success = matches!(web::block(move || clock.set_date(&date)).await, Ok(Ok(())));
+    data.status_cache.invalidate();
+    if entry.success {
+        data.ltc_state.lock().unwrap().note_step();
+    }
+    let success = entry.success;
+    audit::record_after_settle(data.audit.clone(), data.ltc.clone(), entry);
+    if success {
         HttpResponse::Ok()
             .json(serde_json::json!({ "status": "success", "message": "Date update command issued." }))
     } else {
@@ -161,118 +1288,846 @@ async fn set_date(req: web::Json<SetDateRequest>) -> impl Responder {
     }
 }
 
+/// Replace the live config with `new_config`, persist it and trigger a sync if
+/// a timeturner offset is active. Shared by the POST and PATCH handlers.
+/// API tokens and hooks are never taken from a request, so the current ones
+/// are kept. A sync larger than `maxAutoStepMs` needs `override=true`, and
+/// one larger than `confirmStepMs` a confirmation, both checked against the
+/// current config so the request cannot lift the limits itself.
+async fn apply_config(data: &AppState, mut new_config: Config, query: &StepQuery) -> HttpResponse {
+    if let Err(e) = new_config.validate() {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({ "status": "error", "message": e }));
+    }
+
+    let current = data.config.lock().unwrap().clone();
+    let syncs = new_config.timeturner_offset.is_active() || new_config.timeturner_offset != current.timeturner_offset;
+    let step_ms = data
+        .ltc
+        .get()
+        .latest
+        .clone()
+        .filter(|_| syncs)
+        .and_then(|frame| plan_sync(&frame, &new_config).ok())
+        .map(|plan| plan.step_ms);
+    if let Some(step_ms) = step_ms {
+        if current.step_over_limit(step_ms) && !query.override_limit {
+            return HttpResponse::PreconditionRequired().json(serde_json::json!({
+                "status": "error",
+                "message": format!(
+                    "This config change steps the clock by {:+} ms, over maxAutoStepMs ({} ms); send it with override=true to apply it.",
+                    step_ms, current.max_auto_step_ms
+                )
+            }));
+        }
+        let token = query.token.as_deref();
+        if !step_confirmed(data, &current, "config", step_ms, step_ms, query.confirm, token) {
+            return unconfirmed("config change", step_ms, "/api/config/offset");
+        }
+    }
+
+    // The config guard is held only while swapping and persisting, so disk and
+    // memory stay consistent; it is released before ltc_state is touched.
+    let (saved, offset_changed) = {
+        let mut config = data.config.lock().unwrap();
+        let offset_changed = new_config.timeturner_offset != config.timeturner_offset;
+        new_config.api = config.api.clone();
+        new_config.hooks = config.hooks.clone();
+        *config = new_config;
+        let saved = config::save_config(&data.config_path, &config).map(|_| config.clone());
+        (saved, offset_changed)
+    };
+    data.status_cache.invalidate();
+
+    match saved {
+        Ok(config) => {
+            log::info!("🔄 Saved config via API: {:?}", config);
+
+            // If timeturner offset is active, or was just switched off,
+            // trigger a sync immediately; a changed offset may be ramped in.
+            if config.timeturner_offset.is_active() || offset_changed {
+                let (frame, pre_delta) = frame_and_delta(data);
+                match (frame, data.clock_gate.try_enter()) {
+                    (Some(frame), Some(permit)) => {
+                        if let Some(ramp) = ramp::plan(&frame, &config, offset_changed) {
+                            // Ramps run for minutes; the response doesn't wait.
+                            let clock = data.clock.clone();
+                            let (state, ltc, audit_log) =
+                                (data.ltc_state.clone(), data.ltc.clone(), data.audit.clone());
+                            std::thread::spawn(move || {
+                                ramp::run(&*clock, permit, ramp, AuditSource::Config, pre_delta, &state, &ltc, &audit_log)
+                            });
+                        } else {
+                            log::info!("Timeturner offset is active, triggering sync...");
+                            let sync_config = config.clone();
+                            let not_run = system::sync_command(&frame, &config);
+                            let (clock, state) = (data.clock.clone(), data.ltc_state.clone());
+                            let result = web::block(move || {
+                                system::step_to_ltc(&*clock, &frame, &sync_config, &state)
+                            })
+                            .await;
+                            let (command, success) = result.unwrap_or((not_run, false));
+                            let mut entry = AuditEntry::new(AuditSource::Config, "sync", command, pre_delta);
+                            entry.success = success;
+                            if entry.success {
+                                data.ltc_state.lock().unwrap().note_step();
+                            }
+                            permit.note_change(Instant::now());
+                            let success = entry.success;
+                            audit::record_after_settle(data.audit.clone(), data.ltc.clone(), entry);
+                            if success {
+                                log::info!("Sync triggered successfully after config change.");
+                            } else {
+                                log::error!("Sync failed after config change.");
+                            }
+                        }
+                    }
+                    (Some(_), None) => {
+                        log::warn!("Timeturner offset is active, but another clock change is in progress; not syncing now.");
+                    }
+                    (None, _) => {
+                        log::warn!("Timeturner offset is active, but no LTC frame available to sync.");
+                    }
+                }
+            }
+
+            HttpResponse::Ok().json(&config)
+        }
+        Err(_) => {
+            log::error!("Failed to write config.yml");
+            HttpResponse::InternalServerError().json(
+                serde_json::json!({ "status": "error", "message": "Failed to write config.yml" }),
+            )
+        }
+    }
+}
+
+/// The next scheduled preset switch.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ScheduledPreset {
+    pub name: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub at: DateTime<Local>,
+}
+
+/// The configured offset presets and which is in use.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct PresetsStatus {
+    pub presets: Vec<OffsetPreset>,
+    /// The preset whose offset is in use, if any.
+    pub active: Option<String>,
+    pub next_scheduled: Option<ScheduledPreset>,
+}
+
+#[utoipa::path(
+    responses((status = 200, description = "Offset presets, the active one and the next scheduled switch", body = PresetsStatus))
+)]
+#[get("/api/presets")]
+async fn get_presets(data: web::Data<AppState>) -> impl Responder {
+    let config = data.config.lock().unwrap();
+    let list = &config.offset_presets;
+    HttpResponse::Ok().json(PresetsStatus {
+        presets: list.clone(),
+        active: presets::active(list, &config.timeturner_offset).map(|p| p.name.clone()),
+        next_scheduled: presets::next_due(list, &Local::now())
+            .map(|(p, at)| ScheduledPreset { name: p.name.clone(), at }),
+    })
+}
+
+/// The offset preset to switch to.
+#[derive(Deserialize, ToSchema)]
+struct PresetRequest {
+    name: String,
+}
+
+#[utoipa::path(
+    params(StepQuery),
+    request_body = PresetRequest,
+    responses(
+        (status = 200, description = "Offset switched and saved; the clock is re-synced to LTC", body = Config),
+        (status = 404, description = "No preset with that name", body = ApiMessage),
+        (status = 428, description = "The re-sync steps the clock by more than `confirmStepMs` and was not confirmed with a token from `GET /api/config/offset`, or by more than `maxAutoStepMs` without `override=true`", body = ApiMessage),
+        (status = 500, description = "Failed to write config.yml", body = ApiMessage)
+    )
+)]
+#[post("/api/presets/activate")]
+async fn activate_preset(
+    data: web::Data<AppState>,
+    req: web::Json<PresetRequest>,
+    query: web::Query<StepQuery>,
+) -> impl Responder {
+    let name = &req.name;
+    let new_config = {
+        let config = data.config.lock().unwrap();
+        presets::find(&config.offset_presets, name).map(|preset| Config {
+            timeturner_offset: preset.offset.clone(),
+            ..config.clone()
+        })
+    };
+    let Some(new_config) = new_config else {
+        return HttpResponse::NotFound().json(serde_json::json!({
+            "status": "error",
+            "message": format!("No offset preset named '{}'.", name),
+        }));
+    };
+    log::info!("🎚️ Switching to offset preset '{}'", name);
+    apply_config(&data, new_config, &query).await
+}
+
+#[utoipa::path(
+    params(StepQuery),
+    request_body = Config,
+    responses(
+        (status = 200, description = "Configuration saved", body = Config),
+        (status = 400, description = "Configuration failed validation", body = ApiMessage),
+        (status = 428, description = "The resulting sync steps the clock by more than `confirmStepMs` and was not confirmed with a token from `GET /api/config/offset`, or by more than `maxAutoStepMs` without `override=true`", body = ApiMessage),
+        (status = 500, description = "Failed to write config.yml", body = ApiMessage)
+    )
+)]
 #[post("/api/config")]
 async fn update_config(
     data: web::Data<AppState>,
     req: web::Json<Config>,
+    query: web::Query<StepQuery>,
 ) -> impl Responder {
-    let mut config = data.config.lock().unwrap();
-    *config = req.into_inner();
-
-    if config::save_config("config.yml", &config).is_ok() {
-        log::info!("🔄 Saved config via API: {:?}", *config);
-
-        // If timeturner offset is active, trigger a sync immediately.
-        if config.timeturner_offset.is_active() {
-            let state = data.ltc_state.lock().unwrap();
-            if let Some(frame) = &state.latest {
-                log::info!("Timeturner offset is active, triggering sync...");
-                if system::trigger_sync(frame, &config).is_ok() {
-                    log::info!("Sync triggered successfully after config change.");
-                } else {
-                    log::error!("Sync failed after config change.");
-                }
-            } else {
-                log::warn!("Timeturner offset is active, but no LTC frame available to sync.");
-            }
-        }
+    apply_config(&data, req.into_inner(), &query).await
+}
 
-        HttpResponse::Ok().json(&*config)
-    } else {
-        log::error!("Failed to write config.yml");
-        HttpResponse::InternalServerError().json(
-            serde_json::json!({ "status": "error", "message": "Failed to write config.yml" }),
-        )
+#[utoipa::path(
+    params(StepQuery),
+    request_body(
+        content = Object,
+        description = "JSON merge patch (RFC 7386) with only the fields to change, e.g. `{\"hardwareOffsetMs\": 30}`"
+    ),
+    responses(
+        (status = 200, description = "Configuration merged and saved", body = Config),
+        (status = 400, description = "Patch is malformed, has unknown fields or failed validation", body = ApiMessage),
+        (status = 428, description = "The resulting sync steps the clock by more than `confirmStepMs` and was not confirmed with a token from `GET /api/config/offset`, or by more than `maxAutoStepMs` without `override=true`", body = ApiMessage),
+        (status = 500, description = "Failed to write config.yml", body = ApiMessage)
+    )
+)]
+#[patch("/api/config")]
+async fn patch_config(
+    data: web::Data<AppState>,
+    req: web::Json<serde_json::Value>,
+    query: web::Query<StepQuery>,
+) -> impl Responder {
+    if !req.is_object() {
+        return HttpResponse::BadRequest().json(
+            serde_json::json!({ "status": "error", "message": "Patch must be a JSON object." }),
+        );
+    }
+    let merged = data.config.lock().unwrap().patched(&req);
+    match merged {
+        Ok(new_config) => apply_config(&data, new_config, &query).await,
+        Err(e) => HttpResponse::BadRequest()
+            .json(serde_json::json!({ "status": "error", "message": e })),
     }
 }
 
-pub async fn start_api_server(
-    state: Arc<Mutex<LtcState>>,
-    config: Arc<Mutex<Config>>,
-    log_buffer: Arc<Mutex<VecDeque<String>>>,
-) -> std::io::Result<()> {
-    let app_state = web::Data::new(AppState {
-        ltc_state: state,
-        config: config,
-        log_buffer: log_buffer,
-    });
+#[utoipa::path(
+    responses((status = 200, description = "Clock mutations (sync, nudge, set date), oldest first", body = [AuditEntry]))
+)]
+#[get("/api/audit")]
+async fn get_audit(data: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(data.audit.entries())
+}
 
-    log::info!("🚀 Starting API server at http://0.0.0.0:8080");
+#[utoipa::path(
+    responses((
+        status = 200,
+        description = "Prometheus text exposition: LTC and clock gauges, and clock mutations counted by source, action and result",
+        content_type = "text/plain",
+        body = String
+    ))
+)]
+#[get("/api/metrics")]
+async fn get_metrics(data: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok()
+        .content_type(metrics::CONTENT_TYPE)
+        .body(metrics::render(&status_snapshot(&data), &data.audit))
+}
 
-    HttpServer::new(move || {
-        App::new()
-            .app_data(app_state.clone())
-            .service(get_status)
-            .service(manual_sync)
-            .service(get_config)
-            .service(update_config)
-            .service(get_logs)
-            .service(nudge_clock)
-            .service(set_date)
-            // Serve frontend static files
-            .service(fs::Files::new("/", "static/").index_file("index.html"))
-    })
-    .bind("0.0.0.0:8080")?
-    .run()
-    .await
+#[derive(Deserialize, utoipa::IntoParams)]
+struct HistoryQuery {
+    /// How far back to go, in seconds. Defaults to 300.
+    seconds: Option<u64>,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::config::TimeturnerOffset;
-    use crate::sync_logic::LtcFrame;
-    use actix_web::{test, App};
-    use chrono::Utc;
-    use std::collections::VecDeque;
-    use std::fs;
+#[utoipa::path(
+    params(HistoryQuery),
+    responses((status = 200, description = "Smoothed clock delta history at the finest resolution covering the range", body = HistoryResponse))
+)]
+#[get("/api/history")]
+async fn get_history(data: web::Data<AppState>, query: web::Query<HistoryQuery>) -> impl Responder {
+    let seconds = query.seconds.unwrap_or(300);
+    let history = data.history.lock().unwrap().query(seconds, chrono::Utc::now());
+    HttpResponse::Ok().json(history)
+}
 
-    // Helper to create a default LtcState for tests
-    fn get_test_ltc_state() -> LtcState {
-        LtcState {
-            latest: Some(LtcFrame {
-                status: "LOCK".to_string(),
-                hours: 1,
-                minutes: 2,
-                seconds: 3,
-                frames: 4,
-                is_drop_frame: false,
-                frame_rate: Ratio::new(25, 1),
-                timestamp: Utc::now(),
-            }),
-            lock_count: 10,
-            free_count: 1,
-            offset_history: VecDeque::from(vec![1, 2, 3]),
-            ewma_clock_delta: Some(5.0),
-            last_match_status: "IN SYNC".to_string(),
-            last_match_check: Utc::now().timestamp(),
-        }
+#[derive(Deserialize, utoipa::IntoParams)]
+struct SeriesQuery {
+    /// How far back to go, in minutes, from 1 to 60. Defaults to 10.
+    minutes: Option<u64>,
+}
+
+fn series_response(data: &AppState, kind: SeriesKind, query: &SeriesQuery) -> HttpResponse {
+    let minutes = query.minutes.unwrap_or(10);
+    if !(1..=SERIES_MAX_MINUTES).contains(&minutes) {
+        return HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "message": format!("minutes must be between 1 and {}", SERIES_MAX_MINUTES),
+        }));
     }
+    HttpResponse::Ok().json(data.series.lock().unwrap().query(kind, minutes, chrono::Utc::now()))
+}
 
-    // Helper to create a default AppState for tests
+#[utoipa::path(
+    params(SeriesQuery),
+    responses(
+        (status = 200, description = "Smoothed clock delta per second; seconds with no LOCK frame are null", body = SeriesResponse),
+        (status = 400, description = "minutes out of range", body = ApiMessage)
+    )
+)]
+#[get("/api/series/delta")]
+async fn get_delta_series(data: web::Data<AppState>, query: web::Query<SeriesQuery>) -> impl Responder {
+    series_response(&data, SeriesKind::Delta, &query)
+}
+
+#[utoipa::path(
+    params(SeriesQuery),
+    responses(
+        (status = 200, description = "Frame jitter per second; seconds with no LOCK frame are null", body = SeriesResponse),
+        (status = 400, description = "minutes out of range", body = ApiMessage)
+    )
+)]
+#[get("/api/series/jitter")]
+async fn get_jitter_series(data: web::Data<AppState>, query: web::Query<SeriesQuery>) -> impl Responder {
+    series_response(&data, SeriesKind::Jitter, &query)
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+struct ReportQuery {
+    /// `json` (the default) or `html`.
+    format: Option<String>,
+}
+
+fn session_report(data: &AppState) -> SessionReport {
+    let now = chrono::Utc::now();
+    data.session.lock().unwrap().report(&data.audit.entries(), data.serial.reconnects(), now)
+}
+
+#[utoipa::path(
+    params(ReportQuery),
+    responses(
+        (
+            status = 200,
+            description = "Report on the session so far: LOCK/FREE time, clock steps, delta, alerts and serial reconnects. A printable page with `format=html`.",
+            content((SessionReport = "application/json"), (String = "text/html"))
+        ),
+        (status = 400, description = "Unknown format", body = ApiMessage)
+    )
+)]
+#[get("/api/report")]
+async fn get_report(data: web::Data<AppState>, query: web::Query<ReportQuery>) -> impl Responder {
+    let report = session_report(&data);
+    match query.format.as_deref() {
+        None | Some("json") => HttpResponse::Ok().json(report),
+        Some("html") => HttpResponse::Ok().content_type("text/html; charset=utf-8").body(report.to_html()),
+        Some(other) => HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "message": format!("unknown format '{}'; use json or html", other),
+        })),
+    }
+}
+
+#[utoipa::path(
+    responses((status = 200, description = "Final report on the session just ended; a new one starts now", body = SessionReport))
+)]
+#[post("/api/report/reset")]
+async fn reset_report(req: HttpRequest, data: web::Data<AppState>) -> impl Responder {
+    let report = session_report(&data);
+    *data.session.lock().unwrap() = Session::new(report.ended, data.serial.reconnects());
+    log::info!("Session report reset by {}", request_source(&req).as_str());
+    HttpResponse::Ok().json(report)
+}
+
+#[utoipa::path(
+    responses((status = 200, description = "Latest NTP cross-check result per configured server", body = [NtpSample]))
+)]
+#[get("/api/ntp_check")]
+async fn get_ntp_check(data: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(data.ntp_check.lock().unwrap().samples())
+}
+
+#[utoipa::path(
+    responses((status = 200, description = "RTP-MIDI listen address, whether MTC is running and the open sessions", body = RtpMidiStatus))
+)]
+#[get("/api/rtp_midi")]
+async fn get_rtp_midi(data: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(data.rtp_midi.lock().unwrap().status())
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+struct PtpMessagesQuery {
+    /// Only the newest this many. Defaults to all kept.
+    limit: Option<usize>,
+}
+
+#[utoipa::path(
+    params(PtpMessagesQuery),
+    responses((status = 200, description = "PTP messages seen on the network, oldest first; empty unless ptpCapture.enabled is set", body = [PtpMessage]))
+)]
+#[get("/api/ptp/messages")]
+async fn get_ptp_messages(data: web::Data<AppState>, query: web::Query<PtpMessagesQuery>) -> impl Responder {
+    HttpResponse::Ok().json(data.ptp_capture.lock().unwrap().messages(query.limit))
+}
+
+#[utoipa::path(
+    responses((status = 200, description = "Masters heard announcing on ptpCapture.domain, the one the BMCA (or ptpCapture.grandmaster) selects, and why the others lost", body = PtpMasterStatus))
+)]
+#[get("/api/ptp/masters")]
+async fn get_ptp_masters(data: web::Data<AppState>) -> impl Responder {
+    let settings = data.config.lock().unwrap().ptp_capture.clone();
+    let status = data.ptp_capture.lock().unwrap().masters(
+        settings.domain,
+        settings.grandmaster.as_deref(),
+        chrono::Utc::now(),
+    );
+    HttpResponse::Ok().json(status)
+}
+
+#[utoipa::path(
+    responses((status = 200, description = "chrony's tracking state and sources; `error` says why if chronyc failed", body = ChronyReport))
+)]
+#[get("/api/chrony")]
+async fn get_chrony() -> impl Responder {
+    let report = web::block(chrony::query).await.unwrap_or_default();
+    HttpResponse::Ok().json(report)
+}
+
+#[utoipa::path(
+    responses((status = 200, description = "CPU temperature, throttling, load, uptime, memory, disk and network links", body = SystemInfo))
+)]
+#[get("/api/system")]
+async fn get_system() -> impl Responder {
+    HttpResponse::Ok().json(web::block(host::collect).await.unwrap_or_default())
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Every worker thread is running", body = HealthReport),
+        (status = 503, description = "A worker thread has stalled or died", body = HealthReport)
+    )
+)]
+#[get("/api/health")]
+async fn get_health(data: web::Data<AppState>) -> impl Responder {
+    let report = data.health.report(Instant::now());
+    if report.healthy {
+        HttpResponse::Ok().json(report)
+    } else {
+        HttpResponse::ServiceUnavailable().json(report)
+    }
+}
+
+#[utoipa::path(
+    responses((status = 200, description = "Each supervised component: running or waiting to restart, restart count and last error", body = [ComponentHealth]))
+)]
+#[get("/api/components")]
+async fn get_components(data: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(data.supervisor.components())
+}
+
+#[utoipa::path(
+    responses((status = 200, description = "Health and latest estimate of each registered time source", body = [SourceReport]))
+)]
+#[get("/api/sources")]
+async fn get_sources(data: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(data.sources.reports(chrono::Utc::now()))
+}
+
+#[utoipa::path(
+    responses((status = 200, description = "Health of each configured output sink, in config order", body = [SinkHealth]))
+)]
+#[get("/api/sinks")]
+async fn get_sinks(data: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(data.sinks.sinks())
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ExportRequest {
+    /// Start a new file after this many minutes; 15 if omitted.
+    rotate_minutes: Option<u64>,
+    /// Delete the oldest file once there are more than this many; 96 if
+    /// omitted.
+    keep_files: Option<usize>,
+}
+
+#[utoipa::path(
+    responses((status = 200, description = "The running or last raw measurement export", body = ExportStatus))
+)]
+#[get("/api/export")]
+async fn get_export(data: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(data.export.lock().unwrap().status())
+}
+
+#[utoipa::path(
+    request_body(content = ExportRequest, description = "Optional; the body may be left out"),
+    responses(
+        (status = 200, description = "Every measured LOCK frame is now written to CSV", body = ExportStatus),
+        (status = 400, description = "rotateMinutes or keepFiles is zero", body = ApiMessage),
+        (status = 409, description = "An export is already running", body = ApiMessage),
+        (status = 500, description = "The export file could not be created", body = ApiMessage)
+    )
+)]
+#[post("/api/export/start")]
+async fn start_export(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    body: Option<web::Json<ExportRequest>>,
+) -> impl Responder {
+    let body = body.map(|b| b.into_inner());
+    let rotate_minutes = body.as_ref().and_then(|b| b.rotate_minutes).unwrap_or(export::DEFAULT_ROTATE_MINUTES);
+    if rotate_minutes == 0 {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({ "status": "error", "message": "rotateMinutes must be greater than zero." }));
+    }
+    let keep_files = body.as_ref().and_then(|b| b.keep_files).unwrap_or(export::DEFAULT_KEEP_FILES);
+    if keep_files == 0 {
+        return HttpResponse::BadRequest()
+            .json(serde_json::json!({ "status": "error", "message": "keepFiles must be greater than zero." }));
+    }
+    let mut exporter = data.export.lock().unwrap();
+    if exporter.status().active {
+        return HttpResponse::Conflict()
+            .json(serde_json::json!({ "status": "error", "message": "An export is already running." }));
+    }
+    match exporter.start(rotate_minutes, keep_files, chrono::Utc::now()) {
+        Ok(status) => {
+            log::info!("Measurement export started by {}", request_source(&req).as_str());
+            HttpResponse::Ok().json(status)
+        }
+        Err(message) => {
+            HttpResponse::InternalServerError().json(serde_json::json!({ "status": "error", "message": message }))
+        }
+    }
+}
+
+#[utoipa::path(
+    responses((status = 200, description = "The export is closed; lists the files it wrote", body = ExportStatus))
+)]
+#[post("/api/export/stop")]
+async fn stop_export(data: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok().json(data.export.lock().unwrap().stop())
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Workers running, config valid and LTC frames arriving", body = ReadyReport),
+        (status = 503, description = "At least one check failed", body = ReadyReport)
+    )
+)]
+#[get("/api/ready")]
+async fn get_ready(data: web::Data<AppState>) -> impl Responder {
+    let config = data.config.lock().unwrap().clone();
+    let report = data
+        .health
+        .readiness(Instant::now(), &data.ltc.get(), &config, chrono::Utc::now());
+    if report.ready {
+        HttpResponse::Ok().json(report)
+    } else {
+        HttpResponse::ServiceUnavailable().json(report)
+    }
+}
+
+#[utoipa::path(
+    responses((status = 200, description = "OpenAPI 3 document describing this API", body = Object))
+)]
+#[get("/api/openapi.json")]
+async fn get_openapi() -> impl Responder {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+/// Build the HTTP server (API, auth middleware and static web UI) for
+/// `app_state` on an already-bound listener. The returned server must be
+/// awaited to run.
+pub fn serve(app_state: web::Data<AppState>, listeners: Vec<TcpListener>) -> std::io::Result<Server> {
+    let mut server = HttpServer::new(move || {
+        App::new()
+            .app_data(app_state.clone())
+            .wrap(from_fn(clock_gate::limit_clock_commands))
+            .wrap(from_fn(auth::require_role))
+            .service(get_status)
+            .service(stream_status)
+            .service(manual_sync)
+            .service(preview_sync)
+            .service(get_config)
+            .service(get_config_offset)
+            .service(update_config)
+            .service(patch_config)
+            .service(get_logs)
+            .service(get_log_level)
+            .service(set_log_level)
+            .service(nudge_clock)
+            .service(set_date)
+            .service(preview_set_date)
+            .service(get_audit)
+            .service(get_metrics)
+            .service(get_history)
+            .service(get_delta_series)
+            .service(get_jitter_series)
+            .service(get_report)
+            .service(reset_report)
+            .service(get_ntp_check)
+            .service(get_rtp_midi)
+            .service(get_ptp_messages)
+            .service(get_ptp_masters)
+            .service(get_chrony)
+            .service(control_chrony)
+            .service(get_system)
+            .service(get_source)
+            .service(set_source)
+            .service(get_serial_ports)
+            .service(set_serial_port)
+            .service(get_presets)
+            .service(activate_preset)
+            .service(get_health)
+            .service(get_components)
+            .service(get_sources)
+            .service(get_sinks)
+            .service(get_export)
+            .service(start_export)
+            .service(stop_export)
+            .service(get_ready)
+            .service(get_openapi)
+            // Everything else is the web UI
+            .default_service(web::get().to(assets::serve_asset))
+    });
+    for listener in listeners {
+        server = server.listen(listener)?;
+    }
+    Ok(server.run())
+}
+
+/// Listen on every IPv4 and every IPv6 address. The IPv6 socket is v6-only,
+/// so the two share the port whatever `net.ipv6.bindv6only` is set to; a
+/// host without IPv6 is served on IPv4 alone.
+fn bind_dual_stack(port: u16) -> std::io::Result<Vec<TcpListener>> {
+    let mut listeners = vec![TcpListener::bind((Ipv4Addr::UNSPECIFIED, port))?];
+    match bind_v6_only(port) {
+        Ok(listener) => listeners.push(listener),
+        Err(e) => log::warn!("API not listening on IPv6: {}", e),
+    }
+    Ok(listeners)
+}
+
+fn bind_v6_only(port: u16) -> std::io::Result<TcpListener> {
+    let socket = Socket::new(Domain::IPV6, Type::STREAM, Some(Protocol::TCP))?;
+    socket.set_only_v6(true)?;
+    socket.set_reuse_address(true)?;
+    socket.bind(&SocketAddr::from((Ipv6Addr::UNSPECIFIED, port)).into())?;
+    socket.listen(1024)?;
+    Ok(socket.into())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn start_api_server(
+    state: Arc<Mutex<LtcState>>,
+    config: Arc<Mutex<Config>>,
+    log_buffer: Arc<Mutex<VecDeque<String>>>,
+    serial: Arc<ReaderSelection>,
+    audit: Arc<AuditLog>,
+    history: Arc<Mutex<DeltaHistory>>,
+    series: Arc<Mutex<Series>>,
+    session: Arc<Mutex<Session>>,
+    ntp_check: Arc<Mutex<NtpCrossCheck>>,
+    rtp_midi: Arc<Mutex<RtpMidiSessions>>,
+    ptp_capture: Arc<Mutex<PtpCapture>>,
+    web_root: Option<PathBuf>,
+    health: Arc<Health>,
+    clock_gate: Arc<ClockGate>,
+    supervisor: Arc<Supervisor>,
+    sources: Arc<SourceRegistry>,
+    sinks: Arc<SinkBoard>,
+    export: Arc<Mutex<Exporter>>,
+) -> std::io::Result<()> {
+    let ltc = state.lock().unwrap().view();
+    let app_state = web::Data::new(AppState {
+        ltc_state: state,
+        ltc,
+        config,
+        log_buffer,
+        config_path: "config.yml".to_string(),
+        serial,
+        audit,
+        clock: Arc::new(SystemClock),
+        clock_gate,
+        history,
+        series,
+        session,
+        auth: AuthCache::default(),
+        status_cache: StatusCache::default(),
+        ntp_check,
+        rtp_midi,
+        ptp_capture,
+        web_root,
+        health,
+        supervisor,
+        confirmations: Default::default(),
+        sources,
+        sinks,
+        export,
+    });
+
+    let listeners = bind_dual_stack(API_PORT)?;
+    for listener in &listeners {
+        log::info!("🚀 Starting API server at http://{}", listener.local_addr()?);
+    }
+
+    serve(app_state, listeners)?.await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ApiAccessConfig, ApiToken, HooksConfig, Role};
+    use crate::hooks::HookEvent;
+    use crate::ntp_client::NtpCheckConfig;
+    use crate::rejam::RejamConfig;
+    use crate::snmp::SnmpConfig;
+    use crate::syslog::SyslogConfig;
+    use crate::phc::PhcConfig;
+    use crate::rtp_midi::RtpMidiConfig;
+    use crate::vitc::VitcConfig;
+    use crate::sync_logic::LtcFrame;
+    use actix_web::{test, App};
+    use chrono::Utc;
+    use std::collections::VecDeque;
+    use std::fs;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    // Helper to create a default LtcState for tests
+    fn get_test_ltc_state() -> LtcState {
+        let state = LtcState {
+            latest: Some(LtcFrame {
+                status: "LOCK".to_string(),
+                hours: 1,
+                minutes: 2,
+                seconds: 3,
+                frames: 4,
+                is_drop_frame: false,
+                frame_rate: Ratio::new(25, 1),
+                timestamp: Utc::now(),
+                received: None,
+            }),
+            lock_count: 10,
+            free_count: 1,
+            offset_history: VecDeque::from(vec![1, 2, 3]),
+            jitter_window: 20,
+            ewma_clock_delta: Some(5.0),
+            last_match_status: "IN SYNC".to_string(),
+            last_match_check: Utc::now().timestamp(),
+            session_anchor: None,
+            ..LtcState::new()
+        };
+        state.publish();
+        state
+    }
+
+    // Each test persists config to its own file so parallel tests don't race.
+    fn get_test_config_path() -> String {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        std::env::temp_dir()
+            .join(format!(
+                "timeturner-api-test-{}-{}.yml",
+                std::process::id(),
+                NEXT_ID.fetch_add(1, Ordering::SeqCst)
+            ))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    // Helper to create a default AppState for tests
     fn get_test_app_state() -> web::Data<AppState> {
         let ltc_state = Arc::new(Mutex::new(get_test_ltc_state()));
         let config = Arc::new(Mutex::new(Config {
             hardware_offset_ms: 10,
+            reader_latency: ReaderLatency::default(),
+            serial_devices: Vec::new(),
+            ltc_line_pattern: None,
             timeturner_offset: TimeturnerOffset::default(),
+            offset_presets: Vec::new(),
+            offset_ramp_secs: 0,
+            ltc_time_zone: "local".to_string(),
+            secondary_frame_rate: None,
+            expected_frame_rate: None,
             default_nudge_ms: 2,
+            nudge_min_us: -500_000,
+            nudge_max_us: 500_000,
             auto_sync_enabled: false,
+            sync_source: SyncSource::Auto,
+            step_threshold_ms: 40,
+            step_consistent_frames: 25,
+            max_auto_step_ms: 0,
+            sync_on_second: false,
+            in_sync_ahead_ms: 8,
+            in_sync_behind_ms: -8,
+            rtc_writeback: false,
+            stop_competing_time_daemons: false,
+            hooks: HooksConfig::default(),
+            history: HistoryConfig::default(),
+            rejam: RejamConfig::default(),
+            on_air: false,
+            ntp_check: NtpCheckConfig::default(),
+            syslog: SyslogConfig::default(),
+            snmp: SnmpConfig::default(),
+            vitc: VitcConfig::default(),
+            rtp_midi: RtpMidiConfig::default(),
+            ptp_capture: Default::default(),
+            phc: PhcConfig::default(),
+            sinks: Vec::new(),
+            jitter_window: 20,
+            clock_command_interval_ms: 1000,
+            confirm_step_ms: 1000,
+            ready_frame_timeout_secs: 5,
+            signal_timeout_ms: 1000,
+            log_level: "info".to_string(),
+            api: ApiAccessConfig::default(),
+            config_version: crate::config::CONFIG_VERSION,
         }));
         let log_buffer = Arc::new(Mutex::new(VecDeque::new()));
+        let ltc = ltc_state.lock().unwrap().view();
+        let ntp_check = Arc::new(Mutex::new(NtpCrossCheck::default()));
+        let ptp_capture: Arc<Mutex<PtpCapture>> = Default::default();
+        let sources = SourceRegistry::standard(ltc.clone(), config.clone(), ntp_check.clone(), ptp_capture.clone());
         web::Data::new(AppState {
             ltc_state,
+            ltc,
             config,
             log_buffer,
+            config_path: get_test_config_path(),
+            serial: Arc::new(ReaderSelection::fixed("/dev/ttyACM0")),
+            audit: Arc::new(AuditLog::in_memory()),
+            clock: Arc::new(SystemClock),
+            clock_gate: Default::default(),
+            history: Arc::new(Mutex::new(DeltaHistory::new(&HistoryConfig::default()))),
+            series: Default::default(),
+            session: Arc::new(Mutex::new(Session::new(Utc::now(), 0))),
+            auth: AuthCache::default(),
+            status_cache: StatusCache::default(),
+            ntp_check,
+            rtp_midi: Default::default(),
+            ptp_capture,
+            web_root: None,
+            health: Health::new(),
+            supervisor: Supervisor::new(),
+            confirmations: Default::default(),
+            sources: Arc::new(sources),
+            sinks: Default::default(),
+            export: Default::default(),
         })
     }
 
@@ -293,20 +2148,78 @@ mod tests {
         assert_eq!(resp.ltc_timecode, "01:02:03:04");
         assert_eq!(resp.frame_rate, "25.00fps");
         assert_eq!(resp.hardware_offset_ms, 10);
+        assert_eq!(resp.serial_port, "/dev/ttyACM0");
+    }
+
+    #[actix_web::test]
+    async fn test_status_snapshot_is_invalidated_by_changes() {
+        let app_state = get_test_app_state();
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .service(get_status),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/status").to_request();
+        let resp: ApiStatus = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.hardware_offset_ms, 10);
+
+        app_state.config.lock().unwrap().hardware_offset_ms = 99;
+        app_state.status_cache.invalidate();
+        assert!(app_state.status_cache.get().is_none());
+        let req = test::TestRequest::get().uri("/api/status").to_request();
+        let resp: ApiStatus = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.hardware_offset_ms, 99);
+    }
+
+    #[actix_web::test]
+    async fn test_stream_status_pushes_on_change() {
+        use actix_web::body::MessageBody;
+
+        let app_state = get_test_app_state();
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .service(stream_status),
+        )
+        .await;
+        let req = test::TestRequest::get().uri("/api/status/stream").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get("content-type").unwrap(), "text/event-stream");
+
+        let mut body = Box::pin(resp.into_body());
+        let mut next_event = async || {
+            let chunk = std::future::poll_fn(|cx| body.as_mut().poll_next(cx)).await.unwrap().unwrap();
+            let text = String::from_utf8(chunk.to_vec()).unwrap();
+            let json = text.strip_prefix("data: ").unwrap().strip_suffix("\n\n").unwrap().to_string();
+            serde_json::from_str::<ApiStatus>(&json).unwrap()
+        };
+        // The current status is sent straight away.
+        assert_eq!(next_event().await.ltc_timecode, "01:02:03:04");
+
+        // A new frame is pushed without waiting for the heartbeat.
+        let started = Instant::now();
+        {
+            let mut state = app_state.ltc_state.lock().unwrap();
+            let mut frame = state.latest.clone().unwrap();
+            frame.frames = 5;
+            state.update(frame);
+        }
+        app_state.status_cache.invalidate();
+        assert_eq!(next_event().await.ltc_timecode, "01:02:03:05");
+        assert!(started.elapsed() < STREAM_HEARTBEAT);
     }
 
     #[actix_web::test]
     async fn test_get_status_drop_frame() {
         let app_state = get_test_app_state();
         // Set state to drop frame
-        app_state
-            .ltc_state
-            .lock()
-            .unwrap()
-            .latest
-            .as_mut()
-            .unwrap()
-            .is_drop_frame = true;
+        {
+            let mut state = app_state.ltc_state.lock().unwrap();
+            state.latest.as_mut().unwrap().is_drop_frame = true;
+            state.publish();
+        }
 
         let app = test::init_service(
             App::new()
@@ -340,13 +2253,20 @@ mod tests {
     }
 
     #[actix_web::test]
+    #[allow(clippy::bool_assert_comparison)]
     async fn test_update_config() {
         let app_state = get_test_app_state();
-        let config_path = "config.yml";
+        let config_path = app_state.config_path.as_str();
 
-        // This test has the side effect of writing to `config.yml`.
+        // This test has the side effect of writing the config file.
         // We ensure it's cleaned up after.
         let _ = fs::remove_file(config_path);
+        // Without LTC the offset is saved without stepping the real clock.
+        {
+            let mut state = app_state.ltc_state.lock().unwrap();
+            state.latest = None;
+            state.publish();
+        }
 
         let app = test::init_service(
             App::new()
@@ -391,11 +2311,450 @@ mod tests {
         let _ = fs::remove_file(config_path);
     }
 
+    #[actix_web::test]
+    async fn test_patch_config() {
+        let app_state = get_test_app_state();
+        let config_path = app_state.config_path.as_str();
+        let _ = fs::remove_file(config_path);
+        app_state.config.lock().unwrap().auto_sync_enabled = true;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .service(patch_config),
+        )
+        .await;
+
+        let req = test::TestRequest::patch()
+            .uri("/api/config")
+            .set_json(serde_json::json!({
+                "hardwareOffsetMs": 42,
+                "ntpCheck": { "alertThresholdMs": 50 }
+            }))
+            .to_request();
+        let resp: Config = test::call_and_read_body_json(&app, req).await;
+
+        // Patched fields change, everything else is preserved.
+        assert_eq!(resp.hardware_offset_ms, 42);
+        assert_eq!(resp.ntp_check.alert_threshold_ms, 50);
+        assert_eq!(resp.ntp_check.interval_secs, 64);
+        assert!(resp.auto_sync_enabled);
+        assert_eq!(resp.default_nudge_ms, 2);
+        assert_eq!(app_state.config.lock().unwrap().hardware_offset_ms, 42);
+        let contents = fs::read_to_string(config_path).unwrap();
+        assert!(contents.contains("hardwareOffsetMs: 42"));
+        assert!(contents.contains("autoSyncEnabled: true"));
+
+        let _ = fs::remove_file(config_path);
+    }
+
+    #[actix_web::test]
+    async fn test_pin_sync_source() {
+        let app_state = get_test_app_state();
+        let config_path = app_state.config_path.as_str();
+        let _ = fs::remove_file(config_path);
+        app_state.config.lock().unwrap().auto_sync_enabled = true;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .service(get_source)
+                .service(set_source),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/source").to_request();
+        let resp: SourceStatus = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.selected, SyncSource::Auto);
+        assert_eq!(resp.active, Some(SyncSource::Ltc));
+
+        let req = test::TestRequest::put()
+            .uri("/api/source")
+            .set_json(serde_json::json!({ "source": "manual" }))
+            .to_request();
+        let resp: SourceStatus = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.selected, SyncSource::Manual);
+        assert_eq!(resp.active, Some(SyncSource::Manual));
+        assert!(fs::read_to_string(config_path).unwrap().contains("syncSource: manual"));
+
+        let req = test::TestRequest::put()
+            .uri("/api/source")
+            .set_json(serde_json::json!({ "source": "ptp" }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+        assert_eq!(app_state.config.lock().unwrap().sync_source, SyncSource::Manual);
+
+        let _ = fs::remove_file(config_path);
+    }
+
+    #[actix_web::test]
+    async fn test_switch_serial_port() {
+        let app_state = get_test_app_state();
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .service(get_serial_ports)
+                .service(set_serial_port),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/serial/ports").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+
+        let switch = |path: &str| {
+            test::TestRequest::put()
+                .uri("/api/serial/port")
+                .set_json(serde_json::json!({ "path": path }))
+                .to_request()
+        };
+        assert_eq!(test::call_service(&app, switch("/dev/ttyACM1")).await.status(), 409, "fixed input");
+
+        let reader = ReaderPort { path: "/dev/ttyACM0".into(), baud_rate: 115200, latency: ReaderLatency::default() };
+        let mut state = Arc::try_unwrap(get_test_app_state().into_inner()).ok().unwrap();
+        state.serial = Arc::new(ReaderSelection::serial(reader));
+        let serial_state = web::Data::new(state);
+        let app = test::init_service(App::new().app_data(serial_state.clone()).service(set_serial_port)).await;
+        assert_eq!(test::call_service(&app, switch("/dev/does-not-exist")).await.status(), 400);
+        assert_eq!(serial_state.serial.current().path, "/dev/ttyACM0");
+    }
+
+    #[actix_web::test]
+    async fn test_patch_config_rejects_invalid() {
+        let app_state = get_test_app_state();
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .service(patch_config),
+        )
+        .await;
+
+        for patch in [
+            serde_json::json!({ "hardwareOfsetMs": 42 }),
+            serde_json::json!({ "timeturnerOffset": { "hour": 1 } }),
+            serde_json::json!({ "hardwareOffsetMs": "lots" }),
+            serde_json::json!({ "defaultNudgeMs": 0 }),
+            serde_json::json!({ "logLevel": "loud" }),
+            serde_json::json!([1, 2, 3]),
+        ] {
+            let req = test::TestRequest::patch()
+                .uri("/api/config")
+                .set_json(&patch)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 400, "patch {} should be rejected", patch);
+        }
+        assert_eq!(app_state.config.lock().unwrap().hardware_offset_ms, 10);
+    }
+
+    #[actix_web::test]
+    async fn test_update_config_keeps_api_tokens() {
+        let app_state = get_test_app_state();
+        let config_path = app_state.config_path.as_str();
+        let _ = fs::remove_file(config_path);
+        app_state.config.lock().unwrap().api.tokens = vec![ApiToken {
+            token: "secret".to_string(),
+            role: Role::Admin,
+        }];
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .service(get_config)
+                .service(patch_config),
+        )
+        .await;
+
+        // Tokens are not exposed...
+        let req = test::TestRequest::get().uri("/api/config").to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.get("api").is_none());
+
+        // ...cannot be changed through the API...
+        let req = test::TestRequest::patch()
+            .uri("/api/config")
+            .set_json(serde_json::json!({ "api": { "tokens": [] } }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+        // ...and survive an ordinary config change.
+        let req = test::TestRequest::patch()
+            .uri("/api/config")
+            .set_json(serde_json::json!({ "hardwareOffsetMs": 12 }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+        assert_eq!(app_state.config.lock().unwrap().api.role_for("secret"), Some(Role::Admin));
+        let saved = Config::load(&config_path.into()).unwrap();
+        assert_eq!(saved.api.role_for("secret"), Some(Role::Admin));
+
+        let _ = fs::remove_file(config_path);
+    }
+
+    #[actix_web::test]
+    async fn test_clock_changes_need_confirming() {
+        let app_state = get_test_app_state();
+        let config_path = app_state.config_path.as_str();
+        let _ = fs::remove_file(config_path);
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .service(patch_config)
+                .service(activate_preset)
+                .service(set_date)
+                .service(get_config_offset)
+                .service(preview_set_date),
+        )
+        .await;
+
+        // The test frame is 01:02:03, so an offset of a day is far off.
+        let req = test::TestRequest::patch()
+            .uri("/api/config")
+            .set_json(serde_json::json!({ "timeturnerOffset": { "hours": 24 } }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 428);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(body["message"].as_str().unwrap().starts_with("This config change steps the clock by"), "{}", body);
+
+        // Lowering confirmStepMs in the same request does not get around it.
+        let req = test::TestRequest::patch()
+            .uri("/api/config")
+            .set_json(serde_json::json!({ "timeturnerOffset": { "hours": 24 }, "confirmStepMs": 0 }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 428);
+
+        // A token for one offset does not confirm another.
+        let req = test::TestRequest::get().uri("/api/config/offset?hours=-24").to_request();
+        let preview: OffsetPreview = test::call_and_read_body_json(&app, req).await;
+        assert!(preview.confirm_required);
+        assert!(preview.step_ms.unwrap() < -23 * 3_600_000);
+        let req = test::TestRequest::patch()
+            .uri(&format!("/api/config?confirm=true&token={}", preview.token.unwrap()))
+            .set_json(serde_json::json!({ "timeturnerOffset": { "hours": 24 } }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 428);
+
+        app_state.config.lock().unwrap().offset_presets = vec![OffsetPreset {
+            name: "tomorrow".into(),
+            offset: TimeturnerOffset { hours: 24, ..TimeturnerOffset::default() },
+            at: None,
+        }];
+        let req = test::TestRequest::post()
+            .uri("/api/presets/activate")
+            .set_json(serde_json::json!({ "name": "tomorrow" }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 428);
+        assert!(!app_state.config.lock().unwrap().timeturner_offset.is_active());
+
+        let req = test::TestRequest::post()
+            .uri("/api/set_date")
+            .set_json(serde_json::json!({ "date": "2000-01-01" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 428);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(body["message"].as_str().unwrap().contains("/api/set_date/preview"), "{}", body);
+
+        let req = test::TestRequest::get().uri("/api/set_date/preview?date=2000-01-01").to_request();
+        let preview: DatePreview = test::call_and_read_body_json(&app, req).await;
+        assert!(preview.confirm_required && preview.token.is_some());
+        assert_eq!(preview.target_time.format("%Y-%m-%d %H:%M").to_string(), "2000-01-01 10:00");
+        let req = test::TestRequest::get().uri("/api/set_date/preview?date=someday").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+        assert!(app_state.audit.entries().is_empty(), "refused before anything ran");
+        assert!(fs::metadata(config_path).is_err(), "nothing saved");
+    }
+
+    #[actix_web::test]
+    async fn test_update_config_keeps_hooks() {
+        let app_state = get_test_app_state();
+        let config_path = app_state.config_path.as_str();
+        let _ = fs::remove_file(config_path);
+        app_state.config.lock().unwrap().hooks.lock_lost = Some("logger lost".to_string());
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .service(get_config)
+                .service(update_config)
+                .service(patch_config),
+        )
+        .await;
+
+        // Hooks run shell commands, so they are not exposed...
+        let req = test::TestRequest::get().uri("/api/config").to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.get("hooks").is_none());
+
+        // ...a patch cannot set them...
+        let req = test::TestRequest::patch()
+            .uri("/api/config")
+            .set_json(serde_json::json!({ "hooks": { "lockLost": "touch /tmp/pwned" } }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+        // ...and a full config carrying them has them ignored.
+        let req = test::TestRequest::post()
+            .uri("/api/config")
+            .set_json(serde_json::json!({
+                "hardwareOffsetMs": 12,
+                "hooks": { "lockLost": "touch /tmp/pwned", "syncPerformed": "touch /tmp/pwned" }
+            }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+        let hooks = app_state.config.lock().unwrap().hooks.clone();
+        assert_eq!(hooks.lock_lost.as_deref(), Some("logger lost"));
+        assert_eq!(hooks.sync_performed, None);
+        let saved = Config::load(&config_path.into()).unwrap();
+        assert_eq!(saved.hardware_offset_ms, 12);
+        assert_eq!(saved.hooks, hooks);
+
+        let _ = fs::remove_file(config_path);
+    }
+
+    #[actix_web::test]
+    async fn test_api_and_processing_locks_do_not_deadlock() {
+        let app_state = get_test_app_state();
+        let config_path = app_state.config_path.clone();
+        let (done_tx, done_rx) = std::sync::mpsc::channel();
+
+        // Mimic the frame processor: ltc_state first, then config.
+        let loop_state = app_state.clone();
+        let processing = thread::spawn(move || {
+            for _ in 0..2000 {
+                let mut state = loop_state.ltc_state.lock().unwrap();
+                let config = loop_state.config.lock().unwrap();
+                state.record_and_update_ewma_clock_delta(config.hardware_offset_ms);
+            }
+        });
+
+        // Hammer the config and status endpoints from another runtime.
+        let api_state = app_state.clone();
+        thread::spawn(move || {
+            actix_web::rt::System::new().block_on(async move {
+                let app = test::init_service(
+                    App::new()
+                        .app_data(api_state)
+                        .service(get_status)
+                        .service(patch_config),
+                )
+                .await;
+                for i in 0..200 {
+                    let req = test::TestRequest::patch()
+                        .uri("/api/config")
+                        .set_json(serde_json::json!({ "hardwareOffsetMs": i }))
+                        .to_request();
+                    assert!(test::call_service(&app, req).await.status().is_success());
+                    let req = test::TestRequest::get().uri("/api/status").to_request();
+                    assert!(test::call_service(&app, req).await.status().is_success());
+                }
+            });
+            let _ = done_tx.send(());
+        });
+
+        done_rx
+            .recv_timeout(Duration::from_secs(30))
+            .expect("API requests stalled: lock ordering between ltc_state and config is broken");
+        processing.join().unwrap();
+        let _ = fs::remove_file(config_path);
+    }
+
+    #[actix_web::test]
+    async fn test_set_log_level() {
+        let app = test::init_service(App::new().service(get_log_level).service(set_log_level)).await;
+
+        let req = test::TestRequest::put()
+            .uri("/api/logs/level")
+            .set_json(serde_json::json!({ "level": "warn, processor=DEBUG" }))
+            .to_request();
+        let resp: LogLevel = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.level, "warn,processor=debug");
+
+        let req = test::TestRequest::put()
+            .uri("/api/logs/level")
+            .set_json(serde_json::json!({ "level": "processor=loud" }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+        let req = test::TestRequest::get().uri("/api/logs/level").to_request();
+        let resp: LogLevel = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.level, "warn,processor=debug");
+        logger::set_filter(LogFilter::default());
+    }
+
+    #[actix_web::test]
+    async fn test_ipv6_listener_is_v6_only() {
+        let Ok(listener) = bind_v6_only(0) else {
+            return; // no IPv6 on this host
+        };
+        let port = listener.local_addr().unwrap().port();
+        assert!(listener.local_addr().unwrap().is_ipv6());
+        // IPv4 on the same port is left for the IPv4 listener.
+        let v4 = TcpListener::bind((Ipv4Addr::UNSPECIFIED, port)).unwrap();
+        assert_eq!(v4.local_addr().unwrap().port(), port);
+        if std::net::TcpStream::connect((Ipv6Addr::LOCALHOST, port)).is_ok() {
+            assert!(listener.accept().is_ok());
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_get_openapi() {
+        let app = test::init_service(App::new().service(get_openapi)).await;
+
+        let req = test::TestRequest::get().uri("/api/openapi.json").to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+
+        assert!(resp["openapi"].as_str().unwrap().starts_with("3."));
+        let paths = resp["paths"].as_object().unwrap();
+        for path in [
+            "/api/status",
+            "/api/sync",
+            "/api/sync/preview",
+            "/api/config",
+            "/api/config/offset",
+            "/api/logs",
+            "/api/logs/level",
+            "/api/nudge_clock",
+            "/api/set_date",
+            "/api/set_date/preview",
+            "/api/audit",
+            "/api/history",
+            "/api/series/delta",
+            "/api/series/jitter",
+            "/api/report",
+            "/api/report/reset",
+            "/api/ntp_check",
+            "/api/rtp_midi",
+            "/api/ptp/messages",
+            "/api/ptp/masters",
+            "/api/components",
+            "/api/sources",
+            "/api/sinks",
+            "/api/export",
+            "/api/export/start",
+            "/api/export/stop",
+            "/api/serial/ports",
+            "/api/serial/port",
+            "/api/openapi.json",
+        ] {
+            assert!(paths.contains_key(path), "missing path {}", path);
+        }
+        assert!(paths["/api/config"]["get"].is_object());
+        assert!(paths["/api/config"]["post"].is_object());
+        assert!(resp["components"]["schemas"]["ApiStatus"].is_object());
+        assert!(resp["components"]["schemas"]["Config"].is_object());
+    }
+
     #[actix_web::test]
     async fn test_manual_sync_no_ltc() {
         let app_state = get_test_app_state();
         // State with no LTC frame
-        app_state.ltc_state.lock().unwrap().latest = None;
+        {
+            let mut state = app_state.ltc_state.lock().unwrap();
+            state.latest = None;
+            state.publish();
+        }
 
         let app = test::init_service(
             App::new()
@@ -408,5 +2767,385 @@ mod tests {
         let resp = test::call_service(&app, req).await;
 
         assert_eq!(resp.status(), 400); // Bad Request
+        // Nothing was run, so nothing is audited.
+        assert!(app_state.audit.entries().is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_large_sync_needs_a_confirmed_preview() {
+        let app_state = get_test_app_state();
+        {
+            // Twelve hours from now: far past `confirmStepMs`.
+            let mut state = app_state.ltc_state.lock().unwrap();
+            state.latest.as_mut().unwrap().hours = (Local::now().hour() + 12) % 24;
+            state.publish();
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .service(manual_sync)
+                .service(preview_sync),
+        )
+        .await;
+
+        let req = test::TestRequest::post().uri("/api/sync").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 428);
+        let req = test::TestRequest::post().uri("/api/sync?confirm=true&token=0123").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 428);
+        assert!(app_state.audit.entries().is_empty(), "refused before anything ran");
+
+        let req = test::TestRequest::get().uri("/api/sync/preview").to_request();
+        let preview: SyncPreview = test::call_and_read_body_json(&app, req).await;
+        assert!(preview.confirm_required);
+        assert!(preview.step_ms.abs() > 1000);
+        assert_eq!(preview.expires_in_secs, Some(30));
+        let token = preview.token.unwrap();
+        assert!(app_state.confirmations.redeem("sync", &token, preview.step_ms, Instant::now()));
+
+        assert_eq!(preview.timecode, format!("{:02}:02:03:04", (Local::now().hour() + 12) % 24));
+        assert_eq!(preview.target_time, preview.timecode_time, "no offsets configured");
+        assert_eq!(preview.offsets[0].source, "timeturner");
+        assert_eq!(preview.offsets[0].applied_ms, 0.0);
+
+        // Below the threshold there is nothing to confirm.
+        app_state.config.lock().unwrap().confirm_step_ms = 0;
+        let req = test::TestRequest::get().uri("/api/sync/preview").to_request();
+        let preview: SyncPreview = test::call_and_read_body_json(&app, req).await;
+        assert!(!preview.confirm_required);
+        assert_eq!(preview.token, None);
+    }
+
+    #[actix_web::test]
+    async fn test_config_offset_preview() {
+        let app_state = get_test_app_state();
+        app_state.config.lock().unwrap().timeturner_offset =
+            TimeturnerOffset { hours: 0, minutes: 0, seconds: 1, frames: -30, milliseconds: 0 };
+        let app = test::init_service(App::new().app_data(app_state.clone()).service(get_config_offset)).await;
+
+        let req = test::TestRequest::get().uri("/api/config/offset").to_request();
+        let preview: OffsetPreview = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(preview.frame_rate, "25.00fps");
+        assert_eq!(preview.net_ms, -200);
+        assert_eq!(preview.normalized, TimeturnerOffset { frames: -5, ..TimeturnerOffset::default() });
+
+        let req = test::TestRequest::get().uri("/api/config/offset?minutes=90&frames=26").to_request();
+        let preview: OffsetPreview = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(preview.offset, TimeturnerOffset { minutes: 90, frames: 26, ..TimeturnerOffset::default() });
+        assert_eq!(preview.normalized, TimeturnerOffset { hours: 1, minutes: 30, seconds: 1, frames: 1, milliseconds: 0 });
+        assert_eq!(preview.net_ms, 5_401_040);
+
+        // Without LTC, frames are counted at expectedFrameRate.
+        {
+            let mut state = app_state.ltc_state.lock().unwrap();
+            state.latest = None;
+            state.publish();
+        }
+        let req = test::TestRequest::get().uri("/api/config/offset").to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+        app_state.config.lock().unwrap().expected_frame_rate = Some("29.97".into());
+        let req = test::TestRequest::get().uri("/api/config/offset").to_request();
+        let preview: OffsetPreview = test::call_and_read_body_json(&app, req).await;
+        assert_eq!((preview.frame_rate.as_str(), preview.net_ms), ("29.97fps", -1));
+        assert_eq!(preview.normalized, TimeturnerOffset { milliseconds: -1, ..TimeturnerOffset::default() });
+    }
+
+    #[actix_web::test]
+    async fn test_sync_over_step_limit_needs_override() {
+        let app_state = get_test_app_state();
+        {
+            let mut config = app_state.config.lock().unwrap();
+            config.max_auto_step_ms = 2000;
+            config.confirm_step_ms = 0;
+        }
+        {
+            let mut state = app_state.ltc_state.lock().unwrap();
+            state.latest.as_mut().unwrap().hours = (Local::now().hour() + 12) % 24;
+            state.publish();
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .service(manual_sync)
+                .service(preview_sync),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/sync/preview").to_request();
+        let preview: SyncPreview = test::call_and_read_body_json(&app, req).await;
+        assert!(preview.over_step_limit);
+        assert!(!preview.confirm_required);
+
+        let req = test::TestRequest::post().uri("/api/sync").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 428);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(body["message"].as_str().unwrap().contains("maxAutoStepMs"));
+        assert!(app_state.audit.entries().is_empty(), "refused before anything ran");
+    }
+
+    #[actix_web::test]
+    async fn test_config_resync_over_step_limit_needs_override() {
+        let app_state = get_test_app_state();
+        let config_path = app_state.config_path.as_str();
+        let _ = fs::remove_file(config_path);
+        {
+            let mut config = app_state.config.lock().unwrap();
+            config.max_auto_step_ms = 2000;
+            config.confirm_step_ms = 0;
+        }
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .service(patch_config)
+                .service(get_config_offset),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/config/offset?hours=24").to_request();
+        let preview: OffsetPreview = test::call_and_read_body_json(&app, req).await;
+        assert!(preview.over_step_limit);
+        assert!(!preview.confirm_required);
+
+        // Raising the limit in the same request does not get around it.
+        let req = test::TestRequest::patch()
+            .uri("/api/config")
+            .set_json(serde_json::json!({ "timeturnerOffset": { "hours": 24 }, "maxAutoStepMs": 0 }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 428);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(body["message"].as_str().unwrap().contains("maxAutoStepMs"), "{}", body);
+        assert!(!app_state.config.lock().unwrap().timeturner_offset.is_active());
+        assert!(fs::metadata(config_path).is_err(), "nothing was saved");
+    }
+
+    #[actix_web::test]
+    async fn test_sync_preview_breaks_down_offsets() {
+        let app_state = get_test_app_state();
+        app_state.config.lock().unwrap().timeturner_offset =
+            TimeturnerOffset { hours: 1, minutes: 0, seconds: 0, frames: 5, milliseconds: -3 };
+        let app = test::init_service(App::new().app_data(app_state.clone()).service(preview_sync)).await;
+
+        let req = test::TestRequest::get().uri("/api/sync/preview").to_request();
+        let preview: SyncPreview = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(preview.timecode, "01:02:03:04");
+        let timeturner = &preview.offsets[0];
+        assert_eq!(timeturner.configured, "1h 0m 0s 5f -3ms");
+        assert_eq!(timeturner.applied_ms, 3_600_197.0, "5 frames at 25 fps is 200 ms");
+        assert_eq!((preview.target_time - preview.timecode_time).num_milliseconds(), 3_600_197);
+        let hardware = &preview.offsets[1];
+        assert_eq!((hardware.source.as_str(), hardware.applied_ms), ("hardware", 0.0));
+        assert!(app_state.audit.entries().is_empty(), "the clock is not touched");
+    }
+
+    #[actix_web::test]
+    async fn test_get_sources() {
+        let app_state = get_test_app_state();
+        let app = test::init_service(App::new().app_data(app_state.clone()).service(get_sources)).await;
+
+        let req = test::TestRequest::get().uri("/api/sources").to_request();
+        let sources: Vec<SourceReport> = test::call_and_read_body_json(&app, req).await;
+        let names: Vec<_> = sources.iter().map(|s| (s.name.as_str(), s.health)).collect();
+        assert_eq!(names[1..], [("ntp", SourceHealth::Disabled), ("ptp", SourceHealth::Disabled)]);
+        assert_eq!(names[0].0, "ltc");
+    }
+
+    #[actix_web::test]
+    async fn test_get_history() {
+        let app_state = get_test_app_state();
+        {
+            let mut history = app_state.history.lock().unwrap();
+            let now = Utc::now();
+            for s in (1..=120).rev() {
+                history.record(now - chrono::Duration::seconds(s), 3);
+            }
+        }
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .service(get_history),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/history").to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["resolutionSecs"], 1);
+        assert!(resp["points"].as_array().unwrap().len() >= 118);
+        assert_eq!(resp["points"][0]["deltaMs"], 3);
+
+        let req = test::TestRequest::get().uri("/api/history?seconds=86400").to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["resolutionSecs"], 60);
+    }
+
+    #[actix_web::test]
+    async fn test_get_series() {
+        let app_state = get_test_app_state();
+        let at = Utc::now() - chrono::Duration::seconds(5);
+        app_state.series.lock().unwrap().record(at, 1.5, 4);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .service(get_delta_series)
+                .service(get_jitter_series),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/series/delta?minutes=1").to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["resolutionSecs"], 1);
+        let points = resp["points"].as_array().unwrap();
+        assert_eq!(points.len(), 60);
+        let measured: Vec<_> = points.iter().filter(|p| !p["meanMs"].is_null()).collect();
+        assert_eq!(measured.len(), 1);
+        assert_eq!(measured[0]["meanMs"], 1.5);
+
+        let req = test::TestRequest::get().uri("/api/series/jitter").to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let points = resp["points"].as_array().unwrap();
+        assert_eq!(points.len(), 600);
+        assert!(points.iter().any(|p| p["maxMs"] == 4.0));
+
+        for uri in ["/api/series/delta?minutes=0", "/api/series/jitter?minutes=61"] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST, "{}", uri);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_get_report_and_reset() {
+        let app_state = get_test_app_state();
+        app_state.session.lock().unwrap().record_alert(Utc::now(), HookEvent::LockLost, "10:00:00:00".into());
+        let mut entry = AuditEntry::new(AuditSource::Auto, "sync", "date -s".into(), Some(-412));
+        entry.success = true;
+        app_state.audit.record(entry);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .service(get_report)
+                .service(reset_report),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/report").to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["stepCount"], 1);
+        assert_eq!(resp["largestStepMs"], -412);
+        assert_eq!(resp["alerts"][0]["kind"], "lock_lost");
+
+        let req = test::TestRequest::get().uri("/api/report?format=html").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get("content-type").unwrap(), "text/html; charset=utf-8");
+        let body = test::read_body(resp).await;
+        assert!(String::from_utf8_lossy(&body).contains("<td>10:00:00:00</td>"));
+
+        let req = test::TestRequest::get().uri("/api/report?format=pdf").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        // Resetting returns the session just ended and starts an empty one.
+        let req = test::TestRequest::post().uri("/api/report/reset").to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["alertCount"], 1);
+        let req = test::TestRequest::get().uri("/api/report").to_request();
+        let resp: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp["alertCount"], 0);
+    }
+
+    #[actix_web::test]
+    async fn test_get_audit() {
+        let app_state = get_test_app_state();
+        let mut entry = AuditEntry::new(AuditSource::Tui, "nudge", system::nudge_command(-2000), Some(2));
+        entry.success = true;
+        app_state.audit.record(entry);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .service(get_audit),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/audit").to_request();
+        let resp: Vec<serde_json::Value> = test::call_and_read_body_json(&app, req).await;
+
+        assert_eq!(resp.len(), 1);
+        assert_eq!(resp[0]["source"], "tui");
+        assert_eq!(resp[0]["action"], "nudge");
+        assert_eq!(resp[0]["command"], "sudo adjtimex --singleshot -2000");
+        assert_eq!(resp[0]["preDeltaMs"], 2);
+        assert!(resp[0]["postDeltaMs"].is_null());
+    }
+
+    #[actix_web::test]
+    async fn test_get_metrics() {
+        let app_state = get_test_app_state();
+        let mut entry = AuditEntry::new(AuditSource::Auto, "sync", String::new(), None);
+        entry.success = true;
+        app_state.audit.record(entry);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(app_state.clone())
+                .service(get_metrics),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/metrics").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get("content-type").unwrap(), metrics::CONTENT_TYPE);
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.contains("timeturner_ltc_locked 1\n"), "{}", body);
+        assert!(body.contains(
+            "timeturner_clock_mutations_total{source=\"auto\",action=\"sync\",result=\"success\"} 1\n"
+        ));
+    }
+
+    #[actix_web::test]
+    async fn test_request_source() {
+        let req = test::TestRequest::default()
+            .insert_header((audit::SOURCE_HEADER, "tui"))
+            .to_http_request();
+        assert_eq!(request_source(&req), AuditSource::Tui);
+        let req = test::TestRequest::default().to_http_request();
+        assert_eq!(request_source(&req), AuditSource::Api);
+    }
+
+    #[actix_web::test]
+    async fn test_nudge_microseconds() {
+        let config = Config::default();
+        let us = |microseconds, frames, rate| {
+            nudge_microseconds(&NudgeRequest { microseconds, frames }, rate, &config)
+        };
+        assert_eq!(us(Some(-2000), None, None), Ok(-2000));
+        assert_eq!(us(None, Some(-1), Some(Ratio::new(25, 1))), Ok(-40_000));
+        assert_eq!(us(None, Some(1), Some(Ratio::new(30000, 1001))), Ok(33_367));
+        assert_eq!(us(None, Some(1), None).unwrap_err().0, StatusCode::BAD_REQUEST);
+        for (microseconds, frames) in [(None, None), (Some(1), Some(1)), (Some(500_001), None), (None, Some(i64::MAX))] {
+            assert_eq!(
+                us(microseconds, frames, Some(Ratio::new(25, 1))).unwrap_err().0,
+                StatusCode::UNPROCESSABLE_ENTITY
+            );
+        }
+        assert!(us(Some(-500_000), None, None).is_ok());
+    }
+
+    #[actix_web::test]
+    async fn test_control_chrony_rejects_unknown_action() {
+        let app = test::init_service(
+            App::new()
+                .app_data(get_test_app_state())
+                .service(control_chrony),
+        )
+        .await;
+        let req = test::TestRequest::post().uri("/api/services/chrony/reboot").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
     }
 }
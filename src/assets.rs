@@ -0,0 +1,84 @@
+// src/assets.rs
+//
+// The web UI. The files under `static/` are embedded in the binary at build
+// time, so the daemon serves its UI from any working directory; `--web-root`
+// serves them from disk instead, for UI development or a customised UI.
+
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use rust_embed::RustEmbed;
+use std::borrow::Cow;
+use std::path::Path;
+
+use crate::api::AppState;
+
+#[derive(RustEmbed)]
+#[folder = "static/"]
+struct Embedded;
+
+/// The asset behind a request path, or `None` for a path that tries to
+/// leave the web root.
+fn asset_path(request_path: &str) -> Option<String> {
+    let path = request_path.trim_start_matches('/');
+    let path = match path {
+        "" => "index.html",
+        "display" => "display.html",
+        path => path,
+    };
+    if path.split('/').any(|segment| segment.is_empty() || segment == ".." || segment.starts_with('.')) {
+        return None;
+    }
+    Some(path.to_string())
+}
+
+/// The contents of `path`, from `web_root` when set or else the embedded copy.
+fn load(web_root: Option<&Path>, path: &str) -> Option<Cow<'static, [u8]>> {
+    match web_root {
+        Some(root) => std::fs::read(root.join(path)).ok().map(Cow::Owned),
+        None => Embedded::get(path).map(|file| file.data),
+    }
+}
+
+/// Serve a web UI file. Registered as the default service, after the API.
+pub async fn serve_asset(req: HttpRequest, data: web::Data<AppState>) -> HttpResponse {
+    let Some(path) = asset_path(req.path()) else {
+        return HttpResponse::NotFound().finish();
+    };
+    match load(data.web_root.as_deref(), &path) {
+        Some(body) => HttpResponse::Ok()
+            .insert_header((header::CONTENT_TYPE, mime_guess::from_path(&path).first_or_octet_stream().as_ref()))
+            .body(body.into_owned()),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_asset_path() {
+        assert_eq!(asset_path("/").as_deref(), Some("index.html"));
+        assert_eq!(asset_path("/display").as_deref(), Some("display.html"));
+        assert_eq!(asset_path("/assets/header.png").as_deref(), Some("assets/header.png"));
+        assert_eq!(asset_path("/../config.yml"), None);
+        assert_eq!(asset_path("/assets//x"), None);
+        assert_eq!(asset_path("/.git/config"), None);
+    }
+
+    #[test]
+    fn test_embedded_ui_is_complete() {
+        for path in ["index.html", "script.js", "style.css", "display.html", "display.js", "favicon.ico"] {
+            assert!(Embedded::get(path).is_some(), "{} not embedded", path);
+        }
+    }
+
+    #[test]
+    fn test_web_root_overrides_embedded_files() {
+        let root = std::env::temp_dir().join(format!("timeturner-web-root-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("index.html"), "custom").unwrap();
+        assert_eq!(load(Some(&root), "index.html").as_deref(), Some(&b"custom"[..]));
+        assert_eq!(load(Some(&root), "script.js"), None);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,300 @@
+// src/audit.rs
+//
+// Audit trail of every clock mutation (sync, nudge, set date), kept in memory
+// for `/api/audit` and appended to `audit.jsonl` so it survives restarts.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+use crate::sync_logic::{LtcSnapshot, LtcView};
+
+/// Default audit file, next to `config.yml` in the working directory.
+pub const AUDIT_FILE: &str = "audit.jsonl";
+
+/// Request header a client sets to identify itself, e.g. `tui`.
+pub const SOURCE_HEADER: &str = "X-Timeturner-Source";
+
+/// Entries kept in memory and on disk; older ones are dropped on startup.
+const MAX_AUDIT_ENTRIES: usize = 1000;
+
+/// How long to let the delta EWMA settle before sampling the post delta.
+const SETTLE_TIME: Duration = Duration::from_secs(3);
+
+/// What triggered a clock mutation.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditSource {
+    /// The `timeturner-ctl` TUI.
+    Tui,
+    /// `timeturner-ctl` subcommands such as `sync` and `nudge`.
+    Cli,
+    /// Any other API client, including the web UI.
+    Api,
+    /// Auto-sync.
+    Auto,
+    /// A config change with an active timeturner offset.
+    Config,
+    /// The daily re-jam schedule.
+    Schedule,
+    /// A step made outside TimeTurner, caught by the watchdog.
+    External,
+}
+
+impl AuditSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditSource::Tui => "tui",
+            AuditSource::Cli => "cli",
+            AuditSource::Api => "api",
+            AuditSource::Auto => "auto",
+            AuditSource::Config => "config",
+            AuditSource::Schedule => "schedule",
+            AuditSource::External => "external",
+        }
+    }
+
+    /// Map the `X-Timeturner-Source` header of an API request to a source.
+    pub fn from_header(value: Option<&str>) -> Self {
+        match value {
+            Some(v) if v.eq_ignore_ascii_case("tui") => AuditSource::Tui,
+            Some(v) if v.eq_ignore_ascii_case("cli") => AuditSource::Cli,
+            _ => AuditSource::Api,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    /// When the command was issued (UTC).
+    #[schema(value_type = String, format = DateTime)]
+    pub timestamp: DateTime<Utc>,
+    pub source: AuditSource,
+    /// `sync`, `nudge` or `setDate`.
+    pub action: String,
+    /// The command line that was run.
+    pub command: String,
+    pub success: bool,
+    /// Smoothed clock delta just before the command, if LTC was locked.
+    pub pre_delta_ms: Option<i64>,
+    /// Smoothed clock delta a few seconds after the command, if LTC was locked.
+    pub post_delta_ms: Option<i64>,
+}
+
+impl AuditEntry {
+    pub fn new(source: AuditSource, action: &str, command: String, pre_delta_ms: Option<i64>) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            source,
+            action: action.to_string(),
+            command,
+            success: false,
+            pre_delta_ms,
+            post_delta_ms: None,
+        }
+    }
+}
+
+/// The current smoothed clock delta, or `None` when there is no LOCK.
+pub fn current_delta(state: &LtcSnapshot) -> Option<i64> {
+    state.ewma_clock_delta.map(|d| d.round() as i64)
+}
+
+/// Source, action and success of a clock mutation, as counted for metrics.
+pub type MutationKey = (&'static str, String, bool);
+
+pub struct AuditLog {
+    path: Option<PathBuf>,
+    entries: Mutex<VecDeque<AuditEntry>>,
+    /// Mutations recorded since startup; entries reloaded from disk don't count.
+    counts: Mutex<BTreeMap<MutationKey, u64>>,
+}
+
+impl AuditLog {
+    /// Load the trail from `path`, trimming the file to the retained entries.
+    /// Unreadable lines are skipped rather than failing startup.
+    pub fn open(path: &str) -> Self {
+        let path = PathBuf::from(path);
+        let mut entries = VecDeque::new();
+        if let Ok(contents) = fs::read_to_string(&path) {
+            let mut total = 0;
+            for line in contents.lines().filter(|l| !l.trim().is_empty()) {
+                total += 1;
+                match serde_json::from_str::<AuditEntry>(line) {
+                    Ok(entry) => {
+                        if entries.len() == MAX_AUDIT_ENTRIES {
+                            entries.pop_front();
+                        }
+                        entries.push_back(entry);
+                    }
+                    Err(e) => log::warn!("Skipping unreadable audit entry: {}", e),
+                }
+            }
+            if total > entries.len() {
+                let trimmed: String = entries
+                    .iter()
+                    .filter_map(|e| serde_json::to_string(e).ok())
+                    .map(|l| l + "\n")
+                    .collect();
+                if let Err(e) = fs::write(&path, trimmed) {
+                    log::warn!("Failed to trim {}: {}", path.display(), e);
+                }
+            }
+        }
+        Self {
+            path: Some(path),
+            entries: Mutex::new(entries),
+            counts: Mutex::default(),
+        }
+    }
+
+    /// A trail that is never written to disk.
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            entries: Mutex::new(VecDeque::new()),
+            counts: Mutex::default(),
+        }
+    }
+
+    /// Append an entry to memory and to the audit file.
+    pub fn record(&self, entry: AuditEntry) {
+        log::info!(
+            "📝 Audit: {} {} by {} ({})",
+            entry.action,
+            if entry.success { "ok" } else { "failed" },
+            entry.source.as_str(),
+            entry.command
+        );
+        if let Some(path) = &self.path {
+            let appended = serde_json::to_string(&entry).map_err(|e| e.to_string()).and_then(|line| {
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .and_then(|mut f| writeln!(f, "{}", line))
+                    .map_err(|e| e.to_string())
+            });
+            if let Err(e) = appended {
+                log::error!("Failed to write audit entry to {}: {}", path.display(), e);
+            }
+        }
+        *self
+            .counts
+            .lock()
+            .unwrap()
+            .entry((entry.source.as_str(), entry.action.clone(), entry.success))
+            .or_default() += 1;
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() == MAX_AUDIT_ENTRIES {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Seconds since the most recent successful step (`sync`), if any.
+    pub fn secs_since_step(&self) -> Option<i64> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|e| e.success && e.action == "sync")
+            .map(|e| (Utc::now() - e.timestamp).num_seconds())
+    }
+
+    /// How many mutations of each kind were recorded since startup.
+    pub fn mutation_counts(&self) -> BTreeMap<MutationKey, u64> {
+        self.counts.lock().unwrap().clone()
+    }
+
+    /// All retained entries, oldest first.
+    pub fn entries(&self) -> Vec<AuditEntry> {
+        self.entries.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// Record `entry` once the clock has settled, so its post delta reflects the
+/// effect of the command. Runs on its own thread; callers don't wait.
+pub fn record_after_settle(audit: Arc<AuditLog>, ltc: LtcView, mut entry: AuditEntry) {
+    thread::spawn(move || {
+        if entry.success {
+            thread::sleep(SETTLE_TIME);
+            entry.post_delta_ms = current_delta(&ltc.get());
+        }
+        audit.record(entry);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_test_audit_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("timeturner-audit-test-{}-{}.jsonl", std::process::id(), name))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    fn entry(action: &str) -> AuditEntry {
+        let mut e = AuditEntry::new(AuditSource::Auto, action, format!("sudo {}", action), Some(12));
+        e.success = true;
+        e.post_delta_ms = Some(1);
+        e
+    }
+
+    #[test]
+    fn test_source_from_header() {
+        assert_eq!(AuditSource::from_header(Some("TUI")), AuditSource::Tui);
+        assert_eq!(AuditSource::from_header(Some("cli")), AuditSource::Cli);
+        assert_eq!(AuditSource::from_header(Some("curl")), AuditSource::Api);
+        assert_eq!(AuditSource::from_header(None), AuditSource::Api);
+    }
+
+    #[test]
+    fn test_audit_persists_across_restarts() {
+        let path = get_test_audit_path("persist");
+        let _ = fs::remove_file(&path);
+
+        let log = AuditLog::open(&path);
+        log.record(entry("sync"));
+        log.record(entry("nudge"));
+
+        let reopened = AuditLog::open(&path);
+        assert_eq!(reopened.entries(), log.entries());
+        assert_eq!(log.mutation_counts()[&("auto", "nudge".to_string(), true)], 1);
+        assert!(reopened.mutation_counts().is_empty());
+        assert_eq!(reopened.entries()[1].action, "nudge");
+        assert_eq!(reopened.entries()[0].pre_delta_ms, Some(12));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_audit_skips_bad_lines_and_trims_file() {
+        let path = get_test_audit_path("trim");
+        let good = serde_json::to_string(&entry("sync")).unwrap();
+        let mut contents = String::from("not json\n");
+        for _ in 0..MAX_AUDIT_ENTRIES + 5 {
+            contents.push_str(&good);
+            contents.push('\n');
+        }
+        fs::write(&path, contents).unwrap();
+
+        let log = AuditLog::open(&path);
+        assert_eq!(log.entries().len(), MAX_AUDIT_ENTRIES);
+        let lines = fs::read_to_string(&path).unwrap().lines().count();
+        assert_eq!(lines, MAX_AUDIT_ENTRIES);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,229 @@
+// src/auth.rs
+
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{header, Method},
+    middleware::Next,
+    web, Error, HttpResponse,
+};
+
+use crate::api::AppState;
+use crate::auth_backend::Credentials;
+use crate::config::Role;
+
+/// Minimum role needed to call `method path`, or `None` for public routes
+/// (the static web UI, and the health and readiness probes). Unknown mutating API routes default to admin.
+pub fn required_role(method: &Method, path: &str) -> Option<Role> {
+    if !path.starts_with("/api/") || matches!(path, "/api/health" | "/api/ready") {
+        return None;
+    }
+    if method == Method::GET || method == Method::HEAD {
+        return Some(Role::Viewer);
+    }
+    match path {
+        "/api/sync" | "/api/nudge_clock" | "/api/set_date" | "/api/report/reset" => Some(Role::Operator),
+        path if path.starts_with("/api/services/")
+            || path.starts_with("/api/presets/")
+            || path.starts_with("/api/export/") =>
+        {
+            Some(Role::Operator)
+        }
+        _ => Some(Role::Admin),
+    }
+}
+
+fn credentials(req: &ServiceRequest) -> Option<Credentials> {
+    Credentials::parse(req.headers().get(header::AUTHORIZATION)?.to_str().ok()?)
+}
+
+/// Actix middleware enforcing the viewer/operator/admin roles from config,
+/// checking credentials with the configured auth backend.
+pub async fn require_role(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let Some(required) = required_role(req.method(), req.path()) else {
+        return Ok(next.call(req).await?.map_into_left_body());
+    };
+    let backend = req.app_data::<web::Data<AppState>>().and_then(|data| {
+        let config = data.config.lock().unwrap();
+        data.auth.backend(&config.api)
+    });
+    // No backend means auth is disabled.
+    let Some(backend) = backend else {
+        return Ok(next.call(req).await?.map_into_left_body());
+    };
+    let granted = match credentials(&req) {
+        Some(credentials) => {
+            let backend = backend.clone();
+            web::block(move || backend.authenticate(&credentials))
+                .await
+                .ok()
+                .flatten()
+        }
+        None => None,
+    };
+
+    let denied = match granted {
+        Some(role) if role >= required => {
+            return Ok(next.call(req).await?.map_into_left_body());
+        }
+        Some(role) => {
+            log::warn!("🔒 {} token denied {} {}", role.as_str(), req.method(), req.path());
+            HttpResponse::Forbidden().json(serde_json::json!({
+                "status": "error",
+                "message": format!("This action requires the {} role.", required.as_str()),
+            }))
+        }
+        None => HttpResponse::Unauthorized()
+            .insert_header((header::WWW_AUTHENTICATE, backend.challenge()))
+            .json(serde_json::json!({
+                "status": "error",
+                "message": "Missing or unknown API token.",
+            })),
+    };
+    Ok(req.into_response(denied).map_into_right_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::StatusCache;
+    use crate::audit::AuditLog;
+    use crate::auth_backend::AuthCache;
+    use crate::config::{ApiAccessConfig, ApiToken, Config};
+    use crate::health::Health;
+    use crate::history::{DeltaHistory, HistoryConfig};
+    use crate::report::Session;
+    use crate::serial_input::ReaderSelection;
+    use crate::system::SystemClock;
+    use crate::sync_logic::LtcState;
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::{get, middleware::from_fn, post, App, Responder};
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    #[get("/api/status")]
+    async fn status_handler() -> impl Responder {
+        HttpResponse::Ok().finish()
+    }
+
+    #[post("/api/sync")]
+    async fn sync_handler() -> impl Responder {
+        HttpResponse::Ok().finish()
+    }
+
+    #[post("/api/config")]
+    async fn config_handler() -> impl Responder {
+        HttpResponse::Ok().finish()
+    }
+
+    fn get_test_app_state(tokens: Vec<ApiToken>) -> web::Data<AppState> {
+        let config = Config {
+            api: ApiAccessConfig { tokens, ..ApiAccessConfig::default() },
+            ..Config::default()
+        };
+        let ltc_state = LtcState::new();
+        web::Data::new(AppState {
+            ltc: ltc_state.view(),
+            ltc_state: Arc::new(Mutex::new(ltc_state)),
+            config: Arc::new(Mutex::new(config)),
+            log_buffer: Arc::new(Mutex::new(VecDeque::new())),
+            config_path: String::new(),
+            serial: Arc::new(ReaderSelection::fixed("")),
+            audit: Arc::new(AuditLog::in_memory()),
+            clock: Arc::new(SystemClock),
+            clock_gate: Default::default(),
+            history: Arc::new(Mutex::new(DeltaHistory::new(&HistoryConfig::default()))),
+            series: Default::default(),
+            session: Arc::new(Mutex::new(Session::new(chrono::Utc::now(), 0))),
+            auth: AuthCache::default(),
+            status_cache: StatusCache::default(),
+            ntp_check: Default::default(),
+            rtp_midi: Default::default(),
+            ptp_capture: Default::default(),
+            web_root: None,
+            health: Health::new(),
+            supervisor: crate::supervisor::Supervisor::new(),
+            confirmations: Default::default(),
+            sources: Default::default(),
+            sinks: Default::default(),
+            export: Default::default(),
+        })
+    }
+
+    fn token(token: &str, role: Role) -> ApiToken {
+        ApiToken { token: token.to_string(), role }
+    }
+
+    #[test]
+    fn test_required_role() {
+        assert_eq!(required_role(&Method::GET, "/index.html"), None);
+        assert_eq!(required_role(&Method::GET, "/api/health"), None);
+        assert_eq!(required_role(&Method::GET, "/api/ready"), None);
+        assert_eq!(required_role(&Method::GET, "/api/status"), Some(Role::Viewer));
+        assert_eq!(required_role(&Method::GET, "/api/logs"), Some(Role::Viewer));
+        assert_eq!(required_role(&Method::POST, "/api/sync"), Some(Role::Operator));
+        assert_eq!(required_role(&Method::POST, "/api/nudge_clock"), Some(Role::Operator));
+        assert_eq!(required_role(&Method::POST, "/api/services/chrony/restart"), Some(Role::Operator));
+        assert_eq!(required_role(&Method::POST, "/api/presets/activate"), Some(Role::Operator));
+        assert_eq!(required_role(&Method::POST, "/api/report/reset"), Some(Role::Operator));
+        assert_eq!(required_role(&Method::POST, "/api/export/start"), Some(Role::Operator));
+        assert_eq!(required_role(&Method::POST, "/api/config"), Some(Role::Admin));
+        assert_eq!(required_role(&Method::PATCH, "/api/config"), Some(Role::Admin));
+        assert_eq!(required_role(&Method::POST, "/api/unknown"), Some(Role::Admin));
+    }
+
+    #[actix_web::test]
+    async fn test_roles_enforced() {
+        let app_state = get_test_app_state(vec![
+            token("view", Role::Viewer),
+            token("op", Role::Operator),
+            token("adm", Role::Admin),
+        ]);
+        let app = init_service(
+            App::new()
+                .app_data(app_state)
+                .wrap(from_fn(require_role))
+                .service(status_handler)
+                .service(sync_handler)
+                .service(config_handler),
+        )
+        .await;
+
+        let cases = [
+            (Method::GET, "/api/status", None, 401),
+            (Method::GET, "/api/status", Some("wrong"), 401),
+            (Method::GET, "/api/status", Some("view"), 200),
+            (Method::POST, "/api/sync", Some("view"), 403),
+            (Method::POST, "/api/sync", Some("op"), 200),
+            (Method::POST, "/api/config", Some("op"), 403),
+            (Method::POST, "/api/config", Some("adm"), 200),
+            (Method::GET, "/api/status", Some("adm"), 200),
+        ];
+        for (method, path, bearer, expected) in cases {
+            let mut req = TestRequest::default().method(method.clone()).uri(path);
+            if let Some(bearer) = bearer {
+                req = req.insert_header((header::AUTHORIZATION, format!("Bearer {}", bearer)));
+            }
+            let resp = call_service(&app, req.to_request()).await;
+            assert_eq!(resp.status(), expected, "{} {} with {:?}", method, path, bearer);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_open_when_no_tokens_configured() {
+        let app = init_service(
+            App::new()
+                .app_data(get_test_app_state(vec![]))
+                .wrap(from_fn(require_role))
+                .service(config_handler),
+        )
+        .await;
+
+        let req = TestRequest::post().uri("/api/config").to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+    }
+}
@@ -0,0 +1,408 @@
+// src/auth_backend.rs
+//
+// Credential checks behind the API role middleware. `api.backend` in config
+// picks static bearer tokens, local PAM accounts (HTTP Basic) or OIDC bearer
+// JWTs verified against the provider's published keys.
+
+use base64::Engine;
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, DecodingKey, Validation};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::config::{ApiAccessConfig, ApiToken, AuthBackendKind, OidcConfig, PamConfig, Role};
+
+/// How long fetched OIDC signing keys are trusted before refetching.
+const JWKS_TTL: Duration = Duration::from_secs(600);
+
+/// Minimum gap between refetches triggered by an unknown key id, so bad
+/// tokens can't make us hammer the provider.
+const JWKS_MIN_REFRESH: Duration = Duration::from_secs(30);
+
+/// What a client sent in its `Authorization` header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Credentials {
+    Bearer(String),
+    Basic { user: String, password: String },
+}
+
+impl Credentials {
+    /// Parse an `Authorization` header value.
+    pub fn parse(header: &str) -> Option<Self> {
+        let (scheme, value) = header.trim().split_once(' ')?;
+        let value = value.trim();
+        if scheme.eq_ignore_ascii_case("bearer") {
+            return Some(Credentials::Bearer(value.to_string()));
+        }
+        if scheme.eq_ignore_ascii_case("basic") {
+            let decoded = base64::engine::general_purpose::STANDARD.decode(value).ok()?;
+            let decoded = String::from_utf8(decoded).ok()?;
+            let (user, password) = decoded.split_once(':')?;
+            return Some(Credentials::Basic {
+                user: user.to_string(),
+                password: password.to_string(),
+            });
+        }
+        None
+    }
+}
+
+pub trait AuthBackend: Send + Sync {
+    /// `WWW-Authenticate` challenge sent with 401 responses.
+    fn challenge(&self) -> &'static str;
+
+    /// The role granted to `credentials`, or `None` if they are not accepted.
+    /// May block (PAM, JWKS fetches), so the middleware runs it off the
+    /// async workers.
+    fn authenticate(&self, credentials: &Credentials) -> Option<Role>;
+}
+
+/// Bearer tokens listed in config.
+pub struct StaticTokens {
+    tokens: Vec<ApiToken>,
+}
+
+impl AuthBackend for StaticTokens {
+    fn challenge(&self) -> &'static str {
+        "Bearer"
+    }
+
+    fn authenticate(&self, credentials: &Credentials) -> Option<Role> {
+        match credentials {
+            Credentials::Bearer(token) => {
+                self.tokens.iter().find(|t| &t.token == token).map(|t| t.role)
+            }
+            Credentials::Basic { .. } => None,
+        }
+    }
+}
+
+/// Local accounts checked through PAM, mapped to roles by `api.pam.users`.
+pub struct PamAccounts {
+    config: PamConfig,
+}
+
+impl AuthBackend for PamAccounts {
+    fn challenge(&self) -> &'static str {
+        "Basic realm=\"timeturner\""
+    }
+
+    fn authenticate(&self, credentials: &Credentials) -> Option<Role> {
+        let Credentials::Basic { user, password } = credentials else {
+            return None;
+        };
+        // Check the mapping first so unmapped accounts never reach PAM.
+        let role = self.config.role_for_user(user)?;
+        match pam_authenticate(&self.config.service, user, password) {
+            Ok(()) => Some(role),
+            Err(e) => {
+                log::warn!("🔒 PAM rejected {}: {}", user, e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(feature = "pam")]
+fn pam_authenticate(service: &str, user: &str, password: &str) -> Result<(), String> {
+    crate::pam::authenticate(service, user, password)
+}
+
+#[cfg(not(feature = "pam"))]
+fn pam_authenticate(_service: &str, _user: &str, _password: &str) -> Result<(), String> {
+    Err("built without the `pam` feature".to_string())
+}
+
+/// Bearer JWTs issued by an OpenID Connect provider.
+pub struct OidcTokens {
+    config: OidcConfig,
+    agent: ureq::Agent,
+    /// Keys from `jwksUrl` and when they were fetched.
+    keys: Mutex<Option<(Instant, JwkSet)>>,
+}
+
+impl OidcTokens {
+    pub fn new(config: OidcConfig) -> Self {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(5))
+            .build();
+        Self {
+            config,
+            agent,
+            keys: Mutex::new(None),
+        }
+    }
+
+    fn fetch_keys(&self) -> Option<JwkSet> {
+        let fetched = self
+            .agent
+            .get(&self.config.jwks_url)
+            .call()
+            .map_err(|e| e.to_string())
+            .and_then(|resp| resp.into_json::<JwkSet>().map_err(|e| e.to_string()));
+        match fetched {
+            Ok(set) => Some(set),
+            Err(e) => {
+                log::error!("Failed to fetch OIDC keys from {}: {}", self.config.jwks_url, e);
+                None
+            }
+        }
+    }
+
+    /// The decoding key for `kid` (or the only key, for tokens without one),
+    /// refetching the key set when it is stale or the key is unknown.
+    fn key_for(&self, kid: Option<&str>) -> Option<DecodingKey> {
+        let find = |set: &JwkSet| match kid {
+            Some(kid) => set.find(kid).cloned(),
+            None if set.keys.len() == 1 => set.keys.first().cloned(),
+            None => None,
+        };
+        let mut keys = self.keys.lock().unwrap();
+        let (stale, found) = match keys.as_ref() {
+            Some((at, set)) => (at.elapsed() > JWKS_TTL, find(set)),
+            None => (true, None),
+        };
+        let may_refresh = keys
+            .as_ref()
+            .is_none_or(|(at, _)| at.elapsed() > JWKS_MIN_REFRESH);
+        let jwk = match found {
+            Some(jwk) if !stale => jwk,
+            found if may_refresh => match self.fetch_keys() {
+                Some(set) => {
+                    let jwk = find(&set);
+                    *keys = Some((Instant::now(), set));
+                    jwk?
+                }
+                // Keep using the old keys while the provider is unreachable.
+                None => found?,
+            },
+            found => found?,
+        };
+        DecodingKey::from_jwk(&jwk).ok()
+    }
+}
+
+impl AuthBackend for OidcTokens {
+    fn challenge(&self) -> &'static str {
+        "Bearer"
+    }
+
+    fn authenticate(&self, credentials: &Credentials) -> Option<Role> {
+        let Credentials::Bearer(token) = credentials else {
+            return None;
+        };
+        let header = decode_header(token).ok()?;
+        let key = self.key_for(header.kid.as_deref())?;
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&self.config.issuer]);
+        validation.set_audience(&[&self.config.audience]);
+        match decode::<serde_json::Value>(token, &key, &validation) {
+            Ok(data) => role_from_claim(data.claims.get(&self.config.role_claim)?),
+            Err(e) => {
+                log::warn!("🔒 Rejected OIDC token: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// Read a role claim: a single role name, or a list of them (the highest
+/// recognised one wins). Unknown names are ignored.
+pub fn role_from_claim(claim: &serde_json::Value) -> Option<Role> {
+    let parse = |v: &serde_json::Value| serde_json::from_value::<Role>(v.clone()).ok();
+    match claim {
+        serde_json::Value::Array(items) => items.iter().filter_map(parse).max(),
+        other => parse(other),
+    }
+}
+
+/// Refuses everything; used when the configured backend can't be set up.
+struct DenyAll {
+    challenge: &'static str,
+}
+
+impl AuthBackend for DenyAll {
+    fn challenge(&self) -> &'static str {
+        self.challenge
+    }
+
+    fn authenticate(&self, _credentials: &Credentials) -> Option<Role> {
+        None
+    }
+}
+
+/// Build the backend `api` selects. Misconfigurations fail closed.
+pub fn build_backend(api: &ApiAccessConfig) -> Arc<dyn AuthBackend> {
+    match api.backend {
+        AuthBackendKind::Static => Arc::new(StaticTokens { tokens: api.tokens.clone() }),
+        AuthBackendKind::Pam => {
+            if cfg!(not(feature = "pam")) {
+                log::error!("api.backend is pam but this build lacks the `pam` feature; denying all API requests");
+            }
+            Arc::new(PamAccounts { config: api.pam.clone().unwrap_or_default() })
+        }
+        AuthBackendKind::Oidc => match &api.oidc {
+            Some(oidc) => Arc::new(OidcTokens::new(oidc.clone())),
+            None => {
+                log::error!("api.backend is oidc but api.oidc is not set; denying all API requests");
+                Arc::new(DenyAll { challenge: "Bearer" })
+            }
+        },
+    }
+}
+
+/// The backend for the current config, rebuilt only when `api` changes so
+/// fetched OIDC keys survive between requests.
+#[derive(Default)]
+pub struct AuthCache {
+    current: Mutex<Option<(ApiAccessConfig, Arc<dyn AuthBackend>)>>,
+}
+
+impl AuthCache {
+    /// `None` when API authentication is disabled.
+    pub fn backend(&self, api: &ApiAccessConfig) -> Option<Arc<dyn AuthBackend>> {
+        if !api.is_enabled() {
+            return None;
+        }
+        let mut current = self.current.lock().unwrap();
+        match current.as_ref() {
+            Some((cached, backend)) if cached == api => Some(backend.clone()),
+            _ => {
+                let backend = build_backend(api);
+                *current = Some((api.clone(), backend.clone()));
+                Some(backend)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PamUser;
+    use actix_web::{get, App, HttpResponse, HttpServer, Responder};
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use std::net::TcpListener;
+    use std::thread;
+
+    const SECRET: &[u8] = b"timeturner-test-signing-secret";
+
+    #[get("/jwks")]
+    async fn jwks() -> impl Responder {
+        let k = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(SECRET);
+        HttpResponse::Ok().json(serde_json::json!({
+            "keys": [{ "kty": "oct", "kid": "k1", "alg": "HS256", "k": k }]
+        }))
+    }
+
+    /// Serve the test JWKS on an ephemeral port and return its URL.
+    fn start_jwks_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/jwks", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            actix_web::rt::System::new().block_on(async move {
+                HttpServer::new(|| App::new().service(jwks))
+                    .workers(1)
+                    .listen(listener)
+                    .unwrap()
+                    .run()
+                    .await
+            })
+        });
+        url
+    }
+
+    fn jwt(claims: serde_json::Value) -> Credentials {
+        let mut header = Header::new(jsonwebtoken::Algorithm::HS256);
+        header.kid = Some("k1".to_string());
+        Credentials::Bearer(encode(&header, &claims, &EncodingKey::from_secret(SECRET)).unwrap())
+    }
+
+    #[test]
+    fn test_parse_credentials() {
+        assert_eq!(
+            Credentials::parse("Bearer abc"),
+            Some(Credentials::Bearer("abc".to_string()))
+        );
+        // "op:pa:ss" — passwords may contain colons.
+        assert_eq!(
+            Credentials::parse("basic b3A6cGE6c3M="),
+            Some(Credentials::Basic { user: "op".to_string(), password: "pa:ss".to_string() })
+        );
+        assert_eq!(Credentials::parse("Basic !!!"), None);
+        assert_eq!(Credentials::parse("Digest x"), None);
+    }
+
+    #[test]
+    fn test_role_from_claim() {
+        use serde_json::json;
+        assert_eq!(role_from_claim(&json!("operator")), Some(Role::Operator));
+        assert_eq!(role_from_claim(&json!(["viewer", "admin", "nope"])), Some(Role::Admin));
+        assert_eq!(role_from_claim(&json!("root")), None);
+        assert_eq!(role_from_claim(&json!([])), None);
+    }
+
+    #[test]
+    fn test_static_and_pam_backends() {
+        let api = ApiAccessConfig {
+            tokens: vec![ApiToken { token: "t".into(), role: Role::Viewer }],
+            ..ApiAccessConfig::default()
+        };
+        let backend = build_backend(&api);
+        assert_eq!(backend.authenticate(&Credentials::Bearer("t".into())), Some(Role::Viewer));
+        assert_eq!(backend.authenticate(&Credentials::Bearer("x".into())), None);
+
+        let pam = PamConfig {
+            users: vec![PamUser { user: "op".into(), role: Role::Operator }],
+            ..PamConfig::default()
+        };
+        assert_eq!(pam.role_for_user("op"), Some(Role::Operator));
+        assert_eq!(pam.role_for_user("guest"), None);
+        let backend = build_backend(&ApiAccessConfig {
+            backend: AuthBackendKind::Pam,
+            pam: Some(pam),
+            ..ApiAccessConfig::default()
+        });
+        assert!(backend.challenge().starts_with("Basic"));
+        // Unmapped accounts are refused before PAM is consulted.
+        let guest = Credentials::Basic { user: "guest".into(), password: "x".into() };
+        assert_eq!(backend.authenticate(&guest), None);
+    }
+
+    #[test]
+    fn test_oidc_validates_tokens_against_jwks() {
+        let backend = OidcTokens::new(OidcConfig {
+            issuer: "https://idp.example".into(),
+            audience: "timeturner".into(),
+            jwks_url: start_jwks_server(),
+            role_claim: "roles".into(),
+        });
+        let exp = chrono::Utc::now().timestamp() + 600;
+        let claims = |iss: &str, aud: &str, exp: i64| {
+            serde_json::json!({ "iss": iss, "aud": aud, "exp": exp, "roles": ["viewer", "operator"] })
+        };
+
+        let good = jwt(claims("https://idp.example", "timeturner", exp));
+        assert_eq!(backend.authenticate(&good), Some(Role::Operator));
+        let wrong_aud = jwt(claims("https://idp.example", "other", exp));
+        assert_eq!(backend.authenticate(&wrong_aud), None);
+        let wrong_iss = jwt(claims("https://evil.example", "timeturner", exp));
+        assert_eq!(backend.authenticate(&wrong_iss), None);
+        let expired = jwt(claims("https://idp.example", "timeturner", exp - 7200));
+        assert_eq!(backend.authenticate(&expired), None);
+        assert_eq!(backend.authenticate(&Credentials::Bearer("not.a.jwt".into())), None);
+    }
+
+    #[test]
+    fn test_cache_rebuilds_only_on_change() {
+        let cache = AuthCache::default();
+        let mut api = ApiAccessConfig::default();
+        assert!(cache.backend(&api).is_none());
+
+        api.tokens.push(ApiToken { token: "a".into(), role: Role::Admin });
+        let first = cache.backend(&api).unwrap();
+        assert!(Arc::ptr_eq(&first, &cache.backend(&api).unwrap()));
+        api.tokens[0].role = Role::Viewer;
+        assert!(!Arc::ptr_eq(&first, &cache.backend(&api).unwrap()));
+    }
+}
@@ -0,0 +1,147 @@
+// src/bin/timeturner-ctl.rs
+//
+// Operator client for a running `timeturnerd`. Everything goes through the
+// daemon's HTTP API, so it can be started and quit at will, locally or over SSH.
+
+use clap::{Args as ClapArgs, Parser, Subcommand};
+use ntp_timeturner::audit::AuditSource;
+use ntp_timeturner::cli;
+use ntp_timeturner::client::{ApiClient, DEFAULT_URL};
+use ntp_timeturner::ui::start_ui;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Base URL of the timeturnerd API.
+    #[arg(long, env = "TIMETURNER_URL", default_value = DEFAULT_URL)]
+    url: String,
+
+    /// API token, required when the daemon has `api.tokens` configured.
+    #[arg(long, env = "TIMETURNER_TOKEN")]
+    token: Option<String>,
+
+    /// With no subcommand, the interactive TUI is started.
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Print the daemon's status and exit. Exits 1 if the daemon is unreachable.
+    Status {
+        /// Print the raw `/api/status` JSON on one line.
+        #[arg(long)]
+        json: bool,
+        /// Keep printing until interrupted.
+        #[arg(long)]
+        watch: bool,
+        /// Polling interval for `--watch`, in milliseconds.
+        #[arg(long, default_value_t = 1000, requires = "watch")]
+        interval_ms: u64,
+    },
+    /// Step the system clock to the current LTC timecode.
+    Sync {
+        /// Go ahead with a step larger than `confirmStepMs`.
+        #[arg(long)]
+        confirm: bool,
+        /// Go ahead with a step larger than `maxAutoStepMs`.
+        #[arg(long = "override")]
+        override_limit: bool,
+    },
+    /// Slew the system clock by milliseconds or frames (negative to retard it).
+    Nudge {
+        #[arg(long, allow_negative_numbers = true, required_unless_present = "frames", conflicts_with = "frames")]
+        ms: Option<i64>,
+        /// Whole frames at the current LTC frame rate.
+        #[arg(long, allow_negative_numbers = true)]
+        frames: Option<i64>,
+    },
+    /// Change the timeturner offset applied to incoming LTC.
+    Offset {
+        #[command(subcommand)]
+        action: OffsetAction,
+        /// Go ahead with a re-sync larger than `confirmStepMs`.
+        #[arg(long, global = true)]
+        confirm: bool,
+        /// Go ahead with a re-sync larger than `maxAutoStepMs`.
+        #[arg(long = "override", global = true)]
+        override_limit: bool,
+    },
+    /// Print the end-of-show report: LOCK/FREE time, clock steps, delta,
+    /// alerts and serial reconnects since the session started.
+    Report {
+        /// Print the report as JSON.
+        #[arg(long, conflicts_with = "html")]
+        json: bool,
+        /// Print the report as a standalone HTML page.
+        #[arg(long)]
+        html: bool,
+        /// End the session and start a new one; the report covers the session ended.
+        #[arg(long)]
+        reset: bool,
+        /// Write the report to this file instead of stdout.
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum OffsetAction {
+    /// Set one or more offset fields; fields not given are left unchanged.
+    Set(OffsetFields),
+    /// Reset the offset to zero.
+    Clear,
+}
+
+#[derive(ClapArgs, Debug)]
+#[group(required = true, multiple = true)]
+struct OffsetFields {
+    #[arg(long, allow_negative_numbers = true)]
+    hours: Option<i64>,
+    #[arg(long, allow_negative_numbers = true)]
+    minutes: Option<i64>,
+    #[arg(long, allow_negative_numbers = true)]
+    seconds: Option<i64>,
+    #[arg(long, allow_negative_numbers = true)]
+    frames: Option<i64>,
+    #[arg(long, allow_negative_numbers = true)]
+    ms: Option<i64>,
+}
+
+fn main() {
+    let args = Args::parse();
+    let client = ApiClient::new(&args.url, args.token);
+
+    let Some(command) = args.command else {
+        start_ui(client.with_source(AuditSource::Tui));
+        return;
+    };
+    let client = client.with_source(AuditSource::Cli);
+    let code = match command {
+        Command::Status { json, watch, interval_ms } => {
+            let watch = watch.then(|| Duration::from_millis(interval_ms));
+            cli::status(&client, json, watch)
+        }
+        Command::Sync { confirm, override_limit } => cli::sync(&client, confirm, override_limit),
+        Command::Nudge { ms, frames } => cli::nudge(&client, ms, frames),
+        Command::Offset { action: OffsetAction::Set(f), confirm, override_limit } => cli::set_offset(
+            &client,
+            cli::offset_patch(f.hours, f.minutes, f.seconds, f.frames, f.ms),
+            confirm,
+            override_limit,
+        ),
+        Command::Offset { action: OffsetAction::Clear, confirm, override_limit } => cli::set_offset(
+            &client,
+            cli::offset_patch(Some(0), Some(0), Some(0), Some(0), Some(0)),
+            confirm,
+            override_limit,
+        ),
+        Command::Report { json, html, reset, output } => {
+            cli::session_report(&client, json, html, reset, output.as_deref())
+        }
+    };
+    process::exit(code);
+}
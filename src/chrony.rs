@@ -0,0 +1,210 @@
+// src/chrony.rs
+//
+// What chrony itself thinks of the time it serves, from the CSV output of
+// `chronyc -c tracking` and `chronyc -c sources`. `ntp_active` only says
+// whether the service is running; this says whether it is serving good time.
+
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use utoipa::ToSchema;
+
+/// `chronyc tracking`: chrony's reference and its view of the system clock.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChronyTracking {
+    /// Reference ID in hex, e.g. `7F7F0101` for the local clock.
+    pub reference_id: String,
+    /// Reference name or address, e.g. `LOCAL`.
+    pub reference_name: String,
+    pub stratum: u32,
+    /// How far the system clock is from chrony's time, in ms (positive:
+    /// fast). chrony is slewing it away.
+    pub system_time_ms: f64,
+    /// Offset at the last clock update, in ms.
+    pub last_offset_ms: f64,
+    /// Long-term average offset, in ms.
+    pub rms_offset_ms: f64,
+    /// Rate at which the system clock would gain without correction, in ppm.
+    pub frequency_ppm: f64,
+    pub skew_ppm: f64,
+    pub root_delay_ms: f64,
+    pub root_dispersion_ms: f64,
+    pub update_interval_secs: f64,
+    /// `Normal`, `Insert second`, `Delete second` or `Not synchronised`.
+    pub leap_status: String,
+}
+
+/// One line of `chronyc sources`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChronySource {
+    /// `server`, `peer` or `refclock`.
+    pub mode: String,
+    /// `selected`, `combined`, `notCombined`, `unreachable`, `falseticker`
+    /// or `variable`.
+    pub state: String,
+    pub name: String,
+    pub stratum: u32,
+    /// Polling interval as a power of two, in seconds.
+    pub poll: i32,
+    /// Reachability register: the last eight polls as bits, 255 when all
+    /// answered.
+    pub reach: u8,
+    /// Seconds since the last sample, if any.
+    pub last_rx_secs: Option<u64>,
+    /// Offset at the last sample, in ms (positive: the system clock is ahead
+    /// of the source).
+    pub offset_ms: f64,
+    /// Error bound of that sample, in ms.
+    pub error_ms: f64,
+}
+
+/// Served at `/api/chrony`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, ToSchema)]
+pub struct ChronyReport {
+    /// `null` if `chronyc tracking` failed.
+    pub tracking: Option<ChronyTracking>,
+    pub sources: Vec<ChronySource>,
+    /// Why chrony could not be queried, if it could not.
+    pub error: Option<String>,
+}
+
+fn seconds_to_ms(field: &str) -> Result<f64, String> {
+    field
+        .parse::<f64>()
+        .map(|secs| secs * 1000.0)
+        .map_err(|_| format!("bad number '{}'", field))
+}
+
+fn number<T: std::str::FromStr>(field: &str) -> Result<T, String> {
+    field.parse().map_err(|_| format!("bad number '{}'", field))
+}
+
+/// Parse `chronyc -c tracking`.
+pub fn parse_tracking(csv: &str) -> Result<ChronyTracking, String> {
+    let fields: Vec<&str> = csv.trim().split(',').collect();
+    if fields.len() < 14 {
+        return Err(format!("unexpected tracking output '{}'", csv.trim()));
+    }
+    Ok(ChronyTracking {
+        reference_id: fields[0].to_string(),
+        reference_name: fields[1].to_string(),
+        stratum: number(fields[2])?,
+        // chronyc reports the correction still to apply, the opposite sign.
+        system_time_ms: -seconds_to_ms(fields[4])?,
+        last_offset_ms: seconds_to_ms(fields[5])?,
+        rms_offset_ms: seconds_to_ms(fields[6])?,
+        frequency_ppm: number(fields[7])?,
+        skew_ppm: number(fields[9])?,
+        root_delay_ms: seconds_to_ms(fields[10])?,
+        root_dispersion_ms: seconds_to_ms(fields[11])?,
+        update_interval_secs: number(fields[12])?,
+        leap_status: fields[13].to_string(),
+    })
+}
+
+/// Parse `chronyc -c sources`, one source per line.
+pub fn parse_sources(csv: &str) -> Result<Vec<ChronySource>, String> {
+    csv.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let fields: Vec<&str> = line.trim().split(',').collect();
+            if fields.len() < 10 {
+                return Err(format!("unexpected sources output '{}'", line.trim()));
+            }
+            let mode = match fields[0] {
+                "^" => "server",
+                "=" => "peer",
+                "#" => "refclock",
+                other => other,
+            };
+            let state = match fields[1] {
+                "*" => "selected",
+                "+" => "combined",
+                "-" => "notCombined",
+                "?" => "unreachable",
+                "x" => "falseticker",
+                "~" => "variable",
+                other => other,
+            };
+            Ok(ChronySource {
+                mode: mode.to_string(),
+                state: state.to_string(),
+                name: fields[2].to_string(),
+                stratum: number(fields[3])?,
+                poll: number(fields[4])?,
+                reach: u8::from_str_radix(fields[5], 8)
+                    .map_err(|_| format!("bad reach '{}'", fields[5]))?,
+                last_rx_secs: fields[6].parse().ok(),
+                offset_ms: seconds_to_ms(fields[7])?,
+                error_ms: seconds_to_ms(fields[9])?,
+            })
+        })
+        .collect()
+}
+
+/// Run `chronyc -n -c <command>`, returning its output.
+fn chronyc(command: &str) -> Result<String, String> {
+    let output = Command::new("chronyc")
+        .args(["-n", "-c", command])
+        .output()
+        .map_err(|e| format!("cannot run chronyc: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("chronyc {} failed: {}", command, stderr.trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Query the local chrony daemon. Blocking; runs two short `chronyc`
+/// commands.
+pub fn query() -> ChronyReport {
+    let tracking = chronyc("tracking").and_then(|csv| parse_tracking(&csv));
+    let sources = chronyc("sources").and_then(|csv| parse_sources(&csv));
+    let error = match (&tracking, &sources) {
+        (Err(e), _) | (_, Err(e)) => Some(e.clone()),
+        _ => None,
+    };
+    ChronyReport {
+        tracking: tracking.ok(),
+        sources: sources.unwrap_or_default(),
+        error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tracking() {
+        let tracking = parse_tracking(
+            "7F7F0101,LOCAL,1,1723024800.123456789,-0.000012000,0.000000003,0.000000021,-12.345,0.001,0.020,0.000000000,0.000010000,64.2,Normal\n",
+        )
+        .unwrap();
+        assert_eq!(tracking.reference_name, "LOCAL");
+        assert_eq!(tracking.stratum, 1);
+        assert!((tracking.system_time_ms - 0.012).abs() < 1e-9);
+        assert!((tracking.frequency_ppm + 12.345).abs() < 1e-9);
+        assert!((tracking.root_dispersion_ms - 0.01).abs() < 1e-9);
+        assert_eq!(tracking.leap_status, "Normal");
+        assert!(parse_tracking("506 Cannot talk to daemon").is_err());
+    }
+
+    #[test]
+    fn test_parse_sources() {
+        let sources = parse_sources(
+            "#,*,LOCL,0,4,377,12,0.000000000,0.000000000,0.000001000\n\
+             ^,?,192.168.1.1,2,6,0,-,0.001500000,0.001500000,0.020000000\n",
+        )
+        .unwrap();
+        assert_eq!(sources.len(), 2);
+        assert_eq!((sources[0].mode.as_str(), sources[0].state.as_str()), ("refclock", "selected"));
+        assert_eq!(sources[0].reach, 255);
+        assert_eq!(sources[0].last_rx_secs, Some(12));
+        assert_eq!((sources[1].mode.as_str(), sources[1].state.as_str()), ("server", "unreachable"));
+        assert_eq!(sources[1].last_rx_secs, None);
+        assert!((sources[1].offset_ms - 1.5).abs() < 1e-9);
+        assert!(parse_sources("^,*,x").is_err());
+    }
+}
@@ -0,0 +1,366 @@
+// src/cli.rs
+//
+// Non-interactive `timeturner-ctl` subcommands, for scripts and config
+// management checks. Everything goes through the daemon's API.
+
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use crate::api::{ApiStatus, OffsetPreview, SyncPreview};
+use crate::client::{ApiClient, ApiMessage};
+use crate::config::{Config, TimeturnerOffset};
+
+/// Render a status as `key: value` lines, in the order the TUI shows them.
+pub fn format_status(st: &ApiStatus) -> String {
+    [
+        ("Serial Port", st.serial_port.clone()),
+        (
+            "Reader Firmware",
+            st.reader_firmware.as_ref().map_or("unknown".to_string(), |f| format!("{} {}", f.name, f.version)),
+        ),
+        ("Chrony Service", if st.ntp_active { "RUNNING" } else { "MISSING" }.to_string()),
+        ("Time Conflicts", st.time_conflicts_text()),
+        ("Interfaces", st.interfaces.join(", ")),
+        ("LTC Status", st.ltc_status_text()),
+        ("LTC Timecode", st.ltc_timecode.clone()),
+        ("LTC Time Zone", st.ltc_time_zone.clone()),
+        ("Frame Rate", st.frame_rate_warning.as_ref().map_or(st.frame_rate.clone(), |w| format!("⚠️ {}", w))),
+        ("System Clock", format!("{} {}", st.system_date, st.system_clock)),
+        (
+            "Timecode Δ",
+            format!(
+                "{:+} ms ({}) {}",
+                st.timecode_delta_ms,
+                st.delta_frames_text(),
+                st.clock_direction.as_str()
+            ),
+        ),
+        ("Sync Status", st.sync_status.clone()),
+        ("Sync Jitter", st.jitter_status.clone()),
+        ("Jitter Stats", st.jitter.as_ref().map_or("…".to_string(), |j| j.to_string())),
+        ("Lock Ratio", format!("{:.1}% LOCK", st.lock_ratio)),
+        ("Session TC", st.session_timecode.clone()),
+        ("Drift", st.drift_ppm.map_or("…".to_string(), |ppm| format!("{:+.1} ppm", ppm))),
+        ("Why", st.why.clone()),
+    ]
+    .iter()
+    .map(|(label, value)| format!("{:<16} : {}\n", label, value))
+    .collect()
+}
+
+/// Render a status as a single line of JSON, as served by `/api/status`.
+pub fn format_status_json(st: &ApiStatus) -> String {
+    serde_json::to_string(st).expect("status serialises to JSON")
+}
+
+/// `timeturner-ctl status`: print the status once, or every `interval` with
+/// `--watch` (one JSON object per line with `--json`). Returns the exit code:
+/// 0 on success, 1 if the daemon could not be queried. In watch mode errors
+/// are reported on stderr and polling continues.
+pub fn status(client: &ApiClient, json: bool, watch: Option<Duration>) -> i32 {
+    loop {
+        match client.status() {
+            Ok(st) if json => println!("{}", format_status_json(&st)),
+            Ok(st) => {
+                print!("{}", format_status(&st));
+                if watch.is_some() {
+                    println!();
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                if watch.is_none() {
+                    return 1;
+                }
+            }
+        }
+        match watch {
+            Some(interval) => thread::sleep(interval),
+            None => return 0,
+        }
+    }
+}
+
+/// Print the outcome of a command endpoint and return the exit code.
+fn report(result: Result<ApiMessage, String>) -> i32 {
+    match result {
+        Ok(msg) => {
+            println!("{}", msg.message);
+            0
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            1
+        }
+    }
+}
+
+/// `timeturner-ctl sync [--confirm] [--override]`: step the system clock to
+/// the current LTC. A step over `confirmStepMs` is refused unless `confirm`
+/// is set, which previews it and confirms with the preview's token; one
+/// over `maxAutoStepMs` unless `override_limit` is set.
+pub fn sync(client: &ApiClient, confirm: bool, override_limit: bool) -> i32 {
+    let send = |token: Option<&str>| match (token, override_limit) {
+        (_, true) => client.sync_override(token),
+        (Some(token), false) => client.sync_confirmed(token),
+        (None, false) => client.sync(),
+    };
+    if !confirm {
+        return report(send(None));
+    }
+    match client.sync_preview() {
+        Ok(SyncPreview { token: Some(token), step_ms, target_time, .. }) => {
+            println!("Stepping the clock {:+} ms to {}.", step_ms, target_time.format("%H:%M:%S%.3f"));
+            report(send(Some(&token)))
+        }
+        Ok(_) => report(send(None)),
+        Err(e) => report(Err(e)),
+    }
+}
+
+/// `timeturner-ctl nudge --ms N | --frames N`: slew the clock by `ms`
+/// milliseconds or by whole frames.
+pub fn nudge(client: &ApiClient, ms: Option<i64>, frames: Option<i64>) -> i32 {
+    match (ms, frames) {
+        (Some(ms), _) => report(client.nudge(ms * 1000)),
+        (None, Some(frames)) => report(client.nudge_frames(frames)),
+        (None, None) => report(Err("Give --ms or --frames.".into())),
+    }
+}
+
+/// The `timeturnerOffset` merge patch for the fields given on the command
+/// line; fields left out keep their current value.
+pub fn offset_patch(
+    hours: Option<i64>,
+    minutes: Option<i64>,
+    seconds: Option<i64>,
+    frames: Option<i64>,
+    milliseconds: Option<i64>,
+) -> serde_json::Value {
+    let fields = [
+        ("hours", hours),
+        ("minutes", minutes),
+        ("seconds", seconds),
+        ("frames", frames),
+        ("milliseconds", milliseconds),
+    ];
+    let offset: serde_json::Map<String, serde_json::Value> = fields
+        .iter()
+        .filter_map(|(key, value)| value.map(|v| (key.to_string(), v.into())))
+        .collect();
+    serde_json::json!({ "timeturnerOffset": offset })
+}
+
+fn format_offset(offset: &TimeturnerOffset) -> String {
+    format!(
+        "{:+}h {:+}m {:+}s {:+}f {:+}ms",
+        offset.hours, offset.minutes, offset.seconds, offset.frames, offset.milliseconds
+    )
+}
+
+/// The offset `patch` would leave the daemon with: its current offset with
+/// the patched fields replaced.
+fn patched_offset(client: &ApiClient, patch: &serde_json::Value) -> Result<TimeturnerOffset, String> {
+    let mut offset = serde_json::to_value(client.config()?.timeturner_offset).map_err(|e| e.to_string())?;
+    if let (Some(offset), Some(fields)) = (offset.as_object_mut(), patch["timeturnerOffset"].as_object()) {
+        offset.extend(fields.clone());
+    }
+    serde_json::from_value(offset).map_err(|e| e.to_string())
+}
+
+/// Preview the offset `patch` sets and, if the re-sync needs confirming,
+/// apply it with the preview's token.
+fn patch_confirmed(client: &ApiClient, patch: serde_json::Value, override_limit: bool) -> Result<Config, String> {
+    let token = match client.offset_preview(&patched_offset(client, &patch)?) {
+        Ok(OffsetPreview { token: Some(token), step_ms: Some(step_ms), .. }) => {
+            println!("Stepping the clock {:+} ms.", step_ms);
+            Some(token)
+        }
+        _ => None,
+    };
+    match (token, override_limit) {
+        (token, true) => client.patch_config_override(patch, token.as_deref()),
+        (Some(token), false) => client.patch_config_confirmed(patch, &token),
+        (None, false) => client.patch_config(patch),
+    }
+}
+
+/// `timeturner-ctl offset set|clear [--confirm] [--override]`: apply a
+/// `timeturnerOffset` patch and print the offset the daemon saved. A re-sync
+/// over `confirmStepMs` is refused unless `confirm` is set, which previews it
+/// and confirms with the preview's token; one over `maxAutoStepMs` unless
+/// `override_limit` is set.
+pub fn set_offset(client: &ApiClient, patch: serde_json::Value, confirm: bool, override_limit: bool) -> i32 {
+    let result = match (confirm, override_limit) {
+        (true, _) => patch_confirmed(client, patch, override_limit),
+        (false, true) => client.patch_config_override(patch, None),
+        (false, false) => client.patch_config(patch),
+    };
+    match result {
+        Ok(config) => {
+            println!("Timeturner offset: {}", format_offset(&config.timeturner_offset));
+            0
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            1
+        }
+    }
+}
+
+/// `timeturner-ctl report [--json|--html] [--reset] [--output FILE]`: print
+/// or save the session report, ending the session first with `--reset`.
+pub fn session_report(client: &ApiClient, json: bool, html: bool, reset: bool, output: Option<&Path>) -> i32 {
+    let result = if reset { client.reset_report() } else { client.report() };
+    let report = match result {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return 1;
+        }
+    };
+    let body = if json {
+        format!("{}\n", serde_json::to_string_pretty(&report).unwrap_or_default())
+    } else if html {
+        report.to_html()
+    } else {
+        report.to_text()
+    };
+    match output {
+        Some(path) => match std::fs::write(path, body) {
+            Ok(()) => {
+                println!("Report written to {}", path.display());
+                0
+            }
+            Err(e) => {
+                eprintln!("Error: cannot write {}: {}", path.display(), e);
+                1
+            }
+        },
+        None => {
+            print!("{}", body);
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync_logic::{ClockDirection, JitterStats, Staleness};
+    use crate::serial_input::ReaderFirmware;
+
+    fn get_test_status() -> ApiStatus {
+        ApiStatus {
+            ltc_status: "LOCK".to_string(),
+            ltc_signal: Staleness::Live,
+            ltc_frame_age_ms: Some(12),
+            ltc_timecode: "10:20:30:04".to_string(),
+            ltc_time_zone: "UTC".to_string(),
+            frame_rate: "25.00fps".to_string(),
+            frame_rate_warning: None,
+            system_clock: "10:20:30.165".to_string(),
+            system_date: "2025-07-30".to_string(),
+            timecode_delta_ms: -3,
+            timecode_delta_frames: 0,
+            timecode_delta_by_rate: Vec::new(),
+            timecode_delta_raw_ms: Some(-3.2),
+            timecode_delta_unfiltered_ms: Some(-5.1),
+            clock_direction: ClockDirection::InSync,
+            sync_status: "IN SYNC".to_string(),
+            jitter_status: "GOOD".to_string(),
+            lock_ratio: 99.5,
+            ntp_active: true,
+            interfaces: vec!["192.168.1.100".to_string(), "10.0.0.2".to_string()],
+            network_interfaces: Vec::new(),
+            hardware_offset_ms: 20,
+            serial_port: "/dev/ttyACM0".to_string(),
+            reader_firmware: Some(ReaderFirmware {
+                name: "ltc_audiohat_lock_v2".to_string(),
+                version: "2.1.0".to_string(),
+                capabilities: Vec::new(),
+            }),
+            session_timecode: "10:20:25:00".to_string(),
+            why: "monitor-only: auto-sync is off, clock is 3 ms behind".to_string(),
+            drift_ppm: Some(-2.5),
+            jitter: JitterStats::from_samples(&[2, 3, 2, 9]),
+            frame_queue: Default::default(),
+            continuity: Default::default(),
+            time_conflicts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_format_status() {
+        let text = format_status(&get_test_status());
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 18);
+        assert_eq!(lines[0], "Serial Port      : /dev/ttyACM0");
+        assert!(lines.contains(&"Interfaces       : 192.168.1.100, 10.0.0.2"));
+        assert!(lines.contains(&"Timecode Δ       : -3 ms (+0 frames) in sync"));
+        assert!(lines.contains(&"LTC Time Zone    : UTC"));
+        assert!(lines.contains(&"LTC Status       : LOCK (last frame 12 ms ago)"));
+        assert!(lines.contains(&"Lock Ratio       : 99.5% LOCK"));
+        assert!(lines.contains(&"Session TC       : 10:20:25:00"));
+        assert!(lines.contains(&"Drift            : -2.5 ppm"));
+        assert!(lines.contains(&"Time Conflicts   : none"));
+        assert!(lines.contains(&"Reader Firmware  : ltc_audiohat_lock_v2 2.1.0"));
+        assert!(lines.contains(&"Jitter Stats     : mean 4 ms, σ 2.9 ms, p95 9 ms, min 2 / max 9 ms"));
+    }
+
+    #[test]
+    fn test_format_status_with_secondary_rate() {
+        let mut st = get_test_status();
+        st.timecode_delta_ms = 80;
+        st.timecode_delta_frames = 2;
+        st.timecode_delta_by_rate = crate::sync_logic::delta_frames_by_rate(
+            80,
+            Some(num_rational::Ratio::new(25, 1)),
+            Some(num_rational::Ratio::new(30000, 1001)),
+        );
+        let text = format_status(&st);
+        assert!(text.contains("Timecode Δ       : +80 ms (+2 frames, +2 @ 29.97fps) in sync"), "{}", text);
+    }
+
+    #[test]
+    fn test_format_status_json() {
+        let line = format_status_json(&get_test_status());
+        assert!(!line.contains('\n'));
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["sync_status"], "IN SYNC");
+        assert_eq!(value["timecode_delta_ms"], -3);
+    }
+
+    #[test]
+    fn test_offset_patch_only_includes_given_fields() {
+        let patch = offset_patch(Some(1), None, None, Some(-12), None);
+        assert_eq!(
+            patch,
+            serde_json::json!({ "timeturnerOffset": { "hours": 1, "frames": -12 } })
+        );
+    }
+
+    #[test]
+    fn test_format_offset() {
+        let offset = TimeturnerOffset {
+            hours: 1,
+            minutes: 0,
+            seconds: -5,
+            frames: 12,
+            milliseconds: 0,
+        };
+        assert_eq!(format_offset(&offset), "+1h +0m -5s +12f +0ms");
+    }
+
+    #[test]
+    fn test_status_unreachable_daemon_fails() {
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let client = ApiClient::new(&format!("http://127.0.0.1:{}", port), None);
+        assert_eq!(status(&client, true, None), 1);
+    }
+}
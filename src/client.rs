@@ -0,0 +1,352 @@
+// src/client.rs
+
+use crate::api::{ApiStatus, OffsetPreview, SyncPreview};
+use crate::audit::{AuditSource, SOURCE_HEADER};
+use crate::chrony::ChronyReport;
+use crate::config::{Config, TimeturnerOffset};
+use crate::report::SessionReport;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::io::{BufRead, BufReader};
+use std::time::Duration;
+
+/// Default address of the local daemon's API.
+pub const DEFAULT_URL: &str = "http://127.0.0.1:8080";
+
+/// The `{ status, message }` reply returned by command endpoints.
+#[derive(Deserialize, Debug)]
+pub struct ApiMessage {
+    pub status: String,
+    pub message: String,
+}
+
+/// Blocking client for the `timeturnerd` HTTP API, used by `timeturner-ctl`.
+#[derive(Clone)]
+pub struct ApiClient {
+    base_url: String,
+    token: Option<String>,
+    source: Option<AuditSource>,
+    agent: ureq::Agent,
+    /// No overall deadline, only a read timeout well above the heartbeat.
+    stream_agent: ureq::Agent,
+}
+
+impl ApiClient {
+    pub fn new(base_url: &str, token: Option<String>) -> Self {
+        let agent = ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(5))
+            .build();
+        let stream_agent = ureq::AgentBuilder::new()
+            .timeout_connect(Duration::from_secs(5))
+            .timeout_read(Duration::from_secs(5))
+            .build();
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token,
+            source: None,
+            agent,
+            stream_agent,
+        }
+    }
+
+    /// Identify this client in the daemon's audit trail.
+    pub fn with_source(mut self, source: AuditSource) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn request(&self, method: &str, path: &str) -> ureq::Request {
+        self.request_on(&self.agent, method, path)
+    }
+
+    fn request_on(&self, agent: &ureq::Agent, method: &str, path: &str) -> ureq::Request {
+        let mut req = agent.request(method, &format!("{}{}", self.base_url, path));
+        if let Some(source) = self.source {
+            req = req.set(SOURCE_HEADER, source.as_str());
+        }
+        match &self.token {
+            Some(token) => req.set("Authorization", &format!("Bearer {}", token)),
+            None => req,
+        }
+    }
+
+    /// Turn a ureq result into the decoded body, preferring the API's own
+    /// error message over the bare HTTP status when one is available.
+    fn decode<T: DeserializeOwned>(
+        result: Result<ureq::Response, ureq::Error>,
+    ) -> Result<T, String> {
+        match result {
+            Ok(resp) => resp.into_json().map_err(|e| format!("Invalid response: {}", e)),
+            Err(e) => Err(Self::error_message(e)),
+        }
+    }
+
+    fn error_message(error: ureq::Error) -> String {
+        match error {
+            ureq::Error::Status(code, resp) => match resp.into_json::<ApiMessage>() {
+                Ok(msg) => msg.message,
+                Err(_) => format!("HTTP {}", code),
+            },
+            e => format!("Cannot reach timeturnerd: {}", e),
+        }
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, String> {
+        Self::decode(self.request("GET", path).call())
+    }
+
+    pub fn send<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> Result<T, String> {
+        let req = self.request(method, path);
+        Self::decode(match body {
+            Some(body) => req.send_json(body),
+            None => req.call(),
+        })
+    }
+
+    pub fn status(&self) -> Result<ApiStatus, String> {
+        self.get("/api/status")
+    }
+
+    /// Follow `/api/status/stream`: yields a status each time the daemon
+    /// pushes one, and ends (after an `Err`) when the connection drops.
+    pub fn status_stream(&self) -> Result<impl Iterator<Item = Result<ApiStatus, String>>, String> {
+        let resp = self
+            .request_on(&self.stream_agent, "GET", "/api/status/stream")
+            .call()
+            .map_err(Self::error_message)?;
+        let mut lines = BufReader::new(resp.into_reader()).lines();
+        let mut done = false;
+        Ok(std::iter::from_fn(move || loop {
+            if done {
+                return None;
+            }
+            match lines.next() {
+                Some(Ok(line)) => {
+                    if let Some(json) = line.strip_prefix("data: ") {
+                        return Some(serde_json::from_str(json).map_err(|e| format!("Invalid response: {}", e)));
+                    }
+                }
+                Some(Err(e)) => {
+                    done = true;
+                    return Some(Err(format!("Cannot reach timeturnerd: {}", e)));
+                }
+                None => {
+                    done = true;
+                    return Some(Err("Status stream closed".into()));
+                }
+            }
+        }))
+    }
+
+    pub fn chrony(&self) -> Result<ChronyReport, String> {
+        self.get("/api/chrony")
+    }
+
+    pub fn logs(&self) -> Result<Vec<String>, String> {
+        self.get("/api/logs")
+    }
+
+    pub fn sync(&self) -> Result<ApiMessage, String> {
+        self.send("POST", "/api/sync", None)
+    }
+
+    pub fn sync_preview(&self) -> Result<SyncPreview, String> {
+        self.get("/api/sync/preview")
+    }
+
+    /// Sync with the token from `sync_preview`, for a step over `confirmStepMs`.
+    pub fn sync_confirmed(&self, token: &str) -> Result<ApiMessage, String> {
+        self.send("POST", &format!("/api/sync?confirm=true&token={}", token), None)
+    }
+
+    /// Sync past `maxAutoStepMs`, with the token from `sync_preview` if the
+    /// step also needs confirming.
+    pub fn sync_override(&self, token: Option<&str>) -> Result<ApiMessage, String> {
+        let confirm = token.map(|token| format!("&confirm=true&token={}", token)).unwrap_or_default();
+        self.send("POST", &format!("/api/sync?override=true{}", confirm), None)
+    }
+
+    pub fn nudge(&self, microseconds: i64) -> Result<ApiMessage, String> {
+        let body = serde_json::json!({ "microseconds": microseconds });
+        self.send("POST", "/api/nudge_clock", Some(body))
+    }
+
+    /// Nudge by whole frames at the daemon's current LTC frame rate.
+    pub fn nudge_frames(&self, frames: i64) -> Result<ApiMessage, String> {
+        let body = serde_json::json!({ "frames": frames });
+        self.send("POST", "/api/nudge_clock", Some(body))
+    }
+
+    pub fn config(&self) -> Result<Config, String> {
+        self.get("/api/config")
+    }
+
+    /// Switch the timeturner offset to the preset `name`; returns the saved
+    /// config.
+    pub fn activate_preset(&self, name: &str) -> Result<Config, String> {
+        let body = serde_json::json!({ "name": name });
+        self.send("POST", "/api/presets/activate", Some(body))
+    }
+
+    /// Switch to the preset `name` with the token from `offset_preview`.
+    pub fn activate_preset_confirmed(&self, name: &str, token: &str) -> Result<Config, String> {
+        let body = serde_json::json!({ "name": name });
+        self.send("POST", &format!("/api/presets/activate?confirm=true&token={}", token), Some(body))
+    }
+
+    /// Switch to the preset `name` past `maxAutoStepMs`, with the token from
+    /// `offset_preview` if the re-sync also needs confirming.
+    pub fn activate_preset_override(&self, name: &str, token: Option<&str>) -> Result<Config, String> {
+        let body = serde_json::json!({ "name": name });
+        let confirm = token.map(|token| format!("&confirm=true&token={}", token)).unwrap_or_default();
+        self.send("POST", &format!("/api/presets/activate?override=true{}", confirm), Some(body))
+    }
+
+    /// What saving `offset` would do now, with a token if the re-sync needs
+    /// confirming.
+    pub fn offset_preview(&self, offset: &TimeturnerOffset) -> Result<OffsetPreview, String> {
+        self.get(&format!(
+            "/api/config/offset?hours={}&minutes={}&seconds={}&frames={}&milliseconds={}",
+            offset.hours, offset.minutes, offset.seconds, offset.frames, offset.milliseconds
+        ))
+    }
+
+    pub fn report(&self) -> Result<SessionReport, String> {
+        self.get("/api/report")
+    }
+
+    /// End the current session; returns its final report.
+    pub fn reset_report(&self) -> Result<SessionReport, String> {
+        self.send("POST", "/api/report/reset", None)
+    }
+
+    /// Merge `patch` into the daemon's config; returns the saved config.
+    pub fn patch_config(&self, patch: serde_json::Value) -> Result<Config, String> {
+        self.send("PATCH", "/api/config", Some(patch))
+    }
+
+    /// Merge `patch` with the token from `offset_preview`.
+    pub fn patch_config_confirmed(&self, patch: serde_json::Value, token: &str) -> Result<Config, String> {
+        self.send("PATCH", &format!("/api/config?confirm=true&token={}", token), Some(patch))
+    }
+
+    /// Merge `patch` past `maxAutoStepMs`, with the token from
+    /// `offset_preview` if the re-sync also needs confirming.
+    pub fn patch_config_override(&self, patch: serde_json::Value, token: Option<&str>) -> Result<Config, String> {
+        let confirm = token.map(|token| format!("&confirm=true&token={}", token)).unwrap_or_default();
+        self.send("PATCH", &format!("/api/config?override=true{}", confirm), Some(patch))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{get, post, App, HttpRequest, HttpResponse, HttpServer, Responder};
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[get("/api/logs")]
+    async fn logs(req: HttpRequest) -> impl Responder {
+        let auth = req
+            .headers()
+            .get("Authorization")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("none")
+            .to_string();
+        let source = req
+            .headers()
+            .get(SOURCE_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("none")
+            .to_string();
+        HttpResponse::Ok().json(vec![auth, source])
+    }
+
+    #[post("/api/sync")]
+    async fn sync() -> impl Responder {
+        HttpResponse::BadRequest().json(serde_json::json!({
+            "status": "error",
+            "message": "No LTC timecode available to sync to."
+        }))
+    }
+
+    #[get("/api/status/stream")]
+    async fn status_stream() -> impl Responder {
+        let status = r#"{"ltc_status":"LOCK","ltc_timecode":"10:20:30:04","frame_rate":"25.00fps",
+            "system_clock":"10:20:30.165","system_date":"2025-07-30","timecode_delta_ms":-3,
+            "timecode_delta_frames":0,"sync_status":"IN SYNC","jitter_status":"GOOD",
+            "lock_ratio":99.5,"ntp_active":true,"interfaces":[],"hardware_offset_ms":20,
+            "serial_port":"/dev/ttyACM0","session_timecode":"10:20:25:00","why":"in sync"}"#
+            .replace('\n', "");
+        HttpResponse::Ok()
+            .content_type("text/event-stream")
+            .body(format!("data: {}\n\n: comment\ndata: {}\n\n", status, status))
+    }
+
+    /// Serve the stub routes on an ephemeral port and return its base URL.
+    fn start_stub_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            actix_web::rt::System::new().block_on(async move {
+                HttpServer::new(|| App::new().service(logs).service(sync).service(status_stream))
+                    .workers(1)
+                    .listen(listener)
+                    .unwrap()
+                    .run()
+                    .await
+            })
+        });
+        url
+    }
+
+    #[test]
+    fn test_client_sends_token_and_decodes() {
+        let url = start_stub_server();
+        let client = ApiClient::new(&format!("{}/", url), Some("secret".to_string()))
+            .with_source(AuditSource::Tui);
+        assert_eq!(client.base_url(), url);
+        assert_eq!(
+            client.logs().unwrap(),
+            vec!["Bearer secret".to_string(), "tui".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_client_surfaces_api_error_message() {
+        let url = start_stub_server();
+        let client = ApiClient::new(&url, None);
+        assert_eq!(
+            client.sync().unwrap_err(),
+            "No LTC timecode available to sync to."
+        );
+    }
+
+    #[test]
+    fn test_client_follows_status_stream() {
+        let url = start_stub_server();
+        let client = ApiClient::new(&url, None);
+        let events: Vec<_> = client.status_stream().unwrap().collect();
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].as_ref().unwrap().ltc_timecode, "10:20:30:04");
+        assert_eq!(events[1].as_ref().unwrap().timecode_delta_ms, -3);
+        assert_eq!(events[2].as_ref().unwrap_err(), "Status stream closed");
+    }
+
+    #[test]
+    fn test_client_reports_unreachable_daemon() {
+        // Bind then drop to get a port with nothing listening.
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let client = ApiClient::new(&format!("http://127.0.0.1:{}", port), None);
+        assert!(client.status().unwrap_err().starts_with("Cannot reach timeturnerd"));
+    }
+}
@@ -0,0 +1,187 @@
+// src/clock_gate.rs
+//
+// One clock change at a time. API clock commands, auto-sync corrections and
+// the daily re-jam all pass through the gate: a change that arrives while
+// another is running is refused rather than queued, so two operators clicking
+// Sync together, or an operator racing auto-sync, can't double-step the
+// clock. API commands are also limited to one per `clockCommandIntervalMs`.
+
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{header, Method},
+    middleware::Next,
+    web, Error, HttpResponse,
+};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::api::AppState;
+
+#[derive(Default)]
+struct Times {
+    /// When the last API clock command finished.
+    last_command: Option<Instant>,
+    /// When the last clock change of any kind finished.
+    last_change: Option<Instant>,
+}
+
+#[derive(Default)]
+pub struct ClockGate {
+    busy: AtomicBool,
+    times: Mutex<Times>,
+}
+
+/// Why a clock change was not let through.
+#[derive(Debug, PartialEq)]
+pub enum Refusal {
+    /// Another change is running.
+    Busy,
+    /// The last API command was too recent; retry after this long.
+    TooSoon(Duration),
+}
+
+/// The right to change the clock, released on drop.
+pub struct ClockPermit {
+    gate: Arc<ClockGate>,
+}
+
+impl ClockPermit {
+    /// Record that the clock was changed under this permit.
+    pub fn note_change(&self, now: Instant) {
+        self.gate.times.lock().unwrap().last_change = Some(now);
+    }
+
+    /// Record a finished API command, which also starts its rate-limit window.
+    pub fn note_command(&self, now: Instant) {
+        let mut times = self.gate.times.lock().unwrap();
+        times.last_command = Some(now);
+        times.last_change = Some(now);
+    }
+}
+
+impl Drop for ClockPermit {
+    fn drop(&mut self) {
+        self.gate.busy.store(false, Ordering::Release);
+    }
+}
+
+impl ClockGate {
+    /// Enter the gate unless another change is running.
+    pub fn try_enter(self: &Arc<Self>) -> Option<ClockPermit> {
+        self.busy
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| ClockPermit { gate: self.clone() })
+    }
+
+    /// Enter the gate for an API command, at most one per `interval`.
+    pub fn try_command(self: &Arc<Self>, interval: Duration, now: Instant) -> Result<ClockPermit, Refusal> {
+        let last = self.times.lock().unwrap().last_command;
+        if let Some(wait) = last
+            .map(|at| interval.saturating_sub(now.saturating_duration_since(at)))
+            .filter(|wait| !wait.is_zero())
+        {
+            return Err(Refusal::TooSoon(wait));
+        }
+        self.try_enter().ok_or(Refusal::Busy)
+    }
+
+    /// Whether a change is running now.
+    pub fn is_busy(&self) -> bool {
+        self.busy.load(Ordering::Acquire)
+    }
+
+    /// Whether the clock was changed after `at`, making a decision taken
+    /// then stale.
+    pub fn changed_since(&self, at: Instant) -> bool {
+        self.times.lock().unwrap().last_change.is_some_and(|change| change > at)
+    }
+}
+
+/// Whether `method path` changes the clock.
+fn is_clock_command(method: &Method, path: &str) -> bool {
+    method == Method::POST && matches!(path, "/api/sync" | "/api/nudge_clock" | "/api/set_date")
+}
+
+/// Actix middleware passing clock commands through the gate. Registered
+/// inside the auth middleware, so refused credentials never use up the rate.
+pub async fn limit_clock_commands(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let data = req.app_data::<web::Data<AppState>>().cloned();
+    let Some(data) = data.filter(|_| is_clock_command(req.method(), req.path())) else {
+        return Ok(next.call(req).await?.map_into_left_body());
+    };
+    let interval = Duration::from_millis(data.config.lock().unwrap().clock_command_interval_ms);
+    let refused = match data.clock_gate.try_command(interval, Instant::now()) {
+        Ok(permit) => {
+            let res = next.call(req).await?;
+            if res.status().is_success() {
+                permit.note_command(Instant::now());
+            }
+            return Ok(res.map_into_left_body());
+        }
+        Err(Refusal::Busy) => HttpResponse::Conflict().json(serde_json::json!({
+            "status": "error",
+            "message": "Another clock change is in progress.",
+        })),
+        Err(Refusal::TooSoon(wait)) => HttpResponse::TooManyRequests()
+            .insert_header((header::RETRY_AFTER, wait.as_secs_f64().ceil().to_string()))
+            .json(serde_json::json!({
+                "status": "error",
+                "message": format!(
+                    "Clock commands are limited to one every {} ms; try again in {} ms.",
+                    interval.as_millis(),
+                    wait.as_millis()
+                ),
+            })),
+    };
+    log::warn!("⏳ Refused {} {}", req.method(), req.path());
+    Ok(req.into_response(refused).map_into_right_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_change_at_a_time() {
+        let gate = Arc::new(ClockGate::default());
+        let permit = gate.try_enter().unwrap();
+        assert!(gate.try_enter().is_none());
+        assert_eq!(
+            gate.try_command(Duration::ZERO, Instant::now()).err(),
+            Some(Refusal::Busy)
+        );
+        drop(permit);
+        assert!(gate.try_enter().is_some());
+    }
+
+    #[test]
+    fn test_commands_are_rate_limited() {
+        let gate = Arc::new(ClockGate::default());
+        let interval = Duration::from_secs(2);
+        let start = Instant::now();
+        gate.try_command(interval, start).unwrap().note_command(start);
+
+        let early = start + Duration::from_millis(500);
+        assert_eq!(
+            gate.try_command(interval, early).err(),
+            Some(Refusal::TooSoon(Duration::from_millis(1500)))
+        );
+        assert!(gate.try_command(interval, start + interval).is_ok());
+    }
+
+    #[test]
+    fn test_changes_make_earlier_decisions_stale() {
+        let gate = Arc::new(ClockGate::default());
+        let decided = Instant::now();
+        assert!(!gate.changed_since(decided));
+        gate.try_enter().unwrap().note_change(decided + Duration::from_millis(10));
+        assert!(gate.changed_since(decided));
+        assert!(!gate.changed_since(decided + Duration::from_millis(10)));
+    }
+}
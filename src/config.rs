@@ -1,4 +1,6 @@
 ﻿// src/config.rs
+use chrono::{FixedOffset, Local, NaiveDateTime, Utc};
+use num_rational::Ratio;
 use notify::{
     recommended_watcher, Event, EventKind, RecommendedWatcher, RecursiveMode, Result as NotifyResult,
     Watcher,
@@ -7,19 +9,35 @@ use serde::{Deserialize, Serialize};
 use std::{
     fs,
     fs::File,
-    io::Read,
-    path::PathBuf,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
     sync::{Arc, Mutex},
 };
+use utoipa::ToSchema;
 
-#[derive(Deserialize, Serialize, Clone, Debug, Default)]
-#[serde(rename_all = "camelCase")]
+use crate::history::HistoryConfig;
+use crate::logger::LogFilter;
+use crate::ntp_client::NtpCheckConfig;
+use crate::presets::{self, OffsetPreset};
+use crate::rejam::RejamConfig;
+use crate::phc::PhcConfig;
+use crate::sinks::SinkConfig;
+use crate::ptp_capture::PtpCaptureConfig;
+use crate::rtp_midi::RtpMidiConfig;
+use crate::serial_input::{self, LinePattern, ReaderLatency, SerialDevice};
+use crate::snmp::SnmpConfig;
+use crate::sync_logic;
+use crate::syslog::SyslogConfig;
+use crate::vitc::VitcConfig;
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase", default)]
 pub struct TimeturnerOffset {
     pub hours: i64,
     pub minutes: i64,
     pub seconds: i64,
     pub frames: i64,
-    #[serde(default)]
     pub milliseconds: i64,
 }
 
@@ -33,36 +51,651 @@ impl TimeturnerOffset {
     }
 }
 
-#[derive(Deserialize, Serialize, Clone, Debug)]
+/// What clock the incoming LTC counts, parsed from `ltcTimeZone`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LtcZone {
+    /// The host's local time zone, DST included.
+    #[default]
+    Local,
+    Utc,
+    /// A fixed offset from UTC with no DST, e.g. `+05:30`.
+    Fixed(FixedOffset),
+}
+
+impl LtcZone {
+    /// Wall-clock time now in this zone.
+    pub fn now(&self) -> NaiveDateTime {
+        match self {
+            Self::Local => Local::now().naive_local(),
+            Self::Utc => Utc::now().naive_utc(),
+            Self::Fixed(offset) => Utc::now().with_timezone(offset).naive_local(),
+        }
+    }
+
+    /// How LTC is being read right now, e.g. `local (UTC+01:00)`.
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Local => format!("local (UTC{})", Local::now().offset()),
+            Self::Utc => "UTC".to_string(),
+            Self::Fixed(offset) => format!("UTC{}", offset),
+        }
+    }
+}
+
+impl FromStr for LtcZone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "local" => Ok(Self::Local),
+            "utc" => Ok(Self::Utc),
+            other => other
+                .parse::<FixedOffset>()
+                .map(Self::Fixed)
+                .map_err(|_| format!("'{}' is not local, utc or an offset such as +01:00", s)),
+        }
+    }
+}
+
+/// Which source the discipline follows: `syncSource` in config, pinned
+/// with `PUT /api/source`.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncSource {
+    /// LTC while it is locked, holdover otherwise.
+    #[default]
+    Auto,
+    /// LTC only; no corrections while it is not locked.
+    Ltc,
+    /// Ignore LTC deltas and hold the clock's rate with the drift estimate.
+    Holdover,
+    /// No automatic corrections; only operator commands change the clock.
+    Manual,
+}
+
+/// Shell commands run when sync events occur. Each command is executed with
+/// `sh -c` and receives the event details in `TIMETURNER_*` environment variables.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HooksConfig {
+    /// Run after the system clock has been stepped to LTC.
+    pub sync_performed: Option<String>,
+    /// Run when the LTC reader goes from LOCK to FREE.
+    pub lock_lost: Option<String>,
+    /// Run when the LTC reader reaches LOCK.
+    pub lock_acquired: Option<String>,
+    /// Run when the averaged clock delta rises above `deltaThresholdMs`.
+    pub delta_exceeded: Option<String>,
+    /// Run when an NTP server disagrees with LTC by more than
+    /// `ntpCheck.alertThresholdMs`.
+    pub ntp_disagreement: Option<String>,
+    /// Run when the frames have been at a rate other than
+    /// `expectedFrameRate` for more than 25 frames.
+    pub frame_rate_mismatch: Option<String>,
+    #[serde(default = "default_hook_delta_threshold_ms")]
+    pub delta_threshold_ms: i64,
+}
+
+fn default_hook_delta_threshold_ms() -> i64 {
+    40
+}
+
+impl Default for HooksConfig {
+    fn default() -> Self {
+        Self {
+            sync_performed: None,
+            lock_lost: None,
+            lock_acquired: None,
+            delta_exceeded: None,
+            ntp_disagreement: None,
+            frame_rate_mismatch: None,
+            delta_threshold_ms: default_hook_delta_threshold_ms(),
+        }
+    }
+}
+
+/// API access level. Each role includes the permissions of the ones before it.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Viewer,
+    Operator,
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Operator => "operator",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, PartialEq)]
+pub struct ApiToken {
+    pub token: String,
+    pub role: Role,
+}
+
+/// The config is logged whenever it changes, so the token itself is left out.
+impl std::fmt::Debug for ApiToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiToken").field("token", &"<redacted>").field("role", &self.role).finish()
+    }
+}
+
+/// Where API credentials are checked.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthBackendKind {
+    /// Bearer tokens listed under `api.tokens`.
+    #[default]
+    Static,
+    /// Local accounts via PAM, sent as HTTP Basic auth. Needs the `pam` build feature.
+    Pam,
+    /// Bearer JWTs from an OpenID Connect provider, checked against its JWKS.
+    Oidc,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct PamUser {
+    pub user: String,
+    pub role: Role,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PamConfig {
+    /// PAM service name, i.e. the file under /etc/pam.d to use.
+    #[serde(default = "default_pam_service")]
+    pub service: String,
+    #[serde(default)]
+    pub users: Vec<PamUser>,
+    /// Role for accounts not listed in `users`; unset means they are refused.
+    #[serde(default)]
+    pub default_role: Option<Role>,
+}
+
+fn default_pam_service() -> String {
+    "login".to_string()
+}
+
+impl Default for PamConfig {
+    fn default() -> Self {
+        Self {
+            service: default_pam_service(),
+            users: Vec::new(),
+            default_role: None,
+        }
+    }
+}
+
+impl PamConfig {
+    /// Role for an account PAM has already authenticated.
+    pub fn role_for_user(&self, user: &str) -> Option<Role> {
+        self.users
+            .iter()
+            .find(|u| u.user == user)
+            .map(|u| u.role)
+            .or(self.default_role)
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct OidcConfig {
+    /// Expected `iss` claim.
+    pub issuer: String,
+    /// Expected `aud` claim.
+    pub audience: String,
+    /// Where the provider publishes its signing keys.
+    pub jwks_url: String,
+    /// Claim holding `viewer`, `operator` or `admin` (or a list of them).
+    #[serde(default = "default_role_claim")]
+    pub role_claim: String,
+}
+
+fn default_role_claim() -> String {
+    "timeturner_role".to_string()
+}
+
+/// How the HTTP API authenticates requests. With the static backend and no
+/// tokens configured the API is open, as it was before roles existed.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiAccessConfig {
+    #[serde(default)]
+    pub backend: AuthBackendKind,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tokens: Vec<ApiToken>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pam: Option<PamConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oidc: Option<OidcConfig>,
+}
+
+impl ApiAccessConfig {
+    pub fn is_enabled(&self) -> bool {
+        match self.backend {
+            AuthBackendKind::Static => !self.tokens.is_empty(),
+            AuthBackendKind::Pam | AuthBackendKind::Oidc => true,
+        }
+    }
+
+    pub fn role_for(&self, token: &str) -> Option<Role> {
+        self.tokens.iter().find(|t| t.token == token).map(|t| t.role)
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Config {
+    /// Every field has a default, so a file from an older release (or one
+    /// holding only the keys someone changed) loads with the rest filled in.
+    #[serde(default)]
     pub hardware_offset_ms: i64,
+    /// The serial reader's latency, taken off each frame's arrival stamp.
+    /// Read at startup. See `serial_input::ReaderLatency`.
+    #[serde(default)]
+    pub reader_latency: ReaderLatency,
+    /// Which USB serial device is the reader, and its settings. Read at
+    /// startup. See `serial_input::choose_reader`.
+    #[serde(default)]
+    pub serial_devices: Vec<SerialDevice>,
+    /// How the reader's lines are parsed, for firmware that prints them
+    /// differently; `null` for the standard format. Read at startup. See
+    /// `serial_input::LinePattern`.
+    #[serde(default)]
+    pub ltc_line_pattern: Option<String>,
     #[serde(default)]
     pub timeturner_offset: TimeturnerOffset,
+    /// Named offsets to switch `timeturnerOffset` to; see `presets`.
+    #[serde(default)]
+    pub offset_presets: Vec<OffsetPreset>,
+    /// Bring the clock to a changed `timeturnerOffset` by running it fast or
+    /// slow for this many seconds instead of stepping it; 0 steps. See `ramp`.
+    #[serde(default)]
+    pub offset_ramp_secs: u64,
+    /// What clock the LTC counts: `local`, `utc` or a fixed offset such as
+    /// `+01:00`. See `LtcZone`.
+    #[serde(default = "default_ltc_time_zone")]
+    pub ltc_time_zone: String,
+    /// A second rate, such as `29.97` next to 25 fps LTC, to also show the
+    /// clock delta in frames at. Display only.
+    #[serde(default)]
+    pub secondary_frame_rate: Option<String>,
+    /// The rate the LTC should run at, e.g. `25`. Frames at another rate
+    /// for long are alerted on and flagged in the status. Unset, any rate
+    /// is taken as it comes.
+    #[serde(default)]
+    pub expected_frame_rate: Option<String>,
     #[serde(default = "default_nudge_ms")]
     pub default_nudge_ms: i64,
+    /// `/api/nudge_clock` refuses slews outside `nudgeMinUs..=nudgeMaxUs`.
+    #[serde(default = "default_nudge_min_us")]
+    pub nudge_min_us: i64,
+    #[serde(default = "default_nudge_max_us")]
+    pub nudge_max_us: i64,
     #[serde(default)]
     pub auto_sync_enabled: bool,
+    /// What auto-sync follows; `auto` unless pinned for troubleshooting.
+    #[serde(default)]
+    pub sync_source: SyncSource,
+    /// Auto-sync steps the clock when the delta exceeds this, and slews it otherwise.
+    #[serde(default = "default_step_threshold_ms")]
+    pub step_threshold_ms: i64,
+    /// Auto-sync and scheduled steps only step once this many consecutive
+    /// LOCK frames have followed on from each other at the same rate.
+    #[serde(default = "default_step_consistent_frames")]
+    pub step_consistent_frames: u32,
+    /// Auto-sync and re-jams never step the clock further than this many
+    /// milliseconds; a manual sync or an offset change through the API
+    /// needs `override` to. 0, the default, turns the limit off so a board
+    /// without an RTC can be corrected on its first lock.
+    #[serde(default)]
+    pub max_auto_step_ms: u64,
+    /// Steps wait for the next LTC frame 00 and set the clock to that whole
+    /// second as it arrives. See `system::step_to_ltc`.
+    #[serde(default)]
+    pub sync_on_second: bool,
+    /// The clock is IN SYNC while its delta (system clock minus LTC) is
+    /// between `inSyncBehindMs` and `inSyncAheadMs`.
+    #[serde(default = "default_in_sync_ahead_ms")]
+    pub in_sync_ahead_ms: i64,
+    #[serde(default = "default_in_sync_behind_ms")]
+    pub in_sync_behind_ms: i64,
+    /// Copy the system time to the RTC with `hwclock` once in sync. See `rtc`.
+    #[serde(default)]
+    pub rtc_writeback: bool,
+    /// Stop and disable systemd-timesyncd, ntpd and the like while
+    /// auto-sync is on. See `time_daemons`.
+    #[serde(default)]
+    pub stop_competing_time_daemons: bool,
+    /// Jitter samples (one per LOCK frame) behind the jitter statistics.
+    #[serde(default = "default_jitter_window")]
+    pub jitter_window: usize,
+    /// API clock commands (sync, nudge, set date) are limited to one per
+    /// this many milliseconds; 0 turns the limit off.
+    #[serde(default = "default_clock_command_interval_ms")]
+    pub clock_command_interval_ms: u64,
+    /// Manual syncs that would step the clock by more than this many
+    /// milliseconds need a confirmed preview; 0 turns confirmation off.
+    /// See `confirm`.
+    #[serde(default = "default_confirm_step_ms")]
+    pub confirm_step_ms: u64,
+    /// `/api/ready` fails once no frame has arrived for this many seconds.
+    #[serde(default = "default_ready_frame_timeout_secs")]
+    pub ready_frame_timeout_secs: u64,
+    /// LTC shows `NO SIGNAL`, and auto-sync falls back to holdover, once no
+    /// frame has arrived for this many milliseconds.
+    #[serde(default = "default_signal_timeout_ms")]
+    pub signal_timeout_ms: u64,
+    /// What the daemon logs: a level, optionally followed by per-module
+    /// overrides such as `info,ntp_client=warn`. See `logger::LogFilter`.
+    #[serde(default = "default_log_level")]
+    pub log_level: String,
+    /// Hooks run shell commands, so like the tokens they are only read from
+    /// config.yml and never returned or changed by the API.
+    #[serde(default, skip_serializing)]
+    #[schema(ignore)]
+    pub hooks: HooksConfig,
+    #[serde(default)]
+    pub history: HistoryConfig,
+    #[serde(default)]
+    pub rejam: RejamConfig,
+    /// Set while on air; scheduled re-jams are skipped.
+    #[serde(default)]
+    pub on_air: bool,
+    #[serde(default)]
+    pub ntp_check: NtpCheckConfig,
+    #[serde(default)]
+    pub syslog: SyslogConfig,
+    #[serde(default)]
+    pub snmp: SnmpConfig,
+    #[serde(default)]
+    pub vitc: VitcConfig,
+    #[serde(default)]
+    pub rtp_midi: RtpMidiConfig,
+    #[serde(default)]
+    pub phc: PhcConfig,
+    /// Where the reference is delivered besides the system clock. See `sinks`.
+    #[serde(default)]
+    pub sinks: Vec<SinkConfig>,
+    #[serde(default)]
+    pub ptp_capture: PtpCaptureConfig,
+    /// Tokens are only read from config.yml and never returned by the API.
+    #[serde(default, skip_serializing)]
+    #[schema(ignore)]
+    pub api: ApiAccessConfig,
+    /// The schema version of the file; see `migrate`. Always
+    /// `CONFIG_VERSION` once loaded.
+    #[serde(default = "current_config_version")]
+    pub config_version: u32,
+}
+
+/// The `configVersion` this build reads and writes. Bump it, with an entry
+/// in `MIGRATIONS`, whenever a key is renamed, moved or changes meaning.
+pub const CONFIG_VERSION: u32 = 1;
+
+/// `MIGRATIONS[n]` upgrades a version `n` file to version `n + 1`.
+const MIGRATIONS: &[fn(&mut serde_yaml::Mapping)] = &[from_unversioned];
+
+/// Files written before `configVersion` existed use today's keys.
+fn from_unversioned(_: &mut serde_yaml::Mapping) {}
+
+fn current_config_version() -> u32 {
+    CONFIG_VERSION
+}
+
+/// Upgrade a parsed config file to `CONFIG_VERSION` in place and return the
+/// version it was written as; a file without `configVersion` is version 0.
+/// Files from a newer release are left alone.
+pub fn migrate(value: &mut serde_yaml::Value) -> Result<u32, String> {
+    if value.is_null() {
+        *value = serde_yaml::Value::Mapping(Default::default());
+    }
+    let map = value.as_mapping_mut().ok_or("config is not a YAML mapping")?;
+    let from = match map.get("configVersion") {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or("configVersion must be a whole number")?,
+    };
+    if from >= CONFIG_VERSION {
+        return Ok(from);
+    }
+    for step in &MIGRATIONS[from as usize..] {
+        step(map);
+    }
+    map.insert("configVersion".into(), CONFIG_VERSION.into());
+    Ok(from)
+}
+
+/// Parse `contents` as a config of any version, migrated to this one.
+fn parse(contents: &str) -> Result<(Config, u32), String> {
+    let mut value: serde_yaml::Value = serde_yaml::from_str(contents).map_err(|e| e.to_string())?;
+    let from = migrate(&mut value)?;
+    let config = serde_yaml::from_value(value).map_err(|e| e.to_string())?;
+    Ok((config, from))
+}
+
+/// Rewrite an older config file at `path` in the current format, after
+/// copying it to `<path>.v<version>.bak`. Returns the backup, or `None` if
+/// the file needed no upgrade.
+pub fn upgrade_config_file(path: &Path) -> Result<Option<PathBuf>, String> {
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let (config, from) = parse(&contents)?;
+    if from > CONFIG_VERSION {
+        return Err(format!(
+            "configVersion {} is newer than this release understands ({})",
+            from, CONFIG_VERSION
+        ));
+    }
+    if from == CONFIG_VERSION {
+        return Ok(None);
+    }
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".v{}.bak", from));
+    let backup = PathBuf::from(backup);
+    fs::copy(path, &backup).map_err(|e| format!("cannot back up to {}: {}", backup.display(), e))?;
+    let path = path.to_str().ok_or("config path is not valid UTF-8")?;
+    save_config(path, &config).map_err(|e| e.to_string())?;
+    Ok(Some(backup))
+}
+
+/// A day; longer ramps are more likely a typo than a plan.
+const MAX_OFFSET_RAMP_SECS: u64 = 86_400;
+
+fn default_ltc_time_zone() -> String {
+    "local".to_string()
 }
 
 fn default_nudge_ms() -> i64 {
     2 // Default nudge is 2ms
 }
 
+fn default_nudge_min_us() -> i64 {
+    -500_000
+}
+
+fn default_nudge_max_us() -> i64 {
+    500_000
+}
+
+fn default_step_threshold_ms() -> i64 {
+    40
+}
+
+fn default_step_consistent_frames() -> u32 {
+    25
+}
+
+fn default_in_sync_ahead_ms() -> i64 {
+    8
+}
+
+fn default_in_sync_behind_ms() -> i64 {
+    -8
+}
+
+fn default_jitter_window() -> usize {
+    20
+}
+
+fn default_clock_command_interval_ms() -> u64 {
+    1000
+}
+
+fn default_confirm_step_ms() -> u64 {
+    1000
+}
+
+fn default_ready_frame_timeout_secs() -> u64 {
+    5
+}
+
+fn default_signal_timeout_ms() -> u64 {
+    1000
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
 impl Config {
-    pub fn load(path: &PathBuf) -> Self {
-        let mut file = match File::open(path) {
-            Ok(f) => f,
-            Err(_) => return Self::default(),
-        };
+    /// Read and validate the config at `path`. There is no fallback to the
+    /// defaults: they have no `api` section, so falling back would turn
+    /// authentication off.
+    pub fn load(path: &PathBuf) -> Result<Self, String> {
         let mut contents = String::new();
-        if file.read_to_string(&mut contents).is_err() {
-            return Self::default();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut contents))
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        let (config, _) = parse(&contents).map_err(|e| format!("cannot parse {}: {}", path.display(), e))?;
+        config.validate().map_err(|e| format!("invalid {}: {}", path.display(), e))?;
+        Ok(config)
+    }
+
+    /// The parsed `ltcTimeZone`; local time if it does not parse, which
+    /// `validate` refuses.
+    pub fn ltc_zone(&self) -> LtcZone {
+        self.ltc_time_zone.parse().unwrap_or_default()
+    }
+
+    /// The parsed `secondaryFrameRate`; `None` if unset or, which `validate`
+    /// refuses, if it does not parse.
+    pub fn secondary_rate(&self) -> Option<Ratio<i64>> {
+        self.secondary_frame_rate.as_deref().and_then(|s| sync_logic::parse_frame_rate(s).ok())
+    }
+
+    /// Whether a step after only `consistent_frames` consistent LOCK frames
+    /// has to wait for more, per `stepConsistentFrames`.
+    pub fn step_too_early(&self, consistent_frames: u32) -> bool {
+        consistent_frames < self.step_consistent_frames
+    }
+
+    /// Whether a step of `step_ms` is over `maxAutoStepMs`.
+    pub fn step_over_limit(&self, step_ms: i64) -> bool {
+        self.max_auto_step_ms > 0 && step_ms.unsigned_abs() > self.max_auto_step_ms
+    }
+
+    /// The parsed `expectedFrameRate`, like `secondary_rate`.
+    pub fn expected_rate(&self) -> Option<Ratio<i64>> {
+        self.expected_frame_rate.as_deref().and_then(|s| sync_logic::parse_frame_rate(s).ok())
+    }
+
+    /// Check values that deserialize fine but make no sense at runtime.
+    pub fn validate(&self) -> Result<(), String> {
+        self.ltc_time_zone
+            .parse::<LtcZone>()
+            .map_err(|e| format!("ltcTimeZone: {}", e))?;
+        if let Some(rate) = &self.secondary_frame_rate {
+            sync_logic::parse_frame_rate(rate).map_err(|e| format!("secondaryFrameRate: {}", e))?;
         }
-        serde_yaml::from_str(&contents).unwrap_or_else(|e| {
-            log::warn!("Failed to parse config, using default: {}", e);
-            Self::default()
-        })
+        if let Some(rate) = &self.expected_frame_rate {
+            sync_logic::parse_frame_rate(rate).map_err(|e| format!("expectedFrameRate: {}", e))?;
+        }
+        if self.default_nudge_ms <= 0 {
+            return Err("defaultNudgeMs must be greater than zero".into());
+        }
+        if self.nudge_min_us > 0 || self.nudge_max_us < 0 {
+            return Err("nudgeMinUs must not be positive and nudgeMaxUs must not be negative".into());
+        }
+        if self.in_sync_ahead_ms < 0 || self.in_sync_behind_ms > 0 {
+            return Err("inSyncAheadMs must not be negative and inSyncBehindMs must not be positive".into());
+        }
+        if self.step_threshold_ms <= 0 {
+            return Err("stepThresholdMs must be greater than zero".into());
+        }
+        if !(1..=1500).contains(&self.step_consistent_frames) {
+            return Err("stepConsistentFrames must be between 1 and 1500".into());
+        }
+        if self.max_auto_step_ms > 0 && self.max_auto_step_ms <= self.step_threshold_ms as u64 {
+            return Err("maxAutoStepMs must be 0 or larger than stepThresholdMs".into());
+        }
+        if self.jitter_window == 0 {
+            return Err("jitterWindow must be greater than zero".into());
+        }
+        if self.config_version != CONFIG_VERSION {
+            return Err(format!("configVersion must be {}; it is set by timeturnerd", CONFIG_VERSION));
+        }
+        if self.ready_frame_timeout_secs == 0 {
+            return Err("readyFrameTimeoutSecs must be greater than zero".into());
+        }
+        if !(100..=60_000).contains(&self.signal_timeout_ms) {
+            return Err("signalTimeoutMs must be between 100 and 60000".into());
+        }
+        self.log_level
+            .parse::<LogFilter>()
+            .map_err(|e| format!("logLevel: {}", e))?;
+        if self.hooks.delta_threshold_ms < 0 {
+            return Err("hooks.deltaThresholdMs must not be negative".into());
+        }
+        self.rejam.time()?;
+        presets::validate(&self.offset_presets)?;
+        serial_input::validate_devices(&self.serial_devices)?;
+        LinePattern::from_config(self.ltc_line_pattern.as_deref())?;
+        if self.offset_ramp_secs > MAX_OFFSET_RAMP_SECS {
+            return Err(format!("offsetRampSecs must be at most {}", MAX_OFFSET_RAMP_SECS));
+        }
+        if self.ntp_check.interval_secs == 0 {
+            return Err("ntpCheck.intervalSecs must be greater than zero".into());
+        }
+        if self.ntp_check.alert_threshold_ms <= 0 {
+            return Err("ntpCheck.alertThresholdMs must be greater than zero".into());
+        }
+        if self.ntp_check.dscp > 63 {
+            return Err("ntpCheck.dscp must be between 0 and 63".into());
+        }
+        self.syslog.validate()?;
+        self.snmp.validate()?;
+        self.rtp_midi.validate()?;
+        self.phc.validate()?;
+        for sink in &self.sinks {
+            sink.validate()?;
+        }
+        self.ptp_capture.validate()?;
+        Ok(())
+    }
+
+    /// Apply a JSON merge patch (RFC 7386) to this config and return the
+    /// validated result. Keys that are not part of the schema are rejected
+    /// rather than silently ignored.
+    pub fn patched(&self, patch: &serde_json::Value) -> Result<Self, String> {
+        if let Some(key) = FILE_ONLY_KEYS.iter().find(|key| patch.get(**key).is_some()) {
+            return Err(format!("'{}' can only be changed in config.yml", key));
+        }
+        let mut merged = serde_json::to_value(self).map_err(|e| e.to_string())?;
+        if let Some(key) = unknown_key(&merged, patch, "") {
+            return Err(format!("Unknown config field '{}'", key));
+        }
+        merge_patch(&mut merged, patch);
+        let config: Self = serde_json::from_value(merged).map_err(|e| e.to_string())?;
+        config.validate()?;
+        Ok(config)
     }
 
 }
@@ -71,62 +704,435 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             hardware_offset_ms: 0,
+            reader_latency: ReaderLatency::default(),
+            serial_devices: Vec::new(),
+            ltc_line_pattern: None,
             timeturner_offset: TimeturnerOffset::default(),
+            offset_presets: Vec::new(),
+            offset_ramp_secs: 0,
+            ltc_time_zone: default_ltc_time_zone(),
+            secondary_frame_rate: None,
+            expected_frame_rate: None,
             default_nudge_ms: default_nudge_ms(),
+            nudge_min_us: default_nudge_min_us(),
+            nudge_max_us: default_nudge_max_us(),
             auto_sync_enabled: false,
+            sync_source: SyncSource::Auto,
+            step_threshold_ms: default_step_threshold_ms(),
+            step_consistent_frames: default_step_consistent_frames(),
+            max_auto_step_ms: 0,
+            sync_on_second: false,
+            in_sync_ahead_ms: default_in_sync_ahead_ms(),
+            in_sync_behind_ms: default_in_sync_behind_ms(),
+            rtc_writeback: false,
+            stop_competing_time_daemons: false,
+            jitter_window: default_jitter_window(),
+            clock_command_interval_ms: default_clock_command_interval_ms(),
+            confirm_step_ms: default_confirm_step_ms(),
+            ready_frame_timeout_secs: default_ready_frame_timeout_secs(),
+            signal_timeout_ms: default_signal_timeout_ms(),
+            log_level: default_log_level(),
+            hooks: HooksConfig::default(),
+            history: HistoryConfig::default(),
+            rejam: RejamConfig::default(),
+            on_air: false,
+            ntp_check: NtpCheckConfig::default(),
+            syslog: SyslogConfig::default(),
+            snmp: SnmpConfig::default(),
+            vitc: VitcConfig::default(),
+            rtp_midi: RtpMidiConfig::default(),
+            phc: PhcConfig::default(),
+            sinks: Vec::new(),
+            ptp_capture: PtpCaptureConfig::default(),
+            api: ApiAccessConfig::default(),
+            config_version: CONFIG_VERSION,
+        }
+    }
+}
+
+/// Sections left out of the API's view of the config, which only config.yml
+/// sets: the tokens, and the hooks, which run shell commands.
+const FILE_ONLY_KEYS: &[&str] = &["api", "hooks"];
+
+/// Find the first key in `patch` that has no counterpart in `base`.
+fn unknown_key(base: &serde_json::Value, patch: &serde_json::Value, prefix: &str) -> Option<String> {
+    let (Some(base), Some(patch)) = (base.as_object(), patch.as_object()) else {
+        return None;
+    };
+    for (key, value) in patch {
+        let path = format!("{}{}", prefix, key);
+        match base.get(key) {
+            None => return Some(path),
+            Some(base_value) => {
+                if let Some(found) = unknown_key(base_value, value, &format!("{}.", path)) {
+                    return Some(found);
+                }
+            }
+        }
+    }
+    None
+}
+
+fn merge_patch(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    let Some(patch_map) = patch.as_object() else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = serde_json::Value::Object(serde_json::Map::new());
+    }
+    let target_map = target.as_object_mut().unwrap();
+    for (key, value) in patch_map {
+        if value.is_null() {
+            target_map.remove(key);
+        } else {
+            merge_patch(
+                target_map.entry(key.clone()).or_insert(serde_json::Value::Null),
+                value,
+            );
+        }
+    }
+}
+
+/// Comment written above each top-level key by `save_config`, in file order.
+/// Keys not listed here are still saved, after these and without a comment,
+/// so the file always carries every field serde knows about.
+const CONFIG_TEMPLATE: &[(&str, &str)] = &[
+    (
+        "hardwareOffsetMs",
+        "Hardware offset in milliseconds for correcting capture latency.",
+    ),
+    (
+        "readerLatency",
+        "Latency of the serial LTC reader, taken off each line's arrival time:\n\
+         fixedUs is the reader's own delay in microseconds, and perCharacter also\n\
+         counts the time to send the line at the port's baud rate. Read at startup.",
+    ),
+    (
+        "serialDevices",
+        "USB serial devices that may be the LTC reader, matched by hex vid/pid\n\
+         and/or serialNumber whatever port they are plugged into, each with its own\n\
+         baudRate and optional readerLatency. The first one connected is used. When\n\
+         empty, a Teensy or Arduino is preferred over other USB serial devices.",
+    ),
+    (
+        "ltcLinePattern",
+        "Regex for the reader's lines, for firmware that prints them differently.\n\
+         It must name the groups status (LOCK or FREE), hours, minutes, seconds,\n\
+         frames and fps, and may name sep (; for drop-frame). Unset, the standard\n\
+         [LOCK] 10:20:30:00 | 25.00fps format is read. Read at startup.",
+    ),
+    (
+        "ltcTimeZone",
+        "What clock the incoming LTC counts: local (the host's time zone, with DST),\n\
+         utc, or a fixed offset from UTC such as +01:00 or -05:00.",
+    ),
+    (
+        "secondaryFrameRate",
+        "A second frame rate to also show the clock delta in frames at, for mixed\n\
+         setups such as 25 fps LTC feeding 29.97 equipment: 23.98, 24, 25, 29.97,\n\
+         30, 50, 59.94 or 60. Leave empty to show the incoming rate only.",
+    ),
+    (
+        "expectedFrameRate",
+        "The frame rate the LTC should run at. If frames arrive at another rate\n\
+         for more than 25 frames, an alert is raised (the frameRateMismatch hook)\n\
+         and the status says so. Leave empty to accept any rate.",
+    ),
+    (
+        "autoSyncEnabled",
+        "Enable automatic clock synchronization.\n\
+         When enabled, the system corrects the clock once LTC first locks, then\n\
+         periodically nudges it to keep it aligned with the LTC source.",
+    ),
+    (
+        "syncSource",
+        "What auto-sync follows: auto (LTC while locked, holdover otherwise), ltc\n\
+         (LTC only), holdover (the drift estimate only) or manual (no automatic\n\
+         corrections). Also set with PUT /api/source.",
+    ),
+    (
+        "stepThresholdMs",
+        "Auto-sync steps the clock with `date` when the delta is larger than this\n\
+         (in milliseconds), and slews it with `adjtimex` when it is smaller.",
+    ),
+    (
+        "stepConsistentFrames",
+        "Auto-sync, the daily re-jam and scheduled presets only step the clock\n\
+         once this many consecutive frames have been LOCK, at the same frame rate,\n\
+         each following on from the last, so a single corrupted line can never\n\
+         trigger a step to a bogus time.",
+    ),
+    (
+        "maxAutoStepMs",
+        "Auto-sync and the daily re-jam never step the clock by more than this\n\
+         many milliseconds; a larger step is held until an operator syncs with\n\
+         the override (POST /api/sync?override=true, or s twice in the TUI).\n\
+         Offset changes through the API need override=true past it too.\n\
+         0 turns the limit off, the default, so a board without an RTC can be\n\
+         corrected on the first lock; once it keeps time between restarts, a\n\
+         few seconds (e.g. 2000) stops a bad source jumping the clock.",
+    ),
+    (
+        "syncOnSecond",
+        "Hold every step until the next LTC frame 00 and set the clock to that\n\
+         whole second the moment it arrives, rather than to the latest frame,\n\
+         which may already be a frame or more old.",
+    ),
+    (
+        "inSyncAheadMs",
+        "Largest clock delta (system clock minus LTC, positive when ahead), in\n\
+         milliseconds, still reported as IN SYNC.",
+    ),
+    (
+        "inSyncBehindMs",
+        "Most negative clock delta, in milliseconds, still reported as IN SYNC.",
+    ),
+    (
+        "rtcWriteback",
+        "Copy the system time to the RTC (/dev/rtc0, e.g. a DS3231 hat) with\n\
+         hwclock once auto-sync has it in sync with LTC, so a reboot without LTC\n\
+         starts close to the right time.",
+    ),
+    (
+        "stopCompetingTimeDaemons",
+        "Stop and disable systemd-timesyncd, ntpd and other services that\n\
+         discipline the clock while auto-sync is on, so they do not fight it.\n\
+         Conflicts are shown in the status either way.",
+    ),
+    ("defaultNudgeMs", "Default nudge in milliseconds for adjtimex control."),
+    (
+        "nudgeMinUs",
+        "Most negative nudge accepted by /api/nudge_clock, in microseconds.",
+    ),
+    ("nudgeMaxUs", "Largest nudge accepted by /api/nudge_clock, in microseconds."),
+    (
+        "jitterWindow",
+        "Number of jitter samples (one per LOCK frame) behind the jitter statistics.",
+    ),
+    (
+        "clockCommandIntervalMs",
+        "API clock commands (sync, nudge, set date) are limited to one per this\n\
+         many milliseconds, and refused while another clock change is running.\n\
+         0 turns the limit off.",
+    ),
+    (
+        "confirmStepMs",
+        "A manual sync that would step the clock by more than this many\n\
+         milliseconds must be previewed (GET /api/sync/preview) and confirmed\n\
+         with the preview's token. 0 turns confirmation off.",
+    ),
+    (
+        "readyFrameTimeoutSecs",
+        "/api/ready reports not ready once no LTC frame has arrived for this many\n\
+         seconds.",
+    ),
+    (
+        "signalTimeoutMs",
+        "LTC status becomes NO SIGNAL, and auto-sync falls back to holdover, once\n\
+         no frame has arrived for this many milliseconds.",
+    ),
+    (
+        "logLevel",
+        "Log level (off, error, warn, info, debug or trace), optionally followed by\n\
+         per-module levels, e.g. \"info,ntp_client=warn,processor=debug\".",
+    ),
+    (
+        "timeturnerOffset",
+        "Time-turning offsets. All values are added to the incoming LTC time.\n\
+         These can be positive or negative.",
+    ),
+    (
+        "offsetPresets",
+        "Named offsets that timeturnerOffset can be switched to from the API\n\
+         (/api/presets) or the TUI ([P]), or daily at a local time `at` (HH:MM), e.g.\n\
+         \x20 - name: rehearsal\n\
+         \x20   offset: { hours: -1, minutes: 0, seconds: 0, frames: 0 }\n\
+         \x20   at: \"18:00\"",
+    ),
+    (
+        "offsetRampSecs",
+        "Seconds over which a changed timeturnerOffset is ramped in with adjtimex,\n\
+         running the clock up to 10% fast or slow, so NTP clients downstream are\n\
+         not stepped. 0 steps the clock at once, as does a change too large to ramp.",
+    ),
+    (
+        "hooks",
+        "Event hooks. Each command runs via `sh -c` with TIMETURNER_* environment\n\
+         variables describing the event. Only set here; the API cannot change them.",
+    ),
+    (
+        "history",
+        "How long the clock delta history behind /api/history is kept, in seconds,\n\
+         at 1 s, 10 s and 1 min resolution. 0 disables a resolution.",
+    ),
+    (
+        "rejam",
+        "Daily hard re-jam: step the clock to LTC at this local time (HH:MM,\n\
+         system time zone) every day, unless on air or LTC is not locked.",
+    ),
+    ("onAir", "Set while on air; scheduled re-jams are skipped."),
+    (
+        "ntpCheck",
+        "Cross-check against NTP servers (SNTP). Never steers the clock; a\n\
+         disagreement with LTC above alertThresholdMs is logged and fires the\n\
+         ntpDisagreement hook. No servers disables it. dscp marks the queries\n\
+         (46 = EF); 0 leaves them best effort.",
+    ),
+    (
+        "syslog",
+        "Forward log records to a remote syslog collector (RFC 5424) over udp or\n\
+         tcp. server is host or host:port (default 514); no server disables it.",
+    ),
+    (
+        "snmp",
+        "Read-only SNMP v1/v2c agent for the NMS, serving docs/TIMETURNER-MIB.txt\n\
+         and the system group. listen is address:port (161 needs root or\n\
+         CAP_NET_BIND_SERVICE); no listen disables it.",
+    ),
+    (
+        "vitc",
+        "Read VITC from a V4L2 raw VBI device (e.g. /dev/vbi0) instead of the\n\
+         serial LTC reader; standard is \"625\" (25 fps) or \"525\" (29.97 fps).\n\
+         Needs v4l2-ctl (v4l-utils). Read at startup.",
+    ),
+    (
+        "rtpMidi",
+        "Send MTC to network MIDI sessions (RTP-MIDI / AppleMIDI), e.g. QLab via\n\
+         Audio MIDI Setup. listen is the control address:port; data uses the\n\
+         next port. No listen disables it.",
+    ),
+    (
+        "phc",
+        "Steer a NIC's PTP hardware clock (e.g. /dev/ptp0) to the system clock\n\
+         with phc_ctl (linuxptp), so ptp4l as master serves LTC time. The PHC runs\n\
+         on TAI: utcOffsetSecs is TAI - UTC. No device disables it.",
+    ),
+    (
+        "sinks",
+        "Deliver the LTC reference once a second to each sink listed: chrony's\n\
+         SOCK refclock (type: chrony, path from `refclock SOCK` in chrony.conf),\n\
+         a PTP hardware clock (type: phc, device, utcOffsetSecs) or a CSV file\n\
+         (type: csv, path). Health is at /api/sinks.",
+    ),
+    (
+        "ptpCapture",
+        "Log the PTP messages seen on the network (announce, sync, follow-up,\n\
+         delay) at /api/ptp/messages. Listens only; interface is the IPv4 address\n\
+         of the NIC on the PTP network. Ports 319/320 need root. /api/ptp/masters\n\
+         shows the master the BMCA selects on domain; grandmaster forces the pick.",
+    ),
+    (
+        "api",
+        "API authentication: backend (static, pam or oidc) and its settings.\n\
+         Roles are viewer, operator and admin.",
+    ),
+    (
+        "configVersion",
+        "Schema version of this file, used to upgrade it after a release\n\
+         changes a key. Set by timeturnerd; do not edit.",
+    ),
+];
+
+/// Drop `null` entries so unset optional fields are simply absent on disk.
+fn strip_nulls(value: &mut serde_yaml::Value) {
+    if let Some(map) = value.as_mapping_mut() {
+        map.retain(|_, v| !v.is_null());
+        for (_, v) in map.iter_mut() {
+            strip_nulls(v);
+        }
+    }
+}
+
+/// Render `config` as the commented YAML written to `config.yml`.
+pub fn to_yaml(config: &Config) -> Result<String, serde_yaml::Error> {
+    let mut value = serde_yaml::to_value(config)?;
+    // Tokens and hooks are hidden from the API by `skip_serializing`, but
+    // belong on disk.
+    if let Some(map) = value.as_mapping_mut() {
+        map.insert("hooks".into(), serde_yaml::to_value(&config.hooks)?);
+        if config.api.is_enabled() {
+            map.insert("api".into(), serde_yaml::to_value(&config.api)?);
+        }
+    }
+    strip_nulls(&mut value);
+    let map = value.as_mapping().cloned().unwrap_or_default();
+
+    let templated = CONFIG_TEMPLATE.iter().map(|(key, _)| *key);
+    let extra = map
+        .keys()
+        .filter_map(|k| k.as_str())
+        .filter(|k| !CONFIG_TEMPLATE.iter().any(|(key, _)| key == k));
+    let mut out = String::new();
+    for key in templated.chain(extra) {
+        let Some(v) = map.get(key) else { continue };
+        if let Some((_, comment)) = CONFIG_TEMPLATE.iter().find(|(k, _)| *k == key) {
+            for line in comment.lines() {
+                out.push_str(&format!("# {}\n", line));
+            }
         }
+        let mut entry = serde_yaml::Mapping::new();
+        entry.insert(key.into(), v.clone());
+        out.push_str(&serde_yaml::to_string(&entry)?);
+        out.push('\n');
     }
+    Ok(out.trim_end().to_string() + "\n")
 }
 
+/// Write `config` to `path` through a temporary file in the same directory,
+/// renamed over it once complete, so a crash mid-write never leaves a
+/// truncated config.yml.
 pub fn save_config(path: &str, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
-    let mut s = String::new();
-    s.push_str("# Hardware offset in milliseconds for correcting capture latency.\n");
-    s.push_str(&format!("hardwareOffsetMs: {}\n\n", config.hardware_offset_ms));
-
-    s.push_str("# Enable automatic clock synchronization.\n");
-    s.push_str("# When enabled, the system will perform an initial full sync, then periodically\n");
-    s.push_str("# nudge the clock to keep it aligned with the LTC source.\n");
-    s.push_str(&format!("autoSyncEnabled: {}\n\n", config.auto_sync_enabled));
-
-    s.push_str("# Default nudge in milliseconds for adjtimex control.\n");
-    s.push_str(&format!("defaultNudgeMs: {}\n\n", config.default_nudge_ms));
-
-    s.push_str("# Time-turning offsets. All values are added to the incoming LTC time.\n");
-    s.push_str("# These can be positive or negative.\n");
-    s.push_str("timeturnerOffset:\n");
-    s.push_str(&format!("  hours: {}\n", config.timeturner_offset.hours));
-    s.push_str(&format!("  minutes: {}\n", config.timeturner_offset.minutes));
-    s.push_str(&format!("  seconds: {}\n", config.timeturner_offset.seconds));
-    s.push_str(&format!("  frames: {}\n", config.timeturner_offset.frames));
-    s.push_str(&format!("  milliseconds: {}\n", config.timeturner_offset.milliseconds));
-
-    fs::write(path, s)?;
+    let tmp = format!("{}.tmp", path);
+    let mut file = File::create(&tmp)?;
+    file.write_all(to_yaml(config)?.as_bytes())?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)?;
     Ok(())
 }
 
-pub fn watch_config(path: &str) -> Arc<Mutex<Config>> {
-    let initial_config = Config::load(&PathBuf::from(path));
+/// Swap in the config at `path`, or keep the current one if it cannot be
+/// loaded, e.g. after a typo in config.yml.
+fn reload(config: &Mutex<Config>, path: &PathBuf) {
+    match Config::load(path) {
+        Ok(new_cfg) => {
+            let mut cfg = config.lock().unwrap();
+            *cfg = new_cfg;
+            log::info!("🔄 Reloaded config.yml: {:?}", *cfg);
+        }
+        Err(e) => log::error!("❌ Keeping the last good config: {}", e),
+    }
+}
+
+/// Load config.yml and keep it up to date as the file changes. Fails if the
+/// file cannot be loaded at startup, since there is no good config to keep.
+pub fn watch_config(path: &str) -> Result<Arc<Mutex<Config>>, String> {
+    let initial_config = Config::load(&PathBuf::from(path))?;
     let config = Arc::new(Mutex::new(initial_config));
 
     let watch_path = PathBuf::from(path);
     let watch_path_for_cb = watch_path.clone();
     let config_for_cb = Arc::clone(&config);
 
+    // `save_config` renames a new file over config.yml, which a watch on
+    // the file itself would lose, so the directory is watched instead.
+    let watch_dir = match watch_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
     std::thread::spawn(move || {
         let mut watcher: RecommendedWatcher = recommended_watcher(move |res: NotifyResult<Event>| {
             if let Ok(evt) = res {
-                if matches!(evt.kind, EventKind::Modify(_)) {
-                    let new_cfg = Config::load(&watch_path_for_cb);
-                    let mut cfg = config_for_cb.lock().unwrap();
-                    *cfg = new_cfg;
-                    log::info!("🔄 Reloaded config.yml: {:?}", *cfg);
+                let ours = evt.paths.iter().any(|p| p.file_name() == watch_path_for_cb.file_name());
+                if ours && matches!(evt.kind, EventKind::Modify(_) | EventKind::Create(_)) {
+                    reload(&config_for_cb, &watch_path_for_cb);
                 }
             }
         })
         .expect("Failed to create file watcher");
 
         watcher
-            .watch(&watch_path, RecursiveMode::NonRecursive)
+            .watch(&watch_dir, RecursiveMode::NonRecursive)
             .expect("Failed to watch config.yml");
 
         loop {
@@ -134,5 +1140,261 @@ pub fn watch_config(path: &str) -> Arc<Mutex<Config>> {
         }
     });
 
-    config
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_yaml_round_trips_every_field() {
+        let config = Config {
+            hardware_offset_ms: 55,
+            reader_latency: ReaderLatency { fixed_us: 1500, per_character: true },
+            serial_devices: vec![SerialDevice {
+                vid: Some("16c0".into()),
+                pid: None,
+                serial_number: Some("12345".into()),
+                baud_rate: 57600,
+                reader_latency: None,
+            }],
+            ltc_line_pattern: Some(r"(?P<status>LOCK|FREE) (?P<hours>\d\d)".into()),
+            auto_sync_enabled: true,
+            step_threshold_ms: 25,
+            sync_on_second: true,
+            timeturner_offset: TimeturnerOffset {
+                hours: 1,
+                minutes: -2,
+                seconds: 3,
+                frames: 4,
+                milliseconds: 5,
+            },
+            hooks: HooksConfig {
+                lock_lost: Some("logger -t timeturner \"lost: $TIMETURNER_TIMECODE\"".into()),
+                ..HooksConfig::default()
+            },
+            api: ApiAccessConfig {
+                tokens: vec![ApiToken { token: "s3cret: #1".into(), role: Role::Operator }],
+                ..ApiAccessConfig::default()
+            },
+            ..Config::default()
+        };
+
+        let yaml = to_yaml(&config).unwrap();
+        let loaded: Config = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(serde_json::to_value(&loaded).unwrap(), serde_json::to_value(&config).unwrap());
+        assert_eq!(loaded.hooks, config.hooks);
+        assert_eq!(loaded.api.role_for("s3cret: #1"), Some(Role::Operator));
+        assert!(!format!("{:?}", loaded).contains("s3cret"), "tokens stay out of the logs");
+
+        // Unset hooks are left out rather than written as null.
+        assert!(!yaml.contains("null"));
+        assert!(!yaml.contains("syncPerformed"));
+    }
+
+    #[test]
+    fn test_to_yaml_comments_and_order() {
+        let yaml = to_yaml(&Config::default()).unwrap();
+        assert!(yaml.starts_with(
+            "# Hardware offset in milliseconds for correcting capture latency.\nhardwareOffsetMs: 0\n"
+        ));
+        let pos = |key: &str| yaml.find(&format!("\n{}:", key)).unwrap();
+        assert!(pos("autoSyncEnabled") < pos("stepThresholdMs"));
+        assert!(pos("defaultNudgeMs") < pos("timeturnerOffset"));
+        assert!(pos("timeturnerOffset") < pos("hooks"));
+        assert!(yaml.contains("# These can be positive or negative.\ntimeturnerOffset:\n"));
+        // No tokens, no api section
+        assert!(!yaml.contains("api:"));
+    }
+
+    fn temp_config(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir()
+            .join(format!("timeturner-config-{}-{}.yml", name, std::process::id()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let config = Config {
+            hardware_offset_ms: -12,
+            auto_sync_enabled: true,
+            ltc_time_zone: "+01:00".into(),
+            serial_devices: vec![SerialDevice {
+                vid: Some("2341".into()),
+                pid: Some("0043".into()),
+                serial_number: None,
+                baud_rate: 9600,
+                reader_latency: Some(ReaderLatency { fixed_us: 800, per_character: false }),
+            }],
+            ..Config::default()
+        };
+        let path = temp_config("round-trip", "");
+        save_config(path.to_str().unwrap(), &config).unwrap();
+        let loaded = Config::load(&path).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(serde_json::to_value(&loaded).unwrap(), serde_json::to_value(&config).unwrap());
+        assert!(loaded.validate().is_ok());
+    }
+
+    #[test]
+    fn test_load_keeps_settings_from_an_original_config() {
+        // The four keys the first releases wrote; everything else is newer.
+        let path = temp_config(
+            "original",
+            "hardwareOffsetMs: 20\n\
+             autoSyncEnabled: true\n\
+             defaultNudgeMs: 5\n\
+             timeturnerOffset:\n  hours: 1\n  minutes: 0\n  seconds: 0\n  frames: 12\n",
+        );
+        let loaded = Config::load(&path).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(loaded.hardware_offset_ms, 20);
+        assert!(loaded.auto_sync_enabled);
+        assert_eq!(loaded.default_nudge_ms, 5);
+        assert_eq!(loaded.timeturner_offset.hours, 1);
+        assert_eq!(loaded.timeturner_offset.frames, 12);
+        assert_eq!(loaded.step_threshold_ms, default_step_threshold_ms());
+        assert_eq!(loaded.ltc_time_zone, "local");
+        assert!(loaded.serial_devices.is_empty());
+        assert!(loaded.validate().is_ok());
+    }
+
+    #[test]
+    fn test_load_fills_in_missing_keys() {
+        let path = temp_config("partial", "timeturnerOffset:\n  seconds: 3\nonAir: true\n");
+        let loaded = Config::load(&path).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(loaded.hardware_offset_ms, 0);
+        assert_eq!(loaded.timeturner_offset, TimeturnerOffset { seconds: 3, ..Default::default() });
+        assert!(loaded.on_air);
+        assert_eq!(loaded.default_nudge_ms, default_nudge_ms());
+    }
+
+    #[test]
+    fn test_reload_keeps_the_last_good_config() {
+        let path = temp_config("reload", "hardwareOffsetMs: 20\n");
+        let config = Mutex::new(Config::load(&path).unwrap());
+
+        fs::write(&path, "hardwareOffsetMs: [20\n").unwrap();
+        assert!(Config::load(&path).is_err());
+        reload(&config, &path);
+        assert_eq!(config.lock().unwrap().hardware_offset_ms, 20);
+
+        fs::write(&path, "hardwareOffsetMs: 30\n").unwrap();
+        reload(&config, &path);
+        assert_eq!(config.lock().unwrap().hardware_offset_ms, 30);
+
+        // Parses, but fails validation.
+        fs::write(&path, "hardwareOffsetMs: 40\ndefaultNudgeMs: 0\n").unwrap();
+        assert!(Config::load(&path).unwrap_err().contains("invalid"));
+        reload(&config, &path);
+        assert_eq!(config.lock().unwrap().hardware_offset_ms, 30);
+
+        fs::remove_file(&path).ok();
+        reload(&config, &path);
+        assert_eq!(config.lock().unwrap().hardware_offset_ms, 30);
+    }
+
+    #[test]
+    fn test_watch_config_follows_saves() {
+        let dir = std::env::temp_dir().join(format!("timeturner-watch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.yml");
+        fs::write(&path, "hardwareOffsetMs: 20\n").unwrap();
+        let path = path.to_str().unwrap();
+        let config = watch_config(path).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(200));
+
+        // Twice, since a watch on the renamed-over file would see only the first.
+        for offset in [30, 40] {
+            save_config(path, &Config { hardware_offset_ms: offset, ..Config::default() }).unwrap();
+            let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+            while config.lock().unwrap().hardware_offset_ms != offset {
+                assert!(std::time::Instant::now() < deadline, "config.yml was not reloaded");
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+        }
+        assert!(!Path::new(&format!("{}.tmp", path)).exists());
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_upgrade_config_file_backs_up_and_rewrites_unversioned_files() {
+        let original = "hardwareOffsetMs: 30\nautoSyncEnabled: true\n";
+        let path = temp_config("unversioned", original);
+
+        let backup = upgrade_config_file(&path).unwrap().expect("file should be upgraded");
+        let upgraded = fs::read_to_string(&path).unwrap();
+        let saved = fs::read_to_string(&backup).unwrap();
+        // Already current: nothing more to do.
+        let again = upgrade_config_file(&path).unwrap();
+        fs::remove_file(&path).ok();
+        fs::remove_file(&backup).ok();
+
+        assert!(backup.to_str().unwrap().ends_with(".v0.bak"));
+        assert_eq!(saved, original);
+        assert!(upgraded.contains(&format!("configVersion: {}", CONFIG_VERSION)));
+        let loaded: Config = serde_yaml::from_str(&upgraded).unwrap();
+        assert_eq!(loaded.hardware_offset_ms, 30);
+        assert!(loaded.auto_sync_enabled);
+        assert_eq!(again, None);
+    }
+
+    #[test]
+    fn test_upgrade_config_file_leaves_newer_files_alone() {
+        let newer = format!("hardwareOffsetMs: 30\nconfigVersion: {}\n", CONFIG_VERSION + 1);
+        let path = temp_config("newer", &newer);
+        let result = upgrade_config_file(&path);
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert!(result.unwrap_err().contains("newer"));
+        assert_eq!(contents, newer);
+    }
+
+    #[test]
+    fn test_step_limit() {
+        assert!(!Config::default().step_over_limit(43_200_000), "off by default");
+        let config = Config { max_auto_step_ms: 2000, ..Config::default() };
+        assert!(config.validate().is_ok());
+        assert!(!config.step_over_limit(-2000));
+        assert!(config.step_over_limit(2001));
+        assert!(config.step_over_limit(-43_200_000));
+        let below_threshold = Config { max_auto_step_ms: 40, ..Config::default() };
+        assert!(below_threshold.validate().unwrap_err().starts_with("maxAutoStepMs"));
+    }
+
+    #[test]
+    fn test_secondary_frame_rate() {
+        let config = Config { secondary_frame_rate: Some("29.97".into()), ..Config::default() };
+        assert!(config.validate().is_ok());
+        assert_eq!(config.secondary_rate(), Some(Ratio::new(30000, 1001)));
+        assert_eq!(Config::default().secondary_rate(), None);
+        let bad = Config { secondary_frame_rate: Some("26".into()), ..Config::default() };
+        assert!(bad.validate().unwrap_err().starts_with("secondaryFrameRate:"));
+    }
+
+    #[test]
+        fn test_migrate_stamps_the_current_version() {
+        let mut value: serde_yaml::Value = serde_yaml::from_str("onAir: true").unwrap();
+        assert_eq!(migrate(&mut value), Ok(0));
+        assert_eq!(value["configVersion"], serde_yaml::Value::from(CONFIG_VERSION));
+
+        let mut empty = serde_yaml::Value::Null;
+        assert_eq!(migrate(&mut empty), Ok(0));
+
+        let mut bad: serde_yaml::Value = serde_yaml::from_str("configVersion: latest").unwrap();
+        assert!(migrate(&mut bad).is_err());
+    }
+
+    #[test]
+    fn test_patch_cannot_change_config_version() {
+        let patch = serde_json::json!({ "configVersion": CONFIG_VERSION + 1 });
+        assert!(Config::default().patched(&patch).unwrap_err().contains("configVersion"));
+    }
 }
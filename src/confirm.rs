@@ -0,0 +1,104 @@
+// src/confirm.rs
+//
+// Two-step confirmation for large clock steps. `GET /api/sync/preview`
+// shows the time a sync would set and, when the step is larger than
+// `confirmStepMs`, issues a short-lived token for it; `POST /api/sync`
+// then refuses such a step unless it carries `confirm=true` and that
+// token. A token is used once, expires after `TOKEN_TTL`, and only covers
+// a step close to the one that was previewed, so a jam that moved in the
+// meantime has to be looked at again.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a preview's token stays valid.
+pub const TOKEN_TTL: Duration = Duration::from_secs(30);
+
+/// How far the step may move between preview and confirm, in milliseconds.
+pub const STEP_TOLERANCE_MS: i64 = 100;
+
+struct Pending {
+    token: String,
+    action: &'static str,
+    step_ms: i64,
+    expires: Instant,
+}
+
+#[derive(Default)]
+pub struct Confirmations {
+    pending: Mutex<Vec<Pending>>,
+}
+
+/// Whether a step of `step_ms` needs a confirmed preview under `threshold_ms`
+/// (`confirmStepMs`; 0 turns confirmation off).
+pub fn required(step_ms: i64, threshold_ms: u64) -> bool {
+    threshold_ms > 0 && step_ms.unsigned_abs() > threshold_ms
+}
+
+/// A hex token from the process's random hash keys. It guards against a
+/// stray click, not an attacker: the caller has already authenticated.
+fn new_token() -> String {
+    (0..2u8)
+        .map(|half| {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u8(half);
+            format!("{:016x}", hasher.finish())
+        })
+        .collect()
+}
+
+impl Confirmations {
+    /// Issue a token for `action` stepping the clock by `step_ms`.
+    pub fn issue(&self, action: &'static str, step_ms: i64, now: Instant) -> String {
+        let token = new_token();
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|p| p.expires > now);
+        pending.push(Pending { token: token.clone(), action, step_ms, expires: now + TOKEN_TTL });
+        token
+    }
+
+    /// Use up `token` for `action` if it was issued for about `step_ms` and
+    /// has not expired. A token is spent by any attempt to redeem it.
+    pub fn redeem(&self, action: &str, token: &str, step_ms: i64, now: Instant) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|p| p.expires > now);
+        match pending.iter().position(|p| p.token == token && p.action == action) {
+            Some(index) => (pending.remove(index).step_ms - step_ms).abs() <= STEP_TOLERANCE_MS,
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_required_only_above_threshold() {
+        assert!(!required(1000, 1000));
+        assert!(required(-1001, 1000));
+        assert!(!required(3_600_000, 0), "0 turns confirmation off");
+    }
+
+    #[test]
+    fn test_tokens_are_single_use_and_expire() {
+        let confirmations = Confirmations::default();
+        let now = Instant::now();
+        let token = confirmations.issue("sync", 5000, now);
+        assert_eq!(token.len(), 32);
+        assert_ne!(token, confirmations.issue("sync", 5000, now));
+
+        assert!(!confirmations.redeem("nudge", &token, 5000, now), "wrong action");
+        assert!(confirmations.redeem("sync", &token, 5050, now));
+        assert!(!confirmations.redeem("sync", &token, 5050, now), "already used");
+
+        let token = confirmations.issue("sync", 5000, now);
+        assert!(!confirmations.redeem("sync", &token, 5000, now + TOKEN_TTL));
+
+        let token = confirmations.issue("sync", 5000, now);
+        assert!(!confirmations.redeem("sync", &token, 7000, now), "the step moved");
+        assert!(!confirmations.redeem("sync", &token, 5000, now), "spent by the failed attempt");
+    }
+}
@@ -0,0 +1,219 @@
+// src/export.rs
+//
+// Raw measurement export for offline analysis. Between
+// `POST /api/export/start` and `POST /api/export/stop`, every measured LOCK
+// frame is appended to a CSV file under `EXPORT_DIR`: its timestamp,
+// timecode, raw delta, smoothed delta and jitter. A new file is started
+// every `rotateMinutes`, so a long show stays in pieces pandas can load one
+// at a time, and only the newest `keepFiles` are kept, so a forgotten export
+// cannot fill the disk. Files are named for the UTC time they start. Rows are
+// buffered and flushed when a file is closed.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use utoipa::ToSchema;
+
+use crate::processor::Measurement;
+
+/// Where exports are written, relative to the working directory.
+pub const EXPORT_DIR: &str = "export";
+
+pub const DEFAULT_ROTATE_MINUTES: u64 = 15;
+
+/// A day of files at the default rotation.
+pub const DEFAULT_KEEP_FILES: usize = 96;
+
+const HEADER: &str = "timestamp,timecode,rawDeltaMs,deltaMs,jitterMs\n";
+
+/// The current or last export, for `/api/export`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportStatus {
+    pub active: bool,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub started: Option<DateTime<Utc>>,
+    pub rotate_minutes: u64,
+    /// Older files are deleted once there are more than this many.
+    pub keep_files: usize,
+    /// Files written and still kept, oldest first.
+    pub files: Vec<String>,
+    pub rows: u64,
+    /// Why the export stopped on its own, if it did.
+    pub error: Option<String>,
+}
+
+pub struct Exporter {
+    dir: PathBuf,
+    status: ExportStatus,
+    /// The open file and when it was started.
+    file: Option<(BufWriter<File>, DateTime<Utc>)>,
+}
+
+impl Default for Exporter {
+    fn default() -> Self {
+        Self::in_dir(EXPORT_DIR)
+    }
+}
+
+impl Exporter {
+    pub fn in_dir(dir: impl AsRef<Path>) -> Self {
+        Self { dir: dir.as_ref().to_path_buf(), status: ExportStatus::default(), file: None }
+    }
+
+    pub fn status(&self) -> ExportStatus {
+        self.status.clone()
+    }
+
+    /// Start exporting at `now`, with a new file every `rotate_minutes` and
+    /// at most `keep_files` kept.
+    pub fn start(&mut self, rotate_minutes: u64, keep_files: usize, now: DateTime<Utc>) -> Result<ExportStatus, String> {
+        if self.status.active {
+            return Err("An export is already running.".into());
+        }
+        self.status =
+            ExportStatus { active: true, started: Some(now), rotate_minutes, keep_files, ..ExportStatus::default() };
+        if let Err(e) = self.open(now) {
+            self.status.active = false;
+            return Err(e);
+        }
+        log::info!("Exporting measurements to {}.", self.dir.display());
+        Ok(self.status())
+    }
+
+    pub fn stop(&mut self) -> ExportStatus {
+        if self.status.active {
+            log::info!("Export stopped after {} rows in {} files.", self.status.rows, self.status.files.len());
+        }
+        self.status.active = false;
+        if let Err(e) = self.close() {
+            log::error!("{}", e);
+            self.status.error.get_or_insert(e);
+        }
+        self.status()
+    }
+
+    /// Flush and close the open file, if any.
+    fn close(&mut self) -> Result<(), String> {
+        match self.file.take() {
+            Some((mut file, _)) => file.flush().map_err(|e| format!("Cannot write export: {}", e)),
+            None => Ok(()),
+        }
+    }
+
+    fn open(&mut self, now: DateTime<Utc>) -> Result<(), String> {
+        self.close()?;
+        fs::create_dir_all(&self.dir).map_err(|e| format!("Cannot create {}: {}", self.dir.display(), e))?;
+        let path = self.dir.join(format!("timeturner-{}.csv", now.format("%Y%m%dT%H%M%SZ")));
+        let file = File::create(&path).map_err(|e| format!("Cannot create {}: {}", path.display(), e))?;
+        let mut file = BufWriter::new(file);
+        file.write_all(HEADER.as_bytes()).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+        self.status.files.push(path.display().to_string());
+        self.file = Some((file, now));
+        while self.status.files.len() > self.status.keep_files.max(1) {
+            let oldest = self.status.files.remove(0);
+            if let Err(e) = fs::remove_file(&oldest) {
+                log::warn!("Cannot remove old export {}: {}", oldest, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Append `m` for the frame `timecode`, if an export is running. A
+    /// write error stops the export.
+    pub fn record(&mut self, m: &Measurement, timecode: &str) {
+        if !self.status.active {
+            return;
+        }
+        let rotate = chrono::Duration::minutes(self.status.rotate_minutes as i64);
+        let due = self.file.as_ref().is_none_or(|(_, opened)| m.at - *opened >= rotate);
+        let result = if due { self.open(m.at) } else { Ok(()) }.and_then(|()| {
+            let (file, _) = self.file.as_mut().expect("opened above");
+            writeln!(
+                file,
+                "{},{},{:.3},{:.3},{}",
+                m.at.format("%Y-%m-%dT%H:%M:%S%.6fZ"),
+                timecode,
+                m.raw_ms,
+                m.delta_ms,
+                m.jitter_ms
+            )
+            .map_err(|e| format!("Cannot write export: {}", e))
+        });
+        match result {
+            Ok(()) => self.status.rows += 1,
+            Err(e) => {
+                log::error!("{}; export stopped.", e);
+                self.status.error = Some(e);
+                self.stop();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn measurement(at: DateTime<Utc>) -> Measurement {
+        Measurement { at, delta_ms: 1.25, raw_ms: 3.5, jitter_ms: 2 }
+    }
+
+    #[test]
+    fn test_export_writes_rows_and_rotates() {
+        let dir = std::env::temp_dir().join(format!("timeturner-export-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut exporter = Exporter::in_dir(&dir);
+        let start = Utc.with_ymd_and_hms(2025, 7, 30, 10, 0, 0).unwrap();
+        exporter.record(&measurement(start), "10:00:00:00");
+        assert!(!dir.exists(), "nothing is written before start");
+
+        exporter.start(1, 2, start).unwrap();
+        assert!(exporter.start(1, 2, start).is_err(), "already running");
+        exporter.record(&measurement(start), "10:00:00:00");
+        exporter.record(&measurement(start + chrono::Duration::seconds(30)), "10:00:30:00");
+        exporter.record(&measurement(start + chrono::Duration::seconds(60)), "10:01:00:00");
+        let status = exporter.stop();
+        exporter.record(&measurement(start + chrono::Duration::seconds(61)), "10:01:01:00");
+
+        assert_eq!(status.rows, 3);
+        assert_eq!(status.files.len(), 2);
+        assert!(status.files[1].ends_with("timeturner-20250730T100100Z.csv"));
+        let first = fs::read_to_string(&status.files[0]).unwrap();
+        let second = fs::read_to_string(&status.files[1]).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            first,
+            format!(
+                "{}2025-07-30T10:00:00.000000Z,10:00:00:00,3.500,1.250,2\n\
+                 2025-07-30T10:00:30.000000Z,10:00:30:00,3.500,1.250,2\n",
+                HEADER
+            )
+        );
+        assert_eq!(second.lines().count(), 2, "header and the one row before stop");
+    }
+
+    #[test]
+    fn test_export_keeps_only_the_newest_files() {
+        let dir = std::env::temp_dir().join(format!("timeturner-export-keep-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut exporter = Exporter::in_dir(&dir);
+        let start = Utc.with_ymd_and_hms(2025, 7, 30, 10, 0, 0).unwrap();
+        exporter.start(1, 2, start).unwrap();
+        for minute in 0..4 {
+            exporter.record(&measurement(start + chrono::Duration::minutes(minute)), "10:00:00:00");
+        }
+        let status = exporter.stop();
+
+        let mut on_disk: Vec<String> =
+            fs::read_dir(&dir).unwrap().map(|e| e.unwrap().file_name().into_string().unwrap()).collect();
+        on_disk.sort();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(status.rows, 4);
+        assert_eq!(on_disk, vec!["timeturner-20250730T100200Z.csv", "timeturner-20250730T100300Z.csv"]);
+        assert!(status.files[0].ends_with(&on_disk[0]) && status.files[1].ends_with(&on_disk[1]));
+    }
+}
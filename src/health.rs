@@ -0,0 +1,234 @@
+// src/health.rs
+//
+// Liveness and readiness, served at `/api/health` and `/api/ready` for load
+// balancers, orchestrators and monitoring. Each long-running worker thread
+// beats a heartbeat on every pass through its loop; the daemon is alive while
+// every worker has beaten recently. It is ready once it is alive, its config
+// is valid and the serial reader is delivering frames.
+
+use crate::config::Config;
+use crate::sync_logic::{ClockPair, LtcSnapshot};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+struct Worker {
+    name: &'static str,
+    max_silence: Duration,
+    last_beat: Instant,
+}
+
+/// The daemon's worker heartbeats.
+pub struct Health {
+    started: Instant,
+    workers: Mutex<Vec<Worker>>,
+}
+
+/// One worker's handle for reporting that it is still running.
+#[derive(Clone)]
+pub struct Heartbeat {
+    health: Arc<Health>,
+    index: usize,
+}
+
+impl Heartbeat {
+    pub fn beat(&self) {
+        self.health.workers.lock().unwrap()[self.index].last_beat = Instant::now();
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct WorkerHealth {
+    pub name: String,
+    pub alive: bool,
+    /// Milliseconds since the worker last beat.
+    pub last_beat_ms: u64,
+}
+
+/// `/api/health`: whether the process and its worker threads are running.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct HealthReport {
+    pub healthy: bool,
+    pub uptime_secs: u64,
+    pub workers: Vec<WorkerHealth>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct ReadyCheck {
+    /// `workers`, `config` or `serial`.
+    pub name: String,
+    pub ok: bool,
+    pub detail: String,
+}
+
+/// `/api/ready`: whether the daemon is doing its job.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct ReadyReport {
+    pub ready: bool,
+    pub checks: Vec<ReadyCheck>,
+}
+
+impl Health {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            started: Instant::now(),
+            workers: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Register a worker that counts as dead once it has not beaten for
+    /// `max_silence`. Registering counts as the first beat.
+    pub fn heartbeat(self: &Arc<Self>, name: &'static str, max_silence: Duration) -> Heartbeat {
+        let mut workers = self.workers.lock().unwrap();
+        workers.push(Worker {
+            name,
+            max_silence,
+            last_beat: Instant::now(),
+        });
+        Heartbeat {
+            health: self.clone(),
+            index: workers.len() - 1,
+        }
+    }
+
+    pub fn report(&self, now: Instant) -> HealthReport {
+        let workers: Vec<WorkerHealth> = self
+            .workers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|w| {
+                let silence = now.saturating_duration_since(w.last_beat);
+                WorkerHealth {
+                    name: w.name.to_string(),
+                    alive: silence <= w.max_silence,
+                    last_beat_ms: silence.as_millis() as u64,
+                }
+            })
+            .collect();
+        HealthReport {
+            healthy: workers.iter().all(|w| w.alive),
+            uptime_secs: now.saturating_duration_since(self.started).as_secs(),
+            workers,
+        }
+    }
+
+    pub fn readiness(
+        &self,
+        now: Instant,
+        state: &LtcSnapshot,
+        config: &Config,
+        wall_now: DateTime<Utc>,
+    ) -> ReadyReport {
+        let health = self.report(now);
+        let stalled: Vec<&str> = health
+            .workers
+            .iter()
+            .filter(|w| !w.alive)
+            .map(|w| w.name.as_str())
+            .collect();
+        let workers = ReadyCheck {
+            name: "workers".into(),
+            ok: stalled.is_empty(),
+            detail: if stalled.is_empty() {
+                format!("{} workers running", health.workers.len())
+            } else {
+                format!("stalled: {}", stalled.join(", "))
+            },
+        };
+
+        let valid = config.validate();
+        let config_check = ReadyCheck {
+            name: "config".into(),
+            ok: valid.is_ok(),
+            detail: valid.err().unwrap_or_else(|| "loaded".into()),
+        };
+
+        // Aged on the monotonic clock where the frame carries a receive
+        // stamp, so a clock step does not make it look older or newer.
+        let timeout_secs = config.ready_frame_timeout_secs;
+        let pair = ClockPair { real: wall_now, mono: now };
+        let age = state
+            .latest
+            .as_ref()
+            .map(|frame| (wall_now - frame.arrival(&pair)).num_milliseconds());
+        let serial = ReadyCheck {
+            name: "serial".into(),
+            ok: age.is_some_and(|age| age <= (timeout_secs * 1000) as i64),
+            detail: match age {
+                Some(age) => format!("last frame {} ms ago (limit {} s)", age.max(0), timeout_secs),
+                None => "no frames received yet".into(),
+            },
+        };
+
+        let checks = vec![workers, config_check, serial];
+        ReadyReport {
+            ready: checks.iter().all(|c| c.ok),
+            checks,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync_logic::{LtcFrame, LtcState};
+    use num_rational::Ratio;
+
+    fn frame_at(timestamp: DateTime<Utc>) -> LtcFrame {
+        LtcFrame {
+            status: "LOCK".to_string(),
+            hours: 10,
+            minutes: 20,
+            seconds: 30,
+            frames: 0,
+            is_drop_frame: false,
+            frame_rate: Ratio::new(25, 1),
+            timestamp,
+            received: None,
+        }
+    }
+
+    #[test]
+    fn test_silent_worker_is_reported_dead() {
+        let health = Health::new();
+        let processor = health.heartbeat("processor", Duration::from_secs(5));
+        let _history = health.heartbeat("history", Duration::from_secs(10));
+        processor.beat();
+
+        let report = health.report(Instant::now() + Duration::from_secs(7));
+        assert!(!report.healthy);
+        assert_eq!(report.workers[0].name, "processor");
+        assert!(!report.workers[0].alive);
+        assert!(report.workers[1].alive);
+        assert!(health.report(Instant::now()).healthy);
+    }
+
+    #[test]
+    fn test_ready_needs_recent_frames_and_valid_config() {
+        let health = Health::new();
+        let mut state = LtcState::new();
+        let mut config = Config::default();
+        let now = Utc::now();
+
+        let report = health.readiness(Instant::now(), &state.snapshot(), &config, now);
+        assert!(!report.ready);
+        assert_eq!(report.checks[2].detail, "no frames received yet");
+
+        state.update(frame_at(now - chrono::Duration::seconds(1)));
+        let report = health.readiness(Instant::now(), &state.snapshot(), &config, now);
+        assert!(report.ready, "{:?}", report);
+
+        let later = now + chrono::Duration::seconds(10);
+        let report = health.readiness(Instant::now(), &state.snapshot(), &config, later);
+        assert!(!report.ready);
+        assert!(!report.checks[2].ok);
+
+        config.jitter_window = 0;
+        let report = health.readiness(Instant::now(), &state.snapshot(), &config, now);
+        assert!(!report.checks[1].ok);
+        assert_eq!(report.checks[1].detail, "jitterWindow must be greater than zero");
+    }
+}
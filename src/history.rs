@@ -0,0 +1,269 @@
+// src/history.rs
+//
+// Clock delta history for `/api/history`. Samples arrive once a second and
+// are kept at three resolutions (1 s, 10 s, 1 min), each with its own
+// retention, so recent detail and a multi-week overview fit in bounded memory.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use utoipa::ToSchema;
+
+/// How long each resolution tier is kept, in seconds. Zero disables a tier.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryConfig {
+    /// 1 s samples. Default 10 minutes.
+    #[serde(default = "default_seconds_retention")]
+    pub seconds_retention: u64,
+    /// 10 s averages. Default 6 hours.
+    #[serde(default = "default_ten_seconds_retention")]
+    pub ten_seconds_retention: u64,
+    /// 1 min averages. Default 14 days.
+    #[serde(default = "default_minutes_retention")]
+    pub minutes_retention: u64,
+}
+
+fn default_seconds_retention() -> u64 {
+    600
+}
+
+fn default_ten_seconds_retention() -> u64 {
+    6 * 3600
+}
+
+fn default_minutes_retention() -> u64 {
+    14 * 24 * 3600
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            seconds_retention: default_seconds_retention(),
+            ten_seconds_retention: default_ten_seconds_retention(),
+            minutes_retention: default_minutes_retention(),
+        }
+    }
+}
+
+impl HistoryConfig {
+    fn retentions(&self) -> [u64; 3] {
+        [
+            self.seconds_retention,
+            self.ten_seconds_retention,
+            self.minutes_retention,
+        ]
+    }
+}
+
+/// One point of history: the average, minimum and maximum smoothed delta over
+/// `resolutionSecs` starting at `timestamp`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryPoint {
+    #[schema(value_type = String, format = DateTime)]
+    pub timestamp: DateTime<Utc>,
+    pub delta_ms: i64,
+    pub min_delta_ms: i64,
+    pub max_delta_ms: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryResponse {
+    pub resolution_secs: u64,
+    /// Oldest first.
+    pub points: Vec<HistoryPoint>,
+}
+
+/// Samples being folded into the current bucket of a tier.
+struct Bucket {
+    index: i64,
+    sum: i64,
+    count: i64,
+    min: i64,
+    max: i64,
+}
+
+struct Tier {
+    resolution_secs: u64,
+    retention_secs: u64,
+    points: VecDeque<HistoryPoint>,
+    bucket: Option<Bucket>,
+}
+
+impl Tier {
+    fn new(resolution_secs: u64, retention_secs: u64) -> Self {
+        Self {
+            resolution_secs,
+            retention_secs,
+            points: VecDeque::new(),
+            bucket: None,
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(b) = self.bucket.take() {
+            let start = b.index * self.resolution_secs as i64;
+            self.points.push_back(HistoryPoint {
+                timestamp: DateTime::from_timestamp(start, 0).unwrap_or_default(),
+                delta_ms: (b.sum as f64 / b.count as f64).round() as i64,
+                min_delta_ms: b.min,
+                max_delta_ms: b.max,
+            });
+        }
+    }
+
+    fn record(&mut self, at: DateTime<Utc>, delta_ms: i64) {
+        if self.retention_secs == 0 {
+            return;
+        }
+        let index = at.timestamp().div_euclid(self.resolution_secs as i64);
+        if self.bucket.as_ref().is_some_and(|b| b.index != index) {
+            self.flush();
+        }
+        let b = self.bucket.get_or_insert(Bucket {
+            index,
+            sum: 0,
+            count: 0,
+            min: delta_ms,
+            max: delta_ms,
+        });
+        b.sum += delta_ms;
+        b.count += 1;
+        b.min = b.min.min(delta_ms);
+        b.max = b.max.max(delta_ms);
+        self.prune(at);
+    }
+
+    fn prune(&mut self, now: DateTime<Utc>) {
+        let cutoff = now.timestamp() - self.retention_secs as i64;
+        while self.points.front().is_some_and(|p| p.timestamp.timestamp() < cutoff) {
+            self.points.pop_front();
+        }
+    }
+}
+
+pub struct DeltaHistory {
+    tiers: Vec<Tier>,
+}
+
+impl DeltaHistory {
+    pub fn new(config: &HistoryConfig) -> Self {
+        let tiers = [1, 10, 60]
+            .into_iter()
+            .zip(config.retentions())
+            .map(|(resolution, retention)| Tier::new(resolution, retention))
+            .collect();
+        Self { tiers }
+    }
+
+    /// Apply new retentions; shrinking one drops its oldest points at once.
+    pub fn set_config(&mut self, config: &HistoryConfig, now: DateTime<Utc>) {
+        for (tier, retention) in self.tiers.iter_mut().zip(config.retentions()) {
+            if tier.retention_secs != retention {
+                tier.retention_secs = retention;
+                if retention == 0 {
+                    tier.points.clear();
+                    tier.bucket = None;
+                }
+                tier.prune(now);
+            }
+        }
+    }
+
+    /// Add one smoothed delta sample, taken at `at`.
+    pub fn record(&mut self, at: DateTime<Utc>, delta_ms: i64) {
+        for tier in &mut self.tiers {
+            tier.record(at, delta_ms);
+        }
+    }
+
+    /// Points covering the last `seconds` before `now`, from the finest tier
+    /// whose retention reaches back that far (or the longest one kept).
+    pub fn query(&self, seconds: u64, now: DateTime<Utc>) -> HistoryResponse {
+        let enabled = || self.tiers.iter().filter(|t| t.retention_secs > 0);
+        let tier = enabled()
+            .find(|t| t.retention_secs >= seconds)
+            .or_else(|| enabled().max_by_key(|t| t.retention_secs));
+        let Some(tier) = tier else {
+            return HistoryResponse { resolution_secs: 1, points: Vec::new() };
+        };
+        let cutoff = now.timestamp() - seconds as i64;
+        HistoryResponse {
+            resolution_secs: tier.resolution_secs,
+            points: tier
+                .points
+                .iter()
+                .filter(|p| p.timestamp.timestamp() >= cutoff)
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap()
+    }
+
+    #[test]
+    fn test_downsamples_into_tiers() {
+        let mut history = DeltaHistory::new(&HistoryConfig::default());
+        // 1_700_000_000 is a multiple of 10 but not of 60; start on a minute.
+        let base = 40;
+        for s in 0..=120 {
+            history.record(at(base + s), s % 10);
+        }
+        let now = at(base + 120);
+
+        let fine = history.query(300, now);
+        assert_eq!(fine.resolution_secs, 1);
+        // The current second's bucket is still open
+        assert_eq!(fine.points.len(), 120);
+
+        let tens = history.query(3600, now);
+        assert_eq!(tens.resolution_secs, 10);
+        assert_eq!(tens.points.len(), 12);
+        assert_eq!(tens.points[0].delta_ms, 5); // round(4.5)
+        assert_eq!(tens.points[0].min_delta_ms, 0);
+        assert_eq!(tens.points[0].max_delta_ms, 9);
+
+        let minutes = history.query(7 * 24 * 3600, now);
+        assert_eq!(minutes.resolution_secs, 60);
+        assert_eq!(minutes.points.len(), 2);
+        assert_eq!(minutes.points[0].timestamp, at(base));
+    }
+
+    #[test]
+    fn test_retention_prunes_and_falls_back() {
+        let config = HistoryConfig {
+            seconds_retention: 30,
+            ten_seconds_retention: 0,
+            minutes_retention: 600,
+        };
+        let mut history = DeltaHistory::new(&config);
+        for s in 0..100 {
+            history.record(at(s), 1);
+        }
+        let now = at(99);
+        let fine = history.query(30, now);
+        assert_eq!(fine.resolution_secs, 1);
+        assert!(fine.points.len() <= 31);
+        assert!(fine.points.iter().all(|p| p.timestamp >= at(69)));
+
+        // The 10 s tier is disabled, so a one-hour query skips to minutes.
+        assert_eq!(history.query(60, now).resolution_secs, 60);
+        // Longer than anything kept: the longest tier answers.
+        assert_eq!(history.query(86_400, now).resolution_secs, 60);
+
+        history.set_config(
+            &HistoryConfig { seconds_retention: 10, ..config },
+            now,
+        );
+        assert!(history.query(30, now).points.iter().all(|p| p.timestamp >= at(89)));
+    }
+}
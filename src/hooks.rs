@@ -0,0 +1,142 @@
+// src/hooks.rs
+
+use crate::config::HooksConfig;
+use crate::sync_logic::LtcFrame;
+use num_traits::ToPrimitive;
+use std::process::{Child, Command};
+use std::thread;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookEvent {
+    SyncPerformed,
+    LockLost,
+    LockAcquired,
+    DeltaExceeded,
+    NtpDisagreement,
+    FrameRateMismatch,
+}
+
+impl HookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookEvent::SyncPerformed => "sync_performed",
+            HookEvent::LockLost => "lock_lost",
+            HookEvent::LockAcquired => "lock_acquired",
+            HookEvent::DeltaExceeded => "delta_exceeded",
+            HookEvent::NtpDisagreement => "ntp_disagreement",
+            HookEvent::FrameRateMismatch => "frame_rate_mismatch",
+        }
+    }
+
+    fn command<'a>(&self, hooks: &'a HooksConfig) -> Option<&'a str> {
+        let command = match self {
+            HookEvent::SyncPerformed => &hooks.sync_performed,
+            HookEvent::LockLost => &hooks.lock_lost,
+            HookEvent::LockAcquired => &hooks.lock_acquired,
+            HookEvent::DeltaExceeded => &hooks.delta_exceeded,
+            HookEvent::NtpDisagreement => &hooks.ntp_disagreement,
+            HookEvent::FrameRateMismatch => &hooks.frame_rate_mismatch,
+        };
+        command.as_deref().filter(|c| !c.trim().is_empty())
+    }
+}
+
+/// Environment variables describing an LTC frame, for passing to hooks.
+pub fn frame_vars(frame: &LtcFrame) -> Vec<(&'static str, String)> {
+    vec![
+        ("TIMETURNER_TIMECODE", frame.timecode()),
+        (
+            "TIMETURNER_FRAME_RATE",
+            format!("{:.2}", frame.frame_rate.to_f64().unwrap_or(0.0)),
+        ),
+        ("TIMETURNER_LTC_STATUS", frame.status.clone()),
+    ]
+}
+
+/// Build the shell invocation for `event`, or `None` if no hook is configured.
+fn build_command(hooks: &HooksConfig, event: HookEvent, vars: &[(&str, String)]) -> Option<Command> {
+    let command = event.command(hooks)?;
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c")
+        .arg(command)
+        .env("TIMETURNER_EVENT", event.as_str());
+    for (key, value) in vars {
+        cmd.env(key, value);
+    }
+    Some(cmd)
+}
+
+/// Start the configured command for `event`, if any, without waiting for it.
+fn spawn_hook(hooks: &HooksConfig, event: HookEvent, vars: &[(&str, String)]) -> Option<Child> {
+    let mut cmd = build_command(hooks, event, vars)?;
+    match cmd.spawn() {
+        Ok(child) => Some(child),
+        Err(e) => {
+            log::error!("Hook '{}' failed to start: {}", event.as_str(), e);
+            None
+        }
+    }
+}
+
+/// Run the user script configured for `event` in the background. The sync
+/// engine never waits on hooks; their exit status is only logged.
+pub fn fire(hooks: &HooksConfig, event: HookEvent, vars: &[(&str, String)]) {
+    if let Some(mut child) = spawn_hook(hooks, event, vars) {
+        log::info!("🪝 Running '{}' hook", event.as_str());
+        thread::spawn(move || match child.wait() {
+            Ok(status) if status.success() => {}
+            Ok(status) => log::warn!("Hook '{}' exited with {}", event.as_str(), status),
+            Err(e) => log::error!("Hook '{}' could not be waited on: {}", event.as_str(), e),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use num_rational::Ratio;
+
+    fn get_test_frame() -> LtcFrame {
+        LtcFrame {
+            status: "LOCK".to_string(),
+            hours: 10,
+            minutes: 20,
+            seconds: 30,
+            frames: 12,
+            is_drop_frame: true,
+            frame_rate: Ratio::new(30000, 1001),
+            timestamp: Utc::now(),
+            received: None,
+        }
+    }
+
+    #[test]
+    fn test_unconfigured_event_spawns_nothing() {
+        let hooks = HooksConfig {
+            lock_lost: Some("   ".to_string()),
+            ..HooksConfig::default()
+        };
+        assert!(build_command(&hooks, HookEvent::LockAcquired, &[]).is_none());
+        assert!(build_command(&hooks, HookEvent::LockLost, &[]).is_none());
+    }
+
+    #[test]
+    fn test_hook_receives_event_details() {
+        let hooks = HooksConfig {
+            lock_acquired: Some(
+                "echo \"$TIMETURNER_EVENT $TIMETURNER_TIMECODE $TIMETURNER_FRAME_RATE\"".to_string(),
+            ),
+            ..HooksConfig::default()
+        };
+        let vars = frame_vars(&get_test_frame());
+        let output = build_command(&hooks, HookEvent::LockAcquired, &vars)
+            .unwrap()
+            .output()
+            .expect("sh should run");
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout).trim(),
+            "lock_acquired 10:20:30;12 29.97"
+        );
+    }
+}
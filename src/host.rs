@@ -0,0 +1,376 @@
+// src/host.rs
+//
+// Health of the machine the daemon runs on, served at `/api/system`: CPU
+// temperature and Raspberry Pi throttling, load, uptime, memory and disk use,
+// and the link state of each network interface. Thermal throttling and
+// flapping NICs look like sync glitches from the outside. Also the network
+// interfaces listed in `/api/status`, with their addresses and which one
+// carries PTP.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+use std::process::Command;
+use utoipa::ToSchema;
+
+use crate::rtc::{self, RtcWrite};
+
+/// Raspberry Pi firmware throttling flags (`vcgencmd get_throttled`): the
+/// low bits are current conditions, the same bits shifted by 16 mean the
+/// condition has occurred since boot.
+const THROTTLE_FLAGS: [(u32, &str); 4] = [
+    (0, "under-voltage"),
+    (1, "ARM frequency capped"),
+    (2, "throttled"),
+    (3, "soft temperature limit"),
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Usage {
+    pub total_bytes: u64,
+    pub used_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Throttling {
+    /// The raw firmware value, e.g. `0x50000`.
+    pub raw: String,
+    /// Conditions now and since boot, e.g. `throttled (since boot)`.
+    pub flags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NicInfo {
+    pub name: String,
+    /// Kernel operstate: `up`, `down`, `dormant`, `unknown`, ...
+    pub state: String,
+    /// Negotiated speed; unknown while the link is down or for Wi-Fi.
+    pub speed_mbps: Option<u32>,
+    pub duplex: Option<String>,
+    /// Times the link has gone up or down since boot; a climbing count
+    /// means a flapping cable or switch port.
+    pub carrier_changes: Option<u64>,
+}
+
+/// A network interface as listed in `/api/status`. Link details come from
+/// the kernel's view in `/sys/class/net`, addresses from `getifaddrs`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct NetInterface {
+    pub name: String,
+    pub ipv4: Vec<String>,
+    pub ipv6: Vec<String>,
+    pub mac: Option<String>,
+    pub link_up: bool,
+    /// Negotiated speed; unknown while the link is down or for Wi-Fi.
+    pub speed_mbps: Option<u32>,
+    /// The NIC's PTP hardware clock, e.g. `/dev/ptp0`, if it has one.
+    pub phc: Option<String>,
+    /// Whether `phc` is the clock steered for PTP (`phc.device`), so this is
+    /// the NIC PTP is served from.
+    pub ptp: bool,
+}
+
+impl std::fmt::Display for NetInterface {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.name, if self.link_up { "up" } else { "down" })?;
+        if let Some(speed) = self.speed_mbps {
+            write!(f, " {} Mb/s", speed)?;
+        }
+        for ip in self.ipv4.iter().chain(&self.ipv6) {
+            write!(f, " {}", ip)?;
+        }
+        if self.ptp {
+            write!(f, " [PTP {}]", self.phc.as_deref().unwrap_or_default())?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RtcInfo {
+    /// The driver's name, e.g. `rtc-ds1307` for a DS3231.
+    pub name: String,
+    /// The latest write-back since startup; see `rtcWriteback`.
+    pub last_write: Option<RtcWrite>,
+}
+
+/// Served at `/api/system`. Readings the host does not provide are `null`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemInfo {
+    pub cpu_temp_c: Option<f64>,
+    /// Raspberry Pi only.
+    pub throttling: Option<Throttling>,
+    /// 1, 5 and 15 minute load averages.
+    pub load_average: Option<Vec<f64>>,
+    pub uptime_secs: Option<u64>,
+    pub memory: Option<Usage>,
+    /// The root filesystem.
+    pub disk: Option<Usage>,
+    pub interfaces: Vec<NicInfo>,
+    /// The hardware clock, if the host has one.
+    pub rtc: Option<RtcInfo>,
+}
+
+fn read(root: &Path, path: &str) -> Option<String> {
+    fs::read_to_string(root.join(path))
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+fn throttling(raw: u32) -> Throttling {
+    let mut flags = Vec::new();
+    for (bit, name) in THROTTLE_FLAGS {
+        if raw & (1 << bit) != 0 {
+            flags.push(name.to_string());
+        }
+        if raw & (1 << (bit + 16)) != 0 {
+            flags.push(format!("{} (since boot)", name));
+        }
+    }
+    Throttling { raw: format!("{:#x}", raw), flags }
+}
+
+fn memory(meminfo: &str) -> Option<Usage> {
+    let kib = |key: &str| {
+        meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix(key)?.trim().strip_suffix("kB")?.trim().parse::<u64>().ok())
+    };
+    let total = kib("MemTotal:")?;
+    let available = kib("MemAvailable:")?;
+    Some(Usage {
+        total_bytes: total * 1024,
+        used_bytes: total.saturating_sub(available) * 1024,
+    })
+}
+
+/// Parse `df -Pk` output for one filesystem.
+fn disk(df: &str) -> Option<Usage> {
+    let fields: Vec<&str> = df.lines().nth(1)?.split_whitespace().collect();
+    let total: u64 = fields.get(1)?.parse().ok()?;
+    let used: u64 = fields.get(2)?.parse().ok()?;
+    Some(Usage { total_bytes: total * 1024, used_bytes: used * 1024 })
+}
+
+fn interfaces(root: &Path) -> Vec<NicInfo> {
+    let Ok(entries) = fs::read_dir(root.join("sys/class/net")) else {
+        return Vec::new();
+    };
+    let mut nics: Vec<NicInfo> = entries
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name != "lo")
+        .map(|name| {
+            let attr = |file: &str| read(root, &format!("sys/class/net/{}/{}", name, file));
+            NicInfo {
+                state: attr("operstate").unwrap_or_else(|| "unknown".into()),
+                // The kernel reports -1 while the link is down.
+                speed_mbps: attr("speed").and_then(|s| s.parse().ok()),
+                duplex: attr("duplex").filter(|d| d != "unknown"),
+                carrier_changes: attr("carrier_changes").and_then(|s| s.parse().ok()),
+                name,
+            }
+        })
+        .collect();
+    nics.sort_by(|a, b| a.name.cmp(&b.name));
+    nics
+}
+
+/// The interfaces under `root`'s `/sys/class/net` and any others with one of
+/// `addrs`, loopback excepted, in name order.
+fn net_interfaces_from(root: &Path, addrs: &[(String, IpAddr)], phc_device: Option<&str>) -> Vec<NetInterface> {
+    let mut names: Vec<String> = fs::read_dir(root.join("sys/class/net"))
+        .map(|entries| entries.filter_map(|entry| entry.ok()?.file_name().into_string().ok()).collect())
+        .unwrap_or_default();
+    for (name, _) in addrs {
+        if !names.contains(name) {
+            names.push(name.clone());
+        }
+    }
+    names.retain(|name| name != "lo");
+    names.sort();
+    names
+        .into_iter()
+        .map(|name| {
+            let attr = |file: &str| read(root, &format!("sys/class/net/{}/{}", name, file));
+            let ips = || addrs.iter().filter(|(n, _)| *n == name).map(|(_, ip)| ip);
+            let phc = fs::read_dir(root.join(format!("sys/class/net/{}/device/ptp", name)))
+                .ok()
+                .and_then(|mut clocks| clocks.find_map(|entry| entry.ok()?.file_name().into_string().ok()))
+                .map(|clock| format!("/dev/{}", clock));
+            NetInterface {
+                ipv4: ips().filter(|ip| ip.is_ipv4()).map(|ip| ip.to_string()).collect(),
+                ipv6: ips().filter(|ip| ip.is_ipv6()).map(|ip| ip.to_string()).collect(),
+                mac: attr("address").filter(|mac| mac != "00:00:00:00:00:00"),
+                // Virtual links often report `unknown`; the carrier says
+                // whether they pass traffic.
+                link_up: match attr("operstate").as_deref() {
+                    Some("up") => true,
+                    Some("unknown") => attr("carrier").as_deref() == Some("1"),
+                    _ => false,
+                },
+                speed_mbps: attr("speed").and_then(|s| s.parse().ok()),
+                ptp: phc.is_some() && phc.as_deref() == phc_device,
+                phc,
+                name,
+            }
+        })
+        .collect()
+}
+
+/// The host's network interfaces, marking the one whose PHC is
+/// `phc_device`. Blocking.
+pub fn net_interfaces(phc_device: Option<&str>) -> Vec<NetInterface> {
+    let addrs: Vec<(String, IpAddr)> = get_if_addrs::get_if_addrs()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|ifa| !ifa.is_loopback())
+        .map(|ifa| (ifa.name.clone(), ifa.ip()))
+        .collect();
+    net_interfaces_from(Path::new("/"), &addrs, phc_device)
+}
+
+/// Read everything from the `/proc` and `/sys` trees under `root`; the disk
+/// is left to the caller.
+fn collect_from(root: &Path) -> SystemInfo {
+    SystemInfo {
+        cpu_temp_c: read(root, "sys/class/thermal/thermal_zone0/temp")
+            .and_then(|t| t.parse::<f64>().ok())
+            .map(|millidegrees| millidegrees / 1000.0),
+        throttling: read(root, "sys/devices/platform/soc/soc:firmware/get_throttled")
+            .and_then(|raw| u32::from_str_radix(raw.trim_start_matches("0x"), 16).ok())
+            .map(throttling),
+        load_average: read(root, "proc/loadavg").and_then(|line| {
+            line.split_whitespace()
+                .take(3)
+                .map(|n| n.parse().ok())
+                .collect::<Option<Vec<f64>>>()
+                .filter(|loads| loads.len() == 3)
+        }),
+        uptime_secs: read(root, "proc/uptime")
+            .and_then(|line| line.split_whitespace().next()?.parse::<f64>().ok())
+            .map(|secs| secs as u64),
+        memory: read(root, "proc/meminfo").and_then(|m| memory(&m)),
+        disk: None,
+        interfaces: interfaces(root),
+        rtc: read(root, "sys/class/rtc/rtc0/name").map(|name| RtcInfo { name, last_write: None }),
+    }
+}
+
+/// Take the readings. Blocking; runs `df` for the root filesystem.
+pub fn collect() -> SystemInfo {
+    let df = Command::new("df")
+        .args(["-Pk", "/"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).into_owned());
+    let mut info = SystemInfo {
+        disk: df.as_deref().and_then(disk),
+        ..collect_from(Path::new("/"))
+    };
+    if let Some(rtc) = &mut info.rtc {
+        rtc.last_write = rtc::last_write();
+    }
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_from_fake_host() {
+        let root = std::env::temp_dir().join(format!("timeturner-host-{}", std::process::id()));
+        let write = |path: &str, contents: &str| {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        };
+        write("sys/class/thermal/thermal_zone0/temp", "61850\n");
+        write("sys/devices/platform/soc/soc:firmware/get_throttled", "0x50005\n");
+        write("proc/loadavg", "0.52 0.48 0.40 1/230 4242\n");
+        write("proc/uptime", "86400.55 340000.10\n");
+        write("proc/meminfo", "MemTotal:        1966488 kB\nMemFree:          100000 kB\nMemAvailable:    1466488 kB\n");
+        write("sys/class/net/eth0/operstate", "up\n");
+        write("sys/class/net/eth0/speed", "1000\n");
+        write("sys/class/net/eth0/duplex", "full\n");
+        write("sys/class/net/eth0/carrier_changes", "7\n");
+        write("sys/class/net/wlan0/operstate", "down\n");
+        write("sys/class/net/wlan0/speed", "-1\n");
+        write("sys/class/net/lo/operstate", "unknown\n");
+        write("sys/class/rtc/rtc0/name", "rtc-ds1307\n");
+
+        let info = collect_from(&root);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(info.cpu_temp_c, Some(61.85));
+        let throttling = info.throttling.unwrap();
+        assert_eq!(throttling.raw, "0x50005");
+        assert_eq!(
+            throttling.flags,
+            vec!["under-voltage", "under-voltage (since boot)", "throttled", "throttled (since boot)"]
+        );
+        assert_eq!(info.load_average, Some(vec![0.52, 0.48, 0.40]));
+        assert_eq!(info.uptime_secs, Some(86400));
+        assert_eq!(info.memory, Some(Usage { total_bytes: 1966488 * 1024, used_bytes: 500000 * 1024 }));
+        assert_eq!(info.interfaces.len(), 2);
+        assert_eq!(info.interfaces[0].speed_mbps, Some(1000));
+        assert_eq!(info.interfaces[0].carrier_changes, Some(7));
+        assert_eq!((info.interfaces[1].name.as_str(), info.interfaces[1].state.as_str()), ("wlan0", "down"));
+        assert_eq!(info.interfaces[1].speed_mbps, None);
+        assert_eq!(info.rtc.map(|r| r.name), Some("rtc-ds1307".to_string()));
+    }
+
+    #[test]
+    fn test_net_interfaces_from_fake_host() {
+        let root = std::env::temp_dir().join(format!("timeturner-net-{}", std::process::id()));
+        let write = |path: &str, contents: &str| {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        };
+        write("sys/class/net/eth0/operstate", "up\n");
+        write("sys/class/net/eth0/speed", "1000\n");
+        write("sys/class/net/eth0/address", "b8:27:eb:12:34:56\n");
+        fs::create_dir_all(root.join("sys/class/net/eth0/device/ptp/ptp0")).unwrap();
+        write("sys/class/net/wg0/operstate", "unknown\n");
+        write("sys/class/net/wg0/carrier", "1\n");
+        write("sys/class/net/wlan0/operstate", "down\n");
+        write("sys/class/net/lo/operstate", "unknown\n");
+        let addrs = [
+            ("eth0".to_string(), "192.168.1.100".parse().unwrap()),
+            ("eth0".to_string(), "fe80::1".parse().unwrap()),
+            ("wg0".to_string(), "10.8.0.2".parse().unwrap()),
+        ];
+
+        let nics = net_interfaces_from(&root, &addrs, Some("/dev/ptp0"));
+        let unsteered = net_interfaces_from(&root, &addrs, None);
+        fs::remove_dir_all(&root).unwrap();
+
+        let names: Vec<&str> = nics.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, ["eth0", "wg0", "wlan0"]);
+        let eth0 = &nics[0];
+        assert_eq!((eth0.ipv4.clone(), eth0.ipv6.clone()), (vec!["192.168.1.100".to_string()], vec!["fe80::1".to_string()]));
+        assert_eq!(eth0.mac.as_deref(), Some("b8:27:eb:12:34:56"));
+        assert_eq!((eth0.link_up, eth0.speed_mbps), (true, Some(1000)));
+        assert_eq!((eth0.phc.as_deref(), eth0.ptp), (Some("/dev/ptp0"), true));
+        assert_eq!(eth0.to_string(), "eth0 up 1000 Mb/s 192.168.1.100 fe80::1 [PTP /dev/ptp0]");
+        assert!(nics[1].link_up, "carrier on an unknown operstate");
+        assert!(!nics[2].link_up && nics[2].ipv4.is_empty());
+        assert!(!unsteered[0].ptp, "a PHC that is not steered is not PTP");
+    }
+
+    #[test]
+    fn test_disk() {
+        let df = "Filesystem     1024-blocks    Used Available Capacity Mounted on\n\
+                  /dev/mmcblk0p2    29542476 5332344  22984848      19% /\n";
+        assert_eq!(disk(df), Some(Usage { total_bytes: 29542476 * 1024, used_bytes: 5332344 * 1024 }));
+        assert_eq!(disk(""), None);
+    }
+}
@@ -0,0 +1,348 @@
+// src/install.rs
+//
+// `timeturnerd install`: provision the host for the daemon. It creates a
+// `timeturner` system user, puts config.yml under /etc/timeturner and the
+// daemon's working files under /var/lib/timeturner, gives the user
+// password-less sudo for exactly the clock commands the daemon runs, and
+// writes and enables a systemd unit. The steps are planned first so
+// `--dry-run` can show them without touching anything.
+
+use std::fmt;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub const UNIT_PATH: &str = "/etc/systemd/system/timeturner.service";
+pub const SUDOERS_PATH: &str = "/etc/sudoers.d/timeturner";
+
+/// Programs the daemon runs through `sudo`: see `system`, `rtc` and `phc`.
+const SUDO_TOOLS: &[&str] = &["date", "adjtimex", "hwclock", "systemctl", "phc_ctl"];
+
+/// Groups that own serial ports and video (VBI) devices on Debian-like
+/// systems; the user joins whichever of them exist.
+const DEVICE_GROUPS: &[&str] = &["dialout", "video"];
+
+/// Where `SUDO_TOOLS` are looked up, in order.
+const TOOL_DIRS: &[&str] = &["/usr/bin", "/usr/sbin", "/bin", "/sbin", "/usr/local/bin", "/usr/local/sbin"];
+
+#[derive(Debug, Clone)]
+pub struct InstallOptions {
+    /// The system user the daemon runs as.
+    pub user: String,
+    /// Holds config.yml.
+    pub config_dir: PathBuf,
+    /// The daemon's working directory: the audit log and config backups.
+    pub state_dir: PathBuf,
+    /// The `timeturnerd` binary the unit starts.
+    pub binary: PathBuf,
+    /// Enable and start the service once installed.
+    pub enable: bool,
+}
+
+/// One thing `install` does.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    /// Create a system user and group unless it exists, and add it to
+    /// `groups` where they exist.
+    CreateUser { name: String, home: PathBuf, groups: Vec<String> },
+    CreateDir { path: PathBuf },
+    /// Write `contents` with `mode`; an existing file is kept unless
+    /// `replace` is set.
+    WriteFile { path: PathBuf, contents: String, mode: u32, replace: bool },
+    /// Write a sudoers drop-in, checked with `visudo -c` before it is moved
+    /// into place, since a broken one locks everyone out of sudo.
+    WriteSudoers { path: PathBuf, contents: String },
+    Symlink { link: PathBuf, target: PathBuf },
+    Run(Vec<String>),
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::CreateUser { name, groups, .. } if groups.is_empty() => {
+                write!(f, "create system user {}", name)
+            }
+            Step::CreateUser { name, groups, .. } => {
+                write!(f, "create system user {} (groups: {})", name, groups.join(", "))
+            }
+            Step::CreateDir { path } => write!(f, "create {}", path.display()),
+            Step::WriteFile { path, replace: true, .. } => write!(f, "write {}", path.display()),
+            Step::WriteFile { path, .. } => write!(f, "write {} unless it exists", path.display()),
+            Step::WriteSudoers { path, .. } => write!(f, "write {} (checked with visudo)", path.display()),
+            Step::Symlink { link, target } => write!(f, "link {} -> {}", link.display(), target.display()),
+            Step::Run(args) => write!(f, "run {}", args.join(" ")),
+        }
+    }
+}
+
+/// The systemd unit for `opts`. The daemon runs in the foreground; systemd
+/// restarts it if it exits.
+pub fn unit_file(opts: &InstallOptions) -> String {
+    format!(
+        "[Unit]\n\
+         Description=NTP TimeTurner Daemon\n\
+         After=network-online.target chrony.service\n\
+         Wants=network-online.target\n\
+         \n\
+         [Service]\n\
+         Type=simple\n\
+         ExecStart={binary}\n\
+         WorkingDirectory={state}\n\
+         User={user}\n\
+         Group={user}\n\
+         # Clock changes go through sudo (see {sudoers}); binding the SNMP\n\
+         # port needs CAP_NET_BIND_SERVICE.\n\
+         AmbientCapabilities=CAP_SYS_TIME CAP_NET_BIND_SERVICE\n\
+         Restart=always\n\
+         RestartSec=2\n\
+         \n\
+         [Install]\n\
+         WantedBy=multi-user.target\n",
+        binary = opts.binary.display(),
+        state = opts.state_dir.display(),
+        user = opts.user,
+        sudoers = SUDOERS_PATH,
+    )
+}
+
+/// A sudoers drop-in letting `user` run `tools` as root without a password.
+pub fn sudoers(user: &str, tools: &[PathBuf]) -> String {
+    let list: Vec<String> = tools.iter().map(|t| t.display().to_string()).collect();
+    format!(
+        "# Written by timeturnerd install: the clock commands timeturnerd runs.\n\
+         {} ALL=(root) NOPASSWD: {}\n",
+        user,
+        list.join(", ")
+    )
+}
+
+/// The installed path of each of `SUDO_TOOLS`; missing ones are skipped.
+pub fn find_tools() -> Vec<PathBuf> {
+    SUDO_TOOLS
+        .iter()
+        .filter_map(|tool| {
+            TOOL_DIRS.iter().map(|dir| Path::new(dir).join(tool)).find(|p| p.is_file())
+        })
+        .collect()
+}
+
+/// Everything `install` does for `opts`, in order. `default_config` is
+/// written to config.yml if there is none yet.
+pub fn plan(opts: &InstallOptions, default_config: &str, tools: &[PathBuf]) -> Vec<Step> {
+    let config_file = opts.config_dir.join("config.yml");
+    let owner = format!("{}:{}", opts.user, opts.user);
+    let mut steps = vec![
+        Step::CreateUser {
+            name: opts.user.clone(),
+            home: opts.state_dir.clone(),
+            groups: DEVICE_GROUPS.iter().map(|g| g.to_string()).collect(),
+        },
+        Step::CreateDir { path: opts.config_dir.clone() },
+        Step::CreateDir { path: opts.state_dir.clone() },
+        Step::WriteFile {
+            path: config_file.clone(),
+            contents: default_config.trim().to_string() + "\n",
+            // API tokens live in config.yml.
+            mode: 0o640,
+            replace: false,
+        },
+        // The daemon reads config.yml from its working directory.
+        Step::Symlink { link: opts.state_dir.join("config.yml"), target: config_file },
+        Step::Run(vec!["chown".into(), "-R".into(), owner.clone(), opts.config_dir.display().to_string()]),
+        Step::Run(vec!["chown".into(), "-R".into(), owner, opts.state_dir.display().to_string()]),
+        Step::WriteSudoers { path: PathBuf::from(SUDOERS_PATH), contents: sudoers(&opts.user, tools) },
+        Step::WriteFile { path: PathBuf::from(UNIT_PATH), contents: unit_file(opts), mode: 0o644, replace: true },
+        Step::Run(vec!["systemctl".into(), "daemon-reload".into()]),
+    ];
+    if opts.enable {
+        steps.push(Step::Run(vec![
+            "systemctl".into(),
+            "enable".into(),
+            "--now".into(),
+            "timeturner.service".into(),
+        ]));
+    }
+    steps
+}
+
+fn run(args: &[&str]) -> Result<String, String> {
+    let output = Command::new(args[0])
+        .args(&args[1..])
+        .output()
+        .map_err(|e| format!("cannot run {}: {}", args[0], e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(format!(
+            "{} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+fn write_file(path: &Path, contents: &str, mode: u32) -> Result<(), String> {
+    fs::write(path, contents).map_err(|e| format!("cannot write {}: {}", path.display(), e))?;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .map_err(|e| format!("cannot set permissions on {}: {}", path.display(), e))
+}
+
+/// Carry out one step, returning what was done.
+pub fn apply(step: &Step) -> Result<String, String> {
+    match step {
+        Step::CreateUser { name, home, groups } => {
+            let mut done = if run(&["id", "-u", name]).is_ok() {
+                format!("user {} exists", name)
+            } else {
+                let home = home.display().to_string();
+                run(&[
+                    "useradd", "--system", "--user-group", "--no-create-home", "--home-dir", &home,
+                    "--shell", "/usr/sbin/nologin", name,
+                ])?;
+                format!("created user {}", name)
+            };
+            let present: Vec<&str> = groups
+                .iter()
+                .map(String::as_str)
+                .filter(|g| run(&["getent", "group", g]).is_ok())
+                .collect();
+            if !present.is_empty() {
+                run(&["usermod", "-aG", &present.join(","), name])?;
+                done.push_str(&format!(", in groups {}", present.join(", ")));
+            }
+            Ok(done)
+        }
+        Step::CreateDir { path } => {
+            fs::create_dir_all(path).map_err(|e| format!("cannot create {}: {}", path.display(), e))?;
+            Ok(format!("created {}", path.display()))
+        }
+        Step::WriteFile { path, contents, mode, replace } => {
+            if !replace && path.exists() {
+                return Ok(format!("kept existing {}", path.display()));
+            }
+            write_file(path, contents, *mode)?;
+            Ok(format!("wrote {}", path.display()))
+        }
+        Step::WriteSudoers { path, contents } => {
+            let staged = path.with_extension("new");
+            write_file(&staged, contents, 0o440)?;
+            if let Err(e) = run(&["visudo", "-cf", &staged.display().to_string()]) {
+                let _ = fs::remove_file(&staged);
+                return Err(e);
+            }
+            fs::rename(&staged, path).map_err(|e| format!("cannot move {} into place: {}", path.display(), e))?;
+            Ok(format!("wrote {}", path.display()))
+        }
+        Step::Symlink { link, target } => {
+            if fs::read_link(link).is_ok_and(|t| t == *target) {
+                return Ok(format!("{} already links to {}", link.display(), target.display()));
+            }
+            if link.exists() {
+                return Err(format!(
+                    "{} exists; move it to {} or remove it",
+                    link.display(),
+                    target.display()
+                ));
+            }
+            std::os::unix::fs::symlink(target, link)
+                .map_err(|e| format!("cannot link {}: {}", link.display(), e))?;
+            Ok(format!("linked {} -> {}", link.display(), target.display()))
+        }
+        Step::Run(args) => {
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            run(&args)?;
+            Ok(format!("ran {}", args.join(" ")))
+        }
+    }
+}
+
+/// Whether this process runs as root.
+pub fn is_root() -> bool {
+    run(&["id", "-u"]).is_ok_and(|uid| uid == "0")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> InstallOptions {
+        InstallOptions {
+            user: "timeturner".into(),
+            config_dir: PathBuf::from("/etc/timeturner"),
+            state_dir: PathBuf::from("/var/lib/timeturner"),
+            binary: PathBuf::from("/usr/local/bin/timeturnerd"),
+            enable: true,
+        }
+    }
+
+    #[test]
+    fn test_unit_file_runs_the_binary_as_the_user() {
+        let unit = unit_file(&options());
+        assert!(unit.contains("\nExecStart=/usr/local/bin/timeturnerd\n"));
+        assert!(unit.contains("\nWorkingDirectory=/var/lib/timeturner\n"));
+        assert!(unit.contains("\nUser=timeturner\nGroup=timeturner\n"));
+        assert!(unit.contains("CAP_SYS_TIME"));
+        assert!(unit.contains("\nType=simple\n"));
+    }
+
+    #[test]
+    fn test_sudoers_lists_each_tool() {
+        let tools = vec![PathBuf::from("/usr/bin/date"), PathBuf::from("/usr/sbin/adjtimex")];
+        assert_eq!(
+            sudoers("timeturner", &tools).lines().last(),
+            Some("timeturner ALL=(root) NOPASSWD: /usr/bin/date, /usr/sbin/adjtimex")
+        );
+    }
+
+    #[test]
+    fn test_plan_keeps_config_and_enables_last() {
+        let steps = plan(&options(), "\nhardwareOffsetMs: 20\n", &[]);
+
+        let config = steps
+            .iter()
+            .find_map(|s| match s {
+                Step::WriteFile { path, contents, replace, .. } if path.ends_with("config.yml") => {
+                    Some((contents.clone(), *replace))
+                }
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(config, ("hardwareOffsetMs: 20\n".to_string(), false));
+        assert!(steps.contains(&Step::Symlink {
+            link: PathBuf::from("/var/lib/timeturner/config.yml"),
+            target: PathBuf::from("/etc/timeturner/config.yml"),
+        }));
+        assert_eq!(steps.last().unwrap().to_string(), "run systemctl enable --now timeturner.service");
+
+        let no_enable = InstallOptions { enable: false, ..options() };
+        assert_eq!(
+            plan(&no_enable, "", &[]).last().unwrap().to_string(),
+            "run systemctl daemon-reload"
+        );
+    }
+
+    #[test]
+    fn test_apply_keeps_an_existing_config_and_links_it() {
+        let root = std::env::temp_dir().join(format!("timeturner-install-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let config = root.join("config.yml");
+        fs::write(&config, "hardwareOffsetMs: 55\n").unwrap();
+
+        let write = Step::WriteFile { path: config.clone(), contents: "new\n".into(), mode: 0o640, replace: false };
+        assert!(apply(&write).unwrap().starts_with("kept existing"));
+        assert_eq!(fs::read_to_string(&config).unwrap(), "hardwareOffsetMs: 55\n");
+
+        let link = Step::Symlink { link: root.join("linked.yml"), target: config.clone() };
+        apply(&link).unwrap();
+        // Running install again is harmless.
+        assert!(apply(&link).unwrap().contains("already links"));
+        assert_eq!(fs::read_to_string(root.join("linked.yml")).unwrap(), "hardwareOffsetMs: 55\n");
+
+        let clash = Step::Symlink { link: config.clone(), target: root.join("other.yml") };
+        assert!(apply(&clash).is_err());
+
+        fs::remove_dir_all(&root).ok();
+    }
+}
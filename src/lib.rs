@@ -0,0 +1,55 @@
+// src/lib.rs
+//
+// Shared code for the `timeturnerd` daemon (src/main.rs) and the
+// `timeturner-ctl` client (src/bin/timeturner-ctl.rs).
+
+// The clock commands in `system` report failure as `Err(())`; callers only
+// ever log it.
+#![allow(clippy::result_unit_err)]
+
+pub mod api;
+pub mod assets;
+pub mod audit;
+pub mod auth;
+pub mod auth_backend;
+pub mod chrony;
+pub mod cli;
+pub mod client;
+pub mod clock_gate;
+pub mod config;
+pub mod confirm;
+pub mod export;
+pub mod health;
+pub mod history;
+pub mod host;
+pub mod hooks;
+pub mod install;
+pub mod logger;
+pub mod metrics;
+pub mod ntp_client;
+pub mod phc;
+#[cfg(feature = "pam")]
+pub mod pam;
+pub mod presets;
+pub mod processor;
+pub mod ptp_capture;
+pub mod ramp;
+pub mod rejam;
+pub mod report;
+pub mod rtc;
+pub mod rtp_midi;
+pub mod serial_input;
+pub mod series;
+pub mod sinks;
+pub mod snmp;
+pub mod sources;
+pub mod supervisor;
+pub mod status;
+pub mod sync_logic;
+pub mod syslog;
+pub mod system;
+pub mod time_daemons;
+pub mod timecode;
+pub mod ui;
+pub mod vitc;
+pub mod watchdog;
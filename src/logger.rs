@@ -1,17 +1,122 @@
 use chrono::Local;
 use log::{LevelFilter, Log, Metadata, Record};
 use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 
+use crate::syslog::{Forwarder, SyslogConfig};
+
 const MAX_LOG_ENTRIES: usize = 100;
 
+/// Which records are logged: a default level plus per-module overrides,
+/// written `info,ntp_client=warn,hooks=debug`. Modules are matched by
+/// prefix on the record's target, with or without the crate name, and the
+/// longest match wins.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogFilter {
+    default: LevelFilter,
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+    const INFO: LogFilter = LogFilter {
+        default: LevelFilter::Info,
+        modules: Vec::new(),
+    };
+
+    /// The level that applies to records from `target`.
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        // Targets are module paths; match module names relative to the crate too.
+        let relative = target.split_once("::").map(|(_, rest)| rest);
+        self.modules
+            .iter()
+            .filter(|(module, _)| {
+                std::iter::once(target)
+                    .chain(relative)
+                    .any(|t| t == module || t.strip_prefix(module.as_str()).is_some_and(|r| r.starts_with("::")))
+            })
+            .max_by_key(|(module, _)| module.len())
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    /// The most verbose level any module is logged at.
+    pub fn max_level(&self) -> LevelFilter {
+        self.modules
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self::INFO
+    }
+}
+
+impl FromStr for LogFilter {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let parse_level = |level: &str| {
+            LevelFilter::from_str(level.trim())
+                .map_err(|_| format!("Unknown log level '{}' (use off, error, warn, info, debug or trace)", level.trim()))
+        };
+        let mut filter = LogFilter::INFO;
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((module, level)) if !module.trim().is_empty() => {
+                    filter.modules.push((module.trim().to_string(), parse_level(level)?));
+                }
+                Some(_) => return Err(format!("Missing module name in '{}'", directive)),
+                None => filter.default = parse_level(directive)?,
+            }
+        }
+        Ok(filter)
+    }
+}
+
+impl fmt::Display for LogFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.default.as_str().to_lowercase())?;
+        for (module, level) in &self.modules {
+            write!(f, ",{}={}", module, level.as_str().to_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+/// The filter the installed logger applies; see `set_filter`.
+static FILTER: Mutex<LogFilter> = Mutex::new(LogFilter::INFO);
+
+/// Change what is logged from now on.
+pub fn set_filter(filter: LogFilter) {
+    log::set_max_level(filter.max_level());
+    *FILTER.lock().unwrap() = filter;
+}
+
+/// The filter currently applied.
+pub fn filter() -> LogFilter {
+    FILTER.lock().unwrap().clone()
+}
+
+/// Where records are forwarded; see `set_syslog`.
+static SYSLOG: Mutex<Option<Forwarder>> = Mutex::new(None);
+
+/// Forward records to the collector in `config` from now on, replacing any
+/// earlier one; forwarding stops if no server is set.
+pub fn set_syslog(config: &SyslogConfig) {
+    *SYSLOG.lock().unwrap() = Forwarder::start(config);
+}
+
 struct RingBufferLogger {
     buffer: Arc<Mutex<VecDeque<String>>>,
 }
 
 impl Log for RingBufferLogger {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= LevelFilter::Info
+        metadata.level() <= FILTER.lock().unwrap().level_for(metadata.target())
     }
 
     fn log(&self, record: &Record) {
@@ -26,6 +131,10 @@ impl Log for RingBufferLogger {
             // Also print to stderr for console/daemon logging
             eprintln!("{}", msg);
 
+            if let Some(syslog) = SYSLOG.lock().unwrap().as_ref() {
+                syslog.send(record.level(), record.target(), &record.args().to_string());
+            }
+
             let mut buffer = self.buffer.lock().unwrap();
             if buffer.len() == MAX_LOG_ENTRIES {
                 buffer.pop_front();
@@ -46,7 +155,37 @@ pub fn setup_logger() -> Arc<Mutex<VecDeque<String>>> {
     // We use `set_boxed_logger` to install our custom logger.
     // The `log` crate will then route all log messages to it.
     log::set_boxed_logger(Box::new(logger)).expect("Failed to set logger");
-    log::set_max_level(LevelFilter::Info);
+    log::set_max_level(filter().max_level());
 
     buffer
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display_filter() {
+        let filter: LogFilter = " debug , ntp_client=warn,ntp_timeturner::hooks=TRACE".parse().unwrap();
+        assert_eq!(filter.to_string(), "debug,ntp_client=warn,ntp_timeturner::hooks=trace");
+        assert_eq!(filter.max_level(), LevelFilter::Trace);
+        assert_eq!("".parse::<LogFilter>().unwrap(), LogFilter::default());
+        assert!("loud".parse::<LogFilter>().is_err());
+        assert!("=debug".parse::<LogFilter>().is_err());
+        assert!("hooks=loud".parse::<LogFilter>().is_err());
+    }
+
+    #[test]
+    fn test_module_levels() {
+        let filter: LogFilter = "warn,ntp_client=off,processor=debug,processor::queue=error"
+            .parse()
+            .unwrap();
+        assert_eq!(filter.level_for("ntp_timeturner::api"), LevelFilter::Warn);
+        assert_eq!(filter.level_for("ntp_timeturner::ntp_client"), LevelFilter::Off);
+        assert_eq!(filter.level_for("ntp_timeturner::processor"), LevelFilter::Debug);
+        assert_eq!(filter.level_for("ntp_timeturner::processor::queue"), LevelFilter::Error);
+        // A prefix only matches whole path segments.
+        assert_eq!(filter.level_for("ntp_timeturner::processors"), LevelFilter::Warn);
+        assert_eq!(filter.level_for("actix_server::worker"), LevelFilter::Warn);
+    }
+}
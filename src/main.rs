@@ -1,27 +1,41 @@
 ﻿// src/main.rs
+//
+// `timeturnerd`: the headless daemon. It owns the serial reader, the sync
+// engine and the HTTP API; the TUI lives in `timeturner-ctl`.
 
-mod api;
-mod config;
-mod logger;
-mod serial_input;
-mod sync_logic;
-mod system;
-mod ui;
-
-use crate::api::start_api_server;
-use crate::config::watch_config;
-use crate::serial_input::start_serial_thread;
-use crate::sync_logic::LtcState;
-use crate::ui::start_ui;
+use ntp_timeturner::api::start_api_server;
+use ntp_timeturner::audit::{self, AuditLog};
+use ntp_timeturner::clock_gate::ClockGate;
+use ntp_timeturner::export::Exporter;
+use ntp_timeturner::config::{self, watch_config};
+use ntp_timeturner::serial_input::{self, LinePattern, ReaderSelection};
+use ntp_timeturner::series::Series;
+use ntp_timeturner::sinks::{self, SinkBoard};
+use ntp_timeturner::sources::SourceRegistry;
+use ntp_timeturner::supervisor::{Restart, Supervisor};
+use ntp_timeturner::config::Config;
+use ntp_timeturner::health::Health;
+use ntp_timeturner::history::DeltaHistory;
+use ntp_timeturner::ntp_client::{self, NtpCrossCheck};
+use ntp_timeturner::processor::{self, frame_channel, CorrectionRequest};
+use ntp_timeturner::presets::{self, OffsetPreset};
+use ntp_timeturner::report::Session;
+use ntp_timeturner::sync_logic::LtcState;
+use ntp_timeturner::syslog::SyslogConfig;
+use ntp_timeturner::system::{ClockControl, SystemClock};
+use ntp_timeturner::{
+    install, logger, phc, ptp_capture, rejam, rtc, rtp_midi, snmp, time_daemons, vitc, watchdog,
+};
+use chrono::{DateTime, Local, Utc};
 use clap::Parser;
 use daemonize::Daemonize;
-use serialport;
 
 use std::{
     fs,
-    path::Path,
-    sync::{mpsc, Arc, Mutex},
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc, Mutex, PoisonError},
     thread,
+    time::Duration,
 };
 use tokio::task::{self, LocalSet};
 
@@ -30,6 +44,10 @@ use tokio::task::{self, LocalSet};
 struct Args {
     #[command(subcommand)]
     command: Option<Command>,
+    /// Serve the web UI from this directory instead of the copy built into
+    /// the binary.
+    #[arg(long, global = true, value_name = "DIR")]
+    web_root: Option<PathBuf>,
 }
 
 #[derive(clap::Subcommand, Debug)]
@@ -38,6 +56,25 @@ enum Command {
     Daemon,
     /// Stop the running daemon process.
     Kill,
+    /// Set the host up to run the daemon as a systemd service: a system
+    /// user, /etc/timeturner/config.yml, a sudoers entry for the clock
+    /// commands and the unit itself. Run as root.
+    Install {
+        /// The system user to run the daemon as.
+        #[arg(long, default_value = "timeturner")]
+        user: String,
+        #[arg(long, default_value = "/etc/timeturner", value_name = "DIR")]
+        config_dir: PathBuf,
+        /// The daemon's working directory.
+        #[arg(long, default_value = "/var/lib/timeturner", value_name = "DIR")]
+        state_dir: PathBuf,
+        /// Install the unit without enabling or starting it.
+        #[arg(long)]
+        no_enable: bool,
+        /// Print the steps without carrying them out.
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 /// Default config content, embedded in the binary.
@@ -45,14 +82,127 @@ const DEFAULT_CONFIG: &str = r#"
 # Hardware offset in milliseconds for correcting capture latency.
 hardwareOffsetMs: 20
 
+# Latency of the serial LTC reader, taken off each line's arrival time:
+# fixedUs is the reader's own delay in microseconds, and perCharacter also
+# counts the time to send the line at the port's baud rate. Read at startup.
+readerLatency:
+  fixedUs: 0
+  perCharacter: false
+
+# USB serial devices that may be the LTC reader, matched by hex vid/pid
+# and/or serialNumber whatever port they are plugged into, each with its own
+# baudRate and optional readerLatency. The first one connected is used. When
+# empty, a Teensy or Arduino is preferred over other USB serial devices. e.g.
+#   - vid: "16c0"
+#     serialNumber: "12345"
+#     baudRate: 115200
+serialDevices: []
+
+# Regex for the reader's lines, for firmware that prints them differently.
+# It must name the groups status (LOCK or FREE), hours, minutes, seconds,
+# frames and fps, and may name sep (; for drop-frame). Unset, the standard
+# [LOCK] 10:20:30:00 | 25.00fps format is read. Read at startup.
+ltcLinePattern: null
+
+# What clock the incoming LTC counts: local (the host's time zone, with DST),
+# utc, or a fixed offset from UTC such as +01:00 or -05:00.
+ltcTimeZone: local
+
+# A second frame rate to also show the clock delta in frames at, for mixed
+# setups such as 25 fps LTC feeding 29.97 equipment: 23.98, 24, 25, 29.97,
+# 30, 50, 59.94 or 60. Leave empty to show the incoming rate only.
+secondaryFrameRate: null
+
+# The frame rate the LTC should run at. If frames arrive at another rate
+# for more than 25 frames, an alert is raised (the frameRateMismatch hook)
+# and the status says so. Leave empty to accept any rate.
+expectedFrameRate: null
+
 # Enable automatic clock synchronization.
-# When enabled, the system will perform an initial full sync, then periodically
-# nudge the clock to keep it aligned with the LTC source.
+# When enabled, the system corrects the clock once LTC first locks, then
+# periodically nudges it to keep it aligned with the LTC source.
 autoSyncEnabled: false
 
+# What auto-sync follows: auto (LTC while locked, holdover otherwise), ltc
+# (LTC only), holdover (the drift estimate only) or manual (no automatic
+# corrections). Also set with PUT /api/source.
+syncSource: auto
+
+# Auto-sync steps the clock with `date` when the delta is larger than this
+# (in milliseconds), and slews it with `adjtimex` when it is smaller.
+stepThresholdMs: 40
+
+# Auto-sync, the daily re-jam and scheduled presets only step the clock
+# once this many consecutive frames have been LOCK, at the same frame rate,
+# each following on from the last, so a single corrupted line can never
+# trigger a step to a bogus time.
+stepConsistentFrames: 25
+
+# Auto-sync and the daily re-jam never step the clock by more than this
+# many milliseconds; a larger step is held until an operator syncs with
+# the override (POST /api/sync?override=true, or s twice in the TUI).
+# Offset changes through the API need override=true past it too.
+# 0 turns the limit off, the default, so a board without an RTC can be
+# corrected on the first lock; once it keeps time between restarts, a
+# few seconds (e.g. 2000) stops a bad source jumping the clock.
+maxAutoStepMs: 0
+
+# Hold every step until the next LTC frame 00 and set the clock to that
+# whole second the moment it arrives, rather than to the latest frame,
+# which may already be a frame or more old.
+syncOnSecond: false
+
+# Largest clock delta (system clock minus LTC, positive when ahead), in
+# milliseconds, still reported as IN SYNC.
+inSyncAheadMs: 8
+
+# Most negative clock delta, in milliseconds, still reported as IN SYNC.
+inSyncBehindMs: -8
+
+# Copy the system time to the RTC (/dev/rtc0, e.g. a DS3231 hat) with
+# hwclock once auto-sync has it in sync with LTC, so a reboot without LTC
+# starts close to the right time.
+rtcWriteback: false
+
+# Stop and disable systemd-timesyncd, ntpd and other services that
+# discipline the clock while auto-sync is on, so they do not fight it.
+# Conflicts are shown in the status either way.
+stopCompetingTimeDaemons: false
+
 # Default nudge in milliseconds for adjtimex control.
 defaultNudgeMs: 2
 
+# Most negative nudge accepted by /api/nudge_clock, in microseconds.
+nudgeMinUs: -500000
+
+# Largest nudge accepted by /api/nudge_clock, in microseconds.
+nudgeMaxUs: 500000
+
+# Number of jitter samples (one per LOCK frame) behind the jitter statistics.
+jitterWindow: 20
+
+# API clock commands (sync, nudge, set date) are limited to one per this
+# many milliseconds, and refused while another clock change is running.
+# 0 turns the limit off.
+clockCommandIntervalMs: 1000
+
+# A manual sync that would step the clock by more than this many
+# milliseconds must be previewed (GET /api/sync/preview) and confirmed
+# with the preview's token. 0 turns confirmation off.
+confirmStepMs: 1000
+
+# /api/ready reports not ready once no LTC frame has arrived for this many
+# seconds.
+readyFrameTimeoutSecs: 5
+
+# LTC status becomes NO SIGNAL, and auto-sync falls back to holdover, once
+# no frame has arrived for this many milliseconds.
+signalTimeoutMs: 1000
+
+# Log level (off, error, warn, info, debug or trace), optionally followed by
+# per-module levels, e.g. "info,ntp_client=warn,processor=debug".
+logLevel: info
+
 # Time-turning offsets. All values are added to the incoming LTC time.
 # These can be positive or negative.
 timeturnerOffset:
@@ -61,8 +211,183 @@ timeturnerOffset:
   seconds: 0
   frames: 0
   milliseconds: 0
+
+# Named offsets that timeturnerOffset can be switched to from the API
+# (/api/presets) or the TUI ([P]), or daily at a local time `at` (HH:MM), e.g.
+#   - name: rehearsal
+#     offset: { hours: -1, minutes: 0, seconds: 0, frames: 0 }
+#     at: "18:00"
+offsetPresets: []
+
+# Seconds over which a changed timeturnerOffset is ramped in with adjtimex,
+# running the clock up to 10% fast or slow, so NTP clients downstream are
+# not stepped. 0 steps the clock at once, as does a change too large to ramp.
+offsetRampSecs: 0
+
+# Event hooks. Each command runs via `sh -c` with TIMETURNER_EVENT and details
+# such as TIMETURNER_TIMECODE, TIMETURNER_FRAME_RATE and TIMETURNER_DELTA_MS
+# in the environment. Remove the leading `#` to enable a hook. Hooks are only
+# read from this file; the API can neither show nor change them.
+hooks:
+  deltaThresholdMs: 40
+  # syncPerformed: "/opt/timeturner/hooks/sync.sh"
+  # lockLost: "logger -t timeturner LTC lock lost at $TIMETURNER_TIMECODE"
+  # lockAcquired: "/opt/timeturner/hooks/lock.sh"
+  # deltaExceeded: "/opt/timeturner/hooks/alert.sh"
+  # ntpDisagreement: "/opt/timeturner/hooks/ntp_alert.sh"
+  # frameRateMismatch: "/opt/timeturner/hooks/rate_alert.sh"
+
+# How long the clock delta history behind /api/history is kept, in seconds,
+# at 1 s, 10 s and 1 min resolution. 0 disables a resolution.
+history:
+  secondsRetention: 600
+  tenSecondsRetention: 21600
+  minutesRetention: 1209600
+
+# Daily hard re-jam: step the clock to LTC at this local time (HH:MM, system
+# time zone) every day, unless on air or LTC is not locked. Runs are audited.
+rejam:
+  enabled: false
+  at: "04:00"
+
+# Set while on air (e.g. PATCH /api/config {"onAir": true}); scheduled
+# re-jams are skipped.
+onAir: false
+
+# Cross-check against NTP servers (SNTP). Never steers the clock; a
+# disagreement with LTC above alertThresholdMs is logged, shown at
+# /api/ntp_check and fires the ntpDisagreement hook. No servers disables it.
+# dscp marks the queries for switches with QoS (46 = EF); 0 is best effort.
+ntpCheck:
+  servers: []
+  # servers: ["pool.ntp.org", "192.168.1.1:123"]
+  intervalSecs: 64
+  alertThresholdMs: 100
+  dscp: 0
+
+# Forward log records to a remote syslog collector (RFC 5424) over udp or
+# tcp. server is host or host:port (default 514); no server disables it.
+syslog:
+  # server: "logs.venue.lan:514"
+  protocol: udp
+  facility: daemon
+  appName: timeturner
+
+# Read-only SNMP v1/v2c agent for the NMS, serving docs/TIMETURNER-MIB.txt
+# and the system group. listen is address:port (161 needs root or
+# CAP_NET_BIND_SERVICE); no listen disables it.
+snmp:
+  # listen: "0.0.0.0:161"
+  community: public
+
+# Read VITC from a V4L2 raw VBI device (e.g. /dev/vbi0) instead of the
+# serial LTC reader; standard is "625" (25 fps) or "525" (29.97 fps).
+# Needs v4l2-ctl (v4l-utils). Read at startup.
+vitc:
+  # device: /dev/vbi0
+  standard: "625"
+
+# Send MTC to network MIDI sessions (RTP-MIDI / AppleMIDI), e.g. QLab via
+# Audio MIDI Setup. listen is the control address:port; data uses the
+# next port. No listen disables it.
+rtpMidi:
+  # listen: "0.0.0.0:5004"
+  name: Timeturner
+
+# Steer a NIC's PTP hardware clock (e.g. /dev/ptp0) to the system clock
+# with phc_ctl (linuxptp), so ptp4l as master serves LTC time. The PHC runs
+# on TAI: utcOffsetSecs is TAI - UTC. No device disables it.
+phc:
+  # device: /dev/ptp0
+  utcOffsetSecs: 37
+
+# Deliver the LTC reference once a second to each sink listed: chrony's
+# SOCK refclock (type: chrony, path from `refclock SOCK` in chrony.conf),
+# a PTP hardware clock (type: phc, device, utcOffsetSecs) or a CSV file
+# (type: csv, path). Health is at /api/sinks.
+sinks: []
+# sinks:
+#   - type: chrony
+#     path: /run/chrony.ltc.sock
+#   - type: csv
+#     path: /var/log/timeturner/reference.csv
+
+# Log the PTP messages seen on the network (announce, sync, follow-up,
+# delay) at /api/ptp/messages. Listens only; interface is the IPv4 address
+# of the NIC on the PTP network. Ports 319/320 need root. /api/ptp/masters
+# shows the master the BMCA selects on domain; grandmaster forces the pick.
+ptpCapture:
+  enabled: false
+  # interface: "192.168.1.100"
+  maxMessages: 256
+  domain: 0
+  # grandmaster: "001b21.fffe.123456"
+
+# API access control. With no tokens the API is open to anyone on the network.
+# Roles: viewer (read status/logs/config), operator (also sync, nudge, set date),
+# admin (also change config). Send as `Authorization: Bearer <token>`.
+# api:
+#   tokens:
+#     - token: "change-me-viewer"
+#       role: viewer
+#     - token: "change-me-admin"
+#       role: admin
+#
+# Other backends (set `backend`, default static):
+#   backend: pam     # local accounts over HTTP Basic; build with --features pam
+#   pam:
+#     service: login
+#     users:
+#       - user: operator
+#         role: operator
+#     defaultRole: viewer   # optional, for accounts not listed
+#
+#   backend: oidc    # bearer JWTs from an OpenID Connect provider
+#   oidc:
+#     issuer: "https://idp.example.com/realms/venue"
+#     audience: "timeturner"
+#     jwksUrl: "https://idp.example.com/realms/venue/protocol/openid-connect/certs"
+#     roleClaim: timeturner_role   # string or list of role names
+
+# Schema version of this file, used to upgrade it after a release
+# changes a key. Set by timeturnerd; do not edit.
+configVersion: 1
 "#;
 
+/// Switch `timeturnerOffset` to the preset `name` and save it, returning
+/// the new config and whether the offset changed.
+fn switch_preset(name: &str, config: &Arc<Mutex<Config>>) -> Option<(Config, bool)> {
+    let mut config = config.lock().unwrap();
+    let offset = presets::find(&config.offset_presets, name)?.offset.clone();
+    let changed = config.timeturner_offset != offset;
+    config.timeturner_offset = offset;
+    if let Err(e) = config::save_config("config.yml", &config) {
+        log::error!("Offset preset '{}': cannot save config.yml: {}", name, e);
+    }
+    log::info!("🎚️ Switched to offset preset '{}' on schedule.", name);
+    Some((config.clone(), changed))
+}
+
+/// `timeturnerd install`: print or carry out the provisioning steps,
+/// stopping at the first failure.
+fn install(options: install::InstallOptions, dry_run: bool) -> Result<(), String> {
+    let steps = install::plan(&options, DEFAULT_CONFIG, &install::find_tools());
+    if dry_run {
+        for step in &steps {
+            println!("{}", step);
+        }
+        return Ok(());
+    }
+    if !install::is_root() {
+        return Err("install must be run as root, e.g. sudo timeturnerd install".into());
+    }
+    for step in &steps {
+        let done = install::apply(step).map_err(|e| format!("{}: {}", step, e))?;
+        log::info!("✅ {}", done);
+    }
+    Ok(())
+}
+
 /// If no `config.yml` exists alongside the binary, write out the default.
 fn ensure_config() {
     let p = Path::new("config.yml");
@@ -73,20 +398,6 @@ fn ensure_config() {
     }
 }
 
-fn find_serial_port() -> Option<String> {
-    if let Ok(ports) = serialport::available_ports() {
-        for p in ports {
-            if p.port_name.starts_with("/dev/ttyACM")
-                || p.port_name.starts_with("/dev/ttyAMA")
-                || p.port_name.starts_with("/dev/ttyUSB")
-            {
-                return Some(p.port_name);
-            }
-        }
-    }
-    None
-}
-
 #[tokio::main(flavor = "current_thread")]
 async fn main() {
     // This must be called before any logging statements.
@@ -153,135 +464,416 @@ async fn main() {
                 }
                 return;
             }
+            Command::Install { user, config_dir, state_dir, no_enable, dry_run } => {
+                let binary = std::env::current_exe()
+                    .and_then(fs::canonicalize)
+                    .expect("Cannot find the timeturnerd binary");
+                let options = install::InstallOptions {
+                    user: user.clone(),
+                    config_dir: config_dir.clone(),
+                    state_dir: state_dir.clone(),
+                    binary,
+                    enable: !no_enable,
+                };
+                if let Err(e) = install(options, *dry_run) {
+                    log::error!("❌ Install failed: {}", e);
+                    std::process::exit(1);
+                }
+                return;
+            }
         }
     }
 
     // 🔄 Ensure there's always a config.yml present
     ensure_config();
+    match config::upgrade_config_file(Path::new("config.yml")) {
+        Ok(Some(backup)) => log::info!(
+            "⚙️  Upgraded config.yml to configVersion {}; the old file is at {}",
+            config::CONFIG_VERSION,
+            backup.display()
+        ),
+        Ok(None) => {}
+        Err(e) => log::warn!("config.yml was not upgraded: {}", e),
+    }
 
-    // 1️⃣ Start watching config.yml for changes
-    let config = watch_config("config.yml");
+    // 1️⃣ Start watching config.yml for changes. Without a config that loads
+    // there is no `api` section, so nothing starts rather than an open API.
+    let config = match watch_config("config.yml") {
+        Ok(config) => config,
+        Err(e) => {
+            log::error!("❌ Not starting: {}", e);
+            std::process::exit(1);
+        }
+    };
 
-    // 2️⃣ Channel for raw LTC frames
-    let (tx, rx) = mpsc::channel();
+    // Restarts long-lived components that panic; state at /api/components
+    let supervisor = Supervisor::new();
 
-    // 3️⃣ Shared state for UI and serial reader
+    // Apply `logLevel` and `syslog` now and whenever config.yml changes them;
+    // a level set through `PUT /api/logs/level` lasts until then.
+    {
+        let log_config = config.clone();
+        supervisor.spawn("logging", Restart::Always, move || {
+            let mut applied_level: Option<String> = None;
+            let mut applied_syslog: Option<SyslogConfig> = None;
+            loop {
+                let (level, syslog) = {
+                    let config = log_config.lock().unwrap();
+                    (config.log_level.clone(), config.syslog.clone())
+                };
+                if applied_level.as_ref() != Some(&level) {
+                    match level.parse() {
+                        Ok(filter) => {
+                            logger::set_filter(filter);
+                            log::info!("Log level: {}", level);
+                        }
+                        Err(e) => log::error!("Ignoring logLevel: {}", e),
+                    }
+                    applied_level = Some(level);
+                }
+                if applied_syslog.as_ref() != Some(&syslog) {
+                    logger::set_syslog(&syslog);
+                    match (syslog.address(), syslog.validate()) {
+                        (Some(address), Ok(())) => {
+                            log::info!("Forwarding logs to syslog at {} over {:?}.", address, syslog.protocol)
+                        }
+                        (Some(_), Err(e)) => log::error!("Not forwarding logs: {}", e),
+                        (None, _) => {}
+                    }
+                    applied_syslog = Some(syslog);
+                }
+                thread::sleep(Duration::from_secs(1));
+            }
+        });
+    }
+
+    // 2️⃣ Bounded queue for raw LTC frames, drained by the processor
+    let (tx, rx) = frame_channel();
+
+    // 3️⃣ Sync state, written by the frame processor and read through `ltc`
     let ltc_state = Arc::new(Mutex::new(LtcState::new()));
+    let ltc = ltc_state.lock().unwrap().view();
 
-    // 4️⃣ Find serial port and spawn the serial reader thread
-    let serial_port_path = match find_serial_port() {
-        Some(port) => port,
-        None => {
-            log::error!("❌ No serial port found. Please connect the Teensy device.");
-            return;
-        }
+    // Audit trail of clock mutations, reloaded from disk
+    let audit_log = Arc::new(AuditLog::open(audit::AUDIT_FILE));
+
+    // Worker heartbeats for /api/health and /api/ready
+    let health = Health::new();
+
+    // One clock change at a time, from the API, auto-sync or the re-jam
+    let clock_gate = Arc::new(ClockGate::default());
+    // The clock auto-sync and the schedules change
+    let clock: Arc<dyn ClockControl> = Arc::new(SystemClock);
+
+    // Downsampled delta history for /api/history
+    let delta_history = Arc::new(Mutex::new(DeltaHistory::new(&config.lock().unwrap().history)));
+    // Per-second delta and jitter for /api/series, fed by the frame processor
+    let series = Arc::new(Mutex::new(Series::default()));
+    // Totals for the end-of-show report at /api/report
+    let session = Arc::new(Mutex::new(Session::new(Utc::now(), 0)));
+    // Raw measurement CSV export, started and stopped over the API
+    let export = Arc::new(Mutex::new(Exporter::default()));
+
+    // 4️⃣ Spawn the VITC reader if configured, else find the serial port and
+    // spawn the serial reader thread
+    let vitc = config.lock().unwrap().vitc.clone();
+    let serial = if let Some(device) = vitc.device {
+        let tx_clone = tx.clone();
+        let device_clone = device.clone();
+        supervisor.spawn("vitc", Restart::Always, move || {
+            vitc::start_vitc_thread(&device_clone, vitc.standard, tx_clone.clone())
+        });
+        Arc::new(ReaderSelection::fixed(&device))
+    } else {
+        let (devices, latency, pattern) = {
+            let config = config.lock().unwrap();
+            let pattern = LinePattern::from_config(config.ltc_line_pattern.as_deref()).unwrap_or_else(|e| {
+                log::error!("❌ {}; reading the standard line format.", e);
+                LinePattern::default()
+            });
+            (config.serial_devices.clone(), config.reader_latency, pattern)
+        };
+        let ports = serial_input::available_ports();
+        let reader = match serial_input::choose_reader(&ports, &devices, latency) {
+            Some(reader) => reader,
+            None => {
+                let seen: Vec<&str> = ports.iter().map(|p| p.path.as_str()).collect();
+                if devices.is_empty() {
+                    log::error!("❌ No serial port found. Please connect the Teensy device.");
+                } else {
+                    log::error!("❌ None of the configured serialDevices is connected (ports: {:?}).", seen);
+                }
+                return;
+            }
+        };
+        log::info!("Found serial port: {} @ {} baud", reader.path, reader.baud_rate);
+        let serial = Arc::new(ReaderSelection::serial(reader));
+        let tx_clone = tx.clone();
+        let reader_selection = serial.clone();
+        supervisor.spawn("serial", Restart::Always, move || {
+            serial_input::run_serial_reader(reader_selection.clone(), tx_clone.clone(), pattern.clone())
+        });
+        serial
     };
-    log::info!("Found serial port: {}", serial_port_path);
 
+    // 5️⃣ Logging goes to stderr (captured by systemd, or daemon.err when
+    // forked). The interactive TUI is `timeturner-ctl`, which talks to the API.
+    log::info!("🚀 Starting TimeTurner daemon...");
+
+    // Record the smoothed delta once a second while locked, picking up
+    // retention changes from config as they are made.
     {
-        let tx_clone = tx.clone();
-        let state_clone = ltc_state.clone();
-        let port_clone = serial_port_path.clone();
-        thread::spawn(move || {
-            start_serial_thread(
-                &port_clone,
-                115200,
-                tx_clone,
-                state_clone,
-                0, // ignored in serial path
-            );
+        let history_ltc = ltc.clone();
+        let history_config = config.clone();
+        let history = delta_history.clone();
+        let heartbeat = health.heartbeat("history", Duration::from_secs(10));
+        supervisor.spawn("history", Restart::Always, move || loop {
+            heartbeat.beat();
+            let retention = history_config.lock().unwrap().history.clone();
+            let delta = audit::current_delta(&history_ltc.get());
+            {
+                let mut history = history.lock().unwrap();
+                history.set_config(&retention, Utc::now());
+                if let Some(delta) = delta {
+                    history.record(Utc::now(), delta);
+                }
+            }
+            thread::sleep(Duration::from_secs(1));
         });
     }
 
-    // 5️⃣ Spawn UI or setup daemon logging. The web service is only started
-    // when running as a daemon. The TUI is for interactive foreground use.
-    if args.command.is_none() {
-        // --- Interactive TUI Mode ---
-        log::info!("🔧 Watching config.yml...");
-        log::info!("🚀 Serial thread launched");
-        log::info!("🖥️  UI thread launched");
-        let ui_state = ltc_state.clone();
-        let config_clone = config.clone();
-        let port = serial_port_path;
-        thread::spawn(move || {
-            start_ui(ui_state, port, config_clone);
+    // 6️⃣ Spawn the auto-sync executor. The processor decides; this thread
+    // runs the external `date`/`adjtimex` commands so they never hold up
+    // frame processing.
+    let (corrections, correction_rx) = mpsc::channel::<CorrectionRequest>();
+    {
+        let sync_state = ltc_state.clone();
+        let sync_ltc = ltc.clone();
+        let sync_audit = audit_log.clone();
+        let sync_gate = clock_gate.clone();
+        let sync_clock = clock.clone();
+        let correction_rx = Mutex::new(correction_rx);
+        supervisor.spawn("autosync", Restart::OnPanic, move || {
+            processor::execute(
+                &correction_rx.lock().unwrap_or_else(PoisonError::into_inner),
+                sync_clock.clone(),
+                sync_gate.clone(),
+                sync_state.clone(),
+                sync_ltc.clone(),
+                sync_audit.clone(),
+            )
         });
-    } else {
-        // --- Daemon Mode ---
-        // In daemon mode, logging is already set up to go to stderr.
-        // The systemd service will capture it. The web service (API and static files)
-        // is launched later in the main async block.
-        log::info!("🚀 Starting TimeTurner daemon...");
     }
 
-    // 6️⃣ Spawn the auto-sync thread
+    // Daily re-jam. The next run is recomputed whenever the schedule changes,
+    // so enabling it never fires immediately for a time already passed today.
     {
-        let sync_state = ltc_state.clone();
-        let sync_config = config.clone();
-        thread::spawn(move || {
-            // Wait for the first LTC frame to arrive
+        let rejam_state = ltc_state.clone();
+        let rejam_ltc = ltc.clone();
+        let rejam_config = config.clone();
+        let rejam_audit = audit_log.clone();
+        let rejam_gate = clock_gate.clone();
+        let rejam_clock = clock.clone();
+        let heartbeat = health.heartbeat("rejam", Duration::from_secs(10));
+        supervisor.spawn("rejam", Restart::Always, move || {
+            let mut next: Option<(String, DateTime<Local>)> = None;
             loop {
-                if sync_state.lock().unwrap().latest.is_some() {
-                    log::info!("Auto-sync: Initial LTC frame detected.");
-                    break;
+                thread::sleep(Duration::from_secs(1));
+                heartbeat.beat();
+                let config = rejam_config.lock().unwrap().clone();
+                if !config.rejam.enabled {
+                    next = None;
+                    continue;
                 }
-                thread::sleep(std::time::Duration::from_secs(1));
-            }
-
-            // Initial sync
-            {
-                let state = sync_state.lock().unwrap();
-                let config = sync_config.lock().unwrap();
-                if config.auto_sync_enabled {
-                    if let Some(frame) = &state.latest {
-                        log::info!("Auto-sync: Performing initial full sync.");
-                        if system::trigger_sync(frame, &config).is_ok() {
-                            log::info!("Auto-sync: Initial sync successful.");
-                        } else {
-                            log::error!("Auto-sync: Initial sync failed.");
+                let at = match config.rejam.time() {
+                    Ok(at) => at,
+                    Err(e) => {
+                        if next.take().is_some() {
+                            log::error!("Re-jam: disabled, {}", e);
                         }
+                        continue;
                     }
+                };
+                let now = Local::now();
+                match &next {
+                    Some((spec, due)) if *spec == config.rejam.at => {
+                        if now < *due {
+                            continue;
+                        }
+                        rejam::scheduled_step(
+                            "Re-jam",
+                            config.on_air,
+                            false,
+                            &rejam_clock,
+                            &config,
+                            &rejam_state,
+                            &rejam_ltc,
+                            &rejam_audit,
+                            &rejam_gate,
+                        );
+                    }
+                    _ => {}
+                }
+                let due = rejam::next_run(at, &now);
+                if next.as_ref().map(|(_, d)| *d) != Some(due) {
+                    log::info!("Re-jam: next run at {}.", due.format("%Y-%m-%d %H:%M %Z"));
                 }
+                next = Some((config.rejam.at.clone(), due));
             }
+        });
+    }
 
-            thread::sleep(std::time::Duration::from_secs(10));
-
-            // Main auto-sync loop
+    // Scheduled offset presets. Like the re-jam, the next switch is
+    // recomputed whenever the presets change, so a time already passed today
+    // never fires at once. A switch is made even on air: that is what it is for.
+    {
+        let preset_state = ltc_state.clone();
+        let preset_ltc = ltc.clone();
+        let preset_config = config.clone();
+        let preset_audit = audit_log.clone();
+        let preset_gate = clock_gate.clone();
+        let preset_clock = clock.clone();
+        let heartbeat = health.heartbeat("presets", Duration::from_secs(10));
+        supervisor.spawn("presets", Restart::Always, move || {
+            let mut next: Option<(Vec<OffsetPreset>, String, DateTime<Local>)> = None;
             loop {
-                {
-                    let state = sync_state.lock().unwrap();
-                    let config = sync_config.lock().unwrap();
-
-                    if config.auto_sync_enabled && state.latest.is_some() {
-                        let delta = state.get_ewma_clock_delta();
-                        let frame = state.latest.as_ref().unwrap();
-
-                        if delta.abs() > 40 {
-                            log::info!("Auto-sync: Delta > 40ms ({}ms), performing full sync.", delta);
-                            if system::trigger_sync(frame, &config).is_ok() {
-                                log::info!("Auto-sync: Full sync successful.");
-                            } else {
-                                log::error!("Auto-sync: Full sync failed.");
-                            }
-                        } else if delta.abs() >= 1 {
-                            // nudge_clock takes microseconds. A positive delta means clock is
-                            // ahead, so we need a negative nudge.
-                            let nudge_us = -delta * 1000;
-                            log::info!("Auto-sync: Delta is {}ms, nudging clock by {}us.", delta, nudge_us);
-                            if system::nudge_clock(nudge_us).is_ok() {
-                                log::info!("Auto-sync: Clock nudge successful.");
-                            } else {
-                                log::error!("Auto-sync: Clock nudge failed.");
-                            }
+                thread::sleep(Duration::from_secs(1));
+                heartbeat.beat();
+                let list = preset_config.lock().unwrap().offset_presets.clone();
+                let now = Local::now();
+                if let Some((scheduled, name, due)) = &next {
+                    if *scheduled == list && now >= *due {
+                        if let Some((config, changed)) = switch_preset(name, &preset_config) {
+                            rejam::scheduled_step(
+                                "Offset preset",
+                                false,
+                                changed,
+                                &preset_clock,
+                                &config,
+                                &preset_state,
+                                &preset_ltc,
+                                &preset_audit,
+                                &preset_gate,
+                            );
                         }
                     }
-                } // locks released here
-
-                thread::sleep(std::time::Duration::from_secs(10));
+                }
+                let due = presets::next_due(&list, &now).map(|(p, at)| (p.name.clone(), at));
+                if let Some((name, at)) = &due {
+                    if next.as_ref().map(|(_, n, d)| (n, d)) != Some((name, at)) {
+                        log::info!("Offset preset '{}' scheduled for {}.", name, at.format("%Y-%m-%d %H:%M %Z"));
+                    }
+                }
+                next = due.map(|(name, at)| (list, name, at));
             }
         });
     }
 
+    // Watch for the clock being stepped by anything other than TimeTurner
+    {
+        let watch_state = ltc_state.clone();
+        let watch_ltc = ltc.clone();
+        let watch_gate = clock_gate.clone();
+        let watch_audit = audit_log.clone();
+        let heartbeat = health.heartbeat("watchdog", Duration::from_secs(5));
+        supervisor.spawn("watchdog", Restart::Always, move || {
+            watchdog::run(watch_state.clone(), watch_ltc.clone(), watch_gate.clone(), watch_audit.clone(), heartbeat.clone())
+        });
+    }
+
+    // Keep the RTC set from the clock once it is in sync; idle unless rtcWriteback
+    {
+        let rtc_config = config.clone();
+        let rtc_ltc = ltc.clone();
+        let rtc_audit = audit_log.clone();
+        let heartbeat = health.heartbeat("rtc", Duration::from_secs(5));
+        supervisor.spawn("rtc", Restart::Always, move || {
+            rtc::run(rtc_config.clone(), rtc_ltc.clone(), rtc_audit.clone(), heartbeat.clone())
+        });
+    }
+
+    // Look for other daemons disciplining the clock while auto-sync is on
+    {
+        let daemons_config = config.clone();
+        let heartbeat = health.heartbeat("time_daemons", Duration::from_secs(5));
+        supervisor.spawn("time_daemons", Restart::Always, move || {
+            time_daemons::run(daemons_config.clone(), heartbeat.clone())
+        });
+    }
+
+    // NTP cross-check; reports only, never steers the clock
+    let ntp_check = Arc::new(Mutex::new(NtpCrossCheck::default()));
+    {
+        let ntp_config = config.clone();
+        let ntp_ltc = ltc.clone();
+        let ntp_check = ntp_check.clone();
+        let ntp_session = session.clone();
+        supervisor.spawn("ntp_check", Restart::Always, move || {
+            ntp_client::run(ntp_config.clone(), ntp_ltc.clone(), ntp_check.clone(), ntp_session.clone())
+        });
+    }
+
+    // SNMP agent for the venue NMS; idle until snmp.listen is set
+    {
+        let snmp_config = config.clone();
+        let snmp_ltc = ltc.clone();
+        let snmp_ntp_check = ntp_check.clone();
+        let heartbeat = health.heartbeat("snmp", Duration::from_secs(5));
+        supervisor.spawn("snmp", Restart::Always, move || {
+            snmp::run(snmp_config.clone(), snmp_ltc.clone(), snmp_ntp_check.clone(), heartbeat.clone())
+        });
+    }
+
+    // Keep the NIC's PTP hardware clock on the system clock; idle until phc.device is set
+    {
+        let phc_config = config.clone();
+        let heartbeat = health.heartbeat("phc", Duration::from_secs(5));
+        supervisor.spawn("phc", Restart::Always, move || phc::run(phc_config.clone(), heartbeat.clone()));
+    }
+
+    // MTC for network MIDI sessions; idle until rtpMidi.listen is set
+    let rtp_midi_sessions = Arc::new(Mutex::new(rtp_midi::RtpMidiSessions::default()));
+    {
+        let rtp_config = config.clone();
+        let rtp_ltc = ltc.clone();
+        let rtp_sessions = rtp_midi_sessions.clone();
+        let heartbeat = health.heartbeat("rtp_midi", Duration::from_secs(5));
+        supervisor.spawn("rtp_midi", Restart::Always, move || {
+            rtp_midi::run(rtp_config.clone(), rtp_ltc.clone(), rtp_sessions.clone(), heartbeat.clone())
+        });
+    }
+
+    // PTP message log for debugging the network's masters; idle until ptpCapture.enabled is set
+    let ptp_messages = Arc::new(Mutex::new(ptp_capture::PtpCapture::default()));
+    for (name, port) in [("ptp_event", ptp_capture::EVENT_PORT), ("ptp_general", ptp_capture::GENERAL_PORT)] {
+        let ptp_config = config.clone();
+        let ptp_messages = ptp_messages.clone();
+        let heartbeat = health.heartbeat(name, Duration::from_secs(5));
+        supervisor.spawn(name, Restart::Always, move || {
+            ptp_capture::run(ptp_config.clone(), port, ptp_messages.clone(), heartbeat.clone())
+        });
+    }
+
+    // Time sources for /api/sources; new ones register here
+    let sources = Arc::new(SourceRegistry::standard(
+        ltc.clone(),
+        config.clone(),
+        ntp_check.clone(),
+        ptp_messages.clone(),
+    ));
+
+    // Deliver the reference to the configured sinks; idle until sinks are listed
+    let sink_board = Arc::new(SinkBoard::default());
+    {
+        let sink_config = config.clone();
+        let sink_sources = sources.clone();
+        let board = sink_board.clone();
+        let heartbeat = health.heartbeat("sinks", Duration::from_secs(5));
+        supervisor.spawn("sinks", Restart::Always, move || {
+            sinks::run(sink_config.clone(), sink_sources.clone(), board.clone(), heartbeat.clone())
+        });
+    }
+
     // 7️⃣ Set up a LocalSet for the API server and main loop
     let local = LocalSet::new();
     local
@@ -294,34 +886,65 @@ async fn main() {
                 let api_state = ltc_state.clone();
                 let config_clone = config.clone();
                 let log_buffer_clone = log_buffer.clone();
-                task::spawn_local(async move {
-                    if let Err(e) =
-                        start_api_server(api_state, config_clone, log_buffer_clone).await
-                    {
-                        log::error!("API server error: {}", e);
-                    }
-                });
+                let port = serial.clone();
+                let api_audit = audit_log.clone();
+                let api_history = delta_history.clone();
+                let api_series = series.clone();
+                let api_session = session.clone();
+                let api_ntp_check = ntp_check.clone();
+                let api_rtp_midi = rtp_midi_sessions.clone();
+                let api_ptp_messages = ptp_messages.clone();
+                let web_root = args.web_root.clone();
+                let api_health = health.clone();
+                let api_clock_gate = clock_gate.clone();
+                let api_supervisor = supervisor.clone();
+                let api_sources = sources.clone();
+                let api_sinks = sink_board.clone();
+                let api_export = export.clone();
+                task::spawn_local(supervisor.clone().run_local("api", Restart::Always, move || {
+                    start_api_server(
+                        api_state.clone(),
+                        config_clone.clone(),
+                        log_buffer_clone.clone(),
+                        port.clone(),
+                        api_audit.clone(),
+                        api_history.clone(),
+                        api_series.clone(),
+                        api_session.clone(),
+                        api_ntp_check.clone(),
+                        api_rtp_midi.clone(),
+                        api_ptp_messages.clone(),
+                        web_root.clone(),
+                        api_health.clone(),
+                        api_clock_gate.clone(),
+                        api_supervisor.clone(),
+                        api_sources.clone(),
+                        api_sinks.clone(),
+                        api_export.clone(),
+                    )
+                }));
             }
 
-            // 9️⃣ Main logic loop: process frames from serial and update state
+            // 9️⃣ Frame processor: owns every LTC-driven state change and the
+            // auto-sync decisions
             let loop_state = ltc_state.clone();
             let loop_config = config.clone();
-            let logic_task = task::spawn_blocking(move || {
-                for frame in rx {
-                    let mut state = loop_state.lock().unwrap();
-                    let config = loop_config.lock().unwrap();
-
-                    // Only calculate delta for LOCK frames
-                    if frame.status == "LOCK" {
-                        let target_time = system::calculate_target_time(&frame, &config);
-                        let arrival_time_local: chrono::DateTime<chrono::Local> =
-                            frame.timestamp.with_timezone(&chrono::Local);
-                        let delta = arrival_time_local.signed_duration_since(target_time);
-                        state.record_and_update_ewma_clock_delta(delta.num_milliseconds());
-                    }
-
-                    state.update(frame);
-                }
+            let loop_series = series.clone();
+            let loop_session = session.clone();
+            let loop_export = export.clone();
+            let heartbeat = health.heartbeat("processor", Duration::from_secs(5));
+            let rx = Mutex::new(rx);
+            let logic_task = supervisor.spawn("processor", Restart::OnPanic, move || {
+                processor::run(
+                    &rx.lock().unwrap_or_else(PoisonError::into_inner),
+                    loop_state.clone(),
+                    loop_config.clone(),
+                    loop_series.clone(),
+                    loop_session.clone(),
+                    loop_export.clone(),
+                    corrections.clone(),
+                    heartbeat.clone(),
+                );
             });
 
             // 1️⃣0️⃣ Keep main thread alive
@@ -329,10 +952,10 @@ async fn main() {
                 // In daemon mode, wait forever. The logic_task runs in the background.
                 std::future::pending::<()>().await;
             } else {
-                // In TUI mode, block until the logic_task finishes (e.g. serial port disconnects)
-                // This keeps the TUI running.
+                // In the foreground, exit once the logic_task finishes (the
+                // frame queue closed) so a service manager can restart us.
                 log::info!("📡 Main thread entering loop...");
-                let _ = logic_task.await;
+                let _ = task::spawn_blocking(move || logic_task.join()).await;
             }
         })
         .await;
@@ -0,0 +1,117 @@
+// src/metrics.rs
+//
+// Prometheus text exposition for `/api/metrics`: the state of the LTC lock
+// and the clock as gauges, and every clock mutation counted by source,
+// action and outcome, so auto-sync stepping more often than usual (an early
+// sign of a failing serial link) can be alerted on.
+
+use std::fmt::Write;
+
+use crate::audit::AuditLog;
+use crate::status::StatusSnapshot;
+
+/// The `Content-Type` of the exposition format.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Write one metric family. Samples are label sets (already formatted, empty
+/// for none) and values.
+fn family(out: &mut String, name: &str, kind: &str, help: &str, samples: &[(String, f64)]) {
+    if samples.is_empty() {
+        return;
+    }
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{}{} {}", name, labels, value);
+    }
+}
+
+/// Render the current metrics. Gauges without a value, such as the delta
+/// before the first LOCK, are left out rather than reported as zero.
+pub fn render(status: &StatusSnapshot, audit: &AuditLog) -> String {
+    let mut out = String::new();
+    family(
+        &mut out,
+        "timeturner_ltc_locked",
+        "gauge",
+        "1 if the latest LTC frame was LOCK.",
+        &[(String::new(), if status.locked { 1.0 } else { 0.0 })],
+    );
+    family(
+        &mut out,
+        "timeturner_clock_delta_milliseconds",
+        "gauge",
+        "Smoothed difference between the system clock and LTC.",
+        &status.delta_raw_ms.map(|d| (String::new(), d)).into_iter().collect::<Vec<_>>(),
+    );
+    family(
+        &mut out,
+        "timeturner_drift_ppm",
+        "gauge",
+        "Estimated drift of the system clock against LTC.",
+        &status.drift_ppm.map(|d| (String::new(), d)).into_iter().collect::<Vec<_>>(),
+    );
+    family(
+        &mut out,
+        "timeturner_ltc_continuity_percent",
+        "gauge",
+        "Share of recent LTC frames that followed on from the one before.",
+        &status.continuity.score.map(|s| (String::new(), s)).into_iter().collect::<Vec<_>>(),
+    );
+    let mutations: Vec<(String, f64)> = audit
+        .mutation_counts()
+        .into_iter()
+        .map(|((source, action, success), count)| {
+            let result = if success { "success" } else { "failure" };
+            let labels = format!("{{source=\"{}\",action=\"{}\",result=\"{}\"}}", source, action, result);
+            (labels, count as f64)
+        })
+        .collect();
+    family(
+        &mut out,
+        "timeturner_clock_mutations_total",
+        "counter",
+        "Clock mutations since startup, by source, action and result.",
+        &mutations,
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{AuditEntry, AuditSource};
+    use crate::config::Config;
+    use crate::sync_logic::LtcState;
+
+    #[test]
+    fn test_render() {
+        let audit = AuditLog::in_memory();
+        let status = StatusSnapshot::new(&LtcState::new().snapshot(), &Config::default());
+        let text = render(&status, &audit);
+        assert_eq!(
+            text,
+            "# HELP timeturner_ltc_locked 1 if the latest LTC frame was LOCK.\n\
+             # TYPE timeturner_ltc_locked gauge\n\
+             timeturner_ltc_locked 0\n"
+        );
+
+        for (source, action, success) in [
+            (AuditSource::Auto, "sync", true),
+            (AuditSource::Auto, "sync", true),
+            (AuditSource::Api, "nudge", false),
+        ] {
+            let mut entry = AuditEntry::new(source, action, String::new(), None);
+            entry.success = success;
+            audit.record(entry);
+        }
+        let text = render(&status, &audit);
+        assert!(text.contains("# TYPE timeturner_clock_mutations_total counter\n"), "{}", text);
+        assert!(text.contains(
+            "timeturner_clock_mutations_total{source=\"api\",action=\"nudge\",result=\"failure\"} 1\n"
+        ));
+        assert!(text.contains(
+            "timeturner_clock_mutations_total{source=\"auto\",action=\"sync\",result=\"success\"} 2\n"
+        ));
+    }
+}
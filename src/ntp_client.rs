@@ -0,0 +1,382 @@
+// src/ntp_client.rs
+//
+// SNTP cross-check: periodically ask configured NTP servers for the system
+// clock's offset and compare it with the LTC-derived delta. This never steers
+// the clock; a large disagreement is logged, fires the `ntpDisagreement` hook
+// and shows up at `/api/ntp_check`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+use crate::audit;
+use crate::config::Config;
+use crate::hooks::{self, HookEvent};
+use crate::report::Session;
+use crate::sync_logic::LtcView;
+
+/// Seconds from the NTP epoch (1900) to the Unix epoch (1970).
+const NTP_UNIX_OFFSET: i64 = 2_208_988_800;
+
+/// How long to wait for each server's reply.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NtpCheckConfig {
+    /// `host` or `host:port`. Empty disables the cross-check.
+    #[serde(default)]
+    pub servers: Vec<String>,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// Alert when NTP and LTC disagree by more than this.
+    #[serde(default = "default_alert_threshold_ms")]
+    pub alert_threshold_ms: i64,
+    /// DSCP codepoint (0-63) to mark queries with, so switches that
+    /// prioritise timing traffic queue them ahead of bulk data. 0 is best
+    /// effort; 46 (EF) is the usual choice.
+    #[serde(default)]
+    pub dscp: u8,
+}
+
+fn default_interval_secs() -> u64 {
+    64
+}
+
+fn default_alert_threshold_ms() -> i64 {
+    100
+}
+
+impl Default for NtpCheckConfig {
+    fn default() -> Self {
+        Self {
+            servers: Vec::new(),
+            interval_secs: default_interval_secs(),
+            alert_threshold_ms: default_alert_threshold_ms(),
+            dscp: 0,
+        }
+    }
+}
+
+/// One SNTP exchange, in milliseconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Measurement {
+    /// How far the system clock is behind the server (negative: ahead).
+    pub offset_ms: f64,
+    /// Round-trip network delay.
+    pub delay_ms: f64,
+}
+
+/// The latest result for one server.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NtpSample {
+    pub server: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub timestamp: DateTime<Utc>,
+    /// How far the system clock is behind the server, if it answered.
+    pub offset_ms: Option<i64>,
+    pub delay_ms: Option<i64>,
+    /// NTP time minus LTC-derived time, while LTC is locked.
+    pub disagreement_ms: Option<i64>,
+    /// Whether `disagreementMs` is over `ntpCheck.alertThresholdMs`.
+    pub alert: bool,
+    pub error: Option<String>,
+}
+
+fn to_ntp(t: DateTime<Utc>) -> [u8; 8] {
+    let secs = (t.timestamp() + NTP_UNIX_OFFSET) as u32;
+    let frac = ((t.timestamp_subsec_nanos() as u64) << 32) / 1_000_000_000;
+    let mut out = [0u8; 8];
+    out[..4].copy_from_slice(&secs.to_be_bytes());
+    out[4..].copy_from_slice(&(frac as u32).to_be_bytes());
+    out
+}
+
+/// NTP timestamp as Unix milliseconds.
+fn from_ntp(bytes: &[u8]) -> f64 {
+    let secs = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as i64 - NTP_UNIX_OFFSET;
+    let frac = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]) as f64 / 4_294_967_296.0;
+    (secs as f64 + frac) * 1000.0
+}
+
+fn millis(t: DateTime<Utc>) -> f64 {
+    t.timestamp_micros() as f64 / 1000.0
+}
+
+/// An SNTPv4 client request sent at `t1`.
+pub fn request_packet(t1: DateTime<Utc>) -> [u8; 48] {
+    let mut packet = [0u8; 48];
+    packet[0] = 0x23; // LI 0, version 4, mode 3 (client)
+    packet[40..48].copy_from_slice(&to_ntp(t1));
+    packet
+}
+
+/// Check a server reply to a request sent at `t1` and received at `t4`.
+pub fn parse_response(packet: &[u8], t1: DateTime<Utc>, t4: DateTime<Utc>) -> Result<Measurement, String> {
+    if packet.len() < 48 {
+        return Err("short reply".into());
+    }
+    if packet[0] & 0x07 != 4 {
+        return Err("reply is not from a server".into());
+    }
+    if packet[1] == 0 {
+        let code = String::from_utf8_lossy(&packet[12..16]).into_owned();
+        return Err(format!("kiss-o'-death {}", code.trim_end_matches('\0')));
+    }
+    if packet[0] >> 6 == 3 {
+        return Err("server is unsynchronized".into());
+    }
+    if packet[24..32] != to_ntp(t1) {
+        return Err("reply does not match request".into());
+    }
+    let (t1, t4) = (millis(t1), millis(t4));
+    let t2 = from_ntp(&packet[32..40]);
+    let t3 = from_ntp(&packet[40..48]);
+    Ok(Measurement {
+        offset_ms: ((t2 - t1) + (t3 - t4)) / 2.0,
+        delay_ms: (t4 - t1) - (t3 - t2),
+    })
+}
+
+/// Mark everything sent on `socket` with `dscp`: the upper six bits of the
+/// IPv4 TOS byte or the IPv6 traffic class.
+pub fn set_dscp(socket: &UdpSocket, v6: bool, dscp: u8) -> std::io::Result<()> {
+    let tos = u32::from(dscp) << 2;
+    let socket = socket2::SockRef::from(socket);
+    if v6 {
+        socket.set_tclass_v6(tos)
+    } else {
+        socket.set_tos_v4(tos)
+    }
+}
+
+/// Query one server; `server` is `host` or `host:port` (default 123).
+/// Requests are marked with `dscp` unless it is 0.
+pub fn query(server: &str, timeout: Duration, dscp: u8) -> Result<Measurement, String> {
+    // `host:port` and `[v6]:port` resolve as given; bare hosts get port 123.
+    let addr = server
+        .to_socket_addrs()
+        .or_else(|_| (server, 123).to_socket_addrs())
+        .map_err(|e| e.to_string())?
+        .next()
+        .ok_or_else(|| format!("{} did not resolve", server))?;
+    let bind = if addr.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+    let socket = UdpSocket::bind(bind).map_err(|e| e.to_string())?;
+    socket.set_read_timeout(Some(timeout)).map_err(|e| e.to_string())?;
+    if dscp != 0 {
+        set_dscp(&socket, addr.is_ipv6(), dscp).map_err(|e| format!("setting DSCP {}: {}", dscp, e))?;
+    }
+    socket.connect(addr).map_err(|e| e.to_string())?;
+
+    let t1 = Utc::now();
+    socket.send(&request_packet(t1)).map_err(|e| e.to_string())?;
+    let mut buf = [0u8; 68];
+    let len = socket.recv(&mut buf).map_err(|e| e.to_string())?;
+    parse_response(&buf[..len], t1, Utc::now())
+}
+
+/// NTP time minus LTC-derived time. The smoothed LTC delta is how far the
+/// system clock is ahead of LTC; the NTP offset is how far it is behind NTP.
+pub fn disagreement_ms(offset_ms: f64, ltc_delta_ms: Option<i64>) -> Option<i64> {
+    ltc_delta_ms.map(|delta| (offset_ms + delta as f64).round() as i64)
+}
+
+/// Latest sample per server, served at `/api/ntp_check`.
+#[derive(Default)]
+pub struct NtpCrossCheck {
+    samples: Vec<NtpSample>,
+}
+
+impl NtpCrossCheck {
+    /// Store `sample`, replacing the server's previous one. Returns whether
+    /// it newly raised an alert.
+    pub fn record(&mut self, sample: NtpSample) -> bool {
+        let was_alerting = self
+            .samples
+            .iter()
+            .any(|s| s.server == sample.server && s.alert);
+        let raised = sample.alert && !was_alerting;
+        self.samples.retain(|s| s.server != sample.server);
+        self.samples.push(sample);
+        raised
+    }
+
+    /// Drop servers no longer configured.
+    pub fn retain_servers(&mut self, servers: &[String]) {
+        self.samples.retain(|s| servers.contains(&s.server));
+    }
+
+    pub fn samples(&self) -> Vec<NtpSample> {
+        self.samples.clone()
+    }
+}
+
+/// Build the sample for one query result. No alert is raised while a
+/// timeturner offset deliberately moves the clock away from real time.
+pub fn sample(
+    server: &str,
+    result: Result<Measurement, String>,
+    ltc_delta_ms: Option<i64>,
+    config: &Config,
+) -> NtpSample {
+    let (offset_ms, delay_ms, error) = match result {
+        Ok(m) => (Some(m.offset_ms), Some(m.delay_ms.round() as i64), None),
+        Err(e) => (None, None, Some(e)),
+    };
+    let disagreement = offset_ms.and_then(|o| disagreement_ms(o, ltc_delta_ms));
+    let alert = !config.timeturner_offset.is_active()
+        && disagreement.is_some_and(|d| d.abs() > config.ntp_check.alert_threshold_ms);
+    NtpSample {
+        server: server.to_string(),
+        timestamp: Utc::now(),
+        offset_ms: offset_ms.map(|o| o.round() as i64),
+        delay_ms,
+        disagreement_ms: disagreement,
+        alert,
+        error,
+    }
+}
+
+/// Poll the configured servers forever. Run on its own thread.
+pub fn run(
+    config: Arc<Mutex<Config>>,
+    ltc: LtcView,
+    check: Arc<Mutex<NtpCrossCheck>>,
+    session: Arc<Mutex<Session>>,
+) {
+    loop {
+        let config = config.lock().unwrap().clone();
+        check.lock().unwrap().retain_servers(&config.ntp_check.servers);
+        for server in &config.ntp_check.servers {
+            let result = query(server, QUERY_TIMEOUT, config.ntp_check.dscp);
+            let delta = audit::current_delta(&ltc.get());
+            let sample = sample(server, result, delta, &config);
+            match (&sample.error, sample.disagreement_ms) {
+                (Some(e), _) => log::warn!("NTP check: {} failed: {}", server, e),
+                (None, Some(d)) if sample.alert => log::warn!(
+                    "⚠️ NTP check: {} disagrees with LTC by {}ms (threshold {}ms).",
+                    server, d, config.ntp_check.alert_threshold_ms
+                ),
+                _ => {}
+            }
+            if check.lock().unwrap().record(sample.clone()) {
+                session.lock().unwrap().record_alert(
+                    sample.timestamp,
+                    HookEvent::NtpDisagreement,
+                    format!("{} by {} ms", server, sample.disagreement_ms.unwrap_or_default()),
+                );
+                hooks::fire(
+                    &config.hooks,
+                    HookEvent::NtpDisagreement,
+                    &[
+                        ("TIMETURNER_NTP_SERVER", server.clone()),
+                        ("TIMETURNER_DELTA_MS", sample.disagreement_ms.unwrap_or_default().to_string()),
+                    ],
+                );
+            }
+        }
+        // Check back soon when disabled, so newly added servers are picked up.
+        let interval = if config.ntp_check.servers.is_empty() { 5 } else { config.ntp_check.interval_secs };
+        thread::sleep(Duration::from_secs(interval.max(1)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TimeturnerOffset;
+
+    /// A server reply to `request`, with the server clock `skew_ms` ahead.
+    fn reply(request: &[u8], received: DateTime<Utc>, skew_ms: i64) -> [u8; 48] {
+        let server_time = received + chrono::Duration::milliseconds(skew_ms);
+        let mut packet = [0u8; 48];
+        packet[0] = 0x24; // version 4, mode 4 (server)
+        packet[1] = 2; // stratum
+        packet[24..32].copy_from_slice(&request[40..48]);
+        packet[32..40].copy_from_slice(&to_ntp(server_time));
+        packet[40..48].copy_from_slice(&to_ntp(server_time));
+        packet
+    }
+
+    #[test]
+    fn test_parse_response_offset_and_delay() {
+        let t1 = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let request = request_packet(t1);
+        // Server 250 ms ahead, 20 ms each way.
+        let packet = reply(&request, t1 + chrono::Duration::milliseconds(20), 250);
+        let t4 = t1 + chrono::Duration::milliseconds(40);
+        let m = parse_response(&packet, t1, t4).unwrap();
+        assert!((m.offset_ms - 250.0).abs() < 0.01, "{:?}", m);
+        assert!((m.delay_ms - 40.0).abs() < 0.01, "{:?}", m);
+    }
+
+    #[test]
+    fn test_parse_response_rejects_bad_replies() {
+        let t1 = Utc::now();
+        let request = request_packet(t1);
+        let good = reply(&request, t1, 0);
+        assert!(parse_response(&good[..40], t1, t1).is_err());
+
+        let mut kod = good;
+        kod[1] = 0;
+        kod[12..16].copy_from_slice(b"RATE");
+        assert_eq!(parse_response(&kod, t1, t1).unwrap_err(), "kiss-o'-death RATE");
+
+        let other = request_packet(t1 + chrono::Duration::seconds(1));
+        assert!(parse_response(&reply(&other, t1, 0), t1, t1).is_err());
+    }
+
+    #[test]
+    fn test_disagreement_and_alerts() {
+        let config = Config::default();
+        // System clock 30 ms ahead of LTC and 30 ms ahead of NTP: they agree.
+        assert_eq!(disagreement_ms(-30.0, Some(30)), Some(0));
+        assert_eq!(disagreement_ms(-30.0, None), None);
+
+        let ok = Ok(Measurement { offset_ms: 500.0, delay_ms: 3.0 });
+        let s = sample("ntp.example", ok.clone(), Some(0), &config);
+        assert!(s.alert);
+        assert_eq!(s.disagreement_ms, Some(500));
+
+        // A deliberate timeturner offset is not a fault.
+        let turned = Config {
+            timeturner_offset: TimeturnerOffset { hours: 1, ..TimeturnerOffset::default() },
+            ..Config::default()
+        };
+        assert!(!sample("ntp.example", ok.clone(), Some(0), &turned).alert);
+
+        let mut check = NtpCrossCheck::default();
+        assert!(check.record(sample("ntp.example", ok.clone(), Some(0), &config)));
+        assert!(!check.record(sample("ntp.example", ok.clone(), Some(0), &config)), "alert is edge-triggered");
+        check.record(sample("ntp.example", Err("timeout".into()), Some(0), &config));
+        assert_eq!(check.samples().len(), 1);
+        assert!(check.record(sample("ntp.example", ok.clone(), Some(0), &config)));
+        check.retain_servers(&[]);
+        assert!(check.samples().is_empty());
+    }
+
+    #[test]
+    fn test_query_local_server() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            let mut buf = [0u8; 48];
+            let (_, from) = server.recv_from(&mut buf).unwrap();
+            server.send_to(&reply(&buf, Utc::now(), -1000), from).unwrap();
+        });
+        let m = query(&addr, QUERY_TIMEOUT, 46).unwrap();
+        assert!((m.offset_ms + 1000.0).abs() < 50.0, "{:?}", m);
+    }
+
+    #[test]
+    fn test_set_dscp_marks_socket() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        set_dscp(&socket, false, 46).unwrap();
+        assert_eq!(socket2::SockRef::from(&socket).tos_v4().unwrap(), 46 << 2);
+    }
+}
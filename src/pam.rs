@@ -0,0 +1,114 @@
+// src/pam.rs
+//
+// Minimal libpam binding for the `pam` API auth backend: authenticate one
+// user/password pair against a service and check the account is usable.
+// Only built with `--features pam`.
+
+use std::ffi::{c_char, c_int, c_void, CString};
+use std::ptr;
+
+const PAM_SUCCESS: c_int = 0;
+const PAM_BUF_ERR: c_int = 5;
+const PAM_CONV_ERR: c_int = 19;
+const PAM_PROMPT_ECHO_OFF: c_int = 1;
+const PAM_PROMPT_ECHO_ON: c_int = 2;
+
+#[repr(C)]
+struct PamMessage {
+    msg_style: c_int,
+    msg: *const c_char,
+}
+
+#[repr(C)]
+struct PamResponse {
+    resp: *mut c_char,
+    resp_retcode: c_int,
+}
+
+#[repr(C)]
+struct PamConv {
+    conv: extern "C" fn(c_int, *mut *const PamMessage, *mut *mut PamResponse, *mut c_void) -> c_int,
+    appdata_ptr: *mut c_void,
+}
+
+#[link(name = "pam")]
+extern "C" {
+    fn pam_start(service: *const c_char, user: *const c_char, conv: *const PamConv, pamh: *mut *mut c_void) -> c_int;
+    fn pam_authenticate(pamh: *mut c_void, flags: c_int) -> c_int;
+    fn pam_acct_mgmt(pamh: *mut c_void, flags: c_int) -> c_int;
+    fn pam_end(pamh: *mut c_void, status: c_int) -> c_int;
+    fn pam_strerror(pamh: *mut c_void, errnum: c_int) -> *const c_char;
+}
+
+// From libc; PAM frees the responses with free().
+extern "C" {
+    fn calloc(nmemb: usize, size: usize) -> *mut c_void;
+    fn strdup(s: *const c_char) -> *mut c_char;
+}
+
+/// Answers every prompt with the password (`appdata`), which is what the
+/// usual pam_unix stack asks for. Informational messages get empty replies.
+extern "C" fn converse(
+    num_msg: c_int,
+    msg: *mut *const PamMessage,
+    resp: *mut *mut PamResponse,
+    appdata: *mut c_void,
+) -> c_int {
+    if num_msg <= 0 || msg.is_null() || resp.is_null() {
+        return PAM_CONV_ERR;
+    }
+    let n = num_msg as usize;
+    // SAFETY: PAM passes `num_msg` valid message pointers and expects a
+    // calloc'd response array it will free; `appdata` is the CString owned
+    // by `authenticate` for the duration of the PAM calls.
+    unsafe {
+        let replies = calloc(n, std::mem::size_of::<PamResponse>()) as *mut PamResponse;
+        if replies.is_null() {
+            return PAM_BUF_ERR;
+        }
+        for i in 0..n {
+            let m = &**msg.add(i);
+            if m.msg_style == PAM_PROMPT_ECHO_OFF || m.msg_style == PAM_PROMPT_ECHO_ON {
+                (*replies.add(i)).resp = strdup(appdata as *const c_char);
+            }
+        }
+        *resp = replies;
+    }
+    PAM_SUCCESS
+}
+
+/// Check `password` for `user` with the PAM `service` (e.g. `login`).
+pub fn authenticate(service: &str, user: &str, password: &str) -> Result<(), String> {
+    let service = CString::new(service).map_err(|e| e.to_string())?;
+    let user = CString::new(user).map_err(|e| e.to_string())?;
+    let password = CString::new(password).map_err(|e| e.to_string())?;
+    let conv = PamConv {
+        conv: converse,
+        appdata_ptr: password.as_ptr() as *mut c_void,
+    };
+    let mut handle = ptr::null_mut();
+    // SAFETY: every pointer handed to PAM outlives the handle, which is
+    // always released with pam_end.
+    unsafe {
+        let rc = pam_start(service.as_ptr(), user.as_ptr(), &conv, &mut handle);
+        if rc != PAM_SUCCESS {
+            return Err(format!("pam_start failed ({})", rc));
+        }
+        let mut rc = pam_authenticate(handle, 0);
+        if rc == PAM_SUCCESS {
+            rc = pam_acct_mgmt(handle, 0);
+        }
+        let result = if rc == PAM_SUCCESS {
+            Ok(())
+        } else {
+            let msg = pam_strerror(handle, rc);
+            Err(if msg.is_null() {
+                format!("PAM error {}", rc)
+            } else {
+                std::ffi::CStr::from_ptr(msg).to_string_lossy().into_owned()
+            })
+        };
+        pam_end(handle, rc);
+        result
+    }
+}
@@ -0,0 +1,231 @@
+// src/phc.rs
+//
+// PHC steering: keep a NIC's PTP hardware clock on the LTC-disciplined system
+// clock, like `phc2sys` in reverse, so `ptp4l` running as a hardware
+// timestamping master serves LTC-accurate time. Once a second the PHC is
+// compared with the system clock through `phc_ctl` (linuxptp); a PI servo
+// sets its frequency, and an error beyond `STEP_THRESHOLD_NS`, such as after
+// auto-sync steps the system clock, steps it instead. The PHC runs on TAI, so
+// it is steered to the system clock plus `utcOffsetSecs`.
+
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+use crate::config::Config;
+use crate::health::Heartbeat;
+
+const INTERVAL: Duration = Duration::from_secs(1);
+
+/// Errors larger than this are stepped rather than slewed.
+const STEP_THRESHOLD_NS: i64 = 1_000_000;
+
+/// Servo gains, per second: the defaults `phc2sys` uses for hardware clocks.
+const KP: f64 = 0.7;
+const KI: f64 = 0.3;
+
+/// Frequency corrections are kept within what any PHC driver accepts.
+const MAX_FREQUENCY_PPB: f64 = 100_000.0;
+
+/// TAI - UTC since 1 January 2017.
+const DEFAULT_UTC_OFFSET_SECS: i64 = 37;
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PhcConfig {
+    /// The PHC to steer, e.g. `/dev/ptp0` (see `ethtool -T <nic>`). Off
+    /// while this is unset.
+    #[serde(default)]
+    pub device: Option<String>,
+    /// TAI - UTC, in seconds, added to the system clock for the PHC.
+    #[serde(default = "default_utc_offset_secs")]
+    pub utc_offset_secs: i64,
+}
+
+pub(crate) fn default_utc_offset_secs() -> i64 {
+    DEFAULT_UTC_OFFSET_SECS
+}
+
+impl Default for PhcConfig {
+    fn default() -> Self {
+        Self {
+            device: None,
+            utc_offset_secs: default_utc_offset_secs(),
+        }
+    }
+}
+
+impl PhcConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.device.as_ref().is_some_and(|d| d.is_empty()) {
+            return Err("phc.device must not be empty".into());
+        }
+        if !(0..=100).contains(&self.utc_offset_secs) {
+            return Err("phc.utcOffsetSecs must be between 0 and 100".into());
+        }
+        Ok(())
+    }
+}
+
+/// What the servo wants done with the PHC.
+#[derive(Debug, PartialEq)]
+pub enum PhcCorrection {
+    /// Set it to the system clock plus the UTC offset.
+    Step,
+    /// Run it this many ppb fast (negative: slow).
+    Frequency(f64),
+}
+
+/// PI servo from the PHC's error to its frequency. The integral term holds
+/// the oscillator's own frequency error once locked.
+#[derive(Debug, Default)]
+pub struct Servo {
+    integral_ppb: f64,
+    stepped: bool,
+}
+
+impl Servo {
+    /// Feed the PHC's error (target minus PHC, in ns) from one comparison,
+    /// `INTERVAL` after the last.
+    pub fn sample(&mut self, error_ns: i64) -> PhcCorrection {
+        if !self.stepped || error_ns.abs() > STEP_THRESHOLD_NS {
+            self.stepped = true;
+            return PhcCorrection::Step;
+        }
+        let error = error_ns as f64 / INTERVAL.as_secs_f64();
+        self.integral_ppb = (self.integral_ppb + KI * error).clamp(-MAX_FREQUENCY_PPB, MAX_FREQUENCY_PPB);
+        PhcCorrection::Frequency((KP * error + self.integral_ppb).clamp(-MAX_FREQUENCY_PPB, MAX_FREQUENCY_PPB))
+    }
+}
+
+/// The PHC's offset from the system clock (system minus PHC, in ns) from
+/// `phc_ctl cmp` output, e.g. `phc_ctl[12.345]: offset from CLOCK_REALTIME is
+/// -37000000123ns`.
+fn parse_offset(output: &str) -> Option<i64> {
+    output
+        .lines()
+        .find_map(|line| line.split_once("offset from CLOCK_REALTIME is ")?.1.strip_suffix("ns")?.trim().parse().ok())
+}
+
+/// Run `sudo phc_ctl <device> <args>`, returning its output.
+fn phc_ctl(device: &str, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("sudo")
+        .arg("phc_ctl")
+        .arg(device)
+        .args(args)
+        .output()
+        .map_err(|e| format!("cannot run phc_ctl: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("phc_ctl {} failed: {}", args.join(" "), stderr.trim()));
+    }
+    // phc_ctl reports through its log, which goes to stderr by default.
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned() + &String::from_utf8_lossy(&output.stderr))
+}
+
+/// One servo pass toward the system clock plus `target_ns`: compare, then
+/// step or set the frequency.
+pub(crate) fn steer(device: &str, target_ns: i64, servo: &mut Servo) -> Result<(), String> {
+    let output = phc_ctl(device, &["cmp"])?;
+    let offset = parse_offset(&output).ok_or_else(|| format!("unexpected phc_ctl output: {}", output.trim()))?;
+    let error_ns = offset + target_ns;
+    match servo.sample(error_ns) {
+        PhcCorrection::Step => {
+            let adj = format!("{:.9}", target_ns as f64 / 1e9);
+            phc_ctl(device, &["set", "adj", &adj])?;
+            log::info!("PHC {} stepped by {:.3} ms to the system clock.", device, error_ns as f64 / 1e6);
+        }
+        PhcCorrection::Frequency(ppb) => {
+            phc_ctl(device, &["freq", &format!("{:.0}", ppb)])?;
+            log::debug!("PHC {}: error {} ns, frequency {:+.0} ppb.", device, error_ns, ppb);
+        }
+    }
+    Ok(())
+}
+
+/// Steer `phc.device` until the process exits. A change of device starts
+/// the servo over.
+pub fn run(config: Arc<Mutex<Config>>, heartbeat: Heartbeat) {
+    let mut servo = Servo::default();
+    let mut steering: Option<String> = None;
+    let mut failing = false;
+    loop {
+        heartbeat.beat();
+        thread::sleep(INTERVAL);
+        let phc = config.lock().unwrap().phc.clone();
+        if phc.device != steering {
+            servo = Servo::default();
+            steering = phc.device.clone();
+            failing = false;
+            if let Some(device) = &steering {
+                log::info!("Steering PHC {} from the system clock.", device);
+            }
+        }
+        let Some(device) = &steering else {
+            continue;
+        };
+        match steer(device, phc.utc_offset_secs * 1_000_000_000, &mut servo) {
+            Ok(()) if failing => {
+                log::info!("PHC {} steering resumed.", device);
+                failing = false;
+            }
+            Ok(()) => {}
+            Err(e) => {
+                // Log once per outage rather than every second.
+                if !failing {
+                    log::error!("PHC {}: {}", device, e);
+                }
+                failing = true;
+                servo = Servo::default();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_offset() {
+        assert_eq!(
+            parse_offset("phc_ctl[2751.012]: offset from CLOCK_REALTIME is -37000000123ns\n"),
+            Some(-37_000_000_123)
+        );
+        assert_eq!(parse_offset("phc_ctl[1.0]: offset from CLOCK_REALTIME is 42ns"), Some(42));
+        assert_eq!(parse_offset("failed to open clock /dev/ptp9"), None);
+    }
+
+    #[test]
+    fn test_servo() {
+        let mut servo = Servo::default();
+        // Always step first, then slew small errors.
+        assert_eq!(servo.sample(10), PhcCorrection::Step);
+        let PhcCorrection::Frequency(ppb) = servo.sample(1_000) else {
+            panic!("expected a frequency correction");
+        };
+        assert!((ppb - 1_000.0).abs() < 1e-9, "{}", ppb);
+        // The integral remembers the correction once the error is gone.
+        assert_eq!(servo.sample(0), PhcCorrection::Frequency(300.0));
+        assert_eq!(servo.sample(-2_000_000), PhcCorrection::Step);
+        assert_eq!(servo.sample(1_000_000), PhcCorrection::Frequency(MAX_FREQUENCY_PPB));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(PhcConfig::default().validate().is_ok());
+        let config = PhcConfig {
+            device: Some(String::new()),
+            ..PhcConfig::default()
+        };
+        assert!(config.validate().is_err());
+        let config = PhcConfig {
+            utc_offset_secs: -1,
+            ..PhcConfig::default()
+        };
+        assert!(config.validate().is_err());
+    }
+}
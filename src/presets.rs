@@ -0,0 +1,139 @@
+// src/presets.rs
+//
+// Named timeturner offsets, e.g. "rehearsal" running an hour behind and
+// "show" on time of day, switched in through the API or the TUI, or daily
+// at a local time of day, so playback can run offset timecode for part of an
+// evening without anyone editing the config mid-show.
+
+use chrono::{DateTime, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::config::TimeturnerOffset;
+use crate::rejam;
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct OffsetPreset {
+    pub name: String,
+    #[serde(default)]
+    pub offset: TimeturnerOffset,
+    /// Local time of day, `HH:MM`, at which the preset is switched in every
+    /// day; without it the preset is only switched in on request.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub at: Option<String>,
+}
+
+impl OffsetPreset {
+    /// The scheduled time of day, if any, or an error naming the bad value.
+    pub fn time(&self) -> Result<Option<NaiveTime>, String> {
+        self.at
+            .as_deref()
+            .map(|at| {
+                NaiveTime::parse_from_str(at, "%H:%M").map_err(|_| {
+                    format!("offsetPresets '{}': at must be HH:MM, got '{}'", self.name, at)
+                })
+            })
+            .transpose()
+    }
+}
+
+/// Check that every preset has a unique, non-empty name and a valid time.
+pub fn validate(presets: &[OffsetPreset]) -> Result<(), String> {
+    for (i, preset) in presets.iter().enumerate() {
+        if preset.name.trim().is_empty() {
+            return Err("offsetPresets: every preset needs a name".into());
+        }
+        if presets[..i].iter().any(|p| p.name == preset.name) {
+            return Err(format!("offsetPresets: '{}' is defined twice", preset.name));
+        }
+        preset.time()?;
+    }
+    Ok(())
+}
+
+pub fn find<'a>(presets: &'a [OffsetPreset], name: &str) -> Option<&'a OffsetPreset> {
+    presets.iter().find(|p| p.name == name)
+}
+
+/// The first preset whose offset is the one in use.
+pub fn active<'a>(presets: &'a [OffsetPreset], offset: &TimeturnerOffset) -> Option<&'a OffsetPreset> {
+    presets.iter().find(|p| p.offset == *offset)
+}
+
+/// The preset after the active one, wrapping around, for cycling through
+/// them from the TUI. The first preset if none is active.
+pub fn next<'a>(presets: &'a [OffsetPreset], offset: &TimeturnerOffset) -> Option<&'a OffsetPreset> {
+    let after = presets
+        .iter()
+        .position(|p| p.offset == *offset)
+        .map_or(0, |i| i + 1);
+    presets.get(after % presets.len().max(1))
+}
+
+/// The scheduled preset due soonest after `after`, and when. Presets with
+/// a bad time are skipped; `validate` reports them.
+pub fn next_due<'a, Tz: TimeZone>(
+    presets: &'a [OffsetPreset],
+    after: &DateTime<Tz>,
+) -> Option<(&'a OffsetPreset, DateTime<Tz>)> {
+    presets
+        .iter()
+        .filter_map(|p| Some((p, rejam::next_run(p.time().ok()??, after))))
+        .min_by_key(|(_, due)| due.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    fn preset(name: &str, hours: i64, at: Option<&str>) -> OffsetPreset {
+        OffsetPreset {
+            name: name.to_string(),
+            offset: TimeturnerOffset { hours, ..TimeturnerOffset::default() },
+            at: at.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&[preset("rehearsal", -1, Some("18:30")), preset("show", 0, None)]).is_ok());
+        assert!(validate(&[preset("show", 0, None), preset("show", 1, None)]).is_err());
+        assert!(validate(&[preset(" ", 0, None)]).is_err());
+        let err = validate(&[preset("late", 0, Some("7pm"))]).unwrap_err();
+        assert!(err.contains("'late'"), "{}", err);
+    }
+
+    #[test]
+    fn test_active_and_next() {
+        let presets = [preset("rehearsal", -1, None), preset("show", 0, None)];
+        let show = TimeturnerOffset::default();
+        assert_eq!(active(&presets, &show).unwrap().name, "show");
+        assert_eq!(next(&presets, &show).unwrap().name, "rehearsal");
+        assert_eq!(next(&presets, &presets[0].offset).unwrap().name, "show");
+        let custom = TimeturnerOffset { minutes: 5, ..TimeturnerOffset::default() };
+        assert!(active(&presets, &custom).is_none());
+        assert_eq!(next(&presets, &custom).unwrap().name, "rehearsal");
+        assert!(next(&[], &show).is_none());
+    }
+
+    #[test]
+    fn test_next_due() {
+        let presets = [
+            preset("rehearsal", -1, Some("18:00")),
+            preset("show", 0, Some("19:30")),
+            preset("spare", 2, None),
+        ];
+        let tz = FixedOffset::east_opt(3600).unwrap();
+        let afternoon = tz.with_ymd_and_hms(2025, 6, 1, 17, 0, 0).unwrap();
+        let (due, at) = next_due(&presets, &afternoon).unwrap();
+        assert_eq!((due.name.as_str(), at), ("rehearsal", tz.with_ymd_and_hms(2025, 6, 1, 18, 0, 0).unwrap()));
+        let evening = tz.with_ymd_and_hms(2025, 6, 1, 18, 0, 0).unwrap();
+        assert_eq!(next_due(&presets, &evening).unwrap().0.name, "show");
+        let night = tz.with_ymd_and_hms(2025, 6, 1, 22, 0, 0).unwrap();
+        let (due, at) = next_due(&presets, &night).unwrap();
+        assert_eq!((due.name.as_str(), at), ("rehearsal", tz.with_ymd_and_hms(2025, 6, 2, 18, 0, 0).unwrap()));
+        assert!(next_due(&presets[2..], &night).is_none());
+    }
+}
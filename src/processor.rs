@@ -0,0 +1,799 @@
+// src/processor.rs
+//
+// The frame-processing pipeline. The serial reader pushes parsed frames into
+// a bounded queue and a single processor thread drains it. The processor is
+// the only thing that changes `LtcState` from LTC (counts, jitter, EWMA,
+// drift) and it decides when auto-sync corrects the clock; the corrections
+// themselves run on another thread (`execute`) so a slow `date` or `adjtimex`
+// never backs up the queue.
+
+use crate::audit::{self, AuditEntry, AuditLog, AuditSource};
+use crate::clock_gate::ClockGate;
+use crate::config::{Config, SyncSource};
+use crate::export::Exporter;
+use crate::health::Heartbeat;
+use crate::hooks::{self, HookEvent};
+use crate::report::Session;
+use crate::series::Series;
+use crate::sync_logic::{
+    self, choose_correction, frame_duration_ms, holdover_correction, in_leap_window, ClockPair, Correction,
+    LtcFrame, LtcState, LtcView, AUTO_SYNC_PERIOD_SECS, NO_SIGNAL,
+};
+use crate::system::{self, ClockControl};
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// Frames the queue holds before the reader starts dropping them: about a
+/// second of LTC at 60 fps.
+pub const FRAME_QUEUE_CAPACITY: usize = 64;
+
+/// How long the processor waits for a frame before checking auto-sync anyway,
+/// so holdover keeps running when the reader goes quiet.
+const TICK: Duration = Duration::from_millis(250);
+
+/// How long auto-sync lets the delta average settle after the first LOCK
+/// before deciding whether to step or slew.
+pub const INITIAL_SETTLE_TIME: Duration = Duration::from_secs(3);
+
+/// Frames in a row at a rate other than `expectedFrameRate` that are let
+/// pass before the alert, so one misread line does not raise it.
+pub const FRAME_RATE_MISMATCH_FRAMES: u32 = 25;
+
+/// Queue counters, shared by both ends of the frame channel.
+#[derive(Debug, Default)]
+pub struct PipelineStats {
+    queued: AtomicUsize,
+    high_water: AtomicUsize,
+    received: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl PipelineStats {
+    pub fn report(&self) -> PipelineReport {
+        PipelineReport {
+            queued: self.queued.load(Ordering::Relaxed),
+            high_water: self.high_water.load(Ordering::Relaxed),
+            capacity: FRAME_QUEUE_CAPACITY,
+            received: self.received.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Backpressure on the frame queue, as reported in `/api/status`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PipelineReport {
+    /// Frames waiting for the processor.
+    pub queued: usize,
+    /// Most frames ever waiting at once.
+    pub high_water: usize,
+    /// Frames the queue holds before new ones are dropped.
+    pub capacity: usize,
+    /// Frames read from the serial port.
+    pub received: u64,
+    /// Frames dropped because the processor had fallen a full queue behind.
+    pub dropped: u64,
+}
+
+/// The reader's end of the frame queue.
+#[derive(Clone)]
+pub struct FrameSender {
+    tx: SyncSender<LtcFrame>,
+    stats: Arc<PipelineStats>,
+}
+
+impl FrameSender {
+    /// Queue a frame without blocking. A frame that finds the queue full is
+    /// dropped and counted, so the reader keeps timestamping on time.
+    /// Returns `false` once the processor has gone.
+    pub fn send(&self, frame: LtcFrame) -> bool {
+        self.stats.received.fetch_add(1, Ordering::Relaxed);
+        let queued = self.stats.queued.fetch_add(1, Ordering::Relaxed) + 1;
+        match self.tx.try_send(frame) {
+            Ok(()) => {
+                self.stats.high_water.fetch_max(queued, Ordering::Relaxed);
+                true
+            }
+            Err(e) => {
+                self.stats.queued.fetch_sub(1, Ordering::Relaxed);
+                if let TrySendError::Disconnected(_) = e {
+                    return false;
+                }
+                let dropped = self.stats.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                if dropped.is_power_of_two() {
+                    log::warn!(
+                        "Frame queue full ({} frames): processor is falling behind, {} dropped so far.",
+                        FRAME_QUEUE_CAPACITY, dropped
+                    );
+                }
+                true
+            }
+        }
+    }
+}
+
+/// The processor's end of the frame queue.
+pub struct FrameReceiver {
+    rx: Receiver<LtcFrame>,
+    stats: Arc<PipelineStats>,
+}
+
+impl FrameReceiver {
+    fn recv_timeout(&self, timeout: Duration) -> Result<LtcFrame, RecvTimeoutError> {
+        let frame = self.rx.recv_timeout(timeout)?;
+        self.stats.queued.fetch_sub(1, Ordering::Relaxed);
+        Ok(frame)
+    }
+
+    /// The frames already queued, without waiting for more.
+    pub fn try_iter(&self) -> impl Iterator<Item = LtcFrame> + '_ {
+        self.rx.try_iter().inspect(|_| {
+            self.stats.queued.fetch_sub(1, Ordering::Relaxed);
+        })
+    }
+
+    pub fn stats(&self) -> Arc<PipelineStats> {
+        self.stats.clone()
+    }
+}
+
+/// A bounded frame queue of `FRAME_QUEUE_CAPACITY`.
+pub fn frame_channel() -> (FrameSender, FrameReceiver) {
+    let (tx, rx) = mpsc::sync_channel(FRAME_QUEUE_CAPACITY);
+    let stats = Arc::new(PipelineStats::default());
+    (
+        FrameSender { tx, stats: stats.clone() },
+        FrameReceiver { rx, stats },
+    )
+}
+
+/// A correction auto-sync decided on, for the executor to carry out.
+#[derive(Debug)]
+pub struct CorrectionRequest {
+    pub correction: Correction,
+    pub frame: LtcFrame,
+    pub config: Config,
+    /// The smoothed delta when the decision was made, for the audit trail.
+    pub pre_delta: Option<i64>,
+    /// When the decision was made; it is dropped if the clock was changed
+    /// since.
+    pub decided_at: Instant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AutoSyncPhase {
+    /// No LOCK frame has produced a delta yet.
+    WaitingForLock,
+    /// Locked; the first correction is due at this point.
+    Settling(Instant),
+    /// The next periodic check is due at this point.
+    Running(Instant),
+}
+
+/// Everything the processor carries from one frame to the next besides
+/// `LtcState`: hook edges and the auto-sync schedule.
+#[derive(Debug)]
+pub struct Processor {
+    last_status: Option<String>,
+    delta_exceeded: bool,
+    /// Consecutive frames at a rate other than `expectedFrameRate`.
+    rate_mismatch_frames: u32,
+    /// A step over `maxAutoStepMs` is being held, and has been logged.
+    step_held: bool,
+    auto_sync: AutoSyncPhase,
+}
+
+impl Default for Processor {
+    fn default() -> Self {
+        Self {
+            last_status: None,
+            delta_exceeded: false,
+            rate_mismatch_frames: 0,
+            step_held: false,
+            auto_sync: AutoSyncPhase::WaitingForLock,
+        }
+    }
+}
+
+/// What one LOCK frame measured: the smoothed delta after it, its own raw
+/// delta and its jitter. Kept in `series` for the web UI's charts and
+/// written out by `export`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Measurement {
+    pub at: DateTime<Utc>,
+    pub delta_ms: f64,
+    pub raw_ms: f64,
+    pub jitter_ms: i64,
+}
+
+/// What folding one frame in produced.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FrameOutcome {
+    /// Set for a LOCK frame with a valid target.
+    pub measurement: Option<Measurement>,
+    /// Alerts the frame raised, with the timecode: lock lost or delta
+    /// exceeded.
+    pub alerts: Vec<(HookEvent, String)>,
+}
+
+impl Processor {
+    /// Fold one frame into the state: counts, jitter, phase, EWMA and drift,
+    /// firing hooks on LOCK/FREE transitions and threshold crossings.
+    pub fn frame(&mut self, state: &mut LtcState, config: &Config, frame: LtcFrame) -> FrameOutcome {
+        let mut outcome = FrameOutcome::default();
+        // Fire lock hooks on LOCK/FREE transitions
+        if self.last_status.as_deref() != Some(frame.status.as_str()) {
+            let event = match (self.last_status.as_deref(), frame.status.as_str()) {
+                (_, "LOCK") => Some(HookEvent::LockAcquired),
+                (Some("LOCK"), "FREE") => Some(HookEvent::LockLost),
+                _ => None,
+            };
+            if let Some(event) = event {
+                let vars = hooks::frame_vars(&frame);
+                if event == HookEvent::LockLost {
+                    outcome.alerts.push((event, vars[0].1.clone()));
+                }
+                hooks::fire(&config.hooks, event, &vars);
+            }
+            self.last_status = Some(frame.status.clone());
+        }
+
+        // Alert once frames have disagreed with expectedFrameRate for too long
+        match config.expected_rate() {
+            Some(expected) if frame.frame_rate != expected => {
+                self.rate_mismatch_frames = self.rate_mismatch_frames.saturating_add(1);
+                if self.rate_mismatch_frames == FRAME_RATE_MISMATCH_FRAMES + 1 {
+                    let warning = sync_logic::frame_rate_warning(frame.frame_rate, expected);
+                    log::warn!("⚠️ {}.", warning);
+                    let mut vars = hooks::frame_vars(&frame);
+                    vars.push(("TIMETURNER_EXPECTED_FRAME_RATE", sync_logic::frame_rate_label(expected)));
+                    outcome.alerts.push((HookEvent::FrameRateMismatch, format!("{} at {}", warning, vars[0].1)));
+                    hooks::fire(&config.hooks, HookEvent::FrameRateMismatch, &vars);
+                }
+            }
+            _ => self.rate_mismatch_frames = 0,
+        }
+        state.frame_rate_mismatch = self.rate_mismatch_frames > FRAME_RATE_MISMATCH_FRAMES;
+
+        // Only calculate delta for LOCK frames
+        // A timecode with no valid target (see `calculate_target_time`) is skipped,
+        // as is any frame around a possible leap second.
+        if let (true, Ok(target_time)) = (
+            frame.status == "LOCK" && !in_leap_window(frame.timestamp),
+            system::calculate_target_time(&frame, config),
+        ) {
+            let arrival_time_local: DateTime<Local> = frame.arrival(&ClockPair::now()).with_timezone(&Local);
+            let delta = arrival_time_local.signed_duration_since(target_time);
+            let frame_ms = frame_duration_ms(frame.frame_rate);
+            let phase_ms = state.phase.sample(
+                target_time.timestamp_micros(),
+                delta.num_microseconds().unwrap_or(i64::MAX),
+                frame_ms,
+            );
+            state.record_clock_delta(phase_ms);
+            // Jitter: how far this frame arrived from where the fit puts it.
+            state.jitter_window = config.jitter_window;
+            let raw_ms = delta.num_microseconds().unwrap_or(0) as f64 / 1000.0;
+            state.frame_delta_ms = Some(raw_ms);
+            let jitter_ms = (raw_ms - phase_ms).abs().round() as i64;
+            state.record_offset(jitter_ms);
+            if let Some(ewma) = state.ewma_clock_delta {
+                state.drift.sample(Instant::now(), ewma);
+            }
+
+            // Fire the delta hook once each time the threshold is crossed
+            let ewma_delta = state.get_ewma_clock_delta();
+            let exceeded = ewma_delta.abs() > config.hooks.delta_threshold_ms;
+            if exceeded && !self.delta_exceeded {
+                let mut vars = hooks::frame_vars(&frame);
+                vars.push(("TIMETURNER_DELTA_MS", ewma_delta.to_string()));
+                outcome.alerts.push((HookEvent::DeltaExceeded, format!("{} at {} ms", vars[0].1, ewma_delta)));
+                hooks::fire(&config.hooks, HookEvent::DeltaExceeded, &vars);
+            }
+            self.delta_exceeded = exceeded;
+            outcome.measurement = state.ewma_clock_delta.map(|delta_ms| Measurement {
+                at: frame.timestamp,
+                delta_ms,
+                raw_ms,
+                jitter_ms,
+            });
+        }
+
+        state.ltc_zone = config.ltc_zone();
+        if let Some(discontinuity) = state.update(frame) {
+            log::warn!("⚠️ Timecode discontinuity: {}.", discontinuity);
+        }
+        outcome
+    }
+
+    /// Mark the signal lost once no frame has arrived for
+    /// `signalTimeoutMs` (`silence`), so a dead reader or feed stops counting
+    /// as LOCK and auto-sync falls back to holdover. Losing it while locked
+    /// raises a lock-lost alert, with the last timecode seen.
+    pub fn check_signal(&mut self, silence: Duration, state: &mut LtcState, config: &Config) -> Option<(HookEvent, String)> {
+        if state.signal_lost || state.latest.is_none() || silence < Duration::from_millis(config.signal_timeout_ms) {
+            return None;
+        }
+        state.signal_lost = true;
+        state.continuity.restart();
+        state.publish();
+        let frame = state.latest.as_ref()?;
+        log::warn!("⚠️ No LTC for {} ms (last {} at {}): NO SIGNAL.", silence.as_millis(), frame.status, frame.timecode());
+        let was_locked = self.last_status.as_deref() == Some("LOCK");
+        self.last_status = Some(NO_SIGNAL.to_string());
+        if !was_locked {
+            return None;
+        }
+        let mut vars = hooks::frame_vars(frame);
+        vars[2].1 = NO_SIGNAL.to_string();
+        hooks::fire(&config.hooks, HookEvent::LockLost, &vars);
+        Some((HookEvent::LockLost, format!("{} (no signal)", vars[0].1)))
+    }
+
+    /// Decide whether auto-sync corrects the clock now. The first correction
+    /// comes `INITIAL_SETTLE_TIME` after the first locked delta and steps only
+    /// if the clock is far off; after that the delta is re-checked every
+    /// `AUTO_SYNC_PERIOD_SECS`, holding the clock's rate with the drift
+    /// estimate while LTC is not locked.
+    pub fn auto_sync(&mut self, now: Instant, state: &LtcState, config: &Config) -> Option<CorrectionRequest> {
+        let initial = match self.auto_sync {
+            AutoSyncPhase::WaitingForLock => {
+                if state.ewma_clock_delta.is_some() {
+                    log::info!("Auto-sync: Initial LTC lock detected.");
+                    self.auto_sync = AutoSyncPhase::Settling(now + INITIAL_SETTLE_TIME);
+                }
+                return None;
+            }
+            AutoSyncPhase::Settling(due) if now < due => return None,
+            AutoSyncPhase::Running(due) if now < due => return None,
+            AutoSyncPhase::Settling(_) => true,
+            AutoSyncPhase::Running(_) => false,
+        };
+        self.auto_sync = AutoSyncPhase::Running(now + Duration::from_secs(AUTO_SYNC_PERIOD_SECS));
+
+        let frame = state.latest.clone()?;
+        if !config.auto_sync_enabled {
+            return None;
+        }
+        if in_leap_window(Utc::now()) {
+            log::info!("Auto-sync: Holding corrections around a possible leap second.");
+            return None;
+        }
+        let delta = state.get_ewma_clock_delta();
+        let drift_ppm = state.drift.ppm();
+        let pre_delta = state.ewma_clock_delta.map(|d| d.round() as i64);
+        let locked = pre_delta.is_some() && frame.status == "LOCK" && !state.signal_lost;
+        let pinned_holdover = match config.sync_source {
+            SyncSource::Manual => return None,
+            SyncSource::Ltc if !initial && !locked => return None,
+            source => source == SyncSource::Holdover,
+        };
+        let correction = if initial && !pinned_holdover {
+            let correction = choose_correction(delta, drift_ppm, config);
+            log::info!(
+                "Auto-sync: Initial delta is {}ms (step threshold {}ms), chose {:?}.",
+                delta, config.step_threshold_ms, correction
+            );
+            correction
+        } else if pinned_holdover || !locked {
+            // Without LOCK there is no delta to correct; hold the clock's
+            // rate with the drift estimate instead.
+            let correction = holdover_correction(drift_ppm);
+            if let Correction::Slew(us) = correction {
+                log::info!(
+                    "Auto-sync: Holdover, compensating {:+.1} ppm drift by {}us.",
+                    drift_ppm.unwrap_or(0.0), us
+                );
+            }
+            correction
+        } else {
+            let correction = choose_correction(delta, drift_ppm, config);
+            match correction {
+                Correction::Step => log::info!(
+                    "Auto-sync: Delta > {}ms ({}ms), performing full sync.",
+                    config.step_threshold_ms, delta
+                ),
+                Correction::Slew(us) => log::info!(
+                    "Auto-sync: Delta is {}ms, nudging clock by {}us.", delta, us
+                ),
+                Correction::Skip => {}
+            }
+            correction
+        };
+        if correction == Correction::Step && config.step_too_early(state.consistent_frames) {
+            log::warn!(
+                "Auto-sync: Holding step: only {} of {} consecutive LOCK frames are consistent.",
+                state.consistent_frames, config.step_consistent_frames
+            );
+            return None;
+        }
+        let held = correction == Correction::Step && config.step_over_limit(delta);
+        if held && !self.step_held {
+            log::warn!(
+                "⚠️ Auto-sync: Holding a {} ms step, over maxAutoStepMs ({} ms); sync manually with the override to apply it.",
+                delta, config.max_auto_step_ms
+            );
+        }
+        self.step_held = held;
+        if held {
+            return None;
+        }
+        (correction != Correction::Skip).then(|| CorrectionRequest {
+            correction,
+            frame,
+            config: config.clone(),
+            pre_delta,
+            decided_at: now,
+        })
+    }
+}
+
+/// Drain the frame queue into `state` until the reader hangs up, recording
+/// measurements in `series` and the session report and sending auto-sync
+/// decisions to `corrections`.
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    rx: &FrameReceiver,
+    state: Arc<Mutex<LtcState>>,
+    config: Arc<Mutex<Config>>,
+    series: Arc<Mutex<Series>>,
+    session: Arc<Mutex<Session>>,
+    export: Arc<Mutex<Exporter>>,
+    corrections: Sender<CorrectionRequest>,
+    heartbeat: Heartbeat,
+) {
+    let mut processor = Processor::default();
+    let mut last_frame = Instant::now();
+    loop {
+        heartbeat.beat();
+        let frame = match rx.recv_timeout(TICK) {
+            Ok(frame) => Some(frame),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        let mut exported = None;
+        let mut state = state.lock().unwrap();
+        let config = config.lock().unwrap();
+        if frame.is_some() {
+            last_frame = Instant::now();
+        } else if let Some((event, detail)) = processor.check_signal(last_frame.elapsed(), &mut state, &config) {
+            session.lock().unwrap().record_alert(Utc::now(), event, detail);
+        }
+        if let Some(frame) = frame {
+            state.pipeline = rx.stats.report();
+            let outcome = processor.frame(&mut state, &config, frame.clone());
+            if let Some(m) = &outcome.measurement {
+                series.lock().unwrap().record(m.at, m.delta_ms, m.jitter_ms);
+                exported = Some((*m, frame.timecode()));
+            }
+            let mut session = session.lock().unwrap();
+            session.record_frame(&frame, outcome.measurement.as_ref());
+            for (event, detail) in outcome.alerts {
+                session.record_alert(frame.timestamp, event, detail);
+            }
+        }
+        if let Some(request) = processor.auto_sync(Instant::now(), &state, &config) {
+            let _ = corrections.send(request);
+        }
+        // The export writes to disk, so the API and the other workers are
+        // not kept waiting on it.
+        drop((state, config));
+        if let Some((m, timecode)) = exported {
+            export.lock().unwrap().record(&m, &timecode);
+        }
+    }
+}
+
+/// Carry out auto-sync decisions from `corrections` on `clock` until the
+/// processor hangs up. A decision is skipped while another clock change holds
+/// the gate, or if the clock was changed after it was made.
+pub fn execute(
+    corrections: &Receiver<CorrectionRequest>,
+    clock: Arc<dyn ClockControl>,
+    gate: Arc<ClockGate>,
+    state: Arc<Mutex<LtcState>>,
+    ltc: LtcView,
+    audit_log: Arc<AuditLog>,
+) {
+    for request in corrections {
+        carry_out(&request, clock.as_ref(), &gate, &state, &ltc, &audit_log);
+    }
+}
+
+/// Carry out one auto-sync decision on `clock`; see `execute`.
+pub fn carry_out(
+    request: &CorrectionRequest,
+    clock: &dyn ClockControl,
+    gate: &Arc<ClockGate>,
+    state: &Arc<Mutex<LtcState>>,
+    ltc: &LtcView,
+    audit_log: &Arc<AuditLog>,
+) {
+    let Some(permit) = gate.try_enter() else {
+        log::info!("Auto-sync: skipped, another clock change is in progress.");
+        return;
+    };
+    if gate.changed_since(request.decided_at) {
+        log::info!("Auto-sync: skipped, the clock was changed since the decision.");
+        return;
+    }
+    apply_correction(clock, request, state, ltc, audit_log);
+    permit.note_change(Instant::now());
+}
+
+/// Carry out an auto-sync correction, logging and auditing the result.
+fn apply_correction(
+    clock: &dyn ClockControl,
+    request: &CorrectionRequest,
+    state: &Arc<Mutex<LtcState>>,
+    ltc: &LtcView,
+    audit_log: &Arc<AuditLog>,
+) {
+    let (frame, config) = (&request.frame, &request.config);
+    let (action, command, success) = match request.correction {
+        Correction::Step => {
+            let (command, success) = system::step_to_ltc(clock, frame, config, state);
+            ("sync", command, success)
+        }
+        Correction::Slew(us) => ("nudge", system::nudge_command(us), clock.nudge(us).is_ok()),
+        Correction::Skip => return,
+    };
+    if success {
+        let mut st = state.lock().unwrap();
+        match request.correction {
+            Correction::Slew(us) => st.note_slew(us),
+            _ => st.note_step(),
+        }
+    }
+    if success {
+        log::info!("Auto-sync: {} successful.", command);
+    } else {
+        log::error!("Auto-sync: {} failed.", command);
+    }
+    let mut entry = AuditEntry::new(AuditSource::Auto, action, command, request.pre_delta);
+    entry.success = success;
+    audit::record_after_settle(audit_log.clone(), ltc.clone(), entry);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_rational::Ratio;
+    use crate::status::StatusSnapshot;
+
+    fn get_test_frame(status: &str) -> LtcFrame {
+        LtcFrame {
+            status: status.to_string(),
+            hours: 10,
+            minutes: 20,
+            seconds: 30,
+            frames: 0,
+            is_drop_frame: false,
+            frame_rate: Ratio::new(25, 1),
+            timestamp: Utc::now(),
+            received: None,
+        }
+    }
+
+    /// Feed `count` consecutive LOCK frames from 10:20:30:00.
+    fn feed_lock_run(state: &mut LtcState, count: u32) {
+        for frames in 0..count {
+            state.update(LtcFrame {
+                seconds: 30 + frames / 25,
+                frames: frames % 25,
+                ..get_test_frame("LOCK")
+            });
+        }
+    }
+
+    #[test]
+    fn test_full_queue_drops_and_counts() {
+        let (tx, rx) = frame_channel();
+        for _ in 0..FRAME_QUEUE_CAPACITY + 3 {
+            assert!(tx.send(get_test_frame("LOCK")));
+        }
+        let report = rx.stats().report();
+        assert_eq!(report.queued, FRAME_QUEUE_CAPACITY);
+        assert_eq!(report.high_water, FRAME_QUEUE_CAPACITY);
+        assert_eq!(report.received, FRAME_QUEUE_CAPACITY as u64 + 3);
+        assert_eq!(report.dropped, 3);
+
+        rx.recv_timeout(TICK).unwrap();
+        assert_eq!(rx.stats().report().queued, FRAME_QUEUE_CAPACITY - 1);
+        drop(rx);
+        assert!(!tx.send(get_test_frame("LOCK")), "processor gone");
+    }
+
+    #[test]
+    fn test_frame_reports_lock_lost() {
+        let config = Config::default();
+        let mut state = LtcState::new();
+        let mut processor = Processor::default();
+        let outcome = processor.frame(&mut state, &config, get_test_frame("LOCK"));
+        assert!(outcome.measurement.is_some());
+        assert!(outcome.alerts.iter().all(|(event, _)| *event != HookEvent::LockLost));
+        assert!(state.frame_delta_ms.is_some());
+        assert_eq!(state.frame_delta_ms, state.ewma_clock_delta, "one frame: nothing to filter yet");
+        let outcome = processor.frame(&mut state, &config, get_test_frame("FREE"));
+        assert_eq!(outcome.alerts, vec![(HookEvent::LockLost, "10:20:30:00".to_string())]);
+        assert!(outcome.measurement.is_none());
+        assert_eq!(state.frame_delta_ms, None);
+    }
+
+    #[test]
+    fn test_frame_rate_mismatch_alerts_once_past_the_limit() {
+        let config = Config { expected_frame_rate: Some("25".into()), ..Config::default() };
+        let mut state = LtcState::new();
+        let mut processor = Processor::default();
+        let at_24 = LtcFrame { frame_rate: Ratio::new(24, 1), ..get_test_frame("LOCK") };
+        let mut alerts = Vec::new();
+        for _ in 0..FRAME_RATE_MISMATCH_FRAMES {
+            let outcome = processor.frame(&mut state, &config, at_24.clone());
+            alerts.extend(outcome.alerts.into_iter().filter(|(event, _)| *event == HookEvent::FrameRateMismatch));
+        }
+        assert!(alerts.is_empty());
+        assert!(!state.frame_rate_mismatch);
+
+        for _ in 0..10 {
+            let outcome = processor.frame(&mut state, &config, at_24.clone());
+            alerts.extend(outcome.alerts.into_iter().filter(|(event, _)| *event == HookEvent::FrameRateMismatch));
+        }
+        assert_eq!(
+            alerts,
+            vec![(HookEvent::FrameRateMismatch, "LTC 24.00fps, expected 25.00fps at 10:20:30:00".to_string())]
+        );
+        let status = StatusSnapshot::new(&state.snapshot(), &config);
+        assert_eq!(status.frame_rate_warning.as_deref(), Some("LTC 24.00fps, expected 25.00fps"));
+
+        processor.frame(&mut state, &config, get_test_frame("LOCK"));
+        assert!(!state.frame_rate_mismatch, "cleared by a frame at the expected rate");
+        assert_eq!(StatusSnapshot::new(&state.snapshot(), &config).frame_rate_warning, None);
+    }
+
+    #[test]
+    fn test_silence_marks_signal_lost_until_the_next_frame() {
+        let config = Config::default();
+        let mut state = LtcState::new();
+        let mut processor = Processor::default();
+        let ltc = state.view();
+        assert_eq!(processor.check_signal(Duration::from_secs(5), &mut state, &config), None, "no frame yet");
+
+        processor.frame(&mut state, &config, get_test_frame("LOCK"));
+        assert_eq!(processor.check_signal(Duration::from_millis(999), &mut state, &config), None);
+        let alert = processor.check_signal(Duration::from_millis(1000), &mut state, &config);
+        assert_eq!(alert, Some((HookEvent::LockLost, "10:20:30:00 (no signal)".to_string())));
+        assert!(!ltc.get().locked());
+        assert_eq!(ltc.get().ltc_status(), Some(NO_SIGNAL));
+        assert_eq!(processor.check_signal(Duration::from_secs(5), &mut state, &config), None, "raised once");
+
+        let outcome = processor.frame(&mut state, &config, get_test_frame("LOCK"));
+        assert!(outcome.alerts.iter().all(|(event, _)| *event != HookEvent::LockLost));
+        assert!(ltc.get().locked());
+    }
+
+    #[test]
+    fn test_auto_sync_holds_over_without_signal() {
+        let config = Config { auto_sync_enabled: true, ..Config::default() };
+        let mut state = LtcState::new();
+        let mut processor = Processor::default();
+        let start = Instant::now();
+        state.update(get_test_frame("LOCK"));
+        state.ewma_clock_delta = Some(40.0);
+        processor.auto_sync(start, &state, &config);
+        processor.auto_sync(start + INITIAL_SETTLE_TIME, &state, &config);
+
+        state.signal_lost = true;
+        let next = start + INITIAL_SETTLE_TIME + Duration::from_secs(AUTO_SYNC_PERIOD_SECS);
+        assert!(processor.auto_sync(next, &state, &config).is_none(), "no drift estimate: nothing to hold");
+    }
+
+    #[test]
+    fn test_auto_sync_settles_then_runs_periodically() {
+        let config = Config { auto_sync_enabled: true, ..Config::default() };
+        let mut state = LtcState::new();
+        let mut processor = Processor::default();
+        let start = Instant::now();
+
+        assert!(processor.auto_sync(start, &state, &config).is_none());
+        state.update(get_test_frame("LOCK"));
+        state.ewma_clock_delta = Some(40.0);
+        assert!(processor.auto_sync(start, &state, &config).is_none(), "lock detected");
+        assert!(processor.auto_sync(start + Duration::from_secs(1), &state, &config).is_none(), "settling");
+
+        let first = processor.auto_sync(start + INITIAL_SETTLE_TIME, &state, &config).unwrap();
+        assert_eq!(first.correction, Correction::Slew(-40_000));
+        assert_eq!(first.pre_delta, Some(40));
+
+        let next = start + INITIAL_SETTLE_TIME + Duration::from_secs(AUTO_SYNC_PERIOD_SECS);
+        assert!(processor.auto_sync(next - Duration::from_secs(1), &state, &config).is_none());
+        assert!(processor.auto_sync(next, &state, &config).is_some());
+    }
+
+    #[test]
+    fn test_auto_sync_follows_pinned_source() {
+        let mut state = LtcState::new();
+        let start = Instant::now();
+        for s in 0..=120 {
+            state.drift.sample(start + Duration::from_secs(s), s as f64 * 0.01);
+        }
+        feed_lock_run(&mut state, 25);
+        state.ewma_clock_delta = Some(40.0);
+        let decide = |state: &LtcState, source: SyncSource| {
+            let config = Config { auto_sync_enabled: true, sync_source: source, ..Config::default() };
+            let mut processor = Processor::default();
+            processor.auto_sync(start, state, &config);
+            processor
+                .auto_sync(start + INITIAL_SETTLE_TIME, state, &config)
+                .map(|request| request.correction)
+        };
+
+        // With +10 ppm of drift, the 40 ms delta is projected past the step threshold.
+        assert_eq!(decide(&state, SyncSource::Auto), Some(Correction::Step));
+        assert_eq!(decide(&state, SyncSource::Ltc), Some(Correction::Step));
+        let holdover = decide(&state, SyncSource::Holdover);
+        assert!(matches!(holdover, Some(Correction::Slew(us)) if us < 0), "{:?}", holdover);
+        assert_eq!(decide(&state, SyncSource::Manual), None);
+    }
+
+    #[test]
+    fn test_auto_sync_steps_only_after_consistent_frames() {
+        let config = Config { auto_sync_enabled: true, ..Config::default() };
+        let mut state = LtcState::new();
+        feed_lock_run(&mut state, 24);
+        state.ewma_clock_delta = Some(500.0);
+        let start = Instant::now();
+        let mut processor = Processor::default();
+        processor.auto_sync(start, &state, &config);
+        assert!(processor.auto_sync(start + INITIAL_SETTLE_TIME, &state, &config).is_none(), "24 of 25 frames");
+
+        // One corrupted line at another rate starts the run over.
+        feed_lock_run(&mut state, 25);
+        state.update(LtcFrame { frame_rate: Ratio::new(24, 1), ..get_test_frame("LOCK") });
+        assert_eq!(state.consistent_frames, 1);
+        let mut next = start + INITIAL_SETTLE_TIME + Duration::from_secs(AUTO_SYNC_PERIOD_SECS);
+        assert!(processor.auto_sync(next, &state, &config).is_none());
+
+        feed_lock_run(&mut state, 25);
+        assert_eq!(state.consistent_frames, 25);
+        next += Duration::from_secs(AUTO_SYNC_PERIOD_SECS);
+        let request = processor.auto_sync(next, &state, &config).unwrap();
+        assert_eq!(request.correction, Correction::Step);
+    }
+
+    #[test]
+    fn test_auto_sync_holds_steps_over_the_limit() {
+        let config = Config { auto_sync_enabled: true, max_auto_step_ms: 2000, ..Config::default() };
+        let mut state = LtcState::new();
+        feed_lock_run(&mut state, 25);
+        // A mis-read hour: twelve hours out.
+        state.ewma_clock_delta = Some(-43_200_000.0);
+        let start = Instant::now();
+        let mut processor = Processor::default();
+        processor.auto_sync(start, &state, &config);
+        assert!(processor.auto_sync(start + INITIAL_SETTLE_TIME, &state, &config).is_none(), "held");
+        assert!(processor.step_held);
+
+        state.ewma_clock_delta = Some(1500.0);
+        let next = start + INITIAL_SETTLE_TIME + Duration::from_secs(AUTO_SYNC_PERIOD_SECS);
+        let request = processor.auto_sync(next, &state, &config).unwrap();
+        assert_eq!(request.correction, Correction::Step, "within the limit");
+        assert!(!processor.step_held);
+    }
+
+    #[test]
+    fn test_auto_sync_off_decides_nothing() {
+        let config = Config::default();
+        let mut state = LtcState::new();
+        state.update(get_test_frame("LOCK"));
+        state.ewma_clock_delta = Some(40.0);
+        let mut processor = Processor::default();
+        let start = Instant::now();
+        processor.auto_sync(start, &state, &config);
+        assert!(processor.auto_sync(start + INITIAL_SETTLE_TIME, &state, &config).is_none());
+    }
+}
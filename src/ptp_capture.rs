@@ -0,0 +1,735 @@
+// src/ptp_capture.rs
+//
+// A passive PTP message log for debugging the network's PTP: which masters
+// announce, with what clock quality, and how far their Sync time is from the
+// system clock. Off by default; when `ptpCapture.enabled` is set, the event
+// (319) and general (320) ports are opened alongside any running `ptp4l`, the
+// PTP multicast groups are joined, and the last `maxMessages` PTPv2 messages
+// are kept for `/api/ptp/messages`. Nothing is ever sent.
+//
+// Announces also build the foreign master records a PTP slave would keep,
+// and `/api/ptp/masters` runs the best master clock algorithm (BMCA) over
+// them: which master a slave on `ptpCapture.domain` would pick, and why each
+// of the others lost. `ptpCapture.grandmaster` forces the pick while that
+// grandmaster is announcing.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use utoipa::ToSchema;
+
+use crate::config::Config;
+use crate::health::Heartbeat;
+
+pub const EVENT_PORT: u16 = 319;
+pub const GENERAL_PORT: u16 = 320;
+
+/// The default PTP multicast group, and the one for peer delay messages.
+const PRIMARY_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 1, 129);
+const PDELAY_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 107);
+
+/// How long to wait for a message before re-reading the config.
+const POLL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// TAI - UTC since 1 January 2017, until a master announces it.
+const DEFAULT_UTC_OFFSET_SECS: i64 = 37;
+
+/// Two-step Syncs kept waiting for their Follow_Up.
+const MAX_PENDING_SYNCS: usize = 32;
+
+/// A master is qualified once this many Announces arrive within
+/// `FOREIGN_MASTER_TIME_WINDOW` announce intervals (IEEE 1588 9.3.2.4.4).
+const FOREIGN_MASTER_THRESHOLD: usize = 2;
+const FOREIGN_MASTER_TIME_WINDOW: i32 = 4;
+
+/// Masters not heard from for this long are forgotten.
+const FOREIGN_MASTER_EXPIRY_SECS: i64 = 60;
+
+const HEADER_LEN: usize = 34;
+const TIMESTAMP_LEN: usize = 10;
+const ANNOUNCE_LEN: usize = 64;
+
+const SYNC: u8 = 0x0;
+const FOLLOW_UP: u8 = 0x8;
+const ANNOUNCE: u8 = 0xb;
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PtpCaptureConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// IPv4 address of the NIC on the PTP network. Unset joins the
+    /// multicast groups on the default interface.
+    #[serde(default)]
+    pub interface: Option<String>,
+    /// How many messages to keep.
+    #[serde(default = "default_max_messages")]
+    pub max_messages: usize,
+    /// The PTP domain the master selection is made for.
+    #[serde(default)]
+    pub domain: u8,
+    /// Grandmaster clock identity (`001b21.fffe.123456`) to select whenever
+    /// it is announcing, whatever the BMCA says.
+    #[serde(default)]
+    pub grandmaster: Option<String>,
+}
+
+fn default_max_messages() -> usize {
+    256
+}
+
+impl Default for PtpCaptureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interface: None,
+            max_messages: default_max_messages(),
+            domain: 0,
+            grandmaster: None,
+        }
+    }
+}
+
+impl PtpCaptureConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(interface) = &self.interface {
+            interface
+                .parse::<Ipv4Addr>()
+                .map_err(|_| format!("ptpCapture.interface must be an IPv4 address, got '{}'", interface))?;
+        }
+        if !(1..=10_000).contains(&self.max_messages) {
+            return Err("ptpCapture.maxMessages must be between 1 and 10000".into());
+        }
+        if self.domain > 127 {
+            return Err("ptpCapture.domain must be between 0 and 127".into());
+        }
+        if let Some(identity) = &self.grandmaster {
+            let groups: Vec<&str> = identity.split('.').collect();
+            let valid = groups.iter().map(|g| g.len()).eq([6, 4, 6])
+                && groups.iter().all(|g| g.chars().all(|c| c.is_ascii_hexdigit()));
+            if !valid {
+                return Err(format!(
+                    "ptpCapture.grandmaster must be a clock identity like 001b21.fffe.123456, got '{}'",
+                    identity
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The grandmaster an Announce advertises, in BMCA comparison order.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PtpAnnounce {
+    pub grandmaster_identity: String,
+    pub priority1: u8,
+    pub clock_class: u8,
+    pub clock_accuracy: u8,
+    pub offset_scaled_log_variance: u16,
+    pub priority2: u8,
+    pub steps_removed: u16,
+    /// 0x20 GPS, 0x40 PTP, 0x50 NTP, 0xa0 internal oscillator, ...
+    pub time_source: u8,
+    /// TAI - UTC, in seconds.
+    pub current_utc_offset: i16,
+}
+
+/// One received PTP message.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PtpMessage {
+    #[schema(value_type = String, format = DateTime)]
+    pub received: DateTime<Utc>,
+    /// Sender's IP address.
+    pub from: String,
+    /// `sync`, `delayReq`, `followUp`, `delayResp`, `announce`, ...
+    pub message_type: String,
+    pub domain: u8,
+    /// Sending port as `clockIdentity-portNumber`.
+    pub source_port: String,
+    pub sequence_id: u16,
+    /// The timestamp the message carries (TAI), as `seconds.nanoseconds`.
+    pub timestamp: Option<String>,
+    /// System clock minus master time, for a one-step Sync or the
+    /// Follow_Up of a two-step one. Includes the network path delay, which
+    /// a passive listener cannot measure.
+    pub offset_ms: Option<f64>,
+    pub announce: Option<PtpAnnounce>,
+}
+
+/// The parts of a PTPv2 message the log keeps.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Packet {
+    pub message_type: u8,
+    pub domain: u8,
+    pub two_step: bool,
+    /// correctionField, in nanoseconds.
+    pub correction_ns: f64,
+    pub source_port: String,
+    pub sequence_id: u16,
+    /// log2 of the sender's interval for this message type, in seconds.
+    pub log_message_interval: i8,
+    /// Seconds and nanoseconds, for messages that carry a timestamp.
+    pub timestamp: Option<(u64, u32)>,
+    pub announce: Option<PtpAnnounce>,
+}
+
+/// A master heard announcing, as a slave's foreign master record.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ForeignMaster {
+    /// The announcing port, as `clockIdentity-portNumber`.
+    pub source_port: String,
+    pub from: String,
+    pub domain: u8,
+    /// Its latest Announce.
+    pub announce: PtpAnnounce,
+    /// Announces received within the qualification window.
+    pub recent_announces: usize,
+    #[schema(value_type = String, format = DateTime)]
+    pub last_seen: DateTime<Utc>,
+    /// Whether it has announced often enough to be selected.
+    pub qualified: bool,
+    pub selected: bool,
+    /// Why it was or was not selected.
+    pub reason: String,
+}
+
+/// The master a slave on this network would select, served at
+/// `/api/ptp/masters`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PtpMasterStatus {
+    pub domain: u8,
+    /// `ptpCapture.grandmaster`, if set.
+    pub forced_grandmaster: Option<String>,
+    /// Source port of the selected master.
+    pub selected: Option<String>,
+    pub grandmaster: Option<String>,
+    /// How the selection was made.
+    pub reason: String,
+    pub foreign_masters: Vec<ForeignMaster>,
+}
+
+struct ForeignRecord {
+    source_port: String,
+    from: IpAddr,
+    domain: u8,
+    announce: PtpAnnounce,
+    log_announce_interval: i8,
+    received: VecDeque<DateTime<Utc>>,
+}
+
+impl ForeignRecord {
+    fn last_seen(&self) -> DateTime<Utc> {
+        self.received.back().copied().unwrap_or_default()
+    }
+
+    /// Announces received in the last `FOREIGN_MASTER_TIME_WINDOW` intervals.
+    fn recent(&self, now: DateTime<Utc>) -> usize {
+        let interval_ms = 1000.0 * 2f64.powi(self.log_announce_interval.clamp(-3, 4).into());
+        let window = chrono::Duration::milliseconds((interval_ms * f64::from(FOREIGN_MASTER_TIME_WINDOW)) as i64);
+        self.received.iter().filter(|t| now - **t <= window).count()
+    }
+
+    fn qualified(&self, now: DateTime<Utc>) -> bool {
+        self.recent(now) >= FOREIGN_MASTER_THRESHOLD && self.announce.steps_removed < 255
+    }
+}
+
+fn decide<T: Ord + Display>(field: &str, a: T, b: T) -> Option<(Ordering, String)> {
+    match a.cmp(&b) {
+        Ordering::Equal => None,
+        order => Some((order, format!("{} {} vs {}", field, a, b))),
+    }
+}
+
+/// The IEEE 1588 dataset comparison of two Announces from the ports `a_port`
+/// and `b_port`: `Less` if a slave prefers `a`, with the field that decided.
+pub fn compare(a: &PtpAnnounce, a_port: &str, b: &PtpAnnounce, b_port: &str) -> (Ordering, String) {
+    let decided = if a.grandmaster_identity != b.grandmaster_identity {
+        decide("priority1", a.priority1, b.priority1)
+            .or_else(|| decide("clockClass", a.clock_class, b.clock_class))
+            .or_else(|| decide("clockAccuracy", a.clock_accuracy, b.clock_accuracy))
+            .or_else(|| decide("offsetScaledLogVariance", a.offset_scaled_log_variance, b.offset_scaled_log_variance))
+            .or_else(|| decide("priority2", a.priority2, b.priority2))
+            .or_else(|| decide("grandmasterIdentity", &a.grandmaster_identity, &b.grandmaster_identity))
+    } else {
+        // The same grandmaster through different paths: the shorter wins.
+        decide("stepsRemoved", a.steps_removed, b.steps_removed)
+            .or_else(|| decide("sourcePortIdentity", a_port, b_port))
+    };
+    decided.unwrap_or((Ordering::Equal, "identical".into()))
+}
+
+pub fn message_type_name(message_type: u8) -> &'static str {
+    match message_type {
+        0x0 => "sync",
+        0x1 => "delayReq",
+        0x2 => "pdelayReq",
+        0x3 => "pdelayResp",
+        0x8 => "followUp",
+        0x9 => "delayResp",
+        0xa => "pdelayRespFollowUp",
+        0xb => "announce",
+        0xc => "signaling",
+        0xd => "management",
+        _ => "unknown",
+    }
+}
+
+fn clock_identity(bytes: &[u8]) -> String {
+    let hex: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}.{}.{}", hex[..3].concat(), hex[3..5].concat(), hex[5..].concat())
+}
+
+/// Parse a PTPv2 message (IEEE 1588-2008 common header plus the timestamp
+/// and Announce fields).
+pub fn parse(packet: &[u8]) -> Result<Packet, String> {
+    if packet.len() < HEADER_LEN {
+        return Err("short message".into());
+    }
+    if packet[1] & 0x0f != 2 {
+        return Err(format!("PTP version {} is not supported", packet[1] & 0x0f));
+    }
+    let message_type = packet[0] & 0x0f;
+    let correction = i64::from_be_bytes(packet[8..16].try_into().unwrap());
+    let port_number = u16::from_be_bytes([packet[28], packet[29]]);
+    let timestamp = match message_type {
+        0x0..=0x3 | 0x8..=0xb => {
+            let ts = packet
+                .get(HEADER_LEN..HEADER_LEN + TIMESTAMP_LEN)
+                .ok_or_else(|| format!("short {} message", message_type_name(message_type)))?;
+            let mut secs = [0u8; 8];
+            secs[2..].copy_from_slice(&ts[..6]);
+            Some((u64::from_be_bytes(secs), u32::from_be_bytes(ts[6..10].try_into().unwrap())))
+        }
+        _ => None,
+    };
+    let announce = if message_type == ANNOUNCE {
+        let a = packet.get(..ANNOUNCE_LEN).ok_or("short announce message")?;
+        Some(PtpAnnounce {
+            grandmaster_identity: clock_identity(&a[53..61]),
+            priority1: a[47],
+            clock_class: a[48],
+            clock_accuracy: a[49],
+            offset_scaled_log_variance: u16::from_be_bytes([a[50], a[51]]),
+            priority2: a[52],
+            steps_removed: u16::from_be_bytes([a[61], a[62]]),
+            time_source: a[63],
+            current_utc_offset: i16::from_be_bytes([a[44], a[45]]),
+        })
+    } else {
+        None
+    };
+    Ok(Packet {
+        message_type,
+        domain: packet[4],
+        two_step: packet[6] & 0x02 != 0,
+        correction_ns: correction as f64 / 65536.0,
+        source_port: format!("{}-{}", clock_identity(&packet[20..28]), port_number),
+        sequence_id: u16::from_be_bytes([packet[30], packet[31]]),
+        log_message_interval: packet[33] as i8,
+        timestamp,
+        announce,
+    })
+}
+
+/// The last messages received, served at `/api/ptp/messages`.
+pub struct PtpCapture {
+    messages: VecDeque<PtpMessage>,
+    capacity: usize,
+    /// Receive times of two-step Syncs: source port, sequence ID.
+    pending_syncs: VecDeque<(String, u16, DateTime<Utc>)>,
+    utc_offset_secs: i64,
+    masters: Vec<ForeignRecord>,
+}
+
+impl Default for PtpCapture {
+    fn default() -> Self {
+        Self {
+            messages: VecDeque::new(),
+            capacity: default_max_messages(),
+            pending_syncs: VecDeque::new(),
+            utc_offset_secs: DEFAULT_UTC_OFFSET_SECS,
+            masters: Vec::new(),
+        }
+    }
+}
+
+fn round_micro(ms: f64) -> f64 {
+    (ms * 1000.0).round() / 1000.0
+}
+
+impl PtpCapture {
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        while self.messages.len() > self.capacity {
+            self.messages.pop_front();
+        }
+    }
+
+    /// System clock at `received` minus the master time in a Sync or
+    /// Follow_Up timestamp.
+    fn offset_ms(&self, packet: &Packet, received: DateTime<Utc>) -> Option<f64> {
+        let (secs, nanos) = packet.timestamp?;
+        let master_ms = (secs as i64 - self.utc_offset_secs) as f64 * 1000.0
+            + (nanos as f64 + packet.correction_ns) / 1_000_000.0;
+        Some(round_micro(received.timestamp_micros() as f64 / 1000.0 - master_ms))
+    }
+
+    /// Log one message received from `from` at `received`.
+    pub fn record(&mut self, packet: Packet, from: IpAddr, received: DateTime<Utc>) {
+        if let Some(announce) = &packet.announce {
+            self.utc_offset_secs = announce.current_utc_offset.into();
+            self.record_announce(&packet, announce.clone(), from, received);
+        }
+        let offset_ms = match packet.message_type {
+            SYNC if packet.two_step => {
+                self.pending_syncs.push_back((packet.source_port.clone(), packet.sequence_id, received));
+                if self.pending_syncs.len() > MAX_PENDING_SYNCS {
+                    self.pending_syncs.pop_front();
+                }
+                None
+            }
+            SYNC => self.offset_ms(&packet, received),
+            FOLLOW_UP => self
+                .pending_syncs
+                .iter()
+                .position(|(port, seq, _)| *port == packet.source_port && *seq == packet.sequence_id)
+                .and_then(|i| self.pending_syncs.remove(i))
+                .and_then(|(_, _, sync_received)| self.offset_ms(&packet, sync_received)),
+            _ => None,
+        };
+        self.messages.push_back(PtpMessage {
+            received,
+            from: from.to_string(),
+            message_type: message_type_name(packet.message_type).to_string(),
+            domain: packet.domain,
+            source_port: packet.source_port,
+            sequence_id: packet.sequence_id,
+            timestamp: packet.timestamp.map(|(s, n)| format!("{}.{:09}", s, n)),
+            offset_ms,
+            announce: packet.announce,
+        });
+        if self.messages.len() > self.capacity {
+            self.messages.pop_front();
+        }
+    }
+
+    fn record_announce(&mut self, packet: &Packet, announce: PtpAnnounce, from: IpAddr, received: DateTime<Utc>) {
+        self.masters
+            .retain(|m| (received - m.last_seen()).num_seconds() < FOREIGN_MASTER_EXPIRY_SECS);
+        let index = match self.masters.iter().position(|m| m.source_port == packet.source_port) {
+            Some(i) => i,
+            None => {
+                self.masters.push(ForeignRecord {
+                    source_port: packet.source_port.clone(),
+                    from,
+                    domain: packet.domain,
+                    announce: announce.clone(),
+                    log_announce_interval: packet.log_message_interval,
+                    received: VecDeque::new(),
+                });
+                self.masters.len() - 1
+            }
+        };
+        let record = &mut self.masters[index];
+        record.from = from;
+        record.domain = packet.domain;
+        record.announce = announce;
+        record.log_announce_interval = packet.log_message_interval;
+        record.received.push_back(received);
+        if record.received.len() > FOREIGN_MASTER_THRESHOLD * 4 {
+            record.received.pop_front();
+        }
+    }
+
+    /// Run the BMCA over the masters heard on `domain` at `now`, selecting
+    /// `forced` instead while a port of that grandmaster is qualified.
+    pub fn masters(&self, domain: u8, forced: Option<&str>, now: DateTime<Utc>) -> PtpMasterStatus {
+        let forced = forced.map(|f| f.to_ascii_lowercase());
+        let live: Vec<&ForeignRecord> = self
+            .masters
+            .iter()
+            .filter(|m| (now - m.last_seen()).num_seconds() < FOREIGN_MASTER_EXPIRY_SECS)
+            .collect();
+        let candidates: Vec<&ForeignRecord> =
+            live.iter().copied().filter(|m| m.domain == domain && m.qualified(now)).collect();
+        let best_of = |records: &mut dyn Iterator<Item = &ForeignRecord>| {
+            records
+                .min_by(|a, b| compare(&a.announce, &a.source_port, &b.announce, &b.source_port).0)
+                .map(|m| m.source_port.clone())
+        };
+        let best = best_of(&mut candidates.iter().copied());
+        let forced_pick = forced.as_ref().and_then(|f| {
+            best_of(&mut candidates.iter().copied().filter(|m| m.announce.grandmaster_identity == *f))
+        });
+        let forced_selected = forced_pick.is_some();
+        let (selected, reason) = match (&forced, forced_pick, best) {
+            (_, Some(pick), _) => (Some(pick), "forced by ptpCapture.grandmaster".to_string()),
+            (_, None, None) => (None, format!("no qualified master on domain {}", domain)),
+            (Some(f), None, Some(best)) => (
+                Some(best),
+                format!("forced grandmaster {} is not announcing; fell back to the BMCA", f),
+            ),
+            (None, None, Some(best)) => (
+                Some(best),
+                format!("BMCA: best of {} qualified master(s)", candidates.len()),
+            ),
+        };
+        let chosen = live.iter().find(|m| Some(&m.source_port) == selected.as_ref());
+        let foreign_masters = live
+            .iter()
+            .map(|m| {
+                let recent = m.recent(now);
+                let reason = if Some(&m.source_port) == selected.as_ref() {
+                    "selected".to_string()
+                } else if m.domain != domain {
+                    format!("domain {}, not {}", m.domain, domain)
+                } else if m.announce.steps_removed >= 255 {
+                    "stepsRemoved is 255".to_string()
+                } else if !m.qualified(now) {
+                    format!(
+                        "not qualified: {} of the {} Announces needed in the last {} intervals",
+                        recent, FOREIGN_MASTER_THRESHOLD, FOREIGN_MASTER_TIME_WINDOW
+                    )
+                } else if forced_selected {
+                    "not the forced grandmaster".to_string()
+                } else {
+                    let c = chosen.expect("a qualified master means one was selected");
+                    format!("lost on {}", compare(&m.announce, &m.source_port, &c.announce, &c.source_port).1)
+                };
+                ForeignMaster {
+                    source_port: m.source_port.clone(),
+                    from: m.from.to_string(),
+                    domain: m.domain,
+                    announce: m.announce.clone(),
+                    recent_announces: recent,
+                    last_seen: m.last_seen(),
+                    qualified: m.qualified(now),
+                    selected: Some(&m.source_port) == selected.as_ref(),
+                    reason,
+                }
+            })
+            .collect();
+        PtpMasterStatus {
+            domain,
+            forced_grandmaster: forced,
+            grandmaster: chosen.map(|m| m.announce.grandmaster_identity.clone()),
+            selected,
+            reason,
+            foreign_masters,
+        }
+    }
+
+    /// The newest `limit` messages (all of them if `None`), oldest first.
+    pub fn messages(&self, limit: Option<usize>) -> Vec<PtpMessage> {
+        let skip = limit.map_or(0, |n| self.messages.len().saturating_sub(n));
+        self.messages.iter().skip(skip).cloned().collect()
+    }
+}
+
+fn open(port: u16, interface: Ipv4Addr) -> std::io::Result<UdpSocket> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    // ptp4l may already hold the port.
+    socket.set_reuse_address(true)?;
+    socket.bind(&SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)).into())?;
+    socket.join_multicast_v4(&PRIMARY_GROUP, &interface)?;
+    socket.join_multicast_v4(&PDELAY_GROUP, &interface)?;
+    socket.set_read_timeout(Some(POLL_TIMEOUT))?;
+    Ok(socket.into())
+}
+
+/// Log messages arriving on `port` while `ptpCapture.enabled` is set. Run
+/// on its own thread, one per port.
+pub fn run(config: Arc<Mutex<Config>>, port: u16, capture: Arc<Mutex<PtpCapture>>, heartbeat: Heartbeat) {
+    let mut bound: Option<(Option<String>, UdpSocket)> = None;
+    let mut buf = [0u8; 1500];
+    loop {
+        heartbeat.beat();
+        let settings = config.lock().unwrap().ptp_capture.clone();
+        capture.lock().unwrap().set_capacity(settings.max_messages);
+        let wanted = settings.enabled.then_some(settings.interface);
+        if bound.as_ref().map(|(interface, _)| interface) != wanted.as_ref() {
+            bound = wanted.and_then(|interface| {
+                let addr = interface.as_deref().and_then(|i| i.parse().ok()).unwrap_or(Ipv4Addr::UNSPECIFIED);
+                match open(port, addr) {
+                    Ok(socket) => {
+                        log::info!("Capturing PTP messages on port {}.", port);
+                        Some((interface, socket))
+                    }
+                    Err(e) => {
+                        log::error!("Cannot capture PTP messages on port {}: {}", port, e);
+                        None
+                    }
+                }
+            });
+        }
+        let Some((_, socket)) = &bound else {
+            std::thread::sleep(POLL_TIMEOUT);
+            continue;
+        };
+        let Ok((len, from)) = socket.recv_from(&mut buf) else {
+            continue;
+        };
+        let received = Utc::now();
+        match parse(&buf[..len]) {
+            Ok(packet) => capture.lock().unwrap().record(packet, from.ip(), received),
+            Err(e) => log::debug!("Ignored PTP message from {}: {}", from, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLOCK: [u8; 8] = [0x00, 0x1b, 0x21, 0xff, 0xfe, 0x12, 0x34, 0x56];
+
+    fn message(message_type: u8, sequence_id: u16, two_step: bool, timestamp: (u64, u32)) -> Vec<u8> {
+        let len = if message_type == ANNOUNCE { ANNOUNCE_LEN } else { HEADER_LEN + TIMESTAMP_LEN };
+        let mut p = vec![0u8; len];
+        p[0] = message_type;
+        p[1] = 2;
+        p[2..4].copy_from_slice(&(len as u16).to_be_bytes());
+        p[4] = 0;
+        p[6] = if two_step { 0x02 } else { 0 };
+        p[20..28].copy_from_slice(&CLOCK);
+        p[28..30].copy_from_slice(&1u16.to_be_bytes());
+        p[30..32].copy_from_slice(&sequence_id.to_be_bytes());
+        p[34..40].copy_from_slice(&timestamp.0.to_be_bytes()[2..]);
+        p[40..44].copy_from_slice(&timestamp.1.to_be_bytes());
+        p
+    }
+
+    fn announce() -> Vec<u8> {
+        announce_from(CLOCK, 6, 0)
+    }
+
+    fn announce_from(clock: [u8; 8], clock_class: u8, domain: u8) -> Vec<u8> {
+        let mut p = message(ANNOUNCE, 7, false, (0, 0));
+        p[4] = domain;
+        p[20..28].copy_from_slice(&clock);
+        p[44..46].copy_from_slice(&37i16.to_be_bytes());
+        p[47] = 128;
+        p[48] = clock_class;
+        p[49] = 0x21;
+        p[50..52].copy_from_slice(&0x4e5du16.to_be_bytes());
+        p[52] = 128;
+        p[53..61].copy_from_slice(&clock);
+        p[61..63].copy_from_slice(&0u16.to_be_bytes());
+        p[63] = 0x20;
+        p
+    }
+
+    fn at(secs: i64, ms: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(secs, 0).unwrap() + chrono::Duration::milliseconds(ms)
+    }
+
+    #[test]
+    fn test_parse_announce() {
+        let packet = parse(&announce()).unwrap();
+        assert_eq!(message_type_name(packet.message_type), "announce");
+        assert_eq!(packet.source_port, "001b21.fffe.123456-1");
+        assert_eq!(packet.sequence_id, 7);
+        let a = packet.announce.unwrap();
+        assert_eq!(a.grandmaster_identity, "001b21.fffe.123456");
+        assert_eq!((a.priority1, a.clock_class, a.clock_accuracy, a.priority2), (128, 6, 0x21, 128));
+        assert_eq!((a.offset_scaled_log_variance, a.time_source, a.current_utc_offset), (0x4e5d, 0x20, 37));
+
+        assert!(parse(&announce()[..50]).is_err());
+        let mut v1 = announce();
+        v1[1] = 1;
+        assert!(parse(&v1).is_err());
+    }
+
+    #[test]
+    fn test_offsets_for_one_and_two_step_sync() {
+        let unix = 1_700_000_000;
+        let tai = (unix + 37) as u64;
+        let from: IpAddr = "192.168.1.10".parse().unwrap();
+        let mut capture = PtpCapture::default();
+
+        // One-step: the Sync carries the master's time.
+        let sync = parse(&message(SYNC, 1, false, (tai, 0))).unwrap();
+        capture.record(sync, from, at(unix, 3));
+        // Two-step: the Follow_Up carries it for the Sync with the same ID.
+        let sync = parse(&message(SYNC, 2, true, (0, 0))).unwrap();
+        capture.record(sync, from, at(unix + 1, -2));
+        let follow_up = parse(&message(FOLLOW_UP, 2, false, (tai + 1, 0))).unwrap();
+        capture.record(follow_up, from, at(unix + 1, 5));
+
+        let messages = capture.messages(None);
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].offset_ms, Some(3.0));
+        assert_eq!(messages[1].message_type, "sync");
+        assert_eq!(messages[1].offset_ms, None);
+        assert_eq!(messages[2].message_type, "followUp");
+        assert_eq!(messages[2].offset_ms, Some(-2.0));
+        assert_eq!(messages[2].timestamp.as_deref(), Some("1700000038.000000000"));
+        assert_eq!(messages[2].from, "192.168.1.10");
+    }
+
+    #[test]
+    fn test_keeps_the_newest_messages() {
+        let from: IpAddr = "192.168.1.10".parse().unwrap();
+        let mut capture = PtpCapture::default();
+        capture.set_capacity(3);
+        for seq in 0..5 {
+            capture.record(parse(&message(SYNC, seq, true, (0, 0))).unwrap(), from, at(0, 0));
+        }
+        let ids: Vec<u16> = capture.messages(None).iter().map(|m| m.sequence_id).collect();
+        assert_eq!(ids, vec![2, 3, 4]);
+        assert_eq!(capture.messages(Some(1))[0].sequence_id, 4);
+        assert_eq!(capture.pending_syncs.len(), 5);
+    }
+
+    #[test]
+    fn test_bmca_selection_and_forced_grandmaster() {
+        let gps = CLOCK;
+        let free_run = [0x00, 0x1b, 0x21, 0xff, 0xfe, 0x65, 0x43, 0x21];
+        let quiet = [0x00, 0x1b, 0x21, 0xff, 0xfe, 0x00, 0x00, 0x01];
+        let other_domain = [0x00, 0x1b, 0x21, 0xff, 0xfe, 0x00, 0x00, 0x02];
+        let from: IpAddr = "192.168.1.10".parse().unwrap();
+        let mut capture = PtpCapture::default();
+        for s in 0..3 {
+            capture.record(parse(&announce_from(gps, 6, 0)).unwrap(), from, at(s, 0));
+            capture.record(parse(&announce_from(free_run, 248, 0)).unwrap(), from, at(s, 0));
+            capture.record(parse(&announce_from(other_domain, 6, 1)).unwrap(), from, at(s, 0));
+        }
+        capture.record(parse(&announce_from(quiet, 6, 0)).unwrap(), from, at(2, 0));
+
+        let status = capture.masters(0, None, at(2, 500));
+        assert_eq!(status.selected.as_deref(), Some("001b21.fffe.123456-1"));
+        assert_eq!(status.grandmaster.as_deref(), Some("001b21.fffe.123456"));
+        assert_eq!(status.reason, "BMCA: best of 2 qualified master(s)");
+        let reason = |port: &str| {
+            let m = status.foreign_masters.iter().find(|m| m.source_port == port).unwrap();
+            m.reason.clone()
+        };
+        assert_eq!(reason("001b21.fffe.654321-1"), "lost on clockClass 248 vs 6");
+        assert_eq!(reason("001b21.fffe.000001-1"), "not qualified: 1 of the 2 Announces needed in the last 4 intervals");
+        assert_eq!(reason("001b21.fffe.000002-1"), "domain 1, not 0");
+
+        let forced = capture.masters(0, Some("001B21.FFFE.654321"), at(2, 500));
+        assert_eq!(forced.selected.as_deref(), Some("001b21.fffe.654321-1"));
+        assert_eq!(forced.reason, "forced by ptpCapture.grandmaster");
+        assert!(forced.foreign_masters.iter().any(|m| m.reason == "not the forced grandmaster"));
+
+        let missing = capture.masters(0, Some("001b21.fffe.000009"), at(2, 500));
+        assert_eq!(missing.selected, status.selected);
+        assert!(missing.reason.contains("not announcing"), "{}", missing.reason);
+
+        // Long after the last Announce nobody is qualified, then forgotten.
+        assert_eq!(capture.masters(0, None, at(10, 0)).selected, None);
+        assert!(capture.masters(0, None, at(120, 0)).foreign_masters.is_empty());
+    }
+}
@@ -0,0 +1,129 @@
+// src/ramp.rs
+//
+// Bringing in a changed timeturner offset gradually. Stepping the clock an
+// offset's worth makes every NTP client downstream step too; with
+// `offsetRampSecs` set the clock is instead run fast or slow with adjtimex
+// until it has gained or lost the difference, which clients follow as a
+// change of frequency. The ramp holds the clock gate throughout, so
+// auto-sync, the re-jam and API clock commands wait for it, and the watchdog
+// doesn't mistake it for an outside step.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::audit::{self, AuditEntry, AuditLog, AuditSource};
+use crate::clock_gate::ClockPermit;
+use crate::config::Config;
+use crate::sync_logic::{LtcFrame, LtcState, LtcView};
+use crate::system::{self, ClockControl};
+
+/// A planned ramp: gain `microseconds` (lose, if negative) over `duration`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ramp {
+    pub microseconds: i64,
+    pub duration: Duration,
+}
+
+/// The ramp that brings the clock to `frame` under `config`, when the
+/// timeturner offset has just changed and `offsetRampSecs` is set. `None`
+/// means step instead, including when the change is too large to ramp in
+/// the time given.
+pub fn plan(frame: &LtcFrame, config: &Config, offset_changed: bool) -> Option<Ramp> {
+    if !offset_changed || config.offset_ramp_secs == 0 {
+        return None;
+    }
+    let microseconds = system::clock_error_us(frame, config).ok()?;
+    let ramp = Ramp {
+        microseconds,
+        duration: Duration::from_secs(config.offset_ramp_secs),
+    };
+    let ppm = microseconds.unsigned_abs() / config.offset_ramp_secs;
+    if ppm > system::MAX_RAMP_PPM as u64 {
+        log::warn!(
+            "Offset change of {:+} ms is too large to ramp in {} s ({} ppm); stepping instead.",
+            microseconds / 1000,
+            config.offset_ramp_secs,
+            ppm
+        );
+        return None;
+    }
+    Some(ramp)
+}
+
+/// Carry out `ramp` under `permit`, blocking until it is done, then audit
+/// it. The drift estimate is reset, since it took the ramp for drift.
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    clock: &dyn ClockControl,
+    permit: ClockPermit,
+    ramp: Ramp,
+    source: AuditSource,
+    pre_delta: Option<i64>,
+    state: &Arc<Mutex<LtcState>>,
+    ltc: &LtcView,
+    audit_log: &Arc<AuditLog>,
+) {
+    let command = system::ramp_command(ramp.microseconds, ramp.duration);
+    log::info!(
+        "Ramping the clock {:+} ms over {} s to the new timeturner offset.",
+        ramp.microseconds / 1000,
+        ramp.duration.as_secs()
+    );
+    let success = clock.ramp(ramp.microseconds, ramp.duration).is_ok();
+    if success {
+        state.lock().unwrap().note_step();
+        log::info!("Ramp to the new timeturner offset finished.");
+    } else {
+        log::error!("{} failed.", command);
+    }
+    permit.note_change(Instant::now());
+    drop(permit);
+    let mut entry = AuditEntry::new(source, "ramp", command, pre_delta);
+    entry.success = success;
+    audit::record_after_settle(audit_log.clone(), ltc.clone(), entry);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TimeturnerOffset;
+    use chrono::Utc;
+    use num_rational::Ratio;
+
+    fn frame_on_time(config: &Config) -> LtcFrame {
+        let mut frame = LtcFrame {
+            status: "LOCK".to_string(),
+            hours: 10,
+            minutes: 0,
+            seconds: 0,
+            frames: 0,
+            is_drop_frame: false,
+            frame_rate: Ratio::new(25, 1),
+            timestamp: Utc::now(),
+            received: None,
+        };
+        let target = system::calculate_target_time(&frame, config).unwrap();
+        frame.timestamp = target.with_timezone(&Utc);
+        frame
+    }
+
+    #[test]
+    fn test_plan() {
+        let frame = frame_on_time(&Config::default());
+        let ramped = Config {
+            timeturner_offset: TimeturnerOffset { frames: -5, ..TimeturnerOffset::default() },
+            offset_ramp_secs: 60,
+            ..Config::default()
+        };
+        let five_frames = Ramp { microseconds: -200_000, duration: Duration::from_secs(60) };
+        assert_eq!(plan(&frame, &ramped, true), Some(five_frames));
+        // Only a change of offset is ramped, and only when asked for.
+        assert_eq!(plan(&frame, &ramped, false), None);
+        let stepped = Config { offset_ramp_secs: 0, ..ramped.clone() };
+        assert_eq!(plan(&frame, &stepped, true), None);
+        // An hour can't be gained in a minute.
+        let hour = TimeturnerOffset { hours: 1, ..TimeturnerOffset::default() };
+        let too_far = Config { timeturner_offset: hour, ..ramped };
+        assert_eq!(plan(&frame, &too_far, true), None);
+    }
+}
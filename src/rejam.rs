@@ -0,0 +1,207 @@
+// src/rejam.rs
+//
+// Scheduled daily hard re-jam: once a day at a configured local time the
+// clock is stepped to LTC, regardless of continuous discipline, unless the
+// facility is on air. Runs are audited with source `schedule`. Scheduled
+// offset presets step the clock through the same `scheduled_step`.
+
+use chrono::{DateTime, Duration, LocalResult, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+use utoipa::ToSchema;
+
+use crate::audit::{self, AuditEntry, AuditLog, AuditSource};
+use crate::clock_gate::ClockGate;
+use crate::config::Config;
+use crate::ramp;
+use crate::sync_logic::{LtcSnapshot, LtcState, LtcView};
+use crate::system::{self, ClockControl};
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RejamConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Local time of day, `HH:MM`, in the system time zone.
+    #[serde(default = "default_rejam_at")]
+    pub at: String,
+}
+
+fn default_rejam_at() -> String {
+    "04:00".to_string()
+}
+
+impl Default for RejamConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            at: default_rejam_at(),
+        }
+    }
+}
+
+impl RejamConfig {
+    /// The configured time of day, or an error naming the bad value.
+    pub fn time(&self) -> Result<NaiveTime, String> {
+        NaiveTime::parse_from_str(&self.at, "%H:%M")
+            .map_err(|_| format!("rejam.at must be HH:MM, got '{}'", self.at))
+    }
+}
+
+/// The first instant strictly after `after` whose local time of day is `at`.
+///
+/// On a day where `at` falls in a DST gap the re-jam runs at the first valid
+/// minute after the gap; where it is ambiguous (clocks going back) it runs
+/// at the earlier of the two.
+pub fn next_run<Tz: TimeZone>(at: NaiveTime, after: &DateTime<Tz>) -> DateTime<Tz> {
+    let tz = after.timezone();
+    let mut day = after.date_naive();
+    loop {
+        let mut local = day.and_time(at);
+        // A gap is at most a few hours; walk forward until the time exists.
+        let resolved = loop {
+            match tz.from_local_datetime(&local) {
+                LocalResult::Single(t) => break t,
+                LocalResult::Ambiguous(earliest, _) => break earliest,
+                LocalResult::None => local += Duration::minutes(1),
+            }
+        };
+        if resolved > *after {
+            return resolved;
+        }
+        day = day.succ_opt().expect("date out of range");
+    }
+}
+
+/// Why a due re-jam must not run now, or `None` if it may.
+pub fn skip_reason(on_air: bool, state: &LtcSnapshot) -> Option<&'static str> {
+    if on_air {
+        return Some("on air");
+    }
+    match &state.latest {
+        Some(_) if state.signal_lost => Some("no LTC signal"),
+        Some(frame) if frame.status == "LOCK" => None,
+        _ => Some("no LTC lock"),
+    }
+}
+
+/// Step `clock` to LTC for a scheduled job (`label` in the log), unless
+/// `on_air` or unlocked, LTC has not been consistent for
+/// `stepConsistentFrames`, or, for a re-jam, the step is over
+/// `maxAutoStepMs`. A just-changed offset is ramped in instead when
+/// `offsetRampSecs` allows, on a thread of its own.
+#[allow(clippy::too_many_arguments)]
+pub fn scheduled_step(
+    label: &str,
+    on_air: bool,
+    offset_changed: bool,
+    clock: &Arc<dyn ClockControl>,
+    config: &Config,
+    state: &Arc<Mutex<LtcState>>,
+    ltc: &LtcView,
+    audit_log: &Arc<AuditLog>,
+    gate: &Arc<ClockGate>,
+) {
+    let (frame, pre_delta, skip, consistent_frames) = {
+        let st = ltc.get();
+        (st.latest.clone(), audit::current_delta(&st), skip_reason(on_air, &st), st.consistent_frames)
+    };
+    let (Some(frame), None) = (frame, skip) else {
+        log::warn!("{}: skipped ({}).", label, skip.unwrap_or("no LTC"));
+        return;
+    };
+    if config.step_too_early(consistent_frames) {
+        log::warn!(
+            "{}: skipped (only {} of {} consecutive LOCK frames are consistent).",
+            label, consistent_frames, config.step_consistent_frames
+        );
+        return;
+    }
+    if let Some(delta) = pre_delta.filter(|&delta| !offset_changed && config.step_over_limit(delta)) {
+        log::warn!(
+            "⚠️ {}: skipped (a {} ms step is over maxAutoStepMs; sync manually with the override).",
+            label, delta
+        );
+        return;
+    }
+    let Some(permit) = gate.try_enter() else {
+        log::warn!("{}: skipped (another clock change is in progress).", label);
+        return;
+    };
+    if let Some(ramp) = ramp::plan(&frame, config, offset_changed) {
+        let (clock, state, ltc, audit_log) = (clock.clone(), state.clone(), ltc.clone(), audit_log.clone());
+        thread::spawn(move || {
+            ramp::run(clock.as_ref(), permit, ramp, AuditSource::Schedule, pre_delta, &state, &ltc, &audit_log)
+        });
+        return;
+    }
+    let (command, success) = system::step_to_ltc(clock.as_ref(), &frame, config, state);
+    if success {
+        state.lock().unwrap().note_step();
+        log::info!("{}: {} successful.", label, command);
+    } else {
+        log::error!("{}: {} failed.", label, command);
+    }
+    permit.note_change(Instant::now());
+    let mut entry = AuditEntry::new(AuditSource::Schedule, "sync", command, pre_delta);
+    entry.success = success;
+    audit::record_after_settle(audit_log.clone(), ltc.clone(), entry);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync_logic::LtcState;
+    use chrono::{FixedOffset, Utc};
+
+    fn time(s: &str) -> NaiveTime {
+        RejamConfig { enabled: true, at: s.to_string() }.time().unwrap()
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(time("04:00"), NaiveTime::from_hms_opt(4, 0, 0).unwrap());
+        assert_eq!(time("23:59"), NaiveTime::from_hms_opt(23, 59, 0).unwrap());
+        assert!(RejamConfig { enabled: true, at: "25:00".into() }.time().is_err());
+        assert!(RejamConfig { enabled: true, at: "4am".into() }.time().is_err());
+    }
+
+    #[test]
+    fn test_next_run_today_or_tomorrow() {
+        let tz = FixedOffset::east_opt(2 * 3600).unwrap();
+        let before = tz.with_ymd_and_hms(2025, 3, 30, 3, 59, 0).unwrap();
+        assert_eq!(
+            next_run(time("04:00"), &before),
+            tz.with_ymd_and_hms(2025, 3, 30, 4, 0, 0).unwrap()
+        );
+        // Exactly at the time counts as done; the next run is tomorrow.
+        let at = tz.with_ymd_and_hms(2025, 3, 30, 4, 0, 0).unwrap();
+        assert_eq!(
+            next_run(time("04:00"), &at),
+            tz.with_ymd_and_hms(2025, 3, 31, 4, 0, 0).unwrap()
+        );
+        // Local time of day, not UTC: 04:00 at +02:00 is 02:00 UTC.
+        assert_eq!(next_run(time("04:00"), &before).with_timezone(&Utc).time(), time("02:00"));
+    }
+
+    #[test]
+    fn test_skip_when_on_air_or_unlocked() {
+        let mut state = LtcState::new();
+        assert_eq!(skip_reason(false, &state.snapshot()), Some("no LTC lock"));
+        state.latest = Some(crate::sync_logic::LtcFrame {
+            status: "LOCK".into(),
+            hours: 1,
+            minutes: 0,
+            seconds: 0,
+            frames: 0,
+            is_drop_frame: false,
+            frame_rate: num_rational::Ratio::new(25, 1),
+            timestamp: Utc::now(),
+            received: None,
+        });
+        assert_eq!(skip_reason(false, &state.snapshot()), None);
+        assert_eq!(skip_reason(true, &state.snapshot()), Some("on air"));
+    }
+}
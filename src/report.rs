@@ -0,0 +1,353 @@
+// src/report.rs
+//
+// End-of-show session report, as proof of what the timecode did: how long LTC
+// was locked and free, the clock steps and slews made, how far the clock
+// drifted from LTC, the alerts raised and how often the serial link dropped.
+// A session runs from daemon start, or from `POST /api/report/reset`, to the
+// moment the report is made. Served at `/api/report` as JSON or HTML and
+// printed by `timeturner-ctl report`.
+
+use chrono::{DateTime, Utc};
+use num_traits::ToPrimitive;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::audit::AuditEntry;
+use crate::hooks::HookEvent;
+use crate::processor::Measurement;
+use crate::sync_logic::LtcFrame;
+
+/// Alerts listed in a report; older ones are only counted.
+pub const MAX_LISTED_ALERTS: usize = 500;
+
+/// One alert raised during the session.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertRecord {
+    #[schema(value_type = String, format = DateTime)]
+    pub timestamp: DateTime<Utc>,
+    /// `lock_lost`, `delta_exceeded`, `ntp_disagreement` or
+    /// `frame_rate_mismatch`.
+    pub kind: String,
+    /// The timecode, or the server and its disagreement.
+    pub detail: String,
+}
+
+/// Running totals for the current session. Steps and slews are read from
+/// the audit log when the report is made.
+#[derive(Debug)]
+pub struct Session {
+    started: DateTime<Utc>,
+    lock_secs: f64,
+    free_secs: f64,
+    deltas: u64,
+    abs_delta_sum: f64,
+    max_delta_ms: Option<f64>,
+    alerts: Vec<AlertRecord>,
+    alert_count: u64,
+    reconnects_before: u64,
+}
+
+impl Session {
+    /// A session starting at `now`, with the serial reader's reconnect count
+    /// so far as its baseline.
+    pub fn new(now: DateTime<Utc>, reconnects: u64) -> Self {
+        Self {
+            started: now,
+            lock_secs: 0.0,
+            free_secs: 0.0,
+            deltas: 0,
+            abs_delta_sum: 0.0,
+            max_delta_ms: None,
+            alerts: Vec::new(),
+            alert_count: 0,
+            reconnects_before: reconnects,
+        }
+    }
+
+    pub fn started(&self) -> DateTime<Utc> {
+        self.started
+    }
+
+    /// Count one frame's duration as LOCK or FREE time, and its measured
+    /// delta if it had one.
+    pub fn record_frame(&mut self, frame: &LtcFrame, measurement: Option<&Measurement>) {
+        let secs = frame.frame_rate.recip().to_f64().unwrap_or(0.0);
+        match frame.status.as_str() {
+            "LOCK" => self.lock_secs += secs,
+            _ => self.free_secs += secs,
+        }
+        if let Some(m) = measurement {
+            self.deltas += 1;
+            self.abs_delta_sum += m.delta_ms.abs();
+            if self.max_delta_ms.is_none_or(|max| m.delta_ms.abs() > max.abs()) {
+                self.max_delta_ms = Some(m.delta_ms);
+            }
+        }
+    }
+
+    pub fn record_alert(&mut self, at: DateTime<Utc>, event: HookEvent, detail: String) {
+        self.alert_count += 1;
+        if self.alerts.len() < MAX_LISTED_ALERTS {
+            self.alerts.push(AlertRecord { timestamp: at, kind: event.as_str().to_string(), detail });
+        }
+    }
+
+    /// The report at `now`: `audit` is the whole audit log and `reconnects`
+    /// the serial reader's reconnect count since startup.
+    pub fn report(&self, audit: &[AuditEntry], reconnects: u64, now: DateTime<Utc>) -> SessionReport {
+        let entries: Vec<&AuditEntry> = audit
+            .iter()
+            .filter(|e| e.timestamp >= self.started && e.success)
+            .collect();
+        let steps: Vec<AuditEntry> = entries
+            .iter()
+            .filter(|e| e.action == "sync")
+            .map(|e| (*e).clone())
+            .collect();
+        let largest_step_ms = steps.iter().filter_map(|e| e.pre_delta_ms).max_by_key(|d| d.abs());
+        let tracked = self.lock_secs + self.free_secs;
+        SessionReport {
+            started: self.started,
+            ended: now,
+            lock_secs: self.lock_secs.round() as u64,
+            free_secs: self.free_secs.round() as u64,
+            lock_percent: if tracked > 0.0 {
+                (self.lock_secs / tracked * 1000.0).round() / 10.0
+            } else {
+                0.0
+            },
+            step_count: steps.len(),
+            largest_step_ms,
+            slew_count: entries.iter().filter(|e| e.action == "nudge" || e.action == "ramp").count(),
+            steps,
+            mean_abs_delta_ms: (self.deltas > 0)
+                .then(|| (self.abs_delta_sum / self.deltas as f64 * 10.0).round() / 10.0),
+            max_delta_ms: self.max_delta_ms.map(|d| (d * 10.0).round() / 10.0),
+            alert_count: self.alert_count,
+            alerts: self.alerts.clone(),
+            serial_reconnects: reconnects.saturating_sub(self.reconnects_before),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionReport {
+    #[schema(value_type = String, format = DateTime)]
+    pub started: DateTime<Utc>,
+    #[schema(value_type = String, format = DateTime)]
+    pub ended: DateTime<Utc>,
+    /// Time covered by LOCK frames, in seconds.
+    pub lock_secs: u64,
+    /// Time covered by FREE frames, in seconds.
+    pub free_secs: u64,
+    /// LOCK time as a share of all time with LTC, to 0.1 %.
+    pub lock_percent: f64,
+    /// Successful clock steps, from every source.
+    pub step_count: usize,
+    /// The delta just before the largest step, in ms.
+    pub largest_step_ms: Option<i64>,
+    /// Successful slews: nudges and offset ramps.
+    pub slew_count: usize,
+    /// The audit entry of each step, oldest first.
+    pub steps: Vec<AuditEntry>,
+    /// Mean size of the smoothed delta over all LOCK frames, in ms.
+    pub mean_abs_delta_ms: Option<f64>,
+    /// The smoothed delta furthest from zero, with its sign, in ms.
+    pub max_delta_ms: Option<f64>,
+    pub alert_count: u64,
+    /// The first `MAX_LISTED_ALERTS` alerts.
+    pub alerts: Vec<AlertRecord>,
+    /// Times the serial port closed and was reopened.
+    pub serial_reconnects: u64,
+}
+
+fn duration(secs: u64) -> String {
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+fn optional_ms<T: std::fmt::Display>(value: Option<T>) -> String {
+    value.map_or("n/a".to_string(), |v| format!("{} ms", v))
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+impl SessionReport {
+    /// The summary lines shared by the text and HTML forms.
+    fn summary(&self) -> Vec<(&'static str, String)> {
+        vec![
+            (
+                "Session",
+                format!(
+                    "{} to {}",
+                    self.started.format("%Y-%m-%d %H:%M:%S UTC"),
+                    self.ended.format("%Y-%m-%d %H:%M:%S UTC")
+                ),
+            ),
+            ("LOCK time", duration(self.lock_secs)),
+            ("FREE time", duration(self.free_secs)),
+            ("Locked", format!("{}%", self.lock_percent)),
+            ("Clock steps", self.step_count.to_string()),
+            ("Largest step", optional_ms(self.largest_step_ms)),
+            ("Clock slews", self.slew_count.to_string()),
+            ("Mean |Δ|", optional_ms(self.mean_abs_delta_ms)),
+            ("Max Δ", optional_ms(self.max_delta_ms)),
+            ("Alerts", self.alert_count.to_string()),
+            ("Serial reconnects", self.serial_reconnects.to_string()),
+        ]
+    }
+
+    /// Plain text, for the terminal.
+    pub fn to_text(&self) -> String {
+        let mut out: String = self
+            .summary()
+            .iter()
+            .map(|(label, value)| format!("{:<18}: {}\n", label, value))
+            .collect();
+        for step in &self.steps {
+            out.push_str(&format!(
+                "Step {} ({}) from {}\n",
+                step.timestamp.format("%H:%M:%S"),
+                step.source.as_str(),
+                optional_ms(step.pre_delta_ms)
+            ));
+        }
+        for alert in &self.alerts {
+            out.push_str(&format!("Alert {} {}: {}\n", alert.timestamp.format("%H:%M:%S"), alert.kind, alert.detail));
+        }
+        out
+    }
+
+    /// A standalone HTML page, for printing or mailing to production.
+    pub fn to_html(&self) -> String {
+        let rows: String = self
+            .summary()
+            .iter()
+            .map(|(label, value)| format!("<tr><th>{}</th><td>{}</td></tr>\n", label, html_escape(value)))
+            .collect();
+        let steps: String = self
+            .steps
+            .iter()
+            .map(|s| {
+                format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                    s.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                    s.source.as_str(),
+                    optional_ms(s.pre_delta_ms),
+                    optional_ms(s.post_delta_ms)
+                )
+            })
+            .collect();
+        let alerts: String = self
+            .alerts
+            .iter()
+            .map(|a| {
+                format!(
+                    "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                    a.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                    a.kind,
+                    html_escape(&a.detail)
+                )
+            })
+            .collect();
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"UTF-8\">\n\
+             <title>NTP Timeturner session report</title>\n\
+             <style>body{{font-family:sans-serif;margin:2em}}table{{border-collapse:collapse;margin-bottom:2em}}\
+             th,td{{border:1px solid #999;padding:4px 10px;text-align:left}}</style>\n\
+             </head>\n<body>\n<h1>NTP Timeturner session report</h1>\n\
+             <table>\n{}</table>\n\
+             <h2>Clock steps</h2>\n<table>\n<tr><th>Time (UTC)</th><th>Source</th><th>Δ before</th><th>Δ after</th></tr>\n{}</table>\n\
+             <h2>Alerts</h2>\n<table>\n<tr><th>Time (UTC)</th><th>Alert</th><th>Detail</th></tr>\n{}</table>\n\
+             </body>\n</html>\n",
+            rows, steps, alerts
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditSource;
+    use num_rational::Ratio;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap()
+    }
+
+    fn frame(status: &str) -> LtcFrame {
+        LtcFrame {
+            status: status.to_string(),
+            hours: 10,
+            minutes: 0,
+            seconds: 0,
+            frames: 0,
+            is_drop_frame: false,
+            frame_rate: Ratio::new(25, 1),
+            timestamp: at(0),
+            received: None,
+        }
+    }
+
+    fn audit(secs: i64, action: &str, pre_delta: i64, success: bool) -> AuditEntry {
+        let mut entry = AuditEntry::new(AuditSource::Auto, action, String::new(), Some(pre_delta));
+        entry.timestamp = at(secs);
+        entry.success = success;
+        entry
+    }
+
+    #[test]
+    fn test_report_totals() {
+        let mut session = Session::new(at(0), 3);
+        for i in 0..250 {
+            let m = Measurement { at: at(0), delta_ms: if i == 7 { -12.0 } else { 2.0 }, raw_ms: 2.0, jitter_ms: 1 };
+            session.record_frame(&frame("LOCK"), Some(&m));
+        }
+        for _ in 0..50 {
+            session.record_frame(&frame("FREE"), None);
+        }
+        session.record_alert(at(20), HookEvent::LockLost, "10:00:10:00".into());
+
+        let log = vec![
+            audit(-5, "sync", 900, true), // before the session
+            audit(5, "sync", 45, true),
+            audit(6, "sync", -80, true),
+            audit(7, "sync", 500, false), // failed
+            audit(8, "nudge", 3, true),
+            audit(9, "ramp", 0, true),
+        ];
+        let report = session.report(&log, 5, at(60));
+
+        assert_eq!((report.lock_secs, report.free_secs), (10, 2));
+        assert_eq!(report.lock_percent, 83.3);
+        assert_eq!(report.step_count, 2);
+        assert_eq!(report.largest_step_ms, Some(-80));
+        assert_eq!(report.slew_count, 2);
+        assert_eq!(report.max_delta_ms, Some(-12.0));
+        assert_eq!(report.mean_abs_delta_ms, Some(2.0));
+        assert_eq!(report.alert_count, 1);
+        assert_eq!(report.alerts[0].kind, "lock_lost");
+        assert_eq!(report.serial_reconnects, 2);
+    }
+
+    #[test]
+    fn test_report_text_and_html() {
+        let mut session = Session::new(at(0), 0);
+        session.record_alert(at(20), HookEvent::NtpDisagreement, "<ntp1> by 150 ms".into());
+        let report = session.report(&[audit(5, "sync", 45, true)], 0, at(3725));
+
+        let text = report.to_text();
+        assert!(text.contains("LOCK time         : 0:00:00\n"));
+        assert!(text.contains("Clock steps       : 1\n"));
+        assert!(text.contains("Mean |Δ|          : n/a\n"));
+        assert!(text.contains("Step 22:13:25 (auto) from 45 ms\n"));
+
+        let html = report.to_html();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<tr><th>Largest step</th><td>45 ms</td></tr>"));
+        assert!(html.contains("&lt;ntp1&gt; by 150 ms"));
+    }
+}
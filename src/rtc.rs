@@ -0,0 +1,152 @@
+// src/rtc.rs
+//
+// RTC write-back: once auto-sync has the clock confirmed in step with LTC,
+// copy the system time to the hardware clock (e.g. a DS3231 hat on a Pi) with
+// `hwclock`, so a reboot without LTC starts close to the right time. The
+// outcome of the latest write is reported in `/api/system`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+use crate::audit::AuditLog;
+use crate::config::Config;
+use crate::health::Heartbeat;
+use crate::sync_logic::{self, LtcSnapshot, LtcView};
+use crate::system;
+
+/// The RTC `hwclock` writes to.
+pub const RTC_DEVICE: &str = "/dev/rtc0";
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// While in sync the RTC is rewritten this often, so its own drift never
+/// builds up.
+const REWRITE_INTERVAL_SECS: i64 = 3600;
+
+/// The outcome of one write to the RTC.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RtcWrite {
+    #[schema(value_type = String, format = DateTime)]
+    pub timestamp: DateTime<Utc>,
+    pub success: bool,
+    /// hwclock's message when the write failed.
+    pub error: Option<String>,
+}
+
+static LAST_WRITE: Mutex<Option<RtcWrite>> = Mutex::new(None);
+
+/// The latest write since startup, if any.
+pub fn last_write() -> Option<RtcWrite> {
+    LAST_WRITE.lock().unwrap().clone()
+}
+
+/// Whether the clock is confirmed in sync: LTC locked and the smoothed delta
+/// IN SYNC. Never while a timeturner offset is active, so the RTC keeps real
+/// time.
+fn confirmed(state: &LtcSnapshot, config: &Config) -> bool {
+    state.latest.as_ref().is_some_and(|f| f.status == "LOCK")
+        && state.ewma_clock_delta.is_some()
+        && sync_logic::get_sync_status(state.get_ewma_clock_delta(), state.staleness(config), config) == "IN SYNC"
+}
+
+/// Whether to write at `now`: on the first confirmed sync, after every step
+/// since the last attempt, and otherwise once an hour.
+fn due(last_attempt: Option<DateTime<Utc>>, secs_since_step: Option<i64>, now: DateTime<Utc>) -> bool {
+    match last_attempt {
+        None => true,
+        Some(at) => {
+            let since = (now - at).num_seconds();
+            since >= REWRITE_INTERVAL_SECS || secs_since_step.is_some_and(|step| step < since)
+        }
+    }
+}
+
+/// Write the RTC whenever `rtcWriteback` is set and a write is due. Runs
+/// until the process exits.
+pub fn run(config: Arc<Mutex<Config>>, ltc: LtcView, audit: Arc<AuditLog>, heartbeat: Heartbeat) {
+    let mut last_attempt = None;
+    loop {
+        heartbeat.beat();
+        thread::sleep(CHECK_INTERVAL);
+        let config = config.lock().unwrap().clone();
+        if !config.rtc_writeback || !Path::new(RTC_DEVICE).exists() {
+            continue;
+        }
+        let now = Utc::now();
+        if !confirmed(&ltc.get(), &config) || !due(last_attempt, audit.secs_since_step(), now) {
+            continue;
+        }
+        last_attempt = Some(now);
+        let result = system::write_rtc();
+        if result.is_ok() {
+            log::info!("RTC set from the system clock.");
+        }
+        *LAST_WRITE.lock().unwrap() = Some(RtcWrite {
+            timestamp: now,
+            success: result.is_ok(),
+            error: result.err(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TimeturnerOffset;
+    use crate::sync_logic::{LtcFrame, LtcState};
+    use chrono::TimeZone;
+    use num_rational::Ratio;
+
+    #[test]
+    fn test_confirmed() {
+        let config = Config::default();
+        let mut state = LtcState::new();
+        assert!(!confirmed(&state.snapshot(), &config));
+
+        state.latest = Some(LtcFrame {
+            status: "LOCK".to_string(),
+            hours: 10,
+            minutes: 0,
+            seconds: 0,
+            frames: 0,
+            is_drop_frame: false,
+            frame_rate: Ratio::new(25, 1),
+            timestamp: Utc::now(),
+            received: None,
+        });
+        state.record_clock_delta(3.0);
+        assert!(!confirmed(&state.snapshot(), &config), "a frame that never arrived is no signal");
+        state.last_frame_at = Some(std::time::Instant::now());
+        assert!(confirmed(&state.snapshot(), &config));
+
+        let offset = Config {
+            timeturner_offset: TimeturnerOffset {
+                hours: 1,
+                ..TimeturnerOffset::default()
+            },
+            ..Config::default()
+        };
+        assert!(!confirmed(&state.snapshot(), &offset));
+
+        state.record_clock_delta(500.0);
+        assert!(!confirmed(&state.snapshot(), &config));
+    }
+
+    #[test]
+    fn test_due() {
+        let at = Utc.with_ymd_and_hms(2025, 8, 7, 10, 0, 0).unwrap();
+        let later = |secs| at + chrono::Duration::seconds(secs);
+        assert!(due(None, None, at));
+        assert!(!due(Some(at), None, later(60)));
+        // A step after the last write brings the next one forward.
+        assert!(due(Some(at), Some(30), later(60)));
+        assert!(!due(Some(at), Some(90), later(60)));
+        assert!(due(Some(at), None, later(REWRITE_INTERVAL_SECS)));
+    }
+}
@@ -0,0 +1,666 @@
+// src/rtp_midi.rs
+//
+// MIDI Time Code over the network: an RTP-MIDI (AppleMIDI, RFC 6295) session
+// responder, so a Mac running QLab can chase LTC through a Network Session in
+// Audio MIDI Setup with no MIDI interface in between. The Mac invites us on
+// the control port and then the data port (the next one up); while LTC is
+// locked every open session is sent quarter-frame messages, plus a full-frame
+// message whenever the timecode jumps or resumes. Incoming MIDI is ignored.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+use crate::config::Config;
+use crate::health::Heartbeat;
+use crate::sync_logic::{self, LtcFrame, LtcView};
+use crate::timecode::{self, Timecode};
+
+/// Longest sleep between servicing the sockets.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Sessions that have sent nothing for this long are dropped. The Mac sends
+/// a clock sync at least every 10 s while connected.
+const SESSION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Invitations beyond this many open sessions are rejected.
+const MAX_SESSIONS: usize = 8;
+
+/// No MTC is sent once the latest frame is older than this.
+const STALE_FRAME_MS: i64 = 500;
+
+const PROTOCOL_VERSION: u32 = 2;
+
+/// RTP payload type used by AppleMIDI.
+const PAYLOAD_TYPE: u8 = 0x61;
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RtpMidiConfig {
+    /// Control port to accept sessions on, e.g. `0.0.0.0:5004`; the data
+    /// port is the one after it. Off while this is unset.
+    #[serde(default)]
+    pub listen: Option<String>,
+    /// Name shown for this host in the Mac's session list.
+    #[serde(default = "default_name")]
+    pub name: String,
+}
+
+fn default_name() -> String {
+    "Timeturner".to_string()
+}
+
+impl Default for RtpMidiConfig {
+    fn default() -> Self {
+        Self {
+            listen: None,
+            name: default_name(),
+        }
+    }
+}
+
+impl RtpMidiConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(listen) = &self.listen {
+            let addr = listen
+                .parse::<SocketAddr>()
+                .map_err(|_| format!("rtpMidi.listen must be address:port, got '{}'", listen))?;
+            if addr.port() == 0 || addr.port() == u16::MAX {
+                return Err("rtpMidi.listen needs a port with a free one after it for data".into());
+            }
+        }
+        if self.name.is_empty() || self.name.contains('\0') {
+            return Err("rtpMidi.name must be a non-empty string".into());
+        }
+        Ok(())
+    }
+}
+
+/// One open session, as served at `/api/rtp_midi`.
+#[derive(Serialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct RtpMidiPeer {
+    /// Name the initiator gave, e.g. the Mac's session name.
+    pub name: String,
+    /// The initiator's control address.
+    pub address: String,
+    /// Whether the data port has been joined too; MTC is only sent then.
+    pub connected: bool,
+    /// Half the round trip of the latest clock sync, in ms.
+    pub latency_ms: Option<f64>,
+    #[schema(value_type = String, format = DateTime)]
+    pub since: DateTime<Utc>,
+}
+
+/// State of the RTP-MIDI responder, served at `/api/rtp_midi`.
+#[derive(Serialize, Clone, Debug, Default, PartialEq, ToSchema)]
+pub struct RtpMidiStatus {
+    /// The control address being listened on, if any.
+    pub listen: Option<String>,
+    /// Whether MTC is being generated (LTC locked at a rate MTC can carry).
+    pub mtc_running: bool,
+    pub sessions: Vec<RtpMidiPeer>,
+}
+
+/// An AppleMIDI session command, sent on either port with a `0xFFFF` prefix.
+#[derive(Clone, Debug, PartialEq)]
+enum Command {
+    Invitation { token: u32, ssrc: u32, name: String },
+    Accepted { token: u32, ssrc: u32, name: String },
+    Rejected { token: u32, ssrc: u32 },
+    End { token: u32, ssrc: u32 },
+    Sync { ssrc: u32, count: u8, timestamps: [u64; 3] },
+    Feedback { ssrc: u32 },
+}
+
+fn u32_at(packet: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(packet.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn u64_at(packet: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(packet.get(at..at + 8)?.try_into().ok()?))
+}
+
+impl Command {
+    fn parse(packet: &[u8]) -> Option<Self> {
+        if packet.get(..2)? != [0xff, 0xff] {
+            return None;
+        }
+        let session = || -> Option<(u32, u32, String)> {
+            let name = packet.get(16..).unwrap_or_default();
+            let name = name.split(|&b| b == 0).next().unwrap_or_default();
+            Some((u32_at(packet, 8)?, u32_at(packet, 12)?, String::from_utf8_lossy(name).into_owned()))
+        };
+        match packet.get(2..4)? {
+            b"IN" => session().map(|(token, ssrc, name)| Command::Invitation { token, ssrc, name }),
+            b"OK" => session().map(|(token, ssrc, name)| Command::Accepted { token, ssrc, name }),
+            b"NO" => session().map(|(token, ssrc, _)| Command::Rejected { token, ssrc }),
+            b"BY" => session().map(|(token, ssrc, _)| Command::End { token, ssrc }),
+            b"CK" => Some(Command::Sync {
+                ssrc: u32_at(packet, 4)?,
+                count: *packet.get(8)?,
+                timestamps: [u64_at(packet, 12)?, u64_at(packet, 20)?, u64_at(packet, 28)?],
+            }),
+            b"RS" => Some(Command::Feedback { ssrc: u32_at(packet, 4)? }),
+            _ => None,
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut out = vec![0xff, 0xff];
+        let mut session = |tag: &[u8; 2], token: u32, ssrc: u32, name: Option<&str>| {
+            out.extend_from_slice(tag);
+            out.extend_from_slice(&PROTOCOL_VERSION.to_be_bytes());
+            out.extend_from_slice(&token.to_be_bytes());
+            out.extend_from_slice(&ssrc.to_be_bytes());
+            if let Some(name) = name {
+                out.extend_from_slice(name.as_bytes());
+                out.push(0);
+            }
+        };
+        match self {
+            Command::Invitation { token, ssrc, name } => session(b"IN", *token, *ssrc, Some(name)),
+            Command::Accepted { token, ssrc, name } => session(b"OK", *token, *ssrc, Some(name)),
+            Command::Rejected { token, ssrc } => session(b"NO", *token, *ssrc, None),
+            Command::End { token, ssrc } => session(b"BY", *token, *ssrc, None),
+            Command::Sync { ssrc, count, timestamps } => {
+                out.extend_from_slice(b"CK");
+                out.extend_from_slice(&ssrc.to_be_bytes());
+                out.extend_from_slice(&[*count, 0, 0, 0]);
+                for ts in timestamps {
+                    out.extend_from_slice(&ts.to_be_bytes());
+                }
+            }
+            Command::Feedback { ssrc } => {
+                out.extend_from_slice(b"RS");
+                out.extend_from_slice(&ssrc.to_be_bytes());
+                out.extend_from_slice(&0u32.to_be_bytes());
+            }
+        }
+        out
+    }
+}
+
+/// Which of the two ports a packet arrived on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Port {
+    Control,
+    Data,
+}
+
+struct Session {
+    ssrc: u32,
+    name: String,
+    control: SocketAddr,
+    data: Option<SocketAddr>,
+    latency_ms: Option<f64>,
+    since: DateTime<Utc>,
+    last_heard: Instant,
+}
+
+/// Our side of every session: the SSRC and name we answer with, and the
+/// start of the 10 kHz clock used for RTP and clock sync timestamps.
+struct Local {
+    ssrc: u32,
+    name: String,
+    started: Instant,
+}
+
+impl Local {
+    fn clock(&self) -> u64 {
+        (self.started.elapsed().as_micros() / 100) as u64
+    }
+}
+
+/// Open sessions, shared with the API.
+#[derive(Default)]
+pub struct RtpMidiSessions {
+    listen: Option<String>,
+    mtc_running: bool,
+    sessions: Vec<Session>,
+}
+
+impl RtpMidiSessions {
+    pub fn status(&self) -> RtpMidiStatus {
+        RtpMidiStatus {
+            listen: self.listen.clone(),
+            mtc_running: self.mtc_running,
+            sessions: self
+                .sessions
+                .iter()
+                .map(|s| RtpMidiPeer {
+                    name: s.name.clone(),
+                    address: s.control.to_string(),
+                    connected: s.data.is_some(),
+                    latency_ms: s.latency_ms,
+                    since: s.since,
+                })
+                .collect(),
+        }
+    }
+
+    /// Handle one session command from `from`, returning the reply to send
+    /// back on the same port.
+    fn handle(&mut self, command: Command, from: SocketAddr, port: Port, local: &Local, now: Instant) -> Option<Command> {
+        let accepted = |token| Command::Accepted {
+            token,
+            ssrc: local.ssrc,
+            name: local.name.clone(),
+        };
+        let rejected = |token| Command::Rejected { token, ssrc: local.ssrc };
+        match (command, port) {
+            (Command::Invitation { token, ssrc, name }, Port::Control) => {
+                self.sessions.retain(|s| s.ssrc != ssrc);
+                if self.sessions.len() >= MAX_SESSIONS {
+                    log::warn!("RTP-MIDI: rejected '{}' from {}, {} sessions already open.", name, from, MAX_SESSIONS);
+                    return Some(rejected(token));
+                }
+                log::info!("RTP-MIDI: session invitation from '{}' at {}.", name, from);
+                self.sessions.push(Session {
+                    ssrc,
+                    name,
+                    control: from,
+                    data: None,
+                    latency_ms: None,
+                    since: Utc::now(),
+                    last_heard: now,
+                });
+                Some(accepted(token))
+            }
+            (Command::Invitation { token, ssrc, .. }, Port::Data) => {
+                match self.sessions.iter_mut().find(|s| s.ssrc == ssrc && s.control.ip() == from.ip()) {
+                    Some(session) => {
+                        log::info!("RTP-MIDI: session with '{}' connected; sending MTC.", session.name);
+                        session.data = Some(from);
+                        session.last_heard = now;
+                        Some(accepted(token))
+                    }
+                    None => Some(rejected(token)),
+                }
+            }
+            (Command::End { ssrc, .. }, _) => {
+                if let Some(session) = self.sessions.iter().find(|s| s.ssrc == ssrc) {
+                    log::info!("RTP-MIDI: session with '{}' ended.", session.name);
+                }
+                self.sessions.retain(|s| s.ssrc != ssrc);
+                None
+            }
+            (Command::Sync { ssrc, count, timestamps }, _) => {
+                let session = self.sessions.iter_mut().find(|s| s.ssrc == ssrc)?;
+                session.last_heard = now;
+                match count {
+                    0 => Some(Command::Sync {
+                        ssrc: local.ssrc,
+                        count: 1,
+                        timestamps: [timestamps[0], local.clock(), 0],
+                    }),
+                    2 => {
+                        let round_trip = timestamps[2].saturating_sub(timestamps[0]);
+                        session.latency_ms = Some(round_trip as f64 / 20.0);
+                        None
+                    }
+                    _ => None,
+                }
+            }
+            (Command::Feedback { ssrc }, _) => {
+                if let Some(session) = self.sessions.iter_mut().find(|s| s.ssrc == ssrc) {
+                    session.last_heard = now;
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Drop sessions not heard from within `SESSION_TIMEOUT`.
+    fn expire(&mut self, now: Instant) {
+        self.sessions.retain(|s| {
+            let alive = now.duration_since(s.last_heard) < SESSION_TIMEOUT;
+            if !alive {
+                log::warn!("RTP-MIDI: session with '{}' timed out.", s.name);
+            }
+            alive
+        });
+    }
+
+    fn data_peers(&self) -> Vec<SocketAddr> {
+        self.sessions.iter().filter_map(|s| s.data).collect()
+    }
+}
+
+/// The MTC rate code for `frame`, or `None` at rates MTC cannot carry.
+/// 23.976 is sent as 24 and 29.97 non-drop as 30, as MTC has no codes for them.
+fn mtc_rate(frame: &LtcFrame) -> Option<u8> {
+    match (timecode::nominal_fps(frame.frame_rate), frame.is_drop_frame) {
+        (24, false) => Some(0),
+        (25, false) => Some(1),
+        (30, true) => Some(2),
+        (30, false) => Some(3),
+        _ => None,
+    }
+}
+
+/// Where MTC is now: the number of quarter frames since midnight, and the
+/// time until the next one, in ms.
+fn mtc_position(frame: &LtcFrame, now: DateTime<Utc>) -> Option<(i64, f64)> {
+    mtc_rate(frame)?;
+    let elapsed_ms = (now - frame.timestamp).num_microseconds()? as f64 / 1000.0;
+    if frame.status != "LOCK" || !(0.0..STALE_FRAME_MS as f64).contains(&elapsed_ms) {
+        return None;
+    }
+    let quarter_ms = sync_logic::frame_duration_ms(frame.frame_rate) / 4.0;
+    let day = Timecode::frames_per_day(frame.frame_rate, frame.is_drop_frame) * 4;
+    let quarters = (elapsed_ms / quarter_ms).floor();
+    let position = (frame.label().frame_number() * 4 + quarters as i64) % day;
+    Some((position, (quarters + 1.0) * quarter_ms - elapsed_ms))
+}
+
+/// Hours, minutes, seconds and frames of frame `number` since midnight, at
+/// `frame`'s rate.
+fn label_bytes(number: i64, frame: &LtcFrame) -> [u8; 4] {
+    let tc = Timecode::from_frame_number(number, frame.frame_rate, frame.is_drop_frame);
+    [tc.hours, tc.minutes, tc.seconds, tc.frames].map(|v| v as u8)
+}
+
+/// The quarter-frame message at `position`. Piece 0 goes out at the start
+/// of every even frame, and all eight carry that frame's label.
+fn quarter_frame(position: i64, frame: &LtcFrame, rate: u8) -> [u8; 2] {
+    let piece = (position % 8) as u8;
+    let [h, m, s, f] = label_bytes(position / 8 * 2, frame);
+    let nibble = match piece {
+        0 => f & 0x0f,
+        1 => f >> 4,
+        2 => s & 0x0f,
+        3 => s >> 4,
+        4 => m & 0x0f,
+        5 => m >> 4,
+        6 => h & 0x0f,
+        _ => (h >> 4) | (rate << 1),
+    };
+    [0xf1, (piece << 4) | nibble]
+}
+
+/// The full-frame SysEx locating a receiver at the frame holding `position`.
+fn full_frame(position: i64, frame: &LtcFrame, rate: u8) -> [u8; 10] {
+    let [h, m, s, f] = label_bytes(position / 4, frame);
+    [0xf0, 0x7f, 0x7f, 0x01, 0x01, (rate << 5) | h, m, s, f, 0xf7]
+}
+
+/// An RTP-MIDI packet carrying `midi` (at most 15 bytes, no journal).
+fn rtp_packet(seq: u16, timestamp: u32, ssrc: u32, midi: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x80, PAYLOAD_TYPE];
+    out.extend_from_slice(&seq.to_be_bytes());
+    out.extend_from_slice(&timestamp.to_be_bytes());
+    out.extend_from_slice(&ssrc.to_be_bytes());
+    out.push(midi.len() as u8);
+    out.extend_from_slice(midi);
+    out
+}
+
+fn bind(addr: &str) -> std::io::Result<(UdpSocket, UdpSocket)> {
+    let control_addr: SocketAddr = addr
+        .parse()
+        .map_err(|_| std::io::Error::new(ErrorKind::InvalidInput, "not address:port"))?;
+    let mut data_addr = control_addr;
+    data_addr.set_port(control_addr.port() + 1);
+    let control = UdpSocket::bind(control_addr)?;
+    let data = UdpSocket::bind(data_addr)?;
+    control.set_nonblocking(true)?;
+    data.set_nonblocking(true)?;
+    Ok((control, data))
+}
+
+/// Run the responder until the process exits, rebinding when
+/// `rtpMidi.listen` changes.
+pub fn run(config: Arc<Mutex<Config>>, ltc: LtcView, shared: Arc<Mutex<RtpMidiSessions>>, heartbeat: Heartbeat) {
+    let seed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().subsec_nanos();
+    let mut local = Local {
+        ssrc: seed ^ std::process::id().rotate_left(16),
+        name: default_name(),
+        started: Instant::now(),
+    };
+    let mut bound: Option<(String, UdpSocket, UdpSocket)> = None;
+    let mut last_position: Option<i64> = None;
+    let mut seq: u16 = 0;
+    let mut buf = [0u8; 1500];
+    loop {
+        heartbeat.beat();
+        let rtp = config.lock().unwrap().rtp_midi.clone();
+        local.name = rtp.name;
+        if bound.as_ref().map(|(addr, ..)| addr) != rtp.listen.as_ref() {
+            let mut sessions = shared.lock().unwrap();
+            *sessions = RtpMidiSessions::default();
+            bound = rtp.listen.as_ref().and_then(|addr| match bind(addr) {
+                Ok((control, data)) => {
+                    log::info!("RTP-MIDI: accepting sessions on {}.", addr);
+                    sessions.listen = Some(addr.clone());
+                    Some((addr.clone(), control, data))
+                }
+                Err(e) => {
+                    log::error!("RTP-MIDI cannot listen on {}: {}", addr, e);
+                    None
+                }
+            });
+        }
+        let Some((_, control, data)) = &bound else {
+            std::thread::sleep(POLL_INTERVAL);
+            continue;
+        };
+
+        for (socket, port) in [(control, Port::Control), (data, Port::Data)] {
+            while let Ok((len, from)) = socket.recv_from(&mut buf) {
+                let Some(command) = Command::parse(&buf[..len]) else {
+                    continue;
+                };
+                let reply = shared.lock().unwrap().handle(command, from, port, &local, Instant::now());
+                if let Some(reply) = reply {
+                    if let Err(e) = socket.send_to(&reply.encode(), from) {
+                        log::debug!("RTP-MIDI reply to {} failed: {}", from, e);
+                    }
+                }
+            }
+        }
+        shared.lock().unwrap().expire(Instant::now());
+
+        let latest = ltc.get().latest.clone();
+        let position = latest.as_ref().and_then(|f| Some((f, mtc_rate(f)?, mtc_position(f, Utc::now())?)));
+        let mut sleep = POLL_INTERVAL;
+        let peers = {
+            let mut sessions = shared.lock().unwrap();
+            sessions.mtc_running = position.is_some();
+            sessions.data_peers()
+        };
+        if let Some((frame, rate, (at, until_next))) = position {
+            if last_position != Some(at) {
+                let midi = if last_position == Some(at - 1) {
+                    quarter_frame(at, frame, rate).to_vec()
+                } else {
+                    full_frame(at, frame, rate).to_vec()
+                };
+                let packet = rtp_packet(seq, local.clock() as u32, local.ssrc, &midi);
+                seq = seq.wrapping_add(1);
+                for peer in &peers {
+                    if let Err(e) = data.send_to(&packet, peer) {
+                        log::debug!("RTP-MIDI send to {} failed: {}", peer, e);
+                    }
+                }
+            }
+            last_position = Some(at);
+            // Wake just after the next quarter frame is due.
+            sleep = sleep.min(Duration::from_secs_f64((until_next + 0.2) / 1000.0));
+        } else {
+            last_position = None;
+        }
+        std::thread::sleep(sleep);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_rational::Ratio;
+
+    /// Hours, minutes, seconds and frames.
+    type Label = [u32; 4];
+
+    fn frame(label: Label, rate: Ratio<i64>, drop_frame: bool) -> LtcFrame {
+        LtcFrame {
+            status: "LOCK".to_string(),
+            hours: label[0],
+            minutes: label[1],
+            seconds: label[2],
+            frames: label[3],
+            is_drop_frame: drop_frame,
+            frame_rate: rate,
+            timestamp: Utc::now(),
+            received: None,
+        }
+    }
+
+    fn local() -> Local {
+        Local {
+            ssrc: 0x1234_5678,
+            name: "Timeturner".to_string(),
+            started: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_command_round_trip() {
+        let invitation = Command::Invitation {
+            token: 7,
+            ssrc: 0xdead_beef,
+            name: "Stage Mac".to_string(),
+        };
+        let bytes = invitation.encode();
+        assert_eq!(&bytes[..4], b"\xff\xffIN");
+        assert_eq!(&bytes[4..8], &2u32.to_be_bytes());
+        assert_eq!(bytes.last(), Some(&0));
+        assert_eq!(Command::parse(&bytes), Some(invitation));
+
+        let sync = Command::Sync {
+            ssrc: 1,
+            count: 2,
+            timestamps: [10, 20, 30],
+        };
+        assert_eq!(sync.encode().len(), 36);
+        assert_eq!(Command::parse(&sync.encode()), Some(sync));
+        assert_eq!(Command::parse(b"\xff\xffXX\0\0\0\0"), None);
+        assert_eq!(Command::parse(&[0x80, 0x61, 0, 1]), None);
+    }
+
+    #[test]
+    fn test_session_handshake() {
+        let mut sessions = RtpMidiSessions::default();
+        let local = local();
+        let now = Instant::now();
+        let control: SocketAddr = "192.0.2.10:5004".parse().unwrap();
+        let data: SocketAddr = "192.0.2.10:5005".parse().unwrap();
+        let invite = || Command::Invitation {
+            token: 99,
+            ssrc: 42,
+            name: "QLab".to_string(),
+        };
+
+        // The data port is only joined after the control port.
+        assert!(matches!(
+            sessions.handle(invite(), data, Port::Data, &local, now),
+            Some(Command::Rejected { token: 99, .. })
+        ));
+        let reply = sessions.handle(invite(), control, Port::Control, &local, now);
+        assert_eq!(
+            reply,
+            Some(Command::Accepted {
+                token: 99,
+                ssrc: local.ssrc,
+                name: "Timeturner".to_string()
+            })
+        );
+        assert!(sessions.data_peers().is_empty());
+        assert!(sessions.handle(invite(), data, Port::Data, &local, now).is_some());
+        assert_eq!(sessions.data_peers(), vec![data]);
+
+        let reply = sessions.handle(
+            Command::Sync {
+                ssrc: 42,
+                count: 0,
+                timestamps: [500, 0, 0],
+            },
+            data,
+            Port::Data,
+            &local,
+            now,
+        );
+        assert!(matches!(reply, Some(Command::Sync { count: 1, timestamps: [500, _, 0], .. })));
+        sessions.handle(
+            Command::Sync {
+                ssrc: 42,
+                count: 2,
+                timestamps: [500, 510, 540],
+            },
+            data,
+            Port::Data,
+            &local,
+            now,
+        );
+        let status = sessions.status();
+        assert_eq!(status.sessions.len(), 1);
+        assert_eq!(status.sessions[0].name, "QLab");
+        assert!(status.sessions[0].connected);
+        assert_eq!(status.sessions[0].latency_ms, Some(2.0));
+
+        sessions.expire(now + SESSION_TIMEOUT / 2);
+        assert_eq!(sessions.status().sessions.len(), 1);
+        sessions.handle(Command::End { token: 99, ssrc: 42 }, control, Port::Control, &local, now);
+        assert!(sessions.status().sessions.is_empty());
+
+        sessions.handle(invite(), control, Port::Control, &local, now);
+        sessions.expire(now + SESSION_TIMEOUT);
+        assert!(sessions.status().sessions.is_empty());
+    }
+
+    #[test]
+    fn test_mtc_messages() {
+        let f = frame([10, 20, 30, 12], Ratio::new(25, 1), false);
+        let rate = mtc_rate(&f).unwrap();
+        assert_eq!(rate, 1);
+        let position = f.label().frame_number() * 4;
+        let pieces: Vec<u8> = (0..8).map(|i| quarter_frame(position + i, &f, rate)[1]).collect();
+        // 10:20:30:12 = 0x0a:0x14:0x1e:0x0c
+        assert_eq!(pieces, vec![0x0c, 0x10, 0x2e, 0x31, 0x44, 0x51, 0x6a, 0x72]);
+        assert_eq!(
+            full_frame(position + 3, &f, rate),
+            [0xf0, 0x7f, 0x7f, 0x01, 0x01, 0x2a, 20, 30, 12, 0xf7]
+        );
+
+        let df = frame([1, 0, 0, 0], Ratio::new(30000, 1001), true);
+        assert_eq!(mtc_rate(&df), Some(2));
+        assert_eq!(mtc_rate(&frame([1, 0, 0, 0], Ratio::new(50, 1), false)), None);
+
+        assert_eq!(
+            rtp_packet(1, 2, 3, &[0xf1, 0x0c]),
+            vec![0x80, 0x61, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 2, 0xf1, 0x0c]
+        );
+    }
+
+    #[test]
+    fn test_mtc_position() {
+        let mut f = frame([0, 0, 1, 0], Ratio::new(25, 1), false);
+        let now = f.timestamp + chrono::Duration::milliseconds(25);
+        let (at, until_next) = mtc_position(&f, now).unwrap();
+        assert_eq!(at, 25 * 4 + 2);
+        assert!((until_next - 5.0).abs() < 0.01, "{}", until_next);
+
+        assert_eq!(mtc_position(&f, f.timestamp + chrono::Duration::seconds(1)), None);
+        f.status = "FREE".to_string();
+        assert_eq!(mtc_position(&f, now), None);
+
+        // The last quarter of the day wraps to midnight.
+        let f = frame([23, 59, 59, 24], Ratio::new(25, 1), false);
+        let (at, _) = mtc_position(&f, f.timestamp + chrono::Duration::milliseconds(45)).unwrap();
+        assert_eq!(at, 0);
+    }
+}
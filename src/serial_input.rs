@@ -1,178 +1,667 @@
 ﻿// src/serial_input.rs
 
-use std::io::BufRead;
+use std::io::{BufRead, ErrorKind, Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
-use std::sync::mpsc::Sender;
-use chrono::Utc;
+use std::time::Instant;
+use chrono::Duration;
 use regex::Regex;
-use crate::sync_logic::{LtcFrame, LtcState};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use crate::processor::FrameSender;
+use crate::sync_logic::{ClockPair, LtcFrame};
 
-pub fn start_serial_thread(
-    port_path: &str,
-    baud_rate: u32,
-    sender: Sender<LtcFrame>,
-    state: Arc<Mutex<LtcState>>,
-    _hardware_offset_ms: i64, // no longer used here
-) {
-    println!("📡 Opening serial port {} @ {} baud", port_path, baud_rate);
-
-    let port = match serialport::new(port_path, baud_rate)
-        .timeout(std::time::Duration::from_millis(1000))
-        .open()
-    {
-        Ok(p) => {
-            println!("✅ Serial port opened");
-            p
+/// Bits on the wire per serial character: start, eight data bits, stop.
+const BITS_PER_CHAR: i64 = 10;
+
+/// How long after an LTC frame the reader's line for it is read. Arrival
+/// stamps are moved back by this, so the delta measures the clock rather
+/// than the reader.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReaderLatency {
+    /// The reader's own latency, from the frame to its first character, in µs.
+    #[serde(default)]
+    pub fixed_us: i64,
+    /// Also count the time to send the line itself at the port's baud rate.
+    #[serde(default)]
+    pub per_character: bool,
+}
+
+impl ReaderLatency {
+    /// The latency of a line of `chars` characters (its terminator included)
+    /// read at `baud_rate`.
+    pub fn for_line(&self, chars: usize, baud_rate: u32) -> Duration {
+        let sending_us = if self.per_character && baud_rate > 0 {
+            chars as i64 * BITS_PER_CHAR * 1_000_000 / baud_rate as i64
+        } else {
+            0
+        };
+        Duration::microseconds(self.fixed_us + sending_us)
+    }
+}
+
+/// The reader firmware's lines, e.g. `[LOCK] 10:20:30:00 | 25.00fps`.
+pub const DEFAULT_LINE_PATTERN: &str =
+    r"\[(?P<status>LOCK|FREE)\]\s+(?P<hours>\d{2}):(?P<minutes>\d{2}):(?P<seconds>\d{2})(?P<sep>[:;])(?P<frames>\d{2})\s+\|\s+(?P<fps>[\d.]+)fps";
+
+/// Capture groups an `ltcLinePattern` must name. `sep` is optional: with
+/// it, `;` marks drop-frame.
+const LINE_GROUPS: [&str; 6] = ["status", "hours", "minutes", "seconds", "frames", "fps"];
+
+/// How the reader's lines are parsed into frames: `DEFAULT_LINE_PATTERN`,
+/// or the config's `ltcLinePattern` for firmware that prints them
+/// differently.
+#[derive(Clone, Debug)]
+pub struct LinePattern(Regex);
+
+impl Default for LinePattern {
+    fn default() -> Self {
+        Self::new(DEFAULT_LINE_PATTERN).expect("the default pattern is valid")
+    }
+}
+
+impl LinePattern {
+    /// Compile `pattern`, checking it names every group in `LINE_GROUPS`.
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let re = Regex::new(pattern).map_err(|e| format!("ltcLinePattern is not a valid regex: {}", e))?;
+        let names: Vec<&str> = re.capture_names().flatten().collect();
+        if let Some(missing) = LINE_GROUPS.iter().find(|group| !names.contains(group)) {
+            return Err(format!("ltcLinePattern needs a capture group named {}", missing));
         }
-        Err(e) => {
-            eprintln!("❌ Serial open failed: {}", e);
-            return;
+        Ok(Self(re))
+    }
+
+    /// The pattern from `ltcLinePattern`, or the default when unset.
+    pub fn from_config(pattern: Option<&str>) -> Result<Self, String> {
+        pattern.map_or_else(|| Ok(Self::default()), Self::new)
+    }
+
+    /// The frame on `line`, if it matches and names a valid timecode.
+    pub fn parse(&self, line: &str, arrival: ClockPair) -> Option<LtcFrame> {
+        LtcFrame::from_regex(&self.0.captures(line)?, arrival)
+    }
+}
+
+/// Baud rate of the Teensy/Arduino LTC reader.
+pub const DEFAULT_BAUD_RATE: u32 = 115200;
+
+/// Sent on open to ask the reader for its firmware; it answers with a line
+/// such as `[VERSION] ltc_audiohat_lock_v2 2.1.0 caps=fps-detect,free-run`.
+const FIRMWARE_PROBE: &[u8] = b"?\n";
+
+/// A board that resets when the port opens may miss the first probe, so it
+/// is repeated this often, up to `PROBE_ATTEMPTS` times.
+const PROBE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+const PROBE_ATTEMPTS: u32 = 3;
+
+/// What the reader says it runs, from its answer to the probe.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReaderFirmware {
+    /// The build, e.g. `ltc_audiohat_lock_v2`.
+    pub name: String,
+    pub version: String,
+    pub capabilities: Vec<String>,
+}
+
+/// Parse the reader's `[VERSION] <name> <version> [caps=<a>,<b>]` line.
+pub fn parse_firmware(line: &str) -> Option<ReaderFirmware> {
+    let mut words = line.trim().strip_prefix("[VERSION]")?.split_whitespace();
+    let name = words.next()?.to_string();
+    let version = words.next()?.to_string();
+    let capabilities = words
+        .find_map(|word| word.strip_prefix("caps="))
+        .map(|caps| caps.split(',').filter(|cap| !cap.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default();
+    Some(ReaderFirmware { name, version, capabilities })
+}
+
+/// USB vendor IDs of the boards the reader is built on (PJRC Teensy,
+/// Arduino), preferred when no `serialDevices` are configured.
+const READER_VIDS: [u16; 2] = [0x16c0, 0x2341];
+
+fn default_baud_rate() -> u32 {
+    DEFAULT_BAUD_RATE
+}
+
+/// A USB serial device that may be the LTC reader, matched on whichever of
+/// VID, PID and serial number are set, with its own port settings.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SerialDevice {
+    /// USB vendor ID in hex, e.g. `16c0`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vid: Option<String>,
+    /// USB product ID in hex, e.g. `0483`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub serial_number: Option<String>,
+    #[serde(default = "default_baud_rate")]
+    pub baud_rate: u32,
+    /// Overrides the top-level `readerLatency` for this device.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reader_latency: Option<ReaderLatency>,
+}
+
+/// A hex USB ID such as `16c0` or `0x16C0`.
+fn parse_usb_id(id: &str) -> Option<u16> {
+    let hex = id.trim().trim_start_matches("0x").trim_start_matches("0X");
+    u16::from_str_radix(hex, 16).ok()
+}
+
+impl SerialDevice {
+    fn matches(&self, port: &PortInfo) -> bool {
+        let Some(usb) = &port.usb else { return false };
+        self.vid.as_deref().is_none_or(|vid| parse_usb_id(vid) == Some(usb.vid))
+            && self.pid.as_deref().is_none_or(|pid| parse_usb_id(pid) == Some(usb.pid))
+            && self.serial_number.as_ref().is_none_or(|serial| usb.serial_number.as_ref() == Some(serial))
+    }
+}
+
+/// Check `serialDevices`: every entry names at least one ID, and IDs are hex.
+pub fn validate_devices(devices: &[SerialDevice]) -> Result<(), String> {
+    for (i, device) in devices.iter().enumerate() {
+        if device.vid.is_none() && device.pid.is_none() && device.serial_number.is_none() {
+            return Err(format!("serialDevices[{}] needs a vid, pid or serialNumber", i));
+        }
+        for (name, id) in [("vid", &device.vid), ("pid", &device.pid)] {
+            if id.as_deref().is_some_and(|id| parse_usb_id(id).is_none()) {
+                return Err(format!("serialDevices[{}].{} must be a hex USB ID such as 16c0", i, name));
+            }
+        }
+        if device.baud_rate == 0 {
+            return Err(format!("serialDevices[{}].baudRate must be greater than zero", i));
+        }
+    }
+    Ok(())
+}
+
+/// USB identity of a serial port.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UsbId {
+    pub vid: u16,
+    pub pid: u16,
+    pub serial_number: Option<String>,
+}
+
+/// A serial port on this host.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PortInfo {
+    pub path: String,
+    /// Set for USB serial devices.
+    pub usb: Option<UsbId>,
+    /// Manufacturer and product, as a USB device reports them.
+    pub description: Option<String>,
+}
+
+/// A serial port as `GET /api/serial/ports` lists it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SerialPortReport {
+    pub path: String,
+    /// USB vendor ID in hex; `null` unless the port is USB.
+    pub vid: Option<String>,
+    /// USB product ID in hex.
+    pub pid: Option<String>,
+    pub serial_number: Option<String>,
+    pub description: Option<String>,
+    /// Whether the LTC reader is read from this port.
+    pub active: bool,
+}
+
+impl PortInfo {
+    pub fn report(&self, active_path: &str) -> SerialPortReport {
+        SerialPortReport {
+            path: self.path.clone(),
+            vid: self.usb.as_ref().map(|usb| format!("{:04x}", usb.vid)),
+            pid: self.usb.as_ref().map(|usb| format!("{:04x}", usb.pid)),
+            serial_number: self.usb.as_ref().and_then(|usb| usb.serial_number.clone()),
+            description: self.description.clone(),
+            active: self.path == active_path,
         }
+    }
+}
+
+/// The serial ports on this host, empty if they cannot be listed.
+pub fn available_ports() -> Vec<PortInfo> {
+    serialport::available_ports()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|p| match p.port_type {
+            serialport::SerialPortType::UsbPort(usb) => {
+                let names: Vec<String> = [usb.manufacturer, usb.product].into_iter().flatten().collect();
+                PortInfo {
+                    path: p.port_name,
+                    usb: Some(UsbId { vid: usb.vid, pid: usb.pid, serial_number: usb.serial_number }),
+                    description: (!names.is_empty()).then(|| names.join(" ")),
+                }
+            }
+            _ => PortInfo { path: p.port_name, usb: None, description: None },
+        })
+        .collect()
+}
+
+/// The port to read LTC from and how to read it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ReaderPort {
+    pub path: String,
+    pub baud_rate: u32,
+    pub latency: ReaderLatency,
+}
+
+/// How to read `port`: with the settings of the first of `devices` it
+/// matches, or else at `DEFAULT_BAUD_RATE` with `latency`.
+pub fn reader_on(port: &PortInfo, devices: &[SerialDevice], latency: ReaderLatency) -> ReaderPort {
+    let device = devices.iter().find(|device| device.matches(port));
+    ReaderPort {
+        path: port.path.clone(),
+        baud_rate: device.map_or(DEFAULT_BAUD_RATE, |d| d.baud_rate),
+        latency: device.and_then(|d| d.reader_latency).unwrap_or(latency),
+    }
+}
+
+/// Pick the LTC reader among `ports`. With `devices` configured, the first
+/// device (in config order) that is plugged in wins, whatever port it is on.
+/// Without, a Teensy or Arduino is preferred over other USB serial devices,
+/// then the first ttyACM, ttyAMA or ttyUSB port is taken.
+pub fn choose_reader(ports: &[PortInfo], devices: &[SerialDevice], latency: ReaderLatency) -> Option<ReaderPort> {
+    if !devices.is_empty() {
+        return devices.iter().find_map(|device| {
+            ports.iter().find(|port| device.matches(port)).map(|port| ReaderPort {
+                path: port.path.clone(),
+                baud_rate: device.baud_rate,
+                latency: device.reader_latency.unwrap_or(latency),
+            })
+        });
+    }
+    let is_tty = |p: &&PortInfo| {
+        ["/dev/ttyACM", "/dev/ttyAMA", "/dev/ttyUSB"].iter().any(|prefix| p.path.starts_with(prefix))
     };
+    ports
+        .iter()
+        .filter(is_tty)
+        .find(|p| p.usb.as_ref().is_some_and(|usb| READER_VIDS.contains(&usb.vid)))
+        .or_else(|| ports.iter().find(is_tty))
+        .map(|port| ReaderPort {
+            path: port.path.clone(),
+            baud_rate: DEFAULT_BAUD_RATE,
+            latency,
+        })
+}
+
+/// How long a read waits before checking whether the port was switched.
+const READ_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(1000);
+
+/// How long to wait before reopening a port that failed.
+const REOPEN_DELAY: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// The port the serial reader thread reads, switchable at runtime with
+/// `PUT /api/serial/port`. VITC input is fixed and cannot be switched.
+pub struct ReaderSelection {
+    current: Mutex<ReaderPort>,
+    firmware: Mutex<Option<ReaderFirmware>>,
+    generation: AtomicU64,
+    reconnects: AtomicU64,
+    switchable: bool,
+}
+
+impl ReaderSelection {
+    /// A serial reader on `port`.
+    pub fn serial(port: ReaderPort) -> Self {
+        Self {
+            current: Mutex::new(port),
+            firmware: Mutex::new(None),
+            generation: AtomicU64::new(0),
+            reconnects: AtomicU64::new(0),
+            switchable: true,
+        }
+    }
+
+    /// An input that is not a serial port, reported as `path`.
+    pub fn fixed(path: &str) -> Self {
+        let port = ReaderPort { path: path.to_string(), baud_rate: 0, latency: ReaderLatency::default() };
+        Self { switchable: false, ..Self::serial(port) }
+    }
+
+    pub fn current(&self) -> ReaderPort {
+        self.current.lock().unwrap().clone()
+    }
+
+    pub fn is_switchable(&self) -> bool {
+        self.switchable
+    }
+
+    /// Have the reader thread move to `port`. `false` for a fixed input.
+    pub fn switch(&self, port: ReaderPort) -> bool {
+        if !self.switchable {
+            return false;
+        }
+        *self.current.lock().unwrap() = port;
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        true
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// The firmware the reader on the current port reported; `None` until
+    /// it answers the probe, and for firmware that never does.
+    pub fn firmware(&self) -> Option<ReaderFirmware> {
+        self.firmware.lock().unwrap().clone()
+    }
+
+    fn set_firmware(&self, firmware: Option<ReaderFirmware>) {
+        *self.firmware.lock().unwrap() = firmware;
+    }
+
+    /// Times the selected port closed under the reader and was reopened.
+    pub fn reconnects(&self) -> u64 {
+        self.reconnects.load(Ordering::SeqCst)
+    }
+}
 
-    let reader = std::io::BufReader::new(port);
-    let re = Regex::new(
-        r"\[(LOCK|FREE)\]\s+(\d{2}):(\d{2}):(\d{2})([:;])(\d{2})\s+\|\s+([\d.]+)fps",
-    )
-    .unwrap();
-
-    println!("🔄 Entering LTC read loop…");
-    for line in reader.lines() {
-        if let Ok(text) = line {
-            if let Some(caps) = re.captures(&text) {
-                let arrival = Utc::now();
-                if let Some(frame) = LtcFrame::from_regex(&caps, arrival) {
-                    // update LOCK/FREE counts & timestamp
-                    {
-                        let mut st = state.lock().unwrap();
-                        st.update(frame.clone());
-                    }
-                    // forward raw frame
-                    let _ = sender.send(frame);
+/// Read LTC from the selected port until the processor goes away, reopening
+/// the port after an error and moving to another when the selection changes.
+/// Each time the port opens the reader is asked for its firmware.
+pub fn run_serial_reader(selection: Arc<ReaderSelection>, sender: FrameSender, pattern: LinePattern) {
+    loop {
+        let generation = selection.generation();
+        let reader = selection.current();
+        log::info!("📡 Opening serial port {} @ {} baud", reader.path, reader.baud_rate);
+        let port = match serialport::new(&reader.path, reader.baud_rate).timeout(READ_TIMEOUT).open() {
+            Ok(port) => port,
+            Err(e) => {
+                log::error!("❌ Serial open failed: {}", e);
+                std::thread::sleep(REOPEN_DELAY);
+                continue;
+            }
+        };
+        log::info!("🔄 Entering LTC read loop on {}", reader.path);
+        selection.set_firmware(None);
+        let still_selected = || selection.generation() == generation;
+        let mut probe = port.try_clone().ok();
+        let (mut probes, mut last_probe) = (0, None::<Instant>);
+        let mut keep_reading = || {
+            let due = last_probe.is_none_or(|at| at.elapsed() >= PROBE_INTERVAL);
+            if selection.firmware().is_none() && probes < PROBE_ATTEMPTS && due {
+                if let Some(port) = probe.as_mut() {
+                    let _ = port.write_all(FIRMWARE_PROBE);
                 }
+                probes += 1;
+                last_probe = Some(Instant::now());
+            }
+            still_selected()
+        };
+        let on_firmware = |firmware: ReaderFirmware| {
+            log::info!("Reader firmware {} {} ({})", firmware.name, firmware.version, firmware.capabilities.join(", "));
+            selection.set_firmware(Some(firmware));
+        };
+        let read = read_lines(port, &sender, &pattern, reader.latency, reader.baud_rate, &mut keep_reading, &on_firmware);
+        if !read {
+            return;
+        }
+        if still_selected() {
+            log::warn!("Serial port {} closed; reopening.", reader.path);
+            selection.reconnects.fetch_add(1, Ordering::SeqCst);
+            std::thread::sleep(REOPEN_DELAY);
+        }
+    }
+}
+
+/// Parse the reader's `[LOCK] HH:MM:SS:FF | 25.00fps` lines into frames and
+/// queue each one until the input ends or the processor goes away. Each frame
+/// is stamped with its arrival time less `latency` for its line. The
+/// serial path takes no locks; the processor owns the state. Used for simulated
+/// LTC in tests; the serial port goes through `run_serial_reader`.
+pub fn read_ltc_lines<R: Read>(input: R, sender: FrameSender, latency: ReaderLatency, baud_rate: u32) {
+    read_lines(input, &sender, &LinePattern::default(), latency, baud_rate, &mut || true, &|_| {});
+}
+
+/// `read_ltc_lines`, also stopping once `keep_reading` says so; it is asked
+/// after every line and read timeout. A firmware line goes to `on_firmware`.
+/// Returns `false` once the processor has gone.
+fn read_lines<R: Read>(
+    input: R,
+    sender: &FrameSender,
+    pattern: &LinePattern,
+    latency: ReaderLatency,
+    baud_rate: u32,
+    keep_reading: &mut dyn FnMut() -> bool,
+    on_firmware: &dyn Fn(ReaderFirmware),
+) -> bool {
+    let mut reader = std::io::BufReader::new(input);
+
+    // A line cut short by a timeout is kept and completed by the next read.
+    let mut line = Vec::new();
+    while keep_reading() {
+        let at_end = match reader.read_until(b'\n', &mut line) {
+            Ok(0) => return true,
+            Ok(_) => line.last() != Some(&b'\n'),
+            Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::Interrupted) => continue,
+            Err(_) => return true,
+        };
+        let text = String::from_utf8_lossy(&line);
+        let arrival = ClockPair::now().earlier(latency.for_line(line.len(), baud_rate));
+        if let Some(firmware) = parse_firmware(&text) {
+            on_firmware(firmware);
+        } else if let Some(frame) = pattern.parse(&text, arrival) {
+            if !sender.send(frame) {
+                return false;
             }
         }
+        if at_end {
+            return true;
+        }
+        line.clear();
     }
+    true
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::sync::mpsc;
-    use crate::sync_logic::LtcState;
+    use chrono::Utc;
+    use std::io::Cursor;
+    use crate::processor::frame_channel;
     use num_rational::Ratio;
-    use regex::Regex;
 
-    fn get_ltc_regex() -> Regex {
-        Regex::new(
-            r"\[(LOCK|FREE)\]\s+(\d{2}):(\d{2}):(\d{2})([:;])(\d{2})\s+\|\s+([\d.]+)fps",
-        ).unwrap()
+    /// Run `input` through the line reader and collect the frames it forwards.
+    fn read(input: &str) -> Vec<LtcFrame> {
+        let (tx, rx) = frame_channel();
+        read_ltc_lines(Cursor::new(input.to_string()), tx, ReaderLatency::default(), 115200);
+        rx.try_iter().collect()
     }
 
     #[test]
     fn test_process_lock_line() {
-        let (tx, rx) = mpsc::channel();
-        let state = Arc::new(Mutex::new(LtcState::new()));
-        let re = get_ltc_regex();
-        let line = "[LOCK] 10:20:30:00 | 25.00fps";
-
-        // Simulate the processing logic from start_serial_thread
-        if let Some(caps) = re.captures(line) {
-            let arrival = Utc::now();
-            if let Some(frame) = LtcFrame::from_regex(&caps, arrival) {
-                {
-                    let mut st = state.lock().unwrap();
-                    st.update(frame.clone());
-                }
-                let _ = tx.send(frame);
-            }
-        }
-
-        let st = state.lock().unwrap();
-        assert_eq!(st.lock_count, 1);
-        assert_eq!(st.free_count, 0);
-        let received_frame = rx.try_recv().unwrap();
-        assert_eq!(received_frame.status, "LOCK");
-        assert_eq!(received_frame.hours, 10);
+        let frames = read("[LOCK] 10:20:30:00 | 25.00fps");
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].status, "LOCK");
+        assert_eq!(frames[0].hours, 10);
     }
 
     #[test]
     fn test_process_free_line() {
-        let (tx, rx) = mpsc::channel();
-        let state = Arc::new(Mutex::new(LtcState::new()));
-        let re = get_ltc_regex();
-        let line = "[FREE] 01:02:03:04 | 29.97fps";
-
-        // Simulate the processing logic
-        if let Some(caps) = re.captures(line) {
-            let arrival = Utc::now();
-            if let Some(frame) = LtcFrame::from_regex(&caps, arrival) {
-                {
-                    let mut st = state.lock().unwrap();
-                    st.update(frame.clone());
-                }
-                let _ = tx.send(frame);
-            }
-        }
-
-        let st = state.lock().unwrap();
-        assert_eq!(st.lock_count, 0);
-        assert_eq!(st.free_count, 1);
-        let received_frame = rx.try_recv().unwrap();
-        assert_eq!(received_frame.status, "FREE");
-        assert_eq!(received_frame.frame_rate, Ratio::new(30000, 1001));
+        let frames = read("[FREE] 01:02:03:04 | 29.97fps");
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].status, "FREE");
+        assert_eq!(frames[0].frame_rate, Ratio::new(30000, 1001));
     }
 
     #[test]
     fn test_ignore_non_matching_line() {
-        let (tx, rx) = mpsc::channel();
-        let state = Arc::new(Mutex::new(LtcState::new()));
-        let re = get_ltc_regex();
-        let line = "this is not a valid ltc line";
-
-        // Simulate the processing logic
-        if let Some(caps) = re.captures(line) {
-            let arrival = Utc::now();
-            if let Some(frame) = LtcFrame::from_regex(&caps, arrival) {
-                {
-                    let mut st = state.lock().unwrap();
-                    st.update(frame.clone());
-                }
-                let _ = tx.send(frame);
-            }
-        }
-
-        let st = state.lock().unwrap();
-        assert_eq!(st.lock_count, 0);
-        assert_eq!(st.free_count, 0);
-        assert!(rx.try_recv().is_err());
+        assert!(read("this is not a valid ltc line").is_empty());
     }
 
     #[test]
     fn test_ignore_line_with_bad_parseable_data() {
-        let (tx, rx) = mpsc::channel();
-        let state = Arc::new(Mutex::new(LtcState::new()));
-        let re = get_ltc_regex();
-        // The regex will match, but `from_regex` should fail to parse "1.2.3.4" as f64
-        let line = "[LOCK] 10:20:30:00 | 1.2.3.4fps";
-
-        // Simulate the processing logic
-        if let Some(caps) = re.captures(line) {
-            let arrival = Utc::now();
-            if let Some(frame) = LtcFrame::from_regex(&caps, arrival) {
-                {
-                    let mut st = state.lock().unwrap();
-                    st.update(frame.clone());
-                }
-                let _ = tx.send(frame);
-            }
-        } else {
-            panic!("Regex should have matched");
+        // The regex matches, but "1.2.3.4" is not a frame rate
+        assert!(read("[LOCK] 10:20:30:00 | 1.2.3.4fps").is_empty());
+    }
+
+    #[test]
+    fn test_custom_line_pattern() {
+        // Firmware printing `LOCK 10:20:30.05 @25 fps`, with no drop-frame
+        // separator.
+        let pattern = LinePattern::new(
+            r"(?P<status>LOCK|FREE) (?P<hours>\d\d):(?P<minutes>\d\d):(?P<seconds>\d\d)\.(?P<frames>\d\d) @(?P<fps>[\d.]+) fps",
+        )
+        .unwrap();
+        let frame = pattern.parse("LOCK 10:20:30.05 @25 fps", ClockPair::now()).unwrap();
+        assert_eq!((frame.hours, frame.seconds, frame.frames), (10, 30, 5));
+        assert!(!frame.is_drop_frame);
+        assert!(pattern.parse("[LOCK] 10:20:30:05 | 25.00fps", ClockPair::now()).is_none());
+
+        assert_eq!(
+            LinePattern::new(r"(?P<status>LOCK) (?P<hours>\d\d)").unwrap_err(),
+            "ltcLinePattern needs a capture group named minutes"
+        );
+        assert!(LinePattern::new("([").unwrap_err().starts_with("ltcLinePattern is not a valid regex"));
+        assert!(LinePattern::from_config(None).is_ok());
+    }
+
+    #[test]
+    fn test_reads_until_end_of_input() {
+        let frames = read("[LOCK] 10:20:30:00 | 25.00fps\r\nnoise\n[LOCK] 10:20:30:01 | 25.00fps\n");
+        assert_eq!(frames.iter().map(|f| f.frames).collect::<Vec<_>>(), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_reader_latency_per_line() {
+        let fixed = ReaderLatency { fixed_us: 1500, per_character: false };
+        assert_eq!(fixed.for_line(30, 115200), Duration::microseconds(1500));
+        // 30 characters of 10 bits at 115200 baud take 2604 µs.
+        let modelled = ReaderLatency { fixed_us: 1500, per_character: true };
+        assert_eq!(modelled.for_line(30, 115200), Duration::microseconds(1500 + 2604));
+        assert_eq!(modelled.for_line(30, 0), Duration::microseconds(1500), "no baud rate");
+    }
+
+    #[test]
+    fn test_arrival_is_stamped_back_by_the_latency() {
+        let (tx, rx) = frame_channel();
+        let latency = ReaderLatency { fixed_us: 250_000, per_character: false };
+        let before = Utc::now();
+        read_ltc_lines(Cursor::new("[LOCK] 10:20:30:00 | 25.00fps\n"), tx, latency, 115200);
+        let frame = rx.try_iter().next().unwrap();
+        assert!(frame.timestamp <= Utc::now() - Duration::milliseconds(250));
+        assert!(frame.timestamp >= before - Duration::milliseconds(250));
+    }
+
+    fn usb_port(path: &str, vid: u16, pid: u16, serial: &str) -> PortInfo {
+        PortInfo {
+            path: path.to_string(),
+            usb: Some(UsbId { vid, pid, serial_number: Some(serial.to_string()) }),
+            description: None,
+        }
+    }
+
+    fn device(vid: Option<&str>, serial: Option<&str>, baud_rate: u32) -> SerialDevice {
+        SerialDevice {
+            vid: vid.map(str::to_string),
+            pid: None,
+            serial_number: serial.map(str::to_string),
+            baud_rate,
+            reader_latency: None,
         }
+    }
+
+    #[test]
+    fn test_choose_reader_by_configured_device() {
+        let gps = usb_port("/dev/ttyACM0", 0x1546, 0x01a7, "GPS1");
+        let teensy = usb_port("/dev/ttyACM1", 0x16c0, 0x0483, "T123");
+        let ports = vec![gps, teensy];
+        let fixed = ReaderLatency { fixed_us: 100, per_character: false };
 
-        let st = state.lock().unwrap();
-        assert_eq!(st.lock_count, 0);
-        assert_eq!(st.free_count, 0);
-        assert!(rx.try_recv().is_err());
+        let devices = vec![device(Some("0x16C0"), Some("T123"), 57600)];
+        let reader = choose_reader(&ports, &devices, fixed).unwrap();
+        assert_eq!(reader, ReaderPort { path: "/dev/ttyACM1".into(), baud_rate: 57600, latency: fixed });
+
+        // A per-device latency wins over the top-level one.
+        let own = ReaderLatency { fixed_us: 900, per_character: true };
+        let devices = vec![SerialDevice { reader_latency: Some(own), ..device(Some("16c0"), None, 115200) }];
+        assert_eq!(choose_reader(&ports, &devices, fixed).unwrap().latency, own);
+
+        // Configured but not plugged in: nothing, rather than the GPS.
+        let devices = vec![device(None, Some("T999"), 115200)];
+        assert!(choose_reader(&ports, &devices, fixed).is_none());
+    }
+
+    #[test]
+    fn test_choose_reader_prefers_known_boards() {
+        let gps = usb_port("/dev/ttyACM0", 0x1546, 0x01a7, "GPS1");
+        let arduino = usb_port("/dev/ttyACM1", 0x2341, 0x0043, "A1");
+        let uart = PortInfo { path: "/dev/ttyAMA0".into(), usb: None, description: None };
+        let latency = ReaderLatency::default();
+
+        let reader = choose_reader(&[gps.clone(), arduino], &[], latency).unwrap();
+        assert_eq!(reader.path, "/dev/ttyACM1");
+        assert_eq!(reader.baud_rate, DEFAULT_BAUD_RATE);
+        assert_eq!(choose_reader(&[uart, gps], &[], latency).unwrap().path, "/dev/ttyAMA0");
+        assert!(choose_reader(&[PortInfo { path: "/dev/ttyS0".into(), usb: None, description: None }], &[], latency).is_none());
+    }
+
+    #[test]
+    fn test_reader_selection_switches_serial_only() {
+        let reader = ReaderPort { path: "/dev/ttyACM0".into(), baud_rate: 115200, latency: ReaderLatency::default() };
+        let selection = ReaderSelection::serial(reader.clone());
+        let before = selection.generation();
+        let other = ReaderPort { path: "/dev/ttyACM1".into(), ..reader };
+        assert!(selection.switch(other.clone()));
+        assert_eq!(selection.current(), other);
+        assert_ne!(selection.generation(), before);
+
+        let vitc = ReaderSelection::fixed("/dev/vbi0");
+        assert!(!vitc.switch(other));
+        assert_eq!(vitc.current().path, "/dev/vbi0");
+    }
+
+    #[test]
+    fn test_read_stops_when_deselected() {
+        let (tx, rx) = frame_channel();
+        let lines = "[LOCK] 10:20:30:00 | 25.00fps\n[LOCK] 10:20:30:01 | 25.00fps\n";
+        let reads = std::cell::Cell::new(0);
+        let mut keep_reading = || {
+            reads.set(reads.get() + 1);
+            reads.get() <= 1
+        };
+        let pattern = LinePattern::default();
+        assert!(read_lines(Cursor::new(lines), &tx, &pattern, ReaderLatency::default(), 115200, &mut keep_reading, &|_| {}));
+        assert_eq!(rx.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn test_firmware_line_is_reported_not_read_as_a_frame() {
+        assert_eq!(
+            parse_firmware("[VERSION] ltc_audiohat_lock_v2 2.1.0 caps=fps-detect,free-run\r\n"),
+            Some(ReaderFirmware {
+                name: "ltc_audiohat_lock_v2".into(),
+                version: "2.1.0".into(),
+                capabilities: vec!["fps-detect".into(), "free-run".into()],
+            })
+        );
+        assert_eq!(parse_firmware("[VERSION] old 1.0").unwrap().capabilities, Vec::<String>::new());
+        assert_eq!(parse_firmware("[VERSION] nameonly"), None);
+        assert_eq!(parse_firmware("[LOCK] 10:20:30:00 | 25.00fps"), None);
+
+        let (tx, rx) = frame_channel();
+        let lines = "[LOCK] 10:20:30:00 | 25.00fps\n[VERSION] ltc_audiohat_lock_v2 2.1.0\n";
+        let reported = std::cell::RefCell::new(Vec::new());
+        let on_firmware = |firmware: ReaderFirmware| reported.borrow_mut().push(firmware.version);
+        let pattern = LinePattern::default();
+        read_lines(Cursor::new(lines), &tx, &pattern, ReaderLatency::default(), 115200, &mut || true, &on_firmware);
+        assert_eq!(rx.try_iter().count(), 1);
+        assert_eq!(reported.into_inner(), vec!["2.1.0"]);
+    }
+
+    #[test]
+    fn test_validate_devices() {
+        assert!(validate_devices(&[device(Some("16c0"), None, 115200)]).is_ok());
+        assert!(validate_devices(&[device(None, None, 115200)]).is_err());
+        assert!(validate_devices(&[device(Some("teensy"), None, 115200)]).is_err());
+        assert!(validate_devices(&[device(Some("16c0"), None, 0)]).is_err());
     }
 }
@@ -0,0 +1,195 @@
+// src/series.rs
+//
+// Recent clock delta and jitter at a fixed one-second resolution, for the
+// web UI's charts (`/api/series/delta` and `/api/series/jitter`). The frame
+// processor folds every measured LOCK frame into a ring buffer of per-second
+// buckets covering the last hour; seconds with no measurement come back as
+// gaps, so a chart shows where LTC was missing.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use utoipa::ToSchema;
+
+pub const SERIES_RESOLUTION_SECS: u64 = 1;
+
+/// How far back the series reach.
+pub const SERIES_MAX_MINUTES: u64 = 60;
+
+/// Which measurement a series follows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SeriesKind {
+    /// The smoothed clock delta (system clock minus LTC).
+    Delta,
+    /// Each frame's distance from the fitted LTC phase.
+    Jitter,
+}
+
+/// One second of a series. Everything but `timestamp` is `null` when no
+/// frame was measured in that second.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SeriesPoint {
+    #[schema(value_type = String, format = DateTime)]
+    pub timestamp: DateTime<Utc>,
+    pub mean_ms: Option<f64>,
+    pub min_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SeriesResponse {
+    pub resolution_secs: u64,
+    /// One point per second, oldest first.
+    pub points: Vec<SeriesPoint>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Stat {
+    sum: f64,
+    min: f64,
+    max: f64,
+}
+
+impl Stat {
+    fn new(value: f64) -> Self {
+        Self { sum: value, min: value, max: value }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    second: i64,
+    count: u32,
+    delta: Stat,
+    jitter: Stat,
+}
+
+/// The last `SERIES_MAX_MINUTES` of per-second buckets, oldest first. Only
+/// seconds with samples are stored.
+#[derive(Debug, Default)]
+pub struct Series {
+    buckets: VecDeque<Bucket>,
+}
+
+fn round_tenth(ms: f64) -> f64 {
+    (ms * 10.0).round() / 10.0
+}
+
+impl Series {
+    /// Add one measured frame that arrived at `at`.
+    pub fn record(&mut self, at: DateTime<Utc>, delta_ms: f64, jitter_ms: i64) {
+        let second = at.timestamp();
+        let jitter_ms = jitter_ms as f64;
+        match self.buckets.back_mut() {
+            Some(b) if b.second == second => {
+                b.count += 1;
+                b.delta.add(delta_ms);
+                b.jitter.add(jitter_ms);
+            }
+            last => {
+                // The clock was stepped back: the old seconds no longer line
+                // up with the new ones, so start over.
+                if last.is_some_and(|b| b.second > second) {
+                    self.buckets.clear();
+                }
+                self.buckets.push_back(Bucket {
+                    second,
+                    count: 1,
+                    delta: Stat::new(delta_ms),
+                    jitter: Stat::new(jitter_ms),
+                });
+            }
+        }
+        let cutoff = second - (SERIES_MAX_MINUTES * 60) as i64;
+        while self.buckets.front().is_some_and(|b| b.second <= cutoff) {
+            self.buckets.pop_front();
+        }
+    }
+
+    /// One point per second for the last `minutes` (at most
+    /// `SERIES_MAX_MINUTES`) up to and including `now`.
+    pub fn query(&self, kind: SeriesKind, minutes: u64, now: DateTime<Utc>) -> SeriesResponse {
+        let end = now.timestamp();
+        let start = end - (minutes.min(SERIES_MAX_MINUTES) * 60) as i64 + 1;
+        let mut stored = self.buckets.iter().filter(|b| b.second >= start).peekable();
+        let points = (start..=end)
+            .map(|second| {
+                let timestamp = DateTime::from_timestamp(second, 0).unwrap_or_default();
+                match stored.next_if(|b| b.second == second) {
+                    Some(b) => {
+                        let stat = match kind {
+                            SeriesKind::Delta => b.delta,
+                            SeriesKind::Jitter => b.jitter,
+                        };
+                        SeriesPoint {
+                            timestamp,
+                            mean_ms: Some(round_tenth(stat.sum / b.count as f64)),
+                            min_ms: Some(round_tenth(stat.min)),
+                            max_ms: Some(round_tenth(stat.max)),
+                        }
+                    }
+                    None => SeriesPoint { timestamp, mean_ms: None, min_ms: None, max_ms: None },
+                }
+            })
+            .collect();
+        SeriesResponse { resolution_secs: SERIES_RESOLUTION_SECS, points }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn at(secs: i64, ms: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap() + Duration::milliseconds(ms)
+    }
+
+    #[test]
+    fn test_buckets_by_second_with_gaps() {
+        let mut series = Series::default();
+        series.record(at(0, 0), 2.0, 1);
+        series.record(at(0, 40), 3.0, 5);
+        series.record(at(0, 80), 4.0, 3);
+        series.record(at(2, 0), -1.25, 0);
+
+        let delta = series.query(SeriesKind::Delta, 1, at(2, 500));
+        assert_eq!(delta.resolution_secs, 1);
+        assert_eq!(delta.points.len(), 60);
+        let tail = &delta.points[57..];
+        assert_eq!(tail[0].timestamp, at(0, 0));
+        assert_eq!((tail[0].mean_ms, tail[0].min_ms, tail[0].max_ms), (Some(3.0), Some(2.0), Some(4.0)));
+        assert_eq!(tail[1].mean_ms, None, "no frames in second 1");
+        assert_eq!(tail[2].mean_ms, Some(-1.3));
+        assert!(delta.points[..57].iter().all(|p| p.mean_ms.is_none()));
+
+        let jitter = series.query(SeriesKind::Jitter, 1, at(2, 500));
+        assert_eq!(jitter.points[57].mean_ms, Some(3.0));
+        assert_eq!(jitter.points[57].max_ms, Some(5.0));
+    }
+
+    #[test]
+    fn test_keeps_one_hour_and_restarts_after_a_step_back() {
+        let mut series = Series::default();
+        for s in 0..4000 {
+            series.record(at(s, 0), 1.0, 1);
+        }
+        assert_eq!(series.buckets.len(), 3600);
+        // Longer queries are capped at the hour kept.
+        let all = series.query(SeriesKind::Delta, 600, at(3999, 0));
+        assert_eq!(all.points.len(), 3600);
+        assert!(all.points.iter().all(|p| p.mean_ms == Some(1.0)));
+
+        series.record(at(10, 0), 7.0, 2);
+        assert_eq!(series.buckets.len(), 1);
+        assert_eq!(series.query(SeriesKind::Delta, 1, at(10, 0)).points[59].mean_ms, Some(7.0));
+    }
+}
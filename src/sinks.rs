@@ -0,0 +1,348 @@
+// src/sinks.rs
+//
+// Output sinks: once a second the LTC source's estimate of the system
+// clock (see `sources`) is delivered to every sink listed under `sinks` in
+// config.yml, such as chrony's SOCK refclock, a PTP hardware clock or a CSV
+// file. Each sink keeps its own health, served at `/api/sinks`, so one that
+// fails never holds up the others. The system clock itself is disciplined
+// by auto-sync as before; sinks carry the reference to everything else.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::raw::c_long;
+use std::os::unix::net::UnixDatagram;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+use crate::config::Config;
+use crate::health::Heartbeat;
+use crate::phc::{self, Servo};
+use crate::sources::{SourceRegistry, TimeEstimate};
+
+const INTERVAL: Duration = Duration::from_secs(1);
+
+/// `SOCK` in chrony's `struct sock_sample`.
+const CHRONY_SOCK_MAGIC: i32 = 0x534f434b;
+
+const CSV_HEADER: &str = "systemTime,referenceTime,offsetMs,uncertaintyMs\n";
+
+/// One entry under `sinks`.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SinkConfig {
+    /// chrony's SOCK refclock, at the socket named by `refclock SOCK <path>`
+    /// in chrony.conf.
+    Chrony { path: String },
+    /// A NIC's PTP hardware clock, steered with phc_ctl as `phc` does, but
+    /// to the reference rather than the system clock.
+    Phc {
+        device: String,
+        /// TAI - UTC, in seconds.
+        #[serde(rename = "utcOffsetSecs", default = "phc::default_utc_offset_secs")]
+        utc_offset_secs: i64,
+    },
+    /// One row per delivery, appended to `path`.
+    Csv { path: String },
+}
+
+impl SinkConfig {
+    /// How the sink is named in `/api/sinks` and the log.
+    pub fn name(&self) -> String {
+        match self {
+            SinkConfig::Chrony { path } => format!("chrony {}", path),
+            SinkConfig::Phc { device, .. } => format!("phc {}", device),
+            SinkConfig::Csv { path } => format!("csv {}", path),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            SinkConfig::Chrony { path } | SinkConfig::Csv { path } if path.is_empty() => {
+                Err("sinks: chrony and csv need a path".into())
+            }
+            SinkConfig::Phc { device, .. } if device.is_empty() => Err("sinks: phc needs a device".into()),
+            SinkConfig::Phc { utc_offset_secs, .. } if !(0..=100).contains(utc_offset_secs) => {
+                Err("sinks: phc utcOffsetSecs must be between 0 and 100".into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn build(&self) -> Box<dyn ClockSink> {
+        match self {
+            SinkConfig::Chrony { path } => Box::new(ChronySink { path: path.clone() }),
+            SinkConfig::Phc { device, utc_offset_secs } => Box::new(PhcSink {
+                device: device.clone(),
+                utc_offset_secs: *utc_offset_secs,
+                servo: Servo::default(),
+            }),
+            SinkConfig::Csv { path } => Box::new(CsvSink { path: path.clone() }),
+        }
+    }
+}
+
+/// Where the system clock stood against the reference at one instant.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReferenceSample {
+    pub system_time: DateTime<Utc>,
+    /// How far the system clock is ahead of the reference, in milliseconds.
+    pub offset_ms: f64,
+    pub uncertainty_ms: f64,
+}
+
+impl ReferenceSample {
+    pub fn from_estimate(estimate: &TimeEstimate, system_time: DateTime<Utc>) -> Self {
+        Self { system_time, offset_ms: estimate.offset_ms, uncertainty_ms: estimate.uncertainty_ms }
+    }
+
+    /// What the reference read at `system_time`.
+    pub fn reference_time(&self) -> DateTime<Utc> {
+        self.system_time - chrono::Duration::microseconds((self.offset_ms * 1000.0).round() as i64)
+    }
+}
+
+pub trait ClockSink: Send {
+    fn deliver(&mut self, sample: &ReferenceSample) -> Result<(), String>;
+}
+
+/// chrony's `struct sock_sample`, in this platform's layout: a `timeval`,
+/// the reference minus the system clock in seconds, then pulse, leap,
+/// padding and magic as C ints.
+fn sock_sample(sample: &ReferenceSample) -> Vec<u8> {
+    let mut out = Vec::with_capacity(40);
+    out.extend_from_slice(&(sample.system_time.timestamp() as c_long).to_ne_bytes());
+    out.extend_from_slice(&(sample.system_time.timestamp_subsec_micros() as c_long).to_ne_bytes());
+    // The double is 8-byte aligned.
+    out.resize(out.len().next_multiple_of(8), 0);
+    out.extend_from_slice(&(-sample.offset_ms / 1000.0).to_ne_bytes());
+    for int in [0, 0, 0, CHRONY_SOCK_MAGIC] {
+        out.extend_from_slice(&int.to_ne_bytes());
+    }
+    out
+}
+
+struct ChronySink {
+    path: String,
+}
+
+impl ClockSink for ChronySink {
+    fn deliver(&mut self, sample: &ReferenceSample) -> Result<(), String> {
+        let socket = UnixDatagram::unbound().map_err(|e| e.to_string())?;
+        socket
+            .send_to(&sock_sample(sample), &self.path)
+            .map(|_| ())
+            .map_err(|e| format!("cannot send to {}: {}", self.path, e))
+    }
+}
+
+struct PhcSink {
+    device: String,
+    utc_offset_secs: i64,
+    servo: Servo,
+}
+
+impl ClockSink for PhcSink {
+    fn deliver(&mut self, sample: &ReferenceSample) -> Result<(), String> {
+        let target_ns = self.utc_offset_secs * 1_000_000_000 - (sample.offset_ms * 1e6).round() as i64;
+        phc::steer(&self.device, target_ns, &mut self.servo).inspect_err(|_| self.servo = Servo::default())
+    }
+}
+
+fn csv_row(sample: &ReferenceSample) -> String {
+    format!(
+        "{},{},{:.3},{:.3}\n",
+        sample.system_time.format("%Y-%m-%dT%H:%M:%S%.6fZ"),
+        sample.reference_time().format("%Y-%m-%dT%H:%M:%S%.6fZ"),
+        sample.offset_ms,
+        sample.uncertainty_ms
+    )
+}
+
+struct CsvSink {
+    path: String,
+}
+
+impl ClockSink for CsvSink {
+    fn deliver(&mut self, sample: &ReferenceSample) -> Result<(), String> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("cannot open {}: {}", self.path, e))?;
+        let empty = file.metadata().map(|m| m.len() == 0).unwrap_or(false);
+        let text = if empty { format!("{}{}", CSV_HEADER, csv_row(sample)) } else { csv_row(sample) };
+        file.write_all(text.as_bytes()).map_err(|e| format!("cannot write {}: {}", self.path, e))
+    }
+}
+
+/// One sink in `/api/sinks`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SinkHealth {
+    pub name: String,
+    /// The last delivery succeeded.
+    pub healthy: bool,
+    pub deliveries: u64,
+    pub failures: u64,
+    pub last_error: Option<String>,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub last_delivery: Option<DateTime<Utc>>,
+}
+
+/// Health of the configured sinks, in config order.
+#[derive(Default)]
+pub struct SinkBoard {
+    sinks: Mutex<Vec<SinkHealth>>,
+}
+
+impl SinkBoard {
+    pub fn sinks(&self) -> Vec<SinkHealth> {
+        self.sinks.lock().unwrap().clone()
+    }
+
+    fn reset(&self, configs: &[SinkConfig]) {
+        *self.sinks.lock().unwrap() = configs
+            .iter()
+            .map(|c| SinkHealth {
+                name: c.name(),
+                healthy: false,
+                deliveries: 0,
+                failures: 0,
+                last_error: None,
+                last_delivery: None,
+            })
+            .collect();
+    }
+
+    /// Note the outcome of a delivery to sink `index`. Returns whether its
+    /// health changed.
+    fn record(&self, index: usize, result: Result<(), String>, at: DateTime<Utc>) -> bool {
+        let mut sinks = self.sinks.lock().unwrap();
+        let sink = &mut sinks[index];
+        let was_healthy = sink.healthy;
+        match result {
+            Ok(()) => {
+                sink.deliveries += 1;
+                sink.last_delivery = Some(at);
+                sink.healthy = true;
+            }
+            Err(e) => {
+                sink.failures += 1;
+                sink.last_error = Some(e);
+                sink.healthy = false;
+            }
+        }
+        sink.healthy != was_healthy || (!sink.healthy && sink.failures == 1)
+    }
+}
+
+/// Deliver the LTC source's estimate to the configured sinks once a second
+/// while there is one. A change to `sinks` rebuilds them all.
+pub fn run(config: Arc<Mutex<Config>>, sources: Arc<SourceRegistry>, board: Arc<SinkBoard>, heartbeat: Heartbeat) {
+    let mut configured: Vec<SinkConfig> = Vec::new();
+    let mut sinks: Vec<Box<dyn ClockSink>> = Vec::new();
+    board.reset(&configured);
+    loop {
+        heartbeat.beat();
+        thread::sleep(INTERVAL);
+        let wanted = config.lock().unwrap().sinks.clone();
+        if wanted != configured {
+            for sink in &wanted {
+                log::info!("Delivering the reference to {}.", sink.name());
+            }
+            sinks = wanted.iter().map(SinkConfig::build).collect();
+            board.reset(&wanted);
+            configured = wanted;
+        }
+        let now = Utc::now();
+        let Some(estimate) = sources.get("ltc").and_then(|ltc| ltc.estimate(now)) else {
+            continue;
+        };
+        let sample = ReferenceSample::from_estimate(&estimate, now);
+        for (index, sink) in sinks.iter_mut().enumerate() {
+            let result = sink.deliver(&sample);
+            let error = result.as_ref().err().cloned();
+            // Log once per outage rather than every second.
+            if board.record(index, result, now) {
+                match error {
+                    Some(e) => log::error!("Sink {}: {}", configured[index].name(), e),
+                    None => log::info!("Sink {} delivering.", configured[index].name()),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn sample() -> ReferenceSample {
+        ReferenceSample {
+            system_time: Utc.with_ymd_and_hms(2025, 7, 30, 10, 20, 30).unwrap() + chrono::Duration::microseconds(250_000),
+            offset_ms: 1.5,
+            uncertainty_ms: 0.4,
+        }
+    }
+
+    #[test]
+    fn test_sinks_parse_from_a_yaml_list() {
+        let yaml = "- type: chrony\n  path: /run/chrony.ltc.sock\n- type: phc\n  device: /dev/ptp1\n- type: csv\n  path: /tmp/ref.csv\n";
+        let sinks: Vec<SinkConfig> = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            sinks,
+            [
+                SinkConfig::Chrony { path: "/run/chrony.ltc.sock".into() },
+                SinkConfig::Phc { device: "/dev/ptp1".into(), utc_offset_secs: 37 },
+                SinkConfig::Csv { path: "/tmp/ref.csv".into() },
+            ]
+        );
+        assert!(sinks.iter().all(|s| s.validate().is_ok()));
+        assert!(SinkConfig::Csv { path: String::new() }.validate().is_err());
+        assert!(serde_yaml::from_str::<Vec<SinkConfig>>("- type: kernel\n").is_err());
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_sock_sample_layout() {
+        let bytes = sock_sample(&sample());
+        assert_eq!(bytes.len(), 40);
+        assert_eq!(i64::from_ne_bytes(bytes[0..8].try_into().unwrap()), 1753870830);
+        assert_eq!(i64::from_ne_bytes(bytes[8..16].try_into().unwrap()), 250_000);
+        assert_eq!(f64::from_ne_bytes(bytes[16..24].try_into().unwrap()), -0.0015, "reference minus system");
+        assert_eq!(i32::from_ne_bytes(bytes[36..40].try_into().unwrap()), CHRONY_SOCK_MAGIC);
+    }
+
+    #[test]
+    fn test_csv_sink_writes_a_header_once() {
+        let path = std::env::temp_dir().join(format!("timeturner-sink-{}.csv", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut sink = CsvSink { path: path.to_string_lossy().into_owned() };
+        sink.deliver(&sample()).unwrap();
+        sink.deliver(&sample()).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], "2025-07-30T10:20:30.250000Z,2025-07-30T10:20:30.248500Z,1.500,0.400");
+    }
+
+    #[test]
+    fn test_each_sink_keeps_its_own_health() {
+        let board = SinkBoard::default();
+        board.reset(&[SinkConfig::Csv { path: "a".into() }, SinkConfig::Chrony { path: "b".into() }]);
+        let now = Utc::now();
+        assert!(board.record(0, Ok(()), now));
+        assert!(board.record(1, Err("no chrony".into()), now));
+        assert!(!board.record(1, Err("no chrony".into()), now), "already reported");
+        let sinks = board.sinks();
+        assert!(sinks[0].healthy && !sinks[1].healthy);
+        assert_eq!((sinks[1].failures, sinks[1].last_error.as_deref()), (2, Some("no chrony")));
+        assert_eq!(sinks[0].last_delivery, Some(now));
+    }
+}
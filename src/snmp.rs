@@ -0,0 +1,558 @@
+// src/snmp.rs
+//
+// A minimal read-only SNMP agent (v1 and v2c), so a broadcast NMS can poll
+// the Timeturner like any other sync box. It answers GET, GETNEXT and
+// GETBULK for the MIB-II system group and a private MIB (docs/TIMETURNER-MIB.txt)
+// holding the LTC and sync status, the clock delta, the lock ratio and alarm
+// states. SETs are refused, and requests with the wrong community are dropped.
+
+use serde::{Deserialize, Serialize};
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+use crate::config::Config;
+use crate::health::Heartbeat;
+use crate::ntp_client::{NtpCrossCheck, NtpSample};
+use crate::status::StatusSnapshot;
+use crate::sync_logic::LtcView;
+
+/// The private MIB root, under the enterprise number IANA reserves for
+/// documentation (RFC 5612). Also returned as `sysObjectID`.
+pub const TIMETURNER_OID: &[u32] = &[1, 3, 6, 1, 4, 1, 32473, 1];
+
+const SYSTEM_OID: &[u32] = &[1, 3, 6, 1, 2, 1, 1];
+
+/// How long to wait for a request before re-reading the config.
+const POLL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// GETBULK repetitions are capped so a reply always fits in a datagram.
+const MAX_REPETITIONS: usize = 32;
+
+const PDU_GET: u8 = 0xa0;
+const PDU_GET_NEXT: u8 = 0xa1;
+const PDU_RESPONSE: u8 = 0xa2;
+const PDU_SET: u8 = 0xa3;
+const PDU_GET_BULK: u8 = 0xa5;
+
+const VERSION_1: i64 = 0;
+const VERSION_2C: i64 = 1;
+
+const NO_SUCH_NAME: i64 = 2;
+const NOT_WRITABLE: i64 = 17;
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SnmpConfig {
+    /// Address to answer on, e.g. `0.0.0.0:161`. The agent is off while
+    /// this is unset.
+    #[serde(default)]
+    pub listen: Option<String>,
+    /// Read-only community.
+    #[serde(default = "default_community")]
+    pub community: String,
+}
+
+fn default_community() -> String {
+    "public".to_string()
+}
+
+impl Default for SnmpConfig {
+    fn default() -> Self {
+        Self {
+            listen: None,
+            community: default_community(),
+        }
+    }
+}
+
+impl SnmpConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(listen) = &self.listen {
+            listen
+                .parse::<SocketAddr>()
+                .map_err(|_| format!("snmp.listen must be address:port, got '{}'", listen))?;
+        }
+        if self.community.is_empty() {
+            return Err("snmp.community must not be empty".into());
+        }
+        Ok(())
+    }
+}
+
+pub type Oid = Vec<u32>;
+
+/// A variable binding's value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Integer(i64),
+    OctetString(String),
+    Null,
+    ObjectId(Oid),
+    Gauge32(u32),
+    TimeTicks(u32),
+    NoSuchObject,
+    EndOfMibView,
+}
+
+/// An SNMP message. For GETBULK, `error_status` and `error_index` carry
+/// non-repeaters and max-repetitions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Message {
+    pub version: i64,
+    pub community: Vec<u8>,
+    pub pdu: u8,
+    pub request_id: i64,
+    pub error_status: i64,
+    pub error_index: i64,
+    pub bindings: Vec<(Oid, Value)>,
+}
+
+fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    match content.len() {
+        len @ 0..=0x7f => out.push(len as u8),
+        len @ 0x80..=0xff => out.extend([0x81, len as u8]),
+        len => out.extend([0x82, (len >> 8) as u8, len as u8]),
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+/// Minimal two's complement, big endian.
+fn int_bytes(value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let start = (0..7)
+        .find(|&i| {
+            !((bytes[i] == 0x00 && bytes[i + 1] & 0x80 == 0) || (bytes[i] == 0xff && bytes[i + 1] & 0x80 != 0))
+        })
+        .unwrap_or(7);
+    bytes[start..].to_vec()
+}
+
+fn oid_bytes(oid: &[u32]) -> Vec<u8> {
+    let mut out = vec![(oid.first().copied().unwrap_or(0) * 40 + oid.get(1).copied().unwrap_or(0)) as u8];
+    for &id in oid.iter().skip(2) {
+        let mut groups = vec![(id & 0x7f) as u8];
+        let mut rest = id >> 7;
+        while rest > 0 {
+            groups.push((rest & 0x7f) as u8 | 0x80);
+            rest >>= 7;
+        }
+        out.extend(groups.iter().rev());
+    }
+    out
+}
+
+impl Value {
+    fn encode(&self) -> Vec<u8> {
+        match self {
+            Value::Integer(v) => tlv(0x02, &int_bytes(*v)),
+            Value::OctetString(s) => tlv(0x04, s.as_bytes()),
+            Value::Null => tlv(0x05, &[]),
+            Value::ObjectId(oid) => tlv(0x06, &oid_bytes(oid)),
+            Value::Gauge32(v) => tlv(0x42, &int_bytes(*v as i64)),
+            Value::TimeTicks(v) => tlv(0x43, &int_bytes(*v as i64)),
+            Value::NoSuchObject => tlv(0x80, &[]),
+            Value::EndOfMibView => tlv(0x82, &[]),
+        }
+    }
+
+    /// Decode a value; types the agent never sends read as `Null`.
+    fn decode(tag: u8, content: &[u8]) -> Option<Self> {
+        Some(match tag {
+            0x02 => Value::Integer(parse_int(content)?),
+            0x04 => Value::OctetString(String::from_utf8_lossy(content).into_owned()),
+            0x06 => Value::ObjectId(parse_oid(content)?),
+            0x42 => Value::Gauge32(u32::try_from(parse_int(content)?).ok()?),
+            0x43 => Value::TimeTicks(u32::try_from(parse_int(content)?).ok()?),
+            0x80 => Value::NoSuchObject,
+            0x82 => Value::EndOfMibView,
+            _ => Value::Null,
+        })
+    }
+}
+
+/// Reads BER tag-length-value triples off the front of a buffer.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn read(&mut self) -> Option<(u8, &'a [u8])> {
+        let (&tag, rest) = self.0.split_first()?;
+        let (&first, mut rest) = rest.split_first()?;
+        let len = if first < 0x80 {
+            first as usize
+        } else {
+            let n = (first & 0x7f) as usize;
+            if n == 0 || n > 2 || rest.len() < n {
+                return None;
+            }
+            let len = rest[..n].iter().fold(0, |len, b| len << 8 | *b as usize);
+            rest = &rest[n..];
+            len
+        };
+        if rest.len() < len {
+            return None;
+        }
+        let (content, rest) = rest.split_at(len);
+        self.0 = rest;
+        Some((tag, content))
+    }
+
+    fn expect(&mut self, tag: u8) -> Option<&'a [u8]> {
+        self.read().filter(|(t, _)| *t == tag).map(|(_, content)| content)
+    }
+}
+
+fn parse_int(content: &[u8]) -> Option<i64> {
+    if content.is_empty() || content.len() > 8 {
+        return None;
+    }
+    let sign = if content[0] & 0x80 != 0 { -1 } else { 0 };
+    Some(content.iter().fold(sign, |v, b| v << 8 | *b as i64))
+}
+
+fn parse_oid(content: &[u8]) -> Option<Oid> {
+    let (&first, rest) = content.split_first()?;
+    let top = (first / 40).min(2);
+    let mut oid = vec![top as u32, (first - top * 40) as u32];
+    let mut id: u32 = 0;
+    for &b in rest {
+        id = id.checked_mul(128)? | (b & 0x7f) as u32;
+        if b & 0x80 == 0 {
+            oid.push(id);
+            id = 0;
+        }
+    }
+    Some(oid)
+}
+
+impl Message {
+    pub fn encode(&self) -> Vec<u8> {
+        let bindings: Vec<u8> = self
+            .bindings
+            .iter()
+            .flat_map(|(oid, value)| tlv(0x30, &[tlv(0x06, &oid_bytes(oid)), value.encode()].concat()))
+            .collect();
+        let pdu = [
+            tlv(0x02, &int_bytes(self.request_id)),
+            tlv(0x02, &int_bytes(self.error_status)),
+            tlv(0x02, &int_bytes(self.error_index)),
+            tlv(0x30, &bindings),
+        ]
+        .concat();
+        let message = [
+            tlv(0x02, &int_bytes(self.version)),
+            tlv(0x04, &self.community),
+            tlv(self.pdu, &pdu),
+        ]
+        .concat();
+        tlv(0x30, &message)
+    }
+
+    /// Decode a message, or `None` if it isn't well-formed SNMP v1/v2c.
+    pub fn decode(packet: &[u8]) -> Option<Self> {
+        let mut message = Reader(Reader(packet).expect(0x30)?);
+        let version = parse_int(message.expect(0x02)?)?;
+        let community = message.expect(0x04)?.to_vec();
+        let (pdu, content) = message.read()?;
+        let mut fields = Reader(content);
+        let request_id = parse_int(fields.expect(0x02)?)?;
+        let error_status = parse_int(fields.expect(0x02)?)?;
+        let error_index = parse_int(fields.expect(0x02)?)?;
+        let mut list = Reader(fields.expect(0x30)?);
+        let mut bindings = Vec::new();
+        while !list.0.is_empty() {
+            let mut binding = Reader(list.expect(0x30)?);
+            let oid = parse_oid(binding.expect(0x06)?)?;
+            let (tag, value) = binding.read()?;
+            bindings.push((oid, Value::decode(tag, value)?));
+        }
+        Some(Self { version, community, pdu, request_id, error_status, error_index, bindings })
+    }
+}
+
+fn under(base: &[u32], suffix: &[u32]) -> Oid {
+    [base, suffix].concat()
+}
+
+/// 1 (ok) or 2 (alarm), the `AlarmState` textual convention of the MIB.
+fn alarm(raised: bool) -> Value {
+    Value::Integer(if raised { 2 } else { 1 })
+}
+
+/// Every object the agent serves, in OID order.
+pub fn objects(status: &StatusSnapshot, ntp: &[NtpSample], uptime: Duration) -> Vec<(Oid, Value)> {
+    let status_oid = |n: u32| under(TIMETURNER_OID, &[1, n, 0]);
+    let alarm_oid = |n: u32| under(TIMETURNER_OID, &[2, n, 0]);
+    vec![
+        (
+            under(SYSTEM_OID, &[1, 0]),
+            Value::OctetString(format!("NTP Timeturner {}", env!("CARGO_PKG_VERSION"))),
+        ),
+        (under(SYSTEM_OID, &[2, 0]), Value::ObjectId(TIMETURNER_OID.to_vec())),
+        (under(SYSTEM_OID, &[3, 0]), Value::TimeTicks((uptime.as_millis() / 10) as u32)),
+        (
+            status_oid(1),
+            Value::Integer(match (status.locked, &status.ltc_status) {
+                (true, _) => 1,
+                (false, Some(_)) => 2,
+                (false, None) => 0,
+            }),
+        ),
+        (status_oid(2), Value::OctetString(status.timecode.clone().unwrap_or_default())),
+        (status_oid(3), Value::OctetString(status.sync_status.to_string())),
+        (status_oid(4), Value::Integer(status.delta_ms)),
+        (status_oid(5), Value::Gauge32((status.lock_ratio * 10.0).round() as u32)),
+        (status_oid(6), Value::Integer(status.average_jitter_ms)),
+        (alarm_oid(1), alarm(status.ltc_alarm)),
+        (alarm_oid(2), alarm(status.delta_alarm)),
+        (alarm_oid(3), alarm(ntp.iter().any(|s| s.alert))),
+    ]
+}
+
+/// The reply to `packet`, or `None` to drop it: malformed, SNMPv3, the
+/// wrong community, or a response rather than a request.
+pub fn respond(packet: &[u8], community: &str, objects: &[(Oid, Value)]) -> Option<Vec<u8>> {
+    let request = Message::decode(packet)?;
+    if !matches!(request.version, VERSION_1 | VERSION_2C) || request.community != community.as_bytes() {
+        return None;
+    }
+    let v1 = request.version == VERSION_1;
+    let next = |oid: &Oid| objects.iter().find(|(o, _)| o > oid).cloned();
+    let mut reply = Message {
+        pdu: PDU_RESPONSE,
+        error_status: 0,
+        error_index: 0,
+        bindings: Vec::new(),
+        ..request.clone()
+    };
+    // v1 has no exception values: one missing object fails the request.
+    let fail = |reply: &mut Message, status: i64, index: usize| {
+        reply.error_status = status;
+        reply.error_index = index as i64 + 1;
+        reply.bindings = request.bindings.clone();
+    };
+    match request.pdu {
+        PDU_GET => {
+            for (i, (oid, _)) in request.bindings.iter().enumerate() {
+                match objects.iter().find(|(o, _)| o == oid) {
+                    Some(found) => reply.bindings.push(found.clone()),
+                    None if v1 => {
+                        fail(&mut reply, NO_SUCH_NAME, i);
+                        break;
+                    }
+                    None => reply.bindings.push((oid.clone(), Value::NoSuchObject)),
+                }
+            }
+        }
+        PDU_GET_NEXT => {
+            for (i, (oid, _)) in request.bindings.iter().enumerate() {
+                match next(oid) {
+                    Some(found) => reply.bindings.push(found),
+                    None if v1 => {
+                        fail(&mut reply, NO_SUCH_NAME, i);
+                        break;
+                    }
+                    None => reply.bindings.push((oid.clone(), Value::EndOfMibView)),
+                }
+            }
+        }
+        PDU_GET_BULK if !v1 => {
+            let non_repeaters = request.error_status.clamp(0, request.bindings.len() as i64) as usize;
+            let repetitions = request.error_index.clamp(0, MAX_REPETITIONS as i64) as usize;
+            let (singles, repeated) = request.bindings.split_at(non_repeaters);
+            for (oid, _) in singles {
+                reply.bindings.push(next(oid).unwrap_or((oid.clone(), Value::EndOfMibView)));
+            }
+            let mut cursors: Vec<Oid> = repeated.iter().map(|(oid, _)| oid.clone()).collect();
+            for _ in 0..repetitions {
+                if cursors.is_empty() {
+                    break;
+                }
+                let mut all_ended = true;
+                for cursor in cursors.iter_mut() {
+                    match next(cursor) {
+                        Some((oid, value)) => {
+                            all_ended = false;
+                            *cursor = oid.clone();
+                            reply.bindings.push((oid, value));
+                        }
+                        None => reply.bindings.push((cursor.clone(), Value::EndOfMibView)),
+                    }
+                }
+                if all_ended {
+                    break;
+                }
+            }
+        }
+        PDU_SET => fail(&mut reply, if v1 { NO_SUCH_NAME } else { NOT_WRITABLE }, 0),
+        _ => return None,
+    }
+    Some(reply.encode())
+}
+
+/// Answer requests until the process exits, (re)binding whenever
+/// `snmp.listen` changes.
+pub fn run(config: Arc<Mutex<Config>>, ltc: LtcView, ntp_check: Arc<Mutex<NtpCrossCheck>>, heartbeat: Heartbeat) {
+    let started = Instant::now();
+    let mut bound: Option<(String, UdpSocket)> = None;
+    let mut buf = [0u8; 1500];
+    loop {
+        heartbeat.beat();
+        let snmp = config.lock().unwrap().snmp.clone();
+        if bound.as_ref().map(|(addr, _)| addr) != snmp.listen.as_ref() {
+            bound = snmp.listen.as_ref().and_then(|addr| {
+                match UdpSocket::bind(addr).and_then(|s| s.set_read_timeout(Some(POLL_TIMEOUT)).map(|_| s)) {
+                    Ok(socket) => {
+                        log::info!("SNMP agent listening on {}.", addr);
+                        Some((addr.clone(), socket))
+                    }
+                    Err(e) => {
+                        log::error!("SNMP agent cannot listen on {}: {}", addr, e);
+                        None
+                    }
+                }
+            });
+        }
+        let Some((_, socket)) = &bound else {
+            std::thread::sleep(POLL_TIMEOUT);
+            continue;
+        };
+        let Ok((len, peer)) = socket.recv_from(&mut buf) else {
+            continue;
+        };
+        let config = config.lock().unwrap().clone();
+        let ntp = ntp_check.lock().unwrap().samples();
+        let objects = objects(&StatusSnapshot::new(&ltc.get(), &config), &ntp, started.elapsed());
+        match respond(&buf[..len], &snmp.community, &objects) {
+            Some(reply) => {
+                if let Err(e) = socket.send_to(&reply, peer) {
+                    log::debug!("SNMP reply to {} failed: {}", peer, e);
+                }
+            }
+            None => log::debug!("Dropped SNMP request from {}.", peer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync_logic::LtcState;
+
+    fn request(version: i64, community: &str, pdu: u8, fields: (i64, i64), oids: &[&[u32]]) -> Vec<u8> {
+        Message {
+            version,
+            community: community.as_bytes().to_vec(),
+            pdu,
+            request_id: 4242,
+            error_status: fields.0,
+            error_index: fields.1,
+            bindings: oids.iter().map(|o| (o.to_vec(), Value::Null)).collect(),
+        }
+        .encode()
+    }
+
+    fn reply(packet: &[u8], objects: &[(Oid, Value)]) -> Message {
+        Message::decode(&respond(packet, "public", objects).unwrap()).unwrap()
+    }
+
+    fn test_objects() -> Vec<(Oid, Value)> {
+        let status = StatusSnapshot::new(&LtcState::new().snapshot(), &Config::default());
+        objects(&status, &[], Duration::from_secs(5))
+    }
+
+    #[test]
+    fn test_ber_round_trip() {
+        for v in [0, 1, -1, 127, 128, -128, -129, 32473, i32::MAX as i64, i64::MIN] {
+            assert_eq!(parse_int(&int_bytes(v)), Some(v), "{}", v);
+        }
+        assert_eq!(int_bytes(128), vec![0x00, 0x80]);
+        assert_eq!(oid_bytes(TIMETURNER_OID), vec![0x2b, 6, 1, 4, 1, 0x81, 0xfd, 0x59, 1]);
+        assert_eq!(parse_oid(&oid_bytes(TIMETURNER_OID)).unwrap(), TIMETURNER_OID);
+        let long = Value::OctetString("x".repeat(300));
+        let binding = Message {
+            version: VERSION_2C,
+            community: b"public".to_vec(),
+            pdu: PDU_RESPONSE,
+            request_id: -7,
+            error_status: 0,
+            error_index: 0,
+            bindings: vec![(TIMETURNER_OID.to_vec(), long)],
+        };
+        assert_eq!(Message::decode(&binding.encode()), Some(binding));
+        assert_eq!(Message::decode(&[0x30, 0x05, 0x02]), None);
+    }
+
+    #[test]
+    fn test_get() {
+        let objects = test_objects();
+        let sys_object_id: &[u32] = &[1, 3, 6, 1, 2, 1, 1, 2, 0];
+        let ltc_status: &[u32] = &[1, 3, 6, 1, 4, 1, 32473, 1, 1, 1, 0];
+        let missing: &[u32] = &[1, 3, 6, 1, 4, 1, 32473, 1, 9, 0];
+        let got = reply(&request(VERSION_2C, "public", PDU_GET, (0, 0), &[sys_object_id, ltc_status, missing]), &objects);
+        assert_eq!((got.pdu, got.request_id, got.error_status), (PDU_RESPONSE, 4242, 0));
+        assert_eq!(got.bindings[0].1, Value::ObjectId(TIMETURNER_OID.to_vec()));
+        assert_eq!(got.bindings[1].1, Value::Integer(0));
+        assert_eq!(got.bindings[2], (missing.to_vec(), Value::NoSuchObject));
+
+        // v1 fails the whole request on the first missing object.
+        let got = reply(&request(VERSION_1, "public", PDU_GET, (0, 0), &[ltc_status, missing]), &objects);
+        assert_eq!((got.error_status, got.error_index), (NO_SUCH_NAME, 2));
+
+        // Wrong community and SNMPv3 are dropped; SETs refused.
+        assert!(respond(&request(VERSION_2C, "private", PDU_GET, (0, 0), &[ltc_status]), "public", &objects).is_none());
+        assert!(respond(&request(3, "public", PDU_GET, (0, 0), &[ltc_status]), "public", &objects).is_none());
+        let got = reply(&request(VERSION_2C, "public", PDU_SET, (0, 0), &[ltc_status]), &objects);
+        assert_eq!((got.error_status, got.error_index), (NOT_WRITABLE, 1));
+    }
+
+    #[test]
+    fn test_walk() {
+        let objects = test_objects();
+        // GETNEXT from the root walks every object in order, then ends.
+        let mut oid = vec![1, 3];
+        let mut walked = Vec::new();
+        loop {
+            let got = reply(&request(VERSION_2C, "public", PDU_GET_NEXT, (0, 0), &[&oid]), &objects);
+            let (next, value) = got.bindings[0].clone();
+            if value == Value::EndOfMibView {
+                break;
+            }
+            walked.push((next.clone(), value));
+            oid = next;
+        }
+        assert_eq!(walked, objects);
+
+        // GETBULK: one non-repeater, then up to three of the rest.
+        let ltc_alarm: &[u32] = &[1, 3, 6, 1, 4, 1, 32473, 1, 2, 1, 0];
+        let got = reply(&request(VERSION_2C, "public", PDU_GET_BULK, (1, 3), &[&[1, 3], ltc_alarm]), &objects);
+        let oids: Vec<Oid> = got.bindings.iter().map(|(o, _)| o.clone()).collect();
+        let tail = objects.len() - 2;
+        assert_eq!(oids[0], objects[0].0);
+        assert_eq!(&oids[1..3], [objects[tail].0.clone(), objects[tail + 1].0.clone()]);
+        assert_eq!(got.bindings[3].1, Value::EndOfMibView);
+        assert_eq!(got.bindings.len(), 4);
+    }
+
+    #[test]
+    fn test_alarms_follow_state() {
+        let objects = test_objects();
+        let value = |n: u32| objects.iter().find(|(o, _)| *o == under(TIMETURNER_OID, &[2, n, 0])).unwrap().1.clone();
+        assert_eq!(value(1), Value::Integer(2), "no LTC raises the LTC alarm");
+        assert_eq!(value(2), Value::Integer(1));
+        assert_eq!(value(3), Value::Integer(1));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(SnmpConfig::default().validate().is_ok());
+        let listening = SnmpConfig { listen: Some("0.0.0.0:161".into()), ..SnmpConfig::default() };
+        assert!(listening.validate().is_ok());
+        let bad = SnmpConfig { listen: Some("localhost".into()), ..SnmpConfig::default() };
+        assert!(bad.validate().is_err());
+        assert!(SnmpConfig { community: String::new(), ..listening }.validate().is_err());
+    }
+}
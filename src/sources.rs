@@ -0,0 +1,382 @@
+// src/sources.rs
+//
+// Time sources behind one trait. Each source reports its health and its
+// latest estimate of how far the system clock is from true time, with an
+// uncertainty, so failover and custom integrations can compare LTC, NTP,
+// PTP and anything added later without knowing where the numbers come
+// from. Sources are registered in a `SourceRegistry` by name; the standard
+// set is built once at startup and served at `/api/sources`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use utoipa::ToSchema;
+
+use crate::config::Config;
+use crate::ntp_client::NtpCrossCheck;
+use crate::ptp_capture::PtpCapture;
+use crate::sync_logic::{frame_duration_ms, LtcView, Staleness};
+
+/// A PTP Sync older than this no longer counts.
+const PTP_FRESH_SECS: i64 = 10;
+
+/// PTP Syncs behind the PTP source's uncertainty.
+const PTP_WINDOW: usize = 16;
+
+/// One source's view of the system clock.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeEstimate {
+    /// How far the system clock is ahead of the source, in milliseconds
+    /// (negative: behind); the sign of `timecode_delta_ms`.
+    pub offset_ms: f64,
+    /// How far `offsetMs` may be from the truth, in milliseconds.
+    pub uncertainty_ms: f64,
+    /// When the source last measured it.
+    #[schema(value_type = String, format = DateTime)]
+    pub measured: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceHealth {
+    /// Measuring normally.
+    Ok,
+    /// Measuring, but late or partly failing.
+    Degraded,
+    /// Configured, with nothing to go on.
+    Down,
+    /// Not configured.
+    Disabled,
+}
+
+pub trait TimeSource: Send + Sync {
+    /// Unique within a registry, e.g. `ltc`.
+    fn name(&self) -> &str;
+    fn health(&self, now: DateTime<Utc>) -> SourceHealth;
+    /// The latest estimate, or `None` while the source has none worth using.
+    fn estimate(&self, now: DateTime<Utc>) -> Option<TimeEstimate>;
+}
+
+/// One source in `/api/sources`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceReport {
+    pub name: String,
+    pub health: SourceHealth,
+    pub estimate: Option<TimeEstimate>,
+}
+
+#[derive(Default)]
+pub struct SourceRegistry {
+    sources: Mutex<Vec<Arc<dyn TimeSource>>>,
+}
+
+impl SourceRegistry {
+    /// LTC, NTP and PTP, from the daemon's shared state.
+    pub fn standard(
+        ltc: LtcView,
+        config: Arc<Mutex<Config>>,
+        ntp_check: Arc<Mutex<NtpCrossCheck>>,
+        ptp_capture: Arc<Mutex<PtpCapture>>,
+    ) -> Self {
+        let registry = Self::default();
+        registry.register(Arc::new(LtcSource { ltc, config: config.clone() }));
+        registry.register(Arc::new(NtpSource { check: ntp_check, config: config.clone() }));
+        registry.register(Arc::new(PtpSource { capture: ptp_capture, config }));
+        registry
+    }
+
+    /// Add `source`, replacing any registered under the same name.
+    pub fn register(&self, source: Arc<dyn TimeSource>) {
+        let mut sources = self.sources.lock().unwrap();
+        match sources.iter().position(|s| s.name() == source.name()) {
+            Some(i) => sources[i] = source,
+            None => sources.push(source),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn TimeSource>> {
+        self.sources.lock().unwrap().iter().find(|s| s.name() == name).cloned()
+    }
+
+    /// Every source's health and estimate at `now`, in registration order.
+    pub fn reports(&self, now: DateTime<Utc>) -> Vec<SourceReport> {
+        let sources = self.sources.lock().unwrap().clone();
+        sources
+            .iter()
+            .map(|s| SourceReport { name: s.name().to_string(), health: s.health(now), estimate: s.estimate(now) })
+            .collect()
+    }
+}
+
+/// The LTC reader, through the frame processor's smoothed delta. Its
+/// uncertainty is the 95th-percentile jitter, or half a frame before there
+/// is any.
+pub struct LtcSource {
+    pub ltc: LtcView,
+    pub config: Arc<Mutex<Config>>,
+}
+
+impl TimeSource for LtcSource {
+    fn name(&self) -> &str {
+        "ltc"
+    }
+
+    fn health(&self, _now: DateTime<Utc>) -> SourceHealth {
+        let state = self.ltc.get();
+        if state.latest.is_none() {
+            return SourceHealth::Down;
+        }
+        match state.staleness(&self.config.lock().unwrap()) {
+            Staleness::Live if state.locked() => SourceHealth::Ok,
+            Staleness::Stale => SourceHealth::Degraded,
+            _ => SourceHealth::Down,
+        }
+    }
+
+    fn estimate(&self, _now: DateTime<Utc>) -> Option<TimeEstimate> {
+        let state = self.ltc.get();
+        let frame = state.latest.as_ref().filter(|_| state.locked())?;
+        Some(TimeEstimate {
+            offset_ms: state.ewma_clock_delta?,
+            uncertainty_ms: state
+                .jitter
+                .as_ref()
+                .map_or(frame_duration_ms(frame.frame_rate) / 2.0, |j| j.p95_ms as f64),
+            measured: frame.timestamp,
+        })
+    }
+}
+
+/// The NTP cross-check's servers, through the answer with the shortest
+/// round trip. Its uncertainty is half that round trip.
+pub struct NtpSource {
+    pub check: Arc<Mutex<NtpCrossCheck>>,
+    pub config: Arc<Mutex<Config>>,
+}
+
+impl NtpSource {
+    /// How old a sample may be: three polling intervals.
+    fn max_age(&self) -> chrono::Duration {
+        chrono::Duration::seconds(3 * self.config.lock().unwrap().ntp_check.interval_secs as i64)
+    }
+}
+
+impl TimeSource for NtpSource {
+    fn name(&self) -> &str {
+        "ntp"
+    }
+
+    fn health(&self, now: DateTime<Utc>) -> SourceHealth {
+        if self.config.lock().unwrap().ntp_check.servers.is_empty() {
+            return SourceHealth::Disabled;
+        }
+        let samples = self.check.lock().unwrap().samples();
+        let fresh: Vec<_> = samples.iter().filter(|s| now - s.timestamp <= self.max_age()).collect();
+        let answered = fresh.iter().filter(|s| s.offset_ms.is_some()).count();
+        match answered {
+            0 => SourceHealth::Down,
+            n if n < fresh.len() => SourceHealth::Degraded,
+            _ => SourceHealth::Ok,
+        }
+    }
+
+    fn estimate(&self, now: DateTime<Utc>) -> Option<TimeEstimate> {
+        let max_age = self.max_age();
+        let samples = self.check.lock().unwrap().samples();
+        samples
+            .iter()
+            .filter(|s| now - s.timestamp <= max_age)
+            .filter_map(|s| Some((s.offset_ms?, s.delay_ms?, s.timestamp)))
+            .min_by_key(|&(_, delay, _)| delay)
+            .map(|(offset, delay, measured)| TimeEstimate {
+                // NTP offsets say how far the clock is behind the server.
+                offset_ms: -offset as f64,
+                uncertainty_ms: delay as f64 / 2.0,
+                measured,
+            })
+    }
+}
+
+/// The PTP message log, through the latest Sync on `ptpCapture.domain`. A
+/// passive listener cannot measure the path delay, so the uncertainty is
+/// half the spread of the last `PTP_WINDOW` Syncs, which understates it by
+/// that delay.
+pub struct PtpSource {
+    pub capture: Arc<Mutex<PtpCapture>>,
+    pub config: Arc<Mutex<Config>>,
+}
+
+impl PtpSource {
+    /// Recent Sync offsets on the configured domain with when they arrived,
+    /// oldest first, or nothing while the capture is off.
+    fn offsets(&self, now: DateTime<Utc>) -> Option<Vec<(f64, DateTime<Utc>)>> {
+        let config = self.config.lock().unwrap().ptp_capture.clone();
+        if !config.enabled {
+            return None;
+        }
+        let messages = self.capture.lock().unwrap().messages(None);
+        let mut offsets: Vec<_> = messages
+            .iter()
+            .filter(|m| m.domain == config.domain && (now - m.received).num_seconds() < PTP_FRESH_SECS)
+            .filter_map(|m| Some((m.offset_ms?, m.received)))
+            .collect();
+        offsets.drain(..offsets.len().saturating_sub(PTP_WINDOW));
+        Some(offsets)
+    }
+}
+
+impl TimeSource for PtpSource {
+    fn name(&self) -> &str {
+        "ptp"
+    }
+
+    fn health(&self, now: DateTime<Utc>) -> SourceHealth {
+        match self.offsets(now) {
+            None => SourceHealth::Disabled,
+            Some(offsets) if offsets.is_empty() => SourceHealth::Down,
+            Some(_) => SourceHealth::Ok,
+        }
+    }
+
+    fn estimate(&self, now: DateTime<Utc>) -> Option<TimeEstimate> {
+        let offsets = self.offsets(now)?;
+        let &(offset_ms, measured) = offsets.last()?;
+        let (min, max) = offsets
+            .iter()
+            .fold((f64::MAX, f64::MIN), |(lo, hi), &(o, _)| (lo.min(o), hi.max(o)));
+        Some(TimeEstimate { offset_ms, uncertainty_ms: (max - min) / 2.0, measured })
+    }
+}
+
+/// A source that reports whatever it is given, for simulation and tests.
+pub struct SimulatedSource {
+    name: String,
+    estimate: Mutex<Option<TimeEstimate>>,
+}
+
+impl SimulatedSource {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string(), estimate: Mutex::new(None) }
+    }
+
+    /// Report `estimate` from now on; `None` takes the source down.
+    pub fn set(&self, estimate: Option<TimeEstimate>) {
+        *self.estimate.lock().unwrap() = estimate;
+    }
+}
+
+impl TimeSource for SimulatedSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn health(&self, _now: DateTime<Utc>) -> SourceHealth {
+        match *self.estimate.lock().unwrap() {
+            Some(_) => SourceHealth::Ok,
+            None => SourceHealth::Down,
+        }
+    }
+
+    fn estimate(&self, _now: DateTime<Utc>) -> Option<TimeEstimate> {
+        self.estimate.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ntp_client::NtpSample;
+    use crate::sync_logic::{LtcFrame, LtcState};
+    use num_rational::Ratio;
+    use std::time::Instant;
+
+    fn ntp_sample(server: &str, at: DateTime<Utc>, offset_ms: Option<i64>, delay_ms: Option<i64>) -> NtpSample {
+        NtpSample {
+            server: server.to_string(),
+            timestamp: at,
+            offset_ms,
+            delay_ms,
+            disagreement_ms: None,
+            alert: false,
+            error: offset_ms.is_none().then(|| "timed out".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_registry_replaces_sources_by_name() {
+        let registry = SourceRegistry::default();
+        let first = Arc::new(SimulatedSource::new("sim"));
+        registry.register(first.clone());
+        registry.register(Arc::new(SimulatedSource::new("gps")));
+        let now = Utc::now();
+        assert_eq!(registry.reports(now)[0].health, SourceHealth::Down);
+
+        let replacement = Arc::new(SimulatedSource::new("sim"));
+        let estimate = TimeEstimate { offset_ms: 1.5, uncertainty_ms: 0.2, measured: now };
+        replacement.set(Some(estimate.clone()));
+        registry.register(replacement);
+        let reports = registry.reports(now);
+        assert_eq!(reports.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), ["sim", "gps"]);
+        assert_eq!(reports[0].health, SourceHealth::Ok);
+        assert_eq!(registry.get("sim").unwrap().estimate(now), Some(estimate));
+        assert!(registry.get("ptp").is_none());
+    }
+
+    #[test]
+    fn test_ltc_source_follows_lock() {
+        let mut state = LtcState::new();
+        let view = state.view();
+        let source = LtcSource { ltc: view, config: Arc::new(Mutex::new(Config::default())) };
+        let now = Utc::now();
+        assert_eq!(source.health(now), SourceHealth::Down);
+
+        state.latest = Some(LtcFrame {
+            status: "LOCK".to_string(),
+            hours: 10,
+            minutes: 0,
+            seconds: 0,
+            frames: 0,
+            is_drop_frame: false,
+            frame_rate: Ratio::new(25, 1),
+            timestamp: now,
+            received: None,
+        });
+        state.last_frame_at = Some(Instant::now());
+        state.record_clock_delta(-4.0);
+        state.publish();
+        assert_eq!(source.health(now), SourceHealth::Ok);
+        let estimate = source.estimate(now).unwrap();
+        assert_eq!((estimate.offset_ms, estimate.uncertainty_ms), (-4.0, 20.0), "half a frame before any jitter");
+
+        state.latest.as_mut().unwrap().status = "FREE".to_string();
+        state.publish();
+        assert_eq!(source.health(now), SourceHealth::Down);
+        assert_eq!(source.estimate(now), None);
+    }
+
+    #[test]
+    fn test_ntp_source_uses_the_shortest_round_trip() {
+        let check = Arc::new(Mutex::new(NtpCrossCheck::default()));
+        let config = Arc::new(Mutex::new(Config::default()));
+        let source = NtpSource { check: check.clone(), config: config.clone() };
+        let now = Utc::now();
+        assert_eq!(source.health(now), SourceHealth::Disabled);
+
+        config.lock().unwrap().ntp_check.servers = vec!["a".into(), "b".into(), "c".into()];
+        {
+            let mut check = check.lock().unwrap();
+            check.record(ntp_sample("a", now, Some(3), Some(40)));
+            check.record(ntp_sample("b", now, Some(2), Some(10)));
+            check.record(ntp_sample("c", now, None, None));
+        }
+        assert_eq!(source.health(now), SourceHealth::Degraded);
+        let estimate = source.estimate(now).unwrap();
+        assert_eq!((estimate.offset_ms, estimate.uncertainty_ms), (-2.0, 5.0));
+
+        let later = now + chrono::Duration::seconds(3 * 64 + 1);
+        assert_eq!(source.health(later), SourceHealth::Down);
+        assert_eq!(source.estimate(later), None);
+    }
+}
@@ -0,0 +1,130 @@
+// src/status.rs
+//
+// The derived status every surface reports: the API (and through it the TUI
+// and web UI), the SNMP agent and the Prometheus metrics. Built once from a
+// published `LtcSnapshot` and the config, so the lock state, delta, sync and
+// jitter verdicts and alarms are worked out in one place and never disagree
+// between surfaces. Host facts (interfaces, the NTP service) are gathered by
+// the API separately, as they take I/O.
+
+use num_rational::Ratio;
+
+use crate::config::Config;
+use crate::sync_logic::{self, ClockDirection, ContinuityReport, DeltaFrames, LtcSnapshot, Staleness};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusSnapshot {
+    /// `LOCK` or `FREE` from the latest frame, or `NO SIGNAL` once frames
+    /// stop arriving; `None` before the first one.
+    pub ltc_status: Option<String>,
+    pub locked: bool,
+    /// Whether frames are still arriving, by the age of the latest.
+    pub staleness: Staleness,
+    /// Time since the latest frame, in ms; `None` before the first.
+    pub frame_age_ms: Option<u64>,
+    /// The latest frame's label, e.g. `10:20:30;04`.
+    pub timecode: Option<String>,
+    pub frame_rate: Option<Ratio<i64>>,
+    /// Set while the frames disagree with `expectedFrameRate`, e.g.
+    /// `LTC 24.00fps, expected 25.00fps`.
+    pub frame_rate_warning: Option<String>,
+    /// Smoothed clock delta, rounded to the ms; 0 before the first LOCK.
+    pub delta_ms: i64,
+    /// The same delta unrounded, to 1 µs.
+    pub delta_raw_ms: Option<f64>,
+    /// The latest LOCK frame's own delta before filtering, to 1 µs.
+    pub frame_delta_ms: Option<f64>,
+    /// The delta in whole frames at the incoming rate.
+    pub delta_frames: i64,
+    /// The delta at the incoming rate and then at `secondaryFrameRate`.
+    pub delta_by_rate: Vec<DeltaFrames>,
+    pub clock_direction: ClockDirection,
+    pub sync_status: &'static str,
+    pub average_jitter_ms: i64,
+    pub jitter_status: &'static str,
+    pub lock_ratio: f64,
+    /// Drift against LTC to 0.1 ppm, once estimated.
+    pub drift_ppm: Option<f64>,
+    /// Skips, repeats and reversals in the timecode.
+    pub continuity: ContinuityReport,
+    /// Not LOCK: LTC lost, stopped or never seen.
+    pub ltc_alarm: bool,
+    /// Locked with the delta beyond `hooks.deltaThresholdMs`.
+    pub delta_alarm: bool,
+}
+
+impl StatusSnapshot {
+    pub fn new(state: &LtcSnapshot, config: &Config) -> Self {
+        let latest = state.latest.as_ref();
+        let locked = state.locked();
+        let frame_rate = latest.map(|f| f.frame_rate);
+        let delta_ms = state.get_ewma_clock_delta();
+        let staleness = state.staleness(config);
+        Self {
+            ltc_status: state.ltc_status().map(String::from),
+            locked,
+            staleness,
+            frame_age_ms: state.frame_age().map(|age| age.as_millis() as u64),
+            timecode: latest.map(|f| f.timecode()),
+            frame_rate,
+            frame_rate_warning: match (frame_rate, config.expected_rate()) {
+                (Some(rate), Some(expected)) if state.frame_rate_mismatch => {
+                    Some(sync_logic::frame_rate_warning(rate, expected))
+                }
+                _ => None,
+            },
+            delta_ms,
+            delta_raw_ms: state.ewma_clock_delta.map(|d| (d * 1000.0).round() / 1000.0),
+            frame_delta_ms: state.frame_delta_ms.map(|d| (d * 1000.0).round() / 1000.0),
+            delta_frames: frame_rate.map_or(0, |rate| sync_logic::delta_in_frames(delta_ms, rate)),
+            delta_by_rate: sync_logic::delta_frames_by_rate(delta_ms, frame_rate, config.secondary_rate()),
+            clock_direction: sync_logic::clock_direction(delta_ms, config),
+            sync_status: sync_logic::get_sync_status(delta_ms, staleness, config),
+            average_jitter_ms: state.average_jitter,
+            jitter_status: sync_logic::get_jitter_status(state.average_jitter),
+            lock_ratio: state.lock_ratio(),
+            drift_ppm: state.drift_ppm.map(|ppm| (ppm * 10.0).round() / 10.0),
+            continuity: state.continuity.clone(),
+            ltc_alarm: !locked,
+            delta_alarm: locked && delta_ms.abs() > config.hooks.delta_threshold_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync_logic::{LtcFrame, LtcState};
+    use chrono::Utc;
+
+    #[test]
+    fn test_snapshot_from_state() {
+        let empty = StatusSnapshot::new(&LtcState::new().snapshot(), &Config::default());
+        assert_eq!((empty.ltc_status.as_deref(), empty.timecode.as_deref()), (None, None));
+        assert!(empty.ltc_alarm && !empty.delta_alarm);
+        assert!(empty.delta_by_rate.is_empty());
+        assert_eq!((empty.staleness, empty.frame_age_ms, empty.sync_status), (Staleness::NoSignal, None, "NO SIGNAL"));
+
+        let mut state = LtcState::new();
+        state.update(LtcFrame {
+            status: "LOCK".into(),
+            hours: 10,
+            minutes: 20,
+            seconds: 30,
+            frames: 4,
+            is_drop_frame: true,
+            frame_rate: Ratio::new(30000, 1001),
+            timestamp: Utc::now(),
+            received: None,
+        });
+        state.ewma_clock_delta = Some(-66.7004);
+        let config = Config { secondary_frame_rate: Some("25".into()), ..Config::default() };
+        let status = StatusSnapshot::new(&state.snapshot(), &config);
+        assert_eq!(status.timecode.as_deref(), Some("10:20:30;04"));
+        assert_eq!((status.delta_ms, status.delta_raw_ms, status.delta_frames), (-67, Some(-66.7), -2));
+        assert_eq!(status.delta_by_rate[1].frames, -2);
+        assert_eq!(status.clock_direction, ClockDirection::Behind);
+        assert!(!status.ltc_alarm);
+        assert!(status.delta_alarm, "beyond the 40 ms hook threshold");
+    }
+}
@@ -0,0 +1,258 @@
+// src/supervisor.rs
+//
+// Runs the daemon's long-lived components (the LTC reader, frame processor,
+// auto-sync executor, API server and output modules) and restarts any that
+// panic, so one bad frame or device no longer takes a thread down for good.
+// Restarts back off from `INITIAL_BACKOFF`, doubling up to `MAX_BACKOFF`,
+// and start again from the bottom once a component has stayed up for
+// `STABLE_AFTER`. Each component's state is served at `/api/components`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::fmt::Display;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+
+/// When a component that stops is started again.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Restart {
+    /// After a panic, an error or a return: the component is meant to run
+    /// for as long as the daemon does.
+    Always,
+    /// Only after a panic; returning is a clean stop.
+    OnPanic,
+}
+
+/// One component's state, for `/api/components`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct ComponentHealth {
+    pub name: String,
+    pub restart: Restart,
+    /// Running now, rather than waiting to restart or stopped.
+    pub alive: bool,
+    pub restarts: u32,
+    /// Why it last stopped, if it panicked or failed.
+    pub last_error: Option<String>,
+    /// When it last started, or stopped if it is not alive.
+    #[schema(value_type = String, format = DateTime)]
+    pub since: DateTime<Utc>,
+}
+
+/// How one run of a component ended.
+enum Exit {
+    Returned,
+    Failed(String),
+    Panicked(String),
+}
+
+pub struct Supervisor {
+    components: Mutex<Vec<ComponentHealth>>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".into())
+}
+
+impl Supervisor {
+    pub fn new() -> Arc<Self> {
+        Self::with_backoff(INITIAL_BACKOFF, MAX_BACKOFF)
+    }
+
+    pub fn with_backoff(initial: Duration, max: Duration) -> Arc<Self> {
+        Arc::new(Self {
+            components: Mutex::new(Vec::new()),
+            initial_backoff: initial,
+            max_backoff: max,
+        })
+    }
+
+    pub fn components(&self) -> Vec<ComponentHealth> {
+        self.components.lock().unwrap().clone()
+    }
+
+    fn register(&self, name: &str, restart: Restart) -> usize {
+        let mut components = self.components.lock().unwrap();
+        components.push(ComponentHealth {
+            name: name.to_string(),
+            restart,
+            alive: true,
+            restarts: 0,
+            last_error: None,
+            since: Utc::now(),
+        });
+        components.len() - 1
+    }
+
+    fn started(&self, index: usize) {
+        let mut components = self.components.lock().unwrap();
+        components[index].alive = true;
+        components[index].since = Utc::now();
+    }
+
+    /// Note how run `index` ended and return how long to wait before the
+    /// next one, or `None` if it stays stopped.
+    fn stopped(&self, index: usize, exit: Exit, ran_for: Duration, backoff: &mut Duration) -> Option<Duration> {
+        let mut components = self.components.lock().unwrap();
+        let component = &mut components[index];
+        component.alive = false;
+        component.since = Utc::now();
+        let (restart, error) = match exit {
+            Exit::Returned => (component.restart == Restart::Always, None),
+            Exit::Failed(e) => (component.restart == Restart::Always, Some(e)),
+            Exit::Panicked(e) => (true, Some(format!("panicked: {}", e))),
+        };
+        match &error {
+            Some(e) => log::error!("Component {} stopped: {}", component.name, e),
+            None if restart => log::warn!("Component {} exited.", component.name),
+            None => log::info!("Component {} finished.", component.name),
+        }
+        if error.is_some() {
+            component.last_error = error;
+        }
+        if !restart {
+            return None;
+        }
+        if ran_for >= STABLE_AFTER {
+            *backoff = self.initial_backoff;
+        }
+        let wait = *backoff;
+        *backoff = (*backoff * 2).min(self.max_backoff);
+        component.restarts += 1;
+        log::info!("Restarting {} in {:?}.", component.name, wait);
+        Some(wait)
+    }
+
+    /// Run `body` on a thread named `name`, calling it again under `restart`
+    /// whenever it ends. The handle joins once the component stops for good.
+    pub fn spawn<F>(self: &Arc<Self>, name: &str, restart: Restart, body: F) -> JoinHandle<()>
+    where
+        F: Fn() + Send + 'static,
+    {
+        let index = self.register(name, restart);
+        let supervisor = self.clone();
+        thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                let mut backoff = supervisor.initial_backoff;
+                loop {
+                    supervisor.started(index);
+                    let start = Instant::now();
+                    let exit = match panic::catch_unwind(AssertUnwindSafe(&body)) {
+                        Ok(()) => Exit::Returned,
+                        Err(payload) => Exit::Panicked(panic_message(payload.as_ref())),
+                    };
+                    match supervisor.stopped(index, exit, start.elapsed(), &mut backoff) {
+                        Some(wait) => thread::sleep(wait),
+                        None => return,
+                    }
+                }
+            })
+            .expect("failed to spawn component thread")
+    }
+
+    /// Like `spawn`, for a task on the current `LocalSet`: `start` makes the
+    /// future for each run, and an `Err` counts as a failure.
+    pub async fn run_local<F, Fut, E>(self: Arc<Self>, name: &'static str, restart: Restart, start: F)
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<(), E>> + 'static,
+        E: Display + 'static,
+    {
+        let index = self.register(name, restart);
+        let mut backoff = self.initial_backoff;
+        loop {
+            self.started(index);
+            let begun = Instant::now();
+            let exit = match tokio::task::spawn_local(start()).await {
+                Ok(Ok(())) => Exit::Returned,
+                Ok(Err(e)) => Exit::Failed(e.to_string()),
+                Err(e) if e.is_panic() => Exit::Panicked(panic_message(e.into_panic().as_ref())),
+                Err(e) => Exit::Failed(e.to_string()),
+            };
+            match self.stopped(index, exit, begun.elapsed(), &mut backoff) {
+                Some(wait) => tokio::time::sleep(wait).await,
+                None => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_restarts_a_panicking_component_with_backoff() {
+        let supervisor = Supervisor::with_backoff(Duration::from_millis(10), Duration::from_millis(40));
+        let runs = Arc::new(AtomicU32::new(0));
+        let counter = runs.clone();
+        let handle = supervisor.spawn("flaky", Restart::OnPanic, move || {
+            if counter.fetch_add(1, Ordering::SeqCst) < 3 {
+                panic!("bad frame {}", counter.load(Ordering::SeqCst));
+            }
+        });
+        handle.join().unwrap();
+
+        assert_eq!(runs.load(Ordering::SeqCst), 4);
+        let component = &supervisor.components()[0];
+        assert_eq!(component.name, "flaky");
+        assert!(!component.alive, "a clean return stops an OnPanic component");
+        assert_eq!(component.restarts, 3);
+        assert_eq!(component.last_error.as_deref(), Some("panicked: bad frame 3"));
+    }
+
+    #[test]
+    fn test_backoff_doubles_and_resets_once_stable() {
+        let supervisor = Supervisor::with_backoff(Duration::from_secs(1), Duration::from_secs(4));
+        let index = supervisor.register("reader", Restart::Always);
+        let mut backoff = supervisor.initial_backoff;
+        let waits: Vec<_> = (0..4)
+            .map(|_| supervisor.stopped(index, Exit::Returned, Duration::ZERO, &mut backoff).unwrap())
+            .collect();
+        assert_eq!(waits, [1, 2, 4, 4].map(Duration::from_secs));
+        let wait = supervisor.stopped(index, Exit::Failed("gone".into()), STABLE_AFTER, &mut backoff);
+        assert_eq!(wait, Some(Duration::from_secs(1)));
+        assert_eq!(supervisor.components()[0].restarts, 5);
+        assert_eq!(supervisor.components()[0].last_error.as_deref(), Some("gone"));
+    }
+
+    #[tokio::test]
+    async fn test_local_task_failures_are_restarted() {
+        let supervisor = Supervisor::with_backoff(Duration::from_millis(1), Duration::from_millis(1));
+        let runs = Arc::new(AtomicU32::new(0));
+        let local = tokio::task::LocalSet::new();
+        let counter = runs.clone();
+        local
+            .run_until(supervisor.clone().run_local("api", Restart::OnPanic, move || {
+                let run = counter.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    match run {
+                        0 => panic!("handler bug"),
+                        _ => Ok::<(), String>(()),
+                    }
+                }
+            }))
+            .await;
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        let component = &supervisor.components()[0];
+        assert_eq!(component.restarts, 1);
+        assert_eq!(component.last_error.as_deref(), Some("panicked: handler bug"));
+    }
+}
@@ -1,22 +1,148 @@
-﻿use crate::config::Config;
-use chrono::{DateTime, Local, Timelike, Utc};
+﻿use crate::config::{Config, LtcZone, SyncSource};
+use crate::processor::PipelineReport;
+use crate::timecode::{is_valid_label, nominal_fps, Timecode};
+use chrono::{DateTime, Datelike, Timelike, Utc};
 use num_rational::Ratio;
+use num_traits::ToPrimitive;
 use regex::Captures;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use utoipa::ToSchema;
 
 const EWMA_ALPHA: f64 = 0.1;
 
-fn get_frame_rate_ratio(rate_str: &str) -> Option<Ratio<i64>> {
-    match rate_str {
-        "23.98" => Some(Ratio::new(24000, 1001)),
-        "24.00" => Some(Ratio::new(24, 1)),
-        "25.00" => Some(Ratio::new(25, 1)),
-        "29.97" => Some(Ratio::new(30000, 1001)),
-        "30.00" => Some(Ratio::new(30, 1)),
+/// Delta samples kept for the drift regression: 30 minutes at one a second.
+const DRIFT_WINDOW: usize = 1800;
+
+/// Span of samples needed before a drift estimate is reported.
+const DRIFT_MIN_SPAN: Duration = Duration::from_secs(120);
+
+/// Frames in the arrival-time fit behind the sub-frame phase estimate.
+const PHASE_WINDOW: usize = 64;
+
+/// Frames needed before the fit is used instead of the raw delta.
+const PHASE_MIN_FRAMES: usize = 8;
+
+/// Frames in a row off the fit by a frame or more before it starts over: a
+/// real jump in the delta rather than one late or corrupted frame.
+const PHASE_MAX_MISFITS: u32 = 3;
+
+/// Frame rates LTC can run at. The reader prints a rounded, measured float
+/// ("23.98", "29.97", "60.00"), so it is matched to the nearest of these.
+const FRAME_RATES: [(i64, i64); 8] = [
+    (24000, 1001),
+    (24, 1),
+    (25, 1),
+    (30000, 1001),
+    (30, 1),
+    (50, 1),
+    (60000, 1001),
+    (60, 1),
+];
+
+/// How far a printed rate may be from a standard one and still match it.
+const FRAME_RATE_TOLERANCE: f64 = 0.02;
+
+/// The exact rate for the reader's printed `rate_str`, or `None` if it is not
+/// close to a standard rate. Drop-frame only exists at 29.97 and 59.94, so a
+/// drop-frame flag resolves 30/60 to those; at any other rate it is invalid.
+fn get_frame_rate_ratio(rate_str: &str, is_drop_frame: bool) -> Option<Ratio<i64>> {
+    let printed: f64 = rate_str.parse().ok()?;
+    let rate = FRAME_RATES
+        .iter()
+        .map(|&(n, d)| Ratio::new(n, d))
+        .find(|r| (r.to_f64().unwrap_or(0.0) - printed).abs() <= FRAME_RATE_TOLERANCE)?;
+    if !is_drop_frame {
+        return Some(rate);
+    }
+    match nominal_fps(rate) {
+        30 | 60 => Some(Ratio::new(nominal_fps(rate) * 1000, 1001)),
         _ => None,
     }
 }
 
+/// The rate for a configured frame rate such as `25` or `29.97`.
+pub fn parse_frame_rate(s: &str) -> Result<Ratio<i64>, String> {
+    get_frame_rate_ratio(s.trim(), false).ok_or_else(|| {
+        format!("'{}' is not a standard frame rate (23.98, 24, 25, 29.97, 30, 50, 59.94 or 60)", s)
+    })
+}
+
+/// `rate` as shown in the status, e.g. `29.97fps`.
+pub fn frame_rate_label(rate: Ratio<i64>) -> String {
+    format!("{:.2}fps", rate.to_f64().unwrap_or(0.0))
+}
+
+/// The warning for LTC at `rate` when `expectedFrameRate` is `expected`,
+/// e.g. `LTC 24.00fps, expected 25.00fps`.
+pub fn frame_rate_warning(rate: Ratio<i64>, expected: Ratio<i64>) -> String {
+    format!("LTC {}, expected {}", frame_rate_label(rate), frame_rate_label(expected))
+}
+
+/// The clock delta in whole frames at one rate.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct DeltaFrames {
+    /// As in `frame_rate`, e.g. `29.97fps`.
+    pub frame_rate: String,
+    pub frames: i64,
+}
+
+/// `delta_ms` in whole frames at `rate`, rounded to the nearest frame.
+pub fn delta_in_frames(delta_ms: i64, rate: Ratio<i64>) -> i64 {
+    (Ratio::new(delta_ms, 1) * rate / Ratio::new(1000, 1)).round().to_integer()
+}
+
+/// `delta_ms` in frames at the incoming rate, if there is LTC, and then at
+/// the secondary display rate, if one is configured.
+pub fn delta_frames_by_rate(
+    delta_ms: i64,
+    incoming: Option<Ratio<i64>>,
+    secondary: Option<Ratio<i64>>,
+) -> Vec<DeltaFrames> {
+    incoming
+        .into_iter()
+        .chain(secondary)
+        .map(|rate| DeltaFrames { frame_rate: frame_rate_label(rate), frames: delta_in_frames(delta_ms, rate) })
+        .collect()
+}
+
+/// Duration of one frame at `rate`, in ms.
+pub fn frame_duration_ms(rate: Ratio<i64>) -> f64 {
+    (Ratio::new(1000, 1) / rate).to_f64().unwrap_or(40.0)
+}
+
+/// The realtime and monotonic clocks read together, so a realtime stamp can
+/// be carried across later steps and slews of the system clock.
+#[derive(Clone, Copy, Debug)]
+pub struct ClockPair {
+    pub real: DateTime<Utc>,
+    pub mono: Instant,
+}
+
+impl ClockPair {
+    pub fn now() -> Self {
+        let mono = Instant::now();
+        Self { real: Utc::now(), mono }
+    }
+
+    /// The same pair `by` earlier, e.g. to take off a reader's latency.
+    pub fn earlier(self, by: chrono::Duration) -> Self {
+        Self {
+            real: self.real - by,
+            mono: by.to_std().ok().and_then(|by| self.mono.checked_sub(by)).unwrap_or(self.mono),
+        }
+    }
+
+    /// What the realtime clock, as it reads at this pair, read at `mono`.
+    pub fn real_at(&self, mono: Instant) -> DateTime<Utc> {
+        let elapsed = self.mono.saturating_duration_since(mono);
+        self.real - chrono::Duration::from_std(elapsed).unwrap_or_default()
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct LtcFrame {
     pub status: String,
@@ -27,41 +153,618 @@ pub struct LtcFrame {
     pub is_drop_frame: bool,
     pub frame_rate: Ratio<i64>,
     pub timestamp: DateTime<Utc>, // arrival stamp
+    /// Monotonic stamp taken with `timestamp` as the frame was read; `None`
+    /// for frames made up rather than received, such as in tests.
+    pub received: Option<Instant>,
 }
 
 impl LtcFrame {
-    pub fn from_regex(caps: &Captures, timestamp: DateTime<Utc>) -> Option<Self> {
+    /// A frame from the named groups of `serial_input::LinePattern`.
+    pub fn from_regex(caps: &Captures, arrival: ClockPair) -> Option<Self> {
+        let is_drop_frame = caps.name("sep").is_some_and(|sep| sep.as_str() == ";");
+        let frame = Self {
+            status: caps["status"].to_string(),
+            hours: caps["hours"].parse().ok()?,
+            minutes: caps["minutes"].parse().ok()?,
+            seconds: caps["seconds"].parse().ok()?,
+            is_drop_frame,
+            frames: caps["frames"].parse().ok()?,
+            frame_rate: get_frame_rate_ratio(&caps["fps"], is_drop_frame)?,
+            timestamp: arrival.real,
+            received: Some(arrival.mono),
+        };
+        let valid = frame.hours < 24
+            && is_valid_label(frame.minutes, frame.seconds, frame.frames, frame.frame_rate, is_drop_frame);
+        valid.then_some(frame)
+    }
+
+    /// The frame's timecode label.
+    pub fn label(&self) -> Timecode {
+        Timecode {
+            hours: self.hours,
+            minutes: self.minutes,
+            seconds: self.seconds,
+            frames: self.frames,
+            rate: self.frame_rate,
+            drop_frame: self.is_drop_frame,
+        }
+    }
+
+    /// The label as the reader prints it, `;` before the frames for
+    /// drop-frame.
+    pub fn timecode(&self) -> String {
+        self.label().to_string()
+    }
+
+    /// When the frame arrived, on the realtime clock as it reads at `now`:
+    /// `timestamp` moved by any step or slew since it was received, so a
+    /// frame queued across a clock change is measured against the new
+    /// clock.
+    pub fn arrival(&self, now: &ClockPair) -> DateTime<Utc> {
+        self.received.map_or(self.timestamp, |received| now.real_at(received))
+    }
+
+    /// Position of this frame within the day, in milliseconds.
+    pub fn timecode_ms(&self) -> i64 {
+        self.label().time_of_day_ms()
+    }
+
+    /// Compare just HH:MM:SS against the system clock read in `zone`.
+    pub fn matches_system_time(&self, zone: &LtcZone) -> bool {
+        let now = zone.now();
+        now.hour() == self.hours && now.minute() == self.minutes && now.second() == self.seconds
+    }
+}
+
+/// Where the session timecode started: the LTC position of the first LOCK
+/// frame and the monotonic instant it was processed.
+#[derive(Clone, Debug)]
+pub struct SessionAnchor {
+    pub instant: Instant,
+    pub timecode_ms: i64,
+    pub frame_rate: Ratio<i64>,
+    pub is_drop_frame: bool,
+}
+
+impl SessionAnchor {
+    /// Session timecode now, from the monotonic time since the anchor.
+    pub fn timecode_now(&self) -> String {
+        self.timecode_after(self.instant.elapsed().as_millis() as i64)
+    }
+
+    /// Session timecode after `elapsed_ms` of monotonic time, wrapping at 24h.
+    /// Frames are counted as real time, like the rest of the sync logic.
+    pub fn timecode_after(&self, elapsed_ms: i64) -> String {
+        Timecode::at_time_of_day_ms(self.timecode_ms + elapsed_ms, self.frame_rate, self.is_drop_frame).to_string()
+    }
+}
+
+/// Long-term drift of the system clock against LTC, from a least-squares fit
+/// of the smoothed delta over time. Slews applied in the meantime are added
+/// back out, so the fit sees the clock's free-running rate; a step starts the
+/// fit over.
+#[derive(Debug, Default)]
+pub struct DriftEstimator {
+    start: Option<Instant>,
+    /// Seconds since `start` and delta in ms with applied slews removed.
+    samples: VecDeque<(f64, f64)>,
+    /// Total applied slew, in ms.
+    slewed_ms: f64,
+}
+
+impl DriftEstimator {
+    /// Add a smoothed delta measured at `at`; at most one sample a second is kept.
+    pub fn sample(&mut self, at: Instant, delta_ms: f64) {
+        let start = *self.start.get_or_insert(at);
+        let t = at.saturating_duration_since(start).as_secs_f64();
+        if self.samples.back().is_some_and(|(last, _)| t - last < 1.0) {
+            return;
+        }
+        if self.samples.len() == DRIFT_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((t, delta_ms - self.slewed_ms));
+    }
+
+    /// Record a slew of `microseconds`, which moves the delta by the same amount.
+    pub fn note_slew(&mut self, microseconds: i64) {
+        self.slewed_ms += microseconds as f64 / 1000.0;
+    }
+
+    /// Forget everything after the clock is stepped.
+    pub fn note_step(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Drift in ppm (positive: the clock gains on LTC), once enough history
+    /// has been collected.
+    pub fn ppm(&self) -> Option<f64> {
+        let (first, last) = (self.samples.front()?, self.samples.back()?);
+        if last.0 - first.0 < DRIFT_MIN_SPAN.as_secs_f64() {
+            return None;
+        }
+        let n = self.samples.len() as f64;
+        let mean_t = self.samples.iter().map(|(t, _)| t).sum::<f64>() / n;
+        let mean_d = self.samples.iter().map(|(_, d)| d).sum::<f64>() / n;
+        let (mut cov, mut var) = (0.0, 0.0);
+        for (t, d) in &self.samples {
+            cov += (t - mean_t) * (d - mean_d);
+            var += (t - mean_t) * (t - mean_t);
+        }
+        // ms per second is parts per thousand.
+        Some(cov / var * 1000.0)
+    }
+}
+
+/// Sub-frame phase of the LTC against the system clock. Each frame's arrival
+/// time is late by a varying amount of serial and scheduling latency, so a
+/// single frame's delta is only good to a few ms. Fitting a line through the
+/// deltas of consecutive frames averages that out and tracks slews; the
+/// constant part of the latency is left to `hardwareOffsetMs`.
+#[derive(Debug, Default)]
+pub struct PhaseEstimator {
+    /// Target time of the first frame in the fit, in µs since the epoch.
+    origin_us: i64,
+    /// Target time in ms since `origin_us` and measured delta in ms.
+    samples: VecDeque<(f64, f64)>,
+    /// Target time of the last frame, whether it was fitted or not.
+    last_x: Option<f64>,
+    /// Frames in a row that were off the fit and left out of it.
+    misfits: u32,
+}
+
+impl PhaseEstimator {
+    /// Add one LOCK frame and return its delta estimated from the fit, in ms.
+    /// A frame that does not follow on from the last (timecode jump) starts
+    /// the fit over. One off the fit by a frame or more is left out of it,
+    /// unless `PHASE_MAX_MISFITS` arrive in a row (clock step, changed
+    /// offset), which starts the fit over too.
+    pub fn sample(&mut self, target_us: i64, delta_us: i64, frame_ms: f64) -> f64 {
+        let mut x = (target_us - self.origin_us) as f64 / 1000.0;
+        let y = delta_us as f64 / 1000.0;
+        let continues = self.last_x.is_some_and(|last| {
+            let gap = x - last;
+            gap > 0.0 && gap < 2.5 * frame_ms
+        });
+        let misfit = self.fit().filter(|(a, b)| (a + b * x - y).abs() >= frame_ms);
+        if let (true, Some((a, b))) = (continues, misfit) {
+            self.misfits += 1;
+            if self.misfits < PHASE_MAX_MISFITS {
+                self.last_x = Some(x);
+                return a + b * x;
+            }
+        }
+        if !continues || misfit.is_some() {
+            self.reset();
+            self.origin_us = target_us;
+            x = 0.0;
+        }
+        self.misfits = 0;
+        self.last_x = Some(x);
+        if self.samples.len() == PHASE_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((x, y));
+        match self.fit() {
+            Some((a, b)) => a + b * x,
+            None => y,
+        }
+    }
+
+    /// Forget the fit, e.g. when LOCK is lost.
+    pub fn reset(&mut self) {
+        self.samples.clear();
+        self.last_x = None;
+        self.misfits = 0;
+    }
+
+    /// Least-squares intercept and slope of delta over target time, once
+    /// there are enough frames.
+    fn fit(&self) -> Option<(f64, f64)> {
+        if self.samples.len() < PHASE_MIN_FRAMES {
+            return None;
+        }
+        let n = self.samples.len() as f64;
+        let mean_x = self.samples.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = self.samples.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let (mut cov, mut var) = (0.0, 0.0);
+        for (x, y) in &self.samples {
+            cov += (x - mean_x) * (y - mean_y);
+            var += (x - mean_x) * (x - mean_x);
+        }
+        let slope = if var > 0.0 { cov / var } else { 0.0 };
+        Some((mean_y - slope * mean_x, slope))
+    }
+}
+
+/// Frames of continuity history behind the score: a minute at 25 fps.
+const CONTINUITY_WINDOW: usize = 1500;
+
+/// How a frame failed to follow on from the one before.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscontinuityKind {
+    /// Frames were missed: the timecode jumped forward.
+    Skip,
+    /// The same label arrived twice.
+    Repeat,
+    /// The timecode went backwards.
+    Reversal,
+}
+
+impl DiscontinuityKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DiscontinuityKind::Skip => "skip",
+            DiscontinuityKind::Repeat => "repeat",
+            DiscontinuityKind::Reversal => "reversal",
+        }
+    }
+}
+
+/// One frame that was not the one expected.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct Discontinuity {
+    pub kind: DiscontinuityKind,
+    pub expected: String,
+    pub got: String,
+    /// How far `got` is from `expected`, in frames.
+    pub frames: i64,
+    #[schema(value_type = String, format = DateTime)]
+    pub at: DateTime<Utc>,
+}
+
+impl std::fmt::Display for Discontinuity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} to {} ({:+} frames), expected {}", self.kind.as_str(), self.got, self.frames, self.expected)
+    }
+}
+
+/// Timecode continuity over the last `CONTINUITY_WINDOW` frames, as
+/// reported in `/api/status`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, ToSchema)]
+pub struct ContinuityReport {
+    /// Percentage of frames in the window that followed on from the one
+    /// before; `None` before the second frame.
+    pub score: Option<f64>,
+    /// Frames checked in the window.
+    pub frames: usize,
+    pub skips: usize,
+    pub repeats: usize,
+    pub reversals: usize,
+    /// Discontinuities since startup.
+    pub total: u64,
+    pub last: Option<Discontinuity>,
+}
+
+/// Checks each frame is the one after the last, whatever its LOCK or FREE
+/// status, and keeps a window of the results. A change of frame rate or a
+/// lost signal starts over without counting a break.
+#[derive(Debug, Default)]
+pub struct ContinuityChecker {
+    /// The last frame's label.
+    last: Option<Timecode>,
+    /// One entry per checked frame: `None` if it followed on.
+    window: VecDeque<Option<DiscontinuityKind>>,
+    /// Frames in the current unbroken run at one rate, the latest included.
+    run: u32,
+    total: u64,
+    last_break: Option<Discontinuity>,
+}
+
+impl ContinuityChecker {
+    /// Check `frame` against the one expected after the last, and return the
+    /// discontinuity if it was not.
+    pub fn check(&mut self, frame: &LtcFrame) -> Option<Discontinuity> {
+        let label = frame.label();
+        let last = match self.last.replace(label) {
+            Some(last) if last.rate == label.rate && last.drop_frame == label.drop_frame => last,
+            _ => {
+                self.run = 1;
+                return None;
+            }
+        };
+        let expected = last.add_frames(1);
+        // Signed distance, so running through midnight is not a reversal.
+        let frames = expected.frames_to(&label);
+        let kind = match frames {
+            0 => None,
+            -1 => Some(DiscontinuityKind::Repeat),
+            f if f < 0 => Some(DiscontinuityKind::Reversal),
+            _ => Some(DiscontinuityKind::Skip),
+        };
+        if self.window.len() == CONTINUITY_WINDOW {
+            self.window.pop_front();
+        }
+        self.window.push_back(kind);
+        self.run = if kind.is_some() { 1 } else { self.run.saturating_add(1) };
+        let discontinuity = Discontinuity {
+            kind: kind?,
+            expected: expected.to_string(),
+            got: frame.timecode(),
+            frames,
+            at: frame.timestamp,
+        };
+        self.total += 1;
+        self.last_break = Some(discontinuity.clone());
+        Some(discontinuity)
+    }
+
+    /// Forget the last frame, so the next is not checked against it.
+    pub fn restart(&mut self) {
+        self.last = None;
+        self.run = 0;
+    }
+
+    /// Consecutive frames, the latest included, each following on from the
+    /// one before at the same rate.
+    pub fn run(&self) -> u32 {
+        self.run
+    }
+
+    pub fn report(&self) -> ContinuityReport {
+        let count = |kind| self.window.iter().filter(|k| **k == Some(kind)).count();
+        let continuous = self.window.iter().filter(|k| k.is_none()).count();
+        ContinuityReport {
+            score: (!self.window.is_empty())
+                .then(|| (continuous as f64 / self.window.len() as f64 * 1000.0).round() / 10.0),
+            frames: self.window.len(),
+            skips: count(DiscontinuityKind::Skip),
+            repeats: count(DiscontinuityKind::Repeat),
+            reversals: count(DiscontinuityKind::Reversal),
+            total: self.total,
+            last: self.last_break.clone(),
+        }
+    }
+}
+
+/// Spread of the jitter samples, in ms. The mean alone hides bursty glitches.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+pub struct JitterStats {
+    pub samples: usize,
+    pub mean_ms: f64,
+    pub stddev_ms: f64,
+    pub p95_ms: i64,
+    pub min_ms: i64,
+    pub max_ms: i64,
+}
+
+impl JitterStats {
+    /// Statistics over `samples`, or `None` if there are none.
+    pub fn from_samples<'a>(samples: impl IntoIterator<Item = &'a i64>) -> Option<Self> {
+        let mut sorted: Vec<i64> = samples.into_iter().copied().collect();
+        if sorted.is_empty() {
+            return None;
+        }
+        sorted.sort_unstable();
+        let n = sorted.len();
+        let mean = sorted.iter().sum::<i64>() as f64 / n as f64;
+        let variance = sorted.iter().map(|&v| (v as f64 - mean).powi(2)).sum::<f64>() / n as f64;
+        // Nearest-rank percentile.
+        let p95 = sorted[(n * 95).div_ceil(100) - 1];
         Some(Self {
-            status: caps[1].to_string(),
-            hours: caps[2].parse().ok()?,
-            minutes: caps[3].parse().ok()?,
-            seconds: caps[4].parse().ok()?,
-            is_drop_frame: &caps[5] == ";",
-            frames: caps[6].parse().ok()?,
-            frame_rate: get_frame_rate_ratio(&caps[7])?,
-            timestamp,
+            samples: n,
+            mean_ms: (mean * 10.0).round() / 10.0,
+            stddev_ms: (variance.sqrt() * 10.0).round() / 10.0,
+            p95_ms: p95,
+            min_ms: sorted[0],
+            max_ms: sorted[n - 1],
         })
     }
+}
+
+impl std::fmt::Display for JitterStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "mean {} ms, σ {} ms, p95 {} ms, min {} / max {} ms",
+            self.mean_ms, self.stddev_ms, self.p95_ms, self.min_ms, self.max_ms
+        )
+    }
+}
+
+/// `ltc_status` while no frame has arrived for `signalTimeoutMs`.
+pub const NO_SIGNAL: &str = "NO SIGNAL";
+
+/// Frame periods without a frame before LTC counts as STALE.
+const STALE_AFTER_FRAMES: f64 = 5.0;
+
+/// How recently LTC was heard from, by the age of the latest frame. Tells a
+/// feed that has stopped apart from one that is still arriving, LOCK or FREE.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, ToSchema)]
+pub enum Staleness {
+    /// Frames are arriving on time.
+    #[default]
+    #[serde(rename = "LIVE")]
+    Live,
+    /// Frames are late: more than a few frame periods since the last one.
+    #[serde(rename = "STALE")]
+    Stale,
+    /// No frame for `signalTimeoutMs`, or none yet.
+    #[serde(rename = "NO SIGNAL")]
+    NoSignal,
+}
+
+impl Staleness {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Staleness::Live => "LIVE",
+            Staleness::Stale => "STALE",
+            Staleness::NoSignal => NO_SIGNAL,
+        }
+    }
+}
 
-    /// Compare just HH:MM:SS against local time.
-    pub fn matches_system_time(&self) -> bool {
-        let local = Local::now();
-        local.hour() == self.hours
-            && local.minute() == self.minutes
-            && local.second() == self.seconds
+/// Classify a frame `age` at `frame_rate`: STALE once it exceeds
+/// `STALE_AFTER_FRAMES` frame periods, NO SIGNAL from `signalTimeoutMs`.
+pub fn staleness(age: Option<Duration>, frame_rate: Option<Ratio<i64>>, config: &Config) -> Staleness {
+    let Some(age) = age else {
+        return Staleness::NoSignal;
+    };
+    if age >= Duration::from_millis(config.signal_timeout_ms) {
+        return Staleness::NoSignal;
+    }
+    let period = frame_rate
+        .and_then(|rate| rate.recip().to_f64())
+        .unwrap_or(1.0 / 25.0);
+    if age.as_secs_f64() > period * STALE_AFTER_FRAMES {
+        Staleness::Stale
+    } else {
+        Staleness::Live
     }
 }
 
+/// What readers see of `LtcState`: an immutable copy published by the frame
+/// processor after every change, so the API and background threads never
+/// wait on the processing path.
+#[derive(Clone, Debug)]
+pub struct LtcSnapshot {
+    pub latest: Option<LtcFrame>,
+    pub lock_count: u32,
+    pub free_count: u32,
+    pub ewma_clock_delta: Option<f64>,
+    pub frame_delta_ms: Option<f64>,
+    pub average_jitter: i64,
+    pub jitter: Option<JitterStats>,
+    pub last_match_status: String,
+    pub session_anchor: Option<SessionAnchor>,
+    pub drift_ppm: Option<f64>,
+    pub pipeline: PipelineReport,
+    pub signal_lost: bool,
+    pub last_frame_at: Option<Instant>,
+    pub continuity: ContinuityReport,
+    pub consistent_frames: u32,
+    pub frame_rate_mismatch: bool,
+}
+
+impl LtcSnapshot {
+    /// The latest frame is LOCK and frames are still arriving.
+    pub fn locked(&self) -> bool {
+        !self.signal_lost && self.latest.as_ref().is_some_and(|f| f.status == "LOCK")
+    }
+
+    /// `LOCK` or `FREE` from the latest frame, or `NO SIGNAL` once frames
+    /// have stopped; `None` before the first one.
+    pub fn ltc_status(&self) -> Option<&str> {
+        match &self.latest {
+            Some(_) if self.signal_lost => Some(NO_SIGNAL),
+            latest => latest.as_ref().map(|f| f.status.as_str()),
+        }
+    }
+
+    /// Time since the latest frame arrived, as of now.
+    pub fn frame_age(&self) -> Option<Duration> {
+        self.last_frame_at.map(|at| at.elapsed())
+    }
+
+    /// Whether frames are still arriving; NO SIGNAL once the processor has
+    /// marked the signal lost, even if it has not yet timed out here.
+    pub fn staleness(&self, config: &Config) -> Staleness {
+        if self.signal_lost {
+            return Staleness::NoSignal;
+        }
+        staleness(self.frame_age(), self.latest.as_ref().map(|f| f.frame_rate), config)
+    }
+
+    /// Get EWMA of clock delta, in ms.
+    pub fn get_ewma_clock_delta(&self) -> i64 {
+        round_delta(self.ewma_clock_delta)
+    }
+
+    /// Percentage of samples seen in LOCK state versus total.
+    pub fn lock_ratio(&self) -> f64 {
+        lock_percentage(self.lock_count, self.free_count)
+    }
+
+    /// Monotonic timecode counted from the first LOCK, or `None` before it.
+    pub fn session_timecode(&self) -> Option<String> {
+        self.session_anchor.as_ref().map(SessionAnchor::timecode_now)
+    }
+}
+
+/// Read side of an `LtcState`: the latest snapshot, cheap to clone and read
+/// without touching the state's mutex.
+#[derive(Clone)]
+pub struct LtcView(watch::Receiver<Arc<LtcSnapshot>>);
+
+impl LtcView {
+    /// The most recently published snapshot.
+    pub fn get(&self) -> Arc<LtcSnapshot> {
+        self.0.borrow().clone()
+    }
+
+    /// A receiver that is marked changed whenever a snapshot is published,
+    /// so observers can render on change instead of polling.
+    pub fn subscribe(&self) -> watch::Receiver<Arc<LtcSnapshot>> {
+        let mut rx = self.0.clone();
+        rx.mark_unchanged();
+        rx
+    }
+}
+
+fn round_delta(ewma: Option<f64>) -> i64 {
+    ewma.map_or(0, |v| v.round() as i64)
+}
+
+fn lock_percentage(lock_count: u32, free_count: u32) -> f64 {
+    let total = lock_count + free_count;
+    if total == 0 {
+        0.0
+    } else {
+        lock_count as f64 / total as f64 * 100.0
+    }
+}
+
+/// Writer-owned sync state. Only the frame processor and clock commands lock
+/// it; everything else reads the snapshots it publishes (see `view`).
 pub struct LtcState {
     pub latest: Option<LtcFrame>,
     pub lock_count: u32,
     pub free_count: u32,
-    /// Stores the last up-to-20 raw offset measurements in ms.
+    /// Stores the last up-to-`jitter_window` jitter measurements in ms: each
+    /// LOCK frame's distance from the fitted phase.
     pub offset_history: VecDeque<i64>,
+    /// Number of jitter samples kept, from `jitterWindow` in config.
+    pub jitter_window: usize,
+    /// What clock the LTC counts, from `ltcTimeZone` in config.
+    pub ltc_zone: LtcZone,
     /// EWMA of clock delta.
     pub ewma_clock_delta: Option<f64>,
+    /// The latest LOCK frame's own delta in ms, before the phase fit and
+    /// EWMA.
+    pub frame_delta_ms: Option<f64>,
     pub last_match_status: String,
     pub last_match_check: i64,
+    /// Set on the first LOCK and kept for the life of the process, so the
+    /// session timecode never jumps when the wall clock is stepped.
+    pub session_anchor: Option<SessionAnchor>,
+    pub drift: DriftEstimator,
+    pub phase: PhaseEstimator,
+    /// Frame queue backpressure, copied in by the processor.
+    pub pipeline: PipelineReport,
+    /// No frame has arrived for `signalTimeoutMs`: the reader or the feed
+    /// has gone quiet. `latest` is kept but no longer counts as LOCK.
+    pub signal_lost: bool,
+    /// When `latest` arrived, for its age; see `LtcSnapshot::staleness`.
+    pub last_frame_at: Option<Instant>,
+    /// Skips, repeats and reversals in the timecode, checked by `update`.
+    pub continuity: ContinuityChecker,
+    /// Consecutive LOCK frames in the current continuous run; auto-sync and
+    /// scheduled steps only step once there are `stepConsistentFrames`.
+    pub consistent_frames: u32,
+    /// The frames have been at a rate other than `expectedFrameRate` for
+    /// longer than `processor::FRAME_RATE_MISMATCH_FRAMES`.
+    pub frame_rate_mismatch: bool,
+    /// Where snapshots are published; see `publish` and `view`.
+    pub published: watch::Sender<Arc<LtcSnapshot>>,
+    /// Steps waiting for the next second boundary; see `arm_second`.
+    pub second_waiters: Vec<mpsc::Sender<LtcFrame>>,
+}
+
+impl Default for LtcState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl LtcState {
@@ -71,15 +774,105 @@ impl LtcState {
             lock_count: 0,
             free_count: 0,
             offset_history: VecDeque::with_capacity(20),
+            jitter_window: 20,
+            ltc_zone: LtcZone::Local,
             ewma_clock_delta: None,
+            frame_delta_ms: None,
             last_match_status: "UNKNOWN".into(),
             last_match_check: 0,
+            session_anchor: None,
+            drift: DriftEstimator::default(),
+            phase: PhaseEstimator::default(),
+            pipeline: PipelineReport::default(),
+            signal_lost: false,
+            last_frame_at: None,
+            continuity: ContinuityChecker::default(),
+            consistent_frames: 0,
+            frame_rate_mismatch: false,
+            published: watch::Sender::new(Arc::new(LtcSnapshot {
+                latest: None,
+                lock_count: 0,
+                free_count: 0,
+                ewma_clock_delta: None,
+                frame_delta_ms: None,
+                average_jitter: 0,
+                jitter: None,
+                last_match_status: "UNKNOWN".into(),
+                session_anchor: None,
+                drift_ppm: None,
+                pipeline: PipelineReport::default(),
+                signal_lost: false,
+                last_frame_at: None,
+                continuity: ContinuityReport::default(),
+                consistent_frames: 0,
+                frame_rate_mismatch: false,
+            })),
+            second_waiters: Vec::new(),
+        }
+    }
+
+    /// Copy out what readers need.
+    pub fn snapshot(&self) -> LtcSnapshot {
+        LtcSnapshot {
+            latest: self.latest.clone(),
+            lock_count: self.lock_count,
+            free_count: self.free_count,
+            ewma_clock_delta: self.ewma_clock_delta,
+            frame_delta_ms: self.frame_delta_ms,
+            average_jitter: self.average_jitter(),
+            jitter: self.jitter_stats(),
+            last_match_status: self.last_match_status.clone(),
+            session_anchor: self.session_anchor.clone(),
+            drift_ppm: self.drift.ppm(),
+            pipeline: self.pipeline.clone(),
+            signal_lost: self.signal_lost,
+            last_frame_at: self.last_frame_at,
+            continuity: self.continuity.report(),
+            consistent_frames: self.consistent_frames,
+            frame_rate_mismatch: self.frame_rate_mismatch,
         }
     }
 
+    /// Publish the current state to every `LtcView`. `update` does this for
+    /// each frame; call it after any other change readers should see.
+    pub fn publish(&self) {
+        self.published.send_replace(Arc::new(self.snapshot()));
+    }
+
+    /// A read handle on the published snapshots.
+    pub fn view(&self) -> LtcView {
+        LtcView(self.published.subscribe())
+    }
+
+    /// Record a successful clock step, start the phase fit over and publish
+    /// the reset drift estimate.
+    pub fn note_step(&mut self) {
+        self.phase.reset();
+        self.drift.note_step();
+        self.publish();
+    }
+
+    /// Forget every delta filter after the clock was stepped by someone
+    /// else, so the next frames measure the new offset directly instead of
+    /// ramping the EWMA towards it, and publish.
+    pub fn note_external_step(&mut self) {
+        self.ewma_clock_delta = None;
+        self.frame_delta_ms = None;
+        self.clear_offsets();
+        self.phase.reset();
+        self.drift.note_step();
+        self.publish();
+    }
+
+    /// Record a successful slew of `microseconds` and publish.
+    pub fn note_slew(&mut self, microseconds: i64) {
+        self.drift.note_slew(microseconds);
+        self.publish();
+    }
+
     /// Record one measured jitter offset in ms.
     pub fn record_offset(&mut self, offset_ms: i64) {
-        if self.offset_history.len() == 20 {
+        while self.offset_history.len() >= self.jitter_window.max(1) {
             self.offset_history.pop_front();
         }
         self.offset_history.push_back(offset_ms);
@@ -87,7 +880,11 @@ impl LtcState {
 
     /// Update EWMA of clock delta.
     pub fn record_and_update_ewma_clock_delta(&mut self, delta_ms: i64) {
-        let new_delta = delta_ms as f64;
+        self.record_clock_delta(delta_ms as f64);
+    }
+
+    /// Update EWMA of clock delta with a sub-millisecond measurement.
+    pub fn record_clock_delta(&mut self, new_delta: f64) {
         if let Some(current_ewma) = self.ewma_clock_delta {
             self.ewma_clock_delta = Some(EWMA_ALPHA * new_delta + (1.0 - EWMA_ALPHA) * current_ewma);
         } else {
@@ -100,16 +897,40 @@ impl LtcState {
         self.offset_history.clear();
     }
 
-    /// Update LOCK/FREE counts and timecode-match status every 5 s.
-    pub fn update(&mut self, frame: LtcFrame) {
+    /// A receiver for the next LOCK frame 00, the start of a new second of
+    /// LTC, sent as soon as `update` sees it.
+    pub fn arm_second(&mut self) -> mpsc::Receiver<LtcFrame> {
+        let (tx, rx) = mpsc::channel();
+        self.second_waiters.push(tx);
+        rx
+    }
+
+    /// Update LOCK/FREE counts and timecode-match status every 5 s, check
+    /// continuity and publish. Returns the discontinuity if the frame did not
+    /// follow on from the last.
+    pub fn update(&mut self, frame: LtcFrame) -> Option<Discontinuity> {
+        self.signal_lost = false;
+        let discontinuity = self.continuity.check(&frame);
+        self.consistent_frames = match frame.status.as_str() {
+            "LOCK" => self.consistent_frames.saturating_add(1).min(self.continuity.run()),
+            _ => 0,
+        };
         match frame.status.as_str() {
             "LOCK" => {
                 self.lock_count += 1;
+                if self.session_anchor.is_none() {
+                    self.session_anchor = Some(SessionAnchor {
+                        instant: Instant::now(),
+                        timecode_ms: frame.timecode_ms(),
+                        frame_rate: frame.frame_rate,
+                        is_drop_frame: frame.is_drop_frame,
+                    });
+                }
 
                 // Recompute timecode-match every 5 seconds
                 let now_secs = Utc::now().timestamp();
                 if now_secs - self.last_match_check >= 5 {
-                    self.last_match_status = if frame.matches_system_time() {
+                    self.last_match_status = if frame.matches_system_time(&self.ltc_zone) {
                         "IN SYNC"
                     } else {
                         "OUT OF SYNC"
@@ -117,17 +938,28 @@ impl LtcState {
                     .into();
                     self.last_match_check = now_secs;
                 }
+
+                if frame.frames == 0 {
+                    for waiter in self.second_waiters.drain(..) {
+                        let _ = waiter.send(frame.clone());
+                    }
+                }
             }
             "FREE" => {
                 self.free_count += 1;
                 self.clear_offsets();
                 self.ewma_clock_delta = None;
+                self.frame_delta_ms = None;
+                self.phase.reset();
                 self.last_match_status = "UNKNOWN".into();
             }
             _ => {}
         }
 
         self.latest = Some(frame);
+        self.last_frame_at = Some(Instant::now());
+        self.publish();
+        discontinuity
     }
 
     /// Average jitter over stored history, in ms.
@@ -140,6 +972,11 @@ impl LtcState {
         }
     }
 
+    /// Spread of the stored jitter history, or `None` while it is empty.
+    pub fn jitter_stats(&self) -> Option<JitterStats> {
+        JitterStats::from_samples(&self.offset_history)
+    }
+
     /// Convert average jitter into frames (rounded).
     pub fn average_frames(&self) -> i64 {
         if let Some(frame) = &self.latest {
@@ -153,17 +990,17 @@ impl LtcState {
 
     /// Get EWMA of clock delta, in ms.
     pub fn get_ewma_clock_delta(&self) -> i64 {
-        self.ewma_clock_delta.map_or(0, |v| v.round() as i64)
+        round_delta(self.ewma_clock_delta)
     }
 
     /// Percentage of samples seen in LOCK state versus total.
     pub fn lock_ratio(&self) -> f64 {
-        let total = self.lock_count + self.free_count;
-        if total == 0 {
-            0.0
-        } else {
-            self.lock_count as f64 / total as f64 * 100.0
-        }
+        lock_percentage(self.lock_count, self.free_count)
+    }
+
+    /// Monotonic timecode counted from the first LOCK, or `None` before it.
+    pub fn session_timecode(&self) -> Option<String> {
+        self.session_anchor.as_ref().map(SessionAnchor::timecode_now)
     }
 
     /// Get timecode-match status.
@@ -172,15 +1009,53 @@ impl LtcState {
     }
 }
 
-pub fn get_sync_status(delta_ms: i64, config: &Config) -> &'static str {
-    if config.timeturner_offset.is_active() {
-        "TIMETURNING"
-    } else if delta_ms.abs() <= 8 {
-        "IN SYNC"
-    } else if delta_ms > 10 {
-        "CLOCK AHEAD"
+/// Which side of LTC the system clock is on. Every clock delta in the sync
+/// logic is the system clock minus the LTC target, so positive means ahead.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ClockDirection {
+    #[default]
+    InSync,
+    Ahead,
+    Behind,
+}
+
+impl ClockDirection {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ClockDirection::InSync => "in sync",
+            ClockDirection::Ahead => "ahead",
+            ClockDirection::Behind => "behind",
+        }
+    }
+}
+
+/// Where `delta_ms` falls against the window from `inSyncBehindMs` to
+/// `inSyncAheadMs`, both inclusive.
+pub fn clock_direction(delta_ms: i64, config: &Config) -> ClockDirection {
+    if delta_ms > config.in_sync_ahead_ms {
+        ClockDirection::Ahead
+    } else if delta_ms < config.in_sync_behind_ms {
+        ClockDirection::Behind
     } else {
-        "CLOCK BEHIND"
+        ClockDirection::InSync
+    }
+}
+
+/// The sync verdict for `delta_ms`. STALE or NO SIGNAL when LTC is late or
+/// has stopped, as the delta is then only as old as the last frame, even
+/// with an offset set; otherwise TIMETURNING while an offset is set.
+pub fn get_sync_status(delta_ms: i64, staleness: Staleness, config: &Config) -> &'static str {
+    if staleness != Staleness::Live {
+        return staleness.as_str();
+    }
+    if config.timeturner_offset.is_active() {
+        return "TIMETURNING";
+    }
+    match clock_direction(delta_ms, config) {
+        ClockDirection::InSync => "IN SYNC",
+        ClockDirection::Ahead => "CLOCK AHEAD",
+        ClockDirection::Behind => "CLOCK BEHIND",
     }
 }
 
@@ -193,12 +1068,193 @@ pub fn get_jitter_status(jitter_ms: i64) -> &'static str {
         "BAD"
     }
 }
+
+/// How auto-sync corrects a measured clock delta.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Correction {
+    /// Step the clock to LTC with `date`.
+    Step,
+    /// Slew the clock by this many microseconds with `adjtimex`.
+    Slew(i64),
+    /// Within 1 ms; leave the clock alone.
+    Skip,
+}
+
+/// How often auto-sync re-checks the delta, in seconds.
+pub const AUTO_SYNC_PERIOD_SECS: u64 = 10;
+
+/// The delta in microseconds expected one auto-sync period from now, given
+/// the estimated drift.
+fn projected_delta_us(delta_ms: i64, drift_ppm: Option<f64>) -> i64 {
+    // ppm × seconds = microseconds
+    let drift_us = drift_ppm.unwrap_or(0.0) * AUTO_SYNC_PERIOD_SECS as f64;
+    delta_ms * 1000 + drift_us.round() as i64
+}
+
+/// Step when the delta projected one period ahead by the drift exceeds
+/// `stepThresholdMs`, otherwise slew it away, so a fast-drifting clock is
+/// stepped before it crosses the threshold and slews cover the drift too.
+/// A positive delta means the clock is ahead, so it is slewed backwards.
+pub fn choose_correction(delta_ms: i64, drift_ppm: Option<f64>, config: &Config) -> Correction {
+    let projected_us = projected_delta_us(delta_ms, drift_ppm);
+    if projected_us.abs() > config.step_threshold_ms * 1000 {
+        Correction::Step
+    } else if projected_us.abs() >= 1000 {
+        Correction::Slew(-projected_us)
+    } else {
+        Correction::Skip
+    }
+}
+
+/// While LTC is missing, slew away one period's worth of estimated drift so
+/// the clock holds its rate. `Skip` when there is no estimate.
+pub fn holdover_correction(drift_ppm: Option<f64>) -> Correction {
+    match projected_delta_us(0, drift_ppm) {
+        0 => Correction::Skip,
+        us => Correction::Slew(-us),
+    }
+}
+/// How long after a step auto-sync leaves the clock alone; one auto-sync period.
+pub const STEP_SETTLE_SECS: i64 = 10;
+
+/// How long either side of a possible leap second the discipline holds off.
+pub const LEAP_GUARD_SECS: u32 = 10;
+
+/// Whether `at` is within `LEAP_GUARD_SECS` of the end of 30 June or
+/// 31 December UTC, the only places a leap second can be inserted or
+/// deleted. LTC has no 23:59:60, and the kernel or chrony may be stepping
+/// or smearing the clock, so deltas measured there are not to be trusted.
+pub fn in_leap_window(at: DateTime<Utc>) -> bool {
+    let secs = at.num_seconds_from_midnight();
+    match (at.month(), at.day()) {
+        (6, 30) | (12, 31) => secs >= 86_400 - LEAP_GUARD_SECS,
+        (7, 1) | (1, 1) => secs < LEAP_GUARD_SECS,
+        _ => false,
+    }
+}
+
+/// The source auto-sync is following now: LTC, holdover or manual, or
+/// `None` while auto-sync is off or has nothing to follow.
+pub fn active_source(state: &LtcSnapshot, config: &Config) -> Option<SyncSource> {
+    if !config.auto_sync_enabled {
+        return None;
+    }
+    let locked = state.locked() && state.ewma_clock_delta.is_some();
+    match config.sync_source {
+        SyncSource::Auto | SyncSource::Ltc if locked => Some(SyncSource::Ltc),
+        SyncSource::Auto => state.drift_ppm.map(|_| SyncSource::Holdover),
+        SyncSource::Ltc => None,
+        pinned => Some(pinned),
+    }
+}
+
+/// Plain-language account of what the sync engine is doing and why, for
+/// operators. `secs_since_step` is the age of the last successful step.
+pub fn explain(state: &LtcSnapshot, config: &Config, secs_since_step: Option<i64>) -> String {
+    let Some(frame) = &state.latest else {
+        return "waiting for LTC from the reader".into();
+    };
+    if config.auto_sync_enabled {
+        match (config.sync_source, state.drift_ppm) {
+            (SyncSource::Manual, _) => {
+                return "manual: sync source pinned to manual, auto-sync makes no corrections".into()
+            }
+            (SyncSource::Holdover, Some(ppm)) => {
+                return format!("holdover (pinned): compensating {:+.1} ppm drift, ignoring LTC", ppm)
+            }
+            (SyncSource::Holdover, None) => {
+                return "holdover (pinned): no drift estimate yet, ignoring LTC".into()
+            }
+            _ => {}
+        }
+    }
+    if state.signal_lost {
+        return match state.drift_ppm {
+            Some(ppm) if config.auto_sync_enabled && config.sync_source == SyncSource::Auto => format!(
+                "holdover: no LTC from the reader, compensating {:+.1} ppm drift until it returns",
+                ppm
+            ),
+            _ => "holding corrections: no LTC from the reader".into(),
+        };
+    }
+    if frame.status != "LOCK" || state.ewma_clock_delta.is_none() {
+        return match state.drift_ppm {
+            Some(ppm) if config.auto_sync_enabled && config.sync_source == SyncSource::Auto => format!(
+                "holdover: LTC is free-running, compensating {:+.1} ppm drift until LOCK",
+                ppm
+            ),
+            _ => "holding corrections: LTC is free-running, waiting for LOCK".into(),
+        };
+    }
+    if config.auto_sync_enabled && in_leap_window(frame.timestamp) {
+        return "holding corrections: a leap second may be applied at midnight UTC".into();
+    }
+    let delta = state.get_ewma_clock_delta();
+    let prefix = if config.timeturner_offset.is_active() {
+        "timeturning with the configured offset; "
+    } else {
+        ""
+    };
+    let drift = if delta > 0 { "ahead" } else { "behind" };
+    if !config.auto_sync_enabled {
+        return if delta == 0 {
+            format!("{}monitor-only: auto-sync is off, clock matches LTC", prefix)
+        } else {
+            format!("{}monitor-only: auto-sync is off, clock is {} ms {}", prefix, delta.abs(), drift)
+        };
+    }
+    if let Some(age) = secs_since_step.filter(|age| *age < STEP_SETTLE_SECS) {
+        return format!(
+            "{}holding corrections: settling for {} s after step",
+            prefix,
+            STEP_SETTLE_SECS - age
+        );
+    }
+    let ppm = state.drift_ppm;
+    let correction = choose_correction(delta, ppm, config);
+    if correction == Correction::Step && config.step_over_limit(delta) {
+        return format!(
+            "{}holding step: clock is {} ms {}, beyond the {} ms maxAutoStepMs; sync manually with the override",
+            prefix,
+            delta.abs(),
+            drift,
+            config.max_auto_step_ms
+        );
+    }
+    match correction {
+        Correction::Step if delta.abs() <= config.step_threshold_ms => format!(
+            "{}stepping: clock is {} ms {} and drifting {:+.1} ppm, projected beyond the {} ms step threshold",
+            prefix,
+            delta.abs(),
+            drift,
+            ppm.unwrap_or(0.0),
+            config.step_threshold_ms
+        ),
+        Correction::Step => format!(
+            "{}stepping: clock is {} ms {}, beyond the {} ms step threshold",
+            prefix,
+            delta.abs(),
+            drift,
+            config.step_threshold_ms
+        ),
+        Correction::Slew(us) => format!(
+            "{}slewing {:+} ms at 0.5 ms/s: within the {} ms step threshold",
+            prefix,
+            us / 1000,
+            config.step_threshold_ms
+        ),
+        Correction::Skip => format!("{}in sync: clock within 1 ms of LTC, no correction needed", prefix),
+    }
+}
+
+
 // This module provides the logic for handling LTC (Linear Timecode) frames and maintaining state.
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::{Config, TimeturnerOffset};
-    use chrono::{Local, Utc};
+    use chrono::{Local, NaiveDate, TimeZone, Utc};
+    use std::sync::Mutex;
 
     fn get_test_frame(status: &str, h: u32, m: u32, s: u32) -> LtcFrame {
         LtcFrame {
@@ -210,6 +1266,7 @@ mod tests {
             is_drop_frame: false,
             frame_rate: Ratio::new(25, 1),
             timestamp: Utc::now(),
+            received: None,
         }
     }
 
@@ -217,7 +1274,7 @@ mod tests {
     fn test_ltc_frame_matches_system_time() {
         let now = Local::now();
         let frame = get_test_frame("LOCK", now.hour(), now.minute(), now.second());
-        assert!(frame.matches_system_time());
+        assert!(frame.matches_system_time(&LtcZone::Local));
     }
 
     #[test]
@@ -226,7 +1283,20 @@ mod tests {
         // Create a time that is one hour ahead, wrapping around 23:00
         let different_hour = (now.hour() + 1) % 24;
         let frame = get_test_frame("LOCK", different_hour, now.minute(), now.second());
-        assert!(!frame.matches_system_time());
+        assert!(!frame.matches_system_time(&LtcZone::Local));
+    }
+
+    #[test]
+    fn test_ltc_frame_matches_system_time_in_zone() {
+        let now = Utc::now();
+        let frame = get_test_frame("LOCK", now.hour(), now.minute(), now.second());
+        assert!(frame.matches_system_time(&LtcZone::Utc));
+
+        let plus_two = "+02:00".parse::<LtcZone>().unwrap();
+        let later = now + chrono::Duration::hours(2);
+        let frame = get_test_frame("LOCK", later.hour(), later.minute(), later.second());
+        assert!(frame.matches_system_time(&plus_two));
+        assert!(!frame.matches_system_time(&LtcZone::Utc));
     }
 
     #[test]
@@ -239,6 +1309,47 @@ mod tests {
         assert!(state.latest.is_some());
     }
 
+    #[test]
+    fn test_view_reads_without_the_lock() {
+        let state = Arc::new(Mutex::new(LtcState::new()));
+        let view = state.lock().unwrap().view();
+        let mut writer = state.lock().unwrap();
+        writer.update(get_test_frame("LOCK", 10, 20, 30));
+        // The writer still holds the lock; the view reads the published copy.
+        assert_eq!(view.get().lock_count, 1);
+        writer.ewma_clock_delta = Some(3.0);
+        assert_eq!(view.get().ewma_clock_delta, None, "unpublished changes are not seen");
+        writer.note_step();
+        assert_eq!(view.get().get_ewma_clock_delta(), 3);
+        assert_eq!(view.get().lock_ratio(), 100.0);
+    }
+
+    #[test]
+    fn test_ltc_state_notifies_subscribers() {
+        let mut state = LtcState::new();
+        let mut updates = state.view().subscribe();
+        assert!(!updates.has_changed().unwrap());
+        state.update(get_test_frame("LOCK", 10, 20, 30));
+        assert!(updates.has_changed().unwrap());
+        updates.mark_unchanged();
+        state.update(get_test_frame("FREE", 10, 20, 31));
+        assert!(updates.has_changed().unwrap());
+    }
+
+    #[test]
+    fn test_arm_second_fires_on_locked_frame_zero() {
+        let mut state = LtcState::new();
+        let armed = state.arm_second();
+        state.update(LtcFrame { frames: 12, ..get_test_frame("LOCK", 10, 20, 30) });
+        state.update(get_test_frame("FREE", 10, 20, 31));
+        assert!(armed.try_recv().is_err());
+
+        state.update(get_test_frame("LOCK", 10, 20, 32));
+        assert_eq!(armed.try_recv().unwrap().seconds, 32);
+        state.update(get_test_frame("LOCK", 10, 20, 33));
+        assert!(armed.try_recv().is_err(), "fires once");
+    }
+
     #[test]
     fn test_ltc_state_update_free() {
         let mut state = LtcState::new();
@@ -253,6 +1364,149 @@ mod tests {
         assert_eq!(state.last_match_status, "UNKNOWN");
     }
 
+    #[test]
+    fn test_session_anchor_set_on_first_lock_only() {
+        let mut state = LtcState::new();
+        state.update(get_test_frame("FREE", 9, 0, 0));
+        assert!(state.session_timecode().is_none());
+
+        state.update(get_test_frame("LOCK", 10, 20, 30));
+        let anchor_ms = state.session_anchor.as_ref().unwrap().timecode_ms;
+        assert_eq!(anchor_ms, (10 * 3600 + 20 * 60 + 30) * 1000);
+
+        // A later LOCK (e.g. after a clock step or relock) keeps the anchor.
+        state.update(get_test_frame("FREE", 11, 0, 0));
+        state.update(get_test_frame("LOCK", 11, 0, 1));
+        assert_eq!(state.session_anchor.as_ref().unwrap().timecode_ms, anchor_ms);
+        assert!(state.session_timecode().unwrap().starts_with("10:20:3"));
+    }
+
+    #[test]
+    fn test_session_timecode_after() {
+        let anchor = SessionAnchor {
+            instant: Instant::now(),
+            timecode_ms: (23 * 3600 + 59 * 60 + 59) * 1000,
+            frame_rate: Ratio::new(25, 1),
+            is_drop_frame: false,
+        };
+        assert_eq!(anchor.timecode_after(0), "23:59:59:00");
+        assert_eq!(anchor.timecode_after(480), "23:59:59:12");
+        // Wraps at midnight
+        assert_eq!(anchor.timecode_after(1040), "00:00:00:01");
+
+        let df = SessionAnchor {
+            frame_rate: Ratio::new(30000, 1001),
+            is_drop_frame: true,
+            ..anchor
+        };
+        assert_eq!(df.timecode_after(500), "23:59:59;14");
+    }
+
+    #[test]
+    fn test_frame_rate_recognition() {
+        // (printed, drop-frame separator, expected exact rate)
+        let cases = [
+            ("23.98", false, Some(Ratio::new(24000, 1001))),
+            ("23.976", false, Some(Ratio::new(24000, 1001))),
+            ("24.00", false, Some(Ratio::new(24, 1))),
+            ("25.00", false, Some(Ratio::new(25, 1))),
+            ("25", false, Some(Ratio::new(25, 1))),
+            ("29.97", false, Some(Ratio::new(30000, 1001))),
+            ("29.97", true, Some(Ratio::new(30000, 1001))),
+            ("30.00", false, Some(Ratio::new(30, 1))),
+            // Drop-frame only exists at 29.97, whatever the reader printed.
+            ("30.00", true, Some(Ratio::new(30000, 1001))),
+            ("50.00", false, Some(Ratio::new(50, 1))),
+            ("59.94", false, Some(Ratio::new(60000, 1001))),
+            ("60.00", true, Some(Ratio::new(60000, 1001))),
+            ("60.00", false, Some(Ratio::new(60, 1))),
+            ("25.00", true, None),
+            ("24.50", false, None),
+            ("fast", false, None),
+        ];
+        for (printed, df, expected) in cases {
+            assert_eq!(get_frame_rate_ratio(printed, df), expected, "{} df={}", printed, df);
+        }
+    }
+
+    #[test]
+    fn test_frame_labels() {
+        let re = regex::Regex::new(crate::serial_input::DEFAULT_LINE_PATTERN).unwrap();
+        let parse = |line: &str| LtcFrame::from_regex(&re.captures(line).unwrap(), ClockPair::now());
+        // (line, valid)
+        let cases = [
+            ("[LOCK] 10:00:00:23 | 23.98fps", true),
+            ("[LOCK] 10:00:00:24 | 23.98fps", false),
+            ("[LOCK] 10:00:00:24 | 25.00fps", true),
+            ("[LOCK] 10:00:00:25 | 25.00fps", false),
+            ("[LOCK] 10:00:00:29 | 29.97fps", true),
+            ("[LOCK] 10:00:00:30 | 29.97fps", false),
+            ("[LOCK] 10:00:00:59 | 59.94fps", true),
+            ("[LOCK] 24:00:00:00 | 25.00fps", false),
+            ("[LOCK] 10:60:00:00 | 25.00fps", false),
+            // Drop-frame skips frames 0-1 (0-3 at 59.94) each minute but every tenth.
+            ("[LOCK] 10:01:00;00 | 29.97fps", false),
+            ("[LOCK] 10:01:00;01 | 29.97fps", false),
+            ("[LOCK] 10:01:00;02 | 29.97fps", true),
+            ("[LOCK] 10:10:00;00 | 29.97fps", true),
+            ("[LOCK] 10:01:01;00 | 29.97fps", true),
+            ("[LOCK] 10:01:00;03 | 59.94fps", false),
+            ("[LOCK] 10:01:00;04 | 59.94fps", true),
+            // Non-drop at 29.97 has every label.
+            ("[LOCK] 10:01:00:00 | 29.97fps", true),
+        ];
+        for (line, valid) in cases {
+            assert_eq!(parse(line).is_some(), valid, "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_delta_frames_by_rate() {
+        assert_eq!(parse_frame_rate(" 29.97"), Ok(Ratio::new(30000, 1001)));
+        assert_eq!(parse_frame_rate("25"), Ok(Ratio::new(25, 1)));
+        assert!(parse_frame_rate("26").is_err());
+
+        // 100 ms is 2.5 frames at 25 fps and 2.997 at 29.97.
+        let by_rate = delta_frames_by_rate(-100, Some(Ratio::new(25, 1)), Some(Ratio::new(30000, 1001)));
+        assert_eq!(
+            by_rate,
+            vec![
+                DeltaFrames { frame_rate: "25.00fps".into(), frames: -3 },
+                DeltaFrames { frame_rate: "29.97fps".into(), frames: -3 },
+            ]
+        );
+        assert_eq!(delta_in_frames(20, Ratio::new(25, 1)), 1);
+        assert_eq!(delta_in_frames(19, Ratio::new(25, 1)), 0);
+        // Without LTC only the secondary rate is given.
+        assert_eq!(delta_frames_by_rate(40, None, Some(Ratio::new(50, 1))).len(), 1);
+        assert!(delta_frames_by_rate(40, None, None).is_empty());
+    }
+
+    #[test]
+    fn test_frame_duration() {
+        let cases = [
+            (Ratio::new(24000, 1001), 41.708, 24),
+            (Ratio::new(24, 1), 41.667, 24),
+            (Ratio::new(25, 1), 40.0, 25),
+            (Ratio::new(30000, 1001), 33.367, 30),
+            (Ratio::new(30, 1), 33.333, 30),
+            (Ratio::new(50, 1), 20.0, 50),
+            (Ratio::new(60000, 1001), 16.683, 60),
+            (Ratio::new(60, 1), 16.667, 60),
+        ];
+        for (rate, ms, nominal) in cases {
+            assert!((frame_duration_ms(rate) - ms).abs() < 0.001, "{}", rate);
+            assert_eq!(nominal_fps(rate), nominal, "{}", rate);
+        }
+    }
+
+    #[test]
+    fn test_timecode_ms() {
+        let mut frame = get_test_frame("LOCK", 1, 2, 3);
+        frame.frames = 12;
+        assert_eq!(frame.timecode_ms(), 3_723_480);
+    }
+
     #[test]
     fn test_offset_history_management() {
         let mut state = LtcState::new();
@@ -264,6 +1518,33 @@ mod tests {
         assert_eq!(*state.offset_history.back().unwrap(), 24);
     }
 
+    #[test]
+    fn test_jitter_stats() {
+        let mut state = LtcState::new();
+        assert_eq!(state.jitter_stats(), None);
+        // Steady 2 ms with one 50 ms glitch: the mean barely moves.
+        for _ in 0..19 {
+            state.record_offset(2);
+        }
+        state.record_offset(50);
+        let stats = state.jitter_stats().unwrap();
+        assert_eq!(stats.samples, 20);
+        assert_eq!(stats.mean_ms, 4.4);
+        assert_eq!(stats.stddev_ms, 10.5);
+        assert_eq!(stats.p95_ms, 2);
+        assert_eq!((stats.min_ms, stats.max_ms), (2, 50));
+
+        // A smaller window drops the oldest samples.
+        state.jitter_window = 10;
+        for v in 1..=10 {
+            state.record_offset(v);
+        }
+        let stats = state.jitter_stats().unwrap();
+        assert_eq!(stats.samples, 10);
+        assert_eq!(stats.p95_ms, 10);
+        assert_eq!((stats.min_ms, stats.max_ms), (1, 10));
+    }
+
     #[test]
     fn test_timecode_match_status_in_sync() {
         let mut state = LtcState::new();
@@ -354,23 +1635,100 @@ mod tests {
     #[test]
     fn test_get_sync_status() {
         let mut config = Config::default();
-        assert_eq!(get_sync_status(0, &config), "IN SYNC");
-        assert_eq!(get_sync_status(8, &config), "IN SYNC");
-        assert_eq!(get_sync_status(-8, &config), "IN SYNC");
-        assert_eq!(get_sync_status(9, &config), "CLOCK BEHIND");
-        assert_eq!(get_sync_status(10, &config), "CLOCK BEHIND");
-        assert_eq!(get_sync_status(11, &config), "CLOCK AHEAD");
-        assert_eq!(get_sync_status(-9, &config), "CLOCK BEHIND");
-        assert_eq!(get_sync_status(-100, &config), "CLOCK BEHIND");
+        assert_eq!(get_sync_status(0, Staleness::Live, &config), "IN SYNC");
+        assert_eq!(get_sync_status(8, Staleness::Live, &config), "IN SYNC");
+        assert_eq!(get_sync_status(-8, Staleness::Live, &config), "IN SYNC");
+        assert_eq!(get_sync_status(9, Staleness::Live, &config), "CLOCK AHEAD");
+        assert_eq!(get_sync_status(10, Staleness::Live, &config), "CLOCK AHEAD");
+        assert_eq!(get_sync_status(-9, Staleness::Live, &config), "CLOCK BEHIND");
+        assert_eq!(get_sync_status(-100, Staleness::Live, &config), "CLOCK BEHIND");
+
+        // The window need not be symmetric.
+        config.in_sync_ahead_ms = 2;
+        config.in_sync_behind_ms = -20;
+        assert_eq!(clock_direction(3, &config), ClockDirection::Ahead);
+        assert_eq!(clock_direction(-20, &config), ClockDirection::InSync);
+        assert_eq!(clock_direction(-21, &config), ClockDirection::Behind);
 
         // Test auto-sync status
         config.auto_sync_enabled = true;
-        assert_eq!(get_sync_status(0, &config), "IN SYNC");
+        assert_eq!(get_sync_status(0, Staleness::Live, &config), "IN SYNC");
 
-        // Test TIMETURNING status takes precedence
+        // Test TIMETURNING status takes precedence over the delta, but a
+        // stopped feed still shows with an offset set
         config.timeturner_offset = TimeturnerOffset { hours: 1, minutes: 0, seconds: 0, frames: 0, milliseconds: 0 };
-        assert_eq!(get_sync_status(0, &config), "TIMETURNING");
-        assert_eq!(get_sync_status(100, &config), "TIMETURNING");
+        assert_eq!(get_sync_status(0, Staleness::Live, &config), "TIMETURNING");
+        assert_eq!(get_sync_status(100, Staleness::Live, &config), "TIMETURNING");
+        assert_eq!(get_sync_status(0, Staleness::NoSignal, &config), "NO SIGNAL");
+        assert_eq!(get_sync_status(0, Staleness::Stale, &config), "STALE");
+    }
+
+    #[test]
+    fn test_arrival_follows_clock_changes_since_receipt() {
+        let received = ClockPair::now();
+        let frame = LtcFrame {
+            timestamp: received.real,
+            received: Some(received.mono),
+            ..get_test_frame("LOCK", 10, 0, 0)
+        };
+        // The clock is stepped 5 s forward while the frame waits 40 ms in the queue.
+        let now = ClockPair {
+            real: received.real + chrono::Duration::milliseconds(5040),
+            mono: received.mono + Duration::from_millis(40),
+        };
+        assert_eq!(frame.arrival(&now), received.real + chrono::Duration::seconds(5));
+        let made_up = LtcFrame { received: None, ..frame };
+        assert_eq!(made_up.arrival(&now), received.real);
+
+        let earlier = received.earlier(chrono::Duration::microseconds(1500));
+        assert_eq!(received.real - earlier.real, chrono::Duration::microseconds(1500));
+        assert_eq!(received.mono - earlier.mono, Duration::from_micros(1500));
+    }
+
+    #[test]
+    fn test_continuity_flags_skips_repeats_and_reversals() {
+        let mut checker = ContinuityChecker::default();
+        let frame = |h, m, s, f| LtcFrame { frames: f, ..get_test_frame("LOCK", h, m, s) };
+        assert_eq!(checker.check(&frame(0, 8, 7, 9)), None);
+        assert_eq!(checker.check(&frame(0, 8, 7, 10)), None);
+        let skip = checker.check(&frame(0, 8, 7, 13)).unwrap();
+        assert_eq!((skip.kind, skip.expected.as_str(), skip.frames), (DiscontinuityKind::Skip, "00:08:07:11", 2));
+        assert_eq!(checker.check(&frame(0, 8, 7, 13)).unwrap().kind, DiscontinuityKind::Repeat);
+        assert_eq!(checker.check(&frame(0, 8, 7, 2)).unwrap().kind, DiscontinuityKind::Reversal);
+        // Midnight is not a reversal.
+        checker.restart();
+        assert_eq!(checker.check(&frame(23, 59, 59, 24)), None);
+        assert_eq!(checker.check(&frame(0, 0, 0, 0)), None);
+
+        let report = checker.report();
+        assert_eq!((report.frames, report.skips, report.repeats, report.reversals, report.total), (5, 1, 1, 1, 3));
+        assert_eq!(report.score, Some(40.0));
+        assert_eq!(report.last.unwrap().got, "00:08:07:02");
+    }
+
+    #[test]
+    fn test_staleness_tells_stopped_ltc_from_late_frames() {
+        let config = Config::default();
+        let rate = Some(Ratio::from_integer(25));
+        let ms = |ms| Some(Duration::from_millis(ms));
+        assert_eq!(staleness(None, None, &config), Staleness::NoSignal);
+        assert_eq!(staleness(ms(40), rate, &config), Staleness::Live);
+        assert_eq!(staleness(ms(200), rate, &config), Staleness::Live);
+        assert_eq!(staleness(ms(201), rate, &config), Staleness::Stale);
+        assert_eq!(staleness(ms(1000), rate, &config), Staleness::NoSignal);
+        assert_eq!(get_sync_status(0, Staleness::Stale, &config), "STALE");
+        assert_eq!(get_sync_status(0, Staleness::NoSignal, &config), "NO SIGNAL");
+
+        let mut state = LtcState::new();
+        assert_eq!(state.snapshot().staleness(&config), Staleness::NoSignal);
+        state.update(get_test_frame("FREE", 10, 0, 0));
+        let fresh = state.snapshot();
+        assert!(fresh.frame_age().unwrap() < Duration::from_millis(200));
+        assert_eq!(fresh.staleness(&config), Staleness::Live, "a free-running feed is still live");
+        state.last_frame_at = Some(Instant::now() - Duration::from_millis(300));
+        assert_eq!(state.snapshot().staleness(&config), Staleness::Stale);
+        state.signal_lost = true;
+        assert_eq!(state.snapshot().staleness(&config), Staleness::NoSignal);
     }
 
     #[test]
@@ -384,4 +1742,220 @@ mod tests {
         assert_eq!(get_jitter_status(40), "BAD");
         assert_eq!(get_jitter_status(-40), "BAD");
     }
+
+    #[test]
+    fn test_explain() {
+        let auto = Config {
+            auto_sync_enabled: true,
+            ..Config::default()
+        };
+        let mut state = LtcState::new();
+        assert_eq!(explain(&state.snapshot(), &auto, None), "waiting for LTC from the reader");
+
+        state.update(get_test_frame("FREE", 10, 0, 0));
+        assert!(explain(&state.snapshot(), &auto, None).contains("waiting for LOCK"));
+        let start = Instant::now();
+        for s in 0..=120 {
+            state.drift.sample(start + Duration::from_secs(s), s as f64 * 0.01);
+        }
+        assert_eq!(
+            explain(&state.snapshot(), &auto, None),
+            "holdover: LTC is free-running, compensating +10.0 ppm drift until LOCK"
+        );
+        state.drift.note_step();
+
+        state.update(get_test_frame("LOCK", 10, 0, 0));
+        state.ewma_clock_delta = Some(3.0);
+        assert_eq!(
+            explain(&state.snapshot(), &auto, None),
+            "slewing -3 ms at 0.5 ms/s: within the 40 ms step threshold"
+        );
+        assert_eq!(
+            explain(&state.snapshot(), &auto, Some(2)),
+            "holding corrections: settling for 8 s after step"
+        );
+        let mut at_leap = get_test_frame("LOCK", 23, 59, 58);
+        at_leap.timestamp = Utc.with_ymd_and_hms(2016, 12, 31, 23, 59, 58).unwrap();
+        state.latest = Some(at_leap);
+        assert_eq!(
+            explain(&state.snapshot(), &auto, None),
+            "holding corrections: a leap second may be applied at midnight UTC"
+        );
+        state.latest = Some(get_test_frame("LOCK", 10, 0, 0));
+        assert_eq!(
+            explain(&state.snapshot(), &Config::default(), None),
+            "monitor-only: auto-sync is off, clock is 3 ms ahead"
+        );
+
+        state.ewma_clock_delta = Some(-120.0);
+        assert_eq!(
+            explain(&state.snapshot(), &auto, Some(60)),
+            "stepping: clock is 120 ms behind, beyond the 40 ms step threshold"
+        );
+        let limited = Config { max_auto_step_ms: 100, ..auto.clone() };
+        assert_eq!(
+            explain(&state.snapshot(), &limited, Some(60)),
+            "holding step: clock is 120 ms behind, beyond the 100 ms maxAutoStepMs; sync manually with the override"
+        );
+
+        state.ewma_clock_delta = Some(0.2);
+        let turning = Config {
+            timeturner_offset: TimeturnerOffset { hours: 1, ..TimeturnerOffset::default() },
+            ..auto
+        };
+        assert_eq!(
+            explain(&state.snapshot(), &turning, None),
+            "timeturning with the configured offset; in sync: clock within 1 ms of LTC, no correction needed"
+        );
+    }
+
+    #[test]
+    fn test_pinned_source() {
+        let pinned = |source| Config { auto_sync_enabled: true, sync_source: source, ..Config::default() };
+        let mut state = LtcState::new();
+        state.update(get_test_frame("FREE", 10, 0, 0));
+        assert_eq!(active_source(&state.snapshot(), &pinned(SyncSource::Auto)), None);
+        assert_eq!(active_source(&state.snapshot(), &pinned(SyncSource::Ltc)), None);
+        let start = Instant::now();
+        for s in 0..=120 {
+            state.drift.sample(start + Duration::from_secs(s), s as f64 * 0.01);
+        }
+        assert_eq!(
+            active_source(&state.snapshot(), &pinned(SyncSource::Auto)),
+            Some(SyncSource::Holdover)
+        );
+        assert_eq!(
+            explain(&state.snapshot(), &pinned(SyncSource::Ltc), None),
+            "holding corrections: LTC is free-running, waiting for LOCK"
+        );
+
+        state.update(get_test_frame("LOCK", 10, 0, 0));
+        state.ewma_clock_delta = Some(3.0);
+        assert_eq!(active_source(&state.snapshot(), &pinned(SyncSource::Auto)), Some(SyncSource::Ltc));
+        assert_eq!(
+            active_source(&state.snapshot(), &pinned(SyncSource::Holdover)),
+            Some(SyncSource::Holdover)
+        );
+        assert_eq!(
+            explain(&state.snapshot(), &pinned(SyncSource::Holdover), None),
+            "holdover (pinned): compensating +10.0 ppm drift, ignoring LTC"
+        );
+        assert_eq!(
+            explain(&state.snapshot(), &pinned(SyncSource::Manual), None),
+            "manual: sync source pinned to manual, auto-sync makes no corrections"
+        );
+        assert_eq!(active_source(&state.snapshot(), &Config::default()), None);
+    }
+
+    #[test]
+    fn test_in_leap_window() {
+        let at = |mo, d, h, mi, s| Utc.with_ymd_and_hms(2016, mo, d, h, mi, s).unwrap();
+        assert!(in_leap_window(at(12, 31, 23, 59, 50)));
+        assert!(in_leap_window(at(1, 1, 0, 0, 9)));
+        assert!(in_leap_window(at(6, 30, 23, 59, 59)));
+        assert!(in_leap_window(at(7, 1, 0, 0, 0)));
+        // The inserted second itself, 23:59:60.
+        let leap = NaiveDate::from_ymd_opt(2016, 12, 31)
+            .unwrap()
+            .and_hms_milli_opt(23, 59, 59, 1_500)
+            .unwrap()
+            .and_utc();
+        assert!(in_leap_window(leap));
+
+        assert!(!in_leap_window(at(12, 31, 23, 59, 49)));
+        assert!(!in_leap_window(at(1, 1, 0, 0, 10)));
+        assert!(!in_leap_window(at(3, 31, 23, 59, 59)));
+        assert!(!in_leap_window(at(12, 30, 23, 59, 59)));
+    }
+
+    #[test]
+    fn test_choose_correction() {
+        let config = Config {
+            step_threshold_ms: 50,
+            ..Config::default()
+        };
+        assert_eq!(choose_correction(0, None, &config), Correction::Skip);
+        assert_eq!(choose_correction(12, None, &config), Correction::Slew(-12_000));
+        assert_eq!(choose_correction(-50, None, &config), Correction::Slew(50_000));
+        assert_eq!(choose_correction(51, None, &config), Correction::Step);
+        assert_eq!(choose_correction(-400, None, &config), Correction::Step);
+
+        // Drift is projected one period ahead: 200 ppm is 2 ms per 10 s.
+        assert_eq!(choose_correction(12, Some(200.0), &config), Correction::Slew(-14_000));
+        assert_eq!(choose_correction(0, Some(-150.0), &config), Correction::Slew(1_500));
+        assert_eq!(choose_correction(49, Some(200.0), &config), Correction::Step);
+        assert_eq!(choose_correction(0, Some(20.0), &config), Correction::Skip);
+    }
+
+    #[test]
+    fn test_holdover_correction() {
+        assert_eq!(holdover_correction(None), Correction::Skip);
+        assert_eq!(holdover_correction(Some(25.0)), Correction::Slew(-250));
+        assert_eq!(holdover_correction(Some(-3.04)), Correction::Slew(30));
+    }
+
+    #[test]
+    fn test_drift_estimator() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut drift = DriftEstimator::default();
+        // 20 ppm: the delta grows by 0.02 ms a second.
+        for s in 0..120 {
+            drift.sample(at(s), 5.0 + 0.02 * s as f64);
+        }
+        assert_eq!(drift.ppm(), None, "needs two minutes of history");
+        for s in 120..300 {
+            drift.sample(at(s), 5.0 + 0.02 * s as f64);
+            // Extra samples within the same second are ignored.
+            drift.sample(at(s) + Duration::from_millis(500), 100.0);
+        }
+        assert!((drift.ppm().unwrap() - 20.0).abs() < 0.01, "{:?}", drift.ppm());
+
+        // A slew of -10 ms moves the measured delta but not the drift.
+        drift.note_slew(-10_000);
+        for s in 300..400 {
+            drift.sample(at(s), 5.0 + 0.02 * s as f64 - 10.0);
+        }
+        assert!((drift.ppm().unwrap() - 20.0).abs() < 0.01, "{:?}", drift.ppm());
+
+        drift.note_step();
+        assert_eq!(drift.ppm(), None);
+    }
+
+    #[test]
+    fn test_phase_estimator() {
+        let mut phase = PhaseEstimator::default();
+        let frame_us = 40_000;
+        // True phase 2.3 ms, with ±3 ms of alternating arrival jitter.
+        let jitter = |n: i64| if n % 2 == 0 { 3_000 } else { -3_000 };
+        let mut estimate = 0.0;
+        for n in 0..64 {
+            estimate = phase.sample(n * frame_us, 2_300 + jitter(n), 40.0);
+        }
+        assert!((estimate - 2.3).abs() < 0.2, "{}", estimate);
+
+        // A slew shows up as a slope, which the fit follows.
+        for n in 64..128 {
+            estimate = phase.sample(n * frame_us, 2_300 + (n - 64) * 10 + jitter(n), 40.0);
+        }
+        assert!((estimate - (2.3 + 0.63)).abs() < 0.3, "{}", estimate);
+
+        // A single spike is left out, and the fit carries on past it.
+        let slewed = |n: i64| 2_300 + (n - 64) * 10 + jitter(n);
+        estimate = phase.sample(128 * frame_us, slewed(128) + 60_000, 40.0);
+        assert!((estimate - (2.3 + 0.64)).abs() < 0.3, "{}", estimate);
+        estimate = phase.sample(129 * frame_us, slewed(129), 40.0);
+        assert!((estimate - (2.3 + 0.65)).abs() < 0.3, "not the raw delta: {}", estimate);
+
+        // A clock step starts over from the raw delta once it holds.
+        for n in 130..130 + PHASE_MAX_MISFITS as i64 - 1 {
+            assert!(phase.sample(n * frame_us, 500_000, 40.0) < 10.0);
+        }
+        let n = 130 + PHASE_MAX_MISFITS as i64 - 1;
+        assert_eq!(phase.sample(n * frame_us, 500_000, 40.0), 500.0);
+        // So does a timecode jump.
+        assert_eq!(phase.sample(1_000 * frame_us, 1_000, 40.0), 1.0);
+        phase.reset();
+        assert_eq!(phase.sample(1_001 * frame_us, 2_000, 40.0), 2.0);
+    }
 }
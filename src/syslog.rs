@@ -0,0 +1,339 @@
+// src/syslog.rs
+//
+// Remote syslog forwarding: every record the logger keeps is also sent to a
+// collector as an RFC 5424 message, over UDP or TCP (RFC 6587 octet
+// counting). Sending happens on a thread of its own behind a bounded queue,
+// so an unreachable collector never slows down logging.
+
+use chrono::{SecondsFormat, Utc};
+use log::Level;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::mpsc::{self, SyncSender};
+use std::thread;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+/// Messages waiting to be sent before new ones are dropped.
+const QUEUE_CAPACITY: usize = 256;
+
+/// Default syslog port for both transports.
+const DEFAULT_PORT: u16 = 514;
+
+const TCP_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SyslogProtocol {
+    #[default]
+    Udp,
+    Tcp,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SyslogConfig {
+    /// Collector as `host` or `host:port` (port 514 by default). Forwarding
+    /// is off while this is unset.
+    #[serde(default)]
+    pub server: Option<String>,
+    #[serde(default)]
+    pub protocol: SyslogProtocol,
+    /// `user`, `daemon` or `local0` to `local7`.
+    #[serde(default = "default_facility")]
+    pub facility: String,
+    /// APP-NAME field; set per unit to tell Timeturners apart in the collector.
+    #[serde(default = "default_app_name")]
+    pub app_name: String,
+}
+
+fn default_facility() -> String {
+    "daemon".to_string()
+}
+
+fn default_app_name() -> String {
+    "timeturner".to_string()
+}
+
+impl Default for SyslogConfig {
+    fn default() -> Self {
+        Self {
+            server: None,
+            protocol: SyslogProtocol::default(),
+            facility: default_facility(),
+            app_name: default_app_name(),
+        }
+    }
+}
+
+impl SyslogConfig {
+    /// The numeric facility, or an error naming the bad value.
+    pub fn facility_code(&self) -> Result<u8, String> {
+        match self.facility.as_str() {
+            "user" => Ok(1),
+            "daemon" => Ok(3),
+            local => local
+                .strip_prefix("local")
+                .and_then(|n| n.parse::<u8>().ok())
+                .filter(|n| *n <= 7)
+                .map(|n| 16 + n)
+                .ok_or_else(|| {
+                    format!("syslog.facility must be user, daemon or local0-local7, got '{}'", local)
+                }),
+        }
+    }
+
+    /// `server` with the default port filled in, if forwarding is on.
+    pub fn address(&self) -> Option<String> {
+        let server = self.server.as_deref()?.trim();
+        if server.is_empty() {
+            return None;
+        }
+        // A bare IPv6 address has colons but no port.
+        let has_port = server.rsplit_once(':').is_some_and(|(host, port)| {
+            port.parse::<u16>().is_ok() && (!host.contains(':') || host.ends_with(']'))
+        });
+        Some(if has_port {
+            server.to_string()
+        } else if server.contains(':') && !server.starts_with('[') {
+            format!("[{}]:{}", server, DEFAULT_PORT)
+        } else {
+            format!("{}:{}", server, DEFAULT_PORT)
+        })
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.facility_code()?;
+        if self.app_name.is_empty() || self.app_name.len() > 48 || !is_printable(&self.app_name) {
+            return Err("syslog.appName must be 1-48 printable ASCII characters".into());
+        }
+        Ok(())
+    }
+}
+
+fn is_printable(field: &str) -> bool {
+    field.bytes().all(|b| (33..=126).contains(&b))
+}
+
+fn severity(level: Level) -> u8 {
+    match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug | Level::Trace => 7,
+    }
+}
+
+/// This machine's name for the HOSTNAME field, or `-` if unknown.
+fn hostname() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|name| name.trim().to_string())
+        .ok()
+        .filter(|name| !name.is_empty() && is_printable(name))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Render one record as an RFC 5424 message. The MSGID is the logging
+/// module relative to the crate, e.g. `processor`.
+pub fn format_message(
+    facility: u8,
+    level: Level,
+    hostname: &str,
+    app_name: &str,
+    target: &str,
+    message: &str,
+) -> String {
+    let module = target.split_once("::").map_or(target, |(_, rest)| rest);
+    let msgid: String = module.chars().filter(|c| c.is_ascii_graphic()).take(32).collect();
+    format!(
+        "<{}>1 {} {} {} {} {} - {}",
+        facility * 8 + severity(level),
+        Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+        hostname,
+        app_name,
+        std::process::id(),
+        if msgid.is_empty() { "-" } else { &msgid },
+        message
+    )
+}
+
+enum Transport {
+    Udp(Option<UdpSocket>),
+    Tcp(Option<TcpStream>),
+}
+
+impl Transport {
+    /// Send one message, (re)connecting as needed. On failure the connection
+    /// is dropped and retried with the next message.
+    fn send(&mut self, address: &str, message: &str) -> std::io::Result<()> {
+        match self {
+            Transport::Udp(socket) => {
+                if socket.is_none() {
+                    let target = address
+                        .to_socket_addrs()?
+                        .next()
+                        .ok_or_else(|| std::io::Error::other("no address"))?;
+                    let bind = if target.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" };
+                    let udp = UdpSocket::bind(bind)?;
+                    udp.connect(target)?;
+                    *socket = Some(udp);
+                }
+                let result = socket.as_ref().unwrap().send(message.as_bytes()).map(|_| ());
+                if result.is_err() {
+                    *socket = None;
+                }
+                result
+            }
+            Transport::Tcp(stream) => {
+                if stream.is_none() {
+                    let target = address
+                        .to_socket_addrs()?
+                        .next()
+                        .ok_or_else(|| std::io::Error::other("no address"))?;
+                    let tcp = TcpStream::connect_timeout(&target, TCP_TIMEOUT)?;
+                    tcp.set_write_timeout(Some(TCP_TIMEOUT))?;
+                    *stream = Some(tcp);
+                }
+                // RFC 6587 octet counting: "LEN SP MSG".
+                let framed = format!("{} {}", message.len(), message);
+                let result = stream.as_mut().unwrap().write_all(framed.as_bytes());
+                if result.is_err() {
+                    *stream = None;
+                }
+                result
+            }
+        }
+    }
+}
+
+/// A running forwarder. Dropping it stops the sending thread once the
+/// queue is drained.
+pub struct Forwarder {
+    tx: SyncSender<String>,
+    facility: u8,
+    hostname: String,
+    app_name: String,
+}
+
+impl Forwarder {
+    /// Start forwarding to `config.server`; `None` if forwarding is off or
+    /// the config is invalid.
+    pub fn start(config: &SyslogConfig) -> Option<Self> {
+        let address = config.address()?;
+        let facility = config.facility_code().ok()?;
+        let (tx, rx) = mpsc::sync_channel::<String>(QUEUE_CAPACITY);
+        let mut transport = match config.protocol {
+            SyslogProtocol::Udp => Transport::Udp(None),
+            SyslogProtocol::Tcp => Transport::Tcp(None),
+        };
+        thread::spawn(move || {
+            // Errors go to stderr only: logging them would feed them back in.
+            let mut failing = false;
+            for message in rx {
+                match transport.send(&address, &message) {
+                    Ok(()) if failing => {
+                        eprintln!("Syslog: sending to {} again.", address);
+                        failing = false;
+                    }
+                    Ok(()) => {}
+                    Err(e) if !failing => {
+                        eprintln!("Syslog: cannot send to {}: {}", address, e);
+                        failing = true;
+                    }
+                    Err(_) => {}
+                }
+            }
+        });
+        Some(Self {
+            tx,
+            facility,
+            hostname: hostname(),
+            app_name: config.app_name.clone(),
+        })
+    }
+
+    /// Queue a record for sending; dropped if the queue is full.
+    pub fn send(&self, level: Level, target: &str, message: &str) {
+        let message = format_message(self.facility, level, &self.hostname, &self.app_name, target, message);
+        let _ = self.tx.try_send(message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_format_message() {
+        let message = format_message(16, Level::Warn, "tt-1", "timeturner", "ntp_timeturner::processor", "Frame queue full");
+        let fields: Vec<&str> = message.splitn(8, ' ').collect();
+        assert_eq!(fields[0], "<132>1");
+        assert!(fields[1].ends_with('Z'));
+        assert_eq!(fields[2..4], ["tt-1", "timeturner"]);
+        assert_eq!(fields[5..], ["processor", "-", "Frame queue full"]);
+    }
+
+    #[test]
+    fn test_config() {
+        let mut config = SyslogConfig::default();
+        assert_eq!(config.facility_code(), Ok(3));
+        assert_eq!(config.address(), None);
+        config.facility = "local7".into();
+        assert_eq!(config.facility_code(), Ok(23));
+        config.facility = "local8".into();
+        assert!(config.validate().is_err());
+
+        for (server, address) in [
+            ("logs.venue", "logs.venue:514"),
+            ("10.0.0.5:1514", "10.0.0.5:1514"),
+            ("fd00::5", "[fd00::5]:514"),
+            ("[fd00::5]:1514", "[fd00::5]:1514"),
+        ] {
+            config.server = Some(server.into());
+            assert_eq!(config.address().as_deref(), Some(address));
+        }
+    }
+
+    #[test]
+    fn test_forwards_over_udp() {
+        let collector = UdpSocket::bind("127.0.0.1:0").unwrap();
+        collector.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let config = SyslogConfig {
+            server: Some(collector.local_addr().unwrap().to_string()),
+            ..SyslogConfig::default()
+        };
+        let forwarder = Forwarder::start(&config).unwrap();
+        forwarder.send(Level::Info, "ntp_timeturner::processor", "Auto-sync: nudging");
+
+        let mut buf = [0u8; 1024];
+        let len = collector.recv(&mut buf).unwrap();
+        let message = std::str::from_utf8(&buf[..len]).unwrap();
+        assert!(message.starts_with("<30>1 "), "{}", message);
+        assert!(message.ends_with(" processor - Auto-sync: nudging"), "{}", message);
+    }
+
+    #[test]
+    fn test_forwards_over_tcp_with_octet_counting() {
+        let collector = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = SyslogConfig {
+            server: Some(collector.local_addr().unwrap().to_string()),
+            protocol: SyslogProtocol::Tcp,
+            ..SyslogConfig::default()
+        };
+        let forwarder = Forwarder::start(&config).unwrap();
+        forwarder.send(Level::Error, "ntp_timeturner::api", "one");
+        forwarder.send(Level::Error, "ntp_timeturner::api", "two");
+        drop(forwarder);
+
+        let (mut stream, _) = collector.accept().unwrap();
+        let mut received = String::new();
+        stream.read_to_string(&mut received).unwrap();
+        let (len, rest) = received.split_once(' ').unwrap();
+        let first = &rest[..len.parse::<usize>().unwrap()];
+        assert!(first.starts_with("<27>1 ") && first.ends_with(" api - one"), "{}", first);
+        assert!(received.ends_with(" api - two"));
+    }
+}
@@ -1,14 +1,66 @@
-use crate::config::Config;
-use crate::sync_logic::LtcFrame;
-use chrono::{DateTime, Duration as ChronoDuration, Local, TimeZone};
+use crate::config::{Config, LtcZone, TimeturnerOffset};
+use crate::hooks::{self, HookEvent};
+use crate::sync_logic::{LtcFrame, LtcState};
+use crate::timecode;
+use chrono::{
+    DateTime, Duration as ChronoDuration, Local, LocalResult, NaiveDate, NaiveDateTime, TimeZone,
+    Timelike, Utc,
+};
 use num_rational::Ratio;
+use serde::Deserialize;
+use std::fmt;
 use std::process::Command;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+/// The clock-changing commands and the NTP service check, behind a trait so
+/// the HTTP API and auto-sync can be driven end to end in tests without
+/// touching the host clock.
+pub trait ClockControl: Send + Sync {
+    /// Step the clock to `frame`; see `trigger_sync`.
+    fn step(&self, frame: &LtcFrame, config: &Config) -> Result<String, ()>;
+    /// Slew the clock; see `nudge_clock`.
+    fn nudge(&self, microseconds: i64) -> Result<(), ()>;
+    /// Gain `microseconds` over `duration`, blocking until done; see `ramp_clock`.
+    fn ramp(&self, microseconds: i64, duration: Duration) -> Result<(), ()>;
+    /// Set the date; see `set_date`.
+    fn set_date(&self, date: &str) -> Result<(), ()>;
+    /// Whether chrony is running; see `ntp_service_active`.
+    fn ntp_active(&self) -> bool;
+}
+
+/// The real clock, changed with `sudo date` and `adjtimex`.
+pub struct SystemClock;
+
+impl ClockControl for SystemClock {
+    fn step(&self, frame: &LtcFrame, config: &Config) -> Result<String, ()> {
+        trigger_sync(frame, config)
+    }
+
+    fn nudge(&self, microseconds: i64) -> Result<(), ()> {
+        nudge_clock(microseconds)
+    }
+
+    fn ramp(&self, microseconds: i64, duration: Duration) -> Result<(), ()> {
+        ramp_clock(microseconds, duration)
+    }
+
+    fn set_date(&self, date: &str) -> Result<(), ()> {
+        set_date(date)
+    }
+
+    fn ntp_active(&self) -> bool {
+        ntp_service_active()
+    }
+}
 
 /// Check if Chrony is active
 pub fn ntp_service_active() -> bool {
     #[cfg(target_os = "linux")]
     {
-        if let Ok(output) = Command::new("systemctl").args(&["is-active", "chrony"]).output() {
+        if let Ok(output) = Command::new("systemctl").args(["is-active", "chrony"]).output() {
             output.status.success()
                 && String::from_utf8_lossy(&output.stdout).trim() == "active"
         } else {
@@ -22,103 +74,246 @@ pub fn ntp_service_active() -> bool {
     }
 }
 
-/// Toggle Chrony (not used yet)
-#[allow(dead_code)]
-pub fn ntp_service_toggle(start: bool) {
+/// What to do to a system service.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ServiceAction {
+    Start,
+    Stop,
+    Restart,
+}
+
+impl ServiceAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ServiceAction::Start => "start",
+            ServiceAction::Stop => "stop",
+            ServiceAction::Restart => "restart",
+        }
+    }
+}
+
+/// Start, stop or restart chrony with `sudo systemctl`. The error is
+/// systemctl's own message.
+pub fn ntp_service_control(action: ServiceAction) -> Result<(), String> {
     #[cfg(target_os = "linux")]
     {
-        let action = if start { "start" } else { "stop" };
-        let _ = Command::new("systemctl").args(&[action, "chrony"]).status();
+        let output = Command::new("sudo")
+            .args(["systemctl", action.as_str(), "chrony"])
+            .output()
+            .map_err(|e| format!("cannot run systemctl: {}", e))?;
+        if output.status.success() {
+            log::info!("chrony: {} done.", action.as_str());
+            Ok(())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            log::error!("chrony: {} failed: {}", action.as_str(), stderr);
+            Err(stderr)
+        }
     }
     #[cfg(not(target_os = "linux"))]
     {
-        // No-op on non-Linux.
-        // The parameter is unused, but the function is dead code anyway.
-        let _ = start;
+        let _ = action;
+        Err("Service control is only supported on Linux.".into())
     }
 }
 
-pub fn calculate_target_time(frame: &LtcFrame, config: &Config) -> DateTime<Local> {
-    let today_local = Local::now().date_naive();
+/// Why no clock target could be computed for an LTC frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetTimeError {
+    /// No valid local time exists within a day of the timecode.
+    NoValidLocalTime,
+    /// The timecode plus offsets fall outside chrono's date range.
+    OutOfRange,
+}
+
+impl fmt::Display for TargetTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TargetTimeError::NoValidLocalTime => write!(f, "no valid local time for timecode"),
+            TargetTimeError::OutOfRange => write!(f, "target time out of range"),
+        }
+    }
+}
+
+impl std::error::Error for TargetTimeError {}
 
-    // Total seconds from timecode components
-    let timecode_secs =
-        frame.hours as i64 * 3600 + frame.minutes as i64 * 60 + frame.seconds as i64;
+/// Unix second of the last DST resolution warning, so a timecode sitting in
+/// a gap for an hour logs once a minute rather than once a frame.
+static LAST_DST_WARNING: AtomicI64 = AtomicI64::new(0);
 
+fn warn_dst(message: String) {
+    let now = Local::now().timestamp();
+    if now - LAST_DST_WARNING.swap(now, Ordering::Relaxed) >= 60 {
+        log::warn!("{}", message);
+    }
+}
+
+/// Map a local wall-clock time to an instant in `tz`. A time skipped by a
+/// DST change resolves to the earliest valid instant after it (the end of
+/// the gap); a repeated time resolves to its earlier occurrence.
+pub fn resolve_local<Tz: TimeZone>(
+    tz: &Tz,
+    naive: NaiveDateTime,
+) -> Result<DateTime<Tz>, TargetTimeError> {
+    match tz.from_local_datetime(&naive) {
+        LocalResult::Single(dt) => Ok(dt),
+        LocalResult::Ambiguous(earliest, _) => {
+            warn_dst(format!("Local time {} is ambiguous (DST change); using the earlier instant.", naive));
+            Ok(earliest)
+        }
+        LocalResult::None => {
+            // DST gaps start and end on whole minutes, so the first valid
+            // minute is the earliest valid instant after `naive`.
+            let mut minute = naive.with_second(0).and_then(|t| t.with_nanosecond(0));
+            for _ in 0..24 * 60 {
+                minute = minute.and_then(|m| m.checked_add_signed(ChronoDuration::minutes(1)));
+                let Some(m) = minute else { break };
+                if let LocalResult::Single(dt) | LocalResult::Ambiguous(dt, _) = tz.from_local_datetime(&m) {
+                    warn_dst(format!("Local time {} does not exist (DST gap); using {}.", naive, m));
+                    return Ok(dt);
+                }
+            }
+            Err(TargetTimeError::NoValidLocalTime)
+        }
+    }
+}
+
+/// The instant `frame` names today, read in the configured `ltcTimeZone` and
+/// returned in local time for `date`.
+pub fn calculate_target_time(frame: &LtcFrame, config: &Config) -> Result<DateTime<Local>, TargetTimeError> {
+    let zone = config.ltc_zone();
+    let day = zone.now().date();
+    match zone {
+        LtcZone::Local => target_time_on(&Local, day, frame, config),
+        LtcZone::Utc => target_time_on(&Utc, day, frame, config).map(|t| t.with_timezone(&Local)),
+        LtcZone::Fixed(offset) => {
+            target_time_on(&offset, day, frame, config).map(|t| t.with_timezone(&Local))
+        }
+    }
+}
+
+/// The instant `frame` plus the configured offsets names on `day` in `tz`.
+fn target_time_on<Tz: TimeZone>(
+    tz: &Tz,
+    day: NaiveDate,
+    frame: &LtcFrame,
+    config: &Config,
+) -> Result<DateTime<Tz>, TargetTimeError> {
     // Timecode is always treated as wall-clock time. NDF scaling is not applied
     // as the LTC source appears to be pre-compensated.
-    let total_duration_secs =
-        Ratio::new(timecode_secs, 1) + Ratio::new(frame.frames as i64, 1) / frame.frame_rate;
+    let total_us = frame.label().time_of_day_us();
 
-    // Convert to milliseconds
-    let total_ms = (total_duration_secs * Ratio::new(1000, 1))
-        .round()
-        .to_integer();
+    let naive_midnight = day.and_hms_opt(0, 0, 0).ok_or(TargetTimeError::OutOfRange)?;
+    let naive_dt = naive_midnight
+        .checked_add_signed(ChronoDuration::microseconds(total_us))
+        .ok_or(TargetTimeError::OutOfRange)?;
 
-    let naive_midnight = today_local.and_hms_opt(0, 0, 0).unwrap();
-    let naive_dt = naive_midnight + ChronoDuration::milliseconds(total_ms);
-
-    let mut dt_local = Local
-        .from_local_datetime(&naive_dt)
-        .single()
-        .expect("Ambiguous or invalid local time");
+    let dt_local = resolve_local(tz, naive_dt)?;
 
     // Apply timeturner offset
-    let offset = &config.timeturner_offset;
-    dt_local = dt_local
-        + ChronoDuration::hours(offset.hours)
-        + ChronoDuration::minutes(offset.minutes)
-        + ChronoDuration::seconds(offset.seconds);
-    // Frame offset needs to be converted to milliseconds
-    let frame_offset_ms_ratio = Ratio::new(offset.frames * 1000, 1) / frame.frame_rate;
-    let frame_offset_ms = frame_offset_ms_ratio.round().to_integer();
-    dt_local + ChronoDuration::milliseconds(frame_offset_ms + offset.milliseconds)
+    let total_offset = offset_duration(&config.timeturner_offset, frame.frame_rate)?;
+    dt_local
+        .checked_add_signed(total_offset)
+        .ok_or(TargetTimeError::OutOfRange)
 }
 
-pub fn trigger_sync(frame: &LtcFrame, config: &Config) -> Result<String, ()> {
-    let dt_local = calculate_target_time(frame, config);
-
-    #[cfg(target_os = "linux")]
-    let (ts, success) = {
-        let ts = dt_local.format("%H:%M:%S.%3f").to_string();
-        let success = Command::new("sudo")
-            .arg("date")
-            .arg("-s")
-            .arg(&ts)
-            .status()
-            .map(|s| s.success())
-            .unwrap_or(false);
-        (ts, success)
-    };
+/// How far `offset` moves the target time at `frame_rate`.
+pub fn offset_duration(offset: &TimeturnerOffset, frame_rate: Ratio<i64>) -> Result<ChronoDuration, TargetTimeError> {
+    timecode::offset_us(offset, frame_rate)
+        .map(ChronoDuration::microseconds)
+        .ok_or(TargetTimeError::OutOfRange)
+}
 
+/// Arguments to `date` that set the clock to `dt_local` on this platform.
+fn date_args(dt_local: &DateTime<Local>) -> Vec<String> {
     #[cfg(target_os = "macos")]
-    let (ts, success) = {
+    {
         // macOS `date` command format is `mmddHHMMccyy.SS`
-        let ts = dt_local.format("%m%d%H%M%y.%S").to_string();
-        let success = Command::new("sudo")
-            .arg("date")
-            .arg(&ts)
-            .status()
-            .map(|s| s.success())
-            .unwrap_or(false);
-        (ts, success)
-    };
+        vec![dt_local.format("%m%d%H%M%y.%S").to_string()]
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        vec!["-s".to_string(), dt_local.format("%H:%M:%S.%3f").to_string()]
+    }
+}
+
+/// The command line `trigger_sync` runs for `frame`, as recorded in the audit trail.
+pub fn sync_command(frame: &LtcFrame, config: &Config) -> String {
+    match calculate_target_time(frame, config) {
+        Ok(dt_local) => format!("sudo date {}", date_args(&dt_local).join(" ")),
+        Err(e) => format!("sudo date (not run: {})", e),
+    }
+}
+
+/// How long a step waits for the next second boundary with `syncOnSecond`:
+/// a second of LTC, with some slack.
+pub const SECOND_BOUNDARY_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// Step `clock` to LTC, returning the command line for the audit trail and
+/// whether it succeeded. The clock is set to `frame`, unless `syncOnSecond`
+/// is on: then the step waits for the next LOCK frame 00 and is taken as it
+/// arrives, so the clock lands on a whole second instead of a frame that
+/// was read some milliseconds ago.
+pub fn step_to_ltc(
+    clock: &dyn ClockControl,
+    frame: &LtcFrame,
+    config: &Config,
+    state: &Mutex<LtcState>,
+) -> (String, bool) {
+    if !config.sync_on_second {
+        return (sync_command(frame, config), clock.step(frame, config).is_ok());
+    }
+    let armed = state.lock().unwrap().arm_second();
+    match armed.recv_timeout(SECOND_BOUNDARY_TIMEOUT) {
+        Ok(boundary) => (sync_command(&boundary, config), clock.step(&boundary, config).is_ok()),
+        Err(_) => {
+            log::warn!(
+                "No LOCK frame 00 within {} ms to step on; clock left alone.",
+                SECOND_BOUNDARY_TIMEOUT.as_millis()
+            );
+            ("sudo date (not run: no second boundary in LTC)".to_string(), false)
+        }
+    }
+}
+
+pub fn trigger_sync(frame: &LtcFrame, config: &Config) -> Result<String, ()> {
+    let dt_local = calculate_target_time(frame, config).map_err(|e| {
+        log::error!("Cannot sync to {:02}:{:02}:{:02}:{:02}: {}", frame.hours, frame.minutes, frame.seconds, frame.frames, e);
+    })?;
+    let args = date_args(&dt_local);
+    let ts = args.last().cloned().unwrap_or_default();
+
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    let success = Command::new("sudo")
+        .arg("date")
+        .args(&args)
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false);
 
     #[cfg(not(any(target_os = "linux", target_os = "macos")))]
-    let (ts, success) = {
+    let success = {
         // Unsupported OS, always fail
-        let ts = dt_local.format("%H:%M:%S.%3f").to_string();
         eprintln!("Unsupported OS for time synchronization");
-        (ts, false)
+        false
     };
 
     if success {
+        let mut vars = hooks::frame_vars(frame);
+        vars.push(("TIMETURNER_TARGET_TIME", ts.clone()));
+        hooks::fire(&config.hooks, HookEvent::SyncPerformed, &vars);
         Ok(ts)
     } else {
         Err(())
     }
 }
 
+/// The command line `nudge_clock` runs, as recorded in the audit trail.
+pub fn nudge_command(microseconds: i64) -> String {
+    format!("sudo adjtimex --singleshot {}", microseconds)
+}
+
 pub fn nudge_clock(microseconds: i64) -> Result<(), ()> {
     #[cfg(target_os = "linux")]
     {
@@ -146,6 +341,130 @@ pub fn nudge_clock(microseconds: i64) -> Result<(), ()> {
     }
 }
 
+/// Kernel clock rate settings, as `adjtimex --print` reports them. `tick`
+/// is microseconds per 1/100 s tick, one µs of it being 100 ppm; `frequency`
+/// adds up to ±500 ppm more in units of 2^-16 ppm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tuning {
+    pub tick: i64,
+    pub frequency: i64,
+}
+
+const NOMINAL_TICK: i64 = 10_000;
+/// The kernel refuses ticks more than 10% off nominal.
+const MAX_TICK_SHIFT: i64 = NOMINAL_TICK / 10;
+const FREQUENCY_PER_PPM: i64 = 65_536;
+const PPM_PER_TICK: i64 = 100;
+
+/// Fastest rate a ramp is planned at, in ppm, leaving headroom below the
+/// tick limit for whatever tuning is already in place.
+pub const MAX_RAMP_PPM: i64 = 90_000;
+
+/// How far the clock was behind the instant `frame` names when it arrived,
+/// in microseconds; negative when ahead. What a step would correct.
+pub fn clock_error_us(frame: &LtcFrame, config: &Config) -> Result<i64, TargetTimeError> {
+    let target = calculate_target_time(frame, config)?;
+    (target.with_timezone(&Utc) - frame.timestamp)
+        .num_microseconds()
+        .ok_or(TargetTimeError::OutOfRange)
+}
+
+/// The tuning that makes a clock running at `base` gain `microseconds`
+/// (lose, if negative) over `duration`, or `None` if the kernel can't run
+/// that fast or slow.
+pub fn ramp_tuning(base: Tuning, microseconds: i64, duration: Duration) -> Option<Tuning> {
+    if duration.is_zero() {
+        return None;
+    }
+    let ppm = microseconds as f64 / duration.as_secs_f64();
+    let total = base.frequency + (ppm * FREQUENCY_PER_PPM as f64).round() as i64;
+    // Whole hundreds of ppm go on the tick, leaving at most ±50 ppm of frequency.
+    let per_tick = PPM_PER_TICK * FREQUENCY_PER_PPM;
+    let ticks = (total as f64 / per_tick as f64).round() as i64;
+    let tuning = Tuning {
+        tick: base.tick + ticks,
+        frequency: total - ticks * per_tick,
+    };
+    ((tuning.tick - NOMINAL_TICK).abs() <= MAX_TICK_SHIFT).then_some(tuning)
+}
+
+/// Read `tick` and `frequency` from `adjtimex --print` output.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_tuning(output: &str) -> Option<Tuning> {
+    let field = |name: &str| {
+        output
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(key, _)| key.trim() == name)
+            .and_then(|(_, value)| value.trim().parse().ok())
+    };
+    Some(Tuning {
+        tick: field("tick")?,
+        frequency: field("frequency")?,
+    })
+}
+
+/// The ramp `ramp_clock` runs, as recorded in the audit trail.
+pub fn ramp_command(microseconds: i64, duration: Duration) -> String {
+    format!(
+        "sudo adjtimex --tick --frequency (ramp {:+} us over {} s)",
+        microseconds,
+        duration.as_secs()
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn set_tuning(tuning: Tuning) -> Result<(), ()> {
+    Command::new("sudo")
+        .arg("adjtimex")
+        .args(["--tick", &tuning.tick.to_string()])
+        .args(["--frequency", &tuning.frequency.to_string()])
+        .status()
+        .map_err(|_| ())
+        .and_then(|s| if s.success() { Ok(()) } else { Err(()) })
+}
+
+/// Gain `microseconds` over `duration` by running the clock fast or slow,
+/// then put the tuning back as it was. Blocks for `duration`.
+pub fn ramp_clock(microseconds: i64, duration: Duration) -> Result<(), ()> {
+    #[cfg(target_os = "linux")]
+    {
+        let base = Command::new("adjtimex")
+            .arg("--print")
+            .output()
+            .ok()
+            .and_then(|out| parse_tuning(&String::from_utf8_lossy(&out.stdout)))
+            .ok_or_else(|| log::error!("Failed to read the clock tuning with adjtimex"))?;
+        let ramp = ramp_tuning(base, microseconds, duration).ok_or_else(|| {
+            log::error!("Cannot ramp {} us over {} s: too fast for the kernel", microseconds, duration.as_secs());
+        })?;
+        set_tuning(ramp).map_err(|_| log::error!("Failed to start ramp with adjtimex"))?;
+        log::info!("Ramping clock {:+} us over {} s (tick {}, frequency {})", microseconds, duration.as_secs(), ramp.tick, ramp.frequency);
+        std::thread::sleep(duration);
+        set_tuning(base).map_err(|_| {
+            log::error!("Failed to restore tick {} and frequency {} after ramp", base.tick, base.frequency);
+        })
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (microseconds, duration);
+        log::warn!("Clock ramping is only supported on Linux.");
+        Err(())
+    }
+}
+
+/// The time `set_date` sets the clock to: 10:00 local time on `date`
+/// (`YYYY-MM-DD`). `None` if the date does not parse.
+pub fn set_date_target(date: &str) -> Option<DateTime<Local>> {
+    let day = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+    Local.from_local_datetime(&day.and_hms_opt(10, 0, 0)?).earliest()
+}
+
+/// The command line `set_date` runs, as recorded in the audit trail.
+pub fn set_date_command(date: &str) -> String {
+    format!("sudo date --set \"{} 10:00:00\"", date)
+}
+
 pub fn set_date(date: &str) -> Result<(), ()> {
     #[cfg(target_os = "linux")]
     {
@@ -174,11 +493,34 @@ pub fn set_date(date: &str) -> Result<(), ()> {
     }
 }
 
+/// Copy the system time to the RTC with `sudo hwclock --systohc`. The error
+/// is hwclock's own message.
+pub fn write_rtc() -> Result<(), String> {
+    #[cfg(target_os = "linux")]
+    {
+        let output = Command::new("sudo")
+            .args(["hwclock", "--systohc", "--utc"])
+            .output()
+            .map_err(|e| format!("cannot run hwclock: {}", e))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            log::error!("RTC write failed: {}", stderr);
+            Err(stderr)
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        Err("RTC write-back is only supported on Linux.".into())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::TimeturnerOffset;
-    use chrono::{Timelike, Utc};
+    use chrono::{FixedOffset, Utc};
     use num_rational::Ratio;
 
     // Helper to create a test frame
@@ -192,6 +534,7 @@ mod tests {
             is_drop_frame: false,
             frame_rate: Ratio::new(25, 1),
             timestamp: Utc::now(),
+            received: None,
         }
     }
 
@@ -206,26 +549,62 @@ mod tests {
     fn test_calculate_target_time_no_offset() {
         let frame = get_test_frame(10, 20, 30, 0);
         let config = Config::default();
-        let target_time = calculate_target_time(&frame, &config);
+        let target_time = calculate_target_time(&frame, &config).unwrap();
 
         assert_eq!(target_time.hour(), 10);
         assert_eq!(target_time.minute(), 20);
         assert_eq!(target_time.second(), 30);
     }
 
+    #[test]
+    fn test_calculate_target_time_frame_rates() {
+        // (rate, drop-frame, frame, frame offset, expected µs past the second)
+        let cases = [
+            (Ratio::new(24000, 1001), false, 23, 0, 959_292),
+            (Ratio::new(24, 1), false, 23, 0, 958_333),
+            (Ratio::new(25, 1), false, 24, 0, 960_000),
+            (Ratio::new(30000, 1001), true, 29, 0, 967_633),
+            (Ratio::new(30000, 1001), false, 15, 0, 500_500),
+            (Ratio::new(30, 1), false, 29, 0, 966_667),
+            (Ratio::new(50, 1), false, 49, 0, 980_000),
+            (Ratio::new(60000, 1001), true, 59, 0, 984_317),
+            (Ratio::new(60, 1), false, 59, 0, 983_333),
+            // One frame of offset at 29.97 is 33.367 ms, not a rounded 33.
+            (Ratio::new(30000, 1001), true, 0, 1, 33_367),
+            (Ratio::new(60000, 1001), false, 0, 3, 50_050),
+        ];
+        for (rate, df, frames, offset_frames, expected_us) in cases {
+            let frame = LtcFrame {
+                frames,
+                is_drop_frame: df,
+                frame_rate: rate,
+                ..get_test_frame(10, 20, 30, 0)
+            };
+            let config = Config {
+                timeturner_offset: TimeturnerOffset { frames: offset_frames, ..Default::default() },
+                ..Config::default()
+            };
+            let target = calculate_target_time(&frame, &config).unwrap();
+            assert_eq!(target.second(), 30, "{} fps frame {}", rate, frames);
+            assert_eq!(target.nanosecond() / 1000, expected_us, "{} fps frame {}", rate, frames);
+        }
+    }
+
     #[test]
     fn test_calculate_target_time_with_positive_offset() {
         let frame = get_test_frame(10, 20, 30, 0);
-        let mut config = Config::default();
-        config.timeturner_offset = TimeturnerOffset {
-            hours: 1,
-            minutes: 5,
-            seconds: 10,
-            frames: 12, // 12 frames at 25fps is 480ms
-            milliseconds: 20,
+        let config = Config {
+            timeturner_offset: TimeturnerOffset {
+                hours: 1,
+                minutes: 5,
+                seconds: 10,
+                frames: 12, // 12 frames at 25fps is 480ms
+                milliseconds: 20,
+            },
+            ..Config::default()
         };
 
-        let target_time = calculate_target_time(&frame, &config);
+        let target_time = calculate_target_time(&frame, &config).unwrap();
 
         assert_eq!(target_time.hour(), 11);
         assert_eq!(target_time.minute(), 25);
@@ -234,19 +613,47 @@ mod tests {
         assert_eq!(target_time.nanosecond(), 500_000_000);
     }
 
+    #[test]
+    fn test_calculate_target_time_in_ltc_zone() {
+        let frame = get_test_frame(10, 20, 30, 0);
+        for (zone, minutes_east) in [("utc", 0), ("+05:30", 330), ("-03:00", -180)] {
+            let config = Config { ltc_time_zone: zone.to_string(), ..Config::default() };
+            let target = calculate_target_time(&frame, &config).unwrap();
+            let in_zone = target.naive_utc() + ChronoDuration::minutes(minutes_east);
+            assert_eq!(
+                (in_zone.hour(), in_zone.minute(), in_zone.second()),
+                (10, 20, 30),
+                "ltcTimeZone {}",
+                zone
+            );
+        }
+    }
+
+    #[test]
+    fn test_ltc_zone_parse() {
+        assert_eq!("Local".parse::<LtcZone>(), Ok(LtcZone::Local));
+        assert_eq!("UTC".parse::<LtcZone>(), Ok(LtcZone::Utc));
+        assert_eq!("+01:00".parse::<LtcZone>().unwrap().describe(), "UTC+01:00");
+        assert!("Europe/London".parse::<LtcZone>().is_err());
+        let config = Config { ltc_time_zone: "bst".to_string(), ..Config::default() };
+        assert!(config.validate().unwrap_err().starts_with("ltcTimeZone"));
+    }
+
     #[test]
     fn test_calculate_target_time_with_negative_offset() {
         let frame = get_test_frame(10, 20, 30, 12); // 12 frames = 480ms
-        let mut config = Config::default();
-        config.timeturner_offset = TimeturnerOffset {
-            hours: -1,
-            minutes: -5,
-            seconds: -10,
-            frames: -12, // -480ms
-            milliseconds: -80,
+        let config = Config {
+            timeturner_offset: TimeturnerOffset {
+                hours: -1,
+                minutes: -5,
+                seconds: -10,
+                frames: -12, // -480ms
+                milliseconds: -80,
+            },
+            ..Config::default()
         };
 
-        let target_time = calculate_target_time(&frame, &config);
+        let target_time = calculate_target_time(&frame, &config).unwrap();
 
         assert_eq!(target_time.hour(), 9);
         assert_eq!(target_time.minute(), 15);
@@ -254,6 +661,182 @@ mod tests {
         assert_eq!(target_time.nanosecond(), 920_000_000);
     }
 
+    /// +01:00 in winter and +02:00 in summer, switching like Central Europe in
+    /// 2025: 02:00–03:00 is skipped on 30 March and repeated on 26 October.
+    #[derive(Clone, Copy, Debug)]
+    struct TestDst;
+
+    impl TestDst {
+        fn summer_starts() -> NaiveDateTime {
+            NaiveDate::from_ymd_opt(2025, 3, 30).unwrap().and_hms_opt(1, 0, 0).unwrap() // UTC
+        }
+        fn summer_ends() -> NaiveDateTime {
+            NaiveDate::from_ymd_opt(2025, 10, 26).unwrap().and_hms_opt(1, 0, 0).unwrap() // UTC
+        }
+        fn offset(summer: bool) -> FixedOffset {
+            FixedOffset::east_opt(if summer { 7200 } else { 3600 }).unwrap()
+        }
+    }
+
+    impl TimeZone for TestDst {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Self {
+            TestDst
+        }
+
+        /// The offset at local midnight; the changes are at 02:00 or 03:00.
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            let fits = |summer: bool| {
+                let utc = *local - Self::offset(summer);
+                let is_summer = utc >= Self::summer_starts() && utc < Self::summer_ends();
+                is_summer == summer
+            };
+            match (fits(false), fits(true)) {
+                (true, true) => LocalResult::Ambiguous(Self::offset(true), Self::offset(false)),
+                (true, false) => LocalResult::Single(Self::offset(false)),
+                (false, true) => LocalResult::Single(Self::offset(true)),
+                (false, false) => LocalResult::None,
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            Self::offset(*utc >= Self::summer_starts() && *utc < Self::summer_ends())
+        }
+    }
+
+    #[test]
+    fn test_target_time_in_dst_gap_uses_end_of_gap() {
+        let day = NaiveDate::from_ymd_opt(2025, 3, 30).unwrap();
+        let frame = get_test_frame(2, 30, 15, 0);
+        let target = target_time_on(&TestDst, day, &frame, &Config::default()).unwrap();
+        assert_eq!(target.naive_local(), day.and_hms_opt(3, 0, 0).unwrap());
+        assert_eq!(target.naive_utc(), TestDst::summer_starts());
+
+        // The offset is applied to the resolved instant, not the wall time.
+        let config = Config {
+            timeturner_offset: TimeturnerOffset { minutes: 10, ..TimeturnerOffset::default() },
+            ..Config::default()
+        };
+        let target = target_time_on(&TestDst, day, &frame, &config).unwrap();
+        assert_eq!(target.naive_local(), day.and_hms_opt(3, 10, 0).unwrap());
+    }
+
+    #[test]
+    fn test_target_time_when_ambiguous_uses_earlier_instant() {
+        let day = NaiveDate::from_ymd_opt(2025, 10, 26).unwrap();
+        let frame = get_test_frame(2, 30, 0, 0);
+        let target = target_time_on(&TestDst, day, &frame, &Config::default()).unwrap();
+        assert_eq!(target.offset().local_minus_utc(), 7200);
+        assert_eq!(target.naive_local(), day.and_hms_opt(2, 30, 0).unwrap());
+    }
+
+    #[test]
+    fn test_dst_fixture_dates_follow_the_season() {
+        let winter = NaiveDate::from_ymd_opt(2025, 1, 15).unwrap();
+        let summer = NaiveDate::from_ymd_opt(2025, 7, 15).unwrap();
+        assert_eq!(TestDst.offset_from_utc_date(&winter), TestDst::offset(false));
+        assert_eq!(TestDst.offset_from_utc_date(&summer), TestDst::offset(true));
+        assert_eq!(TestDst.offset_from_local_date(&summer), LocalResult::Single(TestDst::offset(true)));
+    }
+
+    #[test]
+    fn test_target_time_either_side_of_dst_changes() {
+        let spring = NaiveDate::from_ymd_opt(2025, 3, 30).unwrap();
+        let fall = NaiveDate::from_ymd_opt(2025, 10, 26).unwrap();
+        let utc_of = |day, h, m, s| {
+            target_time_on(&TestDst, day, &get_test_frame(h, m, s, 0), &Config::default())
+                .unwrap()
+                .naive_utc()
+        };
+        // Spring forward: 01:59:59 and 03:00:00 local are one second apart.
+        assert_eq!(utc_of(spring, 3, 0, 0) - utc_of(spring, 1, 59, 59), ChronoDuration::seconds(1));
+        // Fall back: 01:59:59 summer time is followed by the first 02:00:00,
+        // and 03:00:00 winter time is two hours after it.
+        assert_eq!(utc_of(fall, 2, 0, 0) - utc_of(fall, 1, 59, 59), ChronoDuration::seconds(1));
+        assert_eq!(utc_of(fall, 3, 0, 0) - utc_of(fall, 2, 0, 0), ChronoDuration::hours(2));
+    }
+
+    #[test]
+    fn test_target_time_out_of_range_is_an_error() {
+        let frame = get_test_frame(10, 0, 0, 0);
+        let config = Config {
+            timeturner_offset: TimeturnerOffset { hours: i64::MAX / 4, ..TimeturnerOffset::default() },
+            ..Config::default()
+        };
+        assert_eq!(calculate_target_time(&frame, &config), Err(TargetTimeError::OutOfRange));
+        assert_eq!(
+            target_time_on(&TestDst, NaiveDate::MAX, &get_test_frame(23, 59, 59, 0), &Config {
+                timeturner_offset: TimeturnerOffset { hours: 48, ..TimeturnerOffset::default() },
+                ..Config::default()
+            }),
+            Err(TargetTimeError::OutOfRange)
+        );
+        assert!(sync_command(&frame, &config).contains("not run"));
+    }
+
+    #[test]
+    fn test_command_descriptions() {
+        let frame = get_test_frame(10, 20, 30, 0);
+        let command = sync_command(&frame, &Config::default());
+        assert!(command.starts_with("sudo date "));
+        #[cfg(target_os = "linux")]
+        assert_eq!(command, "sudo date -s 10:20:30.000");
+        assert_eq!(nudge_command(-2000), "sudo adjtimex --singleshot -2000");
+        assert_eq!(set_date_command("2025-07-30"), "sudo date --set \"2025-07-30 10:00:00\"");
+        assert_eq!(
+            ramp_command(-40_000, Duration::from_secs(60)),
+            "sudo adjtimex --tick --frequency (ramp -40000 us over 60 s)"
+        );
+    }
+
+    #[test]
+    fn test_clock_error_us() {
+        let mut frame = get_test_frame(10, 20, 30, 0);
+        let config = Config::default();
+        let target = calculate_target_time(&frame, &config).unwrap().with_timezone(&Utc);
+        frame.timestamp = target - chrono::Duration::milliseconds(250);
+        assert_eq!(clock_error_us(&frame, &config), Ok(250_000));
+        // A one-frame offset moves the target on by 40 ms at 25 fps.
+        let offset = TimeturnerOffset { frames: 1, ..TimeturnerOffset::default() };
+        let config = Config { timeturner_offset: offset, ..Config::default() };
+        assert_eq!(clock_error_us(&frame, &config), Ok(290_000));
+    }
+
+    #[test]
+    fn test_ramp_tuning() {
+        let nominal = Tuning { tick: 10_000, frequency: 0 };
+        // Gaining 1 s over 100 s is 10000 ppm: all on the tick.
+        let fast = ramp_tuning(nominal, 1_000_000, Duration::from_secs(100)).unwrap();
+        assert_eq!(fast, Tuning { tick: 10_100, frequency: 0 });
+        // Losing 4 ms over 100 s is 40 ppm: all on the frequency.
+        let slow = ramp_tuning(nominal, -4_000, Duration::from_secs(100)).unwrap();
+        assert_eq!(slow, Tuning { tick: 10_000, frequency: -40 * 65_536 });
+        // Existing tuning is kept: 480 + 70 ppm is 6 ticks less 50 ppm.
+        let tuned = Tuning { tick: 10_000, frequency: 480 * 65_536 };
+        let more = ramp_tuning(tuned, 7_000, Duration::from_secs(100)).unwrap();
+        assert_eq!(more, Tuning { tick: 10_006, frequency: -50 * 65_536 });
+        // Faster than 10% can't be done.
+        assert!(ramp_tuning(nominal, 2_000_000, Duration::from_secs(10)).is_none());
+        assert!(ramp_tuning(nominal, 1_000, Duration::ZERO).is_none());
+    }
+
+    #[test]
+    fn test_parse_tuning() {
+        let output = "         mode: 0\n       offset: 0\n    frequency: -1234567\n\
+                      \x20        tick: 10001\n   raw time:  1700000000s 0us\n";
+        assert_eq!(parse_tuning(output), Some(Tuning { tick: 10_001, frequency: -1_234_567 }));
+        assert_eq!(parse_tuning("    frequency: 0\n"), None);
+    }
+
     #[test]
     fn test_nudge_clock_on_non_linux() {
         #[cfg(not(target_os = "linux"))]
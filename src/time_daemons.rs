@@ -0,0 +1,182 @@
+// src/time_daemons.rs
+//
+// Other clock disciplines fighting auto-sync. With auto-sync armed, a second
+// daemon steering the same clock (systemd-timesyncd, ntpd) undoes each sync
+// and leaves the delta wandering, a common field misconfiguration. chrony
+// is expected, since it serves the time out, but it must not follow network
+// servers itself: it should serve its local clock or the refclock fed by a
+// `chrony` sink. Conflicts are checked every `CHECK_INTERVAL`, logged when
+// they appear and listed in the status. With `stopCompetingTimeDaemons` the
+// competing services are also stopped and disabled.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+use crate::chrony::{self, ChronyReport};
+use crate::config::Config;
+use crate::health::Heartbeat;
+use crate::system;
+
+/// Services that discipline the clock on their own, by systemd unit name.
+pub const COMPETING_SERVICES: &[&str] = &["systemd-timesyncd", "ntp", "ntpd", "ntpsec", "openntpd"];
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Something other than auto-sync steering the clock.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeConflict {
+    /// The unit, e.g. `systemd-timesyncd` or `chrony`.
+    pub service: String,
+    pub detail: String,
+}
+
+static CONFLICTS: Mutex<Vec<TimeConflict>> = Mutex::new(Vec::new());
+
+/// The conflicts found by the latest check.
+pub fn current() -> Vec<TimeConflict> {
+    CONFLICTS.lock().unwrap().clone()
+}
+
+/// The services in `COMPETING_SERVICES` that `systemctl is-active` lists as
+/// `active`; its output has one state per unit, in order.
+pub fn parse_active(output: &str) -> Vec<&'static str> {
+    COMPETING_SERVICES
+        .iter()
+        .zip(output.lines())
+        .filter(|(_, state)| state.trim() == "active")
+        .map(|(service, _)| *service)
+        .collect()
+}
+
+fn active_services() -> Vec<&'static str> {
+    match Command::new("systemctl").arg("is-active").args(COMPETING_SERVICES).output() {
+        Ok(output) => parse_active(&String::from_utf8_lossy(&output.stdout)),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// What fights auto-sync: every active competing service and, if chrony has
+/// selected a network source, chrony. Nothing while auto-sync is off.
+pub fn assess(auto_sync: bool, active: &[&str], chrony: Option<&ChronyReport>) -> Vec<TimeConflict> {
+    if !auto_sync {
+        return Vec::new();
+    }
+    let mut conflicts: Vec<TimeConflict> = active
+        .iter()
+        .map(|service| TimeConflict {
+            service: service.to_string(),
+            detail: format!("{} is running and also disciplines the system clock.", service),
+        })
+        .collect();
+    let selected = chrony.and_then(|c| c.sources.iter().find(|s| s.state == "selected"));
+    if let Some(source) = selected.filter(|s| s.mode != "refclock") {
+        conflicts.push(TimeConflict {
+            service: "chrony".into(),
+            detail: format!(
+                "chrony follows the {} {}; serve the local clock or a chrony sink's refclock instead.",
+                source.mode, source.name
+            ),
+        });
+    }
+    conflicts
+}
+
+/// `sudo systemctl disable --now <service>`. The error is systemctl's own
+/// message.
+fn stop_service(service: &str) -> Result<(), String> {
+    let output = Command::new("sudo")
+        .args(["systemctl", "disable", "--now", service])
+        .output()
+        .map_err(|e| format!("cannot run systemctl: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Check for conflicts every `CHECK_INTERVAL`, stopping competing services
+/// once each when `stopCompetingTimeDaemons` is set. Runs until the process
+/// exits.
+pub fn run(config: Arc<Mutex<Config>>, heartbeat: Heartbeat) {
+    let mut last_check: Option<Instant> = None;
+    let mut tried: HashSet<&'static str> = HashSet::new();
+    loop {
+        heartbeat.beat();
+        thread::sleep(Duration::from_secs(1));
+        if last_check.is_some_and(|at| at.elapsed() < CHECK_INTERVAL) {
+            continue;
+        }
+        last_check = Some(Instant::now());
+        let config = config.lock().unwrap().clone();
+        let active = if cfg!(target_os = "linux") { active_services() } else { Vec::new() };
+        let chrony = system::ntp_service_active().then(chrony::query);
+        let conflicts = assess(config.auto_sync_enabled, &active, chrony.as_ref());
+
+        tried.retain(|service| active.contains(service));
+        if config.stop_competing_time_daemons && config.auto_sync_enabled {
+            for &service in active.iter().filter(|service| tried.insert(service)) {
+                match stop_service(service) {
+                    Ok(()) => log::info!("Stopped and disabled {}, which competed with auto-sync.", service),
+                    Err(e) => log::error!("Cannot stop {}: {}", service, e),
+                }
+            }
+        }
+
+        let mut latest = CONFLICTS.lock().unwrap();
+        for conflict in conflicts.iter().filter(|c| !latest.contains(c)) {
+            log::warn!("⚠️ Clock discipline conflict: {}", conflict.detail);
+        }
+        *latest = conflicts;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chrony::ChronySource;
+
+    fn source(mode: &str, state: &str, name: &str) -> ChronySource {
+        ChronySource {
+            mode: mode.into(),
+            state: state.into(),
+            name: name.into(),
+            stratum: 2,
+            poll: 6,
+            reach: 255,
+            last_rx_secs: Some(12),
+            offset_ms: 0.1,
+            error_ms: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_parse_active() {
+        assert_eq!(parse_active("active\ninactive\ninactive\nunknown\ninactive\n"), vec!["systemd-timesyncd"]);
+        assert_eq!(parse_active("inactive\nactive\n"), vec!["ntp"]);
+        assert!(parse_active("").is_empty());
+    }
+
+    #[test]
+    fn test_assess() {
+        let network = ChronyReport {
+            sources: vec![source("refclock", "combined", "LTC"), source("server", "selected", "192.168.1.1")],
+            ..ChronyReport::default()
+        };
+        let conflicts = assess(true, &["systemd-timesyncd"], Some(&network));
+        let services: Vec<&str> = conflicts.iter().map(|c| c.service.as_str()).collect();
+        assert_eq!(services, vec!["systemd-timesyncd", "chrony"]);
+        assert!(conflicts[1].detail.contains("server 192.168.1.1"));
+
+        let refclock = ChronyReport { sources: vec![source("refclock", "selected", "LTC")], ..ChronyReport::default() };
+        assert!(assess(true, &[], Some(&refclock)).is_empty());
+        assert!(assess(true, &[], None).is_empty());
+        assert!(assess(false, &["ntp"], Some(&network)).is_empty(), "only while auto-sync is armed");
+    }
+}
@@ -0,0 +1,401 @@
+// src/timecode.rs
+//
+// Timecode arithmetic. A `Timecode` is an HH:MM:SS:FF label at a frame rate,
+// drop-frame or not. Labels are numbered from midnight, leaving out the ones
+// drop-frame skips, so adding frames and measuring the distance between two
+// labels wrap cleanly at 24h. A label's time of day counts frames as real
+// time: the LTC source is pre-compensated, so 29.97 non-drop is not scaled
+// by 1000/1001.
+
+use num_rational::Ratio;
+use std::fmt;
+
+use crate::config::TimeturnerOffset;
+
+pub const SECS_PER_DAY: i64 = 24 * 3600;
+
+/// Frames per timecode second: the integer rate, 30 for 29.97 and so on.
+pub fn nominal_fps(rate: Ratio<i64>) -> i64 {
+    rate.ceil().to_integer()
+}
+
+/// Labels drop-frame skips at the start of each minute but every tenth.
+fn dropped_per_minute(rate: Ratio<i64>, drop_frame: bool) -> i64 {
+    if drop_frame { nominal_fps(rate) / 15 } else { 0 }
+}
+
+/// Whether a timecode label exists: frames below the nominal rate, and for
+/// drop-frame not one of the labels skipped at the start of each minute
+/// except every tenth (frames 0-1 at 29.97, 0-3 at 59.94).
+pub fn is_valid_label(minutes: u32, seconds: u32, frames: u32, rate: Ratio<i64>, is_drop_frame: bool) -> bool {
+    if minutes > 59 || seconds > 59 || frames as i64 >= nominal_fps(rate) {
+        return false;
+    }
+    let dropped = dropped_per_minute(rate, is_drop_frame);
+    !(seconds == 0 && !minutes.is_multiple_of(10) && (frames as i64) < dropped)
+}
+
+/// How far `offset` moves a time at `rate`, in µs: its frames at `rate`,
+/// rounded to the µs, plus the rest as wall-clock time. `None` on overflow.
+pub fn offset_us(offset: &TimeturnerOffset, rate: Ratio<i64>) -> Option<i64> {
+    let frames_us = (Ratio::from_integer(offset.frames.checked_mul(1_000_000)?) / rate).round().to_integer();
+    [(offset.hours, 3_600_000_000), (offset.minutes, 60_000_000), (offset.seconds, 1_000_000), (offset.milliseconds, 1000)]
+        .into_iter()
+        .try_fold(frames_us, |total, (value, us)| total.checked_add(value.checked_mul(us)?))
+}
+
+/// `offset_us` rounded to the ms: the net effect of `offset` at `rate`.
+pub fn offset_ms(offset: &TimeturnerOffset, rate: Ratio<i64>) -> Option<i64> {
+    Some(Ratio::new(offset_us(offset, rate)?, 1000).round().to_integer())
+}
+
+/// `offset` carried and borrowed into canonical form at `rate`, with the
+/// same `offset_ms`: every field takes the sign of the whole, minutes and
+/// seconds stay below 60, frames below the rate and milliseconds under a
+/// frame. `seconds: 1, frames: -30` becomes `frames: -5` at 25 fps but
+/// `milliseconds: -1` at 29.97. `None` on overflow.
+pub fn normalize_offset(offset: &TimeturnerOffset, rate: Ratio<i64>) -> Option<TimeturnerOffset> {
+    let net_ms = offset_ms(offset, rate)?;
+    let sign = net_ms.signum();
+    let rest = net_ms.abs();
+    let frames = (Ratio::new(rest % 1000, 1000) * rate).floor().to_integer();
+    let frames_ms = (Ratio::from_integer(frames * 1000) / rate).round().to_integer();
+    Some(TimeturnerOffset {
+        hours: sign * (rest / 3_600_000),
+        minutes: sign * (rest / 60_000 % 60),
+        seconds: sign * (rest / 1000 % 60),
+        frames: sign * frames,
+        milliseconds: sign * (rest % 1000 - frames_ms),
+    })
+}
+
+/// A timecode label at a frame rate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timecode {
+    pub hours: u32,
+    pub minutes: u32,
+    pub seconds: u32,
+    pub frames: u32,
+    pub rate: Ratio<i64>,
+    pub drop_frame: bool,
+}
+
+impl Timecode {
+    /// The label, or `None` if it does not exist at `rate`.
+    pub fn new(hours: u32, minutes: u32, seconds: u32, frames: u32, rate: Ratio<i64>, drop_frame: bool) -> Option<Self> {
+        let valid = hours < 24 && is_valid_label(minutes, seconds, frames, rate, drop_frame);
+        valid.then_some(Self { hours, minutes, seconds, frames, rate, drop_frame })
+    }
+
+    /// Labels in a day at `rate`.
+    pub fn frames_per_day(rate: Ratio<i64>, drop_frame: bool) -> i64 {
+        let minutes = SECS_PER_DAY / 60;
+        SECS_PER_DAY * nominal_fps(rate) - dropped_per_minute(rate, drop_frame) * (minutes - minutes / 10)
+    }
+
+    /// The label of frame `number` counted from midnight, wrapping at 24h.
+    pub fn from_frame_number(number: i64, rate: Ratio<i64>, drop_frame: bool) -> Self {
+        let nominal = nominal_fps(rate);
+        let mut number = number.rem_euclid(Self::frames_per_day(rate, drop_frame));
+        let dropped = dropped_per_minute(rate, drop_frame);
+        if dropped > 0 {
+            let per_minute = nominal * 60 - dropped;
+            let per_ten = per_minute * 10 + dropped;
+            let rem = number % per_ten;
+            let skipped = if rem < dropped { 0 } else { dropped * ((rem - dropped) / per_minute) };
+            number += dropped * 9 * (number / per_ten) + skipped;
+        }
+        let secs = number / nominal;
+        Self {
+            hours: (secs / 3600) as u32,
+            minutes: (secs / 60 % 60) as u32,
+            seconds: (secs % 60) as u32,
+            frames: (number % nominal) as u32,
+            rate,
+            drop_frame,
+        }
+    }
+
+    /// Number of this label counted from midnight, the inverse of
+    /// `from_frame_number`.
+    pub fn frame_number(&self) -> i64 {
+        let minutes = self.hours as i64 * 60 + self.minutes as i64;
+        (minutes * 60 + self.seconds as i64) * nominal_fps(self.rate) + self.frames as i64
+            - dropped_per_minute(self.rate, self.drop_frame) * (minutes - minutes / 10)
+    }
+
+    /// The label `frames` later, or earlier if negative, wrapping at 24h.
+    pub fn add_frames(&self, frames: i64) -> Self {
+        Self::from_frame_number(self.frame_number() + frames, self.rate, self.drop_frame)
+    }
+
+    /// Frames from here to `other`, at the same rate, the short way round
+    /// midnight: 1 for the next label, -1 for the one before.
+    pub fn frames_to(&self, other: &Timecode) -> i64 {
+        let per_day = Self::frames_per_day(self.rate, self.drop_frame);
+        (other.frame_number() - self.frame_number() + per_day / 2).rem_euclid(per_day) - per_day / 2
+    }
+
+    /// The time of day the label names, in seconds.
+    pub fn time_of_day(&self) -> Ratio<i64> {
+        let secs = self.hours as i64 * 3600 + self.minutes as i64 * 60 + self.seconds as i64;
+        Ratio::from_integer(secs) + Ratio::from_integer(self.frames as i64) / self.rate
+    }
+
+    /// `time_of_day` in µs, rounded; whole ms would put fractional-rate
+    /// frames (33.367 ms at 29.97) up to half a ms off.
+    pub fn time_of_day_us(&self) -> i64 {
+        (self.time_of_day() * 1_000_000).round().to_integer()
+    }
+
+    /// `time_of_day` in ms, rounded.
+    pub fn time_of_day_ms(&self) -> i64 {
+        (self.time_of_day() * 1000).round().to_integer()
+    }
+
+    /// The label running `ms` into the day, wrapping at 24h: the second it
+    /// falls in and the frame started within that second. As frames count
+    /// real time, at drop-frame rates this may be a label drop-frame skips.
+    pub fn at_time_of_day_ms(ms: i64, rate: Ratio<i64>, drop_frame: bool) -> Self {
+        let ms = ms.rem_euclid(SECS_PER_DAY * 1000);
+        let secs = ms / 1000;
+        Self {
+            hours: (secs / 3600) as u32,
+            minutes: (secs / 60 % 60) as u32,
+            seconds: (secs % 60) as u32,
+            frames: (Ratio::new(ms % 1000, 1000) * rate).floor().to_integer() as u32,
+            rate,
+            drop_frame,
+        }
+    }
+}
+
+/// As the reader prints it, `;` before the frames for drop-frame.
+impl fmt::Display for Timecode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sep = if self.drop_frame { ';' } else { ':' };
+        write!(f, "{:02}:{:02}:{:02}{}{:02}", self.hours, self.minutes, self.seconds, sep, self.frames)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every rate LTC runs at, with drop-frame where it exists.
+    fn rates() -> Vec<(Ratio<i64>, bool)> {
+        let mut rates: Vec<(Ratio<i64>, bool)> = [(24000, 1001), (24, 1), (25, 1), (30000, 1001), (30, 1), (50, 1), (60000, 1001), (60, 1)]
+            .into_iter()
+            .map(|(n, d)| (Ratio::new(n, d), false))
+            .collect();
+        rates.push((Ratio::new(30000, 1001), true));
+        rates.push((Ratio::new(60000, 1001), true));
+        rates
+    }
+
+    /// SplitMix64, so the property checks below see the same cases every run.
+    struct Cases(u64);
+
+    impl Cases {
+        fn next(&mut self) -> u64 {
+            self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = self.0;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        }
+
+        fn below(&mut self, n: i64) -> i64 {
+            (self.next() % n as u64) as i64
+        }
+
+        fn between(&mut self, lo: i64, hi: i64) -> i64 {
+            lo + self.below(hi - lo + 1)
+        }
+    }
+
+    #[test]
+    fn test_labels_and_numbers() {
+        let df = Ratio::new(30000, 1001);
+        let tc = |h, m, s, f| Timecode::new(h, m, s, f, df, true).unwrap();
+        assert_eq!(tc(0, 1, 0, 2).frame_number(), 1800);
+        assert_eq!(Timecode::from_frame_number(1799, df, true), tc(0, 0, 59, 29));
+        assert_eq!(Timecode::from_frame_number(1800, df, true), tc(0, 1, 0, 2));
+        assert_eq!(Timecode::from_frame_number(17982, df, true).to_string(), "00:10:00;00");
+        assert_eq!(Timecode::frames_per_day(df, true), 2_589_408);
+        assert_eq!(Timecode::frames_per_day(Ratio::from_integer(25), false), 25 * 86_400);
+        assert_eq!(Timecode::new(0, 1, 0, 1, df, true), None, "skipped by drop-frame");
+        assert_eq!(Timecode::new(24, 0, 0, 0, df, true), None);
+
+        let pal = Timecode::new(0, 8, 7, 11, Ratio::from_integer(25), false).unwrap();
+        assert_eq!(Timecode::from_frame_number(pal.frame_number(), pal.rate, false), pal);
+        assert_eq!(pal.to_string(), "00:08:07:11");
+        assert_eq!(pal.add_frames(-(pal.frame_number() + 1)).to_string(), "23:59:59:24");
+        assert_eq!(pal.time_of_day_ms(), 487_440);
+
+        let ntsc = Timecode::new(1, 2, 3, 1, Ratio::new(30000, 1001), false).unwrap();
+        assert_eq!(ntsc.time_of_day_us(), 3_723_033_367);
+        assert_eq!(ntsc.time_of_day_ms(), 3_723_033);
+    }
+
+    #[test]
+    fn test_time_of_day_label_wraps_at_midnight() {
+        let pal = Ratio::from_integer(25);
+        let last = (SECS_PER_DAY - 1) * 1000;
+        assert_eq!(Timecode::at_time_of_day_ms(last + 480, pal, false).to_string(), "23:59:59:12");
+        assert_eq!(Timecode::at_time_of_day_ms(last + 1040, pal, false).to_string(), "00:00:00:01");
+        assert_eq!(Timecode::at_time_of_day_ms(-960, pal, false).to_string(), "23:59:59:01");
+        assert_eq!(Timecode::at_time_of_day_ms(last + 500, Ratio::new(30000, 1001), true).to_string(), "23:59:59;14");
+    }
+
+    #[test]
+    fn test_offset_us() {
+        let offset = |hours, minutes, seconds, frames, milliseconds| TimeturnerOffset {
+            hours,
+            minutes,
+            seconds,
+            frames,
+            milliseconds,
+        };
+        assert_eq!(offset_us(&offset(1, -2, 3, 12, -5), Ratio::from_integer(25)), Some(3_483_475_000));
+        assert_eq!(offset_us(&offset(0, 0, 0, 1, 0), Ratio::new(30000, 1001)), Some(33_367));
+        assert_eq!(offset_us(&offset(0, 0, 0, -1, 0), Ratio::new(30000, 1001)), Some(-33_367));
+        assert_eq!(offset_us(&offset(i64::MAX, 0, 0, 0, 0), Ratio::from_integer(25)), None);
+        assert_eq!(offset_us(&offset(0, 0, 0, i64::MAX, 0), Ratio::from_integer(25)), None);
+    }
+
+    #[test]
+    fn test_normalize_offset() {
+        let pal = Ratio::from_integer(25);
+        let ntsc = Ratio::new(30000, 1001);
+        let borrowed = TimeturnerOffset { seconds: 1, frames: -30, ..TimeturnerOffset::default() };
+        assert_eq!(normalize_offset(&borrowed, pal), Some(TimeturnerOffset { frames: -5, ..TimeturnerOffset::default() }));
+        assert_eq!(normalize_offset(&borrowed, ntsc), Some(TimeturnerOffset { milliseconds: -1, ..TimeturnerOffset::default() }));
+        assert_eq!(offset_ms(&borrowed, ntsc), Some(-1));
+
+        let carried = TimeturnerOffset { hours: 0, minutes: 59, seconds: 61, frames: 27, milliseconds: 30 };
+        assert_eq!(
+            normalize_offset(&carried, pal),
+            Some(TimeturnerOffset { hours: 1, minutes: 0, seconds: 2, frames: 2, milliseconds: 30 })
+        );
+        assert_eq!(normalize_offset(&TimeturnerOffset::default(), pal), Some(TimeturnerOffset::default()));
+        assert_eq!(normalize_offset(&TimeturnerOffset { hours: i64::MAX, ..TimeturnerOffset::default() }, pal), None);
+    }
+
+    // Properties over seeded pseudo-random cases, at every rate.
+
+    #[test]
+    fn prop_frame_numbers_round_trip_through_valid_labels() {
+        let mut cases = Cases(1);
+        for (rate, drop_frame) in rates() {
+            let per_day = Timecode::frames_per_day(rate, drop_frame);
+            for _ in 0..2000 {
+                let number = cases.below(per_day);
+                let tc = Timecode::from_frame_number(number, rate, drop_frame);
+                assert_eq!(Timecode::new(tc.hours, tc.minutes, tc.seconds, tc.frames, rate, drop_frame), Some(tc));
+                assert_eq!(tc.frame_number(), number, "{} at {}", tc, rate);
+                let wrapped = cases.between(-3, 3) * per_day + number;
+                assert_eq!(Timecode::from_frame_number(wrapped, rate, drop_frame), tc, "wraps at 24h");
+            }
+        }
+    }
+
+    #[test]
+    fn prop_adding_frames_is_consistent() {
+        let mut cases = Cases(2);
+        for (rate, drop_frame) in rates() {
+            let per_day = Timecode::frames_per_day(rate, drop_frame);
+            for _ in 0..2000 {
+                let tc = Timecode::from_frame_number(cases.below(per_day), rate, drop_frame);
+                let (a, b) = (cases.between(-2 * per_day, 2 * per_day), cases.between(-per_day, per_day));
+                assert_eq!(tc.add_frames(a).add_frames(b), tc.add_frames(a + b), "{} + {} + {}", tc, a, b);
+                assert_eq!(tc.add_frames(a).add_frames(-a), tc);
+                assert_eq!(tc.add_frames(per_day), tc);
+                let short = cases.between(-per_day / 2 + 1, per_day / 2 - 1);
+                assert_eq!(tc.frames_to(&tc.add_frames(short)), short, "{} + {}", tc, short);
+                assert_eq!(tc.frames_to(&tc.add_frames(1)), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn prop_next_label_is_one_frame_on() {
+        let mut cases = Cases(3);
+        for (rate, drop_frame) in rates() {
+            let per_day = Timecode::frames_per_day(rate, drop_frame);
+            let frame_us = Ratio::new(1_000_000, 1) / rate;
+            for _ in 0..2000 {
+                let tc = Timecode::from_frame_number(cases.below(per_day - 1), rate, drop_frame);
+                let next = tc.add_frames(1);
+                let step = next.time_of_day() - tc.time_of_day();
+                assert!(step > Ratio::from_integer(0), "{} then {}", tc, next);
+                // Counting frames as real time, a second ends with a short
+                // frame at fractional rates and drop-frame skips labels.
+                let step_us = step * 1_000_000;
+                let skipped = Ratio::from_integer(dropped_per_minute(rate, drop_frame) + 1) * frame_us;
+                assert!(step_us <= skipped, "{} then {}", tc, next);
+                let rounding = Ratio::from_integer(tc.time_of_day_us()) - tc.time_of_day() * 1_000_000;
+                assert!(rounding <= Ratio::new(1, 2) && rounding >= Ratio::new(-1, 2), "{}", tc);
+            }
+        }
+    }
+
+    #[test]
+    fn prop_time_of_day_label_holds_the_time() {
+        let mut cases = Cases(4);
+        for (rate, drop_frame) in rates() {
+            for _ in 0..2000 {
+                let ms = cases.between(-SECS_PER_DAY * 1000, 2 * SECS_PER_DAY * 1000);
+                let tc = Timecode::at_time_of_day_ms(ms, rate, drop_frame);
+                let into_day = ms.rem_euclid(SECS_PER_DAY * 1000);
+                assert!((tc.frames as i64) < nominal_fps(rate));
+                // The frame started at or before the time, and the next one
+                // has not.
+                assert!(tc.time_of_day_ms() <= into_day + 1, "{} for {} ms", tc, into_day);
+                let start = tc.time_of_day() * 1000;
+                assert!(Ratio::from_integer(into_day) < start + Ratio::from_integer(1000) / rate, "{} for {} ms", tc, into_day);
+            }
+        }
+    }
+
+    #[test]
+    fn prop_offsets_add_up_negate_and_normalize() {
+        let mut cases = Cases(5);
+        for (rate, _) in rates() {
+            for _ in 0..2000 {
+                let mut value = || cases.between(-100_000, 100_000);
+                let offset = TimeturnerOffset {
+                    hours: value(),
+                    minutes: value(),
+                    seconds: value(),
+                    frames: value(),
+                    milliseconds: value(),
+                };
+                let negated = TimeturnerOffset {
+                    hours: -offset.hours,
+                    minutes: -offset.minutes,
+                    seconds: -offset.seconds,
+                    frames: -offset.frames,
+                    milliseconds: -offset.milliseconds,
+                };
+                let us = offset_us(&offset, rate).unwrap();
+                assert_eq!(offset_us(&negated, rate), Some(-us), "rounds symmetrically");
+                let frames_only = TimeturnerOffset { frames: offset.frames, ..TimeturnerOffset::default() };
+                let rest = TimeturnerOffset { frames: 0, ..offset };
+                assert_eq!(offset_us(&frames_only, rate).unwrap() + offset_us(&rest, rate).unwrap(), us);
+                let normalized = normalize_offset(&offset, rate).unwrap();
+                assert_eq!(offset_ms(&normalized, rate), offset_ms(&offset, rate), "{:?} at {}", offset, rate);
+                assert_eq!(normalize_offset(&normalized, rate).as_ref(), Some(&normalized), "{:?} at {}", offset, rate);
+                let fields = [normalized.hours, normalized.minutes, normalized.seconds, normalized.frames, normalized.milliseconds];
+                assert!(fields.iter().all(|f| f.signum() * us.signum() >= 0), "{:?} at {}", normalized, rate);
+                assert!(normalized.minutes.abs() < 60 && normalized.seconds.abs() < 60);
+                assert!(normalized.frames.abs() < nominal_fps(rate));
+                assert!(Ratio::from_integer(normalized.milliseconds.abs()) < Ratio::from_integer(1000) / rate + Ratio::new(1, 2));
+                let whole_seconds = TimeturnerOffset { frames: nominal_fps(rate) * offset.seconds, ..TimeturnerOffset::default() };
+                if rate.is_integer() {
+                    assert_eq!(offset_us(&whole_seconds, rate), Some(offset.seconds * 1_000_000));
+                }
+            }
+        }
+    }
+}
@@ -1,234 +1,815 @@
 ﻿use std::{
     io::{stdout, Write},
     process::{self},
-    sync::{Arc, Mutex},
     thread,
     time::{Duration, Instant},
 };
 use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver};
 
-use chrono::{
-    DateTime, Local, Timelike, Utc,
-};
 use crossterm::{
     cursor::{Hide, MoveTo, Show},
     event::{poll, read, Event, KeyCode},
     execute, queue,
-    style::{Color, Print, ResetColor, SetForegroundColor},
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
     terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
 };
 
+use log::LevelFilter;
+
+use crate::api::{ApiStatus, OffsetPreview, SyncPreview};
+use crate::chrony::ChronyReport;
+use crate::client::{ApiClient, ApiMessage};
 use crate::config::Config;
-use crate::sync_logic::{get_jitter_status, get_sync_status, LtcState};
-use crate::system;
-use get_if_addrs::get_if_addrs;
-use num_rational::Ratio;
-use num_traits::ToPrimitive;
+use crate::presets;
+use crate::sync_logic::Staleness;
 
+/// How long to wait before reconnecting a dropped status stream.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
 
-pub fn start_ui(
-    state: Arc<Mutex<LtcState>>,
-    serial_port: String,
-    config: Arc<Mutex<Config>>,
-) {
+/// How often the daemon's log buffer is fetched.
+const LOG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Daemon log lines kept for scrollback, beyond the daemon's own 100.
+const SCROLLBACK: usize = 1000;
+
+/// How often the daemon's config is fetched for the Config page.
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often chrony's tracking state is fetched.
+const CHRONY_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long to wait for a key press before checking for a new status.
+const KEY_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Follow the daemon's status stream on a background thread, forwarding each
+/// status (or connection error) to the UI and reconnecting when it drops.
+fn spawn_status_feed(client: ApiClient) -> Receiver<Result<ApiStatus, String>> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || loop {
+        match client.status_stream() {
+            Ok(stream) => {
+                for status in stream {
+                    if tx.send(status).is_err() {
+                        return;
+                    }
+                }
+            }
+            Err(e) => {
+                if tx.send(Err(e)).is_err() {
+                    return;
+                }
+            }
+        }
+        thread::sleep(RECONNECT_DELAY);
+    });
+    rx
+}
+
+/// Call `fetch` on a background thread every `interval`, forwarding each
+/// result to the UI.
+fn spawn_poller<T: Send + 'static>(
+    interval: Duration,
+    fetch: impl Fn() -> Result<T, String> + Send + 'static,
+) -> Receiver<Result<T, String>> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || loop {
+        if tx.send(fetch()).is_err() {
+            return;
+        }
+        thread::sleep(interval);
+    });
+    rx
+}
+
+/// The level in a `2025-08-07 10:00:00 [INFO] ...` log line.
+fn line_level(line: &str) -> Option<log::Level> {
+    let (_, rest) = line.split_once('[')?;
+    let (level, _) = rest.split_once(']')?;
+    level.parse().ok()
+}
+
+/// The TUI's log pane: daemon log lines with scrollback beyond the daemon's
+/// ring buffer, a minimum level, and how far the view is scrolled back.
+#[derive(Debug)]
+struct LogPane {
+    lines: VecDeque<String>,
+    min_level: LevelFilter,
+    /// Shown lines between the bottom of the view and the newest one.
+    scroll: usize,
+}
+
+impl Default for LogPane {
+    fn default() -> Self {
+        Self {
+            lines: VecDeque::with_capacity(SCROLLBACK),
+            min_level: LevelFilter::Trace,
+            scroll: 0,
+        }
+    }
+}
+
+impl LogPane {
+    fn shows(&self, line: &str) -> bool {
+        line_level(line).is_none_or(|level| level <= self.min_level)
+    }
+
+    /// Append the lines of a fresh copy of the daemon's buffer that are not
+    /// already here: whatever follows the longest run matching our tail.
+    /// A view scrolled back stays on the same lines.
+    fn merge(&mut self, buffer: Vec<String>) -> bool {
+        let overlap = (1..=buffer.len().min(self.lines.len()))
+            .rev()
+            .find(|&n| self.lines.range(self.lines.len() - n..).eq(buffer[..n].iter()))
+            .unwrap_or(0);
+        let new = &buffer[overlap..];
+        if self.scroll > 0 {
+            self.scroll += new.iter().filter(|line| self.shows(line)).count();
+        }
+        for line in new {
+            if self.lines.len() == SCROLLBACK {
+                self.lines.pop_front();
+            }
+            self.lines.push_back(line.clone());
+        }
+        !new.is_empty()
+    }
+
+    fn set_min_level(&mut self, level: LevelFilter) {
+        self.min_level = level;
+        self.scroll = 0;
+    }
+
+    /// Scroll back (`lines` > 0) or forward, staying within the scrollback.
+    fn scroll_by(&mut self, lines: isize, height: usize) {
+        let shown = self.lines.iter().filter(|line| self.shows(line)).count();
+        let max = shown.saturating_sub(height);
+        self.scroll = self.scroll.saturating_add_signed(lines).min(max);
+    }
+
+    /// The `height` lines to draw, oldest first.
+    fn visible(&self, height: usize) -> Vec<&String> {
+        let shown: Vec<&String> = self.lines.iter().filter(|line| self.shows(line)).collect();
+        let end = shown.len().saturating_sub(self.scroll);
+        shown[end.saturating_sub(height)..end].to_vec()
+    }
+}
+
+/// The TUI's pages, in tab order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Page {
+    Overview,
+    Clock,
+    Ltc,
+    Serial,
+    Config,
+    Logs,
+}
+
+impl Page {
+    const ALL: [Page; 6] = [Page::Overview, Page::Clock, Page::Ltc, Page::Serial, Page::Config, Page::Logs];
+
+    fn title(self) -> &'static str {
+        match self {
+            Page::Overview => "Overview",
+            Page::Clock => "Clock",
+            Page::Ltc => "LTC",
+            Page::Serial => "Serial",
+            Page::Config => "Config",
+            Page::Logs => "Logs",
+        }
+    }
+
+    fn index(self) -> usize {
+        Page::ALL.iter().position(|p| *p == self).unwrap()
+    }
+
+    /// The page `step` tabs along, wrapping around.
+    fn cycle(self, step: isize) -> Page {
+        let n = Page::ALL.len() as isize;
+        Page::ALL[(self.index() as isize + step).rem_euclid(n) as usize]
+    }
+
+    /// The page for number key `c` (`1` is the first).
+    fn from_key(c: char) -> Option<Page> {
+        let n = c.to_digit(10)? as usize;
+        Page::ALL.get(n.checked_sub(1)?).copied()
+    }
+}
+
+/// First screen row of a page's content, below the title and tab bar.
+const CONTENT_TOP: u16 = 4;
+
+/// Rows available to the log page in the current terminal, between the
+/// content top and the footer.
+fn log_height() -> usize {
+    let (_, rows) = terminal::size().unwrap_or((80, 24));
+    rows.saturating_sub(CONTENT_TOP + 3) as usize
+}
+
+/// What the UI draws from, gathered in one place.
+struct View {
+    page: Page,
+    status: Result<ApiStatus, String>,
+    config: Result<Config, String>,
+    chrony: Result<ChronyReport, String>,
+    logs: LogPane,
+    last_action: String,
+    /// A previewed sync waiting on a second `s`: its token, if the step
+    /// needs confirming, and whether it is over `maxAutoStepMs`.
+    pending_sync: Option<(Option<String>, bool)>,
+    /// A previewed preset switch waiting on a second `p`: its name, its
+    /// token if the re-sync needs confirming, and whether it is over
+    /// `maxAutoStepMs`.
+    pending_preset: Option<(String, Option<String>, bool)>,
+    delta_ms: i64,
+    delta_frames: String,
+}
+
+pub fn start_ui(client: ApiClient) {
     let mut stdout = stdout();
     execute!(stdout, EnterAlternateScreen, Hide).unwrap();
     terminal::enable_raw_mode().unwrap();
 
-    let mut logs: VecDeque<String> = VecDeque::with_capacity(10);
+    let mut view = View {
+        page: Page::Overview,
+        status: Err("connecting…".into()),
+        config: Err("loading…".into()),
+        chrony: Err("loading…".into()),
+        logs: LogPane::default(),
+        last_action: String::new(),
+        pending_sync: None,
+        pending_preset: None,
+        delta_ms: 0,
+        delta_frames: "+0 frames".to_string(),
+    };
     let mut last_delta_update = Instant::now() - Duration::from_secs(1);
-    let mut cached_delta_ms: i64 = 0;
-    let mut cached_delta_frames: i64 = 0;
+    let feed = spawn_status_feed(client.clone());
+    let log_client = client.clone();
+    let log_feed = spawn_poller(LOG_POLL_INTERVAL, move || log_client.logs());
+    let config_client = client.clone();
+    let config_feed = spawn_poller(CONFIG_POLL_INTERVAL, move || config_client.config());
+    let chrony_client = client.clone();
+    let chrony_feed = spawn_poller(CHRONY_POLL_INTERVAL, move || chrony_client.chrony());
+    // Redraw only when something on screen has changed.
+    let mut dirty = true;
 
     loop {
-        // 1️⃣ config
-        let cfg = config.lock().unwrap().clone();
-        let hw_offset_ms = cfg.hardware_offset_ms;
-
-        // 2️⃣ Chrony + interfaces
-        let ntp_active = system::ntp_service_active();
-        let interfaces: Vec<String> = get_if_addrs()
-            .unwrap_or_default()
-            .into_iter()
-            .filter(|ifa| !ifa.is_loopback())
-            .map(|ifa| ifa.ip().to_string())
-            .collect();
-
-        // 3️⃣ jitter
-        {
-            let mut st = state.lock().unwrap();
-            if let Some(frame) = st.latest.clone() {
-                if frame.status == "LOCK" {
-                    // jitter
-                    let now_utc = Utc::now();
-                    let raw = (now_utc - frame.timestamp).num_milliseconds();
-                    let measured = raw - hw_offset_ms;
-                    st.record_offset(measured);
-                }
+        // 1️⃣ take the newest status pushed by the daemon
+        while let Ok(next) = feed.try_recv() {
+            view.status = next;
+            dirty = true;
+        }
+        while let Ok(buffer) = log_feed.try_recv() {
+            if let Ok(buffer) = buffer {
+                dirty |= view.logs.merge(buffer) && view.page == Page::Logs;
             }
         }
+        while let Ok(config) = config_feed.try_recv() {
+            view.config = config;
+            dirty |= view.page == Page::Config;
+        }
+        while let Ok(chrony) = chrony_feed.try_recv() {
+            view.chrony = chrony;
+            dirty |= view.page == Page::Overview;
+        }
 
-        // 4️⃣ averages & status override
-        let (avg_jitter_ms, _avg_frames, _, lock_ratio, avg_delta) = {
-            let st = state.lock().unwrap();
-            (
-                st.average_jitter(),
-                st.average_frames(),
-                st.timecode_match().to_string(),
-                st.lock_ratio(),
-                st.get_ewma_clock_delta(),
-            )
-        };
-
-        // 5️⃣ cache Δ once/sec & Δ in frames
+        // 2️⃣ cache Δ once/sec
         if last_delta_update.elapsed() >= Duration::from_secs(1) {
-            cached_delta_ms = avg_delta;
-            if let Some(frame) = &state.lock().unwrap().latest {
-                let delta_ms_ratio = Ratio::new(avg_delta, 1);
-                let frames_ratio = delta_ms_ratio * frame.frame_rate / Ratio::new(1000, 1);
-                cached_delta_frames = frames_ratio.round().to_integer();
-            } else {
-                cached_delta_frames = 0;
+            if let Ok(st) = &view.status {
+                view.delta_ms = st.timecode_delta_ms;
+                view.delta_frames = st.delta_frames_text();
             }
             last_delta_update = Instant::now();
         }
 
-        // 6️⃣ sync status wording
-        let sync_status = get_sync_status(cached_delta_ms, &cfg);
-
-        // 7️⃣ header & LTC metrics display
-        {
-            let st = state.lock().unwrap();
-            let opt = st.latest.as_ref();
-            let status_str = opt.map(|f| f.status.as_str()).unwrap_or("(waiting)");
-            let tc_str = match opt {
-                Some(f) => format!("LTC Timecode     : {:02}:{:02}:{:02}:{:02}",
-                                   f.hours, f.minutes, f.seconds, f.frames),
-                None => "LTC Timecode     : …".to_string(),
-            };
-            let fr_str = match opt {
-                Some(f) => format!("Frame Rate       : {:.2}fps", f.frame_rate.to_f64().unwrap_or(0.0)),
-                None => "Frame Rate       : …".to_string(),
-            };
-
-            queue!(
-                stdout,
-                MoveTo(0, 0), Clear(ClearType::All),
-                MoveTo(2, 1), Print("Have Blue - NTP Timeturner"),
-                MoveTo(2, 2), Print(format!("Serial Port      : {}", serial_port)),
-                MoveTo(2, 3), Print(format!("Chrony Service   : {}",
-                    if ntp_active { "RUNNING" } else { "MISSING" })),
-                MoveTo(2, 4), Print(format!("Interfaces       : {}",
-                    interfaces.join(", "))),
-                MoveTo(2, 6), Print(format!("LTC Status       : {}", status_str)),
-                MoveTo(2, 7), Print(tc_str),
-                MoveTo(2, 8), Print(fr_str),
-            ).unwrap();
-        }
-
-        // system clock
-        let now_local: DateTime<Local> = DateTime::from(Utc::now());
-        let sys_ts = format!(
-            "{:02}:{:02}:{:02}.{:03}",
-            now_local.hour(),
-            now_local.minute(),
-            now_local.second(),
-            now_local.timestamp_subsec_millis(),
-        );
-        queue!(stdout,
-            MoveTo(2, 9), Print(format!(
-                "System Clock     : {}",
-                sys_ts
-            ))).unwrap();
-
-        // Δ display
-        let dcol = if cached_delta_ms.abs() < 20 {
-            Color::Green
-        } else if cached_delta_ms.abs() < 100 {
-            Color::Yellow
+        if dirty {
+            draw(&mut stdout, &client, &view);
+            dirty = false;
+        }
+
+        // page switching, manual sync, log scrolling and filtering, quit
+        if poll(KEY_POLL_INTERVAL).unwrap() {
+            let page = log_height().max(1) as isize;
+            match read().unwrap() {
+                Event::Key(evt) => {
+                    dirty = true;
+                    match evt.code {
+                        KeyCode::Char(c) if c.eq_ignore_ascii_case(&'q') => {
+                            execute!(stdout, Show, LeaveAlternateScreen).unwrap();
+                            terminal::disable_raw_mode().unwrap();
+                            process::exit(0);
+                        }
+                        KeyCode::Char(c) if c.eq_ignore_ascii_case(&'s') => {
+                            // A step over `confirmStepMs` or `maxAutoStepMs`
+                            // is shown first and only runs on a second `s`.
+                            let outcome = |result: Result<ApiMessage, String>| match result {
+                                Ok(msg) => format!("✔ {}", msg.message),
+                                Err(e) => format!("❌ {}", e),
+                            };
+                            view.last_action = match view.pending_sync.take() {
+                                Some((token, true)) => outcome(client.sync_override(token.as_deref())),
+                                Some((Some(token), false)) => outcome(client.sync_confirmed(&token)),
+                                Some((None, false)) => outcome(client.sync()),
+                                None => match client.sync_preview() {
+                                    Ok(SyncPreview { token, over_step_limit, step_ms, target_time, .. })
+                                        if token.is_some() || over_step_limit =>
+                                    {
+                                        view.pending_sync = Some((token, over_step_limit));
+                                        format!(
+                                            "⚠ Sync steps the clock {:+} ms to {}{}; press s again to confirm",
+                                            step_ms,
+                                            target_time.format("%H:%M:%S%.3f"),
+                                            if over_step_limit { ", over maxAutoStepMs" } else { "" }
+                                        )
+                                    }
+                                    _ => outcome(client.sync()),
+                                },
+                            };
+                        }
+                        KeyCode::Char(c) if c.eq_ignore_ascii_case(&'p') => {
+                            // A switch that steps the clock over `confirmStepMs`
+                            // or `maxAutoStepMs` is shown first and only runs on
+                            // a second `p`.
+                            let next = view.config.as_ref().ok().and_then(|config| {
+                                presets::next(&config.offset_presets, &config.timeturner_offset)
+                                    .map(|preset| (preset.name.clone(), preset.offset.clone()))
+                            });
+                            let mut outcome = |name: &str, result: Result<Config, String>| match result {
+                                Ok(saved) => {
+                                    view.config = Ok(saved);
+                                    format!("✔ Offset preset '{}'", name)
+                                }
+                                Err(e) => format!("❌ {}", e),
+                            };
+                            view.last_action = match (view.pending_preset.take(), next) {
+                                (Some((name, token, over_step_limit)), _) => {
+                                    let result = match (token, over_step_limit) {
+                                        (token, true) => client.activate_preset_override(&name, token.as_deref()),
+                                        (Some(token), false) => client.activate_preset_confirmed(&name, &token),
+                                        (None, false) => client.activate_preset(&name),
+                                    };
+                                    outcome(&name, result)
+                                }
+                                (None, Some((name, offset))) => match client.offset_preview(&offset) {
+                                    Ok(OffsetPreview { token, over_step_limit, step_ms: Some(step_ms), .. })
+                                        if token.is_some() || over_step_limit =>
+                                    {
+                                        let warning = format!(
+                                            "⚠ Preset '{}' steps the clock {:+} ms{}; press p again to confirm",
+                                            name,
+                                            step_ms,
+                                            if over_step_limit { ", over maxAutoStepMs" } else { "" }
+                                        );
+                                        view.pending_preset = Some((name, token, over_step_limit));
+                                        warning
+                                    }
+                                    _ => {
+                                        let result = client.activate_preset(&name);
+                                        outcome(&name, result)
+                                    }
+                                },
+                                (None, None) => "❌ No offset presets configured".to_string(),
+                            };
+                        }
+                        KeyCode::Tab => view.page = view.page.cycle(1),
+                        KeyCode::BackTab => view.page = view.page.cycle(-1),
+                        KeyCode::Char(c) if Page::from_key(c).is_some() => {
+                            view.page = Page::from_key(c).unwrap();
+                        }
+                        KeyCode::PageUp if view.page == Page::Logs => view.logs.scroll_by(page, page as usize),
+                        KeyCode::PageDown if view.page == Page::Logs => view.logs.scroll_by(-page, page as usize),
+                        KeyCode::End if view.page == Page::Logs => view.logs.scroll = 0,
+                        KeyCode::Char(c) if view.page == Page::Logs => {
+                            let level = match c.to_ascii_lowercase() {
+                                'a' => LevelFilter::Trace,
+                                'i' => LevelFilter::Info,
+                                'w' => LevelFilter::Warn,
+                                'e' => LevelFilter::Error,
+                                _ => continue,
+                            };
+                            view.logs.set_min_level(level);
+                        }
+                        _ => dirty = false,
+                    }
+                }
+                Event::Resize(..) => dirty = true,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Print `label : value` on `row`, optionally in `color`.
+fn field(stdout: &mut std::io::Stdout, row: u16, label: &str, value: impl std::fmt::Display, color: Option<Color>) {
+    queue!(
+        stdout,
+        MoveTo(2, row),
+        SetForegroundColor(color.unwrap_or(Color::Reset)),
+        Print(format!("{:<16} : {}", label, value)),
+        ResetColor,
+    ).unwrap();
+}
+
+fn draw(stdout: &mut std::io::Stdout, client: &ApiClient, view: &View) {
+    // header and tab bar
+    queue!(
+        stdout,
+        MoveTo(0, 0), Clear(ClearType::All),
+        MoveTo(2, 1), Print("Have Blue - NTP Timeturner"),
+        MoveTo(2, 2),
+    ).unwrap();
+    for page in Page::ALL {
+        let label = format!(" {} {} ", page.index() + 1, page.title());
+        if page == view.page {
+            queue!(stdout, SetAttribute(Attribute::Reverse), Print(label), SetAttribute(Attribute::Reset)).unwrap();
         } else {
-            Color::Red
-        };
+            queue!(stdout, Print(label)).unwrap();
+        }
+        queue!(stdout, Print(" ")).unwrap();
+    }
+
+    match (&view.status, view.page) {
+        (_, Page::Logs) => draw_logs(stdout, &view.logs),
+        (_, Page::Config) => draw_config(stdout, &view.config),
+        (Ok(st), Page::Overview) => draw_overview(stdout, st, &view.chrony, view.delta_ms, &view.delta_frames),
+        (Ok(st), Page::Clock) => draw_clock(stdout, st),
+        (Ok(st), Page::Ltc) => draw_ltc(stdout, st, &view.delta_frames),
+        (Ok(st), Page::Serial) => draw_serial(stdout, st),
+        (Err(e), _) => {
+            field(stdout, CONTENT_TOP, "Daemon", client.base_url(), None);
+            field(stdout, CONTENT_TOP + 1, "Status", e, Some(Color::Red));
+        }
+    }
+
+    // footer
+    let (_, rows) = terminal::size().unwrap_or((80, 24));
+    queue!(
+        stdout,
+        MoveTo(2, rows.saturating_sub(1)),
+        Print(format!("[1-6/Tab] Page  [S] Sync System Clock to LTC  [P] Next Offset Preset  [Q] Quit    {}", view.last_action)),
+    ).unwrap();
+
+    stdout.flush().unwrap();
+}
+
+fn delta_color(delta_ms: i64) -> Color {
+    if delta_ms.abs() < 20 {
+        Color::Green
+    } else if delta_ms.abs() < 100 {
+        Color::Yellow
+    } else {
+        Color::Red
+    }
+}
+
+fn sync_color(sync_status: &str) -> Color {
+    match sync_status {
+        "IN SYNC" => Color::Green,
+        "TIMETURNING" => Color::Cyan,
+        "STALE" => Color::Yellow,
+        _ => Color::Red,
+    }
+}
+
+/// Yellow while LTC frames are late and red once they stop.
+fn signal_color(signal: Staleness) -> Option<Color> {
+    match signal {
+        Staleness::Live => None,
+        Staleness::Stale => Some(Color::Yellow),
+        Staleness::NoSignal => Some(Color::Red),
+    }
+}
+
+fn jitter_color(jitter_status: &str) -> Color {
+    match jitter_status {
+        "GOOD" => Color::Green,
+        "AVERAGE" => Color::Yellow,
+        _ => Color::Red,
+    }
+}
+
+/// The essentials: is LTC there, is the clock on it, and what is being done.
+fn draw_overview(
+    stdout: &mut std::io::Stdout,
+    st: &ApiStatus,
+    chrony: &Result<ChronyReport, String>,
+    delta_ms: i64,
+    delta_frames: &str,
+) {
+    let top = CONTENT_TOP;
+    field(stdout, top, "LTC Status", st.ltc_status_text(), signal_color(st.ltc_signal));
+    field(stdout, top + 1, "LTC Timecode", &st.ltc_timecode, None);
+    field(stdout, top + 2, "System Clock", &st.system_clock, None);
+    field(
+        stdout,
+        top + 3,
+        "Timecode Δ",
+        format!("{:+} ms ({}) {}", delta_ms, delta_frames, st.clock_direction.as_str()),
+        Some(delta_color(delta_ms)),
+    );
+    field(stdout, top + 4, "Sync Status", &st.sync_status, Some(sync_color(&st.sync_status)));
+    field(stdout, top + 5, "Why", &st.why, None);
+    if !st.time_conflicts.is_empty() {
+        field(stdout, top + 6, "Time Conflicts", st.time_conflicts_text(), Some(Color::Red));
+    }
+    field(stdout, top + 7, "Chrony Service", if st.ntp_active { "RUNNING" } else { "MISSING" }, None);
+    let (offset, color) = chrony_summary(chrony);
+    field(stdout, top + 8, "Chrony Offset", offset, Some(color));
+    // One row per NIC, so it is plain which one PTP is served from.
+    if st.network_interfaces.is_empty() {
+        field(stdout, top + 9, "Interfaces", st.interfaces.join(", "), None);
+    }
+    for (row, nic) in (top + 9..).zip(&st.network_interfaces) {
+        let label = if row == top + 9 { "Interfaces" } else { "" };
+        let color = if nic.link_up { Color::Green } else { Color::Red };
+        field(stdout, row, label, nic, Some(color));
+    }
+}
+
+/// chrony's own offset, reference and leap status, coloured like the LTC
+/// delta, or why it is unknown.
+fn chrony_summary(chrony: &Result<ChronyReport, String>) -> (String, Color) {
+    match chrony {
+        Ok(ChronyReport { tracking: Some(t), .. }) => (
+            format!(
+                "{:+.3} ms (stratum {}, {}, {})",
+                t.system_time_ms, t.stratum, t.reference_name, t.leap_status
+            ),
+            delta_color(t.system_time_ms.round() as i64),
+        ),
+        Ok(ChronyReport { error, .. }) => (
+            error.clone().unwrap_or_else(|| "no tracking data".into()),
+            Color::Red,
+        ),
+        Err(e) => (e.clone(), Color::Red),
+    }
+}
+
+/// 3×5 pixel glyphs for the big clock, one string per row.
+fn glyph(c: char) -> [&'static str; 5] {
+    match c {
+        '0' => ["###", "# #", "# #", "# #", "###"],
+        '1' => [" # ", "## ", " # ", " # ", "###"],
+        '2' => ["###", "  #", "###", "#  ", "###"],
+        '3' => ["###", "  #", "###", "  #", "###"],
+        '4' => ["# #", "# #", "###", "  #", "  #"],
+        '5' => ["###", "#  ", "###", "  #", "###"],
+        '6' => ["###", "#  ", "###", "# #", "###"],
+        '7' => ["###", "  #", "  #", "  #", "  #"],
+        '8' => ["###", "# #", "###", "# #", "###"],
+        '9' => ["###", "# #", "###", "  #", "###"],
+        ':' => [" ", "#", " ", "#", " "],
+        ';' => [" ", "#", " ", "#", "#"],
+        '.' => [" ", " ", " ", " ", "#"],
+        '-' => ["   ", "   ", "###", "   ", "   "],
+        _ => ["   "; 5],
+    }
+}
+
+/// `text` in block characters, five rows high. Each pixel is two cells
+/// wide so the digits come out roughly square on a terminal.
+fn big_text(text: &str) -> [String; 5] {
+    let mut rows: [String; 5] = Default::default();
+    for (i, c) in text.chars().enumerate() {
+        for (row, pixels) in rows.iter_mut().zip(glyph(c)) {
+            if i > 0 {
+                row.push_str("  ");
+            }
+            for pixel in pixels.chars() {
+                row.push_str(if pixel == '#' { "██" } else { "  " });
+            }
+        }
+    }
+    rows
+}
+
+/// The incoming timecode in block characters, coloured by sync state, for
+/// reading across a room.
+fn draw_clock(stdout: &mut std::io::Stdout, st: &ApiStatus) {
+    let locked = st.ltc_status == "LOCK";
+    let timecode = if st.ltc_timecode == "…" { "--:--:--:--" } else { st.ltc_timecode.as_str() };
+    let (state, color) = if locked {
+        (st.sync_status.clone(), sync_color(&st.sync_status))
+    } else {
+        (format!("NO LOCK ({})", st.ltc_status), Color::Red)
+    };
+    let (cols, _) = terminal::size().unwrap_or((80, 24));
+    let rows = big_text(timecode);
+    let width = rows[0].chars().count() as u16;
+    let left = cols.saturating_sub(width) / 2;
+    for (i, row) in rows.iter().enumerate() {
         queue!(
             stdout,
-            MoveTo(2, 11), SetForegroundColor(dcol),
-            Print(format!("Timecode Δ       : {:+} ms ({:+} frames)", cached_delta_ms, cached_delta_frames)),
-            ResetColor,
+            MoveTo(left, CONTENT_TOP + 1 + i as u16), SetForegroundColor(color), Print(row), ResetColor,
         ).unwrap();
+    }
+    let banner = format!("  {}  ", state);
+    queue!(
+        stdout,
+        MoveTo(cols.saturating_sub(banner.chars().count() as u16) / 2, CONTENT_TOP + 8),
+        SetForegroundColor(color), SetAttribute(Attribute::Reverse), SetAttribute(Attribute::Bold),
+        Print(banner),
+        SetAttribute(Attribute::Reset), ResetColor,
+    ).unwrap();
+    let details = format!("{}   Δ {:+} ms", st.frame_rate, st.timecode_delta_ms);
+    queue!(
+        stdout,
+        MoveTo(cols.saturating_sub(details.chars().count() as u16) / 2, CONTENT_TOP + 10),
+        Print(details),
+    ).unwrap();
+}
 
-        // sync status
-        let scol = if sync_status == "IN SYNC" {
-            Color::Green
-        } else if sync_status == "TIMETURNING" {
-            Color::Cyan
-        } else {
-            Color::Red
+/// Signal quality and the clock's behaviour against LTC.
+fn draw_ltc(stdout: &mut std::io::Stdout, st: &ApiStatus, delta_frames: &str) {
+    let top = CONTENT_TOP;
+    field(stdout, top, "LTC Status", st.ltc_status_text(), signal_color(st.ltc_signal));
+    field(stdout, top + 1, "LTC Timecode", &st.ltc_timecode, None);
+    field(stdout, top + 2, "Frame Rate", &st.frame_rate, None);
+    field(stdout, top + 3, "Session TC", &st.session_timecode, None);
+    field(
+        stdout,
+        top + 4,
+        "Timecode Δ",
+        format!("{:+} ms ({}) {}", st.timecode_delta_ms, delta_frames, st.clock_direction.as_str()),
+        Some(delta_color(st.timecode_delta_ms)),
+    );
+    field(stdout, top + 5, "Sync Jitter", &st.jitter_status, Some(jitter_color(&st.jitter_status)));
+    field(
+        stdout,
+        top + 6,
+        "Jitter Stats",
+        st.jitter.as_ref().map_or("…".to_string(), |j| j.to_string()),
+        None,
+    );
+    field(stdout, top + 7, "Lock Ratio", format!("{:.1}% LOCK", st.lock_ratio), None);
+    field(
+        stdout,
+        top + 8,
+        "Drift",
+        st.drift_ppm.map_or("…".to_string(), |ppm| format!("{:+.1} ppm", ppm)),
+        None,
+    );
+    field(stdout, top + 9, "Hardware Offset", format!("{} ms", st.hardware_offset_ms), None);
+}
+
+/// The reader and the queue between it and the frame processor.
+fn draw_serial(stdout: &mut std::io::Stdout, st: &ApiStatus) {
+    let top = CONTENT_TOP;
+    let q = &st.frame_queue;
+    field(stdout, top, "Serial Port", &st.serial_port, None);
+    field(stdout, top + 1, "LTC Status", st.ltc_status_text(), signal_color(st.ltc_signal));
+    field(stdout, top + 2, "Frames Received", q.received, None);
+    field(
+        stdout,
+        top + 3,
+        "Frames Dropped",
+        q.dropped,
+        Some(if q.dropped > 0 { Color::Yellow } else { Color::Green }),
+    );
+    field(stdout, top + 4, "Queue", format!("{} / {} (peak {})", q.queued, q.capacity, q.high_water), None);
+    let c = &st.continuity;
+    let (continuity, color) = match c.score {
+        Some(score) => (
+            format!(
+                "{:.1}% of {} frames ({} skips, {} repeats, {} reversals)",
+                score, c.frames, c.skips, c.repeats, c.reversals
+            ),
+            Some(if score < 100.0 { Color::Yellow } else { Color::Green }),
+        ),
+        None => ("…".to_string(), None),
+    };
+    field(stdout, top + 5, "Continuity", continuity, color);
+    let last = c.last.as_ref().map_or("none".to_string(), |d| format!("{} at {}", d, d.at.format("%H:%M:%S")));
+    field(stdout, top + 6, "Last Break", last, None);
+}
+
+/// The daemon's config as saved to `config.yml`, without the comments.
+fn draw_config(stdout: &mut std::io::Stdout, config: &Result<Config, String>) {
+    let text = match config.as_ref().map(crate::config::to_yaml) {
+        Ok(Ok(yaml)) => yaml,
+        Ok(Err(e)) => format!("Cannot render config: {}", e),
+        Err(e) => format!("Cannot load config: {}", e),
+    };
+    let lines = text.lines().filter(|line| !line.trim_start().starts_with('#') && !line.is_empty());
+    for (i, line) in lines.take(log_height()).enumerate() {
+        queue!(stdout, MoveTo(2, CONTENT_TOP + i as u16), Print(line)).unwrap();
+    }
+}
+
+fn draw_logs(stdout: &mut std::io::Stdout, logs: &LogPane) {
+    let filter = match logs.min_level {
+        LevelFilter::Error => "errors",
+        LevelFilter::Warn => "warnings and errors",
+        LevelFilter::Info => "info and above",
+        _ => "all",
+    };
+    let position = if logs.scroll > 0 {
+        format!(", {} newer below [End]", logs.scroll)
+    } else {
+        String::new()
+    };
+    queue!(
+        stdout,
+        MoveTo(2, CONTENT_TOP), SetForegroundColor(Color::DarkGrey),
+        Print(format!(
+            "Showing {}{}    [PgUp/PgDn] scroll  [A]ll [I]nfo [W]arn [E]rror",
+            filter, position
+        )),
+        ResetColor,
+    ).unwrap();
+    for (i, line) in logs.visible(log_height()).into_iter().enumerate() {
+        let color = match line_level(line) {
+            Some(log::Level::Error) => Color::Red,
+            Some(log::Level::Warn) => Color::Yellow,
+            _ => Color::Reset,
         };
         queue!(
             stdout,
-            MoveTo(2, 12), SetForegroundColor(scol),
-            Print(format!("Sync Status      : {}", sync_status)),
-            ResetColor,
+            MoveTo(2, CONTENT_TOP + 1 + i as u16), SetForegroundColor(color), Print(line), ResetColor,
         ).unwrap();
+    }
+}
 
-        // jitter & lock ratio
-        let jstatus = get_jitter_status(avg_jitter_ms);
-        let jcol = if jstatus == "GOOD" {
-            Color::Green
-        } else if jstatus == "AVERAGE" {
-            Color::Yellow
-        } else {
-            Color::Red
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer(range: std::ops::Range<usize>) -> Vec<String> {
+        range.map(|i| format!("2025-08-07 10:00:00 [{}] line {}", if i % 5 == 0 { "WARN" } else { "INFO" }, i)).collect()
+    }
+
+    #[test]
+    fn test_page_navigation() {
+        assert_eq!(Page::Overview.cycle(1), Page::Clock);
+        assert_eq!(Page::Overview.cycle(-1), Page::Logs);
+        assert_eq!(Page::Logs.cycle(1), Page::Overview);
+        assert_eq!(Page::from_key('1'), Some(Page::Overview));
+        assert_eq!(Page::from_key('6'), Some(Page::Logs));
+        assert_eq!(Page::from_key('0'), None);
+        assert_eq!(Page::from_key('7'), None);
+    }
+
+    #[test]
+    fn test_big_text() {
+        let rows = big_text("1:0");
+        assert_eq!(rows[0], "  ██        ██████");
+        assert_eq!(rows[1], "████    ██  ██  ██");
+        assert!(rows.iter().all(|row| row.chars().count() == 18));
+    }
+
+    #[test]
+    fn test_chrony_summary() {
+        let report = ChronyReport {
+            tracking: Some(crate::chrony::parse_tracking(
+                "7F7F0101,LOCAL,1,1723024800.1,-0.000012,0,0,-12.3,0,0.02,0,0.00001,64.2,Normal",
+            ).unwrap()),
+            ..ChronyReport::default()
         };
-        queue!(
-            stdout,
-            MoveTo(2, 13), SetForegroundColor(jcol),
-            Print(format!("Sync Jitter      : {}", jstatus)),
-            ResetColor,
-        ).unwrap();
-        queue!(
-            stdout,
-            MoveTo(2, 14), Print(format!("Lock Ratio       : {:.1}% LOCK",
-                lock_ratio
-            )),
-        ).unwrap();
+        assert_eq!(
+            chrony_summary(&Ok(report)),
+            ("+0.012 ms (stratum 1, LOCAL, Normal)".to_string(), Color::Green)
+        );
+        let failed = ChronyReport { error: Some("cannot run chronyc".into()), ..ChronyReport::default() };
+        assert_eq!(chrony_summary(&Ok(failed)), ("cannot run chronyc".to_string(), Color::Red));
+    }
 
-        // footer + logs
-        queue!(
-            stdout,
-            MoveTo(2, 16), Print("[S] Sync System Clock to LTC    [Q] Quit"),
-        ).unwrap();
-        for (i, msg) in logs.iter().enumerate() {
-            queue!(stdout, MoveTo(2, 18 + i as u16), Print(msg)).unwrap();
-        }
+    #[test]
+    fn test_line_level() {
+        assert_eq!(line_level("2025-08-07 10:00:00 [ERROR] Sync failed"), Some(log::Level::Error));
+        assert_eq!(line_level("no level here"), None);
+    }
 
-        stdout.flush().unwrap();
+    #[test]
+    fn test_merge_appends_only_new_lines() {
+        let mut pane = LogPane::default();
+        assert!(pane.merge(buffer(0..100)));
+        // The daemon's ring buffer has moved on by 3 lines.
+        assert!(pane.merge(buffer(3..103)));
+        assert!(!pane.merge(buffer(3..103)));
+        assert_eq!(pane.lines.len(), 103);
+        assert_eq!(pane.lines.back().unwrap(), &buffer(102..103)[0]);
+    }
 
-        // manual sync & quit
-        if poll(Duration::from_millis(50)).unwrap() {
-            if let Event::Key(evt) = read().unwrap() {
-                match evt.code {
-                    KeyCode::Char(c) if c.eq_ignore_ascii_case(&'q') => {
-                        execute!(stdout, Show, LeaveAlternateScreen).unwrap();
-                        terminal::disable_raw_mode().unwrap();
-                        process::exit(0);
-                    }
-                    KeyCode::Char(c) if c.eq_ignore_ascii_case(&'s') => {
-                        if let Some(frame) = &state.lock().unwrap().latest {
-                            let entry = match system::trigger_sync(frame, &cfg) {
-                                Ok(ts) => format!("✔ Synced exactly to LTC: {}", ts),
-                                Err(_) => "❌ date cmd failed".into(),
-                            };
-                            if logs.len() == 10 { logs.pop_front(); }
-                            logs.push_back(entry);
-                        }
-                    }
-                    _ => {}
-                }
-            }
-        }
+    #[test]
+    fn test_scroll_and_filter() {
+        let mut pane = LogPane::default();
+        pane.merge(buffer(0..50));
+        assert_eq!(pane.visible(10).last().unwrap().as_str(), buffer(49..50)[0]);
 
-        thread::sleep(Duration::from_millis(25));
+        pane.scroll_by(10, 10);
+        assert_eq!(pane.visible(10).last().unwrap().as_str(), buffer(39..40)[0]);
+        // New lines arriving while scrolled back leave the view where it is.
+        pane.merge(buffer(0..52));
+        assert_eq!(pane.visible(10).last().unwrap().as_str(), buffer(39..40)[0]);
+        pane.scroll_by(1000, 10);
+        assert_eq!(pane.visible(10)[0].as_str(), buffer(0..1)[0]);
+        pane.scroll_by(-1000, 10);
+        assert_eq!(pane.scroll, 0);
+
+        pane.set_min_level(LevelFilter::Warn);
+        let warnings = pane.visible(100);
+        assert_eq!(warnings.len(), 11);
+        assert!(warnings.iter().all(|line| line.contains("[WARN]")));
     }
 }
-
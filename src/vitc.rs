@@ -0,0 +1,343 @@
+// src/vitc.rs
+//
+// VITC input: timecode read from the vertical interval of a video signal
+// through a V4L2 raw VBI capture device (`/dev/vbiN`), for installations with
+// no LTC feed. Each read from the device returns the VBI lines of one video
+// frame; every line is tried until one carries a VITC word with a good CRC,
+// which becomes an `LtcFrame` just like a line from the serial reader.
+// Embedded SDI/HDMI timecode (SMPTE 12-2 ATC in VANC) is not exposed through
+// the V4L2 VBI interface, so it is not read here.
+
+use chrono::{DateTime, Utc};
+use num_rational::Ratio;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Read;
+use std::process::Command;
+use utoipa::ToSchema;
+
+use crate::processor::FrameSender;
+use crate::sync_logic::{ClockPair, LtcFrame};
+use crate::timecode;
+
+/// Bits in a VITC word: nine groups of two sync bits and eight data bits,
+/// the last group being the CRC.
+const WORD_BITS: usize = 90;
+
+/// VITC runs at 115 bits per line period.
+const BITS_PER_LINE: f64 = 115.0;
+
+/// Smallest black-to-white swing that can be a VITC line.
+const MIN_SWING: u8 = 48;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, ToSchema)]
+pub enum VideoStandard {
+    /// 625 lines, 25 frames per second.
+    #[default]
+    #[serde(rename = "625")]
+    Lines625,
+    /// 525 lines, 29.97 frames per second.
+    #[serde(rename = "525")]
+    Lines525,
+}
+
+impl VideoStandard {
+    fn line_rate_hz(self) -> f64 {
+        match self {
+            VideoStandard::Lines625 => 15_625.0,
+            VideoStandard::Lines525 => 4_500_000.0 / 286.0,
+        }
+    }
+
+    fn frame_rate(self) -> Ratio<i64> {
+        match self {
+            VideoStandard::Lines625 => Ratio::new(25, 1),
+            VideoStandard::Lines525 => Ratio::new(30000, 1001),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct VitcConfig {
+    /// V4L2 VBI device, e.g. `/dev/vbi0`. While set, timecode is read from
+    /// it instead of the serial LTC reader. Read at startup.
+    #[serde(default)]
+    pub device: Option<String>,
+    #[serde(default)]
+    pub standard: VideoStandard,
+}
+
+/// The raw VBI format the device delivers, as `v4l2-ctl --get-fmt-vbi`
+/// reports it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VbiFormat {
+    pub sampling_rate: u32,
+    pub samples_per_line: usize,
+    /// VBI lines per frame, both fields together.
+    pub lines: usize,
+}
+
+impl VbiFormat {
+    fn samples_per_bit(&self, standard: VideoStandard) -> f64 {
+        self.sampling_rate as f64 / (standard.line_rate_hz() * BITS_PER_LINE)
+    }
+}
+
+/// Read the VBI format from `v4l2-ctl --get-fmt-vbi` output.
+pub fn parse_vbi_format(output: &str) -> Option<VbiFormat> {
+    let field = |name: &str| -> Option<u64> {
+        let line = output.lines().find(|l| l.split(':').next().is_some_and(|k| k.trim() == name))?;
+        line.split_once(':')?.1.split_whitespace().next()?.parse().ok()
+    };
+    let format = VbiFormat {
+        sampling_rate: field("Sampling Rate")? as u32,
+        samples_per_line: field("Samples per Line")? as usize,
+        lines: (field("Count 1st Field")? + field("Count 2nd Field")?) as usize,
+    };
+    (format.sampling_rate > 0 && format.samples_per_line > 0 && format.lines > 0).then_some(format)
+}
+
+/// Ask `v4l2-ctl` for `device`'s VBI format.
+pub fn read_vbi_format(device: &str) -> Result<VbiFormat, String> {
+    let output = Command::new("v4l2-ctl")
+        .args(["-d", device, "--get-fmt-vbi"])
+        .output()
+        .map_err(|e| format!("cannot run v4l2-ctl: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    parse_vbi_format(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| "v4l2-ctl reported no raw VBI format".to_string())
+}
+
+/// A decoded VITC word.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VitcCode {
+    pub hours: u32,
+    pub minutes: u32,
+    pub seconds: u32,
+    pub frames: u32,
+    pub drop_frame: bool,
+}
+
+/// Decode the VITC word on one scan line of 8-bit luma samples, if there is
+/// one: each group must start with the 1-0 sync pair and the CRC
+/// (G(x) = x^8 + 1) must check.
+pub fn decode_line(line: &[u8], samples_per_bit: f64) -> Option<VitcCode> {
+    let (min, max) = line.iter().fold((u8::MAX, u8::MIN), |(lo, hi), &s| (lo.min(s), hi.max(s)));
+    if max.saturating_sub(min) < MIN_SWING {
+        return None;
+    }
+    let threshold = min + (max - min) / 2;
+    // The word starts with the rising edge of the first sync bit.
+    let start = line.iter().position(|&s| s > threshold)?;
+    let bits: Vec<bool> = (0..WORD_BITS)
+        .map(|k| line.get(start + ((k as f64 + 0.5) * samples_per_bit) as usize).map(|&s| s > threshold))
+        .collect::<Option<_>>()?;
+    if (0..9).any(|g| !bits[g * 10] || bits[g * 10 + 1]) {
+        return None;
+    }
+    let mut crc = [false; 8];
+    for (i, &bit) in bits.iter().enumerate() {
+        crc[i % 8] ^= bit;
+    }
+    if crc.iter().any(|&b| b) {
+        return None;
+    }
+    // The first `len` data bits of group `g`, LSB first.
+    let value = |g: usize, len: usize| (0..len).fold(0, |v, i| v | (bits[g * 10 + 2 + i] as u32) << i);
+    Some(VitcCode {
+        frames: value(1, 2) * 10 + value(0, 4),
+        // The third data bit of the frame tens group.
+        drop_frame: bits[14],
+        seconds: value(3, 3) * 10 + value(2, 4),
+        minutes: value(5, 3) * 10 + value(4, 4),
+        hours: value(7, 2) * 10 + value(6, 4),
+    })
+}
+
+/// The frame `code` names, if it is a valid label at `standard`'s rate.
+pub fn to_frame(code: VitcCode, standard: VideoStandard, timestamp: DateTime<Utc>) -> Option<LtcFrame> {
+    let frame_rate = standard.frame_rate();
+    let valid = code.hours < 24
+        && timecode::is_valid_label(code.minutes, code.seconds, code.frames, frame_rate, code.drop_frame);
+    valid.then(|| LtcFrame {
+        status: "LOCK".to_string(),
+        hours: code.hours,
+        minutes: code.minutes,
+        seconds: code.seconds,
+        frames: code.frames,
+        is_drop_frame: code.drop_frame,
+        frame_rate,
+        timestamp,
+        received: None,
+    })
+}
+
+/// Read VBI frames from `input` and queue one frame per video frame that
+/// carries VITC, until the input ends or the processor goes away.
+pub fn read_vbi<R: Read>(mut input: R, format: VbiFormat, standard: VideoStandard, sender: FrameSender) {
+    let samples_per_bit = format.samples_per_bit(standard);
+    let mut buf = vec![0u8; format.samples_per_line * format.lines];
+    while input.read_exact(&mut buf).is_ok() {
+        let arrival = ClockPair::now();
+        let code = buf
+            .chunks_exact(format.samples_per_line)
+            .find_map(|line| decode_line(line, samples_per_bit));
+        let frame = code.and_then(|c| to_frame(c, standard, arrival.real));
+        if let Some(frame) = frame.map(|f| LtcFrame { received: Some(arrival.mono), ..f }) {
+            if !sender.send(frame) {
+                return;
+            }
+        }
+    }
+}
+
+/// Open `device` and read VITC from it until it fails.
+pub fn start_vitc_thread(device: &str, standard: VideoStandard, sender: FrameSender) {
+    let format = match read_vbi_format(device) {
+        Ok(format) => format,
+        Err(e) => {
+            log::error!("❌ Cannot read the VBI format of {}: {}", device, e);
+            return;
+        }
+    };
+    let input = match File::open(device) {
+        Ok(f) => f,
+        Err(e) => {
+            log::error!("❌ Cannot open {}: {}", device, e);
+            return;
+        }
+    };
+    log::info!(
+        "📺 Reading VITC from {} ({} lines of {} samples at {} Hz)",
+        device,
+        format.lines,
+        format.samples_per_line,
+        format.sampling_rate
+    );
+    read_vbi(input, format, standard, sender);
+    log::error!("❌ VITC input from {} ended.", device);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::frame_channel;
+    use std::io::Cursor;
+
+    const FORMAT: VbiFormat = VbiFormat { sampling_rate: 27_000_000, samples_per_line: 1440, lines: 4 };
+
+    /// The 90 bits of the VITC word for `code`, CRC included.
+    fn word(code: VitcCode) -> Vec<bool> {
+        let nibbles = [
+            (code.frames % 10, 0),
+            ((code.frames / 10) | ((code.drop_frame as u32) << 2), 0),
+            (code.seconds % 10, 0),
+            (code.seconds / 10, 0),
+            (code.minutes % 10, 0),
+            (code.minutes / 10, 0),
+            (code.hours % 10, 0),
+            (code.hours / 10, 0),
+        ];
+        let mut bits = Vec::new();
+        for (value, user) in nibbles {
+            bits.extend([true, false]);
+            bits.extend((0..4).map(|i| value >> i & 1 == 1));
+            bits.extend((0..4).map(|i| user >> i & 1 == 1));
+        }
+        bits.extend([true, false]);
+        let mut crc = [false; 8];
+        for (i, &bit) in bits.iter().enumerate() {
+            crc[i % 8] ^= bit;
+        }
+        // The CRC bits sit at positions 82..90, i.e. classes 2..8 then 0..2.
+        bits.extend((82..90).map(|i| crc[i % 8]));
+        bits
+    }
+
+    /// One scan line carrying `bits` after some blanking, as a capture
+    /// card samples it.
+    fn line(bits: &[bool], standard: VideoStandard) -> Vec<u8> {
+        let spb = FORMAT.samples_per_bit(standard);
+        let start = 40;
+        (0..FORMAT.samples_per_line)
+            .map(|s| {
+                let k = ((s as f64 - start as f64) / spb).floor();
+                let high = s >= start && (k as usize) < bits.len() && bits[k as usize];
+                if high { 200 } else { 16 }
+            })
+            .collect()
+    }
+
+    fn code(hours: u32, minutes: u32, seconds: u32, frames: u32, drop_frame: bool) -> VitcCode {
+        VitcCode { hours, minutes, seconds, frames, drop_frame }
+    }
+
+    #[test]
+    fn test_decode_line() {
+        for (standard, c) in [
+            (VideoStandard::Lines625, code(10, 20, 30, 24, false)),
+            (VideoStandard::Lines525, code(23, 59, 59, 29, true)),
+        ] {
+            let spb = FORMAT.samples_per_bit(standard);
+            assert_eq!(decode_line(&line(&word(c), standard), spb), Some(c));
+        }
+
+        let spb = FORMAT.samples_per_bit(VideoStandard::Lines625);
+        let mut corrupt = word(code(1, 2, 3, 4, false));
+        corrupt[13] = !corrupt[13];
+        assert_eq!(decode_line(&line(&corrupt, VideoStandard::Lines625), spb), None, "CRC must fail");
+        assert_eq!(decode_line(&[16; 1440], spb), None, "black line");
+    }
+
+    #[test]
+    fn test_read_vbi() {
+        let standard = VideoStandard::Lines625;
+        let blank = vec![16u8; FORMAT.samples_per_line];
+        let mut input = Vec::new();
+        for frames in [7, 8] {
+            // VITC on the second of the four VBI lines.
+            input.extend(&blank);
+            input.extend(line(&word(code(10, 0, 0, frames, false)), standard));
+            input.extend(&blank);
+            input.extend(&blank);
+        }
+        // A frame without VITC is skipped.
+        input.extend(vec![16u8; FORMAT.samples_per_line * FORMAT.lines]);
+
+        let (tx, rx) = frame_channel();
+        read_vbi(Cursor::new(input), FORMAT, standard, tx);
+        let frames: Vec<LtcFrame> = rx.try_iter().collect();
+        assert_eq!(frames.iter().map(|f| f.frames).collect::<Vec<_>>(), vec![7, 8]);
+        assert_eq!(frames[0].status, "LOCK");
+        assert_eq!(frames[0].frame_rate, Ratio::new(25, 1));
+    }
+
+    #[test]
+    fn test_to_frame_rejects_impossible_labels() {
+        let now = Utc::now();
+        assert!(to_frame(code(10, 0, 0, 25, false), VideoStandard::Lines625, now).is_none());
+        assert!(to_frame(code(10, 1, 0, 0, true), VideoStandard::Lines525, now).is_none());
+        assert!(to_frame(code(10, 1, 0, 2, true), VideoStandard::Lines525, now).is_some());
+    }
+
+    #[test]
+    fn test_parse_vbi_format() {
+        let output = "Format VBI Capture:\n\
+                      \tSampling Rate   : 27000000 Hz\n\
+                      \tOffset          : 248 samples (9.18519e-06 secs after leading edge)\n\
+                      \tSamples per Line: 1440\n\
+                      \tSample Format   : GREY\n\
+                      \tStart 1st Field : 6\n\
+                      \tCount 1st Field : 17\n\
+                      \tStart 2nd Field : 319\n\
+                      \tCount 2nd Field : 17\n";
+        assert_eq!(
+            parse_vbi_format(output),
+            Some(VbiFormat { sampling_rate: 27_000_000, samples_per_line: 1440, lines: 34 })
+        );
+        assert_eq!(parse_vbi_format("Format Video Capture:\n"), None);
+    }
+}
@@ -0,0 +1,122 @@
+// src/watchdog.rs
+//
+// Catches the system clock being stepped behind TimeTurner's back, by chrony,
+// an operator's `date` or a VM resume. The monotonic clock never jumps, so
+// the wall clock advancing more or less than it did between two polls means
+// it was stepped. The delta filters are reset at once rather than left to
+// ramp slowly through the jump, and the step goes in the audit trail.
+
+use chrono::{DateTime, Utc};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::audit::{self, AuditEntry, AuditLog, AuditSource};
+use crate::clock_gate::ClockGate;
+use crate::health::Heartbeat;
+use crate::sync_logic::{LtcState, LtcView};
+
+/// How often the two clocks are compared.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Disagreement between the clocks over one poll that counts as a step. A
+/// slew moves the wall clock at most 0.5 ms/s, far below this.
+pub const JUMP_THRESHOLD_MS: i64 = 20;
+
+/// A step of the wall clock seen between two polls.
+#[derive(Debug, PartialEq)]
+pub struct Jump {
+    /// How far the wall clock moved beyond (positive) or short of the
+    /// monotonic clock, in ms.
+    pub ms: i64,
+    /// The earlier poll; the step happened after it.
+    pub after: Instant,
+}
+
+/// The last reading of both clocks.
+#[derive(Default)]
+pub struct ClockWatch {
+    last: Option<(Instant, DateTime<Utc>)>,
+}
+
+impl ClockWatch {
+    /// Take a reading and report a jump since the last one, if any.
+    pub fn check(&mut self, mono: Instant, real: DateTime<Utc>) -> Option<Jump> {
+        let (last_mono, last_real) = self.last.replace((mono, real))?;
+        let mono_us = mono.saturating_duration_since(last_mono).as_micros() as i64;
+        let real_us = (real - last_real).num_microseconds().unwrap_or(i64::MAX);
+        let ms = real_us.saturating_sub(mono_us) / 1000;
+        (ms.abs() >= JUMP_THRESHOLD_MS).then_some(Jump { ms, after: last_mono })
+    }
+}
+
+/// Poll the clocks until the process exits. A jump made while TimeTurner
+/// was itself changing the clock (see `ClockGate`) is left to the code that
+/// made it; any other resets the delta filters and is audited with source
+/// `external`.
+pub fn run(
+    state: Arc<Mutex<LtcState>>,
+    ltc: LtcView,
+    gate: Arc<ClockGate>,
+    audit_log: Arc<AuditLog>,
+    heartbeat: Heartbeat,
+) {
+    let mut watch = ClockWatch::default();
+    loop {
+        heartbeat.beat();
+        thread::sleep(POLL_INTERVAL);
+        let Some(jump) = watch.check(Instant::now(), Utc::now()) else {
+            continue;
+        };
+        if gate.is_busy() || gate.changed_since(jump.after) {
+            log::debug!("Clock moved {:+} ms by TimeTurner's own command.", jump.ms);
+            continue;
+        }
+        log::warn!(
+            "⏱️ System clock stepped {:+} ms outside TimeTurner; resetting the delta filters.",
+            jump.ms
+        );
+        let pre_delta = audit::current_delta(&ltc.get());
+        state.lock().unwrap().note_external_step();
+        let mut entry = AuditEntry::new(
+            AuditSource::External,
+            "step",
+            format!("system clock stepped {:+} ms", jump.ms),
+            pre_delta,
+        );
+        entry.success = true;
+        audit::record_after_settle(audit_log.clone(), ltc.clone(), entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration as ChronoDuration;
+
+    #[test]
+    fn test_clock_watch_detects_steps_not_slews() {
+        let mut watch = ClockWatch::default();
+        let mono = Instant::now();
+        let real = Utc::now();
+        assert_eq!(watch.check(mono, real), None);
+
+        // A slew of a few hundred microseconds over a poll is not a step.
+        let mono2 = mono + POLL_INTERVAL;
+        let real2 = real + ChronoDuration::milliseconds(250) + ChronoDuration::microseconds(400);
+        assert_eq!(watch.check(mono2, real2), None);
+
+        // The wall clock stepped back 2 s.
+        let mono3 = mono2 + POLL_INTERVAL;
+        let real3 = real2 + ChronoDuration::milliseconds(250 - 2000);
+        assert_eq!(watch.check(mono3, real3), Some(Jump { ms: -2000, after: mono2 }));
+
+        // A late poll is not a step as long as both clocks moved together.
+        let mono4 = mono3 + Duration::from_secs(5);
+        assert_eq!(watch.check(mono4, real3 + ChronoDuration::seconds(5)), None);
+
+        let mono5 = mono4 + POLL_INTERVAL;
+        let real5 = real3 + ChronoDuration::milliseconds(5000 + 250 + 40);
+        assert_eq!(watch.check(mono5, real5), Some(Jump { ms: 40, after: mono4 }));
+    }
+}
@@ -0,0 +1,54 @@
+// Full-screen timecode display (`/display`) for a machine-room monitor:
+// the incoming LTC and the sync state, readable from across the room.
+document.addEventListener('DOMContentLoaded', () => {
+    // Same token handling as the main UI: `?token=...` once, then remembered.
+    const urlToken = new URLSearchParams(window.location.search).get('token');
+    if (urlToken) {
+        localStorage.setItem('timeturnerToken', urlToken);
+    }
+
+    function apiFetch(url) {
+        const token = localStorage.getItem('timeturnerToken');
+        const headers = token ? { 'Authorization': `Bearer ${token}` } : {};
+        return fetch(url, { headers });
+    }
+
+    const timecode = document.getElementById('timecode');
+    const syncState = document.getElementById('sync-state');
+    const details = document.getElementById('details');
+
+    function syncClass(status) {
+        if (status === 'IN SYNC') return 'in-sync';
+        if (status === 'TIMETURNING') return 'timeturning';
+        return 'out-of-sync';
+    }
+
+    function show(data) {
+        const locked = data.ltc_status === 'LOCK';
+        const state = locked ? data.sync_status : `NO LOCK (${data.ltc_status})`;
+        const cls = locked ? syncClass(data.sync_status) : 'out-of-sync';
+        timecode.textContent = data.ltc_timecode === '…' ? '--:--:--:--' : data.ltc_timecode;
+        timecode.className = cls;
+        syncState.textContent = state;
+        syncState.className = cls;
+        details.textContent = `${data.frame_rate}  ·  Δ ${data.timecode_delta_ms >= 0 ? '+' : ''}${data.timecode_delta_ms} ms`;
+    }
+
+    async function refresh() {
+        try {
+            const response = await apiFetch('/api/status');
+            if (!response.ok) throw new Error(`HTTP ${response.status}`);
+            show(await response.json());
+        } catch (error) {
+            timecode.className = 'out-of-sync';
+            syncState.className = 'out-of-sync';
+            syncState.textContent = 'NO CONNECTION';
+            details.textContent = String(error);
+        }
+    }
+
+    // Four times a second keeps the frames digit moving without polling
+    // once per frame.
+    refresh();
+    setInterval(refresh, 250);
+});
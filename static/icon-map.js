@@ -3,6 +3,7 @@ const iconMap = {
     ltcStatus: {
         'LOCK': { src: 'assets/timeturner_ltc_green.png', tooltip: 'LTC signal is locked and stable.' },
         'FREE': { src: 'assets/timeturner_ltc_orange.png', tooltip: 'LTC signal is in freewheel mode.' },
+        'NO SIGNAL': { src: 'assets/timeturner_ltc_red.png', tooltip: 'LTC frames have stopped arriving.' },
         'default': { src: 'assets/timeturner_ltc_red.png', tooltip: 'LTC signal is not detected.' }
     },
     ntpActive: {
@@ -14,6 +15,8 @@ const iconMap = {
         'CLOCK AHEAD': { src: 'assets/timeturner_sync_orange.png', tooltip: 'System clock is ahead of the LTC source.' },
         'CLOCK BEHIND': { src: 'assets/timeturner_sync_orange.png', tooltip: 'System clock is behind the LTC source.' },
         'TIMETURNING': { src: 'assets/timeturner_timeturning.png', tooltip: 'Timeturner offset is active.' },
+        'STALE': { src: 'assets/timeturner_sync_orange.png', tooltip: 'LTC frames are late; the delta may be out of date.' },
+        'NO SIGNAL': { src: 'assets/timeturner_sync_red.png', tooltip: 'LTC frames have stopped arriving.' },
         'default': { src: 'assets/timeturner_sync_red.png', tooltip: 'Sync status is unknown.' }
     },
     jitterStatus: {
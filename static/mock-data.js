@@ -12,8 +12,22 @@ const mockApiDataSets = {
             sync_status: 'IN SYNC',
             timecode_delta_ms: 5,
             timecode_delta_frames: 0.125,
+            timecode_delta_by_rate: [
+                { frame_rate: '25.00fps', frames: 0 },
+                { frame_rate: '29.97fps', frames: 0 },
+            ],
             jitter_status: 'GOOD',
             interfaces: ['192.168.1.100/24 (eth0)', '10.0.0.5/8 (wlan0)'],
+            network_interfaces: [
+                {
+                    name: 'eth0', ipv4: ['192.168.1.100'], ipv6: ['fe80::ba27:ebff:fe12:3456'],
+                    mac: 'b8:27:eb:12:34:56', link_up: true, speed_mbps: 1000, phc: '/dev/ptp0', ptp: true,
+                },
+                {
+                    name: 'wlan0', ipv4: ['10.0.0.5'], ipv6: [],
+                    mac: 'b8:27:eb:65:43:21', link_up: true, speed_mbps: null, phc: null, ptp: false,
+                },
+            ],
         },
         config: {
             hardwareOffsetMs: 10,
@@ -166,3 +180,31 @@ const mockApiDataSets = {
         logs: [ '2025-08-07 16:00:00 [INFO] NTP service is inactive.' ]
     }
 };
+
+// Serial ports as listed by GET /api/serial/ports, shared by every data set.
+const mockSerialPorts = [
+    { path: '/dev/ttyACM0', vid: '16c0', pid: '0483', serialNumber: '12345', description: 'Teensyduino USB Serial', active: true },
+    { path: '/dev/ttyAMA0', vid: null, pid: null, serialNumber: null, description: null, active: false },
+];
+
+// A /api/series response for the last `minutes`, with a short LTC dropout.
+function mockSeries(kind, minutes) {
+    const now = Math.floor(Date.now() / 1000);
+    const points = [];
+    for (let i = minutes * 60 - 1; i >= 0; i--) {
+        const t = now - i;
+        const timestamp = new Date(t * 1000).toISOString();
+        if (i > 200 && i < 230) {
+            points.push({ timestamp, meanMs: null, minMs: null, maxMs: null });
+            continue;
+        }
+        const mean = kind === 'delta' ? 3 * Math.sin(t / 60) : 2 + Math.abs(Math.sin(t / 7));
+        points.push({
+            timestamp,
+            meanMs: Math.round(mean * 10) / 10,
+            minMs: Math.round((mean - 1) * 10) / 10,
+            maxMs: Math.round((mean + 1.5) * 10) / 10,
+        });
+    }
+    return { resolutionSecs: 1, points };
+}
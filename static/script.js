@@ -7,6 +7,23 @@
     let lastApiData = null;
     let lastApiFetchTime = null;
 
+    // --- API Authentication ---
+    // When the daemon has API tokens configured, open the UI once with
+    // `?token=...`; the token is remembered in this browser afterwards.
+    const urlToken = new URLSearchParams(window.location.search).get('token');
+    if (urlToken) {
+        localStorage.setItem('timeturnerToken', urlToken);
+    }
+
+    function apiFetch(url, options = {}) {
+        const token = localStorage.getItem('timeturnerToken');
+        const headers = Object.assign({}, options.headers);
+        if (token) {
+            headers['Authorization'] = `Bearer ${token}`;
+        }
+        return fetch(url, Object.assign({}, options, { headers }));
+    }
+
     const statusElements = {
         ltcStatus: document.getElementById('ltc-status'),
         ltcTimecode: document.getElementById('ltc-timecode'),
@@ -19,6 +36,7 @@
         deltaStatus: document.getElementById('delta-status'),
         jitterStatus: document.getElementById('jitter-status'),
         deltaText: document.getElementById('delta-text'),
+        timeConflicts: document.getElementById('time-conflicts'),
         interfaces: document.getElementById('interfaces'),
         logs: document.getElementById('logs'),
     };
@@ -45,6 +63,15 @@
     const setDateButton = document.getElementById('set-date');
     const dateMessage = document.getElementById('date-message');
 
+    const serialPortSelect = document.getElementById('serial-port');
+    const serialBaudInput = document.getElementById('serial-baud');
+    const serialApplyButton = document.getElementById('serial-apply');
+    const serialMessage = document.getElementById('serial-message');
+
+    const deltaChart = document.getElementById('delta-chart');
+    const jitterChart = document.getElementById('jitter-chart');
+    const SERIES_MINUTES = 10;
+
     // --- Collapsible Sections ---
     const controlsToggle = document.getElementById('controls-toggle');
     const controlsContent = document.getElementById('controls-content');
@@ -84,12 +111,15 @@
         const ltcStatus = data.ltc_status || 'UNKNOWN';
         const ltcIconInfo = iconMap.ltcStatus[ltcStatus] || iconMap.ltcStatus.default;
         statusElements.ltcStatus.innerHTML = `<img src="${ltcIconInfo.src}" class="status-icon" alt="" title="${ltcIconInfo.tooltip}">`;
-        statusElements.ltcStatus.className = ltcStatus.toLowerCase();
+        statusElements.ltcStatus.className = ltcStatus.toLowerCase().replace(' ', '-');
         statusElements.ltcTimecode.textContent = data.ltc_timecode;
 
         const frameRate = data.frame_rate || 'unknown';
         const frameRateIconInfo = iconMap.frameRate[frameRate] || iconMap.frameRate.default;
         statusElements.frameRate.innerHTML = `<img src="${frameRateIconInfo.src}" class="status-icon" alt="" title="${frameRateIconInfo.tooltip}">`;
+        if (data.frame_rate_warning) {
+            statusElements.frameRate.innerHTML += ` <span class="rate-warning">⚠️ ${data.frame_rate_warning}</span>`;
+        }
 
         const lockRatio = data.lock_ratio;
         let lockRatioCategory;
@@ -137,15 +167,27 @@
         const deltaIconInfo = iconMap.deltaStatus[deltaCategory];
         statusElements.deltaStatus.innerHTML = `<img src="${deltaIconInfo.src}" class="status-icon" alt="" title="${deltaIconInfo.tooltip}">`;
 
-        const deltaTextValue = `${data.timecode_delta_ms} ms (${data.timecode_delta_frames} frames)`;
+        const secondaryFrames = (data.timecode_delta_by_rate || [])
+            .slice(1)
+            .map(d => `, ${d.frames} @ ${d.frame_rate}`)
+            .join('');
+        const deltaTextValue = `${data.timecode_delta_ms} ms (${data.timecode_delta_frames} frames${secondaryFrames})`;
         statusElements.deltaText.textContent = `Δ ${deltaTextValue}`;
+        statusElements.timeConflicts.textContent = (data.time_conflicts || []).map(c => `⚠️ ${c.detail}`).join(' ');
 
         const jitterStatus = data.jitter_status || 'UNKNOWN';
         const jitterIconInfo = iconMap.jitterStatus[jitterStatus] || iconMap.jitterStatus.default;
         statusElements.jitterStatus.innerHTML = `<img src="${jitterIconInfo.src}" class="status-icon" alt="" title="${jitterIconInfo.tooltip}">`;
         statusElements.jitterStatus.className = jitterStatus.toLowerCase();
 
-        if (data.interfaces.length > 0) {
+        const nics = data.network_interfaces || [];
+        if (nics.length > 0) {
+            statusElements.interfaces.textContent = nics.map(nic => {
+                const link = nic.link_up ? `up${nic.speed_mbps ? ` ${nic.speed_mbps} Mb/s` : ''}` : 'down';
+                const addresses = nic.ipv4.concat(nic.ipv6).join(', ');
+                return `${nic.name} (${link})${addresses ? ` ${addresses}` : ''}${nic.ptp ? ' [PTP]' : ''}`;
+            }).join(' | ');
+        } else if (data.interfaces.length > 0) {
             statusElements.interfaces.textContent = data.interfaces.join(' | ');
         } else {
             statusElements.interfaces.textContent = 'No active interfaces found.';
@@ -220,7 +262,7 @@
             return;
         }
         try {
-            const response = await fetch('/api/status');
+            const response = await apiFetch('/api/status');
             if (!response.ok) throw new Error('Failed to fetch status');
             const data = await response.json();
             updateStatus(data);
@@ -247,7 +289,7 @@
             return;
         }
         try {
-            const response = await fetch('/api/config');
+            const response = await apiFetch('/api/config');
             if (!response.ok) throw new Error('Failed to fetch config');
             const data = await response.json();
             hwOffsetInput.value = data.hardwareOffsetMs;
@@ -281,13 +323,28 @@
             console.log('Mock save:', config);
             alert('Configuration saved (mock).');
             // We can also update the mock data in memory to see changes reflected
-            mockApiDataSets[currentMockSetKey].config = config;
+            Object.assign(mockApiDataSets[currentMockSetKey].config, config);
             return;
         }
 
         try {
-            const response = await fetch('/api/config', {
-                method: 'POST',
+            // An offset that steps the clock over confirmStepMs needs the
+            // preview's token.
+            let url = '/api/config';
+            const params = new URLSearchParams(config.timeturnerOffset);
+            const previewResponse = await apiFetch(`/api/config/offset?${params}`);
+            const preview = await previewResponse.json();
+            if (previewResponse.ok && (preview.confirmRequired || preview.overStepLimit)) {
+                const step = `${preview.stepMs > 0 ? '+' : ''}${preview.stepMs}`;
+                const limit = preview.overStepLimit ? ', beyond maxAutoStepMs' : '';
+                if (!window.confirm(`Saving this offset steps the clock ${step} ms${limit}. Continue?`)) {
+                    return;
+                }
+                url = stepUrl('/api/config', preview);
+            }
+            // PATCH so settings the page doesn't show (hooks, thresholds) are kept.
+            const response = await apiFetch(url, {
+                method: 'PATCH',
                 headers: { 'Content-Type': 'application/json' },
                 body: JSON.stringify(config),
             });
@@ -309,7 +366,7 @@
             return;
         }
         try {
-            const response = await fetch('/api/logs');
+            const response = await apiFetch('/api/logs');
             if (!response.ok) throw new Error('Failed to fetch logs');
             const logs = await response.json();
             // Show latest 20 logs, with the newest at the top.
@@ -321,6 +378,161 @@
         }
     }
 
+    function showSerialPorts(ports) {
+        serialPortSelect.innerHTML = '';
+        ports.forEach(port => {
+            const option = document.createElement('option');
+            option.value = port.path;
+            option.textContent = port.description ? `${port.path} (${port.description})` : port.path;
+            option.selected = port.active;
+            serialPortSelect.appendChild(option);
+        });
+    }
+
+    async function fetchSerialPorts() {
+        if (useMockData) {
+            showSerialPorts(mockSerialPorts);
+            return;
+        }
+        try {
+            const response = await apiFetch('/api/serial/ports');
+            if (!response.ok) throw new Error('Failed to fetch serial ports');
+            showSerialPorts(await response.json());
+        } catch (error) {
+            console.error('Error fetching serial ports:', error);
+        }
+    }
+
+    async function switchSerialPort() {
+        const request = { path: serialPortSelect.value };
+        const baudRate = parseInt(serialBaudInput.value, 10);
+        if (baudRate > 0) {
+            request.baudRate = baudRate;
+        }
+
+        serialMessage.textContent = 'Switching port...';
+        if (useMockData) {
+            mockSerialPorts.forEach(port => { port.active = port.path === request.path; });
+            serialMessage.textContent = `Success: Reading LTC from ${request.path} (mock).`;
+            setTimeout(() => { serialMessage.textContent = ''; }, 5000);
+            return;
+        }
+        try {
+            const response = await apiFetch('/api/serial/port', {
+                method: 'PUT',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify(request),
+            });
+            const data = await response.json();
+            if (response.ok) {
+                serialMessage.textContent = `Success: Reading LTC from ${data.path} at ${data.baudRate} baud.`;
+                fetchSerialPorts();
+            } else {
+                serialMessage.textContent = `Error: ${data.message}`;
+            }
+        } catch (error) {
+            console.error('Error switching serial port:', error);
+            serialMessage.textContent = 'Failed to send port switch.';
+        }
+        setTimeout(() => { serialMessage.textContent = ''; }, 5000);
+    }
+
+    // Draw one /api/series response: the min-max spread of each second as a
+    // band, the mean as a line, and nothing where LTC was not locked.
+    function drawChart(canvas, series, color) {
+        const dpr = window.devicePixelRatio || 1;
+        canvas.width = canvas.clientWidth * dpr;
+        canvas.height = canvas.clientHeight * dpr;
+        const ctx = canvas.getContext('2d');
+        ctx.scale(dpr, dpr);
+        const width = canvas.clientWidth;
+        const height = canvas.clientHeight;
+        const pad = { left: 45, right: 5, top: 8, bottom: 8 };
+        ctx.clearRect(0, 0, width, height);
+
+        const points = series.points;
+        const measured = points.filter(p => p.meanMs !== null);
+        let lo = Math.min(0, ...measured.map(p => p.minMs));
+        let hi = Math.max(1, ...measured.map(p => p.maxMs));
+        if (hi === lo) hi = lo + 1;
+
+        const x = i => pad.left + (i / Math.max(points.length - 1, 1)) * (width - pad.left - pad.right);
+        const y = v => pad.top + (hi - v) / (hi - lo) * (height - pad.top - pad.bottom);
+
+        // Axis labels and the zero line
+        ctx.font = '11px sans-serif';
+        ctx.fillStyle = '#c5ced6';
+        ctx.textBaseline = 'middle';
+        ctx.fillText(hi.toFixed(1), 2, y(hi));
+        ctx.fillText(lo.toFixed(1), 2, y(lo));
+        ctx.strokeStyle = '#666';
+        ctx.lineWidth = 1;
+        ctx.beginPath();
+        ctx.moveTo(pad.left, y(0));
+        ctx.lineTo(width - pad.right, y(0));
+        ctx.stroke();
+
+        if (measured.length === 0) {
+            ctx.fillText('No LOCK frames in this period.', pad.left + 10, height / 2);
+            return;
+        }
+
+        ctx.fillStyle = color + '40';
+        points.forEach((p, i) => {
+            if (p.meanMs === null) return;
+            ctx.fillRect(x(i) - 0.5, y(p.maxMs), 1, Math.max(y(p.minMs) - y(p.maxMs), 1));
+        });
+
+        ctx.strokeStyle = color;
+        ctx.lineWidth = 1.5;
+        ctx.beginPath();
+        let drawing = false;
+        points.forEach((p, i) => {
+            if (p.meanMs === null) {
+                drawing = false;
+                return;
+            }
+            if (drawing) {
+                ctx.lineTo(x(i), y(p.meanMs));
+            } else {
+                ctx.moveTo(x(i), y(p.meanMs));
+                drawing = true;
+            }
+        });
+        ctx.stroke();
+    }
+
+    async function fetchSeries() {
+        if (useMockData) {
+            drawChart(deltaChart, mockSeries('delta', SERIES_MINUTES), '#1a7db6');
+            drawChart(jitterChart, mockSeries('jitter', SERIES_MINUTES), '#ffc107');
+            return;
+        }
+        try {
+            const [delta, jitter] = await Promise.all(['delta', 'jitter'].map(async kind => {
+                const response = await apiFetch(`/api/series/${kind}?minutes=${SERIES_MINUTES}`);
+                if (!response.ok) throw new Error(`Failed to fetch ${kind} series`);
+                return response.json();
+            }));
+            drawChart(deltaChart, delta, '#1a7db6');
+            drawChart(jitterChart, jitter, '#ffc107');
+        } catch (error) {
+            console.error('Error fetching series:', error);
+        }
+    }
+
+    // `path` with what a preview says the step needs: its token, and the
+    // override past maxAutoStepMs.
+    function stepUrl(path, preview) {
+        const params = new URLSearchParams();
+        if (preview.overStepLimit) params.set('override', 'true');
+        if (preview.token) {
+            params.set('confirm', 'true');
+            params.set('token', preview.token);
+        }
+        return `${path}?${params}`;
+    }
+
     async function triggerManualSync() {
         syncMessage.textContent = 'Issuing sync command...';
         if (useMockData) {
@@ -329,7 +541,22 @@
             return;
         }
         try {
-            const response = await fetch('/api/sync', { method: 'POST' });
+            // Steps over confirmStepMs need the preview's token.
+            let url = '/api/sync';
+            const previewResponse = await apiFetch('/api/sync/preview');
+            const preview = await previewResponse.json();
+            if (previewResponse.ok && (preview.confirmRequired || preview.overStepLimit)) {
+                const target = new Date(preview.targetTime).toLocaleTimeString();
+                const step = `${preview.stepMs > 0 ? '+' : ''}${preview.stepMs}`;
+                const limit = preview.overStepLimit ? ', beyond maxAutoStepMs' : '';
+                if (!window.confirm(`This steps the clock ${step} ms to ${target}${limit}. Continue?`)) {
+                    syncMessage.textContent = 'Sync cancelled.';
+                    setTimeout(() => { syncMessage.textContent = ''; }, 5000);
+                    return;
+                }
+                url = stepUrl('/api/sync', preview);
+            }
+            const response = await apiFetch(url, { method: 'POST' });
             const data = await response.json();
             if (response.ok) {
                 syncMessage.textContent = `Success: ${data.message}`;
@@ -351,7 +578,7 @@
             return;
         }
         try {
-            const response = await fetch('/api/nudge_clock', {
+            const response = await apiFetch('/api/nudge_clock', {
                 method: 'POST',
                 headers: { 'Content-Type': 'application/json' },
                 body: JSON.stringify({ microseconds: ms * 1000 }),
@@ -385,7 +612,20 @@
             return;
         }
         try {
-            const response = await fetch('/api/set_date', {
+            // A date change over confirmStepMs needs the preview's token.
+            let url = '/api/set_date';
+            const previewResponse = await apiFetch(`/api/set_date/preview?date=${encodeURIComponent(date)}`);
+            const preview = await previewResponse.json();
+            if (previewResponse.ok && preview.confirmRequired) {
+                const target = new Date(preview.targetTime).toLocaleString();
+                if (!window.confirm(`This sets the clock to ${target}. Continue?`)) {
+                    dateMessage.textContent = 'Date change cancelled.';
+                    setTimeout(() => { dateMessage.textContent = ''; }, 5000);
+                    return;
+                }
+                url = `/api/set_date?confirm=true&token=${encodeURIComponent(preview.token)}`;
+            }
+            const response = await apiFetch(url, {
                 method: 'POST',
                 headers: { 'Content-Type': 'application/json' },
                 body: JSON.stringify({ date: date }),
@@ -416,6 +656,7 @@
         nudgeClock(ms);
     });
     setDateButton.addEventListener('click', setDate);
+    serialApplyButton.addEventListener('click', switchSerialPort);
 
     // --- Collapsible Section Listeners ---
     controlsToggle.addEventListener('click', () => {
@@ -433,11 +674,14 @@
     fetchStatus();
     fetchConfig();
     fetchLogs();
+    fetchSerialPorts();
+    fetchSeries();
 
     // Refresh data every 2 seconds if not using mock data
     if (!useMockData) {
         setInterval(fetchStatus, 2000);
         setInterval(fetchLogs, 2000);
+        setInterval(fetchSeries, 5000);
     }
     setInterval(animateClocks, 50); // High-frequency clock animation
 });
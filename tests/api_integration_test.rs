@@ -0,0 +1,613 @@
+// tests/api_integration_test.rs
+//
+// Boots the real HTTP server (`api::serve`) with the real frame processor,
+// feeds it simulated LTC through the serial line parser, and drives it over
+// HTTP with the `timeturner-ctl` client. Clock commands go to a mock so
+// the host clock is never touched.
+
+mod common;
+
+use actix_web::web;
+use common::MockClock;
+use chrono::{Local, Timelike};
+use ntp_timeturner::api::{serve, AppState, StatusCache};
+use ntp_timeturner::audit::{AuditLog, AuditSource};
+use ntp_timeturner::auth_backend::AuthCache;
+use ntp_timeturner::client::ApiClient;
+use ntp_timeturner::config::{ApiAccessConfig, ApiToken, Config, Role, TimeturnerOffset};
+use ntp_timeturner::export::{ExportStatus, Exporter};
+use ntp_timeturner::health::{Health, HealthReport, ReadyReport};
+use ntp_timeturner::supervisor::Supervisor;
+use ntp_timeturner::history::DeltaHistory;
+use ntp_timeturner::presets::OffsetPreset;
+use ntp_timeturner::processor::{self, frame_channel, FrameSender};
+use ntp_timeturner::serial_input::{read_ltc_lines, ReaderLatency, ReaderSelection};
+use ntp_timeturner::report::Session;
+use ntp_timeturner::series::Series;
+use ntp_timeturner::sync_logic::LtcState;
+use std::collections::VecDeque;
+use std::io::Cursor;
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+struct Harness {
+    url: String,
+    state: web::Data<AppState>,
+    clock: Arc<MockClock>,
+    frames: FrameSender,
+}
+
+impl Harness {
+    fn start(config: Config) -> Self {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        let config_path = std::env::temp_dir()
+            .join(format!(
+                "timeturner-integration-{}-{}.yml",
+                std::process::id(),
+                NEXT_ID.fetch_add(1, Ordering::SeqCst)
+            ))
+            .to_string_lossy()
+            .into_owned();
+
+        let ltc_state = Arc::new(Mutex::new(LtcState::new()));
+        let config = Arc::new(Mutex::new(config));
+        let (frames, rx) = frame_channel();
+        let health = Health::new();
+        let series = Arc::new(Mutex::new(Series::default()));
+        let session = Arc::new(Mutex::new(Session::new(chrono::Utc::now(), 0)));
+        let export = Arc::new(Mutex::new(Exporter::in_dir(format!("{}.export", config_path))));
+        {
+            let ltc_state = ltc_state.clone();
+            let config = config.clone();
+            let series = series.clone();
+            let session = session.clone();
+            let export = export.clone();
+            // Auto-sync decisions are dropped; clock commands come from the API.
+            let (corrections, _) = mpsc::channel();
+            let heartbeat = health.heartbeat("processor", Duration::from_secs(5));
+            thread::spawn(move || processor::run(&rx, ltc_state, config, series, session, export, corrections, heartbeat));
+        }
+
+        let clock = Arc::new(MockClock::default());
+        clock.ntp_active.store(true, Ordering::SeqCst);
+        let history = DeltaHistory::new(&config.lock().unwrap().history);
+        let ltc = ltc_state.lock().unwrap().view();
+        let state = web::Data::new(AppState {
+            ltc_state,
+            ltc,
+            config,
+            log_buffer: Arc::new(Mutex::new(VecDeque::new())),
+            config_path,
+            serial: Arc::new(ReaderSelection::fixed("simulator")),
+            audit: Arc::new(AuditLog::in_memory()),
+            clock: clock.clone(),
+            clock_gate: Default::default(),
+            history: Arc::new(Mutex::new(history)),
+            series,
+            session,
+            auth: AuthCache::default(),
+            status_cache: StatusCache::default(),
+            ntp_check: Default::default(),
+            rtp_midi: Default::default(),
+            ptp_capture: Default::default(),
+            web_root: None,
+            health,
+            supervisor: Supervisor::new(),
+            confirmations: Default::default(),
+            sources: Default::default(),
+            sinks: Default::default(),
+            export,
+        });
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server_state = state.clone();
+        thread::spawn(move || {
+            actix_web::rt::System::new()
+                .block_on(async move { serve(server_state, vec![listener]).unwrap().await })
+        });
+
+        Self { url, state, clock, frames }
+    }
+
+    fn client(&self, token: Option<&str>) -> ApiClient {
+        ApiClient::new(&self.url, token.map(str::to_string)).with_source(AuditSource::Cli)
+    }
+
+    /// Push simulated reader output through the serial line parser.
+    fn feed(&self, lines: &[String]) {
+        read_ltc_lines(Cursor::new(lines.join("\n")), self.frames.clone(), ReaderLatency::default(), 115200);
+    }
+
+    /// A second of LTC matching the local clock, as the reader would print it.
+    fn feed_ltc(&self, status: &str) {
+        let now = Local::now();
+        let lines: Vec<String> = (0..25)
+            .map(|f| {
+                format!(
+                    "[{}] {:02}:{:02}:{:02}:{:02} | 25.00fps",
+                    status,
+                    now.hour(),
+                    now.minute(),
+                    now.second(),
+                    f
+                )
+            })
+            .collect();
+        self.feed(&lines);
+    }
+
+    fn audit(&self) -> Vec<serde_json::Value> {
+        self.client(None).get("/api/audit").unwrap()
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.state.config_path);
+        let _ = std::fs::remove_dir_all(format!("{}.export", self.state.config_path));
+    }
+}
+
+/// Poll `f` until it returns `Some`, failing the test after 10 seconds.
+fn wait_for<T>(what: &str, mut f: impl FnMut() -> Option<T>) -> T {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        if let Some(v) = f() {
+            return v;
+        }
+        assert!(Instant::now() < deadline, "timed out waiting for {}", what);
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[test]
+fn test_sync_waits_for_ltc_then_steps_clock() {
+    let h = Harness::start(Config::default());
+    let client = h.client(None);
+
+    let status = client.status().unwrap();
+    assert_eq!(status.ltc_status, "(waiting)");
+    assert_eq!(status.serial_port, "simulator");
+    assert!(status.ntp_active, "reported by the mock clock");
+    assert_eq!(client.sync().unwrap_err(), "No LTC timecode available to sync to.");
+    assert!(h.clock.calls().is_empty());
+
+    h.feed_ltc("LOCK");
+    wait_for("LOCK status", || {
+        client.status().ok().filter(|s| s.ltc_status == "LOCK")
+    });
+
+    assert_eq!(client.sync().unwrap().message, "Sync command issued.");
+    let calls = h.clock.calls();
+    assert_eq!(calls.len(), 1);
+    assert!(calls[0].starts_with("step "), "{:?}", calls);
+
+    let audit = wait_for("audit entry", || {
+        let audit = h.audit();
+        (!audit.is_empty()).then_some(audit)
+    });
+    assert_eq!(audit[0]["source"], "cli");
+    assert_eq!(audit[0]["action"], "sync");
+    assert_eq!(audit[0]["success"], true);
+    assert!(audit[0]["preDeltaMs"].is_i64());
+}
+
+#[test]
+fn test_large_sync_runs_only_with_a_preview_token() {
+    let h = Harness::start(Config { clock_command_interval_ms: 0, ..Config::default() });
+    let client = h.client(None);
+    // LTC twelve hours away from the local clock.
+    let now = Local::now();
+    let lines: Vec<String> = (0..25)
+        .map(|f| format!("[LOCK] {:02}:{:02}:{:02}:{:02} | 25.00fps", (now.hour() + 12) % 24, now.minute(), now.second(), f))
+        .collect();
+    h.feed(&lines);
+    wait_for("LOCK status", || {
+        client.status().ok().filter(|s| s.ltc_status == "LOCK")
+    });
+
+    let err = client.sync().unwrap_err();
+    assert!(err.starts_with("This sync steps the clock by"), "{}", err);
+    assert!(h.clock.calls().is_empty());
+
+    let preview = client.sync_preview().unwrap();
+    assert!(preview.confirm_required);
+    let token = preview.token.unwrap();
+    assert_eq!(client.sync_confirmed(&token).unwrap().message, "Sync command issued.");
+    assert_eq!(h.clock.calls().len(), 1);
+    let err = client.sync_confirmed(&token).unwrap_err();
+    assert!(err.starts_with("This sync steps the clock by"), "the token is spent: {}", err);
+}
+
+#[test]
+fn test_sync_over_the_step_limit_runs_only_with_the_override() {
+    let h = Harness::start(Config {
+        max_auto_step_ms: 2000,
+        confirm_step_ms: 0,
+        clock_command_interval_ms: 0,
+        ..Config::default()
+    });
+    let client = h.client(None);
+    let now = Local::now();
+    let lines: Vec<String> = (0..25)
+        .map(|f| format!("[LOCK] {:02}:{:02}:{:02}:{:02} | 25.00fps", (now.hour() + 12) % 24, now.minute(), now.second(), f))
+        .collect();
+    h.feed(&lines);
+    wait_for("LOCK status", || {
+        client.status().ok().filter(|s| s.ltc_status == "LOCK")
+    });
+
+    let err = client.sync().unwrap_err();
+    assert!(err.contains("maxAutoStepMs (2000 ms)"), "{}", err);
+    assert!(client.sync_preview().unwrap().over_step_limit);
+    assert!(h.clock.calls().is_empty());
+
+    assert_eq!(client.sync_override(None).unwrap().message, "Sync command issued.");
+    assert_eq!(h.clock.calls().len(), 1);
+}
+
+#[test]
+fn test_offset_change_over_the_step_limit_runs_only_with_the_override() {
+    let h = Harness::start(Config {
+        max_auto_step_ms: 2000,
+        confirm_step_ms: 0,
+        clock_command_interval_ms: 0,
+        ..Config::default()
+    });
+    let client = h.client(None);
+    h.feed_ltc("LOCK");
+    wait_for("LOCK status", || {
+        client.status().ok().filter(|s| s.ltc_status == "LOCK")
+    });
+
+    let patch = serde_json::json!({ "timeturnerOffset": { "hours": 1 } });
+    let err = client.patch_config(patch.clone()).unwrap_err();
+    assert!(err.contains("maxAutoStepMs (2000 ms)"), "{}", err);
+    assert!(!client.config().unwrap().timeturner_offset.is_active());
+    assert!(h.clock.calls().is_empty());
+
+    let saved = client.patch_config_override(patch, None).unwrap();
+    assert_eq!(saved.timeturner_offset.hours, 1);
+    assert_eq!(h.clock.calls().len(), 1);
+}
+
+#[test]
+fn test_nudge_and_set_date_reach_the_clock() {
+    let h = Harness::start(Config { clock_command_interval_ms: 0, ..Config::default() });
+    let client = h.client(None);
+
+    assert_eq!(client.nudge(-2000).unwrap().message, "Clock nudge command issued.");
+    let err = client.nudge(5_000_000).unwrap_err();
+    assert_eq!(err, "A nudge of 5000000 us is outside the allowed -500000 to 500000 us.");
+    let body = serde_json::json!({ "date": "2025-07-30" });
+    let err = client.send::<serde_json::Value>("POST", "/api/set_date", Some(body.clone())).unwrap_err();
+    assert!(err.starts_with("This date change steps the clock by"), "{}", err);
+    let preview: serde_json::Value = client.get("/api/set_date/preview?date=2025-07-30").unwrap();
+    let token = preview["token"].as_str().unwrap();
+    let ok: serde_json::Value = client
+        .send("POST", &format!("/api/set_date?confirm=true&token={}", token), Some(body))
+        .unwrap();
+    assert_eq!(ok["status"], "success");
+    // `date` would take "tomorrow", unconfirmed; it never gets that far.
+    let err = client
+        .send::<serde_json::Value>("POST", "/api/set_date", Some(serde_json::json!({ "date": "tomorrow" })))
+        .unwrap_err();
+    assert_eq!(err, "The date must be YYYY-MM-DD.");
+    assert_eq!(h.clock.calls(), vec!["nudge -2000", "set_date 2025-07-30"]);
+
+    h.clock.fail.store(true, Ordering::SeqCst);
+    let preview: serde_json::Value = client.get("/api/set_date/preview?date=2025-07-31").unwrap();
+    let token = preview["token"].as_str().unwrap();
+    let err = client
+        .send::<serde_json::Value>(
+            "POST",
+            &format!("/api/set_date?confirm=true&token={}", token),
+            Some(serde_json::json!({ "date": "2025-07-31" })),
+        )
+        .unwrap_err();
+    assert_eq!(err, "Date update command failed.");
+
+    assert_eq!(
+        h.clock.calls(),
+        vec!["nudge -2000", "set_date 2025-07-30", "set_date 2025-07-31"]
+    );
+    let audit = wait_for("three audit entries", || {
+        let audit = h.audit();
+        (audit.len() == 3).then_some(audit)
+    });
+    let mut actions: Vec<(String, bool)> = audit
+        .iter()
+        .map(|e| (e["action"].as_str().unwrap().to_string(), e["success"].as_bool().unwrap()))
+        .collect();
+    actions.sort();
+    assert_eq!(
+        actions,
+        vec![
+            ("nudge".to_string(), true),
+            ("setDate".to_string(), false),
+            ("setDate".to_string(), true)
+        ]
+    );
+}
+
+#[test]
+fn test_clock_commands_are_rate_limited_and_serialised() {
+    let h = Harness::start(Config { clock_command_interval_ms: 60_000, ..Config::default() });
+    let client = h.client(None);
+
+    assert_eq!(client.nudge(-2000).unwrap().message, "Clock nudge command issued.");
+    let err = client.nudge(-2000).unwrap_err();
+    assert!(err.starts_with("Clock commands are limited to one every 60000 ms"), "{}", err);
+    assert_eq!(h.clock.calls(), vec!["nudge -2000"]);
+
+    // Auto-sync or the re-jam holding the gate.
+    let h = Harness::start(Config::default());
+    let client = h.client(None);
+    let permit = h.state.clock_gate.try_enter().unwrap();
+    assert_eq!(client.nudge(-2000).unwrap_err(), "Another clock change is in progress.");
+    drop(permit);
+    assert!(client.nudge(-2000).is_ok());
+}
+
+#[test]
+fn test_config_changes_persist_and_offset_triggers_sync() {
+    let h = Harness::start(Config::default());
+    let client = h.client(None);
+    h.feed_ltc("LOCK");
+    wait_for("LOCK status", || {
+        client.status().ok().filter(|s| s.ltc_status == "LOCK")
+    });
+
+    let saved = client
+        .patch_config(serde_json::json!({ "hardwareOffsetMs": 30 }))
+        .unwrap();
+    assert_eq!(saved.hardware_offset_ms, 30);
+    assert_eq!(client.status().unwrap().hardware_offset_ms, 30);
+    let on_disk = Config::load(&h.state.config_path.clone().into()).unwrap();
+    assert_eq!(on_disk.hardware_offset_ms, 30);
+    assert!(h.clock.calls().is_empty(), "no offset, so no sync");
+
+    let err = client
+        .patch_config(serde_json::json!({ "defaultNudgeMs": 0 }))
+        .unwrap_err();
+    assert!(err.contains("defaultNudgeMs"), "{}", err);
+    let err = client
+        .patch_config(serde_json::json!({ "noSuchField": 1 }))
+        .unwrap_err();
+    assert!(err.contains("noSuchField"), "{}", err);
+
+    // An hour's step has to be previewed and confirmed.
+    let patch = serde_json::json!({ "timeturnerOffset": { "hours": 1 } });
+    let err = client.patch_config(patch.clone()).unwrap_err();
+    assert!(err.starts_with("This config change steps the clock by"), "{}", err);
+    assert!(h.clock.calls().is_empty());
+    let preview = client
+        .offset_preview(&TimeturnerOffset { hours: 1, ..TimeturnerOffset::default() })
+        .unwrap();
+    let saved = client.patch_config_confirmed(patch, &preview.token.unwrap()).unwrap();
+    assert_eq!(saved.timeturner_offset.hours, 1);
+    assert_eq!(saved.hardware_offset_ms, 30);
+    assert_eq!(h.clock.calls().len(), 1);
+    assert_eq!(client.status().unwrap().sync_status, "TIMETURNING");
+
+    let audit = wait_for("config audit entry", || {
+        let audit = h.audit();
+        (!audit.is_empty()).then_some(audit)
+    });
+    assert_eq!(audit[0]["source"], "config");
+}
+
+#[test]
+fn test_offset_presets_switch_and_resync() {
+    let rehearsal = TimeturnerOffset { hours: -1, ..TimeturnerOffset::default() };
+    let h = Harness::start(Config {
+        offset_presets: vec![
+            OffsetPreset { name: "rehearsal -1h".into(), offset: rehearsal.clone(), at: None },
+            OffsetPreset { name: "show".into(), offset: TimeturnerOffset::default(), at: Some("19:30".into()) },
+        ],
+        ..Config::default()
+    });
+    let client = h.client(None);
+    h.feed_ltc("LOCK");
+    wait_for("LOCK status", || {
+        client.status().ok().filter(|s| s.ltc_status == "LOCK")
+    });
+
+    let presets: serde_json::Value = client.get("/api/presets").unwrap();
+    assert_eq!(presets["active"], "show");
+    assert_eq!(presets["next_scheduled"]["name"], "show");
+
+    let err = client.activate_preset("rehearsal -1h").unwrap_err();
+    assert!(err.starts_with("This config change steps the clock by"), "{}", err);
+    assert!(h.clock.calls().is_empty());
+    let token = client.offset_preview(&rehearsal).unwrap().token.unwrap();
+    let saved = client.activate_preset_confirmed("rehearsal -1h", &token).unwrap();
+    assert_eq!(saved.timeturner_offset, rehearsal);
+    assert_eq!(Config::load(&h.state.config_path.clone().into()).unwrap().timeturner_offset, rehearsal);
+    assert_eq!(h.clock.calls().len(), 1);
+
+    // Switching back to no offset re-syncs too. The mock clock never moved,
+    // so that is a small step and needs no confirming.
+    let saved = client.activate_preset("show").unwrap();
+    assert!(!saved.timeturner_offset.is_active());
+    assert_eq!(h.clock.calls().len(), 2);
+
+    let err = client.activate_preset("encore").unwrap_err();
+    assert!(err.contains("encore"), "{}", err);
+}
+
+#[test]
+fn test_offset_change_is_ramped_when_configured() {
+    let h = Harness::start(Config { offset_ramp_secs: 600, ..Config::default() });
+    let client = h.client(None);
+    h.feed_ltc("LOCK");
+    wait_for("LOCK status", || {
+        client.status().ok().filter(|s| s.ltc_status == "LOCK")
+    });
+
+    client
+        .patch_config(serde_json::json!({ "timeturnerOffset": { "frames": 2 } }))
+        .unwrap();
+    let calls = wait_for("ramp", || Some(h.clock.calls()).filter(|c| !c.is_empty()));
+    assert!(calls[0].starts_with("ramp ") && calls[0].ends_with(" over 600s"), "{:?}", calls);
+    let audit = wait_for("ramp audit entry", || {
+        let audit = h.audit();
+        (!audit.is_empty()).then_some(audit)
+    });
+    assert_eq!((&audit[0]["action"], &audit[0]["source"]), (&"ramp".into(), &"config".into()));
+
+    // Ramping five seconds in is over confirmStepMs all the same.
+    let patch = serde_json::json!({ "timeturnerOffset": { "frames": 0, "seconds": 5 } });
+    let err = client.patch_config(patch.clone()).unwrap_err();
+    assert!(err.starts_with("This config change steps the clock by"), "{}", err);
+    let preview = client
+        .offset_preview(&TimeturnerOffset { seconds: 5, ..TimeturnerOffset::default() })
+        .unwrap();
+    // The first ramp holds the gate until it is done.
+    wait_for("first ramp done", || h.state.clock_gate.try_enter());
+    client.patch_config_confirmed(patch, &preview.token.unwrap()).unwrap();
+    let calls = wait_for("second ramp", || Some(h.clock.calls()).filter(|c| c.len() == 2));
+    assert!(calls[1].starts_with("ramp "), "{:?}", calls);
+
+    // An hour is too far to ramp in ten minutes, so it is stepped.
+    let patch = serde_json::json!({ "timeturnerOffset": { "seconds": 0, "hours": 1 } });
+    let preview = client
+        .offset_preview(&TimeturnerOffset { hours: 1, ..TimeturnerOffset::default() })
+        .unwrap();
+    wait_for("second ramp done", || h.state.clock_gate.try_enter());
+    client.patch_config_confirmed(patch, &preview.token.unwrap()).unwrap();
+    let calls = h.clock.calls();
+    assert_eq!(calls.len(), 3);
+    assert!(calls[2].starts_with("step "), "{:?}", calls);
+}
+
+#[test]
+fn test_lock_transitions_show_in_status() {
+    let h = Harness::start(Config::default());
+    let client = h.client(None);
+
+    h.feed_ltc("LOCK");
+    let locked = wait_for("LOCK status", || {
+        client.status().ok().filter(|s| s.ltc_status == "LOCK")
+    });
+    assert_eq!(locked.frame_rate, "25.00fps");
+    assert_ne!(locked.session_timecode, "…");
+
+    h.feed_ltc("FREE");
+    let free = wait_for("FREE status", || {
+        client.status().ok().filter(|s| s.ltc_status == "FREE")
+    });
+    assert_eq!(free.timecode_delta_ms, 0);
+    assert!(free.lock_ratio < 100.0);
+    // The session timecode keeps running through the dropout.
+    assert_ne!(free.session_timecode, "…");
+}
+
+#[test]
+fn test_locked_frames_fill_the_series() {
+    let h = Harness::start(Config::default());
+    let client = h.client(None);
+
+    h.feed_ltc("FREE");
+    h.feed_ltc("LOCK");
+    let measured = |path: &str| {
+        let series: serde_json::Value = client.get(path).unwrap();
+        series["points"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|p| !p["meanMs"].is_null())
+            .count()
+    };
+    wait_for("a measured second", || (measured("/api/series/delta?minutes=1") > 0).then_some(()));
+    assert!(measured("/api/series/jitter?minutes=1") > 0);
+}
+
+#[test]
+fn test_export_writes_locked_frames_to_csv() {
+    let h = Harness::start(Config::default());
+    let client = h.client(None);
+
+    let started: ExportStatus = client.send("POST", "/api/export/start", None).unwrap();
+    assert!(started.active);
+    assert_eq!(started.rotate_minutes, 15);
+    assert_eq!(started.keep_files, 96);
+    let again = client.send::<ExportStatus>("POST", "/api/export/start", None).unwrap_err();
+    assert_eq!(again, "An export is already running.");
+
+    h.feed_ltc("FREE");
+    h.feed_ltc("LOCK");
+    wait_for("exported rows", || {
+        let status: ExportStatus = client.get("/api/export").unwrap();
+        (status.rows > 0).then_some(())
+    });
+    let stopped: ExportStatus = client.send("POST", "/api/export/stop", None).unwrap();
+    assert!(!stopped.active);
+
+    let csv = std::fs::read_to_string(&stopped.files[0]).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("timestamp,timecode,rawDeltaMs,deltaMs,jitterMs"));
+    assert_eq!(lines.count() as u64, stopped.rows, "one row per measured LOCK frame");
+}
+
+#[test]
+fn test_probes_are_public_and_ready_follows_ltc() {
+    let h = Harness::start(Config {
+        api: ApiAccessConfig {
+            tokens: vec![ApiToken { token: "view".into(), role: Role::Viewer }],
+            ..Default::default()
+        },
+        ..Config::default()
+    });
+    let anonymous = h.client(None);
+
+    let health: HealthReport = anonymous.get("/api/health").unwrap();
+    assert!(health.healthy);
+    assert_eq!(health.workers[0].name, "processor");
+    assert_eq!(anonymous.get::<ReadyReport>("/api/ready").unwrap_err(), "HTTP 503");
+    assert!(anonymous.status().is_err());
+
+    h.feed_ltc("LOCK");
+    let ready = wait_for("ready", || anonymous.get::<ReadyReport>("/api/ready").ok());
+    assert!(ready.checks.iter().all(|c| c.ok));
+}
+
+#[test]
+fn test_roles_enforced_over_http() {
+    let h = Harness::start(Config {
+        api: ApiAccessConfig {
+            tokens: vec![
+                ApiToken { token: "view".into(), role: Role::Viewer },
+                ApiToken { token: "op".into(), role: Role::Operator },
+            ],
+            ..ApiAccessConfig::default()
+        },
+        ..Config::default()
+    });
+
+    assert_eq!(
+        h.client(None).status().unwrap_err(),
+        "Missing or unknown API token."
+    );
+    assert!(h.client(Some("view")).status().is_ok());
+    assert_eq!(
+        h.client(Some("view")).sync().unwrap_err(),
+        "This action requires the operator role."
+    );
+    // The operator gets through to the handler, which has no LTC yet.
+    assert_eq!(
+        h.client(Some("op")).sync().unwrap_err(),
+        "No LTC timecode available to sync to."
+    );
+    assert_eq!(
+        h.client(Some("op"))
+            .patch_config(serde_json::json!({ "hardwareOffsetMs": 1 }))
+            .unwrap_err(),
+        "This action requires the admin role."
+    );
+}
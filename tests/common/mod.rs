@@ -0,0 +1,68 @@
+// tests/common/mod.rs
+//
+// Shared by the integration tests: a `ClockControl` that records clock
+// commands instead of running them.
+
+use ntp_timeturner::config::Config;
+use ntp_timeturner::sync_logic::LtcFrame;
+use ntp_timeturner::system::{self, ClockControl};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Records clock commands instead of running them. `set_date("bad")` fails,
+/// and every command fails once `fail` is set.
+#[derive(Default)]
+pub struct MockClock {
+    calls: Mutex<Vec<String>>,
+    pub fail: AtomicBool,
+    pub ntp_active: AtomicBool,
+}
+
+#[allow(dead_code)]
+impl MockClock {
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    fn record(&self, call: String) -> Result<(), ()> {
+        self.calls.lock().unwrap().push(call);
+        if self.fail.load(Ordering::SeqCst) {
+            Err(())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl ClockControl for MockClock {
+    fn step(&self, frame: &LtcFrame, config: &Config) -> Result<String, ()> {
+        let ts = system::calculate_target_time(frame, config)
+            .map_err(|_| ())?
+            .format("%H:%M:%S%.3f")
+            .to_string();
+        self.record(format!("step {}", ts))?;
+        Ok(ts)
+    }
+
+    fn nudge(&self, microseconds: i64) -> Result<(), ()> {
+        self.record(format!("nudge {}", microseconds))
+    }
+
+    fn ramp(&self, microseconds: i64, duration: Duration) -> Result<(), ()> {
+        self.record(format!("ramp {} over {}s", microseconds, duration.as_secs()))
+    }
+
+    fn set_date(&self, date: &str) -> Result<(), ()> {
+        self.record(format!("set_date {}", date))?;
+        if date == "bad" {
+            Err(())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn ntp_active(&self) -> bool {
+        self.ntp_active.load(Ordering::SeqCst)
+    }
+}
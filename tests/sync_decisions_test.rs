@@ -0,0 +1,279 @@
+// tests/sync_decisions_test.rs
+//
+// Scripted LTC through the real frame processor and auto-sync executor,
+// asserting exactly which clock commands are issued. Time is scripted too:
+// auto-sync is driven with chosen `Instant`s, and the clock is a mock.
+
+mod common;
+
+use chrono::{Duration as ChronoDuration, Local, Timelike, Utc};
+use common::MockClock;
+use ntp_timeturner::audit::AuditLog;
+use ntp_timeturner::clock_gate::ClockGate;
+use ntp_timeturner::config::Config;
+use ntp_timeturner::health::Health;
+use ntp_timeturner::processor::{self, frame_channel, CorrectionRequest, Processor, INITIAL_SETTLE_TIME};
+use ntp_timeturner::rejam;
+use ntp_timeturner::report::Session;
+use ntp_timeturner::series::Series;
+use ntp_timeturner::sync_logic::{LtcFrame, LtcState, AUTO_SYNC_PERIOD_SECS};
+use ntp_timeturner::system::{self, ClockControl};
+use num_rational::Ratio;
+use std::sync::atomic::Ordering;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A second of 25 fps LTC starting on a whole local second, each frame
+/// arriving `delta_ms` after the instant it names.
+fn ltc_second(status: &str, delta_ms: i64) -> Vec<LtcFrame> {
+    let now = Local::now();
+    let start = now - ChronoDuration::nanoseconds(now.nanosecond() as i64);
+    (0..25)
+        .map(|f| {
+            let named = start + ChronoDuration::milliseconds(f * 40);
+            LtcFrame {
+                status: status.to_string(),
+                hours: start.hour(),
+                minutes: start.minute(),
+                seconds: start.second(),
+                frames: f as u32,
+                is_drop_frame: false,
+                frame_rate: Ratio::new(25, 1),
+                timestamp: (named + ChronoDuration::milliseconds(delta_ms)).with_timezone(&Utc),
+                received: None,
+            }
+        })
+        .collect()
+}
+
+/// The processor, its state and the executor's surroundings.
+struct Script {
+    processor: Processor,
+    state: Arc<Mutex<LtcState>>,
+    config: Config,
+    clock: MockClock,
+    gate: Arc<ClockGate>,
+    audit: Arc<AuditLog>,
+    start: Instant,
+}
+
+impl Script {
+    fn new(config: Config) -> Self {
+        Self {
+            processor: Processor::default(),
+            state: Arc::new(Mutex::new(LtcState::new())),
+            config,
+            clock: MockClock::default(),
+            gate: Arc::new(ClockGate::default()),
+            audit: Arc::new(AuditLog::in_memory()),
+            start: Instant::now(),
+        }
+    }
+
+    fn feed(&mut self, frames: Vec<LtcFrame>) {
+        let mut state = self.state.lock().unwrap();
+        for frame in frames {
+            self.processor.frame(&mut state, &self.config, frame);
+        }
+    }
+
+    /// The auto-sync decision `after` the script started.
+    fn decide(&mut self, after: Duration) -> Option<CorrectionRequest> {
+        let state = self.state.lock().unwrap();
+        self.processor.auto_sync(self.start + after, &state, &self.config)
+    }
+
+    /// Lock on, then take the first decision once settled.
+    fn first_decision(&mut self) -> Option<CorrectionRequest> {
+        self.decide(Duration::ZERO);
+        self.decide(INITIAL_SETTLE_TIME)
+    }
+
+    fn carry_out(&self, request: &CorrectionRequest) {
+        let ltc = self.state.lock().unwrap().view();
+        processor::carry_out(request, &self.clock, &self.gate, &self.state, &ltc, &self.audit);
+    }
+}
+
+fn auto_sync() -> Config {
+    Config { auto_sync_enabled: true, ..Config::default() }
+}
+
+#[test]
+fn test_small_delta_is_slewed_away() {
+    let mut s = Script::new(auto_sync());
+    s.feed(ltc_second("LOCK", 20));
+    let request = s.first_decision().expect("a correction");
+    s.carry_out(&request);
+
+    assert_eq!(s.clock.calls(), vec!["nudge -20000"]);
+}
+
+#[test]
+fn test_large_delta_is_stepped_to_ltc() {
+    let mut s = Script::new(auto_sync());
+    s.feed(ltc_second("LOCK", 500));
+    let request = s.first_decision().expect("a correction");
+    s.carry_out(&request);
+
+    let calls = s.clock.calls();
+    assert_eq!(calls.len(), 1);
+    let target = system::calculate_target_time(&request.frame, &s.config).unwrap();
+    assert_eq!(calls[0], format!("step {}", target.format("%H:%M:%S%.3f")));
+}
+
+#[test]
+fn test_corrupted_line_never_steps() {
+    let mut s = Script::new(auto_sync());
+    let mut second = ltc_second("LOCK", 500);
+    second[24].frame_rate = Ratio::new(24, 1);
+    s.feed(second);
+    assert!(s.first_decision().is_none());
+    assert!(s.clock.calls().is_empty());
+}
+
+#[test]
+fn test_clock_within_a_millisecond_is_left_alone() {
+    let mut s = Script::new(auto_sync());
+    s.feed(ltc_second("LOCK", 0));
+    assert!(s.first_decision().is_none());
+    assert!(s.clock.calls().is_empty());
+}
+
+#[test]
+fn test_free_run_ltc_never_touches_the_clock() {
+    let mut s = Script::new(auto_sync());
+    s.feed(ltc_second("FREE", 500));
+    assert!(s.first_decision().is_none());
+    assert!(s.decide(Duration::from_secs(AUTO_SYNC_PERIOD_SECS * 3)).is_none());
+    assert!(s.clock.calls().is_empty());
+}
+
+#[test]
+fn test_lost_lock_holds_without_slewing() {
+    let mut s = Script::new(auto_sync());
+    s.feed(ltc_second("LOCK", 20));
+    let first = s.first_decision().unwrap();
+    s.carry_out(&first);
+
+    // No drift estimate from a second of frames, so holdover has nothing to do.
+    s.feed(ltc_second("FREE", 20));
+    let next = INITIAL_SETTLE_TIME + Duration::from_secs(AUTO_SYNC_PERIOD_SECS);
+    assert!(s.decide(next).is_none());
+    assert_eq!(s.clock.calls(), vec!["nudge -20000"]);
+}
+
+#[test]
+fn test_decision_is_dropped_while_the_gate_is_held_or_stale() {
+    let mut s = Script::new(auto_sync());
+    s.feed(ltc_second("LOCK", 20));
+    let request = s.first_decision().unwrap();
+
+    let permit = s.gate.try_enter().unwrap();
+    s.carry_out(&request);
+    assert!(s.clock.calls().is_empty(), "another change holds the gate");
+
+    permit.note_change(request.decided_at + Duration::from_millis(1));
+    drop(permit);
+    s.carry_out(&request);
+    assert!(s.clock.calls().is_empty(), "the clock changed after the decision");
+}
+
+#[test]
+fn test_failed_correction_is_audited() {
+    let mut s = Script::new(auto_sync());
+    s.feed(ltc_second("LOCK", 20));
+    let request = s.first_decision().unwrap();
+    s.clock.fail.store(true, Ordering::SeqCst);
+    s.carry_out(&request);
+
+    assert_eq!(s.clock.calls(), vec!["nudge -20000"]);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let entries = loop {
+        let entries = s.audit.entries();
+        if !entries.is_empty() || Instant::now() > deadline {
+            break entries;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].action, "nudge");
+    assert!(!entries[0].success);
+}
+
+#[test]
+fn test_sync_on_second_steps_on_the_next_frame_zero() {
+    // Twelve consistent frames are enough to step here.
+    let mut s = Script::new(Config { sync_on_second: true, step_consistent_frames: 12, ..auto_sync() });
+    let second = ltc_second("LOCK", 500);
+    s.feed(second[..12].to_vec());
+    let request = s.first_decision().expect("a correction");
+    assert_eq!(request.frame.frames, 11);
+
+    let next = ltc_second("LOCK", 500)[0].clone();
+    let next = LtcFrame { seconds: (next.seconds + 1) % 60, ..next };
+    thread::scope(|scope| {
+        scope.spawn(|| s.carry_out(&request));
+        thread::sleep(Duration::from_millis(100));
+        assert!(s.clock.calls().is_empty(), "armed until frame 00");
+        let mut state = s.state.lock().unwrap();
+        state.update(second[12].clone());
+        state.update(next.clone());
+    });
+
+    let target = system::calculate_target_time(&next, &s.config).unwrap();
+    assert_eq!(target.nanosecond(), 0);
+    assert_eq!(s.clock.calls(), vec![format!("step {}", target.format("%H:%M:%S%.3f"))]);
+}
+
+#[test]
+fn test_scheduled_step_waits_for_consistent_frames() {
+    let mut s = Script::new(Config::default());
+    let clock = Arc::new(MockClock::default());
+    let ltc = s.state.lock().unwrap().view();
+    let rejam = |s: &Script| {
+        let control: Arc<dyn ClockControl> = clock.clone();
+        rejam::scheduled_step("Re-jam", false, false, &control, &s.config, &s.state, &ltc, &s.audit, &s.gate);
+    };
+    let mut second = ltc_second("LOCK", 500);
+    let rest = second.split_off(10);
+    s.feed(second);
+    rejam(&s);
+    assert!(clock.calls().is_empty(), "10 of 25 frames is too few");
+
+    s.feed(rest);
+    rejam(&s);
+    assert_eq!(clock.calls().len(), 1);
+    assert!(clock.calls()[0].starts_with("step "), "{:?}", clock.calls());
+}
+
+#[test]
+fn test_headless_processing_records_offsets() {
+    // The daemon's own processing path, with no UI attached: every LOCK
+    // frame is measured as it comes off the queue.
+    let (tx, rx) = frame_channel();
+    let state = Arc::new(Mutex::new(LtcState::new()));
+    let config = Arc::new(Mutex::new(auto_sync()));
+    let series = Arc::new(Mutex::new(Series::default()));
+    let session = Arc::new(Mutex::new(Session::new(Utc::now(), 0)));
+    let (corrections, _decisions) = mpsc::channel();
+    let heartbeat = Health::new().heartbeat("processor", Duration::from_secs(5));
+    let worker = {
+        let state = state.clone();
+        let export = Default::default();
+        thread::spawn(move || processor::run(&rx, state, config, series, session, export, corrections, heartbeat))
+    };
+    for frame in ltc_second("LOCK", 20) {
+        assert!(tx.send(frame));
+    }
+    drop(tx);
+    worker.join().unwrap();
+
+    let state = state.lock().unwrap();
+    assert_eq!(state.lock_count, 25);
+    assert_eq!(state.offset_history.len(), state.jitter_window);
+    let delta = state.ewma_clock_delta.expect("a smoothed delta");
+    assert!((delta - 20.0).abs() < 1.0, "{}", delta);
+    assert!(state.frame_delta_ms.is_some_and(|d| (d - 20.0).abs() < 1.0));
+}
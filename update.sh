@@ -15,9 +15,14 @@ cargo build --release
 echo "🛑 Stopping TimeTurner service..."
 sudo systemctl stop timeturner.service || true
 
-# 4. Copy the new binary to the installation directory
-echo "🚀 Deploying new binary..."
-sudo cp target/release/ntp_timeturner /opt/timeturner/timeturner
+# 4. Copy the new binaries to the installation directory
+echo "🚀 Deploying new binaries..."
+sudo cp target/release/timeturnerd /opt/timeturner/timeturnerd
+sudo cp target/release/timeturner-ctl /opt/timeturner/timeturner-ctl
+sudo ln -sf /opt/timeturner/timeturner-ctl /usr/local/bin/timeturner
+# The service file changed when the binary was split; refresh it
+sudo cp timeturner.service /etc/systemd/system/
+sudo systemctl daemon-reload
 
 # 5. Restart the service with the new binary
 echo "✅ Restarting TimeTurner service..."